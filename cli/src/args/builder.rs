@@ -0,0 +1,54 @@
+use clap::{Args, Subcommand};
+
+use super::parse_size;
+
+#[derive(Subcommand)]
+pub enum BuilderCommand {
+    /// List build cache entries with their instruction, size, last use and number of uses
+    Ls(LsArgs),
+    /// Show the disk usage of the build cache
+    Du(DuArgs),
+    /// Remove build cache entries
+    Prune(PruneArgs),
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Format the output using a template (e.g. '{{.ID}}\t{{.CreatedBy}}\t{{.UsageCount}}')
+    #[arg(long)]
+    pub format: Option<String>,
+
+    /// Only display cache entry IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Do not truncate the instructions
+    #[arg(long)]
+    pub no_trunc: bool,
+}
+
+#[derive(Args)]
+pub struct DuArgs {
+    /// List the entries taking the most space along with the totals
+    #[arg(short, long)]
+    pub verbose: bool,
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+    /// Remove all entries, not just those whose layer no image uses
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Provide filter values (e.g. "until=24h" keeps the entries used in the last 24 hours)
+    #[arg(long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Remove the least recently used entries only until the cache takes at most this much (e.g. 10g)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub keep_storage: Option<u64>,
+
+    /// Do not prompt for confirmation
+    #[arg(short, long)]
+    pub force: bool,
+}
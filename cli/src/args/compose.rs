@@ -0,0 +1,398 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::parse_duration;
+
+#[derive(Subcommand)]
+pub enum ComposeCommand {
+    /// Create and start containers
+    Up(UpArgs),
+    /// Stop and remove containers, networks, images, and volumes
+    Down(DownArgs),
+    /// View output from containers
+    Logs(LogsArgs),
+    /// List containers of the project
+    Ps(PsArgs),
+    /// Display the running processes of the project's containers
+    Top(ServicesArgs),
+    /// Display a snapshot of the resource usage of the project's running containers
+    Stats(ServicesArgs),
+    /// Execute a command in a running service container
+    Exec(ExecArgs),
+    /// Run a one-off command on a service
+    Run(RunArgs),
+    /// Build or rebuild the project's services
+    Build(BuildArgs),
+    /// Pull the images of the project's services
+    Pull(ParallelArgs),
+    /// Push the images of the project's built services
+    Push(ParallelArgs),
+    /// Set the number of containers for services
+    Scale(ScaleArgs),
+    /// Validate and view the compose file with variables resolved
+    Config(ConfigArgs),
+    /// Start services and update them when the files in develop.watch change
+    Watch(ServicesArgs),
+    /// Convert the compose files to another format
+    Convert(ConvertArgs),
+    /// Stream events of the project's containers
+    Events(EventsArgs),
+    /// Print the public address of a port binding of a service
+    Port(PortArgs),
+    /// Start existing containers of the project's services
+    Start(ServicesArgs),
+    /// Stop the project's containers without removing them
+    Stop(StopArgs),
+    /// Restart the project's containers
+    Restart(StopArgs),
+    /// Pause the running containers of the project's services
+    Pause(ServicesArgs),
+    /// Unpause the paused containers of the project's services
+    Unpause(ServicesArgs),
+}
+
+// compose ファイル（-f を他のオプションに使うコマンドでは --file のみ）
+#[derive(Args)]
+pub struct FileArgs {
+    /// Specify an alternate compose file (can be given multiple times)
+    #[arg(short = 'f', long = "file", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+}
+
+// サービスを選ぶだけのコマンド（top・watch・start・pause・unpause）
+#[derive(Args)]
+pub struct ServicesArgs {
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Services to use (all services if omitted)
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct UpArgs {
+    /// Detached mode: Run containers in the background
+    #[arg(short, long)]
+    pub detach: bool,
+
+    /// Build images before starting containers
+    #[arg(long)]
+    pub build: bool,
+
+    /// Recreate containers even if their configuration and image haven't changed
+    #[arg(long, conflicts_with = "no_recreate")]
+    pub force_recreate: bool,
+
+    /// If containers already exist, don't recreate them
+    #[arg(long)]
+    pub no_recreate: bool,
+
+    /// Don't start linked services
+    #[arg(long)]
+    pub no_deps: bool,
+
+    /// Scale SERVICE to NUM containers (overrides deploy.replicas)
+    #[arg(long, value_name = "SERVICE=NUM", value_parser = parse_scale)]
+    pub scale: Vec<(String, usize)>,
+
+    /// Wait for services to be running and healthy (implies detached mode)
+    #[arg(long)]
+    pub wait: bool,
+
+    /// Maximum time to wait for services to be running and healthy (seconds, or a duration such as 2m)
+    #[arg(long, requires = "wait", value_parser = parse_duration)]
+    pub wait_timeout: Option<Duration>,
+
+    /// Watch the develop.watch paths and sync, restart or rebuild services on changes
+    #[arg(long)]
+    pub watch: bool,
+
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Services to start (all services if omitted)
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct DownArgs {
+    /// Remove named volumes declared in the volumes section
+    #[arg(long)]
+    pub volumes: bool,
+
+    /// Remove images used by services (local: only images without a custom name)
+    #[arg(long, value_parser = ["all", "local"])]
+    pub rmi: Option<String>,
+
+    /// Remove containers for services not defined in the compose file
+    #[arg(long)]
+    pub remove_orphans: bool,
+
+    /// Time to wait for containers to stop before killing them (seconds, or a duration such as 1m30s)
+    #[arg(short, long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Args)]
+pub struct LogsArgs {
+    /// Follow log output
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// Number of lines to show from the end of the logs for each container
+    #[arg(long)]
+    pub tail: Option<usize>,
+
+    /// Show timestamps
+    #[arg(short, long)]
+    pub timestamps: bool,
+
+    /// Produce monochrome output
+    #[arg(long)]
+    pub no_color: bool,
+
+    /// Specify an alternate compose file (can be given multiple times)
+    #[arg(long = "file", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Services to show logs for (all services if omitted)
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct PsArgs {
+    /// Show all containers (default shows just running)
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Only display container IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Services to list (all services if omitted)
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ExecArgs {
+    /// Detached mode: Run command in the background
+    #[arg(short, long)]
+    pub detach: bool,
+
+    /// Keep STDIN open and send it to the command
+    #[arg(short, long, conflicts_with = "detach")]
+    pub interactive: bool,
+
+    /// Run the command as this user
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// Working directory inside the container
+    #[arg(short, long)]
+    pub workdir: Option<String>,
+
+    /// Set environment variables
+    #[arg(short, long)]
+    pub env: Vec<String>,
+
+    /// Index of the container if the service has multiple replicas (default: 1)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub index: Option<u32>,
+
+    /// Specify an alternate compose file (can be given multiple times)
+    #[arg(long = "file", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Service to run the command in
+    pub service: String,
+
+    /// Command to execute
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// Run container in the background and print its name
+    #[arg(short, long)]
+    pub detach: bool,
+
+    /// Remove the container after it exits
+    #[arg(long)]
+    pub rm: bool,
+
+    /// Assign a name to the container
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Run as this user
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// Working directory inside the container
+    #[arg(short, long)]
+    pub workdir: Option<String>,
+
+    /// Set environment variables
+    #[arg(short, long)]
+    pub env: Vec<String>,
+
+    /// Publish the service's ports to the host
+    #[arg(long)]
+    pub service_ports: bool,
+
+    /// Don't start linked services
+    #[arg(long)]
+    pub no_deps: bool,
+
+    /// Start the container only once this dependency is ready, in addition to the service's x-wait-for (a container or service name, or tcp://host:port)
+    #[arg(long, value_name = "TARGET")]
+    pub wait_for: Vec<String>,
+
+    /// Specify an alternate compose file (can be given multiple times)
+    #[arg(long = "file", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Service to run
+    pub service: String,
+
+    /// Command to run instead of the service's command
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct BuildArgs {
+    /// Build the services in parallel
+    #[arg(long)]
+    pub parallel: bool,
+
+    /// Always attempt to pull a newer version of the base images
+    #[arg(long)]
+    pub pull: bool,
+
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Services to build (all services with a build section if omitted)
+    pub services: Vec<String>,
+}
+
+// pull・push
+#[derive(Args)]
+pub struct ParallelArgs {
+    /// Transfer the images in parallel
+    #[arg(long)]
+    pub parallel: bool,
+
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Services to use (all services if omitted; push only uses services with build and image)
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ScaleArgs {
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Number of containers for each service
+    #[arg(required = true, value_name = "SERVICE=NUM", value_parser = parse_scale)]
+    pub scale: Vec<(String, usize)>,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    /// Only validate the configuration, don't print anything
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Print the service names, one per line
+    #[arg(long, conflicts_with_all = ["quiet", "images", "graph"])]
+    pub services: bool,
+
+    /// Print the images the services use, including the names of images built by the project
+    #[arg(long, conflicts_with_all = ["quiet", "graph"])]
+    pub images: bool,
+
+    /// Print the depends_on graph of the services in start order (dot: Graphviz, json)
+    #[arg(long, value_parser = ["dot", "json"], conflicts_with = "quiet")]
+    pub graph: Option<String>,
+
+    #[command(flatten)]
+    pub file: FileArgs,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Output format (k8s: Kubernetes Deployments, Services and PersistentVolumeClaims)
+    #[arg(long, value_parser = ["yaml", "k8s"], default_value = "yaml")]
+    pub format: String,
+}
+
+#[derive(Args)]
+pub struct EventsArgs {
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Output events as JSON lines
+    #[arg(long)]
+    pub json: bool,
+
+    /// Services to show events for (all services if omitted)
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct PortArgs {
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Protocol of the port
+    #[arg(long, value_parser = ["tcp", "udp", "sctp"], default_value = "tcp")]
+    pub protocol: String,
+
+    /// Index of the container if the service has multiple replicas
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub index: u32,
+
+    /// Service name
+    pub service: String,
+
+    /// Port in the container
+    pub private_port: u16,
+}
+
+// stop・restart
+#[derive(Args)]
+pub struct StopArgs {
+    #[command(flatten)]
+    pub file: FileArgs,
+
+    /// Time to wait for containers to stop before killing them (seconds, or a duration such as 1m30s)
+    #[arg(short, long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Services to use (all services if omitted)
+    pub services: Vec<String>,
+}
+
+// SERVICE=NUM
+fn parse_scale(s: &str) -> Result<(String, usize), String> {
+    let (service, count) = s
+        .split_once('=')
+        .filter(|(service, _)| !service.is_empty())
+        .ok_or_else(|| format!("Invalid scale (expected SERVICE=NUM): {}", s))?;
+    let count = count
+        .parse()
+        .map_err(|_| format!("Invalid number of containers for {}: {}", service, count))?;
+    Ok((service.to_string(), count))
+}
@@ -0,0 +1,76 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ContextCommand {
+    /// Create a context
+    Create(CreateArgs),
+    /// Set the current context
+    Use(UseArgs),
+    /// List contexts
+    Ls(LsArgs),
+    /// Display detailed information on contexts
+    Inspect(InspectArgs),
+    /// Remove contexts
+    Rm(RmArgs),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Context name
+    pub name: String,
+
+    /// Daemon endpoint (unix:///path or tcp://host:port)
+    #[arg(long)]
+    pub host: String,
+
+    /// Description of the context
+    #[arg(long)]
+    pub description: Option<String>,
+
+    /// CA certificate to verify the daemon with
+    #[arg(long)]
+    pub tls_ca: Option<PathBuf>,
+
+    /// Client certificate
+    #[arg(long, requires = "tls_key")]
+    pub tls_cert: Option<PathBuf>,
+
+    /// Private key of the client certificate
+    #[arg(long, requires = "tls_cert")]
+    pub tls_key: Option<PathBuf>,
+
+    /// Use TLS and verify the daemon's certificate
+    #[arg(long)]
+    pub tls_verify: bool,
+}
+
+#[derive(Args)]
+pub struct UseArgs {
+    /// Context name (default for the local daemon)
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Only display context names
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Context names (the current context if omitted)
+    pub names: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Remove the context even if it is in use
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Context names
+    #[arg(required = true)]
+    pub names: Vec<String>,
+}
@@ -0,0 +1,67 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ImageCommand {
+    /// Show the files added, removed and changed between two images (per layer), or in a container
+    Diff(DiffArgs),
+    /// Generate a software bill of materials for an image
+    Sbom(SbomArgs),
+    /// Scan an image for vulnerabilities with the scanner configured in the daemon
+    Scan(ScanArgs),
+    /// Squash an image into a single layer
+    Squash(SquashArgs),
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Compare the files of this container instead of a second image
+    #[arg(long, value_name = "CONTAINER")]
+    pub container: Option<String>,
+
+    /// Only list the changes as a whole, not those of each layer
+    #[arg(long)]
+    pub total: bool,
+
+    /// The image to compare with (with --container, defaults to the container's image)
+    #[arg(required_unless_present = "container")]
+    pub from: Option<String>,
+
+    /// The image to compare
+    #[arg(required_unless_present = "container", conflicts_with = "container")]
+    pub to: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SbomArgs {
+    /// SBOM format
+    #[arg(long, value_parser = ["spdx-json", "cyclonedx-json"], default_value = "spdx-json")]
+    pub format: String,
+
+    /// Write to a file, instead of STDOUT
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// The image to describe
+    pub image: String,
+}
+
+#[derive(Args)]
+pub struct ScanArgs {
+    /// Only show vulnerabilities of these severities (e.g. "HIGH,CRITICAL")
+    #[arg(long, value_delimiter = ',')]
+    pub severity: Vec<String>,
+
+    /// The image to scan
+    pub image: String,
+}
+
+#[derive(Args)]
+pub struct SquashArgs {
+    /// Name and optionally a tag for the squashed image (Default is the name of the image)
+    #[arg(short, long)]
+    pub tag: Option<String>,
+
+    /// The image to squash
+    pub image: String,
+}
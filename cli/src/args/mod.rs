@@ -0,0 +1,607 @@
+use clap::{Args, Parser, Subcommand};
+use rocker_client::DEFAULT_DETACH_KEYS;
+use rocker_core::{RestartPolicy, WaitFor};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub mod builder;
+pub mod compose;
+pub mod context;
+pub mod image;
+pub mod network;
+pub mod node;
+pub mod schedule;
+pub mod secret;
+pub mod service;
+pub mod stack;
+pub mod swarm;
+pub mod system;
+pub mod volume;
+
+// rocker のコマンドライン（各コマンドの引数は commands の execute にそのまま渡す）
+#[derive(Parser)]
+#[command(name = "rocker", version, author = "Rocker Team", arg_required_else_help = true)]
+#[command(about = "Container management system written in Rust")]
+pub struct Cli {
+    /// Daemon to connect to (unix:///path or tcp://host:port, overrides ROCKER_HOST and the context)
+    #[arg(short = 'H', long)]
+    pub host: Option<String>,
+
+    /// Name of the context to use (overrides ROCKER_CONTEXT and the current context)
+    #[arg(short, long, conflicts_with = "host")]
+    pub context: Option<String>,
+
+    /// Suppress the progress output of pulls, pushes and builds
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Disable colored output (also disabled when NO_COLOR is set)
+    #[arg(long)]
+    pub no_color: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+// 引数の構造体はパースの後に一度だけ作るので、サイズの差は気にしない
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run a container
+    Run(RunArgs),
+    /// Create a container without starting it
+    Create(CreateArgs),
+    /// Start one or more created or stopped containers
+    Start(StartArgs),
+    /// Run a command in a running container
+    Exec(ExecArgs),
+    /// Fetch the logs of a container
+    Logs(LogsArgs),
+    /// Stop one or more running containers
+    Stop(StopArgs),
+    /// Remove one or more containers
+    Rm(RmArgs),
+    /// List containers
+    Ps(PsArgs),
+    /// Display detailed information on one or more containers, including their health
+    Inspect(InspectArgs),
+    /// Display a live stream of container resource usage statistics
+    Stats(StatsArgs),
+    /// List images
+    Images(ImagesArgs),
+    /// Manage images
+    #[command(subcommand)]
+    Image(image::ImageCommand),
+    /// Export a container's filesystem as a tar archive
+    Export(ExportArgs),
+    /// Import the contents from a tarball to create a filesystem image
+    Import(ImportArgs),
+    /// Manage networks
+    #[command(subcommand)]
+    Network(network::NetworkCommand),
+    /// Manage volumes
+    #[command(subcommand)]
+    Volume(volume::VolumeCommand),
+    /// Manage secrets (stored encrypted by the daemon and mounted into containers with --secret)
+    #[command(subcommand)]
+    Secret(secret::SecretCommand),
+    /// Manage schedules (containers created on a cron expression with run --schedule)
+    #[command(subcommand)]
+    Schedule(schedule::ScheduleCommand),
+    /// Manage rocker
+    #[command(subcommand)]
+    System(system::SystemCommand),
+    /// Manage the swarm (a manager and the workers that joined it)
+    #[command(subcommand)]
+    Swarm(swarm::SwarmCommand),
+    /// Manage swarm services
+    #[command(subcommand)]
+    Service(service::ServiceCommand),
+    /// Manage swarm nodes
+    #[command(subcommand)]
+    Node(node::NodeCommand),
+    /// Deploy compose files onto the swarm
+    #[command(subcommand)]
+    Stack(stack::StackCommand),
+    /// Build an image from a Rockerfile
+    Build(BuildArgs),
+    /// Manage the build cache
+    #[command(subcommand)]
+    Builder(builder::BuilderCommand),
+    /// Define and run multi-container applications
+    #[command(subcommand)]
+    Compose(compose::ComposeCommand),
+    /// Manage contexts (daemon endpoints with their TLS settings)
+    #[command(subcommand)]
+    Context(context::ContextCommand),
+    /// Log in to a registry
+    Login(LoginArgs),
+    /// Log out from a registry
+    Logout(LogoutArgs),
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    /// Run container in background
+    #[arg(short, long)]
+    pub detach: bool,
+
+    /// Key sequence detaching from a foreground container, leaving it running (e.g. ctrl-p,ctrl-q; empty to disable)
+    #[arg(long, value_name = "KEYS", default_value = DEFAULT_DETACH_KEYS, value_parser = detach_keys, conflicts_with = "detach")]
+    pub detach_keys: String,
+
+    /// Instead of running the container now, create a schedule running it on a cron expression ("minute hour day month weekday" in the daemon's time zone, or @hourly, @daily, @weekly, @monthly, @yearly)
+    #[arg(long, value_name = "CRON", conflicts_with_all = ["rm", "detach"])]
+    pub schedule: Option<String>,
+
+    /// What a scheduled run does while the previous one still runs ("allow" starts anyway, "forbid" skips it, "replace" stops the previous one)
+    #[arg(long, value_parser = ["allow", "forbid", "replace"], requires = "schedule")]
+    pub schedule_overlap: Option<String>,
+
+    /// Number of finished runs a schedule keeps, the containers of older runs are removed (default 10)
+    #[arg(long, value_name = "N", requires = "schedule")]
+    pub schedule_history: Option<usize>,
+
+    #[command(flatten)]
+    pub container: CreateArgs,
+}
+
+// run と create に共通する、コンテナの設定の引数
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Assign a name to the container
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Replace the container of the same name, if any (it is stopped and removed once the new one is created)
+    #[arg(long, requires = "name")]
+    pub replace: bool,
+
+    /// Restart policy to apply when the container exits (no, on-failure[:max-retries], always, unless-stopped)
+    #[arg(long, value_parser = RestartPolicy::parse, conflicts_with = "rm")]
+    pub restart: Option<RestartPolicy>,
+
+    /// Container host name
+    #[arg(long)]
+    pub hostname: Option<String>,
+
+    /// Username or UID (format: <name|uid>[:<group|gid>])
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// Working directory inside the container
+    #[arg(short, long)]
+    pub workdir: Option<String>,
+
+    /// Set metadata on the container (key=value)
+    #[arg(short, long = "label", value_name = "LABEL")]
+    pub labels: Vec<String>,
+
+    /// Overwrite the default ENTRYPOINT of the image (the image's CMD is then not used)
+    #[arg(long)]
+    pub entrypoint: Option<String>,
+
+    /// Set environment variables (KEY=VALUE, or KEY to pass the variable from this environment)
+    #[arg(short, long)]
+    pub env: Vec<String>,
+
+    /// Read environment variables from a file (KEY=VALUE or KEY lines, # comments; later files and -e take precedence)
+    #[arg(long = "env-file", value_name = "FILE")]
+    pub env_files: Vec<PathBuf>,
+
+    /// Bind mount a volume ([src:]dst[:opts], an anonymous volume is created without src; opts: ro, rw, z, Z, [r]private, [r]shared, [r]slave)
+    #[arg(short, long = "volume", value_name = "VOLUME")]
+    pub volumes: Vec<String>,
+
+    /// Mount volumes from the specified container(s) (container[:ro|rw])
+    #[arg(long)]
+    pub volumes_from: Vec<String>,
+
+    /// Mount a secret as a file under /run/secrets (name or source=name[,target=path][,uid=N][,gid=N][,mode=0400])
+    #[arg(long = "secret", value_name = "SECRET")]
+    pub secrets: Vec<String>,
+
+    /// Automatically remove the container and its anonymous volumes when it exits
+    #[arg(long)]
+    pub rm: bool,
+
+    /// Pull the image before creating the container ("always", "missing" or "never")
+    #[arg(long, value_parser = ["always", "missing", "never"], default_value = "missing")]
+    pub pull: String,
+
+    /// Command to run to check health (run with /bin/sh -c, replaces the image's HEALTHCHECK command)
+    #[arg(long, value_name = "COMMAND")]
+    pub health_cmd: Option<String>,
+
+    /// Time between running the check (e.g. 30s, 1m; default 30s)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub health_interval: Option<Duration>,
+
+    /// Consecutive failures needed to report unhealthy (default 3)
+    #[arg(long, value_name = "N")]
+    pub health_retries: Option<u32>,
+
+    /// Maximum time to allow one check to run (default 30s)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub health_timeout: Option<Duration>,
+
+    /// Time for the container to initialize before failed checks count (default 0s)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub health_start_period: Option<Duration>,
+
+    /// Disable any health check, including the image's HEALTHCHECK
+    #[arg(long, conflicts_with_all = ["health_cmd", "health_interval", "health_retries", "health_timeout", "health_start_period", "health_restart"])]
+    pub no_healthcheck: bool,
+
+    /// Restart the container when its health check reports it as unhealthy
+    #[arg(long, conflicts_with = "rm")]
+    pub health_restart: bool,
+
+    /// Start the container only once this dependency is ready: a container (running, and healthy if it has a health check) or tcp://host:port accepting connections
+    #[arg(long, value_name = "TARGET", value_parser = WaitFor::parse)]
+    pub wait_for: Vec<WaitFor>,
+
+    /// How long to wait for the --wait-for dependencies before failing the start (default 60s)
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, requires = "wait_for")]
+    pub wait_timeout: Option<Duration>,
+
+    /// Publish a container's port(s) to the host ([[ip:][host]:]container[/proto], ports may be ranges)
+    #[arg(short, long = "port", value_name = "PORT")]
+    pub ports: Vec<String>,
+
+    /// Publish all exposed ports to random ports
+    #[arg(short = 'P', long)]
+    pub publish_all: bool,
+
+    /// Memory limit (e.g. 512m, 2g)
+    #[arg(short, long, value_parser = parse_size)]
+    pub memory: Option<u64>,
+
+    /// Number of CPUs (e.g. 0.5)
+    #[arg(long)]
+    pub cpus: Option<f64>,
+
+    /// Add a host device to the container (host[:container][:permissions])
+    #[arg(long = "device", value_name = "DEVICE")]
+    pub devices: Vec<String>,
+
+    /// Add a rule to the cgroup allowed devices list
+    #[arg(long = "device-cgroup-rule", value_name = "DEVICE_CGROUP_RULE")]
+    pub device_cgroup_rules: Vec<String>,
+
+    /// GPU devices to add to the container ('all' to pass all GPUs)
+    #[arg(long)]
+    pub gpus: Option<String>,
+
+    /// Signal to stop the container
+    #[arg(long)]
+    pub stop_signal: Option<String>,
+
+    /// Timeout to stop the container (seconds, or a duration such as 1m30s)
+    #[arg(long, value_parser = parse_duration)]
+    pub stop_timeout: Option<Duration>,
+
+    /// Add a custom host-to-IP mapping (host:ip)
+    #[arg(long = "add-host", value_name = "ADD_HOST")]
+    pub add_hosts: Vec<String>,
+
+    /// Connect a container to a network (bridge, host, none, container:<name|id> or a network name)
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// IPv4 address (e.g., 172.30.100.104)
+    #[arg(long)]
+    pub ip: Option<Ipv4Addr>,
+
+    /// Network endpoint options (rate, egress-rate, ingress-rate, priority)
+    #[arg(long = "network-opt", value_name = "NETWORK_OPT")]
+    pub network_opts: Vec<String>,
+
+    /// Set custom DNS servers
+    #[arg(long)]
+    pub dns: Vec<IpAddr>,
+
+    /// Set custom DNS search domains
+    #[arg(long)]
+    pub dns_search: Vec<String>,
+
+    /// Sysctl options (key=value)
+    #[arg(long = "sysctl", value_name = "SYSCTL")]
+    pub sysctls: Vec<String>,
+
+    /// Ulimit options (name=soft[:hard])
+    #[arg(long = "ulimit", value_name = "ULIMIT")]
+    pub ulimits: Vec<String>,
+
+    /// Tune container pids limit (set -1 for unlimited)
+    #[arg(long, allow_negative_numbers = true)]
+    pub pids_limit: Option<i64>,
+
+    /// Run an executable on the host at a lifecycle point (prestart|poststart|poststop=/path [args])
+    #[arg(long = "hook", value_name = "HOOK")]
+    pub hooks: Vec<String>,
+
+    /// Disable OOM Killer
+    #[arg(long)]
+    pub oom_kill_disable: bool,
+
+    /// Tune host's OOM preferences (-1000 to 1000)
+    #[arg(long, allow_negative_numbers = true, value_parser = clap::value_parser!(i32).range(-1000..=1000))]
+    pub oom_score_adj: Option<i32>,
+
+    /// Logging driver for the container (json-file|fluentd|gelf|none)
+    #[arg(long)]
+    pub log_driver: Option<String>,
+
+    /// Log driver options (e.g. max-size=10m, max-file=3)
+    #[arg(long = "log-opt", value_name = "LOG_OPT")]
+    pub log_opts: Vec<String>,
+
+    /// The image to run
+    pub image: String,
+
+    /// Command to run
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct StartArgs {
+    /// The containers to start
+    #[arg(required = true)]
+    pub containers: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ExecArgs {
+    /// Detached mode: run command in the background
+    #[arg(short, long)]
+    pub detach: bool,
+
+    /// Keep STDIN open and send it to the command
+    #[arg(short, long, conflicts_with = "detach")]
+    pub interactive: bool,
+
+    /// Username or UID (format: <name|uid>[:<group|gid>])
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// Set environment variables (KEY=VALUE, or KEY to pass the variable from this environment)
+    #[arg(short, long)]
+    pub env: Vec<String>,
+
+    /// Read environment variables from a file (KEY=VALUE or KEY lines, # comments; later files and -e take precedence)
+    #[arg(long = "env-file", value_name = "FILE")]
+    pub env_files: Vec<PathBuf>,
+
+    /// Working directory inside the container
+    #[arg(short, long)]
+    pub workdir: Option<String>,
+
+    /// The container to run the command in
+    pub container: String,
+
+    /// Command to run
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct LogsArgs {
+    /// Follow log output
+    #[arg(short, long)]
+    pub follow: bool,
+
+    /// Number of lines to show from the end of the logs (default "all")
+    #[arg(short = 'n', long)]
+    pub tail: Option<String>,
+
+    /// Show logs since a timestamp (e.g. "2024-01-02T15:04:05Z") or relative (e.g. "10m")
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Show logs before a timestamp (e.g. "2024-01-02T15:04:05Z") or relative (e.g. "10m")
+    #[arg(long)]
+    pub until: Option<String>,
+
+    /// Show timestamps
+    #[arg(short, long)]
+    pub timestamps: bool,
+
+    /// The container to show the logs of
+    pub container: String,
+}
+
+#[derive(Args)]
+pub struct StopArgs {
+    /// Time to wait before killing the container (seconds, or a duration such as 1m30s)
+    #[arg(short, long, value_parser = parse_duration)]
+    pub time: Option<Duration>,
+
+    /// The containers to stop
+    #[arg(required = true)]
+    pub containers: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Force the removal of a running container
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Remove anonymous volumes associated with the container
+    #[arg(short, long)]
+    pub volumes: bool,
+
+    /// The containers to remove
+    #[arg(required = true)]
+    pub containers: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct PsArgs {
+    /// Show all containers (default shows just running)
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Filter output (id, name, label, status or health=starting|healthy|unhealthy|none; KEY!=VALUE excludes)
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Display total file sizes (writable layer and virtual size)
+    #[arg(short, long)]
+    pub size: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Add the size of the writable layer (size_rw) and the virtual size (size_root_fs)
+    #[arg(short, long)]
+    pub size: bool,
+
+    /// The containers to inspect
+    #[arg(required = true)]
+    pub containers: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    /// Show all containers (default shows just running)
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Disable streaming stats and only pull the first result
+    #[arg(long)]
+    pub no_stream: bool,
+
+    /// The containers to show (default shows all running)
+    pub containers: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct ImagesArgs {
+    /// Show all images (default hides intermediate images)
+    #[arg(short, long)]
+    pub all: bool,
+
+    /// Filter output (dangling=true|false, reference=PATTERN, label=KEY[=VALUE], before=IMAGE or since=IMAGE; KEY!=VALUE excludes)
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Show the registry digests of the images
+    #[arg(long)]
+    pub digests: bool,
+
+    /// Don't truncate the image IDs
+    #[arg(long)]
+    pub no_trunc: bool,
+
+    /// Show the images as a tree of their parent images
+    #[arg(long)]
+    pub tree: bool,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Write to a file, instead of STDOUT
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// The container to export
+    pub container: String,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Apply Rockerfile instruction to the created image
+    #[arg(short, long = "change", value_name = "CHANGE")]
+    pub changes: Vec<String>,
+
+    /// Set commit message for imported image
+    #[arg(short, long)]
+    pub message: Option<String>,
+
+    /// The tarball to import ('-' to read from STDIN)
+    pub file: String,
+
+    /// Name and optionally a tag in the 'name:tag' format
+    pub reference: Option<String>,
+}
+
+#[derive(Args)]
+pub struct LoginArgs {
+    /// Username (prompted for if omitted)
+    #[arg(short, long)]
+    pub username: Option<String>,
+
+    /// Password or access token (visible in the process list, prefer --password-stdin)
+    #[arg(short, long, conflicts_with = "password_stdin")]
+    pub password: Option<String>,
+
+    /// Read the password or access token from STDIN
+    #[arg(long)]
+    pub password_stdin: bool,
+
+    /// Registry to log in to (default: defaultRegistry in config.json, or docker.io)
+    pub server: Option<String>,
+}
+
+#[derive(Args)]
+pub struct LogoutArgs {
+    /// Registry to log out from (default: defaultRegistry in config.json, or docker.io)
+    pub server: Option<String>,
+}
+
+#[derive(Args)]
+pub struct BuildArgs {
+    /// Name and optionally a tag in the 'name:tag' format
+    #[arg(short, long)]
+    pub tag: Option<String>,
+
+    /// Name of the Rockerfile (Default is 'Rockerfile')
+    #[arg(short, long)]
+    pub file: Option<PathBuf>,
+
+    /// Squash the built image into a single layer
+    #[arg(long)]
+    pub squash: bool,
+
+    /// Do not use the build cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Import a build cache (type=local,src=<dir>, type=registry,ref=<repo:tag> or <repo:tag>; repeatable)
+    #[arg(long)]
+    pub cache_from: Vec<String>,
+
+    /// Export the build cache (type=local,dest=<dir>, type=registry,ref=<repo:tag> or <repo:tag>)
+    #[arg(long)]
+    pub cache_to: Option<String>,
+
+    /// Write the image ID, its layers and a summary of the build as JSON to the file
+    #[arg(long, value_name = "FILE")]
+    pub metadata_file: Option<PathBuf>,
+
+    /// Path to the build context
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+}
+
+// 512m・2g などの大きさ（単位のない値はバイト）
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    rocker_core::parse_memory_size(s)
+}
+
+// 秒数（10）か単位を付けた時間（500ms・30s・1m30s・2h）
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    rocker_core::parse_duration(s)
+}
+
+// ctrl-p,ctrl-q のようなキーの並び（空ならキーで切り離さない）
+pub fn detach_keys(s: &str) -> Result<String, String> {
+    if !s.is_empty() {
+        rocker_client::parse_detach_keys(s)?;
+    }
+    Ok(s.to_string())
+}
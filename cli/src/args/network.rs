@@ -0,0 +1,116 @@
+use clap::{Args, Subcommand};
+use std::net::Ipv4Addr;
+
+#[derive(Subcommand)]
+pub enum NetworkCommand {
+    /// Create a network
+    Create(CreateArgs),
+    /// List networks
+    Ls(LsArgs),
+    /// Remove one or more networks
+    Rm(RmArgs),
+    /// Display detailed information on one or more networks
+    Inspect(InspectArgs),
+    /// Remove all unused networks
+    Prune(PruneArgs),
+    /// Connect a container to a network
+    Connect(ConnectArgs),
+    /// Disconnect a container from a network
+    Disconnect(DisconnectArgs),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Driver to manage the Network
+    #[arg(short, long, default_value = "bridge")]
+    pub driver: String,
+
+    /// Subnet in CIDR format that represents a network segment
+    #[arg(long)]
+    pub subnet: Option<String>,
+
+    /// IPv4 Gateway for the master subnet
+    #[arg(long)]
+    pub gateway: Option<Ipv4Addr>,
+
+    /// Allocate container ip from a sub-range
+    #[arg(long)]
+    pub ip_range: Option<String>,
+
+    /// Restrict external access to the network
+    #[arg(long)]
+    pub internal: bool,
+
+    /// Set metadata on a network
+    #[arg(long = "label", value_name = "LABEL")]
+    pub labels: Vec<String>,
+
+    /// Set driver specific options
+    #[arg(short, long = "opt", value_name = "OPT")]
+    pub opts: Vec<String>,
+
+    /// Network name
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Provide filter values (e.g. 'driver=bridge')
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Only display network IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Networks to remove
+    #[arg(required = true)]
+    pub networks: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Networks to inspect
+    #[arg(required = true)]
+    pub networks: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+    /// Provide filter values (e.g. "label=ci", "until=24h")
+    #[arg(long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Do not prompt for confirmation
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct ConnectArgs {
+    /// Add network-scoped alias for the container
+    #[arg(long = "alias", value_name = "ALIAS")]
+    pub aliases: Vec<String>,
+
+    /// IPv4 address (e.g., 172.30.100.104)
+    #[arg(long)]
+    pub ip: Option<Ipv4Addr>,
+
+    /// The network to connect to
+    pub network: String,
+
+    /// The container to connect
+    pub container: String,
+}
+
+#[derive(Args)]
+pub struct DisconnectArgs {
+    /// The network to disconnect from
+    pub network: String,
+
+    /// The container to disconnect
+    pub container: String,
+}
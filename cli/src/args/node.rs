@@ -0,0 +1,61 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum NodeCommand {
+    /// List the nodes of the swarm
+    Ls(LsArgs),
+    /// Display detailed information on one or more nodes
+    Inspect(InspectArgs),
+    /// Move the tasks of one or more nodes to other nodes and stop scheduling on them
+    Drain(DrainArgs),
+    /// Update the availability or labels of a node
+    Update(UpdateArgs),
+    /// Remove one or more workers from the swarm
+    Rm(RmArgs),
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Only display node IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Nodes to inspect (ID, ID prefix or hostname)
+    #[arg(required = true)]
+    pub nodes: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct DrainArgs {
+    /// Nodes to drain (ID, ID prefix or hostname)
+    #[arg(required = true)]
+    pub nodes: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct UpdateArgs {
+    /// Availability of the node
+    #[arg(long, value_parser = ["active", "pause", "drain"])]
+    pub availability: Option<String>,
+
+    /// Add or change a node label (key=value), matched by "node.labels.<key>" constraints
+    #[arg(long = "label-add", value_name = "LABEL")]
+    pub labels_add: Vec<String>,
+
+    /// Remove a node label
+    #[arg(long = "label-rm", value_name = "KEY")]
+    pub labels_rm: Vec<String>,
+
+    /// Node to update (ID, ID prefix or hostname)
+    pub node: String,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Nodes to remove (ID, ID prefix or hostname)
+    #[arg(required = true)]
+    pub nodes: Vec<String>,
+}
@@ -0,0 +1,52 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum ScheduleCommand {
+    /// List schedules (created with run --schedule)
+    Ls(LsArgs),
+    /// Display detailed information on one or more schedules
+    Inspect(InspectArgs),
+    /// Show the runs of a schedule with their containers and exit codes
+    History(HistoryArgs),
+    /// Remove one or more schedules and the containers of their runs
+    Rm(RmArgs),
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Provide filter values (e.g. 'name=backup')
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Only display schedule IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Schedules to inspect
+    #[arg(required = true)]
+    pub schedules: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// Don't truncate output
+    #[arg(long)]
+    pub no_trunc: bool,
+
+    /// Schedule to show the runs of
+    pub schedule: String,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Stop the running containers of the schedules before removing them
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Schedules to remove
+    #[arg(required = true)]
+    pub schedules: Vec<String>,
+}
@@ -0,0 +1,51 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum SecretCommand {
+    /// Create a secret from a file or from standard input
+    Create(CreateArgs),
+    /// List secrets
+    Ls(LsArgs),
+    /// Display detailed information on one or more secrets (never their data)
+    Inspect(InspectArgs),
+    /// Remove one or more secrets
+    Rm(RmArgs),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Set metadata for a secret
+    #[arg(short, long = "label", value_name = "LABEL")]
+    pub labels: Vec<String>,
+
+    /// Secret name
+    pub name: String,
+
+    /// File to read the secret from ("-" reads standard input)
+    pub file: String,
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Provide filter values (e.g. 'label=app', 'name=db')
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Only display secret IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Secrets to inspect
+    #[arg(required = true)]
+    pub secrets: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Secrets to remove
+    #[arg(required = true)]
+    pub secrets: Vec<String>,
+}
@@ -0,0 +1,85 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum ServiceCommand {
+    /// Create a service with replicas scheduled across the swarm
+    Create(CreateArgs),
+    /// List services
+    Ls(LsArgs),
+    /// Display detailed information on one or more services
+    Inspect(InspectArgs),
+    /// List the tasks of a service
+    Ps(PsArgs),
+    /// Scale one or more services (SERVICE=REPLICAS)
+    Scale(ScaleArgs),
+    /// Remove one or more services
+    Rm(RmArgs),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Service name
+    #[arg(long)]
+    pub name: String,
+
+    /// Number of tasks
+    #[arg(long, default_value_t = 1)]
+    pub replicas: u64,
+
+    /// Set environment variables
+    #[arg(short, long)]
+    pub env: Vec<String>,
+
+    /// Set metadata on the service's containers
+    #[arg(short, long = "label", value_name = "LABEL")]
+    pub labels: Vec<String>,
+
+    /// Attach the tasks to an overlay network created for the service across the swarm
+    #[arg(long)]
+    pub network: Option<String>,
+
+    /// Placement constraint (e.g. 'node.labels.zone==east', 'node.role!=manager')
+    #[arg(long = "constraint", value_name = "CONSTRAINT")]
+    pub constraints: Vec<String>,
+
+    /// Image to run
+    pub image: String,
+
+    /// Command to run in the containers
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Only display service IDs
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Services to inspect
+    #[arg(required = true)]
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct PsArgs {
+    /// Service whose tasks to list
+    pub service: String,
+}
+
+#[derive(Args)]
+pub struct ScaleArgs {
+    /// Services and their number of replicas (SERVICE=REPLICAS)
+    #[arg(required = true, value_name = "SERVICE=REPLICAS")]
+    pub services: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Services to remove
+    #[arg(required = true)]
+    pub services: Vec<String>,
+}
@@ -0,0 +1,41 @@
+use clap::{Args, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum StackCommand {
+    /// Deploy a compose file as services of the swarm, or update them
+    Deploy(DeployArgs),
+    /// List stacks
+    Ls,
+    /// List the tasks of a stack
+    Ps(PsArgs),
+    /// Remove one or more stacks
+    Rm(RmArgs),
+}
+
+#[derive(Args)]
+pub struct DeployArgs {
+    /// Compose file to deploy (can be given multiple times, rocker-compose.yaml by default)
+    #[arg(short = 'c', long = "compose-file", value_name = "FILE")]
+    pub files: Vec<PathBuf>,
+
+    /// Remove services of the stack that are no longer in the compose file
+    #[arg(long)]
+    pub prune: bool,
+
+    /// Stack name
+    pub stack: String,
+}
+
+#[derive(Args)]
+pub struct PsArgs {
+    /// Stack name
+    pub stack: String,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Stacks to remove
+    #[arg(required = true)]
+    pub stacks: Vec<String>,
+}
@@ -0,0 +1,58 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum SwarmCommand {
+    /// Initialize a swarm with this daemon as its manager
+    Init(InitArgs),
+    /// Join a swarm as a worker
+    Join(JoinArgs),
+    /// Leave the swarm
+    Leave(LeaveArgs),
+    /// Display or rotate the token for joining the swarm
+    JoinToken(JoinTokenArgs),
+    /// Display the swarm this daemon is part of and its join token
+    Inspect,
+}
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Address other nodes reach this manager at (IPv4[:port], port 2377 by default)
+    #[arg(long = "advertise-addr", value_name = "ADDR")]
+    pub advertise_addr: String,
+
+    /// Address to accept node connections on (defaults to 0.0.0.0 with the advertised port)
+    #[arg(long = "listen-addr", value_name = "ADDR")]
+    pub listen_addr: Option<String>,
+}
+
+#[derive(Args)]
+pub struct JoinArgs {
+    /// Join token printed by "rocker swarm init" on the manager
+    #[arg(long)]
+    pub token: String,
+
+    /// Address other nodes reach this node at (IPv4, used as its overlay network endpoint)
+    #[arg(long = "advertise-addr", value_name = "ADDR")]
+    pub advertise_addr: String,
+
+    /// Manager address (HOST[:PORT], port 2377 by default)
+    pub remote: String,
+}
+
+#[derive(Args)]
+pub struct LeaveArgs {
+    /// Leave even if other nodes remain (manager) or the manager cannot be reached (worker)
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct JoinTokenArgs {
+    /// Replace the token (nodes that already joined are not affected)
+    #[arg(long)]
+    pub rotate: bool,
+
+    /// Only display the token
+    #[arg(short, long)]
+    pub quiet: bool,
+}
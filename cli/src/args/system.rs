@@ -0,0 +1,16 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum SystemCommand {
+    /// Show rocker disk usage
+    Df(DfArgs),
+    /// Show system-wide information, including the CPUs and memory reserved by containers
+    Info,
+}
+
+#[derive(Args)]
+pub struct DfArgs {
+    /// Show detailed information on space usage
+    #[arg(short, long)]
+    pub verbose: bool,
+}
@@ -0,0 +1,114 @@
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand)]
+pub enum VolumeCommand {
+    /// Create a volume
+    Create(CreateArgs),
+    /// List volumes
+    Ls(LsArgs),
+    /// Remove one or more volumes
+    Rm(RmArgs),
+    /// Display detailed information on one or more volumes
+    Inspect(InspectArgs),
+    /// Remove all unused volumes
+    Prune(PruneArgs),
+    /// Create a volume with a copy of the data of another volume
+    Clone(CloneArgs),
+    /// Manage volume snapshots (requires btrfs, XFS or ZFS)
+    #[command(subcommand)]
+    Snapshot(SnapshotCommand),
+}
+
+#[derive(Args)]
+pub struct CreateArgs {
+    /// Specify volume driver name
+    #[arg(short, long, default_value = "local")]
+    pub driver: String,
+
+    /// Set metadata for a volume
+    #[arg(long = "label", value_name = "LABEL")]
+    pub labels: Vec<String>,
+
+    /// Set driver specific options
+    #[arg(short, long = "opt", value_name = "OPT")]
+    pub opts: Vec<String>,
+
+    /// Volume name (generated if omitted)
+    pub name: Option<String>,
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    /// Provide filter values (e.g. 'dangling=true')
+    #[arg(short, long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Format the output using a template (e.g. '{{.Name}}\t{{.Size}}')
+    #[arg(long)]
+    pub format: Option<String>,
+
+    /// Only display volume names
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Args)]
+pub struct RmArgs {
+    /// Volumes to remove
+    #[arg(required = true)]
+    pub volumes: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// Volumes to inspect
+    #[arg(required = true)]
+    pub volumes: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+    /// Provide filter values (e.g. "label=ci", "until=24h")
+    #[arg(long = "filter", value_name = "FILTER")]
+    pub filters: Vec<String>,
+
+    /// Do not prompt for confirmation
+    #[arg(short, long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct CloneArgs {
+    /// Volume to copy
+    pub source: String,
+
+    /// Name of the new volume
+    pub name: String,
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotCommand {
+    /// Take a snapshot of the data of a volume
+    Create(SnapshotArgs),
+    /// List the snapshots of a volume
+    Ls(SnapshotLsArgs),
+    /// Restore the data of a volume from a snapshot
+    Restore(SnapshotArgs),
+    /// Remove a snapshot
+    Rm(SnapshotArgs),
+}
+
+#[derive(Args)]
+pub struct SnapshotArgs {
+    /// Volume name
+    pub volume: String,
+
+    /// Snapshot name
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct SnapshotLsArgs {
+    /// Volume name
+    pub volume: String,
+}
@@ -0,0 +1,55 @@
+use rocker_client::{Client, ImageBuildOptions, Progress};
+use std::error::Error;
+use std::path::Path;
+
+use crate::args::BuildArgs;
+use crate::utils::block_on;
+
+// build [-t NAME[:TAG]] [-f ROCKERFILE] [--squash] [--no-cache] [--cache-from SPEC] [--cache-to SPEC] [--metadata-file FILE] [PATH]
+//
+// PATH のディレクトリを tar にしてデーモンに送り、ビルドの進捗を表示する。-f は PATH の中のファイルを指す
+// 必要がある。--metadata-file ではビルドしたイメージの ID・レイヤ・ステップ数を JSON で書き出す。
+pub fn execute(args: &BuildArgs) -> Result<(), Box<dyn Error>> {
+    if !args.path.is_dir() {
+        return Err(format!("Build context not found: {}", args.path.display()).into());
+    }
+    let options = ImageBuildOptions {
+        tag: args.tag.clone(),
+        rockerfile: args.file.as_deref().map(|file| rockerfile_in_context(&args.path, file)).transpose()?,
+        squash: args.squash,
+        no_cache: args.no_cache,
+        cache_from: args.cache_from.clone(),
+        cache_to: args.cache_to.clone(),
+        ..ImageBuildOptions::default()
+    };
+    let client = Client::new();
+
+    let metadata = block_on(async {
+        let context = rocker_core::archive_dir(&args.path).await?;
+        let build = client.build_image(&options, context).await?;
+        Progress::new().report_build(build).await
+    })?;
+
+    if let Some(metadata) = metadata {
+        if rocker_client::is_quiet() {
+            println!("{}", metadata.image_id);
+        }
+        if let Some(path) = &args.metadata_file {
+            std::fs::write(path, serde_json::to_string_pretty(&metadata)?)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+// -f のファイルのビルドコンテキストの中でのパス
+fn rockerfile_in_context(context: &Path, file: &Path) -> Result<String, Box<dyn Error>> {
+    let context = context.canonicalize()?;
+    let file = file
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let relative = file
+        .strip_prefix(&context)
+        .map_err(|_| format!("The Rockerfile {} must be inside the build context {}", file.display(), context.display()))?;
+    Ok(relative.to_string_lossy().into_owned())
+}
@@ -0,0 +1,42 @@
+use rocker_client::Client;
+use rocker_core::format_size;
+use std::error::Error;
+
+use crate::args::builder::DuArgs;
+use crate::utils::{block_on, print_table, short_id, time_ago};
+
+// -v で表示するエントリの数
+const LARGEST_ENTRIES: usize = 10;
+
+// builder du [-v]
+//
+// キャッシュの合計と、イメージが使っていない（prune で消せる）分を表示する。-v では大きい順にエントリも表示する。
+pub fn execute(args: &DuArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let mut entries = block_on(client.build_cache())?;
+
+    if args.verbose {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.size));
+        let rows: Vec<[String; 4]> = entries
+            .iter()
+            .take(LARGEST_ENTRIES)
+            .map(|entry| {
+                [
+                    short_id(&entry.id),
+                    format_size(entry.size),
+                    entry.last_used_at.map_or_else(String::new, time_ago),
+                    entry.in_use.to_string(),
+                ]
+            })
+            .collect();
+        print_table(&["CACHE ID", "SIZE", "LAST USED", "IN USE"], &rows);
+        println!();
+    }
+
+    let total: u64 = entries.iter().map(|entry| entry.size).sum();
+    let reclaimable: u64 = entries.iter().filter(|entry| !entry.in_use).map(|entry| entry.size).sum();
+    println!("Entries:      {}", entries.len());
+    println!("Total:        {}", format_size(total));
+    println!("Reclaimable:  {}", format_size(reclaimable));
+    Ok(())
+}
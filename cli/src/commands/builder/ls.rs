@@ -0,0 +1,62 @@
+use rocker_client::Client;
+use rocker_core::{format_size, BuildCacheEntry};
+use std::error::Error;
+
+use crate::args::builder::LsArgs;
+use crate::utils::{block_on, print_table, render_template, short_id, time_ago};
+
+// 一覧で命令を切り詰める長さ
+const CREATED_BY_WIDTH: usize = 40;
+
+// builder ls [--format TEMPLATE] [-q] [--no-trunc]（最近使った順）
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let entries = block_on(client.build_cache())?;
+    if args.quiet {
+        for entry in &entries {
+            println!("{}", entry.id);
+        }
+        return Ok(());
+    }
+    if let Some(format) = &args.format {
+        for entry in &entries {
+            println!("{}", render_template(format, &fields(entry))?);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|entry| {
+            let created_by = entry.created_by.clone().unwrap_or_default();
+            let created_by = if args.no_trunc || created_by.chars().count() <= CREATED_BY_WIDTH {
+                created_by
+            } else {
+                format!("{}…", created_by.chars().take(CREATED_BY_WIDTH - 1).collect::<String>())
+            };
+            [
+                short_id(&entry.id),
+                created_by,
+                format_size(entry.size),
+                entry.last_used_at.map_or_else(String::new, time_ago),
+                entry.usage_count.to_string(),
+                entry.in_use.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&["CACHE ID", "CREATED BY", "SIZE", "LAST USED", "USAGE", "IN USE"], &rows);
+    Ok(())
+}
+
+// --format で使えるフィールド
+fn fields(entry: &BuildCacheEntry) -> [(&'static str, String); 7] {
+    [
+        ("ID", entry.id.clone()),
+        ("CreatedBy", entry.created_by.clone().unwrap_or_default()),
+        ("Size", format_size(entry.size)),
+        ("InUse", entry.in_use.to_string()),
+        ("CreatedAt", entry.created_at.map_or_else(String::new, |time| time.to_rfc3339())),
+        ("LastUsedAt", entry.last_used_at.map_or_else(String::new, |time| time.to_rfc3339())),
+        ("UsageCount", entry.usage_count.to_string()),
+    ]
+}
@@ -0,0 +1,3 @@
+pub mod du;
+pub mod ls;
+pub mod prune;
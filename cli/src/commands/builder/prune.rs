@@ -0,0 +1,31 @@
+use rocker_client::Client;
+use rocker_core::format_size;
+use std::error::Error;
+
+use crate::args::builder::PruneArgs;
+use crate::utils::{block_on, confirm, parse_filters};
+
+// builder prune [-a] [--filter until=DURATION] [--keep-storage SIZE] [-f]
+pub fn execute(args: &PruneArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let warning = if args.all {
+        "This will remove all build cache."
+    } else {
+        "This will remove the build cache no image uses."
+    };
+    if !args.force && !confirm(warning)? {
+        return Ok(());
+    }
+
+    let client = Client::new();
+    let report = block_on(client.prune_build_cache(args.all, &filters, args.keep_storage))?;
+    if !report.caches_deleted.is_empty() {
+        println!("Deleted build cache objects:");
+        for id in &report.caches_deleted {
+            println!("{}", id);
+        }
+        println!();
+    }
+    println!("Total reclaimed space: {}", format_size(report.space_reclaimed));
+    Ok(())
+}
@@ -0,0 +1,15 @@
+use rocker_compose::BuildOptions;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::BuildArgs;
+use crate::utils::block_on;
+
+// compose build [--parallel] [--pull] [SERVICE...]
+pub fn execute(args: &BuildArgs) -> Result<(), Box<dyn Error>> {
+    let options = BuildOptions {
+        parallel: args.parallel,
+        pull: args.pull,
+    };
+    block_on(rocker_compose::build_command(&files(&args.file.files), None, &args.services, &options))
+}
@@ -0,0 +1,22 @@
+use rocker_compose::GraphFormat;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::ConfigArgs;
+
+// compose config [-q] [--services] [--images] [--graph dot|json]
+//
+// 既定では変数を展開して重ねた設定を表示する。--services・--images・--graph ではそれだけを表示する。
+pub fn execute(args: &ConfigArgs) -> Result<(), Box<dyn Error>> {
+    let files = files(&args.file.files);
+    if args.services {
+        rocker_compose::config_services_command(&files)
+    } else if args.images {
+        rocker_compose::config_images_command(&files, None)
+    } else if let Some(graph) = &args.graph {
+        let format: GraphFormat = graph.parse()?;
+        rocker_compose::config_graph_command(&files, None, format)
+    } else {
+        rocker_compose::config_command(&files, args.quiet)
+    }
+}
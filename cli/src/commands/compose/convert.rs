@@ -0,0 +1,11 @@
+use rocker_compose::ConvertFormat;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::ConvertArgs;
+
+// compose convert [--format yaml|k8s]
+pub fn execute(args: &ConvertArgs) -> Result<(), Box<dyn Error>> {
+    let format: ConvertFormat = args.format.parse()?;
+    rocker_compose::convert_command(&files(&args.file.files), None, format)
+}
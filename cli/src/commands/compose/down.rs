@@ -0,0 +1,16 @@
+use rocker_compose::DownOptions;
+use std::error::Error;
+
+use crate::args::compose::DownArgs;
+use crate::utils::block_on;
+
+// compose down [-v] [--rmi all|local] [--remove-orphans] [-t TIMEOUT]（既定の compose ファイルのプロジェクト）
+pub fn execute(args: &DownArgs) -> Result<(), Box<dyn Error>> {
+    let options = DownOptions {
+        remove_volumes: args.volumes,
+        remove_images: args.rmi.as_deref().map(str::parse).transpose()?,
+        remove_orphans: args.remove_orphans,
+        timeout: args.timeout.map(|timeout| timeout.as_secs()),
+    };
+    block_on(rocker_compose::down_command(&[], None, &options))
+}
@@ -0,0 +1,10 @@
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::EventsArgs;
+use crate::utils::block_on;
+
+// compose events [--json] [SERVICE...]
+pub fn execute(args: &EventsArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::events_command(&files(&args.file.files), None, &args.services, args.json))
+}
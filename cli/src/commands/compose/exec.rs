@@ -0,0 +1,29 @@
+use rocker_compose::ExecOptions;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::ExecArgs;
+use crate::utils::block_on;
+
+// compose exec [-d] [-i] [-u USER] [-w DIR] [-e KEY=VALUE] [--index N] SERVICE COMMAND...（コマンドの終了コードで終了する）
+pub fn execute(args: &ExecArgs) -> Result<(), Box<dyn Error>> {
+    let options = ExecOptions {
+        detach: args.detach,
+        user: args.user.clone(),
+        workdir: args.workdir.clone(),
+        env: args.env.clone(),
+        index: args.index.map(|index| index as usize),
+        interactive: args.interactive,
+    };
+    let exit_code = block_on(rocker_compose::exec_command(
+        &files(&args.files),
+        None,
+        &args.service,
+        args.command.clone(),
+        &options,
+    ))?;
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use rocker_compose::LogsOptions;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::LogsArgs;
+use crate::utils::block_on;
+
+// compose logs [-f] [-n N] [-t] [--no-color] [SERVICE...]
+pub fn execute(args: &LogsArgs) -> Result<(), Box<dyn Error>> {
+    let options = LogsOptions {
+        follow: args.follow,
+        tail: args.tail,
+        timestamps: args.timestamps,
+        no_color: args.no_color,
+    };
+    block_on(rocker_compose::logs_command(&files(&args.files), None, &args.services, &options))
+}
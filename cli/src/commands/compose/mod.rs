@@ -0,0 +1,29 @@
+use std::path::PathBuf;
+
+pub mod build;
+pub mod config;
+pub mod convert;
+pub mod down;
+pub mod events;
+pub mod exec;
+pub mod logs;
+pub mod pause;
+pub mod port;
+pub mod ps;
+pub mod pull;
+pub mod push;
+pub mod restart;
+pub mod run;
+pub mod scale;
+pub mod start;
+pub mod stats;
+pub mod stop;
+pub mod top;
+pub mod unpause;
+pub mod up;
+pub mod watch;
+
+// -f の compose ファイル（空なら rocker-compose が既定のファイルを探す）
+pub fn files(paths: &[PathBuf]) -> Vec<String> {
+    paths.iter().map(|path| path.to_string_lossy().into_owned()).collect()
+}
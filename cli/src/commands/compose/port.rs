@@ -0,0 +1,19 @@
+use rocker_core::PortProtocol;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::PortArgs;
+use crate::utils::block_on;
+
+// compose port [--protocol tcp|udp] [--index N] SERVICE PRIVATE_PORT（公開しているホストのアドレスとポート）
+pub fn execute(args: &PortArgs) -> Result<(), Box<dyn Error>> {
+    let protocol = PortProtocol::parse(&args.protocol)?;
+    block_on(rocker_compose::port_command(
+        &files(&args.file.files),
+        None,
+        &args.service,
+        args.private_port,
+        protocol,
+        args.index as usize,
+    ))
+}
@@ -0,0 +1,10 @@
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::PsArgs;
+use crate::utils::block_on;
+
+// compose ps [-a] [-q] [SERVICE...]
+pub fn execute(args: &PsArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::ps_command(&files(&args.file.files), None, &args.services, args.all, args.quiet))
+}
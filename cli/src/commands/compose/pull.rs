@@ -0,0 +1,10 @@
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::ParallelArgs;
+use crate::utils::block_on;
+
+// compose pull [--parallel] [SERVICE...]
+pub fn execute(args: &ParallelArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::pull_command(&files(&args.file.files), None, &args.services, args.parallel))
+}
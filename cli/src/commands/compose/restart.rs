@@ -0,0 +1,11 @@
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::StopArgs;
+use crate::utils::block_on;
+
+// compose restart [-t TIMEOUT] [SERVICE...]
+pub fn execute(args: &StopArgs) -> Result<(), Box<dyn Error>> {
+    let timeout = args.timeout.map(|timeout| timeout.as_secs());
+    block_on(rocker_compose::restart_command(&files(&args.file.files), None, &args.services, timeout))
+}
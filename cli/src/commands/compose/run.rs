@@ -0,0 +1,34 @@
+use rocker_compose::RunOptions;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::RunArgs;
+use crate::utils::block_on;
+
+// compose run [-d] [--rm] [--name NAME] [-u USER] [-w DIR] [-e KEY=VALUE] [--service-ports] [--no-deps] [--wait-for TARGET] SERVICE [COMMAND...]
+//
+// サービスの設定で一回限りのコンテナを作って実行し、その終了コードで終了する。
+pub fn execute(args: &RunArgs) -> Result<(), Box<dyn Error>> {
+    let options = RunOptions {
+        detach: args.detach,
+        rm: args.rm,
+        name: args.name.clone(),
+        user: args.user.clone(),
+        workdir: args.workdir.clone(),
+        env: args.env.clone(),
+        service_ports: args.service_ports,
+        no_deps: args.no_deps,
+        wait_for: args.wait_for.clone(),
+    };
+    let exit_code = block_on(rocker_compose::run_command(
+        &files(&args.files),
+        None,
+        &args.service,
+        args.command.clone(),
+        &options,
+    ))?;
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::ScaleArgs;
+use crate::utils::block_on;
+
+// compose scale SERVICE=NUM...
+pub fn execute(args: &ScaleArgs) -> Result<(), Box<dyn Error>> {
+    let scale: HashMap<String, usize> = args.scale.iter().cloned().collect();
+    block_on(rocker_compose::scale_command(&files(&args.file.files), None, &scale))
+}
@@ -0,0 +1,10 @@
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::ServicesArgs;
+use crate::utils::block_on;
+
+// compose top [SERVICE...]（サービスのコンテナのプロセス）
+pub fn execute(args: &ServicesArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::top_command(&files(&args.file.files), None, &args.services))
+}
@@ -0,0 +1,22 @@
+use rocker_compose::UpOptions;
+use std::error::Error;
+
+use super::files;
+use crate::args::compose::UpArgs;
+use crate::utils::block_on;
+
+// compose up [-d] [--build] [--force-recreate|--no-recreate] [--no-deps] [--scale SERVICE=NUM] [--wait [--wait-timeout T]] [--watch] [SERVICE...]
+pub fn execute(args: &UpArgs) -> Result<(), Box<dyn Error>> {
+    let options = UpOptions {
+        detach: args.detach,
+        build: args.build,
+        force_recreate: args.force_recreate,
+        no_recreate: args.no_recreate,
+        no_deps: args.no_deps,
+        scale: args.scale.iter().cloned().collect(),
+        watch: args.watch,
+        wait: args.wait,
+        wait_timeout: args.wait_timeout.map(|timeout| timeout.as_secs()),
+    };
+    block_on(rocker_compose::up_command(&files(&args.file.files), None, &args.services, &options))
+}
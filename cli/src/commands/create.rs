@@ -0,0 +1,26 @@
+use rocker_client::{Client, Progress, PullPolicy};
+use std::error::Error;
+
+use super::run::container_config;
+use crate::args::CreateArgs;
+use crate::utils::block_on;
+
+// create [OPTIONS] IMAGE [COMMAND...]
+//
+// run と同じ設定でコンテナを作成し、起動せずに ID を表示する（起動は start で行う）。
+pub fn execute(args: &CreateArgs) -> Result<(), Box<dyn Error>> {
+    let config = container_config(args)?;
+    let client = Client::new();
+    block_on(async {
+        let policy: PullPolicy = args.pull.parse()?;
+        if let Some(pull) = client.ensure_image(&config.image, policy).await? {
+            Progress::new().report(pull).await?;
+        }
+        let container = match (&args.name, args.replace) {
+            (Some(name), true) => client.replace_container(name, &config).await?,
+            (name, _) => client.create_container(name.as_deref(), &config).await?,
+        };
+        println!("{}", container.id);
+        Ok(())
+    })
+}
@@ -0,0 +1,59 @@
+use rocker_client::Client;
+use rocker_core::{resolve_env, ExecConfig, LogStream};
+use std::error::Error;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::args::ExecArgs;
+use crate::utils::block_on;
+
+// 終了コードが記録されたかを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// exec [-d] [-i] [-u USER] [-e KEY=VALUE] [--env-file FILE] [-w DIR] CONTAINER COMMAND...
+//
+// -d では起動だけして戻る。それ以外は出力を表示し、コマンドの終了コードで終了する。
+pub fn execute(args: &ExecArgs) -> Result<(), Box<dyn Error>> {
+    let config = ExecConfig {
+        cmd: args.command.clone(),
+        user: args.user.clone(),
+        env: resolve_env(&args.env_files, &args.env)?,
+        working_dir: args.workdir.clone(),
+        attach_stdin: args.interactive,
+    };
+    let client = Client::new();
+
+    let exit_code = block_on(async {
+        let exec_id = client.create_exec(&args.container, &config).await?;
+        if args.detach {
+            client.start_exec(&exec_id).await?;
+            return Ok(0);
+        }
+
+        let mut output = if args.interactive {
+            client.attach_exec_with_stdin(&exec_id, tokio::io::stdin()).await?
+        } else {
+            client.attach_exec(&exec_id).await?
+        };
+        while let Some(record) = output.next().await? {
+            match record.stream {
+                LogStream::Stdout => writeln!(std::io::stdout(), "{}", record.line)?,
+                LogStream::Stderr => writeln!(std::io::stderr(), "{}", record.line)?,
+            }
+        }
+
+        // 出力が閉じてから終了コードが記録されるまで待つ
+        loop {
+            let exec = client.inspect_exec(&exec_id).await?;
+            if !exec.running {
+                return Ok(exec.exit_code.unwrap_or(0));
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })?;
+
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use rocker_client::Client;
+use std::error::Error;
+use std::io::IsTerminal;
+
+use crate::args::ExportArgs;
+use crate::utils::block_on;
+
+// export [-o FILE] CONTAINER（-o が無ければ tar を標準出力に書く。端末には書かない）
+pub fn execute(args: &ExportArgs) -> Result<(), Box<dyn Error>> {
+    if args.output.is_none() && std::io::stdout().is_terminal() {
+        return Err("Refusing to write the archive to a terminal, use -o or redirect the output".into());
+    }
+    let client = Client::new();
+    block_on(async {
+        match &args.output {
+            Some(path) => {
+                let mut file = tokio::fs::File::create(path)
+                    .await
+                    .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+                client.export_container(&args.container, &mut file).await?;
+            }
+            None => {
+                client.export_container(&args.container, &mut tokio::io::stdout()).await?;
+            }
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,52 @@
+use rocker_client::Client;
+use rocker_core::{format_size, FileChange};
+use std::error::Error;
+
+use crate::args::image::DiffArgs;
+use crate::utils::{block_on, short_id};
+
+// image diff [--total] FROM TO / image diff --container CONTAINER [--total] [FROM]
+//
+// 共有していないレイヤごとに、追加（A）・削除（D）・変更（C）したファイルと大きさの増減を表示する。
+// --container では FROM（省略時はコンテナのイメージ）とコンテナを比べ、書き込み可能なレイヤを最後のレイヤにする。
+pub fn execute(args: &DiffArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let diff = block_on(async {
+        match (&args.container, &args.from, &args.to) {
+            (Some(container), from, _) => client.container_diff(container, from.as_deref()).await,
+            (None, Some(from), Some(to)) => client.image_diff(to, from).await,
+            _ => Err("Specify the two images to compare, or --container".into()),
+        }
+    })?;
+
+    println!("{} -> {} ({} shared layers)", diff.from, diff.to, diff.shared_layers);
+    if args.total {
+        print_changes(&diff.changes);
+    } else {
+        for layer in &diff.layers {
+            println!();
+            println!(
+                "{}  {}  {}",
+                layer.diff_id.as_deref().map_or("<writable>".to_string(), short_id),
+                signed_size(layer.size_delta),
+                layer.created_by.as_deref().unwrap_or("")
+            );
+            print_changes(&layer.changes);
+        }
+    }
+    println!();
+    println!("Total: {} ({} files)", signed_size(diff.size_delta), diff.changes.len());
+    Ok(())
+}
+
+fn print_changes(changes: &[FileChange]) {
+    for change in changes {
+        println!("{} {} ({})", change.kind.marker(), change.path, format_size(change.size));
+    }
+}
+
+// 大きさの増減（+1.50 MB・-300 B）
+fn signed_size(delta: i64) -> String {
+    let sign = if delta < 0 { '-' } else { '+' };
+    format!("{}{}", sign, format_size(delta.unsigned_abs()))
+}
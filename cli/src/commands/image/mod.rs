@@ -0,0 +1,4 @@
+pub mod diff;
+pub mod sbom;
+pub mod scan;
+pub mod squash;
@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::image::SbomArgs;
+use crate::utils::block_on;
+
+// image sbom [--format spdx-json|cyclonedx-json] [-o FILE] IMAGE
+pub fn execute(args: &SbomArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let sbom = block_on(client.image_sbom(&args.image, &args.format))?;
+    let document = serde_json::to_string_pretty(&sbom)?;
+    match &args.output {
+        Some(path) => std::fs::write(path, document).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?,
+        None => println!("{}", document),
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::image::ScanArgs;
+use crate::utils::{block_on, print_table};
+
+// 件数を表示する深刻度（重い順）
+const SEVERITIES: [&str; 5] = ["CRITICAL", "HIGH", "MEDIUM", "LOW", "UNKNOWN"];
+
+// image scan [--severity LIST] IMAGE
+//
+// デーモンに設定したスキャナで調べ、脆弱性の表と深刻度ごとの件数を表示する（結果はイメージにも保存される）。
+pub fn execute(args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let scan = block_on(client.scan_image(&args.image))?;
+
+    let rows: Vec<[String; 5]> = scan
+        .vulnerabilities
+        .iter()
+        .filter(|vulnerability| {
+            args.severity.is_empty()
+                || args
+                    .severity
+                    .iter()
+                    .any(|severity| severity.eq_ignore_ascii_case(&vulnerability.severity))
+        })
+        .map(|vulnerability| {
+            [
+                vulnerability.id.clone(),
+                vulnerability.severity.clone(),
+                vulnerability.package.clone(),
+                vulnerability.installed_version.clone(),
+                vulnerability.fixed_version.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(&["ID", "SEVERITY", "PACKAGE", "INSTALLED", "FIXED"], &rows);
+
+    let counts: Vec<String> = SEVERITIES
+        .iter()
+        .map(|severity| format!("{}: {}", severity, scan.count(severity)))
+        .collect();
+    println!();
+    println!(
+        "{} vulnerabilities found by {} ({})",
+        scan.vulnerabilities.len(),
+        scan.scanner,
+        counts.join(", ")
+    );
+    Ok(())
+}
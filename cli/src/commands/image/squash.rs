@@ -0,0 +1,18 @@
+use rocker_client::{Client, Progress};
+use std::error::Error;
+
+use crate::args::image::SquashArgs;
+use crate::utils::block_on;
+
+// image squash [-t NAME[:TAG]] IMAGE（1 つのレイヤにしたイメージの ID を表示する）
+pub fn execute(args: &SquashArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let image_id = block_on(async {
+        let squash = client.squash_image(&args.image, args.tag.as_deref()).await?;
+        Progress::new().report(squash).await
+    })?;
+    if let Some(image_id) = image_id {
+        println!("{}", image_id);
+    }
+    Ok(())
+}
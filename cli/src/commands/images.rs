@@ -0,0 +1,95 @@
+use rocker_client::Client;
+use rocker_core::{format_size, Image};
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::args::ImagesArgs;
+use crate::utils::{block_on, parse_filters, print_table, short_id, time_ago};
+
+// images [-a] [-f KEY=VALUE] [--digests] [--no-trunc] [--tree]
+//
+// -a が無ければ中間イメージ（名前が無く、別のイメージの親になっているもの）を表示しない。
+// --tree では親のイメージから子のイメージへの木にして表示する。
+pub fn execute(args: &ImagesArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let client = Client::new();
+    let mut images = block_on(client.list_images(&filters))?;
+    if !args.all {
+        let parents: HashSet<String> = images.iter().filter_map(|image| image.parent_id.clone()).collect();
+        images.retain(|image| image.repo.is_some() || !parents.contains(&image.id));
+    }
+
+    let id = |image: &Image| if args.no_trunc { image.id.clone() } else { short_id(&image.id) };
+    if args.tree {
+        let ids: HashSet<&str> = images.iter().map(|image| image.id.as_str()).collect();
+        let roots: Vec<&Image> = images
+            .iter()
+            .filter(|image| image.parent_id.as_deref().is_none_or(|parent| !ids.contains(parent)))
+            .collect();
+        for root in roots {
+            print_tree(&images, root, "", None, &id);
+        }
+        return Ok(());
+    }
+
+    let mut titles = vec!["REPOSITORY", "TAG"];
+    if args.digests {
+        titles.push("DIGEST");
+    }
+    titles.extend(["IMAGE ID", "CREATED", "SIZE"]);
+    let rows: Vec<Vec<String>> = images
+        .iter()
+        .map(|image| {
+            let mut row = vec![
+                image.repo.clone().unwrap_or_else(|| "<none>".to_string()),
+                image.tag.clone().unwrap_or_else(|| "<none>".to_string()),
+            ];
+            if args.digests {
+                row.push(digest(image));
+            }
+            row.extend([id(image), time_ago(image.created_at), format_size(image.size)]);
+            row
+        })
+        .collect();
+    print_table(&titles, &rows);
+    Ok(())
+}
+
+// レジストリのダイジェスト（repo@sha256:... の sha256:... の部分）
+fn digest(image: &Image) -> String {
+    image
+        .repo_digests
+        .first()
+        .map(|digest| digest.rsplit_once('@').map_or(digest.as_str(), |(_, digest)| digest).to_string())
+        .unwrap_or_else(|| "<none>".to_string())
+}
+
+// イメージとその子のイメージを枝の線を付けて表示する（last は兄弟の最後かどうか、根は None）
+fn print_tree(images: &[Image], image: &Image, indent: &str, last: Option<bool>, id: &dyn Fn(&Image) -> String) {
+    let branch = match last {
+        None => "",
+        Some(true) => "└─ ",
+        Some(false) => "├─ ",
+    };
+    println!(
+        "{}{}{} {} ({})",
+        indent,
+        branch,
+        id(image),
+        image.full_name().unwrap_or_else(|| "<none>".to_string()),
+        format_size(image.size)
+    );
+
+    let children: Vec<&Image> = images
+        .iter()
+        .filter(|child| child.parent_id.as_deref() == Some(image.id.as_str()))
+        .collect();
+    let indent = match last {
+        None => indent.to_string(),
+        Some(true) => format!("{}   ", indent),
+        Some(false) => format!("{}│  ", indent),
+    };
+    for (index, child) in children.iter().enumerate() {
+        print_tree(images, child, &indent, Some(index + 1 == children.len()), id);
+    }
+}
@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::ImportArgs;
+use crate::utils::{block_on, read_input};
+
+// import [-c INSTRUCTION] [-m MESSAGE] FILE|- [REPOSITORY[:TAG]]（作成したイメージの ID を表示する）
+pub fn execute(args: &ImportArgs) -> Result<(), Box<dyn Error>> {
+    let archive = read_input(&args.file)?;
+    let client = Client::new();
+    let image = block_on(client.import_image(
+        archive,
+        args.reference.as_deref(),
+        &args.changes,
+        args.message.as_deref(),
+    ))?;
+    println!("{}", image.id);
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::InspectArgs;
+use crate::utils::block_on;
+
+// inspect [-s] CONTAINER...（すべて取得できてから JSON の配列で表示する）
+//
+// health にはヘルスチェックの状態と最近の結果が、oom_killed には OOM killer にプロセスを止められたかが入る。
+// -s では ps -s と同じ大きさを size_rw と size_root_fs に入れる。
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let containers = block_on(async {
+        let mut containers = Vec::new();
+        for container in &args.containers {
+            let inspected = if args.size {
+                client.inspect_container_with_size(container).await?
+            } else {
+                client.inspect_container(container).await?
+            };
+            containers.push(inspected);
+        }
+        Ok(containers)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&containers)?);
+    Ok(())
+}
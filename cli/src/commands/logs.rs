@@ -0,0 +1,40 @@
+use rocker_client::{Client, ContainerLogsOptions};
+use rocker_core::LogStream;
+use std::error::Error;
+use std::io::Write;
+
+use crate::args::LogsArgs;
+use crate::utils::block_on;
+
+// logs [-f] [-n N|all] [--since TIME] [--until TIME] [-t] CONTAINER
+//
+// 標準出力と標準エラー出力に分けて表示する（-t では行の前に書かれた時刻を付ける）。
+pub fn execute(args: &LogsArgs) -> Result<(), Box<dyn Error>> {
+    let tail = match args.tail.as_deref() {
+        None | Some("all") => None,
+        Some(tail) => Some(tail.parse().map_err(|_| format!("Invalid --tail (expected a number or \"all\"): {}", tail))?),
+    };
+    let options = ContainerLogsOptions {
+        follow: args.follow,
+        tail,
+        since: args.since.clone(),
+        until: args.until.clone(),
+    };
+    let client = Client::new();
+
+    block_on(async {
+        let mut records = client.container_logs(&args.container, &options).await?;
+        while let Some(record) = records.next().await? {
+            let line = if args.timestamps {
+                format!("{} {}", record.time.to_rfc3339(), record.line)
+            } else {
+                record.line
+            };
+            match record.stream {
+                LogStream::Stdout => writeln!(std::io::stdout(), "{}", line)?,
+                LogStream::Stderr => writeln!(std::io::stderr(), "{}", line)?,
+            }
+        }
+        Ok(())
+    })
+}
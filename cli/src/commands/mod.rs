@@ -0,0 +1,26 @@
+pub mod build;
+pub mod builder;
+pub mod compose;
+pub mod create;
+pub mod exec;
+pub mod export;
+pub mod image;
+pub mod images;
+pub mod import;
+pub mod inspect;
+pub mod logs;
+pub mod network;
+pub mod node;
+pub mod ps;
+pub mod rm;
+pub mod run;
+pub mod schedule;
+pub mod secret;
+pub mod service;
+pub mod stack;
+pub mod start;
+pub mod stats;
+pub mod stop;
+pub mod swarm;
+pub mod system;
+pub mod volume;
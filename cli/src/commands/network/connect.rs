@@ -0,0 +1,12 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::network::ConnectArgs;
+use crate::utils::block_on;
+
+// network connect [--alias ALIAS] [--ip IP] NETWORK CONTAINER
+pub fn execute(args: &ConnectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(client.connect_network(&args.network, &args.container, &args.aliases, args.ip))?;
+    Ok(())
+}
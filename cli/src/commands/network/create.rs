@@ -0,0 +1,25 @@
+use rocker_client::{Client, NetworkCreateOptions};
+use std::error::Error;
+
+use crate::args::network::CreateArgs;
+use crate::utils::{block_on, parse_key_values};
+
+// network create [-d DRIVER] [--subnet CIDR] [--gateway IP] [--ip-range CIDR] [--internal] [--label KEY=VALUE] [-o KEY=VALUE] NAME
+//
+// 作成したネットワークの ID を表示する。サブネットを省略するとデーモンが空いているものを割り当てる。
+pub fn execute(args: &CreateArgs) -> Result<(), Box<dyn Error>> {
+    let options = NetworkCreateOptions {
+        name: args.name.clone(),
+        driver: Some(args.driver.clone()),
+        subnet: args.subnet.clone(),
+        gateway: args.gateway.map(|gateway| gateway.to_string()),
+        ip_range: args.ip_range.clone(),
+        internal: args.internal,
+        labels: parse_key_values(&args.labels)?,
+        options: parse_key_values(&args.opts)?,
+    };
+    let client = Client::new();
+    let network = block_on(client.create_network(&options))?;
+    println!("{}", network.id);
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::network::DisconnectArgs;
+use crate::utils::block_on;
+
+// network disconnect NETWORK CONTAINER
+pub fn execute(args: &DisconnectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(client.disconnect_network(&args.network, &args.container))
+}
@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::network::InspectArgs;
+use crate::utils::block_on;
+
+// network inspect NETWORK...（すべて取得できてから JSON の配列で表示する）
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let networks = block_on(async {
+        let mut networks = Vec::new();
+        for network in &args.networks {
+            networks.push(client.inspect_network(network).await?);
+        }
+        Ok(networks)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&networks)?);
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::network::LsArgs;
+use crate::utils::{block_on, parse_filters, print_table, short_id};
+
+// network ls [-f KEY=VALUE] [-q]
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let client = Client::new();
+    let networks = block_on(client.list_networks(&filters))?;
+    if args.quiet {
+        for network in &networks {
+            println!("{}", network.id);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 4]> = networks
+        .iter()
+        .map(|network| {
+            [
+                short_id(&network.id),
+                network.name.clone(),
+                network.driver.to_string(),
+                network.config.subnet.clone(),
+            ]
+        })
+        .collect();
+    print_table(&["NETWORK ID", "NAME", "DRIVER", "SUBNET"], &rows);
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::network::PruneArgs;
+use crate::utils::{block_on, confirm, parse_filters};
+
+// network prune [--filter KEY=VALUE] [-f]（コンテナが接続していないネットワークを削除する）
+pub fn execute(args: &PruneArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    if !args.force && !confirm("This will remove all custom networks not used by at least one container.")? {
+        return Ok(());
+    }
+
+    let client = Client::new();
+    let deleted = block_on(client.prune_networks(&filters))?;
+    if !deleted.is_empty() {
+        println!("Deleted Networks:");
+        for name in &deleted {
+            println!("{}", name);
+        }
+    }
+    Ok(())
+}
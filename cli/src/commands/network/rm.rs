@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::network::RmArgs;
+use crate::utils::block_on;
+
+// network rm NETWORK...
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for network in &args.networks {
+            client.remove_network(network).await?;
+            println!("{}", network);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,22 @@
+use rocker_client::Client;
+use rocker_core::{NodeAvailability, NodeUpdate};
+use std::error::Error;
+
+use crate::args::node::DrainArgs;
+use crate::utils::block_on;
+
+// node drain NODE...（タスクをほかのノードに移し、新しいタスクを割り当てない）
+pub fn execute(args: &DrainArgs) -> Result<(), Box<dyn Error>> {
+    let update = NodeUpdate {
+        availability: Some(NodeAvailability::Drain),
+        ..NodeUpdate::default()
+    };
+    let client = Client::new();
+    block_on(async {
+        for node in &args.nodes {
+            client.update_node(node, &update).await?;
+            println!("{}", node);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::node::InspectArgs;
+use crate::utils::block_on;
+
+// node inspect NODE...（すべて取得できてから JSON の配列で表示する）
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let nodes = block_on(async {
+        let mut nodes = Vec::new();
+        for node in &args.nodes {
+            nodes.push(client.inspect_node(node).await?);
+        }
+        Ok(nodes)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&nodes)?);
+    Ok(())
+}
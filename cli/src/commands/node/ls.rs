@@ -0,0 +1,33 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::node::LsArgs;
+use crate::utils::{block_on, print_table, short_id};
+
+// node ls [-q]
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let nodes = block_on(client.list_nodes())?;
+    if args.quiet {
+        for node in &nodes {
+            println!("{}", node.id);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 6]> = nodes
+        .iter()
+        .map(|node| {
+            [
+                short_id(&node.id),
+                node.hostname.clone(),
+                node.role.to_string(),
+                node.status.to_string(),
+                node.availability.to_string(),
+                node.address.clone(),
+            ]
+        })
+        .collect();
+    print_table(&["ID", "HOSTNAME", "ROLE", "STATUS", "AVAILABILITY", "ADDRESS"], &rows);
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod drain;
+pub mod inspect;
+pub mod ls;
+pub mod rm;
+pub mod update;
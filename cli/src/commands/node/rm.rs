@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::node::RmArgs;
+use crate::utils::block_on;
+
+// node rm NODE...（ノードのタスクはほかのノードに割り当て直される）
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for node in &args.nodes {
+            client.remove_node(node).await?;
+            println!("{}", node);
+        }
+        Ok(())
+    })
+}
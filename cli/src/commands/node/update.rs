@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use rocker_core::NodeUpdate;
+use std::error::Error;
+
+use crate::args::node::UpdateArgs;
+use crate::utils::{block_on, parse_key_values};
+
+// node update [--availability active|pause|drain] [--label-add KEY=VALUE] [--label-rm KEY] NODE
+pub fn execute(args: &UpdateArgs) -> Result<(), Box<dyn Error>> {
+    let update = NodeUpdate {
+        availability: args.availability.as_deref().map(str::parse).transpose()?,
+        labels_add: parse_key_values(&args.labels_add)?,
+        labels_rm: args.labels_rm.clone(),
+    };
+    let client = Client::new();
+    block_on(client.update_node(&args.node, &update))?;
+    println!("{}", args.node);
+    Ok(())
+}
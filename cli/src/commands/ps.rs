@@ -0,0 +1,91 @@
+use rocker_client::Client;
+use rocker_core::{format_size, Container};
+use std::error::Error;
+
+use crate::args::PsArgs;
+use crate::utils::{block_on, parse_filters, print_table, short_id, time_ago};
+
+// ps [-a] [-f KEY=VALUE] [-s]
+//
+// -s では書き込み可能なレイヤの大きさと、イメージを含めた仮想的な大きさを SIZE の列に付ける。
+pub fn execute(args: &PsArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let client = Client::new();
+    let containers = block_on(async {
+        if args.size {
+            client.list_containers_with_size(args.all, &filters).await
+        } else {
+            client.list_containers(args.all, &filters).await
+        }
+    })?;
+
+    let mut titles = vec!["CONTAINER ID", "IMAGE", "COMMAND", "CREATED", "STATUS", "PORTS", "NAMES"];
+    if args.size {
+        titles.push("SIZE");
+    }
+    let rows: Vec<Vec<String>> = containers
+        .iter()
+        .map(|container| {
+            let mut row = vec![
+                short_id(&container.id),
+                container.image_name.clone().unwrap_or_else(|| container.config.image.clone()),
+                command(container),
+                time_ago(container.created_at),
+                container.status(),
+                ports(container),
+                container.name.clone(),
+            ];
+            if args.size {
+                row.push(format!(
+                    "{} (virtual {})",
+                    format_size(container.size_rw.unwrap_or(0)),
+                    format_size(container.size_root_fs.unwrap_or(0))
+                ));
+            }
+            row
+        })
+        .collect();
+    print_table(&titles, &rows);
+    Ok(())
+}
+
+// エントリポイントとコマンドを続けて引用符で囲む（長いものは 20 文字で切る）
+fn command(container: &Container) -> String {
+    let config = &container.config;
+    let words: Vec<&str> = config
+        .entrypoint
+        .iter()
+        .flatten()
+        .chain(config.cmd.iter().flatten())
+        .map(String::as_str)
+        .collect();
+    let command = words.join(" ");
+    if command.chars().count() > 20 {
+        format!("\"{}…\"", command.chars().take(19).collect::<String>())
+    } else {
+        format!("\"{}\"", command)
+    }
+}
+
+// 公開中のポート（動作していなければ設定されたポート）を "0.0.0.0:8080->80/tcp" の形で並べる
+fn ports(container: &Container) -> String {
+    let ports = if container.ports.is_empty() {
+        &container.config.port_bindings
+    } else {
+        &container.ports
+    };
+    ports
+        .iter()
+        .map(|binding| match binding.host_port {
+            Some(host_port) => format!(
+                "{}:{}->{}/{}",
+                binding.host_ip.map_or("0.0.0.0".to_string(), |ip| ip.to_string()),
+                host_port,
+                binding.container_port,
+                binding.protocol
+            ),
+            None => format!("{}/{}", binding.container_port, binding.protocol),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
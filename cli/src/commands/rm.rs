@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::RmArgs;
+use crate::utils::block_on;
+
+// rm [-f] [-v] CONTAINER...
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for container in &args.containers {
+            client.remove_container(container, args.force, args.volumes).await?;
+            println!("{}", container);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,185 @@
+use rocker_client::{parse_detach_keys, Attached, Client, Progress, PullPolicy};
+use rocker_core::{
+    resolve_env, ContainerConfig, DeviceCgroupRule, DeviceMapping, GpuRequest, HealthConfig, Hook, HostEntry, LogConfig,
+    Mount, NetworkMode, PortBinding, ResourceLimits, RestartPolicy, ScheduleCreateRequest, SecretReference,
+    TrafficShaping, Ulimit, VolumesFrom, DEFAULT_LOG_DRIVER,
+};
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::args::{CreateArgs, RunArgs};
+use crate::utils::{block_on, parse_key_values};
+
+// run [OPTIONS] IMAGE [COMMAND...]
+//
+// -d ではコンテナを起動して ID を表示する。-d が無ければ終了するまで出力を表示し、コンテナの終了コードで終了する
+// （--detach-keys のキーで切り離した場合はコンテナを動かしたまま戻る）。--schedule では今は実行せず、
+// cron 式で実行するスケジュールを作って ID を表示する。
+pub fn execute(args: &RunArgs) -> Result<(), Box<dyn Error>> {
+    let config = container_config(&args.container)?;
+    let client = Client::new();
+
+    if let Some(cron) = &args.schedule {
+        let request = ScheduleCreateRequest {
+            name: args.container.name.clone().unwrap_or_default(),
+            cron: cron.clone(),
+            config,
+            overlap: args.schedule_overlap.as_deref().map(str::parse).transpose()?.unwrap_or_default(),
+            history_limit: args.schedule_history,
+        };
+        let schedule = block_on(client.create_schedule(&request))?;
+        println!("{}", schedule.id);
+        return Ok(());
+    }
+
+    let detach_keys = if args.detach_keys.is_empty() {
+        None
+    } else {
+        Some(parse_detach_keys(&args.detach_keys)?)
+    };
+    let exit_code = block_on(async {
+        let policy: PullPolicy = args.container.pull.parse()?;
+        if let Some(pull) = client.ensure_image(&config.image, policy).await? {
+            Progress::new().report(pull).await?;
+        }
+
+        let container = match (&args.container.name, args.container.replace) {
+            (Some(name), true) => client.replace_container(name, &config).await?,
+            (name, _) => client.create_container(name.as_deref(), &config).await?,
+        };
+        if args.detach {
+            client.start_container(&container.id).await?;
+            println!("{}", container.id);
+            return Ok(0);
+        }
+        match client.run_attached(&container.id, detach_keys.as_deref()).await? {
+            Attached::Exited(code) => Ok(code),
+            Attached::Detached => Ok(0),
+        }
+    })?;
+
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
+    Ok(())
+}
+
+// 引数をコンテナの設定にする（値の誤りはデーモンに送る前にエラーにする）
+pub fn container_config(args: &CreateArgs) -> Result<ContainerConfig, Box<dyn Error>> {
+    let mut traffic_shaping = TrafficShaping::default();
+    for option in &args.network_opts {
+        traffic_shaping.apply_option(option)?;
+    }
+
+    let sysctls = args
+        .sysctls
+        .iter()
+        .map(|sysctl| {
+            sysctl
+                .split_once('=')
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .ok_or_else(|| format!("Invalid sysctl (expected key=value): {}", sysctl))
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    let mut port_bindings = Vec::new();
+    for port in &args.ports {
+        port_bindings.extend(PortBinding::parse(port)?);
+    }
+
+    // --entrypoint "" はイメージの ENTRYPOINT を使わない
+    let entrypoint = args
+        .entrypoint
+        .as_ref()
+        .map(|entrypoint| if entrypoint.is_empty() { Vec::new() } else { vec![entrypoint.clone()] });
+
+    let log_config = if args.log_driver.is_some() || !args.log_opts.is_empty() {
+        Some(LogConfig {
+            driver: args.log_driver.clone().unwrap_or_else(|| DEFAULT_LOG_DRIVER.to_string()),
+            options: parse_key_values(&args.log_opts)?,
+        })
+    } else {
+        None
+    };
+
+    Ok(ContainerConfig {
+        image: args.image.clone(),
+        cmd: (!args.command.is_empty()).then(|| args.command.clone()),
+        entrypoint,
+        working_dir: args.workdir.clone(),
+        env: resolve_env(&args.env_files, &args.env)?,
+        port_bindings,
+        publish_all: args.publish_all,
+        mounts: args.volumes.iter().map(|volume| Mount::parse(volume)).collect::<Result<_, _>>()?,
+        volumes_from: args.volumes_from.iter().map(|spec| VolumesFrom::parse(spec)).collect::<Result<_, _>>()?,
+        secrets: args.secrets.iter().map(|spec| SecretReference::parse(spec)).collect::<Result<_, _>>()?,
+        restart_policy: args.restart.clone().unwrap_or(RestartPolicy::No),
+        resource_limits: ResourceLimits {
+            cpus: args.cpus,
+            memory_bytes: args.memory,
+            pids_limit: args.pids_limit,
+            oom_kill_disable: args.oom_kill_disable,
+            ..ResourceLimits::default()
+        },
+        network_mode: args.network.as_deref().map(NetworkMode::parse).transpose()?.unwrap_or(NetworkMode::Bridge),
+        user: args.user.clone(),
+        hostname: args.hostname.clone(),
+        labels: parse_key_values(&args.labels)?,
+        stop_signal: args.stop_signal.clone(),
+        stop_timeout: args.stop_timeout.map(|timeout| timeout.as_secs()),
+        devices: args.devices.iter().map(|spec| DeviceMapping::parse(spec)).collect::<Result<_, _>>()?,
+        device_cgroup_rules: args
+            .device_cgroup_rules
+            .iter()
+            .map(|rule| DeviceCgroupRule::parse(rule))
+            .collect::<Result<_, _>>()?,
+        gpus: args.gpus.as_deref().map(GpuRequest::parse).transpose()?,
+        sysctls,
+        ulimits: args.ulimits.iter().map(|spec| Ulimit::parse(spec)).collect::<Result<_, _>>()?,
+        extra_hosts: args.add_hosts.iter().map(|spec| HostEntry::parse(spec)).collect::<Result<_, _>>()?,
+        ip_address: args.ip,
+        dns: args.dns.clone(),
+        dns_search: args.dns_search.clone(),
+        traffic_shaping,
+        oom_score_adj: args.oom_score_adj,
+        hooks: args.hooks.iter().map(|spec| Hook::parse(spec)).collect::<Result<_, _>>()?,
+        auto_remove: args.rm,
+        healthcheck: healthcheck(args),
+        health_restart: args.health_restart,
+        wait_for: args.wait_for.clone(),
+        wait_timeout: args.wait_timeout.map(|timeout| timeout.as_secs()),
+        log_config,
+        ..ContainerConfig::default()
+    })
+}
+
+// --health-* の指定（コマンドを省略した場合はイメージの HEALTHCHECK のコマンドに間隔などだけを変える）
+fn healthcheck(args: &CreateArgs) -> Option<HealthConfig> {
+    if args.no_healthcheck {
+        return Some(HealthConfig {
+            test: vec!["NONE".to_string()],
+            interval: None,
+            timeout: None,
+            retries: None,
+            start_period: None,
+        });
+    }
+    let given = args.health_cmd.is_some()
+        || args.health_interval.is_some()
+        || args.health_timeout.is_some()
+        || args.health_retries.is_some()
+        || args.health_start_period.is_some();
+    if !given {
+        return None;
+    }
+    Some(HealthConfig {
+        test: args
+            .health_cmd
+            .as_ref()
+            .map_or_else(Vec::new, |cmd| vec!["CMD-SHELL".to_string(), cmd.clone()]),
+        interval: args.health_interval.map(|interval| interval.as_secs()),
+        timeout: args.health_timeout.map(|timeout| timeout.as_secs()),
+        retries: args.health_retries,
+        start_period: args.health_start_period.map(|period| period.as_secs()),
+    })
+}
@@ -0,0 +1,37 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::schedule::HistoryArgs;
+use crate::utils::{block_on, print_table};
+
+// 一覧でエラーを切り詰める長さ
+const ERROR_WIDTH: usize = 40;
+
+// schedule history [--no-trunc] SCHEDULE（新しい実行から順に）
+pub fn execute(args: &HistoryArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let schedule = block_on(client.inspect_schedule(&args.schedule))?;
+
+    let rows: Vec<[String; 5]> = schedule
+        .history
+        .iter()
+        .rev()
+        .map(|run| {
+            let error = run.error.clone().unwrap_or_default();
+            let error = if args.no_trunc || error.chars().count() <= ERROR_WIDTH {
+                error
+            } else {
+                format!("{}…", error.chars().take(ERROR_WIDTH - 1).collect::<String>())
+            };
+            [
+                run.scheduled_at.to_rfc3339(),
+                run.state.to_string(),
+                run.container_name.clone().unwrap_or_default(),
+                run.exit_code.map_or_else(String::new, |code| code.to_string()),
+                error,
+            ]
+        })
+        .collect();
+    print_table(&["SCHEDULED", "STATE", "CONTAINER", "EXIT CODE", "ERROR"], &rows);
+    Ok(())
+}
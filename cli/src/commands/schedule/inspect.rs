@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::schedule::InspectArgs;
+use crate::utils::block_on;
+
+// schedule inspect SCHEDULE...（すべて取得できてから JSON の配列で表示する）
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let schedules = block_on(async {
+        let mut schedules = Vec::new();
+        for schedule in &args.schedules {
+            schedules.push(client.inspect_schedule(schedule).await?);
+        }
+        Ok(schedules)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&schedules)?);
+    Ok(())
+}
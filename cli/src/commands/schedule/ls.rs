@@ -0,0 +1,35 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::schedule::LsArgs;
+use crate::utils::{block_on, parse_filters, print_table, short_id};
+
+// schedule ls [-f KEY=VALUE] [-q]
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let client = Client::new();
+    let schedules = block_on(client.list_schedules(&filters))?;
+    if args.quiet {
+        for schedule in &schedules {
+            println!("{}", schedule.id);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 6]> = schedules
+        .iter()
+        .map(|schedule| {
+            let last = schedule.history.last();
+            [
+                short_id(&schedule.id),
+                schedule.name.clone(),
+                schedule.cron.clone(),
+                schedule.config.image.clone(),
+                schedule.next_run.map_or_else(String::new, |time| time.to_rfc3339()),
+                last.map_or_else(String::new, |run| run.state.to_string()),
+            ]
+        })
+        .collect();
+    print_table(&["ID", "NAME", "SCHEDULE", "IMAGE", "NEXT RUN", "LAST RUN"], &rows);
+    Ok(())
+}
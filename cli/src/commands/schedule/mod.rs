@@ -0,0 +1,4 @@
+pub mod history;
+pub mod inspect;
+pub mod ls;
+pub mod rm;
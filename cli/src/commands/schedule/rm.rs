@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::schedule::RmArgs;
+use crate::utils::block_on;
+
+// schedule rm [-f] SCHEDULE...（-f では実行中のコンテナも止めて削除する）
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for schedule in &args.schedules {
+            client.remove_schedule(schedule, args.force).await?;
+            println!("{}", schedule);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,15 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::secret::CreateArgs;
+use crate::utils::{block_on, parse_key_values, read_input};
+
+// secret create [-l KEY=VALUE] NAME FILE|-（作成したシークレットの ID を表示する）
+pub fn execute(args: &CreateArgs) -> Result<(), Box<dyn Error>> {
+    let data = read_input(&args.file)?;
+    let labels = parse_key_values(&args.labels)?;
+    let client = Client::new();
+    let secret = block_on(client.create_secret(&args.name, &data, labels))?;
+    println!("{}", secret.id);
+    Ok(())
+}
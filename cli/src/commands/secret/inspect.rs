@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::secret::InspectArgs;
+use crate::utils::block_on;
+
+// secret inspect SECRET...（デーモンは内容を返さない）
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let secrets = block_on(async {
+        let mut secrets = Vec::new();
+        for secret in &args.secrets {
+            secrets.push(client.inspect_secret(secret).await?);
+        }
+        Ok(secrets)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&secrets)?);
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use rocker_client::Client;
+use rocker_core::format_size;
+use std::error::Error;
+
+use crate::args::secret::LsArgs;
+use crate::utils::{block_on, parse_filters, print_table, short_id, time_ago};
+
+// secret ls [-f KEY=VALUE] [-q]（内容は表示しない）
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let client = Client::new();
+    let secrets = block_on(client.list_secrets(&filters))?;
+    if args.quiet {
+        for secret in &secrets {
+            println!("{}", secret.id);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 4]> = secrets
+        .iter()
+        .map(|secret| {
+            [
+                short_id(&secret.id),
+                secret.name.clone(),
+                format_size(secret.size as u64),
+                time_ago(secret.created_at),
+            ]
+        })
+        .collect();
+    print_table(&["ID", "NAME", "SIZE", "CREATED"], &rows);
+    Ok(())
+}
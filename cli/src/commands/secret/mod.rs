@@ -0,0 +1,4 @@
+pub mod create;
+pub mod inspect;
+pub mod ls;
+pub mod rm;
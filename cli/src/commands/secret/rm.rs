@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::secret::RmArgs;
+use crate::utils::block_on;
+
+// secret rm SECRET...（コンテナが参照しているシークレットはデーモンが拒否する）
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for secret in &args.secrets {
+            client.remove_secret(secret).await?;
+            println!("{}", secret);
+        }
+        Ok(())
+    })
+}
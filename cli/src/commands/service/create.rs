@@ -0,0 +1,30 @@
+use rocker_client::Client;
+use rocker_core::{resolve_env, ContainerConfig, ServiceSpec};
+use std::error::Error;
+use std::path::PathBuf;
+
+use crate::args::service::CreateArgs;
+use crate::utils::{block_on, parse_key_values};
+
+// service create --name NAME [--replicas N] [-e KEY=VALUE] [-l KEY=VALUE] [--network NETWORK] [--constraint EXPR] IMAGE [COMMAND...]
+//
+// マネージャーがタスクをノードに割り当てる。作成したサービスの ID を表示する。
+pub fn execute(args: &CreateArgs) -> Result<(), Box<dyn Error>> {
+    let spec = ServiceSpec {
+        name: args.name.clone(),
+        replicas: args.replicas,
+        template: ContainerConfig {
+            image: args.image.clone(),
+            cmd: (!args.command.is_empty()).then(|| args.command.clone()),
+            env: resolve_env::<PathBuf>(&[], &args.env)?,
+            ..ContainerConfig::default()
+        },
+        network: args.network.clone(),
+        constraints: args.constraints.clone(),
+        labels: parse_key_values(&args.labels)?,
+    };
+    let client = Client::new();
+    let service = block_on(client.create_service(&spec))?;
+    println!("{}", service.id);
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::service::InspectArgs;
+use crate::utils::block_on;
+
+// service inspect SERVICE...（すべて取得できてから JSON の配列で表示する）
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let services = block_on(async {
+        let mut services = Vec::new();
+        for service in &args.services {
+            services.push(client.inspect_service(service).await?);
+        }
+        Ok(services)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&services)?);
+    Ok(())
+}
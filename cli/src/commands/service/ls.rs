@@ -0,0 +1,31 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::service::LsArgs;
+use crate::utils::{block_on, print_table, short_id};
+
+// service ls [-q]（REPLICAS は動作中のタスクの数 / 指定した数）
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let services = block_on(client.list_services())?;
+    if args.quiet {
+        for service in &services {
+            println!("{}", service.id);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 4]> = services
+        .iter()
+        .map(|service| {
+            [
+                short_id(&service.id),
+                service.spec.name.clone(),
+                format!("{}/{}", service.running, service.spec.replicas),
+                service.spec.template.image.clone(),
+            ]
+        })
+        .collect();
+    print_table(&["ID", "NAME", "REPLICAS", "IMAGE"], &rows);
+    Ok(())
+}
@@ -0,0 +1,6 @@
+pub mod create;
+pub mod inspect;
+pub mod ls;
+pub mod ps;
+pub mod rm;
+pub mod scale;
@@ -0,0 +1,38 @@
+use rocker_client::Client;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::args::service::PsArgs;
+use crate::utils::{block_on, print_table};
+
+// service ps SERVICE（タスクごとに割り当てたノードと状態を表示する）
+pub fn execute(args: &PsArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let (service, nodes) = block_on(async {
+        let service = client.inspect_service(&args.service).await?;
+        let nodes = client.list_nodes().await?;
+        Ok((service, nodes))
+    })?;
+    let hostnames: HashMap<&str, &str> = nodes.iter().map(|node| (node.id.as_str(), node.hostname.as_str())).collect();
+
+    let mut tasks: Vec<_> = service.tasks.iter().collect();
+    tasks.sort_by_key(|task| task.slot);
+    let rows: Vec<[String; 4]> = tasks
+        .iter()
+        .map(|task| {
+            let node = task
+                .node_id
+                .as_deref()
+                .map(|id| hostnames.get(id).copied().unwrap_or(id))
+                .unwrap_or_default();
+            [
+                format!("{}.{}", service.spec.name, task.slot),
+                node.to_string(),
+                task.state.to_string(),
+                task.error.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    print_table(&["NAME", "NODE", "STATE", "ERROR"], &rows);
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::service::RmArgs;
+use crate::utils::block_on;
+
+// service rm SERVICE...（タスクのコンテナも削除される）
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for service in &args.services {
+            client.remove_service(service).await?;
+            println!("{}", service);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,28 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::service::ScaleArgs;
+use crate::utils::block_on;
+
+// service scale SERVICE=REPLICAS...（値をすべて確かめてから変更する）
+pub fn execute(args: &ScaleArgs) -> Result<(), Box<dyn Error>> {
+    let scales = args
+        .services
+        .iter()
+        .map(|spec| {
+            spec.split_once('=')
+                .and_then(|(service, replicas)| Some((service, replicas.parse::<u64>().ok()?)))
+                .filter(|(service, _)| !service.is_empty())
+                .ok_or_else(|| format!("Invalid scale (expected SERVICE=REPLICAS): {}", spec))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let client = Client::new();
+    block_on(async {
+        for (service, replicas) in scales {
+            client.scale_service(service, replicas).await?;
+            println!("{} scaled to {}", service, replicas);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,10 @@
+use std::error::Error;
+
+use crate::args::stack::DeployArgs;
+use crate::commands::compose::files;
+use crate::utils::block_on;
+
+// stack deploy [-c FILE] [--prune] STACK（compose ファイルのサービスをスワームのサービスにする）
+pub fn execute(args: &DeployArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::stack_deploy_command(&files(&args.files), &args.stack, args.prune))
+}
@@ -0,0 +1,8 @@
+use std::error::Error;
+
+use crate::utils::block_on;
+
+// stack ls
+pub fn execute() -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::stack_ls_command())
+}
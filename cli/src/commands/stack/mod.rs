@@ -0,0 +1,4 @@
+pub mod deploy;
+pub mod ls;
+pub mod ps;
+pub mod rm;
@@ -0,0 +1,9 @@
+use std::error::Error;
+
+use crate::args::stack::PsArgs;
+use crate::utils::block_on;
+
+// stack ps STACK
+pub fn execute(args: &PsArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::stack_ps_command(&args.stack))
+}
@@ -0,0 +1,9 @@
+use std::error::Error;
+
+use crate::args::stack::RmArgs;
+use crate::utils::block_on;
+
+// stack rm STACK...
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    block_on(rocker_compose::stack_rm_command(&args.stacks))
+}
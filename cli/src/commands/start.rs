@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::StartArgs;
+use crate::utils::block_on;
+
+// start CONTAINER...（create で作ったコンテナや停止したコンテナを起動する）
+pub fn execute(args: &StartArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for container in &args.containers {
+            client.start_container(container).await?;
+            println!("{}", container);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,98 @@
+use rocker_client::{Client, ClientError, JsonStream};
+use rocker_core::{format_size, ContainerStats};
+use std::error::Error;
+
+use crate::args::StatsArgs;
+use crate::utils::{block_on, print_table};
+
+// 統計を読んでいるコンテナ（CPU 使用率は前回の統計との差から求める）
+struct Sampled {
+    name: String,
+    stats: JsonStream<ContainerStats>,
+    previous: Option<ContainerStats>,
+}
+
+// stats [-a] [--no-stream] [CONTAINER...]
+//
+// デーモンが 1 秒ごとに送る統計で表を書き直し続ける。--no-stream では 2 回目の統計で一度だけ表示する。
+// 停止したコンテナは表から外し、表示するコンテナが無くなったら終了する。
+pub fn execute(args: &StatsArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        let names = if args.containers.is_empty() {
+            client
+                .list_containers(args.all, &[])
+                .await?
+                .into_iter()
+                .map(|container| container.name)
+                .collect()
+        } else {
+            args.containers.clone()
+        };
+
+        let mut sampled = Vec::new();
+        for name in names {
+            match client.stream_container_stats(&name).await {
+                Ok(stats) => sampled.push(Sampled { name, stats, previous: None }),
+                // 動作していないコンテナ（-a で一覧に入るもの）は飛ばす
+                Err(e) if !args.containers.is_empty() || !is_gone(e.as_ref()) => return Err(e),
+                Err(_) => {}
+            }
+        }
+
+        while !sampled.is_empty() {
+            let samples = futures::future::join_all(sampled.iter_mut().map(|container| container.stats.next())).await;
+            let mut rows = Vec::new();
+            let mut remaining = Vec::new();
+            for (mut container, sample) in sampled.into_iter().zip(samples) {
+                let Some(stats) = sample? else {
+                    continue;
+                };
+                if let Some(previous) = &container.previous {
+                    rows.push(row(&container.name, previous, &stats));
+                }
+                container.previous = Some(stats);
+                remaining.push(container);
+            }
+            sampled = remaining;
+
+            if rows.is_empty() {
+                continue;
+            }
+            if args.no_stream {
+                print_table(&TITLES, &rows);
+                break;
+            }
+            // 画面を消して左上から書き直す
+            print!("\x1b[2J\x1b[H");
+            print_table(&TITLES, &rows);
+        }
+        Ok(())
+    })
+}
+
+const TITLES: [&str; 7] = ["NAME", "CPU %", "MEM USAGE / LIMIT", "MEM %", "NET I/O", "BLOCK I/O", "PIDS"];
+
+fn row(name: &str, previous: &ContainerStats, stats: &ContainerStats) -> [String; 7] {
+    let (rx, tx) = stats.network_totals();
+    let (read, written) = stats.io_totals();
+    [
+        name.to_string(),
+        format!("{:.2}%", stats.cpu_percent(previous)),
+        format!(
+            "{} / {}",
+            format_size(stats.memory.usage_bytes),
+            stats.memory.limit_bytes.map_or("unlimited".to_string(), format_size)
+        ),
+        stats.memory_percent().map_or("--".to_string(), |percent| format!("{:.2}%", percent)),
+        format!("{} / {}", format_size(rx), format_size(tx)),
+        format!("{} / {}", format_size(read), format_size(written)),
+        stats.pids_current.map_or("--".to_string(), |pids| pids.to_string()),
+    ]
+}
+
+// 一覧を取ってから停止・削除されたコンテナ
+fn is_gone(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<ClientError>()
+        .is_some_and(|e| matches!(e.status.as_u16(), 404 | 409))
+}
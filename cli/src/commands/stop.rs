@@ -0,0 +1,18 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::StopArgs;
+use crate::utils::block_on;
+
+// stop [-t TIME] CONTAINER...（-t を省略するとコンテナの stop_timeout、無ければ 10 秒待つ）
+pub fn execute(args: &StopArgs) -> Result<(), Box<dyn Error>> {
+    let timeout = args.time.map(|time| time.as_secs());
+    let client = Client::new();
+    block_on(async {
+        for container in &args.containers {
+            client.stop_container(container, timeout).await?;
+            println!("{}", container);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,25 @@
+use rocker_client::Client;
+use rocker_core::SwarmInitRequest;
+use std::error::Error;
+
+use crate::args::swarm::InitArgs;
+use crate::utils::block_on;
+
+// swarm init --advertise-addr ADDR [--listen-addr ADDR]（ワーカーを参加させるコマンドを表示する）
+pub fn execute(args: &InitArgs) -> Result<(), Box<dyn Error>> {
+    let request = SwarmInitRequest {
+        listen_address: args.listen_addr.clone(),
+        advertise_address: args.advertise_addr.clone(),
+    };
+    let client = Client::new();
+    let info = block_on(client.init_swarm(&request))?;
+
+    println!("Swarm initialized: current node ({}) is now a manager.", info.node_id);
+    if let Some(token) = &info.join_token {
+        println!();
+        println!("To add a worker to this swarm, run the following command:");
+        println!();
+        println!("    rocker swarm join --token {} --advertise-addr <ADDR> {}", token, info.manager_address);
+    }
+    Ok(())
+}
@@ -0,0 +1,12 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::utils::block_on;
+
+// swarm inspect（このノードの ID・役割とマネージャーのアドレス）
+pub fn execute() -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let info = block_on(client.swarm_info())?;
+    println!("{}", serde_json::to_string_pretty(&info)?);
+    Ok(())
+}
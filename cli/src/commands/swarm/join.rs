@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use rocker_core::SwarmJoinRequest;
+use std::error::Error;
+
+use crate::args::swarm::JoinArgs;
+use crate::utils::block_on;
+
+// swarm join --token TOKEN --advertise-addr ADDR MANAGER
+pub fn execute(args: &JoinArgs) -> Result<(), Box<dyn Error>> {
+    let request = SwarmJoinRequest {
+        remote_address: args.remote.clone(),
+        token: args.token.clone(),
+        advertise_address: args.advertise_addr.clone(),
+    };
+    let client = Client::new();
+    let info = block_on(client.join_swarm(&request))?;
+    println!("This node joined a swarm as a {} (node {}).", info.role, info.node_id);
+    Ok(())
+}
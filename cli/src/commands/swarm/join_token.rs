@@ -0,0 +1,27 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::swarm::JoinTokenArgs;
+use crate::utils::block_on;
+
+// swarm join-token [--rotate] [-q]（-q ではトークンだけを表示する）
+pub fn execute(args: &JoinTokenArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let info = block_on(async {
+        if args.rotate {
+            client.rotate_join_token().await
+        } else {
+            client.swarm_info().await
+        }
+    })?;
+    let token = info.join_token.ok_or("This node is not a swarm manager")?;
+
+    if args.quiet {
+        println!("{}", token);
+    } else {
+        println!("To add a worker to this swarm, run the following command:");
+        println!();
+        println!("    rocker swarm join --token {} --advertise-addr <ADDR> {}", token, info.manager_address);
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::swarm::LeaveArgs;
+use crate::utils::block_on;
+
+// swarm leave [-f]（マネージャーは -f を付けた場合だけ抜けられる）
+pub fn execute(args: &LeaveArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(client.leave_swarm(args.force))?;
+    println!("Node left the swarm.");
+    Ok(())
+}
@@ -0,0 +1,5 @@
+pub mod init;
+pub mod inspect;
+pub mod join;
+pub mod join_token;
+pub mod leave;
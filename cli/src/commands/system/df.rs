@@ -0,0 +1,125 @@
+use rocker_client::{Client, DiskUsage};
+use rocker_core::{format_size, Container, Image, Volume};
+use std::error::Error;
+
+use crate::args::system::DfArgs;
+use crate::utils::{block_on, print_table, short_id, time_ago};
+
+// system df [-v]
+//
+// イメージ・コンテナ・ボリューム・ビルドキャッシュごとに数と大きさ、削除できる分を表示する。
+// -v ではそれぞれの一覧も表示する。
+pub fn execute(args: &DfArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let usage = block_on(client.disk_usage())?;
+
+    // 使われていないものの大きさが削除できる分（ボリュームはマウントしているコンテナの無いもの）
+    let (active_images, unused_images): (Vec<&Image>, Vec<&Image>) = usage
+        .images
+        .iter()
+        .partition(|image| usage.containers.iter().any(|container| uses_image(container, image)));
+    let (running, stopped): (Vec<&Container>, Vec<&Container>) =
+        usage.containers.iter().partition(|container| container.state.is_running());
+    let (used_volumes, unused_volumes): (Vec<&Volume>, Vec<&Volume>) = usage
+        .volumes
+        .iter()
+        .partition(|volume| volume.usage.as_ref().is_some_and(|usage| usage.ref_count > 0));
+
+    let rows = [
+        row("Images", usage.images.len(), active_images.len(), usage.images_size, unused_images.iter().map(|image| image.size).sum()),
+        row(
+            "Containers",
+            usage.containers.len(),
+            running.len(),
+            usage.containers_size,
+            stopped.iter().map(|container| container.size_rw.unwrap_or(0)).sum(),
+        ),
+        row(
+            "Local Volumes",
+            usage.volumes.len(),
+            used_volumes.len(),
+            usage.volumes_size,
+            unused_volumes.iter().map(|volume| volume.usage.as_ref().map_or(0, |usage| usage.size)).sum(),
+        ),
+        // ビルドキャッシュの大きさはイメージが使っていないレイヤの分
+        row("Build Cache", usage.build_cache, 0, usage.build_cache_size, usage.build_cache_size),
+    ];
+    print_table(&["TYPE", "TOTAL", "ACTIVE", "SIZE", "RECLAIMABLE"], &rows);
+
+    if args.verbose {
+        print_verbose(&usage);
+    }
+    Ok(())
+}
+
+fn row(kind: &str, total: usize, active: usize, size: u64, reclaimable: u64) -> [String; 5] {
+    let percent = (reclaimable * 100).checked_div(size).unwrap_or(0);
+    [
+        kind.to_string(),
+        total.to_string(),
+        active.to_string(),
+        format_size(size),
+        format!("{} ({}%)", format_size(reclaimable), percent),
+    ]
+}
+
+// コンテナが ID か名前でイメージを使っているか
+fn uses_image(container: &Container, image: &Image) -> bool {
+    container.config.image == image.id || image.matches_name(&container.config.image)
+}
+
+fn print_verbose(usage: &DiskUsage) {
+    println!();
+    println!("Images space usage:");
+    println!();
+    let rows: Vec<[String; 5]> = usage
+        .images
+        .iter()
+        .map(|image| {
+            let containers = usage.containers.iter().filter(|container| uses_image(container, image)).count();
+            [
+                image.full_name().unwrap_or_else(|| "<none>".to_string()),
+                short_id(&image.id),
+                time_ago(image.created_at),
+                format_size(image.size),
+                containers.to_string(),
+            ]
+        })
+        .collect();
+    print_table(&["IMAGE", "IMAGE ID", "CREATED", "SIZE", "CONTAINERS"], &rows);
+
+    println!();
+    println!("Containers space usage:");
+    println!();
+    let rows: Vec<[String; 6]> = usage
+        .containers
+        .iter()
+        .map(|container| {
+            [
+                short_id(&container.id),
+                container.config.image.clone(),
+                time_ago(container.created_at),
+                container.status(),
+                format_size(container.size_rw.unwrap_or(0)),
+                container.name.clone(),
+            ]
+        })
+        .collect();
+    print_table(&["CONTAINER ID", "IMAGE", "CREATED", "STATUS", "SIZE", "NAMES"], &rows);
+
+    println!();
+    println!("Local Volumes space usage:");
+    println!();
+    let rows: Vec<[String; 3]> = usage
+        .volumes
+        .iter()
+        .map(|volume| {
+            let (links, size) = match &volume.usage {
+                Some(usage) => (usage.ref_count.to_string(), format_size(usage.size)),
+                None => ("N/A".to_string(), "N/A".to_string()),
+            };
+            [volume.name.clone(), links, size]
+        })
+        .collect();
+    print_table(&["VOLUME NAME", "LINKS", "SIZE"], &rows);
+}
@@ -0,0 +1,27 @@
+use rocker_client::Client;
+use rocker_core::format_size;
+use std::error::Error;
+
+use crate::utils::block_on;
+
+// system info（コンテナとイメージの数、ホストの資源と動作中のコンテナが予約している分）
+pub fn execute() -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let info = block_on(client.info())?;
+    let resources = &info.resources;
+
+    println!("Containers: {}", info.containers);
+    println!(" Running: {}", info.containers_running);
+    println!(" Paused: {}", info.containers_paused);
+    println!(" Stopped: {}", info.containers_stopped);
+    println!("Images: {}", info.images);
+    println!("CPUs: {}", info.ncpu);
+    println!("Total Memory: {}", format_size(info.mem_total));
+    println!("Resources:");
+    println!(" Allocatable CPUs: {}", resources.allocatable_cpus);
+    println!(" Allocatable Memory: {}", format_size(resources.allocatable_memory));
+    println!(" Reserved CPUs: {}", resources.reserved_cpus);
+    println!(" Reserved Memory: {}", format_size(resources.reserved_memory));
+    println!(" Reject Oversubscription: {}", resources.reject_oversubscription);
+    Ok(())
+}
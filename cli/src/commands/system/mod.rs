@@ -0,0 +1,2 @@
+pub mod df;
+pub mod info;
@@ -0,0 +1,13 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::volume::CloneArgs;
+use crate::utils::block_on;
+
+// volume clone SOURCE NAME（作成したボリュームの名前を表示する）
+pub fn execute(args: &CloneArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let volume = block_on(client.clone_volume(&args.source, &args.name))?;
+    println!("{}", volume.name);
+    Ok(())
+}
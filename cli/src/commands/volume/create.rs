@@ -0,0 +1,19 @@
+use rocker_client::{Client, VolumeCreateOptions};
+use std::error::Error;
+
+use crate::args::volume::CreateArgs;
+use crate::utils::{block_on, parse_key_values};
+
+// volume create [-d DRIVER] [--label KEY=VALUE] [-o KEY=VALUE] [NAME]（作成したボリュームの名前を表示する）
+pub fn execute(args: &CreateArgs) -> Result<(), Box<dyn Error>> {
+    let options = VolumeCreateOptions {
+        name: args.name.clone().unwrap_or_default(),
+        driver: Some(args.driver.clone()),
+        driver_opts: parse_key_values(&args.opts)?,
+        labels: parse_key_values(&args.labels)?,
+    };
+    let client = Client::new();
+    let volume = block_on(client.create_volume(&options))?;
+    println!("{}", volume.name);
+    Ok(())
+}
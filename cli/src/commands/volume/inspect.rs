@@ -0,0 +1,19 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::volume::InspectArgs;
+use crate::utils::block_on;
+
+// volume inspect VOLUME...（すべて取得できてから JSON の配列で表示する）
+pub fn execute(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let volumes = block_on(async {
+        let mut volumes = Vec::new();
+        for volume in &args.volumes {
+            volumes.push(client.inspect_volume(volume).await?);
+        }
+        Ok(volumes)
+    })?;
+    println!("{}", serde_json::to_string_pretty(&volumes)?);
+    Ok(())
+}
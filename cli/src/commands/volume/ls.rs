@@ -0,0 +1,53 @@
+use rocker_client::Client;
+use rocker_core::{format_size, Volume};
+use std::error::Error;
+
+use crate::args::volume::LsArgs;
+use crate::utils::{block_on, parse_filters, print_table, render_template};
+
+// volume ls [-f KEY=VALUE] [--format TEMPLATE] [-q]
+//
+// 使用量はディレクトリを走査して求めるため、--format で Size か Links を使った場合だけデーモンに求める。
+pub fn execute(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    let size = args
+        .format
+        .as_deref()
+        .is_some_and(|format| format.contains("Size") || format.contains("Links"));
+    let client = Client::new();
+    let volumes = block_on(client.list_volumes(&filters, size))?;
+    if args.quiet {
+        for volume in &volumes {
+            println!("{}", volume.name);
+        }
+        return Ok(());
+    }
+    if let Some(format) = &args.format {
+        for volume in &volumes {
+            println!("{}", render_template(format, &fields(volume))?);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 2]> = volumes
+        .iter()
+        .map(|volume| [volume.driver.to_string(), volume.name.clone()])
+        .collect();
+    print_table(&["DRIVER", "VOLUME NAME"], &rows);
+    Ok(())
+}
+
+// --format で使えるフィールド（使用量が分からなければ N/A）
+fn fields(volume: &Volume) -> [(&'static str, String); 7] {
+    let mut labels: Vec<String> = volume.labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+    labels.sort();
+    [
+        ("Name", volume.name.clone()),
+        ("Driver", volume.driver.to_string()),
+        ("Mountpoint", volume.mountpoint.display().to_string()),
+        ("Scope", volume.scope.to_string()),
+        ("Labels", labels.join(",")),
+        ("Size", volume.usage.as_ref().map_or("N/A".to_string(), |usage| format_size(usage.size))),
+        ("Links", volume.usage.as_ref().map_or("N/A".to_string(), |usage| usage.ref_count.to_string())),
+    ]
+}
@@ -0,0 +1,7 @@
+pub mod clone;
+pub mod create;
+pub mod inspect;
+pub mod ls;
+pub mod prune;
+pub mod rm;
+pub mod snapshot;
@@ -0,0 +1,26 @@
+use rocker_client::Client;
+use rocker_core::format_size;
+use std::error::Error;
+
+use crate::args::volume::PruneArgs;
+use crate::utils::{block_on, confirm, parse_filters};
+
+// volume prune [--filter KEY=VALUE] [-f]（コンテナが使っていないボリュームを削除する）
+pub fn execute(args: &PruneArgs) -> Result<(), Box<dyn Error>> {
+    let filters = parse_filters(&args.filters)?;
+    if !args.force && !confirm("This will remove all local volumes not used by at least one container.")? {
+        return Ok(());
+    }
+
+    let client = Client::new();
+    let report = block_on(client.prune_volumes(&filters))?;
+    if !report.volumes_deleted.is_empty() {
+        println!("Deleted Volumes:");
+        for name in &report.volumes_deleted {
+            println!("{}", name);
+        }
+        println!();
+    }
+    println!("Total reclaimed space: {}", format_size(report.space_reclaimed));
+    Ok(())
+}
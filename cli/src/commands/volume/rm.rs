@@ -0,0 +1,17 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::volume::RmArgs;
+use crate::utils::block_on;
+
+// volume rm VOLUME...（コンテナが使っているボリュームはデーモンが拒否する）
+pub fn execute(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(async {
+        for volume in &args.volumes {
+            client.remove_volume(volume).await?;
+            println!("{}", volume);
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,42 @@
+use rocker_client::Client;
+use std::error::Error;
+
+use crate::args::volume::{SnapshotArgs, SnapshotLsArgs};
+use crate::utils::{block_on, print_table, time_ago};
+
+// volume snapshot create VOLUME NAME
+pub fn create(args: &SnapshotArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let snapshot = block_on(client.snapshot_volume(&args.volume, &args.name))?;
+    println!("{}", snapshot.name);
+    Ok(())
+}
+
+// volume snapshot ls VOLUME（古い順）
+pub fn ls(args: &SnapshotLsArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let mut snapshots = block_on(client.inspect_volume(&args.volume))?.snapshots;
+    snapshots.sort_by_key(|snapshot| snapshot.created_at);
+    let rows: Vec<[String; 2]> = snapshots
+        .iter()
+        .map(|snapshot| [snapshot.name.clone(), time_ago(snapshot.created_at)])
+        .collect();
+    print_table(&["NAME", "CREATED"], &rows);
+    Ok(())
+}
+
+// volume snapshot restore VOLUME NAME（コンテナが使っているボリュームはデーモンが拒否する）
+pub fn restore(args: &SnapshotArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(client.restore_snapshot(&args.volume, &args.name))?;
+    println!("{}", args.name);
+    Ok(())
+}
+
+// volume snapshot rm VOLUME NAME
+pub fn rm(args: &SnapshotArgs) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    block_on(client.remove_snapshot(&args.volume, &args.name))?;
+    println!("{}", args.name);
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use clap::CommandFactory;
+use rocker_client::ClientConfig;
+use std::ffi::OsString;
+
+use crate::args::Cli;
+
+// 値を取る rocker 自体のオプション（コマンド名の前に置ける）
+const GLOBAL_OPTIONS_WITH_VALUE: [&str; 4] = ["-H", "--host", "-c", "--context"];
+
+// config.json の aliases と formats を引数に展開する
+//
+// コマンドの名前がエイリアスなら、その引数に置き換える（組み込みのコマンドと同じ名前のエイリアスは
+// 使わず、展開したものを再び展開することもない）。--format を付けずに実行したコマンドに formats の
+// 値があれば、コマンドの名前の直後に --format を加える。
+pub fn expand_args(args: Vec<OsString>, config: &ClientConfig) -> Vec<OsString> {
+    let Some(index) = command_index(&args) else {
+        return args;
+    };
+    let mut args = args;
+    let cli = Cli::command();
+
+    let name = args[index].to_string_lossy().to_string();
+    if cli.find_subcommand(&name).is_none() {
+        if let Some(expansion) = config.aliases.get(&name) {
+            args.splice(index..=index, expansion.iter().map(OsString::from));
+        }
+    }
+
+    // サブコマンドの名前をたどり、実行するコマンド（"volume ls" など）を求める
+    let mut command = &cli;
+    let mut path = Vec::new();
+    let mut end = index;
+    while let Some(subcommand) = args
+        .get(end)
+        .and_then(|arg| arg.to_str())
+        .and_then(|name| command.find_subcommand(name))
+    {
+        path.push(subcommand.get_name().to_string());
+        command = subcommand;
+        end += 1;
+    }
+    let Some(format) = config.formats.get(&path.join(" ")) else {
+        return args;
+    };
+    let accepts_format = command.get_arguments().any(|arg| arg.get_long() == Some("format"));
+    let has_format = args[end..]
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .filter_map(|arg| arg.to_str())
+        .any(|arg| arg == "--format" || arg.starts_with("--format="));
+    if accepts_format && !has_format {
+        args.splice(end..end, [OsString::from("--format"), OsString::from(format)]);
+    }
+    args
+}
+
+// コマンドの名前の位置（rocker 自体のオプションを飛ばした最初の引数）
+fn command_index(args: &[OsString]) -> Option<usize> {
+    let mut index = 1;
+    while let Some(arg) = args.get(index) {
+        let arg = arg.to_str()?;
+        if GLOBAL_OPTIONS_WITH_VALUE.contains(&arg) {
+            index += 2;
+        } else if arg.starts_with('-') {
+            index += 1;
+        } else {
+            return Some(index);
+        }
+    }
+    None
+}
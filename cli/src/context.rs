@@ -0,0 +1,325 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::args::context::{CreateArgs, InspectArgs, LsArgs, RmArgs, UseArgs};
+
+// 接続先のデーモン（クライアントが読む環境変数）
+const HOST_ENV: &str = "ROCKER_HOST";
+const CERT_PATH_ENV: &str = "ROCKER_CERT_PATH";
+const TLS_VERIFY_ENV: &str = "ROCKER_TLS_VERIFY";
+// 使うコンテキスト（設定ファイルの currentContext より優先する）
+const CONTEXT_ENV: &str = "ROCKER_CONTEXT";
+
+// 組み込みのコンテキスト（ローカルのデーモンの Unix ソケット）
+const DEFAULT_CONTEXT: &str = "default";
+const DEFAULT_HOST: &str = "unix:///var/run/rocker.sock";
+
+// コンテキストのディレクトリに置く TLS のファイル
+const TLS_FILES: [&str; 3] = ["ca.pem", "cert.pem", "key.pem"];
+
+// contexts/<name>/meta.json に保存するコンテキスト
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Context {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // unix:///path か tcp://host:port
+    pub host: String,
+    // デーモンの証明書を ca.pem で検証する
+    #[serde(default)]
+    pub tls_verify: bool,
+    // TLS のファイルを置いたディレクトリ（TLS を使わなければ None）
+    #[serde(default)]
+    pub tls_path: Option<PathBuf>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Context {
+    fn default_context() -> Self {
+        Context {
+            name: DEFAULT_CONTEXT.to_string(),
+            description: "Current local daemon".to_string(),
+            host: DEFAULT_HOST.to_string(),
+            tls_verify: false,
+            tls_path: None,
+            created_at: None,
+        }
+    }
+}
+
+// コンテキストと現在のコンテキストを保存する設定ディレクトリ（ROCKER_CONFIG、既定は $XDG_CONFIG_HOME/rocker か ~/.config/rocker）
+struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    fn open() -> Result<Self, Box<dyn Error>> {
+        Ok(Store {
+            dir: rocker_client::config_dir()?,
+        })
+    }
+
+    fn context_dir(&self, name: &str) -> PathBuf {
+        self.dir.join("contexts").join(name)
+    }
+
+    fn config_path(&self) -> PathBuf {
+        self.dir.join("config.json")
+    }
+
+    fn get(&self, name: &str) -> Result<Context, Box<dyn Error>> {
+        if name == DEFAULT_CONTEXT {
+            return Ok(Context::default_context());
+        }
+        let path = self.context_dir(name).join("meta.json");
+        let data = std::fs::read(&path).map_err(|_| format!("Context \"{}\" does not exist", name))?;
+        Ok(serde_json::from_slice(&data).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?)
+    }
+
+    // 保存したコンテキスト（default を先頭に、残りは名前順）
+    fn list(&self) -> Result<Vec<Context>, Box<dyn Error>> {
+        let mut contexts = Vec::new();
+        if let Ok(entries) = std::fs::read_dir(self.dir.join("contexts")) {
+            for entry in entries {
+                let name = entry?.file_name().to_string_lossy().to_string();
+                contexts.push(self.get(&name)?);
+            }
+        }
+        contexts.sort_by(|a, b| a.name.cmp(&b.name));
+        contexts.insert(0, Context::default_context());
+        Ok(contexts)
+    }
+
+    // config.json（他の設定のキーは書き換えずに残す）
+    fn config(&self) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn Error>> {
+        let path = self.config_path();
+        match std::fs::read(&path) {
+            Ok(data) => Ok(serde_json::from_slice(&data).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(serde_json::Map::new()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        }
+    }
+
+    fn current(&self) -> Result<String, Box<dyn Error>> {
+        Ok(self
+            .config()?
+            .get("currentContext")
+            .and_then(|name| name.as_str())
+            .unwrap_or(DEFAULT_CONTEXT)
+            .to_string())
+    }
+
+    fn set_current(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        let mut config = self.config()?;
+        if name == DEFAULT_CONTEXT {
+            config.remove("currentContext");
+        } else {
+            config.insert("currentContext".to_string(), serde_json::Value::String(name.to_string()));
+        }
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(self.config_path(), serde_json::to_vec_pretty(&config)?)?;
+        Ok(())
+    }
+}
+
+// --host・ROCKER_HOST・--context・ROCKER_CONTEXT・現在のコンテキストの順に接続先を決め、クライアントの環境変数に設定する
+pub fn apply(host: Option<&str>, context: Option<&str>) -> Result<(), Box<dyn Error>> {
+    if let Some(host) = host {
+        validate_host(host)?;
+        std::env::set_var(HOST_ENV, host);
+        return Ok(());
+    }
+
+    let name = match context {
+        Some(name) => name.to_string(),
+        None => match std::env::var(CONTEXT_ENV).ok().filter(|name| !name.is_empty()) {
+            Some(name) => name,
+            // ROCKER_HOST をそのまま使う
+            None if std::env::var_os(HOST_ENV).is_some() => return Ok(()),
+            None => Store::open()?.current()?,
+        },
+    };
+    let context = Store::open()?.get(&name)?;
+    std::env::set_var(HOST_ENV, &context.host);
+    match &context.tls_path {
+        Some(tls_path) => {
+            std::env::set_var(CERT_PATH_ENV, tls_path);
+            std::env::set_var(TLS_VERIFY_ENV, if context.tls_verify { "1" } else { "" });
+        }
+        None => {
+            std::env::remove_var(CERT_PATH_ENV);
+            std::env::remove_var(TLS_VERIFY_ENV);
+        }
+    }
+    Ok(())
+}
+
+// context create NAME --host HOST [--description TEXT] [--tls-ca FILE --tls-cert FILE --tls-key FILE] [--tls-verify]
+pub fn create(args: &CreateArgs) -> Result<(), Box<dyn Error>> {
+    let name = args.name.as_str();
+    validate_name(name)?;
+    let host = args.host.as_str();
+    validate_host(host)?;
+
+    let store = Store::open()?;
+    let dir = store.context_dir(name);
+    if name == DEFAULT_CONTEXT || dir.exists() {
+        return Err(format!("Context \"{}\" already exists", name).into());
+    }
+
+    // TLS のファイルをコンテキストのディレクトリに複製する（元のファイルを消しても使えるように）
+    let sources: Vec<(&str, Option<&PathBuf>)> = TLS_FILES
+        .iter()
+        .copied()
+        .zip([args.tls_ca.as_ref(), args.tls_cert.as_ref(), args.tls_key.as_ref()])
+        .collect();
+    let tls_verify = args.tls_verify;
+    let uses_tls = tls_verify || sources.iter().any(|(_, source)| source.is_some());
+    if uses_tls && !host.starts_with("tcp://") {
+        return Err("TLS can only be used with a tcp:// host".into());
+    }
+
+    std::fs::create_dir_all(&dir)?;
+    let tls_path = if uses_tls {
+        let tls_dir = dir.join("tls");
+        std::fs::create_dir_all(&tls_dir)?;
+        for (file, source) in &sources {
+            if let Some(source) = source {
+                copy_tls_file(source, &tls_dir.join(file))?;
+            }
+        }
+        Some(tls_dir)
+    } else {
+        None
+    };
+
+    let context = Context {
+        name: name.to_string(),
+        description: args.description.clone().unwrap_or_default(),
+        host: host.to_string(),
+        tls_verify,
+        tls_path,
+        created_at: Some(Utc::now()),
+    };
+    std::fs::write(dir.join("meta.json"), serde_json::to_vec_pretty(&context)?)?;
+    println!("{}", name);
+    Ok(())
+}
+
+// context use NAME
+pub fn use_context(args: &UseArgs) -> Result<(), Box<dyn Error>> {
+    let name = args.name.as_str();
+    let store = Store::open()?;
+    store.get(name)?;
+    store.set_current(name)?;
+    println!("Current context is now \"{}\"", name);
+    Ok(())
+}
+
+// context ls（現在のコンテキストに * を付ける）
+pub fn ls(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let store = Store::open()?;
+    let contexts = store.list()?;
+    let current = store.current()?;
+    if args.quiet {
+        for context in &contexts {
+            println!("{}", context.name);
+        }
+        return Ok(());
+    }
+
+    let rows: Vec<[String; 3]> = contexts
+        .iter()
+        .map(|context| {
+            let marker = if context.name == current { " *" } else { "" };
+            [format!("{}{}", context.name, marker), context.description.clone(), context.host.clone()]
+        })
+        .collect();
+    let width = |column: usize, header: &str| rows.iter().map(|row| row[column].len()).chain([header.len()]).max().unwrap_or(0);
+    let (name_width, description_width) = (width(0, "NAME"), width(1, "DESCRIPTION"));
+    println!("{:<name_width$}   {:<description_width$}   ENDPOINT", "NAME", "DESCRIPTION");
+    for [name, description, host] in &rows {
+        println!("{:<name_width$}   {:<description_width$}   {}", name, description, host);
+    }
+    Ok(())
+}
+
+// context inspect NAME...（省略すると現在のコンテキスト）
+pub fn inspect(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let store = Store::open()?;
+    let names = if args.names.is_empty() {
+        vec![store.current()?]
+    } else {
+        args.names.clone()
+    };
+    let contexts = names.iter().map(|name| store.get(name)).collect::<Result<Vec<_>, _>>()?;
+    println!("{}", serde_json::to_string_pretty(&contexts)?);
+    Ok(())
+}
+
+// context rm NAME...（使用中のコンテキストは --force を付けた場合のみ削除して default に戻す）
+pub fn rm(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    let store = Store::open()?;
+    let current = store.current()?;
+    for name in &args.names {
+        if name == DEFAULT_CONTEXT {
+            return Err("The default context cannot be removed".into());
+        }
+        store.get(name)?;
+        if *name == current {
+            if !args.force {
+                return Err(format!("Context \"{}\" is in use, set another context or use --force", name).into());
+            }
+            store.set_current(DEFAULT_CONTEXT)?;
+        }
+        std::fs::remove_dir_all(store.context_dir(name))?;
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<(), Box<dyn Error>> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-'));
+    if !valid {
+        return Err(format!("Invalid context name: {} (must match [a-zA-Z0-9][a-zA-Z0-9_.+-]*)", name).into());
+    }
+    Ok(())
+}
+
+fn validate_host(host: &str) -> Result<(), Box<dyn Error>> {
+    let valid = match host.split_once("://") {
+        Some(("unix", path)) => path.starts_with('/'),
+        Some(("tcp", address)) => address
+            .trim_end_matches('/')
+            .rsplit_once(':')
+            .is_some_and(|(name, port)| !name.is_empty() && port.parse::<u16>().is_ok()),
+        _ => false,
+    };
+    if !valid {
+        return Err(format!("Invalid host (expected unix:///path or tcp://host:port): {}", host).into());
+    }
+    Ok(())
+}
+
+// 秘密鍵を含むため、所有者だけが読めるファイルを新しく作ってからコピーする（コピーの途中も他のユーザーに読ませない）
+fn copy_tls_file(source: &Path, destination: &Path) -> Result<(), Box<dyn Error>> {
+    let copy_error = |e: std::io::Error| format!("Failed to copy {}: {}", source.display(), e);
+    let mut reader = std::fs::File::open(source).map_err(copy_error)?;
+    match std::fs::remove_file(destination) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(copy_error(e).into()),
+        _ => {}
+    }
+    let mut writer = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(destination)
+        .map_err(copy_error)?;
+    std::io::copy(&mut reader, &mut writer).map_err(copy_error)?;
+    Ok(())
+}
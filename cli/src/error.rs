@@ -0,0 +1,122 @@
+use rocker_client::{paint, use_color, ClientError, ConnectError};
+use rocker_core::errors::{ErrorClass, RockerError};
+use std::error::Error;
+
+// 終了コード（clap も引数の誤りに 2 を使う）
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_NOT_FOUND: i32 = 3;
+pub const EXIT_CONFLICT: i32 = 4;
+pub const EXIT_UNAVAILABLE: i32 = 5;
+pub const EXIT_INTERNAL: i32 = 6;
+
+// エラーの分類（終了コードと、あれば次に試すことのヒント）
+struct Failure {
+    code: i32,
+    hint: Option<String>,
+}
+
+impl Failure {
+    fn new(code: i32) -> Self {
+        Failure { code, hint: None }
+    }
+
+    fn with_hint(code: i32, hint: impl Into<String>) -> Self {
+        Failure {
+            code,
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+// エラーを "Error: メッセージ" とヒントの形で標準エラーに表示し、終了コードを返す
+pub fn present(e: &(dyn Error + 'static)) -> i32 {
+    let failure = classify(e);
+    let stderr = std::io::stderr();
+    let color = use_color(&stderr);
+
+    let message = match e.downcast_ref::<ClientError>() {
+        // デーモン内部のエラーは、デーモン側で起きたとわかるようにする
+        Some(e) if e.status.is_server_error() => format!("Error response from daemon: {}", e.message),
+        _ => e.to_string(),
+    };
+    eprintln!("{} {}", paint(color, "31;1", "Error:"), message);
+    if let Some(hint) = failure.hint {
+        eprintln!("{} {}", paint(color, "33", "Hint:"), hint);
+    }
+    failure.code
+}
+
+// 原因をたどり、最初に分類できたエラーで終了コードとヒントを選ぶ（compose などが付けた文脈の下も見る）
+fn classify(e: &(dyn Error + 'static)) -> Failure {
+    let mut current = Some(e);
+    while let Some(e) = current {
+        if let Some(failure) = classify_one(e) {
+            return failure;
+        }
+        current = e.source();
+    }
+    Failure::new(EXIT_FAILURE)
+}
+
+fn classify_one(e: &(dyn Error + 'static)) -> Option<Failure> {
+    if let Some(e) = e.downcast_ref::<ConnectError>() {
+        return Some(Failure::with_hint(
+            EXIT_UNAVAILABLE,
+            format!(
+                "Is rockerd running at {}? Start it with 'sudo rockerd', or choose another daemon with --host, --context or ROCKER_HOST",
+                e.endpoint
+            ),
+        ));
+    }
+    if let Some(e) = e.downcast_ref::<ClientError>() {
+        let class = match e.status.as_u16() {
+            400 => ErrorClass::Invalid,
+            401 => ErrorClass::Unauthorized,
+            403 => ErrorClass::Forbidden,
+            404 => ErrorClass::NotFound,
+            409 => ErrorClass::Conflict,
+            503 => ErrorClass::Unavailable,
+            _ => ErrorClass::Internal,
+        };
+        return Some(failure(class, e.kind.as_deref()));
+    }
+
+    // CLI の中で起きたエラー（デーモンと同じ分類にする）
+    RockerError::code_of(e).map(|code| failure(code.class, code.kind))
+}
+
+fn failure(class: ErrorClass, kind: Option<&str>) -> Failure {
+    match class {
+        ErrorClass::NotFound => not_found(kind),
+        ErrorClass::Invalid => Failure::new(EXIT_USAGE),
+        ErrorClass::Conflict => Failure::new(EXIT_CONFLICT),
+        ErrorClass::Unauthorized => Failure::with_hint(
+            EXIT_FAILURE,
+            "Check the username and password, or log in again with 'rocker login'",
+        ),
+        ErrorClass::Unavailable if kind == Some("swarm") => Failure::with_hint(
+            EXIT_FAILURE,
+            "Run this on the swarm manager, or create a swarm with 'rocker swarm init' first",
+        ),
+        ErrorClass::Internal => Failure::new(EXIT_INTERNAL),
+        ErrorClass::Forbidden | ErrorClass::Unavailable => Failure::new(EXIT_FAILURE),
+    }
+}
+
+// 見つからなかったものの一覧を表示するコマンドをヒントにする
+fn not_found(kind: Option<&str>) -> Failure {
+    let command = match kind {
+        Some("container") => "rocker ps -a",
+        Some("image") => "rocker images",
+        Some("network") => "rocker network ls",
+        Some("volume") => "rocker volume ls",
+        Some("secret") => "rocker secret ls",
+        Some("schedule") => "rocker schedule ls",
+        Some("service") => "rocker service ls",
+        Some("node") => "rocker node ls",
+        Some("compose") => "rocker compose config",
+        _ => return Failure::new(EXIT_NOT_FOUND),
+    };
+    Failure::with_hint(EXIT_NOT_FOUND, format!("Run '{}' to see what exists", command))
+}
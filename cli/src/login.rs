@@ -0,0 +1,111 @@
+use rocker_client::{registry_host, Client, ClientConfig, CredentialStore};
+use rocker_core::{RegistryAuth, DEFAULT_REGISTRY};
+use std::error::Error;
+use std::io::{BufRead, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::args::{LoginArgs, LogoutArgs};
+
+// login [SERVER] [-u USER] [-p PASSWORD | --password-stdin]
+//
+// デーモンでレジストリにログインできることを確かめてから、config.json の設定に従って
+// 認証情報をヘルパ（credHelpers・credsStore）か auths に保存する。
+pub fn login(args: &LoginArgs) -> Result<(), Box<dyn Error>> {
+    let registry = registry_or_default(args.server.as_deref())?;
+    if args.password.is_some() {
+        eprintln!("WARNING! Using --password via the CLI is insecure. Use --password-stdin.");
+    }
+    if args.password_stdin && args.username.is_none() {
+        return Err("Must provide --username with --password-stdin".into());
+    }
+
+    let username = match &args.username {
+        Some(username) => username.clone(),
+        None => prompt(&format!("Username for {}: ", registry), true)?,
+    };
+    let password = match &args.password {
+        Some(password) => password.clone(),
+        None if args.password_stdin => read_line(&mut std::io::stdin().lock())?,
+        None => prompt("Password: ", false)?,
+    };
+    if username.is_empty() || password.is_empty() {
+        return Err("Username and password are required".into());
+    }
+
+    let auth = RegistryAuth {
+        username,
+        password,
+        server_address: registry.clone(),
+    };
+    let store = CredentialStore::open()?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let (status, helper) = runtime.block_on(async {
+        let status = Client::new().login(&auth).await?;
+        let helper = store.store(&auth).await?;
+        Ok::<_, Box<dyn Error>>((status, helper))
+    })?;
+
+    if helper.is_none() {
+        eprintln!(
+            "WARNING! Your password is stored unencrypted in {}.\nConfigure a credential helper (credsStore or credHelpers) to keep it in a keychain.",
+            store.path().display()
+        );
+    }
+    println!("{}", status);
+    Ok(())
+}
+
+// logout [SERVER]（ヘルパに保存した認証情報も消す）
+pub fn logout(args: &LogoutArgs) -> Result<(), Box<dyn Error>> {
+    let registry = registry_or_default(args.server.as_deref())?;
+    let store = CredentialStore::open()?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    if runtime.block_on(store.erase(&registry))? {
+        println!("Removing login credentials for {}", registry);
+    } else {
+        println!("Not logged in to {}", registry);
+    }
+    Ok(())
+}
+
+// 指定したレジストリ、無ければ config.json の defaultRegistry か docker.io
+fn registry_or_default(server: Option<&str>) -> Result<String, Box<dyn Error>> {
+    let server = match server {
+        Some(server) => server.to_string(),
+        None => ClientConfig::load()?
+            .default_registry
+            .unwrap_or_else(|| DEFAULT_REGISTRY.to_string()),
+    };
+    Ok(registry_host(&server))
+}
+
+// 端末に表示して 1 行読む（echo しない場合は stty で入力を隠す）
+fn prompt(message: &str, echo: bool) -> Result<String, Box<dyn Error>> {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        return Err("Cannot prompt for credentials without a terminal, use --username and --password-stdin".into());
+    }
+    eprint!("{}", message);
+    std::io::stderr().flush()?;
+    let hidden = !echo && stty("-echo");
+    let line = read_line(&mut stdin.lock());
+    if hidden {
+        stty("echo");
+        eprintln!();
+    }
+    line
+}
+
+fn read_line(input: &mut impl BufRead) -> Result<String, Box<dyn Error>> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn stty(mode: &str) -> bool {
+    Command::new("stty")
+        .arg(mode)
+        .stdin(Stdio::inherit())
+        .status()
+        .is_ok_and(|status| status.success())
+}
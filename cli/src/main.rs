@@ -1,158 +1,180 @@
-use clap::{App, AppSettings, Arg, SubCommand};
+use clap::Parser;
+use rocker_client::ClientConfig;
 use std::error::Error;
 
+use args::builder::BuilderCommand;
+use args::compose::ComposeCommand;
+use args::context::ContextCommand;
+use args::image::ImageCommand;
+use args::network::NetworkCommand;
+use args::node::NodeCommand;
+use args::schedule::ScheduleCommand;
+use args::secret::SecretCommand;
+use args::service::ServiceCommand;
+use args::stack::StackCommand;
+use args::swarm::SwarmCommand;
+use args::system::SystemCommand;
+use args::volume::{SnapshotCommand, VolumeCommand};
+use args::{Cli, Command};
+
+mod args;
 mod commands;
+mod config;
+mod context;
+mod error;
+mod login;
 mod utils;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let matches = App::new("rocker")
-        .version("0.1.0")
-        .author("Rocker Team")
-        .about("Container management system written in Rust")
-        .setting(AppSettings::SubcommandRequiredElseHelp)
-        .subcommand(
-            SubCommand::with_name("run")
-                .about("Run a container")
-                .arg(
-                    Arg::with_name("detach")
-                        .short("d")
-                        .long("detach")
-                        .help("Run container in background"),
-                )
-                .arg(
-                    Arg::with_name("name")
-                        .long("name")
-                        .takes_value(true)
-                        .help("Assign a name to the container"),
-                )
-                .arg(
-                    Arg::with_name("volume")
-                        .short("v")
-                        .long("volume")
-                        .takes_value(true)
-                        .multiple(true)
-                        .help("Bind mount a volume"),
-                )
-                .arg(
-                    Arg::with_name("port")
-                        .short("p")
-                        .long("port")
-                        .takes_value(true)
-                        .multiple(true)
-                        .help("Publish a container's port to the host"),
-                )
-                .arg(
-                    Arg::with_name("image")
-                        .required(true)
-                        .help("The image to run"),
-                )
-                .arg(
-                    Arg::with_name("command")
-                        .multiple(true)
-                        .help("Command to run"),
-                ),
-        )
-        .subcommand(
-            SubCommand::with_name("ps")
-                .about("List containers")
-                .arg(
-                    Arg::with_name("all")
-                        .short("a")
-                        .long("all")
-                        .help("Show all containers (default shows just running)"),
-                ),
-        )
-        .subcommand(
-            SubCommand::with_name("images")
-                .about("List images")
-                .arg(
-                    Arg::with_name("all")
-                        .short("a")
-                        .long("all")
-                        .help("Show all images (default hides intermediate images)"),
-                ),
-        )
-        .subcommand(
-            SubCommand::with_name("build")
-                .about("Build an image from a Rockerfile")
-                .arg(
-                    Arg::with_name("tag")
-                        .short("t")
-                        .long("tag")
-                        .takes_value(true)
-                        .help("Name and optionally a tag in the 'name:tag' format"),
-                )
-                .arg(
-                    Arg::with_name("file")
-                        .short("f")
-                        .long("file")
-                        .takes_value(true)
-                        .help("Name of the Rockerfile (Default is 'Rockerfile')"),
-                )
-                .arg(
-                    Arg::with_name("path")
-                        .default_value(".")
-                        .help("Path to the build context"),
-                ),
-        )
-        .subcommand(
-            SubCommand::with_name("compose")
-                .about("Define and run multi-container applications")
-                .subcommand(
-                    SubCommand::with_name("up")
-                        .about("Create and start containers")
-                        .arg(
-                            Arg::with_name("detach")
-                                .short("d")
-                                .long("detach")
-                                .help("Detached mode: Run containers in the background"),
-                        )
-                        .arg(
-                            Arg::with_name("file")
-                                .short("f")
-                                .long("file")
-                                .takes_value(true)
-                                .help("Specify an alternate compose file"),
-                        ),
-                )
-                .subcommand(
-                    SubCommand::with_name("down")
-                        .about("Stop and remove containers, networks, images, and volumes")
-                        .arg(
-                            Arg::with_name("volumes")
-                                .long("volumes")
-                                .help("Remove named volumes declared in the volumes section"),
-                        ),
-                ),
-        )
-        .get_matches();
+fn main() {
+    // compose などの進捗のログは出力を汚さないよう標準エラー出力に書く
+    tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+    // config.json のエイリアスと既定の --format を展開してから解析する
+    let config = ClientConfig::load().unwrap_or_else(|e| std::process::exit(error::present(e.as_ref())));
+    let cli = Cli::parse_from(config::expand_args(std::env::args_os().collect(), &config));
+    rocker_client::set_quiet(cli.quiet);
+    rocker_client::set_no_color(cli.no_color);
+
+    // エラーは種類に応じたメッセージと終了コードにする
+    if let Err(e) = run(cli) {
+        std::process::exit(error::present(e.as_ref()));
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+
+    // コンテキストの管理とログアウト以外のコマンドは選んだデーモンに接続する
+    if !matches!(cli.command, Command::Context(_) | Command::Logout(_)) {
+        context::apply(cli.host.as_deref(), cli.context.as_deref())?;
+    }
 
-    match matches.subcommand() {
-        ("run", Some(run_matches)) => {
-            commands::run::execute(run_matches)?;
-        }
-        ("ps", Some(ps_matches)) => {
-            commands::ps::execute(ps_matches)?;
-        }
-        ("images", Some(images_matches)) => {
-            commands::images::execute(images_matches)?;
-        }
-        ("build", Some(build_matches)) => {
-            commands::build::execute(build_matches)?;
-        }
-        ("compose", Some(compose_matches)) => {
-            match compose_matches.subcommand() {
-                ("up", Some(up_matches)) => {
-                    commands::compose::up::execute(up_matches)?;
-                }
-                ("down", Some(down_matches)) => {
-                    commands::compose::down::execute(down_matches)?;
-                }
-                _ => unreachable!(),
-            }
-        }
-        _ => unreachable!(),
+    match cli.command {
+        Command::Context(command) => match command {
+            ContextCommand::Create(args) => context::create(&args)?,
+            ContextCommand::Use(args) => context::use_context(&args)?,
+            ContextCommand::Ls(args) => context::ls(&args)?,
+            ContextCommand::Inspect(args) => context::inspect(&args)?,
+            ContextCommand::Rm(args) => context::rm(&args)?,
+        },
+        Command::Login(args) => login::login(&args)?,
+        Command::Logout(args) => login::logout(&args)?,
+        Command::Run(args) => commands::run::execute(&args)?,
+        Command::Create(args) => commands::create::execute(&args)?,
+        Command::Start(args) => commands::start::execute(&args)?,
+        Command::Exec(args) => commands::exec::execute(&args)?,
+        Command::Logs(args) => commands::logs::execute(&args)?,
+        Command::Stop(args) => commands::stop::execute(&args)?,
+        Command::Rm(args) => commands::rm::execute(&args)?,
+        Command::Ps(args) => commands::ps::execute(&args)?,
+        Command::Inspect(args) => commands::inspect::execute(&args)?,
+        Command::Stats(args) => commands::stats::execute(&args)?,
+        Command::Images(args) => commands::images::execute(&args)?,
+        Command::Image(command) => match command {
+            ImageCommand::Diff(args) => commands::image::diff::execute(&args)?,
+            ImageCommand::Sbom(args) => commands::image::sbom::execute(&args)?,
+            ImageCommand::Scan(args) => commands::image::scan::execute(&args)?,
+            ImageCommand::Squash(args) => commands::image::squash::execute(&args)?,
+        },
+        Command::Export(args) => commands::export::execute(&args)?,
+        Command::Import(args) => commands::import::execute(&args)?,
+        Command::Network(command) => match command {
+            NetworkCommand::Create(args) => commands::network::create::execute(&args)?,
+            NetworkCommand::Ls(args) => commands::network::ls::execute(&args)?,
+            NetworkCommand::Rm(args) => commands::network::rm::execute(&args)?,
+            NetworkCommand::Inspect(args) => commands::network::inspect::execute(&args)?,
+            NetworkCommand::Prune(args) => commands::network::prune::execute(&args)?,
+            NetworkCommand::Connect(args) => commands::network::connect::execute(&args)?,
+            NetworkCommand::Disconnect(args) => commands::network::disconnect::execute(&args)?,
+        },
+        Command::Volume(command) => match command {
+            VolumeCommand::Create(args) => commands::volume::create::execute(&args)?,
+            VolumeCommand::Ls(args) => commands::volume::ls::execute(&args)?,
+            VolumeCommand::Rm(args) => commands::volume::rm::execute(&args)?,
+            VolumeCommand::Inspect(args) => commands::volume::inspect::execute(&args)?,
+            VolumeCommand::Prune(args) => commands::volume::prune::execute(&args)?,
+            VolumeCommand::Clone(args) => commands::volume::clone::execute(&args)?,
+            VolumeCommand::Snapshot(command) => match command {
+                SnapshotCommand::Create(args) => commands::volume::snapshot::create(&args)?,
+                SnapshotCommand::Ls(args) => commands::volume::snapshot::ls(&args)?,
+                SnapshotCommand::Restore(args) => commands::volume::snapshot::restore(&args)?,
+                SnapshotCommand::Rm(args) => commands::volume::snapshot::rm(&args)?,
+            },
+        },
+        Command::Secret(command) => match command {
+            SecretCommand::Create(args) => commands::secret::create::execute(&args)?,
+            SecretCommand::Ls(args) => commands::secret::ls::execute(&args)?,
+            SecretCommand::Inspect(args) => commands::secret::inspect::execute(&args)?,
+            SecretCommand::Rm(args) => commands::secret::rm::execute(&args)?,
+        },
+        Command::Schedule(command) => match command {
+            ScheduleCommand::Ls(args) => commands::schedule::ls::execute(&args)?,
+            ScheduleCommand::Inspect(args) => commands::schedule::inspect::execute(&args)?,
+            ScheduleCommand::History(args) => commands::schedule::history::execute(&args)?,
+            ScheduleCommand::Rm(args) => commands::schedule::rm::execute(&args)?,
+        },
+        Command::System(command) => match command {
+            SystemCommand::Df(args) => commands::system::df::execute(&args)?,
+            SystemCommand::Info => commands::system::info::execute()?,
+        },
+        Command::Swarm(command) => match command {
+            SwarmCommand::Init(args) => commands::swarm::init::execute(&args)?,
+            SwarmCommand::Join(args) => commands::swarm::join::execute(&args)?,
+            SwarmCommand::Leave(args) => commands::swarm::leave::execute(&args)?,
+            SwarmCommand::JoinToken(args) => commands::swarm::join_token::execute(&args)?,
+            SwarmCommand::Inspect => commands::swarm::inspect::execute()?,
+        },
+        Command::Service(command) => match command {
+            ServiceCommand::Create(args) => commands::service::create::execute(&args)?,
+            ServiceCommand::Ls(args) => commands::service::ls::execute(&args)?,
+            ServiceCommand::Inspect(args) => commands::service::inspect::execute(&args)?,
+            ServiceCommand::Ps(args) => commands::service::ps::execute(&args)?,
+            ServiceCommand::Scale(args) => commands::service::scale::execute(&args)?,
+            ServiceCommand::Rm(args) => commands::service::rm::execute(&args)?,
+        },
+        Command::Node(command) => match command {
+            NodeCommand::Ls(args) => commands::node::ls::execute(&args)?,
+            NodeCommand::Inspect(args) => commands::node::inspect::execute(&args)?,
+            NodeCommand::Drain(args) => commands::node::drain::execute(&args)?,
+            NodeCommand::Update(args) => commands::node::update::execute(&args)?,
+            NodeCommand::Rm(args) => commands::node::rm::execute(&args)?,
+        },
+        Command::Stack(command) => match command {
+            StackCommand::Deploy(args) => commands::stack::deploy::execute(&args)?,
+            StackCommand::Ls => commands::stack::ls::execute()?,
+            StackCommand::Ps(args) => commands::stack::ps::execute(&args)?,
+            StackCommand::Rm(args) => commands::stack::rm::execute(&args)?,
+        },
+        Command::Build(args) => commands::build::execute(&args)?,
+        Command::Builder(command) => match command {
+            BuilderCommand::Ls(args) => commands::builder::ls::execute(&args)?,
+            BuilderCommand::Du(args) => commands::builder::du::execute(&args)?,
+            BuilderCommand::Prune(args) => commands::builder::prune::execute(&args)?,
+        },
+        Command::Compose(command) => match command {
+            ComposeCommand::Up(args) => commands::compose::up::execute(&args)?,
+            ComposeCommand::Down(args) => commands::compose::down::execute(&args)?,
+            ComposeCommand::Logs(args) => commands::compose::logs::execute(&args)?,
+            ComposeCommand::Ps(args) => commands::compose::ps::execute(&args)?,
+            ComposeCommand::Top(args) => commands::compose::top::execute(&args)?,
+            ComposeCommand::Stats(args) => commands::compose::stats::execute(&args)?,
+            ComposeCommand::Exec(args) => commands::compose::exec::execute(&args)?,
+            ComposeCommand::Run(args) => commands::compose::run::execute(&args)?,
+            ComposeCommand::Build(args) => commands::compose::build::execute(&args)?,
+            ComposeCommand::Pull(args) => commands::compose::pull::execute(&args)?,
+            ComposeCommand::Push(args) => commands::compose::push::execute(&args)?,
+            ComposeCommand::Scale(args) => commands::compose::scale::execute(&args)?,
+            ComposeCommand::Config(args) => commands::compose::config::execute(&args)?,
+            ComposeCommand::Watch(args) => commands::compose::watch::execute(&args)?,
+            ComposeCommand::Convert(args) => commands::compose::convert::execute(&args)?,
+            ComposeCommand::Events(args) => commands::compose::events::execute(&args)?,
+            ComposeCommand::Port(args) => commands::compose::port::execute(&args)?,
+            ComposeCommand::Start(args) => commands::compose::start::execute(&args)?,
+            ComposeCommand::Stop(args) => commands::compose::stop::execute(&args)?,
+            ComposeCommand::Restart(args) => commands::compose::restart::execute(&args)?,
+            ComposeCommand::Pause(args) => commands::compose::pause::execute(&args)?,
+            ComposeCommand::Unpause(args) => commands::compose::unpause::execute(&args)?,
+        },
     }
 
     Ok(())
-} 
+}
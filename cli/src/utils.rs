@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use rocker_core::format_duration;
+use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::io::{BufRead, Read, Write};
+
+// コマンドの非同期の処理をランタイムを作って最後まで実行する
+pub fn block_on<T>(future: impl Future<Output = Result<T, Box<dyn Error>>>) -> Result<T, Box<dyn Error>> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(future)
+}
+
+// 列の幅を揃えて表示する（最後の列は詰めない）
+pub fn print_table<R: AsRef<[String]>>(titles: &[&str], rows: &[R]) {
+    let mut widths: Vec<usize> = titles.iter().map(|title| title.len()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row.as_ref()) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let line = |values: Vec<&str>| {
+        let last = values.len().saturating_sub(1);
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                if index == last {
+                    value.to_string()
+                } else {
+                    format!("{:<width$}", value, width = widths[index])
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("   ")
+    };
+    println!("{}", line(titles.to_vec()));
+    for row in rows {
+        println!("{}", line(row.as_ref().iter().map(String::as_str).collect()));
+    }
+}
+
+// 一覧に表示する ID（先頭の 12 文字、イメージの ID は sha256: を除く）
+pub fn short_id(id: &str) -> String {
+    id.trim_start_matches("sha256:").chars().take(12).collect()
+}
+
+// "5m3s ago" の形の経過時間
+pub fn time_ago(time: DateTime<Utc>) -> String {
+    let seconds = (Utc::now() - time).num_seconds().max(0) as u64;
+    format!("{} ago", format_duration(seconds))
+}
+
+// --filter KEY=VALUE をクライアントに渡す組にする（KEY!=VALUE は "KEY!" のキーのまま渡し、デーモンが除外に使う）
+pub fn parse_filters(filters: &[String]) -> Result<Vec<(&str, &str)>, Box<dyn Error>> {
+    filters
+        .iter()
+        .map(|filter| {
+            filter
+                .split_once('=')
+                .filter(|(key, _)| !key.is_empty() && *key != "!")
+                .ok_or_else(|| format!("Invalid filter (expected KEY=VALUE): {}", filter).into())
+        })
+        .collect()
+}
+
+// --label や --opt の KEY=VALUE（= の無い KEY は空の値にする）
+pub fn parse_key_values(values: &[String]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    values
+        .iter()
+        .map(|value| {
+            let (key, value) = value.split_once('=').unwrap_or((value, ""));
+            if key.is_empty() {
+                return Err(format!("Invalid key-value pair (expected KEY=VALUE): ={}", value).into());
+            }
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+// --format のテンプレートの {{.Field}} を値に置き換える（\t と \n はタブと改行にする）
+pub fn render_template(template: &str, fields: &[(&str, String)]) -> Result<String, Box<dyn Error>> {
+    let template = template.replace("\\t", "\t").replace("\\n", "\n");
+    let mut output = String::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| format!("Unterminated {{{{ in --format: {}", template))?;
+        let field = rest[start + 2..start + end].trim();
+        let name = field
+            .strip_prefix('.')
+            .ok_or_else(|| format!("Invalid field in --format (expected {{{{.Field}}}}): {}", field))?;
+        let (_, value) = fields
+            .iter()
+            .find(|(key, _)| *key == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = fields.iter().map(|(key, _)| *key).collect();
+                format!("Unknown field in --format: {} (expected one of {})", name, names.join(", "))
+            })?;
+        output.push_str(value);
+        rest = &rest[start + end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+// prune などの前に確認する（y か yes で続ける）
+pub fn confirm(warning: &str) -> Result<bool, Box<dyn Error>> {
+    eprint!("WARNING! {}\nAre you sure you want to continue? [y/N] ", warning);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
+
+// ファイルの内容（"-" は標準入力）
+pub fn read_input(file: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    if file == "-" {
+        let mut data = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut data)?;
+        return Ok(data);
+    }
+    Ok(std::fs::read(file).map_err(|e| format!("Failed to read {}: {}", file, e))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_filters() {
+        let filters = vec!["label=env=staging".to_string(), "label!=canary".to_string(), "dangling=true".to_string()];
+        assert_eq!(
+            parse_filters(&filters).unwrap(),
+            vec![("label", "env=staging"), ("label!", "canary"), ("dangling", "true")]
+        );
+        for filter in ["dangling", "=true", "!=x"] {
+            assert!(parse_filters(&[filter.to_string()]).is_err(), "{}", filter);
+        }
+    }
+
+    #[test]
+    fn parses_key_values() {
+        let values = parse_key_values(&["team=web".to_string(), "canary".to_string(), "a=b=c".to_string()]).unwrap();
+        assert_eq!(values["team"], "web");
+        assert_eq!(values["canary"], "");
+        assert_eq!(values["a"], "b=c");
+        assert!(parse_key_values(&["=x".to_string()]).is_err());
+    }
+
+    #[test]
+    fn renders_templates() {
+        let fields = [("Name", "data".to_string()), ("Size", "1.50 MB".to_string())];
+        assert_eq!(render_template("{{.Name}}\\t{{ .Size }}", &fields).unwrap(), "data\t1.50 MB");
+        assert_eq!(render_template("name: {{.Name}}", &fields).unwrap(), "name: data");
+        let error = render_template("{{.Driver}}", &fields).unwrap_err().to_string();
+        assert!(error.contains("Driver") && error.contains("Name, Size"), "{}", error);
+        assert!(render_template("{{.Name", &fields).is_err());
+        assert!(render_template("{{Name}}", &fields).is_err());
+    }
+
+    #[test]
+    fn shortens_ids() {
+        assert_eq!(short_id("sha256:0123456789abcdef"), "0123456789ab");
+        assert_eq!(short_id("3f2c1a90-1b2c-4d5e-8f90-1234567890ab"), "3f2c1a90-1b2");
+    }
+}
@@ -0,0 +1,198 @@
+use futures::stream::{BoxStream, StreamExt};
+use nix::sys::termios::{self, InputFlags, LocalFlags, SetArg, SpecialCharacterIndices, Termios};
+use rocker_core::{ContainerExit, LogRecord, LogStream};
+use std::error::Error;
+use std::io::{IsTerminal, Read, Write};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+use crate::client::{encode, is_not_found, Client};
+use crate::containers::ContainerLogsOptions;
+use crate::stream::JsonStream;
+
+/// Key sequence detaching from a foreground container when `--detach-keys` is not given
+pub const DEFAULT_DETACH_KEYS: &str = "ctrl-p,ctrl-q";
+
+/// How [`Client::run_attached`] returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attached {
+    /// The container exited with this code
+    Exited(i32),
+    /// The detach keys were typed, the container keeps running
+    Detached,
+}
+
+/// Parse a detach key sequence: comma-separated keys, each a single character or `ctrl-<key>` with a
+/// key of `a`-`z`, `@`, `[`, `\`, `]`, `^` or `_` (e.g. `ctrl-p,ctrl-q`)
+pub fn parse_detach_keys(keys: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid detach keys (expected e.g. ctrl-p,ctrl-q): {}", keys);
+    keys.split(',')
+        .map(|key| match key.strip_prefix("ctrl-") {
+            Some(key) => match key.as_bytes() {
+                [c @ b'a'..=b'z'] => Ok(c - b'a' + 1),
+                [c @ (b'@' | b'[' | b'\\' | b']' | b'^' | b'_')] => Ok(c - b'@'),
+                _ => Err(invalid()),
+            },
+            None => match key.as_bytes() {
+                [c] if c.is_ascii() => Ok(*c),
+                _ => Err(invalid()),
+            },
+        })
+        .collect()
+}
+
+impl Client {
+    /// Send a signal (a name such as `SIGINT` or a number) to the main process of a running container,
+    /// `SIGKILL` if None
+    pub async fn kill_container(&self, container: &str, signal: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let mut path = format!("/containers/{}/kill", encode(container));
+        if let Some(signal) = signal {
+            path.push_str(&format!("?signal={}", encode(signal)));
+        }
+        self.post_empty(&path).await
+    }
+
+    /// Wait for the next exit of a container and return its exit code (at once if it is not running)
+    pub async fn wait_container(&self, container: &str) -> Result<i32, Box<dyn Error>> {
+        let mut exit = self.post_wait(container).await?;
+        next_exit(&mut exit, container).await
+    }
+
+    /// Start a created container in the foreground: print its output until it exits and return its
+    /// exit code
+    ///
+    /// SIGINT and SIGTERM received meanwhile are sent to the container, so that Ctrl-C stops the
+    /// container rather than only the client. With `detach_keys` (see [`parse_detach_keys`]) and a
+    /// terminal on stdin, typing the sequence returns [`Attached::Detached`] and leaves the container
+    /// running. The exit code is also returned for containers the daemon removes when they exit (`--rm`).
+    pub async fn run_attached(&self, container: &str, detach_keys: Option<&[u8]>) -> Result<Attached, Box<dyn Error>> {
+        // Wait before starting, so that the exit of a container ending at once is not missed
+        let mut exit = self.post_wait(container).await?;
+        self.start_container(container).await?;
+
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        let mut terminate = signal(SignalKind::terminate())?;
+        let detach_keys = detach_keys.filter(|keys| !keys.is_empty() && std::io::stdin().is_terminal());
+        let _terminal = detach_keys.map(|_| TerminalMode::keys()).transpose()?;
+        let mut detached = detach_keys.map(|keys| watch_keys(keys.to_vec()));
+
+        let options = ContainerLogsOptions {
+            follow: true,
+            ..ContainerLogsOptions::default()
+        };
+        let mut output: Option<BoxStream<'_, Result<LogRecord, Box<dyn Error>>>> =
+            match self.container_logs(container, &options).await {
+                Ok(records) => Some(records.into_stream().boxed()),
+                // Containers whose output is not kept (--log-driver none) only wait for the exit
+                Err(e) if is_not_found(e.as_ref()) => None,
+                Err(e) => return Err(e),
+            };
+
+        loop {
+            tokio::select! {
+                // The exit code is returned once all of the output is printed
+                exit_code = next_exit(&mut exit, container), if output.is_none() => {
+                    return Ok(Attached::Exited(exit_code?));
+                }
+                record = next_record(&mut output), if output.is_some() => match record {
+                    Some(record) => print_record(&record?)?,
+                    None => output = None,
+                },
+                Some(()) = interrupt.recv() => self.forward_signal(container, "SIGINT").await,
+                Some(()) = terminate.recv() => self.forward_signal(container, "SIGTERM").await,
+                Some(()) = next_key_match(&mut detached) => return Ok(Attached::Detached),
+            }
+        }
+    }
+
+    async fn post_wait(&self, container: &str) -> Result<JsonStream<ContainerExit>, Box<dyn Error>> {
+        Ok(self.post_lines(&format!("/containers/{}/wait", encode(container))).await?.into())
+    }
+
+    // Failures are ignored: a container that just exited takes no signals, and its exit code comes from the wait
+    async fn forward_signal(&self, container: &str, signal: &str) {
+        let _ = self.kill_container(container, Some(signal)).await;
+    }
+}
+
+async fn next_exit(exit: &mut JsonStream<ContainerExit>, container: &str) -> Result<i32, Box<dyn Error>> {
+    match exit.next().await? {
+        Some(exit) => Ok(exit.exit_code),
+        None => Err(format!("Container {} was removed before it exited", container).into()),
+    }
+}
+
+async fn next_record(
+    output: &mut Option<BoxStream<'_, Result<LogRecord, Box<dyn Error>>>>,
+) -> Option<Result<LogRecord, Box<dyn Error>>> {
+    match output {
+        Some(output) => output.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn next_key_match(detached: &mut Option<mpsc::Receiver<()>>) -> Option<()> {
+    match detached {
+        Some(detached) => detached.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+// Print a line of output to the stream it was written to
+fn print_record(record: &LogRecord) -> std::io::Result<()> {
+    match record.stream {
+        LogStream::Stdout => writeln!(std::io::stdout(), "{}", record.line),
+        LogStream::Stderr => writeln!(std::io::stderr(), "{}", record.line),
+    }
+}
+
+// Read stdin and notify once the key sequence is typed (on a thread of its own, as the read cannot be stopped)
+fn watch_keys(keys: Vec<u8>) -> mpsc::Receiver<()> {
+    let (tx, rx) = mpsc::channel(1);
+    std::thread::spawn(move || {
+        let mut matched = 0;
+        for byte in std::io::stdin().lock().bytes() {
+            let Ok(byte) = byte else {
+                return;
+            };
+            matched = if byte == keys[matched] {
+                matched + 1
+            } else if byte == keys[0] {
+                1
+            } else {
+                0
+            };
+            if matched == keys.len() {
+                let _ = tx.blocking_send(());
+                return;
+            }
+        }
+    });
+    rx
+}
+
+// Terminal mode reading keys one at a time: no line editing, echo or flow control (which takes Ctrl-Q)
+//
+// Ctrl-C still raises SIGINT, which is sent to the container. The original mode is restored on drop.
+struct TerminalMode {
+    original: Termios,
+}
+
+impl TerminalMode {
+    fn keys() -> nix::Result<Self> {
+        let original = termios::tcgetattr(std::io::stdin())?;
+        let mut mode = original.clone();
+        mode.local_flags.remove(LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::IEXTEN);
+        mode.input_flags.remove(InputFlags::IXON);
+        mode.control_chars[SpecialCharacterIndices::VMIN as usize] = 1;
+        mode.control_chars[SpecialCharacterIndices::VTIME as usize] = 0;
+        termios::tcsetattr(std::io::stdin(), SetArg::TCSANOW, &mode)?;
+        Ok(TerminalMode { original })
+    }
+}
+
+impl Drop for TerminalMode {
+    fn drop(&mut self) {
+        let _ = termios::tcsetattr(std::io::stdin(), SetArg::TCSANOW, &self.original);
+    }
+}
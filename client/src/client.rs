@@ -0,0 +1,364 @@
+use hyper::client::conn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UnixStream};
+
+use crate::config::{ClientConfig, ProxyConfig};
+use crate::credentials::CredentialStore;
+use crate::stream::Lines;
+
+/// Unix socket the daemon listens on by default
+pub const DEFAULT_SOCKET: &str = "/var/run/rocker.sock";
+
+/// Daemon to connect to (`unix:///path` or `tcp://host:port`)
+pub const HOST_ENV: &str = "ROCKER_HOST";
+/// Directory holding the ca.pem, cert.pem and key.pem used for TLS
+pub const CERT_PATH_ENV: &str = "ROCKER_CERT_PATH";
+/// Verify the daemon's certificate against ca.pem when set to 1
+pub const TLS_VERIFY_ENV: &str = "ROCKER_TLS_VERIFY";
+
+/// Address the daemon API listens on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Endpoint {
+    Unix(PathBuf),
+    /// host:port
+    Tcp(String),
+}
+
+impl Endpoint {
+    /// Parse `unix:///path` or `tcp://host:port` (an address without a scheme is TCP)
+    pub fn parse(host: &str) -> Result<Self, String> {
+        if let Some(path) = host.strip_prefix("unix://") {
+            if !path.starts_with('/') {
+                return Err(format!("Invalid unix socket path: {}", host));
+            }
+            return Ok(Endpoint::Unix(PathBuf::from(path)));
+        }
+        let address = host.strip_prefix("tcp://").unwrap_or(host).trim_end_matches('/');
+        match address.rsplit_once(':') {
+            Some((name, port)) if !name.is_empty() && port.parse::<u16>().is_ok() => Ok(Endpoint::Tcp(address.to_string())),
+            _ => Err(format!("Invalid host (expected unix:///path or tcp://host:port): {}", host)),
+        }
+    }
+}
+
+impl std::fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Unix(path) => write!(f, "unix://{}", path.display()),
+            Endpoint::Tcp(address) => write!(f, "tcp://{}", address),
+        }
+    }
+}
+
+/// TLS settings for TCP connections
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// Directory holding ca.pem, cert.pem and key.pem (missing files are skipped)
+    pub cert_path: Option<PathBuf>,
+    /// Verify the daemon's certificate
+    pub verify: bool,
+}
+
+impl TlsOptions {
+    /// TLS settings from ROCKER_CERT_PATH and ROCKER_TLS_VERIFY (None if neither is set)
+    pub fn from_env() -> Option<Self> {
+        let cert_path = std::env::var_os(CERT_PATH_ENV).filter(|path| !path.is_empty()).map(PathBuf::from);
+        let verify = std::env::var(TLS_VERIFY_ENV).is_ok_and(|value| !value.is_empty() && value != "0");
+        if cert_path.is_none() && !verify {
+            return None;
+        }
+        Some(TlsOptions { cert_path, verify })
+    }
+
+    async fn connect(&self, address: &str, stream: TcpStream) -> Result<tokio_native_tls::TlsStream<TcpStream>, Box<dyn Error>> {
+        let mut builder = native_tls::TlsConnector::builder();
+        if let Some(cert_path) = &self.cert_path {
+            if let Some(ca) = read_pem(cert_path, "ca.pem")? {
+                builder.add_root_certificate(native_tls::Certificate::from_pem(&ca)?);
+            }
+            if let (Some(cert), Some(key)) = (read_pem(cert_path, "cert.pem")?, read_pem(cert_path, "key.pem")?) {
+                builder.identity(native_tls::Identity::from_pkcs8(&cert, &key)?);
+            }
+        }
+        if !self.verify {
+            builder.danger_accept_invalid_certs(true);
+        }
+        let connector = tokio_native_tls::TlsConnector::from(builder.build()?);
+        let domain = address.rsplit_once(':').map_or(address, |(name, _)| name);
+        let domain = domain.trim_start_matches('[').trim_end_matches(']');
+        Ok(connector.connect(domain, stream).await?)
+    }
+}
+
+fn read_pem(dir: &Path, name: &str) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let path = dir.join(name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    std::fs::read(&path)
+        .map(Some)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e).into())
+}
+
+/// Error returned by the daemon (status code and the message of its `{"message": ...}` body)
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct ClientError {
+    pub status: StatusCode,
+    pub message: String,
+    /// Stable identifier of the error (such as `container_not_found`), if the daemon sent it
+    pub code: Option<String>,
+    /// Kind of object the error is about (`container`, `image`, `network` or `volume`), if the daemon sent it
+    pub kind: Option<String>,
+}
+
+/// The daemon could not be reached
+#[derive(Debug, thiserror::Error)]
+#[error("Cannot connect to the rocker daemon at {endpoint}: {source}")]
+pub struct ConnectError {
+    pub endpoint: String,
+    #[source]
+    pub source: std::io::Error,
+}
+
+/// Whether the error is a 404 returned by the daemon
+pub fn is_not_found(e: &(dyn Error + 'static)) -> bool {
+    e.downcast_ref::<ClientError>()
+        .is_some_and(|e| e.status == StatusCode::NOT_FOUND)
+}
+
+/// Client for the daemon's HTTP API (connects to the daemon for each request)
+#[derive(Debug, Clone)]
+pub struct Client {
+    // Endpoint in the ROCKER_HOST format, parsed when connecting
+    host: String,
+    tls: Option<TlsOptions>,
+    // Where to look up registry credentials for pulls, pushes and builds (None to use none)
+    credentials: Option<CredentialStore>,
+    // Proxies passed to builds and created containers (None to pass none)
+    proxy: Option<ProxyConfig>,
+}
+
+impl Client {
+    /// Client for the daemon in ROCKER_HOST, or the default Unix socket if it is not set
+    ///
+    /// Pulls, pushes and builds use the registry credentials saved by `rocker login`, and builds and
+    /// created containers get the proxies configured for the daemon in `config.json`.
+    pub fn new() -> Self {
+        let host = std::env::var(HOST_ENV)
+            .ok()
+            .filter(|host| !host.is_empty())
+            .unwrap_or_else(|| format!("unix://{}", DEFAULT_SOCKET));
+        let proxy = ClientConfig::load().ok().and_then(|config| config.proxy_for(&host).cloned());
+        Client::with_host(&host, TlsOptions::from_env())
+            .with_credentials(CredentialStore::open().ok())
+            .with_proxy(proxy)
+    }
+
+    /// Client for the daemon at `host` (`unix:///path` or `tcp://host:port`), without registry credentials or proxies
+    pub fn with_host(host: &str, tls: Option<TlsOptions>) -> Self {
+        Client {
+            host: host.to_string(),
+            tls,
+            credentials: None,
+            proxy: None,
+        }
+    }
+
+    /// Use the registry credentials of `credentials` for pulls, pushes and builds (None to pull and push anonymously)
+    pub fn with_credentials(mut self, credentials: Option<CredentialStore>) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    pub(crate) fn credentials(&self) -> Option<&CredentialStore> {
+        self.credentials.as_ref()
+    }
+
+    /// Pass the proxies of `proxy` to builds (as build arguments) and created containers (as environment
+    /// variables), unless they set their own
+    pub fn with_proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub(crate) fn proxy(&self) -> Option<&ProxyConfig> {
+        self.proxy.as_ref()
+    }
+
+    /// GET a path and decode the JSON response
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, Box<dyn Error>> {
+        let response = self.send(Method::GET, path, None).await?;
+        read_json(response).await
+    }
+
+    /// POST a JSON body and decode the JSON response
+    pub async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, Box<dyn Error>> {
+        let response = self.send(Method::POST, path, Some(serde_json::to_vec(body)?)).await?;
+        read_json(response).await
+    }
+
+    /// POST a JSON body, ignoring the response (for endpoints that return 204 No Content)
+    pub async fn post_ignore_response<B: Serialize>(&self, path: &str, body: &B) -> Result<(), Box<dyn Error>> {
+        self.send(Method::POST, path, Some(serde_json::to_vec(body)?)).await?;
+        Ok(())
+    }
+
+    /// POST without a body, ignoring the response (start, stop, ...)
+    pub async fn post_empty(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.send(Method::POST, path, None).await?;
+        Ok(())
+    }
+
+    /// DELETE a path, ignoring the response
+    pub async fn delete(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        self.send(Method::DELETE, path, None).await?;
+        Ok(())
+    }
+
+    /// GET an endpoint that keeps returning one JSON object per line (logs with follow, events, ...)
+    pub async fn get_lines(&self, path: &str) -> Result<Lines, Box<dyn Error>> {
+        let response = self.send(Method::GET, path, None).await?;
+        Ok(Lines::new(response.into_body()))
+    }
+
+    /// POST without a body to an endpoint that streams its output (exec start, pull, ...)
+    pub async fn post_lines(&self, path: &str) -> Result<Lines, Box<dyn Error>> {
+        let response = self.send(Method::POST, path, None).await?;
+        Ok(Lines::new(response.into_body()))
+    }
+
+    /// POST a non-JSON body to an endpoint that streams its output (a build context tar, ...)
+    pub async fn post_body_lines(&self, path: &str, body: Vec<u8>, content_type: &str) -> Result<Lines, Box<dyn Error>> {
+        let response = self.send_body(Method::POST, path, Some((body, content_type)), &[]).await?;
+        Ok(Lines::new(response.into_body()))
+    }
+
+    /// PUT a non-JSON body (a tar of files to copy into a container, ...)
+    pub async fn put_body(&self, path: &str, body: Vec<u8>, content_type: &str) -> Result<(), Box<dyn Error>> {
+        self.send_body(Method::PUT, path, Some((body, content_type)), &[]).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn send(&self, method: Method, path: &str, body: Option<Vec<u8>>) -> Result<Response<Body>, Box<dyn Error>> {
+        self.send_body(method, path, body.map(|body| (body, "application/json")), &[]).await
+    }
+
+    /// POST a body that is sent while the response streams (the stdin of an exec, ...)
+    pub async fn post_stream_lines(&self, path: &str, body: Body, content_type: &str) -> Result<Lines, Box<dyn Error>> {
+        let response = self.send_stream(Method::POST, path, Some((body, content_type)), &[]).await?;
+        Ok(Lines::new(response.into_body()))
+    }
+
+    pub(crate) async fn send_body(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<(Vec<u8>, &str)>,
+        headers: &[(&str, String)],
+    ) -> Result<Response<Body>, Box<dyn Error>> {
+        let body = body.map(|(body, content_type)| (Body::from(body), content_type));
+        self.send_stream(method, path, body, headers).await
+    }
+
+    async fn send_stream(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<(Body, &str)>,
+        headers: &[(&str, String)],
+    ) -> Result<Response<Body>, Box<dyn Error>> {
+        let mut request = Request::builder().method(method).uri(path).header("Host", "rocker");
+        if let Some((_, content_type)) = &body {
+            request = request.header("Content-Type", *content_type);
+        }
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let request = request.body(body.map(|(body, _)| body).unwrap_or_else(Body::empty))?;
+
+        let endpoint = Endpoint::parse(&self.host)?;
+        let unreachable = |source| ConnectError {
+            endpoint: endpoint.to_string(),
+            source,
+        };
+        let response = match &endpoint {
+            Endpoint::Unix(path) => send_request(UnixStream::connect(path).await.map_err(unreachable)?, request).await?,
+            Endpoint::Tcp(address) => {
+                let stream = TcpStream::connect(address).await.map_err(unreachable)?;
+                match &self.tls {
+                    Some(tls) => send_request(tls.connect(address, stream).await?, request).await?,
+                    None => send_request(stream, request).await?,
+                }
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            let value = serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default();
+            let message = value["message"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| String::from_utf8_lossy(&body).trim().to_string());
+            let code = value["code"].as_str().map(str::to_string);
+            let kind = value["kind"].as_str().map(str::to_string);
+            return Err(ClientError {
+                status,
+                message,
+                code,
+                kind,
+            }
+            .into());
+        }
+        Ok(response)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Send one request over a connected stream
+async fn send_request<S>(stream: S, request: Request<Body>) -> Result<Response<Body>, Box<dyn Error>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = conn::handshake(stream).await?;
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+    Ok(sender.send_request(request).await?)
+}
+
+pub(crate) async fn read_json<T: DeserializeOwned>(response: Response<Body>) -> Result<T, Box<dyn Error>> {
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Percent-encode a query string value
+pub fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// `filter=key=value` query parameters for the list, prune and events endpoints
+pub(crate) fn filter_query(filters: &[(&str, &str)]) -> String {
+    filters
+        .iter()
+        .map(|(key, value)| format!("filter={}", encode(&format!("{}={}", key, value))))
+        .collect::<Vec<_>>()
+        .join("&")
+}
@@ -0,0 +1,30 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Disables colored output when set to any non-empty value (<https://no-color.org>)
+pub const NO_COLOR_ENV: &str = "NO_COLOR";
+
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Disable colored output (the CLI's `--no-color`)
+pub fn set_no_color(no_color: bool) {
+    NO_COLOR.store(no_color, Ordering::SeqCst);
+}
+
+/// Whether output written to `stream` may be colored
+///
+/// Colors are used only on terminals, and neither with `--no-color` ([`set_no_color`]) nor with `NO_COLOR` set.
+pub fn use_color(stream: &impl IsTerminal) -> bool {
+    !NO_COLOR.load(Ordering::SeqCst)
+        && std::env::var_os(NO_COLOR_ENV).is_none_or(|value| value.is_empty())
+        && stream.is_terminal()
+}
+
+/// `text` in the SGR color `code` (e.g. "31" for red) if `color` is set, otherwise unchanged
+pub fn paint(color: bool, code: &str, text: &str) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
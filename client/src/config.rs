@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the client configuration, overriding `$XDG_CONFIG_HOME/rocker` and `~/.config/rocker`
+pub const CONFIG_DIR_ENV: &str = "ROCKER_CONFIG";
+/// Key of [`ClientConfig::proxies`] used for daemons without their own entry
+pub const DEFAULT_PROXY_KEY: &str = "default";
+
+/// Directory of the client configuration (`config.json` and the contexts)
+///
+/// `ROCKER_CONFIG` if set, otherwise `$XDG_CONFIG_HOME/rocker` or `~/.config/rocker`.
+pub fn config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV).filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("rocker"));
+    }
+    let home = std::env::var_os("HOME").ok_or("HOME is not set")?;
+    Ok(PathBuf::from(home).join(".config").join("rocker"))
+}
+
+/// The client configuration file, `config.json` in [`config_dir`]
+pub fn config_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(config_dir()?.join("config.json"))
+}
+
+/// Per-user defaults of the CLI, read from `config.json`
+///
+/// Every key is optional, and keys that are not known are ignored. The file is also written by
+/// `rocker context use` (`currentContext`) and `rocker login` (`auths`, see [`CredentialStore`](crate::CredentialStore)).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientConfig {
+    /// Registry `rocker login` and `rocker logout` use when none is given (`docker.io` if not set)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_registry: Option<String>,
+    /// Value of `--format` for commands run without it, by command (e.g. `"volume ls"`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub formats: HashMap<String, String>,
+    /// Proxies for builds and containers, by daemon host, with [`DEFAULT_PROXY_KEY`] for all other daemons
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub proxies: HashMap<String, ProxyConfig>,
+    /// Commands standing for a command with arguments (e.g. `"dev": ["run", "--rm", "-it"]`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub aliases: HashMap<String, Vec<String>>,
+    /// Context used when neither `--host`, `--context`, `ROCKER_HOST` nor `ROCKER_CONTEXT` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_context: Option<String>,
+    /// Credential helper for registries without an entry in `cred_helpers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creds_store: Option<String>,
+    /// Credential helper by registry
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cred_helpers: HashMap<String, String>,
+}
+
+/// Proxy settings, in the format of Docker's `proxies`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub https_proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ftp_proxy: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub all_proxy: Option<String>,
+    /// Comma-separated hosts and domains reached without a proxy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Environment variables for builds and containers, in both upper and lower case (e.g. `HTTP_PROXY` and `http_proxy`)
+    pub fn env(&self) -> Vec<(String, String)> {
+        let values = [
+            ("HTTP_PROXY", &self.http_proxy),
+            ("HTTPS_PROXY", &self.https_proxy),
+            ("FTP_PROXY", &self.ftp_proxy),
+            ("ALL_PROXY", &self.all_proxy),
+            ("NO_PROXY", &self.no_proxy),
+        ];
+        let mut env = Vec::new();
+        for (name, value) in values {
+            if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+                env.push((name.to_string(), value.clone()));
+                env.push((name.to_lowercase(), value.clone()));
+            }
+        }
+        env
+    }
+}
+
+/// Add the proxy variables of `proxy` to `env`, except those already set in either case
+pub(crate) fn add_proxy_env(env: &mut HashMap<String, String>, proxy: &ProxyConfig) {
+    let is_set = |env: &HashMap<String, String>, name: &str| {
+        env.contains_key(&name.to_uppercase()) || env.contains_key(&name.to_lowercase())
+    };
+    let missing: Vec<_> = proxy.env().into_iter().filter(|(name, _)| !is_set(env, name)).collect();
+    env.extend(missing);
+}
+
+impl ClientConfig {
+    /// Configuration in [`config_path`] (the defaults if the file does not exist)
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        ClientConfig::load_from(&config_path()?)
+    }
+
+    /// Configuration in another file (the defaults if it does not exist)
+    pub fn load_from(path: &Path) -> Result<Self, Box<dyn Error>> {
+        match std::fs::read(path) {
+            Ok(data) => Ok(serde_json::from_slice(&data).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ClientConfig::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e).into()),
+        }
+    }
+
+    /// Proxies to use with the daemon at `host` (its own entry, or else the default one)
+    pub fn proxy_for(&self, host: &str) -> Option<&ProxyConfig> {
+        self.proxies.get(host).or_else(|| self.proxies.get(DEFAULT_PROXY_KEY))
+    }
+}
@@ -0,0 +1,205 @@
+use hyper::body::HttpBody;
+use hyper::Method;
+use rocker_core::{Container, ContainerConfig, ContainerStats, ContainerTop, ImageDiff, LogRecord};
+use std::error::Error;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::client::{encode, filter_query, Client};
+use crate::config::add_proxy_env;
+use crate::stream::JsonStream;
+
+/// Options for [`Client::container_logs`]
+#[derive(Debug, Clone, Default)]
+pub struct ContainerLogsOptions {
+    /// Keep streaming the output until the container exits
+    pub follow: bool,
+    /// Number of lines from the end of the logs
+    pub tail: Option<usize>,
+    /// Only lines written at or after this time: RFC 3339, Unix seconds or a duration such as `10m`
+    pub since: Option<String>,
+    /// Only lines written up to this time, in the same formats as `since`
+    pub until: Option<String>,
+}
+
+impl ContainerLogsOptions {
+    fn query(&self) -> String {
+        let mut params = vec![format!("follow={}", if self.follow { 1 } else { 0 })];
+        if let Some(tail) = self.tail {
+            params.push(format!("tail={}", tail));
+        }
+        if let Some(since) = &self.since {
+            params.push(format!("since={}", encode(since)));
+        }
+        if let Some(until) = &self.until {
+            params.push(format!("until={}", encode(until)));
+        }
+        params.join("&")
+    }
+}
+
+impl Client {
+    /// List containers, only running and paused ones unless `all` is set
+    ///
+    /// Filters are `(key, value)` pairs with the keys `id`, `label`, `name` and `status`; filters with
+    /// the same key match any of their values.
+    pub async fn list_containers(&self, all: bool, filters: &[(&str, &str)]) -> Result<Vec<Container>, Box<dyn Error>> {
+        let mut query = format!("all={}", if all { 1 } else { 0 });
+        if !filters.is_empty() {
+            query.push('&');
+            query.push_str(&filter_query(filters));
+        }
+        self.get(&format!("/containers?{}", query)).await
+    }
+
+    /// [`Client::list_containers`] with `size_rw` and `size_root_fs` filled in
+    pub async fn list_containers_with_size(
+        &self,
+        all: bool,
+        filters: &[(&str, &str)],
+    ) -> Result<Vec<Container>, Box<dyn Error>> {
+        let mut query = format!("all={}&size=1", if all { 1 } else { 0 });
+        if !filters.is_empty() {
+            query.push('&');
+            query.push_str(&filter_query(filters));
+        }
+        self.get(&format!("/containers?{}", query)).await
+    }
+
+    /// Create a container (the daemon generates a name if `name` is None)
+    ///
+    /// The proxies of [`Client::with_proxy`] are added to the environment, except the variables it already sets.
+    pub async fn create_container(&self, name: Option<&str>, config: &ContainerConfig) -> Result<Container, Box<dyn Error>> {
+        let path = match name {
+            Some(name) => format!("/containers/create?name={}", encode(name)),
+            None => "/containers/create".to_string(),
+        };
+        self.post_create(&path, config).await
+    }
+
+    /// Create a container named `name`, removing the container of that name first if there is one
+    ///
+    /// A running container is stopped. If the new container cannot be created, the old one is kept.
+    pub async fn replace_container(&self, name: &str, config: &ContainerConfig) -> Result<Container, Box<dyn Error>> {
+        self.post_create(&format!("/containers/create?name={}&replace=1", encode(name)), config)
+            .await
+    }
+
+    async fn post_create(&self, path: &str, config: &ContainerConfig) -> Result<Container, Box<dyn Error>> {
+        if let Some(proxy) = self.proxy() {
+            let mut config = config.clone();
+            add_proxy_env(&mut config.env, proxy);
+            return self.post(path, &config).await;
+        }
+        self.post(path, config).await
+    }
+
+    /// Container by ID, ID prefix or name
+    pub async fn inspect_container(&self, container: &str) -> Result<Container, Box<dyn Error>> {
+        self.get(&format!("/containers/{}", encode(container))).await
+    }
+
+    /// [`Client::inspect_container`] with `size_rw` and `size_root_fs` filled in
+    pub async fn inspect_container_with_size(&self, container: &str) -> Result<Container, Box<dyn Error>> {
+        self.get(&format!("/containers/{}?size=1", encode(container))).await
+    }
+
+    pub async fn start_container(&self, container: &str) -> Result<(), Box<dyn Error>> {
+        self.post_empty(&format!("/containers/{}/start", encode(container))).await
+    }
+
+    /// Stop a container, killing it after `timeout` seconds (the container's stop timeout if None)
+    pub async fn stop_container(&self, container: &str, timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
+        let mut path = format!("/containers/{}/stop", encode(container));
+        if let Some(timeout) = timeout {
+            path.push_str(&format!("?t={}", timeout));
+        }
+        self.post_empty(&path).await
+    }
+
+    pub async fn pause_container(&self, container: &str) -> Result<(), Box<dyn Error>> {
+        self.post_empty(&format!("/containers/{}/pause", encode(container))).await
+    }
+
+    pub async fn unpause_container(&self, container: &str) -> Result<(), Box<dyn Error>> {
+        self.post_empty(&format!("/containers/{}/unpause", encode(container))).await
+    }
+
+    /// Remove a container, stopping it first if `force` is set and removing its anonymous volumes if
+    /// `volumes` is set
+    pub async fn remove_container(&self, container: &str, force: bool, volumes: bool) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!(
+            "/containers/{}?force={}&v={}",
+            encode(container),
+            if force { 1 } else { 0 },
+            if volumes { 1 } else { 0 }
+        ))
+        .await
+    }
+
+    /// Processes running in a container
+    pub async fn top_container(&self, container: &str) -> Result<ContainerTop, Box<dyn Error>> {
+        self.get(&format!("/containers/{}/top", encode(container))).await
+    }
+
+    /// Write the filesystem of a container to `writer` as a tar and return the number of bytes written
+    pub async fn export_container<W>(&self, container: &str, writer: &mut W) -> Result<u64, Box<dyn Error>>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let response = self.send(Method::GET, &format!("/containers/{}/export", encode(container)), None).await?;
+        let mut body = response.into_body();
+        let mut written = 0;
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk?;
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Resource usage of a running container
+    pub async fn container_stats(&self, container: &str) -> Result<ContainerStats, Box<dyn Error>> {
+        self.get(&format!("/containers/{}/stats?stream=0", encode(container))).await
+    }
+
+    /// Resource usage of a running container every second, until it stops
+    pub async fn stream_container_stats(&self, container: &str) -> Result<JsonStream<ContainerStats>, Box<dyn Error>> {
+        Ok(self.get_lines(&format!("/containers/{}/stats", encode(container))).await?.into())
+    }
+
+    /// Files of a container compared with an image (the container's image if None), with the container's
+    /// writable layer as the last layer
+    pub async fn container_diff(&self, container: &str, from: Option<&str>) -> Result<ImageDiff, Box<dyn Error>> {
+        let mut path = format!("/containers/{}/diff", encode(container));
+        if let Some(from) = from {
+            path.push_str(&format!("?from={}", encode(from)));
+        }
+        self.get(&path).await
+    }
+
+    /// Extract a tar archive into the directory `path` of a container
+    pub async fn put_archive(&self, container: &str, path: &str, archive: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        let path = format!("/containers/{}/archive?path={}", encode(container), encode(path));
+        self.put_body(&path, archive, "application/x-tar").await
+    }
+
+    /// Remove a file or directory from a container
+    pub async fn remove_path(&self, container: &str, path: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/containers/{}/archive?path={}", encode(container), encode(path)))
+            .await
+    }
+
+    /// Log records of a container within the range of `options`, waiting for new output if `follow`
+    /// is set
+    pub async fn container_logs(
+        &self,
+        container: &str,
+        options: &ContainerLogsOptions,
+    ) -> Result<JsonStream<LogRecord>, Box<dyn Error>> {
+        Ok(self
+            .get_lines(&format!("/containers/{}/logs?{}", encode(container), options.query()))
+            .await?
+            .into())
+    }
+}
@@ -0,0 +1,339 @@
+use base64::{engine::general_purpose, Engine as _};
+use rocker_core::{RegistryAuth, DEFAULT_REGISTRY};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config::config_path;
+
+/// Prefix of the programs implementing the credential helper protocol (`docker-credential-<name>`)
+pub const CREDENTIAL_HELPER_PREFIX: &str = "docker-credential-";
+/// Server URL the default registry is stored under, as by Docker (so that existing helper entries are found)
+const DEFAULT_REGISTRY_SERVER: &str = "https://index.docker.io/v1/";
+/// Output of a helper that has no credentials for a server
+const NOT_FOUND_MESSAGE: &str = "credentials not found in native keychain";
+
+/// Registry host of a server address (`https://index.docker.io/v1/` and `registry-1.docker.io` are `docker.io`)
+pub fn registry_host(server: &str) -> String {
+    let host = server.trim_start_matches("https://").trim_start_matches("http://");
+    let host = host.split('/').next().unwrap_or(host);
+    match host {
+        "" | "index.docker.io" | "registry-1.docker.io" => DEFAULT_REGISTRY.to_string(),
+        host => host.to_string(),
+    }
+}
+
+// Server URL of a registry in config.json and for credential helpers
+fn server_url(registry: &str) -> String {
+    if registry == DEFAULT_REGISTRY {
+        DEFAULT_REGISTRY_SERVER.to_string()
+    } else {
+        registry.to_string()
+    }
+}
+
+/// Credentials exchanged with a credential helper
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HelperCredentials {
+    #[serde(rename = "ServerURL", default)]
+    server_url: String,
+    username: String,
+    secret: String,
+}
+
+/// A program implementing the docker-credential-helpers protocol (`osxkeychain`, `secretservice`,
+/// `pass`, `ecr-login`, `gcloud`, ...)
+///
+/// The action is the only argument of `docker-credential-<name>`, and the server URL or the
+/// credentials are written to its stdin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CredentialHelper {
+    name: String,
+}
+
+impl CredentialHelper {
+    pub fn new(name: &str) -> Self {
+        CredentialHelper { name: name.to_string() }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Program run for the helper
+    pub fn program(&self) -> String {
+        format!("{}{}", CREDENTIAL_HELPER_PREFIX, self.name)
+    }
+
+    /// Credentials for a registry (None if the helper has none)
+    pub async fn get(&self, registry: &str) -> Result<Option<RegistryAuth>, Box<dyn Error>> {
+        let output = match self.run("get", server_url(registry).as_bytes()).await {
+            Ok(output) => output,
+            Err(e) if e.contains(NOT_FOUND_MESSAGE) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let credentials: HelperCredentials = serde_json::from_slice(&output)
+            .map_err(|e| format!("Invalid output of {} get: {}", self.program(), e))?;
+        Ok(Some(RegistryAuth {
+            username: credentials.username,
+            password: credentials.secret,
+            server_address: registry.to_string(),
+        }))
+    }
+
+    /// Save the credentials of `auth.server_address`
+    pub async fn store(&self, auth: &RegistryAuth) -> Result<(), Box<dyn Error>> {
+        let credentials = HelperCredentials {
+            server_url: server_url(&auth.server_address),
+            username: auth.username.clone(),
+            secret: auth.password.clone(),
+        };
+        self.run("store", &serde_json::to_vec(&credentials)?).await?;
+        Ok(())
+    }
+
+    /// Remove the credentials of a registry
+    pub async fn erase(&self, registry: &str) -> Result<(), Box<dyn Error>> {
+        match self.run("erase", server_url(registry).as_bytes()).await {
+            Err(e) if !e.contains(NOT_FOUND_MESSAGE) => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Registries the helper has credentials for, with their usernames
+    pub async fn list(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let output = self.run("list", b"").await?;
+        let servers: HashMap<String, String> = serde_json::from_slice(&output)
+            .map_err(|e| format!("Invalid output of {} list: {}", self.program(), e))?;
+        Ok(servers
+            .into_iter()
+            .map(|(server, username)| (registry_host(&server), username))
+            .collect())
+    }
+
+    // Run the helper with one action, returning its stdout or, if it fails, what it printed
+    async fn run(&self, action: &str, input: &[u8]) -> Result<Vec<u8>, String> {
+        let program = self.program();
+        let mut child = Command::new(&program)
+            .arg(action)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run credential helper {}: {}", program, e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(input)
+                .await
+                .map_err(|e| format!("Failed to write to {}: {}", program, e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| format!("Failed to run credential helper {}: {}", program, e))?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(if output.stdout.is_empty() { &output.stderr } else { &output.stdout });
+            return Err(format!("{} {}: {}", program, action, message.trim()));
+        }
+        Ok(output.stdout)
+    }
+}
+
+/// Registry credentials saved by `rocker login`, as configured in `config.json`
+///
+/// `credHelpers` selects a helper per registry and `credsStore` the helper for all other registries.
+/// Without a helper, the credentials are kept base64-encoded in `auths` and the file is made readable
+/// only by its owner. Other keys of the file are left unchanged.
+#[derive(Debug, Clone)]
+pub struct CredentialStore {
+    path: PathBuf,
+}
+
+impl CredentialStore {
+    /// Store of the client configuration file ([`config_path`])
+    pub fn open() -> Result<Self, Box<dyn Error>> {
+        Ok(CredentialStore::at(config_path()?))
+    }
+
+    /// Store of another configuration file
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        CredentialStore { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Helper keeping the credentials of a registry (None if they are kept in the file)
+    pub fn helper(&self, registry: &str) -> Result<Option<CredentialHelper>, Box<dyn Error>> {
+        let config = self.read()?;
+        let per_registry = config
+            .get("credHelpers")
+            .and_then(|helpers| helpers.as_object())
+            .and_then(|helpers| {
+                helpers
+                    .iter()
+                    .find(|(server, _)| registry_host(server) == registry)
+                    .map(|(_, helper)| helper)
+            });
+        let helper = per_registry
+            .or_else(|| config.get("credsStore"))
+            .and_then(|helper| helper.as_str())
+            .filter(|helper| !helper.is_empty());
+        Ok(helper.map(CredentialHelper::new))
+    }
+
+    /// Credentials for a registry (None if the user has not logged in to it)
+    pub async fn get(&self, registry: &str) -> Result<Option<RegistryAuth>, Box<dyn Error>> {
+        if let Some(helper) = self.helper(registry)? {
+            return helper.get(registry).await;
+        }
+        let config = self.read()?;
+        let Some(auths) = config.get("auths").and_then(|auths| auths.as_object()) else {
+            return Ok(None);
+        };
+        match auths.iter().find(|(server, _)| registry_host(server) == registry) {
+            Some((server, entry)) => decode_entry(server, entry, registry),
+            None => Ok(None),
+        }
+    }
+
+    /// Credentials for every registry the user has logged in to, by registry
+    ///
+    /// Registries of `auths`, of `credHelpers` and, if `credsStore` is set, the ones listed by that
+    /// helper. Registries whose helper has no credentials are left out.
+    pub async fn all(&self) -> Result<HashMap<String, RegistryAuth>, Box<dyn Error>> {
+        let config = self.read()?;
+        let mut registries: Vec<String> = ["auths", "credHelpers"]
+            .iter()
+            .filter_map(|key| config.get(*key).and_then(|section| section.as_object()))
+            .flat_map(|section| section.keys().map(|server| registry_host(server)))
+            .collect();
+        if let Some(helper) = config.get("credsStore").and_then(|helper| helper.as_str()).filter(|helper| !helper.is_empty()) {
+            registries.extend(CredentialHelper::new(helper).list().await?.into_keys());
+        }
+        registries.sort();
+        registries.dedup();
+
+        let mut auths = HashMap::new();
+        for registry in registries {
+            if let Some(auth) = self.get(&registry).await? {
+                auths.insert(registry, auth);
+            }
+        }
+        Ok(auths)
+    }
+
+    /// Save the credentials of `auth.server_address`, with its helper if it has one
+    ///
+    /// Returns the helper used, if any.
+    pub async fn store(&self, auth: &RegistryAuth) -> Result<Option<CredentialHelper>, Box<dyn Error>> {
+        let registry = registry_host(&auth.server_address);
+        let auth = RegistryAuth {
+            server_address: registry.clone(),
+            ..auth.clone()
+        };
+        let helper = self.helper(&registry)?;
+        let mut config = self.read()?;
+        let auths = config
+            .entry("auths")
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        let auths = auths.as_object_mut().ok_or_else(|| format!("Invalid auths in {}", self.path.display()))?;
+        auths.retain(|server, _| registry_host(server) != registry);
+        match &helper {
+            // Keep an empty entry so that the registry is still listed, as Docker does
+            Some(helper) => {
+                helper.store(&auth).await?;
+                auths.insert(server_url(&registry), serde_json::json!({}));
+            }
+            None => {
+                let encoded = encode_basic(&auth.username, &auth.password);
+                auths.insert(server_url(&registry), serde_json::json!({ "auth": encoded }));
+            }
+        }
+        self.write(&config)?;
+        Ok(helper)
+    }
+
+    /// Remove the credentials of a registry, returning whether there were any
+    pub async fn erase(&self, registry: &str) -> Result<bool, Box<dyn Error>> {
+        let helper = self.helper(registry)?;
+        let mut config = self.read()?;
+        let mut found = false;
+        if let Some(auths) = config.get_mut("auths").and_then(|auths| auths.as_object_mut()) {
+            let before = auths.len();
+            auths.retain(|server, _| registry_host(server) != registry);
+            found = auths.len() != before;
+        }
+        if let Some(helper) = helper {
+            found |= helper.get(registry).await?.is_some();
+            helper.erase(registry).await?;
+        }
+        if found {
+            self.write(&config)?;
+        }
+        Ok(found)
+    }
+
+    fn read(&self) -> Result<serde_json::Map<String, serde_json::Value>, Box<dyn Error>> {
+        match std::fs::read(&self.path) {
+            Ok(data) => Ok(serde_json::from_slice(&data).map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(serde_json::Map::new()),
+            Err(e) => Err(format!("Failed to read {}: {}", self.path.display(), e).into()),
+        }
+    }
+
+    fn write(&self, config: &serde_json::Map<String, serde_json::Value>) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // The file may hold passwords, so it is written to a new 0600 file that is then renamed over the
+        // old one; it is never readable by others, even for a moment or when the old file was
+        let temporary = self.path.with_extension("json.tmp");
+        match std::fs::remove_file(&temporary) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&temporary)?;
+        file.write_all(&serde_json::to_vec_pretty(config)?)?;
+        file.sync_all()?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+}
+
+// base64 of username:password, the format of auths entries
+fn encode_basic(username: &str, password: &str) -> String {
+    general_purpose::STANDARD.encode(format!("{}:{}", username, password))
+}
+
+// An entry without auth only lists a registry whose credentials are kept by a helper
+fn decode_entry(server: &str, entry: &serde_json::Value, registry: &str) -> Result<Option<RegistryAuth>, Box<dyn Error>> {
+    let Some(encoded) = entry["auth"].as_str().filter(|encoded| !encoded.is_empty()) else {
+        return Ok(None);
+    };
+    let decoded = general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or_else(|| format!("Invalid credentials for {} in config.json", server))?;
+    let (username, password) = decoded
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid credentials for {} in config.json", server))?;
+    Ok(Some(RegistryAuth {
+        username: username.to_string(),
+        password: password.to_string(),
+        server_address: registry.to_string(),
+    }))
+}
@@ -0,0 +1,64 @@
+use futures::stream;
+use hyper::Body;
+use rocker_core::{ExecConfig, ExecInstance, LogRecord};
+use std::error::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::client::{encode, Client};
+use crate::stream::JsonStream;
+
+impl Client {
+    /// Create an exec instance in a running container and return its ID
+    pub async fn create_exec(&self, container: &str, config: &ExecConfig) -> Result<String, Box<dyn Error>> {
+        let created: serde_json::Value = self.post(&format!("/containers/{}/exec", encode(container)), config).await?;
+        created["id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "The daemon did not return the exec ID".into())
+    }
+
+    /// Start an exec instance without waiting for its output
+    pub async fn start_exec(&self, exec: &str) -> Result<(), Box<dyn Error>> {
+        self.post_empty(&format!("/exec/{}/start", encode(exec))).await
+    }
+
+    /// Start an exec instance and stream its output
+    ///
+    /// The exit code is recorded shortly after the output ends; read it with [`Client::inspect_exec`].
+    pub async fn attach_exec(&self, exec: &str) -> Result<JsonStream<LogRecord>, Box<dyn Error>> {
+        Ok(self.post_lines(&format!("/exec/{}/start?attach=1", encode(exec))).await?.into())
+    }
+
+    /// Start an exec instance created with `attach_stdin`, send the data of `stdin` to the command and
+    /// stream its output
+    ///
+    /// The command's stdin is closed once `stdin` ends.
+    pub async fn attach_exec_with_stdin<R>(&self, exec: &str, stdin: R) -> Result<JsonStream<LogRecord>, Box<dyn Error>>
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let chunks = stream::unfold(Box::pin(stdin), |mut stdin| async move {
+            let mut buffer = vec![0; 8192];
+            match stdin.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(read) => {
+                    buffer.truncate(read);
+                    Some((Ok::<_, std::io::Error>(buffer), stdin))
+                }
+                Err(e) => Some((Err(e), stdin)),
+            }
+        });
+        let body = Body::wrap_stream(chunks);
+        let path = format!("/exec/{}/start?attach=1", encode(exec));
+        Ok(self.post_stream_lines(&path, body, "application/octet-stream").await?.into())
+    }
+
+    pub async fn inspect_exec(&self, exec: &str) -> Result<ExecInstance, Box<dyn Error>> {
+        self.get(&format!("/exec/{}", encode(exec))).await
+    }
+
+    /// List the exec instances of a container
+    pub async fn list_execs(&self, container: &str) -> Result<Vec<ExecInstance>, Box<dyn Error>> {
+        self.get(&format!("/containers/{}/exec", encode(container))).await
+    }
+}
@@ -0,0 +1,305 @@
+use hyper::Method;
+use rocker_core::{
+    BuildCacheEntry, BuildCachePruneReport, Image, ImageDiff, ImageScan, ProgressMessage, RegistryAuth, RegistryReference, REGISTRY_AUTH_HEADER, REGISTRY_CONFIG_HEADER,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+
+use crate::client::{encode, filter_query, is_not_found, read_json, Client};
+use crate::config::add_proxy_env;
+use crate::stream::{JsonStream, Lines};
+
+/// Options for [`Client::build_image`]
+#[derive(Debug, Clone, Default)]
+pub struct ImageBuildOptions {
+    /// repo:tag given to the built image
+    pub tag: Option<String>,
+    /// Path of the Rockerfile inside the build context
+    pub rockerfile: Option<String>,
+    /// Stage to stop at in a multi-stage build
+    pub target: Option<String>,
+    pub build_args: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    /// Pull the base images even if they exist locally
+    pub pull: bool,
+    /// Squash the built image into a single layer
+    pub squash: bool,
+    /// Run every instruction instead of using the build cache
+    pub no_cache: bool,
+    /// Caches to import, preferring earlier ones: `type=local,src=<dir>` (a directory on the daemon's
+    /// host), `type=registry,ref=<repo:tag>` or just `<repo:tag>`
+    pub cache_from: Vec<String>,
+    /// Where to export the cache of the build: `type=local,dest=<dir>`, `type=registry,ref=<repo:tag>`
+    /// or just `<repo:tag>`
+    pub cache_to: Option<String>,
+}
+
+/// When [`Client::ensure_image`] pulls an image (`--pull` of `rocker run`, `pull_policy` of compose)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PullPolicy {
+    /// Pull every time, to get the current image of the tag
+    Always,
+    /// Pull only if the image does not exist locally
+    #[default]
+    Missing,
+    /// Never pull, and fail if the image does not exist locally
+    Never,
+}
+
+impl FromStr for PullPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(PullPolicy::Always),
+            "missing" => Ok(PullPolicy::Missing),
+            "never" => Ok(PullPolicy::Never),
+            _ => Err(format!("Invalid pull policy (expected always, missing or never): {}", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for PullPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PullPolicy::Always => "always",
+            PullPolicy::Missing => "missing",
+            PullPolicy::Never => "never",
+        })
+    }
+}
+
+impl ImageBuildOptions {
+    fn query(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(tag) = &self.tag {
+            params.push(format!("t={}", encode(tag)));
+        }
+        if let Some(rockerfile) = &self.rockerfile {
+            params.push(format!("rockerfile={}", encode(rockerfile)));
+        }
+        if let Some(target) = &self.target {
+            params.push(format!("target={}", encode(target)));
+        }
+        for (key, value) in &self.build_args {
+            params.push(format!("buildarg={}", encode(&format!("{}={}", key, value))));
+        }
+        for (key, value) in &self.labels {
+            params.push(format!("label={}", encode(&format!("{}={}", key, value))));
+        }
+        if self.pull {
+            params.push("pull=1".to_string());
+        }
+        if self.squash {
+            params.push("squash=1".to_string());
+        }
+        if self.no_cache {
+            params.push("nocache=1".to_string());
+        }
+        for spec in &self.cache_from {
+            params.push(format!("cachefrom={}", encode(spec)));
+        }
+        if let Some(spec) = &self.cache_to {
+            params.push(format!("cacheto={}", encode(spec)));
+        }
+        params.join("&")
+    }
+}
+
+impl Client {
+    /// Images, newest first
+    ///
+    /// Filters are `(key, value)` pairs with the keys `dangling` (`true` or `false`), `reference` (a
+    /// `repo:tag` pattern with `*`), `label`, `before` and `since` (an image); filters with the same key
+    /// match any of their values.
+    pub async fn list_images(&self, filters: &[(&str, &str)]) -> Result<Vec<Image>, Box<dyn Error>> {
+        if filters.is_empty() {
+            return self.get("/images").await;
+        }
+        self.get(&format!("/images?{}", filter_query(filters))).await
+    }
+
+    /// Image by ID, ID prefix or repo:tag
+    pub async fn inspect_image(&self, image: &str) -> Result<Image, Box<dyn Error>> {
+        self.get(&format!("/images/{}", encode(image))).await
+    }
+
+    /// Pull an image, streaming the progress
+    ///
+    /// The last message carries `error` if the pull failed and `image_id` if it succeeded.
+    pub async fn pull_image(&self, image: &str) -> Result<JsonStream<ProgressMessage>, Box<dyn Error>> {
+        let headers = self.registry_auth_header(image).await?;
+        let path = format!("/images/create?fromImage={}", encode(image));
+        let response = self.send_body(Method::POST, &path, None, &headers).await?;
+        Ok(Lines::new(response.into_body()).into())
+    }
+
+    /// Make sure `image` exists before creating a container from it, pulling it as `policy` says
+    ///
+    /// Returns the progress of the pull if one was started (read it to the end before using the image),
+    /// or None if the local image is used. With [`PullPolicy::Never`], a missing image fails with the
+    /// daemon's 404.
+    pub async fn ensure_image(
+        &self,
+        image: &str,
+        policy: PullPolicy,
+    ) -> Result<Option<JsonStream<ProgressMessage>>, Box<dyn Error>> {
+        if policy != PullPolicy::Always {
+            match self.inspect_image(image).await {
+                Ok(_) => return Ok(None),
+                Err(e) if policy == PullPolicy::Missing && is_not_found(e.as_ref()) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.pull_image(image).await.map(Some)
+    }
+
+    /// Push an image to its registry, streaming the progress
+    pub async fn push_image(&self, image: &str) -> Result<JsonStream<ProgressMessage>, Box<dyn Error>> {
+        let headers = self.registry_auth_header(image).await?;
+        let path = format!("/images/{}/push", encode(image));
+        let response = self.send_body(Method::POST, &path, None, &headers).await?;
+        Ok(Lines::new(response.into_body()).into())
+    }
+
+    /// Check that the daemon can log in to `auth.server_address` with the credentials, and return its status message
+    ///
+    /// The daemon does not keep the credentials; save them with [`CredentialStore::store`](crate::CredentialStore::store).
+    pub async fn login(&self, auth: &RegistryAuth) -> Result<String, Box<dyn Error>> {
+        let response: serde_json::Value = self.post("/auth", auth).await?;
+        Ok(response["status"].as_str().unwrap_or("Login Succeeded").to_string())
+    }
+
+    // X-Registry-Auth with the saved credentials for the registry of an image (none if there are none)
+    async fn registry_auth_header(&self, image: &str) -> Result<Vec<(&'static str, String)>, Box<dyn Error>> {
+        let (Some(credentials), Ok(reference)) = (self.credentials(), RegistryReference::parse(image)) else {
+            return Ok(Vec::new());
+        };
+        Ok(match credentials.get(&reference.registry).await? {
+            Some(auth) => vec![(REGISTRY_AUTH_HEADER, auth.to_header())],
+            None => Vec::new(),
+        })
+    }
+
+    /// Remove an image and return it (fails with 409 while containers use it)
+    pub async fn remove_image(&self, image: &str) -> Result<Image, Box<dyn Error>> {
+        let response = self.send(Method::DELETE, &format!("/images/{}", encode(image)), None).await?;
+        read_json(response).await
+    }
+
+    /// Create a single-layer image from a tar (optionally gzip-compressed) of a filesystem
+    ///
+    /// `changes` are Rockerfile instructions (`ENV`, `CMD`, `WORKDIR`, ...) applied to the image config.
+    pub async fn import_image(
+        &self,
+        archive: Vec<u8>,
+        reference: Option<&str>,
+        changes: &[String],
+        message: Option<&str>,
+    ) -> Result<Image, Box<dyn Error>> {
+        let mut params: Vec<String> = changes.iter().map(|change| format!("change={}", encode(change))).collect();
+        if let Some(reference) = reference {
+            params.push(format!("repo={}", encode(reference)));
+        }
+        if let Some(message) = message {
+            params.push(format!("message={}", encode(message)));
+        }
+        let path = format!("/images/import?{}", params.join("&"));
+        let response = self.send_body(Method::POST, &path, Some((archive, "application/x-tar")), &[]).await?;
+        read_json(response).await
+    }
+
+    /// Files added, removed and changed in `image` compared with `from`, per layer the two do not share
+    pub async fn image_diff(&self, image: &str, from: &str) -> Result<ImageDiff, Box<dyn Error>> {
+        self.get(&format!("/images/{}/diff?from={}", encode(image), encode(from)))
+            .await
+    }
+
+    /// SBOM of the packages installed in an image, as an SPDX (`spdx-json`) or CycloneDX
+    /// (`cyclonedx-json`) JSON document
+    pub async fn image_sbom(&self, image: &str, format: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        self.get(&format!("/images/{}/sbom?format={}", encode(image), encode(format)))
+            .await
+    }
+
+    /// Scan an image with the scanner configured in the daemon and return the result, which is also
+    /// stored in [`Image::scan`]
+    pub async fn scan_image(&self, image: &str) -> Result<ImageScan, Box<dyn Error>> {
+        self.post(&format!("/images/{}/scan", encode(image)), &serde_json::json!({}))
+            .await
+    }
+
+    /// Squash an image into a single layer with the same configuration, streaming the progress
+    ///
+    /// The new image is tagged `tag`, or takes over the name of `image` if None. The last message
+    /// carries `error` if squashing failed and `image_id` if it succeeded.
+    pub async fn squash_image(&self, image: &str, tag: Option<&str>) -> Result<JsonStream<ProgressMessage>, Box<dyn Error>> {
+        let mut path = format!("/images/{}/squash", encode(image));
+        if let Some(tag) = tag {
+            path.push_str(&format!("?tag={}", encode(tag)));
+        }
+        let response = self.send_body(Method::POST, &path, None, &[]).await?;
+        Ok(Lines::new(response.into_body()).into())
+    }
+
+    /// Build an image from a tar (optionally gzip-compressed) of the build context, streaming each
+    /// step and the output of RUN
+    ///
+    /// The last message carries `error` if the build failed and `image_id` if it succeeded. Base images
+    /// are pulled with the saved credentials of every registry the user has logged in to, and the proxies
+    /// of [`Client::with_proxy`] are passed as build arguments (`HTTP_PROXY` and so on) not given in `options`.
+    pub async fn build_image(
+        &self,
+        options: &ImageBuildOptions,
+        context: Vec<u8>,
+    ) -> Result<JsonStream<ProgressMessage>, Box<dyn Error>> {
+        let mut headers = Vec::new();
+        if let Some(credentials) = self.credentials() {
+            let auths = credentials.all().await?;
+            if !auths.is_empty() {
+                headers.push((REGISTRY_CONFIG_HEADER, RegistryAuth::to_config_header(&auths)));
+            }
+        }
+        let query = match self.proxy() {
+            Some(proxy) => {
+                let mut options = options.clone();
+                add_proxy_env(&mut options.build_args, proxy);
+                options.query()
+            }
+            None => options.query(),
+        };
+        let path = format!("/build?{}", query);
+        let response = self
+            .send_body(Method::POST, &path, Some((context, "application/x-tar")), &headers)
+            .await?;
+        Ok(Lines::new(response.into_body()).into())
+    }
+
+    /// Entries of the daemon's build cache, most recently used first
+    pub async fn build_cache(&self) -> Result<Vec<BuildCacheEntry>, Box<dyn Error>> {
+        self.get("/build/cache").await
+    }
+
+    /// Remove build cache entries and the layers no image uses any more (fails with 409 during builds)
+    ///
+    /// Only entries whose layer no image uses are removed unless `all` is set. An `until` filter keeps
+    /// the entries used since then, and `keep_storage` stops once the remaining entries take at most
+    /// that many bytes, removing the least recently used first.
+    pub async fn prune_build_cache(
+        &self,
+        all: bool,
+        filters: &[(&str, &str)],
+        keep_storage: Option<u64>,
+    ) -> Result<BuildCachePruneReport, Box<dyn Error>> {
+        let mut query = filter_query(filters);
+        if all {
+            query.push_str("&all=1");
+        }
+        if let Some(bytes) = keep_storage {
+            query.push_str(&format!("&keep-storage={}", bytes));
+        }
+        self.post(&format!("/build/prune?{}", query.trim_start_matches('&')), &serde_json::json!({}))
+            .await
+    }
+}
@@ -0,0 +1,42 @@
+//! Async client for the Rocker daemon API
+//!
+//! [`Client`] connects to the daemon in `ROCKER_HOST` (a Unix socket or TCP, optionally with TLS)
+//! and has typed methods for containers, exec instances, images, networks, volumes, secrets,
+//! schedules, events and swarm nodes and services.
+//! Endpoints that keep returning output (logs, exec, pull, build, events) are read through
+//! [`JsonStream`], and [`Progress`] shows the progress of pulls, pushes and builds.
+//! [`Client::run_attached`] runs a container in the foreground, forwarding Ctrl-C to it.
+//! [`use_color`] decides whether output is colored (`--no-color`, `NO_COLOR`). [`ClientConfig`] holds the per-user
+//! defaults of `config.json`, and [`CredentialStore`] keeps the registry credentials of `rocker login`
+//! there or with a [`CredentialHelper`]. The raw `get`/`post`/... methods remain available for
+//! anything not covered.
+
+mod attach;
+mod client;
+mod color;
+mod config;
+mod containers;
+mod credentials;
+mod exec;
+mod images;
+mod networks;
+mod progress;
+mod schedules;
+mod secrets;
+mod stream;
+mod swarm;
+mod system;
+mod volumes;
+
+pub use crate::attach::{parse_detach_keys, Attached, DEFAULT_DETACH_KEYS};
+pub use crate::client::*;
+pub use crate::color::*;
+pub use crate::config::*;
+pub use crate::containers::ContainerLogsOptions;
+pub use crate::credentials::*;
+pub use crate::images::{ImageBuildOptions, PullPolicy};
+pub use crate::networks::NetworkCreateOptions;
+pub use crate::progress::*;
+pub use crate::stream::*;
+pub use crate::system::{DiskUsage, ResourceInfo, SystemInfo};
+pub use crate::volumes::{VolumeCreateOptions, VolumePruneReport};
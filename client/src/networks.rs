@@ -0,0 +1,87 @@
+use rocker_core::{Network, NetworkEndpoint};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::Ipv4Addr;
+
+use crate::client::{encode, filter_query, Client};
+
+/// Options for [`Client::create_network`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NetworkCreateOptions {
+    pub name: String,
+    /// Network driver (bridge if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    /// Subnet in CIDR form (allocated by the daemon if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subnet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway: Option<String>,
+    /// Range within the subnet that container addresses are allocated from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_range: Option<String>,
+    /// Do not route traffic outside the network
+    pub internal: bool,
+    pub labels: HashMap<String, String>,
+    /// Driver options
+    pub options: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct PruneResponse {
+    networks_deleted: Vec<String>,
+}
+
+impl Client {
+    /// List networks
+    ///
+    /// Filters are `(key, value)` pairs with the keys `driver`, `id`, `label`, `name` and `type`;
+    /// filters with the same key match any of their values.
+    pub async fn list_networks(&self, filters: &[(&str, &str)]) -> Result<Vec<Network>, Box<dyn Error>> {
+        self.get(&format!("/networks?{}", filter_query(filters))).await
+    }
+
+    pub async fn create_network(&self, options: &NetworkCreateOptions) -> Result<Network, Box<dyn Error>> {
+        self.post("/networks/create", options).await
+    }
+
+    /// Network by ID, ID prefix or name
+    pub async fn inspect_network(&self, network: &str) -> Result<Network, Box<dyn Error>> {
+        self.get(&format!("/networks/{}", encode(network))).await
+    }
+
+    pub async fn remove_network(&self, network: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/networks/{}", encode(network))).await
+    }
+
+    /// Remove the networks no container is connected to and return their names
+    ///
+    /// Filters are `(key, value)` pairs with the keys `label` (`key` or `key=value`) and `until` (an
+    /// RFC 3339 timestamp, Unix seconds or a duration such as `24h`, matching networks created before it).
+    pub async fn prune_networks(&self, filters: &[(&str, &str)]) -> Result<Vec<String>, Box<dyn Error>> {
+        let response: PruneResponse = self
+            .post(&format!("/networks/prune?{}", filter_query(filters)), &json!({}))
+            .await?;
+        Ok(response.networks_deleted)
+    }
+
+    /// Connect a container to a network with extra DNS aliases and optionally a fixed address
+    pub async fn connect_network(
+        &self,
+        network: &str,
+        container: &str,
+        aliases: &[String],
+        ip_address: Option<Ipv4Addr>,
+    ) -> Result<NetworkEndpoint, Box<dyn Error>> {
+        let request = json!({ "container": container, "aliases": aliases, "ip_address": ip_address });
+        self.post(&format!("/networks/{}/connect", encode(network)), &request).await
+    }
+
+    pub async fn disconnect_network(&self, network: &str, container: &str) -> Result<(), Box<dyn Error>> {
+        let request = json!({ "container": container });
+        self.post_ignore_response(&format!("/networks/{}/disconnect", encode(network)), &request)
+            .await
+    }
+}
@@ -0,0 +1,300 @@
+use rocker_core::{format_size, BuildEvent, BuildMetadata, ProgressMessage};
+use std::error::Error;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::stream::JsonStream;
+
+/// Minimum time between two plain lines repeating the byte progress of the same layer
+const PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+/// Width of the bar drawn on terminals
+const BAR_WIDTH: usize = 25;
+/// Terminal width used when `COLUMNS` is not set
+const DEFAULT_COLUMNS: usize = 80;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static RENDERER: Mutex<Renderer> = Mutex::new(Renderer::new());
+
+/// Silence all progress output (the CLI's `-q/--quiet`)
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::SeqCst);
+}
+
+/// Whether progress output is silenced
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::SeqCst)
+}
+
+/// Progress reports the progress messages of a pull, push or build on stdout
+///
+/// On a terminal every layer or build step keeps one line with a bar that is redrawn in place, and the
+/// lines of concurrent operations (e.g. a parallel `compose pull`) are drawn together. Otherwise the
+/// messages are printed as plain lines, repeating the byte progress of a layer at most every few
+/// seconds. Nothing is printed in quiet mode ([`set_quiet`]).
+pub struct Progress {
+    prefix: Option<String>,
+    started: bool,
+}
+
+impl Progress {
+    /// Progress of a single operation
+    pub fn new() -> Self {
+        Progress {
+            prefix: None,
+            started: false,
+        }
+    }
+
+    /// Progress of one of several operations, with its lines prefixed by `prefix | ` (e.g. a service name)
+    pub fn with_prefix(prefix: impl Into<String>) -> Self {
+        Progress {
+            prefix: Some(prefix.into()),
+            started: false,
+        }
+    }
+
+    /// Show one message
+    pub fn update(&mut self, message: &ProgressMessage) {
+        if is_quiet() {
+            return;
+        }
+        let mut renderer = RENDERER.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.started {
+            self.started = true;
+            renderer.active += 1;
+        }
+        renderer.update(self.prefix.as_deref(), message);
+    }
+
+    /// Show the messages of `stream` until it ends, and return the image ID of the last message
+    ///
+    /// A message carrying `error` ends the report with that error.
+    pub async fn report(
+        &mut self,
+        stream: impl Into<JsonStream<ProgressMessage>>,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let mut image_id = None;
+        self.report_with(stream.into(), |message| {
+            if message.image_id.is_some() {
+                image_id = message.image_id.clone();
+            }
+        })
+        .await?;
+        Ok(image_id)
+    }
+
+    /// Show the messages of a build until it ends, and return the summary of its
+    /// [`BuildEvent::Finished`] event (e.g. for `rocker build --metadata-file`)
+    pub async fn report_build(
+        &mut self,
+        stream: impl Into<JsonStream<ProgressMessage>>,
+    ) -> Result<Option<BuildMetadata>, Box<dyn Error>> {
+        let mut metadata = None;
+        self.report_with(stream.into(), |message| {
+            if let Some(BuildEvent::Finished(finished)) = &message.event {
+                metadata = Some(finished.clone());
+            }
+        })
+        .await?;
+        Ok(metadata)
+    }
+
+    async fn report_with(
+        &mut self,
+        mut stream: JsonStream<ProgressMessage>,
+        mut on_message: impl FnMut(&ProgressMessage),
+    ) -> Result<(), Box<dyn Error>> {
+        let result = loop {
+            match stream.next().await {
+                Ok(Some(message)) => {
+                    if let Some(error) = message.error {
+                        break Err(error.into());
+                    }
+                    self.update(&message);
+                    on_message(&message);
+                }
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        self.finish();
+        result
+    }
+
+    /// Stop drawing the lines of this operation (they stay on the screen)
+    pub fn finish(&mut self) {
+        if !self.started {
+            return;
+        }
+        self.started = false;
+        let mut renderer = RENDERER.lock().unwrap_or_else(|e| e.into_inner());
+        renderer.active -= 1;
+        if renderer.active == 0 {
+            renderer.rows.clear();
+            renderer.drawn = 0;
+        }
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress::new()
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Line of a layer or build step
+struct Row {
+    prefix: Option<String>,
+    id: String,
+    status: String,
+    current: Option<u64>,
+    total: Option<u64>,
+    /// When the row was last printed as a plain line
+    printed_at: Option<Instant>,
+}
+
+impl Row {
+    fn label(&self) -> String {
+        match &self.prefix {
+            Some(prefix) => format!("{} | {}: {}", prefix, self.id, self.status),
+            None => format!("{}: {}", self.id, self.status),
+        }
+    }
+
+    fn sizes(&self) -> Option<String> {
+        match (self.current, self.total) {
+            (Some(current), Some(total)) => Some(format!("{}/{}", format_size(current), format_size(total))),
+            _ => None,
+        }
+    }
+
+    fn bar(&self) -> Option<String> {
+        let (current, total) = (self.current?, self.total.filter(|total| *total > 0)?);
+        let filled = ((current.min(total) as f64 / total as f64) * BAR_WIDTH as f64) as usize;
+        let mut bar = "=".repeat(filled);
+        if filled < BAR_WIDTH {
+            bar.push('>');
+            bar.push_str(&" ".repeat(BAR_WIDTH - filled - 1));
+        }
+        Some(format!("[{}]", bar))
+    }
+}
+
+/// Output shared by all operations reporting at the same time
+struct Renderer {
+    rows: Vec<Row>,
+    /// Number of row lines currently drawn below the cursor's line on the terminal
+    drawn: usize,
+    /// Number of operations that have not finished
+    active: usize,
+}
+
+impl Renderer {
+    const fn new() -> Self {
+        Renderer {
+            rows: Vec::new(),
+            drawn: 0,
+            active: 0,
+        }
+    }
+
+    fn update(&mut self, prefix: Option<&str>, message: &ProgressMessage) {
+        let terminal = std::io::stdout().is_terminal();
+        let mut stdout = std::io::stdout().lock();
+        let Some(id) = &message.id else {
+            if message.status.is_empty() {
+                return;
+            }
+            let line = match prefix {
+                Some(prefix) => format!("{} | {}", prefix, message.status),
+                None => message.status.clone(),
+            };
+            // On a terminal, print the line above the drawn rows and draw them again below it
+            if terminal {
+                let _ = writeln!(stdout, "{}\x1b[J{}", cursor_up(self.drawn), line);
+                self.drawn = 0;
+                self.draw(&mut stdout);
+            } else {
+                let _ = writeln!(stdout, "{}", line);
+            }
+            let _ = stdout.flush();
+            return;
+        };
+
+        let index = match self
+            .rows
+            .iter()
+            .position(|row| row.id == *id && row.prefix.as_deref() == prefix)
+        {
+            Some(index) => index,
+            None => {
+                self.rows.push(Row {
+                    prefix: prefix.map(str::to_string),
+                    id: id.clone(),
+                    status: String::new(),
+                    current: None,
+                    total: None,
+                    printed_at: None,
+                });
+                self.rows.len() - 1
+            }
+        };
+        let row = &mut self.rows[index];
+        let status_changed = row.status != message.status;
+        row.status = message.status.clone();
+        row.current = message.current;
+        row.total = message.total;
+
+        if terminal {
+            self.draw(&mut stdout);
+        } else if status_changed || row.printed_at.is_none_or(|at| at.elapsed() >= PLAIN_INTERVAL) {
+            row.printed_at = Some(Instant::now());
+            let _ = match row.sizes() {
+                Some(sizes) => writeln!(stdout, "{} {}", row.label(), sizes),
+                None => writeln!(stdout, "{}", row.label()),
+            };
+        }
+        let _ = stdout.flush();
+    }
+
+    // Move back over the drawn rows and draw all of them again, cut to the terminal width so that no
+    // line wraps and moves the position to return to
+    fn draw(&mut self, out: &mut impl Write) {
+        let columns = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_COLUMNS);
+        let mut frame = cursor_up(self.drawn);
+        for row in &self.rows {
+            let mut line = row.label();
+            if let Some(bar) = row.bar() {
+                line.push(' ');
+                line.push_str(&bar);
+            }
+            if let Some(sizes) = row.sizes() {
+                line.push(' ');
+                line.push_str(&sizes);
+            }
+            let line: String = line.chars().take(columns.saturating_sub(1)).collect();
+            frame.push_str(&format!("\x1b[2K{}\n", line));
+        }
+        let _ = out.write_all(frame.as_bytes());
+        self.drawn = self.rows.len();
+    }
+}
+
+fn cursor_up(lines: usize) -> String {
+    if lines == 0 {
+        String::new()
+    } else {
+        format!("\x1b[{}A", lines)
+    }
+}
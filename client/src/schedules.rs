@@ -0,0 +1,31 @@
+use rocker_core::{Schedule, ScheduleCreateRequest};
+use std::error::Error;
+
+use crate::client::{encode, filter_query, Client};
+
+impl Client {
+    /// List schedules, narrowed down by `name` filters
+    pub async fn list_schedules(&self, filters: &[(&str, &str)]) -> Result<Vec<Schedule>, Box<dyn Error>> {
+        self.get(&format!("/schedules?{}", filter_query(filters))).await
+    }
+
+    /// Create a schedule running containers on a cron expression
+    pub async fn create_schedule(&self, request: &ScheduleCreateRequest) -> Result<Schedule, Box<dyn Error>> {
+        self.post("/schedules/create", request).await
+    }
+
+    /// Schedule by ID, ID prefix or name, with the history of its runs
+    pub async fn inspect_schedule(&self, schedule: &str) -> Result<Schedule, Box<dyn Error>> {
+        self.get(&format!("/schedules/{}", encode(schedule))).await
+    }
+
+    /// Remove a schedule and the containers of its runs (`force` stops the running ones)
+    pub async fn remove_schedule(&self, schedule: &str, force: bool) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!(
+            "/schedules/{}?force={}",
+            encode(schedule),
+            if force { 1 } else { 0 }
+        ))
+        .await
+    }
+}
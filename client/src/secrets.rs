@@ -0,0 +1,32 @@
+use rocker_core::{Secret, SecretCreateRequest};
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::client::{encode, filter_query, Client};
+
+impl Client {
+    /// List secrets (never their data), narrowed down by `label` and `name` filters
+    pub async fn list_secrets(&self, filters: &[(&str, &str)]) -> Result<Vec<Secret>, Box<dyn Error>> {
+        self.get(&format!("/secrets?{}", filter_query(filters))).await
+    }
+
+    /// Store `data` encrypted in the daemon as the secret `name`
+    pub async fn create_secret(
+        &self,
+        name: &str,
+        data: &[u8],
+        labels: HashMap<String, String>,
+    ) -> Result<Secret, Box<dyn Error>> {
+        self.post("/secrets/create", &SecretCreateRequest::new(name, data, labels)).await
+    }
+
+    /// Secret by ID, ID prefix or name
+    pub async fn inspect_secret(&self, secret: &str) -> Result<Secret, Box<dyn Error>> {
+        self.get(&format!("/secrets/{}", encode(secret))).await
+    }
+
+    /// Remove a secret (fails with 409 while containers reference it)
+    pub async fn remove_secret(&self, secret: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/secrets/{}", encode(secret))).await
+    }
+}
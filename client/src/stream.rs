@@ -0,0 +1,89 @@
+use futures::stream::{self, Stream};
+use hyper::body::HttpBody;
+use hyper::Body;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::marker::PhantomData;
+
+/// Response body read line by line
+pub struct Lines {
+    body: Body,
+    buffer: Vec<u8>,
+}
+
+impl Lines {
+    pub(crate) fn new(body: Body) -> Self {
+        Lines {
+            body,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Next line (None once the body ends)
+    pub async fn next_line(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                return Ok(Some(String::from_utf8_lossy(&line[..end]).to_string()));
+            }
+            match self.body.data().await {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                None if self.buffer.is_empty() => return Ok(None),
+                None => {
+                    let line = std::mem::take(&mut self.buffer);
+                    return Ok(Some(String::from_utf8_lossy(&line).to_string()));
+                }
+            }
+        }
+    }
+
+    /// Next non-empty line decoded as JSON
+    pub async fn next_json<T: DeserializeOwned>(&mut self) -> Result<Option<T>, Box<dyn Error>> {
+        loop {
+            match self.next_line().await? {
+                Some(line) if line.trim().is_empty() => continue,
+                Some(line) => return Ok(Some(serde_json::from_str(&line)?)),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+
+/// Response body holding one JSON object of type `T` per line (logs, events, pull progress, ...)
+pub struct JsonStream<T> {
+    lines: Lines,
+    item: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> JsonStream<T> {
+    /// Next object (None once the body ends)
+    pub async fn next(&mut self) -> Result<Option<T>, Box<dyn Error>> {
+        self.lines.next_json().await
+    }
+
+    /// Turn the body into a `Stream` of objects, for use with `StreamExt` combinators
+    pub fn into_stream(self) -> impl Stream<Item = Result<T, Box<dyn Error>>> {
+        stream::unfold(self, |mut objects| async move {
+            match objects.next().await {
+                Ok(Some(object)) => Some((Ok(object), objects)),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), objects)),
+            }
+        })
+    }
+
+    /// The underlying lines
+    pub fn into_lines(self) -> Lines {
+        self.lines
+    }
+}
+
+impl<T> From<Lines> for JsonStream<T> {
+    fn from(lines: Lines) -> Self {
+        JsonStream {
+            lines,
+            item: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use rocker_core::{Node, NodeUpdate, Service, ServiceSpec, SwarmInfo, SwarmInitRequest, SwarmJoinRequest};
+use serde_json::json;
+use std::error::Error;
+
+use crate::client::{encode, Client};
+
+impl Client {
+    /// Create a swarm with this daemon as its manager; the returned info has the join token
+    pub async fn init_swarm(&self, request: &SwarmInitRequest) -> Result<SwarmInfo, Box<dyn Error>> {
+        self.post("/swarm/init", request).await
+    }
+
+    /// Join the swarm of the manager at `request.remote_address` as a worker
+    pub async fn join_swarm(&self, request: &SwarmJoinRequest) -> Result<SwarmInfo, Box<dyn Error>> {
+        self.post("/swarm/join", request).await
+    }
+
+    /// Leave the swarm (a manager with other nodes, or a worker that cannot reach its manager, needs
+    /// `force`)
+    pub async fn leave_swarm(&self, force: bool) -> Result<(), Box<dyn Error>> {
+        let path = if force { "/swarm/leave?force=1" } else { "/swarm/leave" };
+        self.post_empty(path).await
+    }
+
+    /// The swarm this daemon is part of (fails with 503 outside a swarm)
+    pub async fn swarm_info(&self) -> Result<SwarmInfo, Box<dyn Error>> {
+        self.get("/swarm").await
+    }
+
+    /// Replace the join token; nodes that already joined are not affected
+    pub async fn rotate_join_token(&self) -> Result<SwarmInfo, Box<dyn Error>> {
+        self.post("/swarm/rotate-token", &json!({})).await
+    }
+
+    /// Nodes of the swarm (manager only)
+    pub async fn list_nodes(&self) -> Result<Vec<Node>, Box<dyn Error>> {
+        self.get("/nodes").await
+    }
+
+    /// Node by ID, ID prefix or hostname
+    pub async fn inspect_node(&self, node: &str) -> Result<Node, Box<dyn Error>> {
+        self.get(&format!("/nodes/{}", encode(node))).await
+    }
+
+    /// Change the availability or labels of a node; tasks that may no longer run on it move to other
+    /// nodes
+    pub async fn update_node(&self, node: &str, update: &NodeUpdate) -> Result<Node, Box<dyn Error>> {
+        self.post(&format!("/nodes/{}/update", encode(node)), update).await
+    }
+
+    /// Remove a worker by ID, ID prefix or hostname; its tasks move to the other nodes
+    pub async fn remove_node(&self, node: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/nodes/{}", encode(node))).await
+    }
+
+    pub async fn create_service(&self, spec: &ServiceSpec) -> Result<Service, Box<dyn Error>> {
+        self.post("/services/create", spec).await
+    }
+
+    pub async fn list_services(&self) -> Result<Vec<Service>, Box<dyn Error>> {
+        self.get("/services").await
+    }
+
+    /// Service by ID, ID prefix or name, with its tasks
+    pub async fn inspect_service(&self, service: &str) -> Result<Service, Box<dyn Error>> {
+        self.get(&format!("/services/{}", encode(service))).await
+    }
+
+    /// Replace the spec of a service; its tasks are replaced if the container configuration, network or
+    /// constraints change
+    pub async fn update_service(&self, service: &str, spec: &ServiceSpec) -> Result<Service, Box<dyn Error>> {
+        self.post(&format!("/services/{}/update", encode(service)), spec).await
+    }
+
+    /// Change the number of replicas of a service
+    pub async fn scale_service(&self, service: &str, replicas: u64) -> Result<Service, Box<dyn Error>> {
+        self.post(
+            &format!("/services/{}/scale?replicas={}", encode(service), replicas),
+            &json!({}),
+        )
+        .await
+    }
+
+    /// Remove a service; the nodes remove its containers on their next report
+    pub async fn remove_service(&self, service: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/services/{}", encode(service))).await
+    }
+}
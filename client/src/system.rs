@@ -0,0 +1,77 @@
+use rocker_core::{Container, Event, Image, Volume};
+use serde::Deserialize;
+use std::error::Error;
+
+use crate::client::{filter_query, Client};
+use crate::stream::JsonStream;
+
+/// Disk usage of images, containers and volumes, returned by [`Client::disk_usage`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiskUsage {
+    pub images: Vec<Image>,
+    /// Total size of the images in bytes
+    pub images_size: u64,
+    /// Containers with their `size_rw` and `size_root_fs`
+    #[serde(default)]
+    pub containers: Vec<Container>,
+    /// Total size of the writable layers of the containers in bytes
+    #[serde(default)]
+    pub containers_size: u64,
+    /// Volumes with their usage and the number of containers mounting them
+    pub volumes: Vec<Volume>,
+    /// Total size of the volumes in bytes
+    pub volumes_size: u64,
+    /// Number of build cache entries
+    #[serde(default)]
+    pub build_cache: usize,
+    /// Bytes of the build cache layers no image uses
+    #[serde(default)]
+    pub build_cache_size: u64,
+}
+
+/// Counts of containers and images, and the host's resources, returned by [`Client::info`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct SystemInfo {
+    pub containers: usize,
+    pub containers_running: usize,
+    pub containers_paused: usize,
+    pub containers_stopped: usize,
+    pub images: usize,
+    /// CPUs of the host
+    pub ncpu: f64,
+    /// Memory of the host in bytes
+    pub mem_total: u64,
+    pub resources: ResourceInfo,
+}
+
+/// CPUs and memory the daemon allocates to containers, and what the running containers reserve
+/// through their `cpus` and memory reservation (or memory limit)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResourceInfo {
+    pub allocatable_cpus: f64,
+    /// Allocatable memory in bytes
+    pub allocatable_memory: u64,
+    pub reserved_cpus: f64,
+    /// Reserved memory in bytes
+    pub reserved_memory: u64,
+    /// Whether containers whose reservation exceeds what is left are refused
+    pub reject_oversubscription: bool,
+}
+
+impl Client {
+    /// Events that happen after the call, until the stream is dropped
+    ///
+    /// A `label` filter matches the labels of the container or network in the event attributes; an
+    /// `until` filter makes the daemon end the stream at that time.
+    pub async fn events(&self, filters: &[(&str, &str)]) -> Result<JsonStream<Event>, Box<dyn Error>> {
+        Ok(self.get_lines(&format!("/events?{}", filter_query(filters))).await?.into())
+    }
+
+    pub async fn info(&self) -> Result<SystemInfo, Box<dyn Error>> {
+        self.get("/info").await
+    }
+
+    pub async fn disk_usage(&self) -> Result<DiskUsage, Box<dyn Error>> {
+        self.get("/system/df").await
+    }
+}
@@ -0,0 +1,89 @@
+use rocker_core::{Volume, VolumeSnapshot};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::client::{encode, filter_query, Client};
+
+/// Options for [`Client::create_volume`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VolumeCreateOptions {
+    /// Volume name (generated by the daemon if empty)
+    pub name: String,
+    /// Volume driver (local if None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+    pub driver_opts: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+}
+
+/// Result of [`Client::prune_volumes`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct VolumePruneReport {
+    /// Names of the removed volumes
+    pub volumes_deleted: Vec<String>,
+    /// Bytes freed by removing them
+    pub space_reclaimed: u64,
+}
+
+impl Client {
+    /// List volumes, with their disk usage if `size` is set (this walks each volume's directory)
+    ///
+    /// Filters are `(key, value)` pairs with the keys `dangling`, `driver`, `label` and `name`;
+    /// filters with the same key match any of their values.
+    pub async fn list_volumes(&self, filters: &[(&str, &str)], size: bool) -> Result<Vec<Volume>, Box<dyn Error>> {
+        let mut query = filter_query(filters);
+        if size {
+            if !query.is_empty() {
+                query.push('&');
+            }
+            query.push_str("size=1");
+        }
+        self.get(&format!("/volumes?{}", query)).await
+    }
+
+    pub async fn create_volume(&self, options: &VolumeCreateOptions) -> Result<Volume, Box<dyn Error>> {
+        self.post("/volumes/create", options).await
+    }
+
+    /// Volume by ID, ID prefix or name
+    pub async fn inspect_volume(&self, volume: &str) -> Result<Volume, Box<dyn Error>> {
+        self.get(&format!("/volumes/{}", encode(volume))).await
+    }
+
+    /// Remove a volume (fails with 409 while containers use it)
+    pub async fn remove_volume(&self, volume: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/volumes/{}", encode(volume))).await
+    }
+
+    /// Remove the volumes no container uses, narrowed down by `label` and `until` filters like
+    /// [`Client::prune_networks`]
+    pub async fn prune_volumes(&self, filters: &[(&str, &str)]) -> Result<VolumePruneReport, Box<dyn Error>> {
+        self.post(&format!("/volumes/prune?{}", filter_query(filters)), &json!({}))
+            .await
+    }
+
+    /// Copy a volume's data into a new volume `name`
+    pub async fn clone_volume(&self, volume: &str, name: &str) -> Result<Volume, Box<dyn Error>> {
+        self.post(&format!("/volumes/{}/clone", encode(volume)), &json!({ "name": name }))
+            .await
+    }
+
+    /// Save the current contents of a volume as the snapshot `name`
+    pub async fn snapshot_volume(&self, volume: &str, name: &str) -> Result<VolumeSnapshot, Box<dyn Error>> {
+        self.post(&format!("/volumes/{}/snapshots", encode(volume)), &json!({ "name": name }))
+            .await
+    }
+
+    /// Replace the contents of a volume with one of its snapshots
+    pub async fn restore_snapshot(&self, volume: &str, snapshot: &str) -> Result<(), Box<dyn Error>> {
+        self.post_empty(&format!("/volumes/{}/snapshots/{}/restore", encode(volume), encode(snapshot)))
+            .await
+    }
+
+    pub async fn remove_snapshot(&self, volume: &str, snapshot: &str) -> Result<(), Box<dyn Error>> {
+        self.delete(&format!("/volumes/{}/snapshots/{}", encode(volume), encode(snapshot)))
+            .await
+    }
+}
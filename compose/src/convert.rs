@@ -0,0 +1,271 @@
+use rocker_core::{ComposeError, ContainerConfig, HealthConfig, Mount, MountType, PortBinding};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::{PROJECT_LABEL, SERVICE_LABEL};
+
+// 名前付きボリュームから作る PersistentVolumeClaim の容量
+const DEFAULT_STORAGE: &str = "100Mi";
+
+// compose convert の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertFormat {
+    // 変数と extends を展開した compose ファイル（compose config と同じ）
+    Yaml,
+    // Kubernetes の Deployment・Service・PersistentVolumeClaim
+    Kubernetes,
+}
+
+impl FromStr for ConvertFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yaml" => Ok(ConvertFormat::Yaml),
+            "k8s" | "kubernetes" => Ok(ConvertFormat::Kubernetes),
+            _ => Err(ComposeError::InvalidConfig(format!("Invalid format: {} (expected yaml or k8s)", s)).into()),
+        }
+    }
+}
+
+// Kubernetes のオブジェクト名に使える形（小文字の英数字と -）にする
+pub(crate) fn object_name(name: &str) -> String {
+    let name: String = name
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    name.trim_matches('-').to_string()
+}
+
+// サービスのコンテナを動かす Deployment
+pub(crate) fn deployment(project: &str, service: &str, config: &ContainerConfig, replicas: usize) -> Value {
+    let labels = json!({ PROJECT_LABEL: project, SERVICE_LABEL: service });
+    let volumes: Vec<Value> = config.mounts.iter().enumerate().map(|(index, mount)| pod_volume(index, mount)).collect();
+    let uses_claims = config.mounts.iter().any(|mount| matches!(mount.mount_type, MountType::Volume));
+
+    let mut container = json!({
+        "name": object_name(service),
+        "image": config.image,
+    });
+    if let Some(entrypoint) = &config.entrypoint {
+        container["command"] = json!(entrypoint);
+    }
+    if let Some(cmd) = &config.cmd {
+        container["args"] = json!(cmd);
+    }
+    if let Some(working_dir) = &config.working_dir {
+        container["workingDir"] = json!(working_dir);
+    }
+    if !config.env.is_empty() {
+        let mut env: Vec<(&String, &String)> = config.env.iter().collect();
+        env.sort();
+        container["env"] = env.iter().map(|(name, value)| json!({ "name": name, "value": value })).collect();
+    }
+    if !config.port_bindings.is_empty() {
+        container["ports"] = config
+            .port_bindings
+            .iter()
+            .map(|binding| json!({ "containerPort": binding.container_port, "protocol": protocol(binding) }))
+            .collect();
+    }
+    if !config.mounts.is_empty() {
+        container["volumeMounts"] = config
+            .mounts
+            .iter()
+            .enumerate()
+            .map(|(index, mount)| {
+                json!({
+                    "name": volume_name(index, mount),
+                    "mountPath": mount.destination,
+                    "readOnly": mount.read_only,
+                })
+            })
+            .collect();
+    }
+    if let Some(resources) = resources(config) {
+        container["resources"] = resources;
+    }
+    if let Some(security_context) = security_context(service, config) {
+        container["securityContext"] = security_context;
+    }
+    if let Some(probe) = config.healthcheck.as_ref().and_then(liveness_probe) {
+        container["livenessProbe"] = probe;
+    }
+
+    let mut pod = json!({ "containers": [container] });
+    if !volumes.is_empty() {
+        pod["volumes"] = json!(volumes);
+    }
+    if !config.extra_hosts.is_empty() {
+        pod["hostAliases"] = config
+            .extra_hosts
+            .iter()
+            .map(|entry| json!({ "ip": entry.ip.to_string(), "hostnames": [entry.hostname] }))
+            .collect();
+    }
+    if let Some(stop_timeout) = config.stop_timeout {
+        pod["terminationGracePeriodSeconds"] = json!(stop_timeout);
+    }
+
+    let mut spec = json!({
+        "replicas": replicas,
+        "selector": { "matchLabels": labels },
+        "template": {
+            "metadata": { "labels": labels },
+            "spec": pod,
+        },
+    });
+    // ReadWriteOnce のボリュームは新旧の Pod で同時に使えない
+    if uses_claims {
+        spec["strategy"] = json!({ "type": "Recreate" });
+    }
+    json!({
+        "apiVersion": "apps/v1",
+        "kind": "Deployment",
+        "metadata": { "name": object_name(service), "labels": labels },
+        "spec": spec,
+    })
+}
+
+// 公開するポートがあるサービスの Service（ポートは compose ファイルの公開側の番号で受ける）
+pub(crate) fn service(project: &str, service: &str, config: &ContainerConfig) -> Option<Value> {
+    if config.port_bindings.is_empty() {
+        return None;
+    }
+    let labels = json!({ PROJECT_LABEL: project, SERVICE_LABEL: service });
+    let ports: Vec<Value> = config
+        .port_bindings
+        .iter()
+        .map(|binding| {
+            let port = binding.host_port.unwrap_or(binding.container_port);
+            json!({
+                "name": format!("{}-{}", port, binding.protocol),
+                "port": port,
+                "targetPort": binding.container_port,
+                "protocol": protocol(binding),
+            })
+        })
+        .collect();
+    Some(json!({
+        "apiVersion": "v1",
+        "kind": "Service",
+        "metadata": { "name": object_name(service), "labels": labels },
+        "spec": {
+            "selector": labels,
+            "ports": ports,
+        },
+    }))
+}
+
+// 名前付きボリュームの PersistentVolumeClaim
+pub(crate) fn persistent_volume_claim(project: &str, volume: &str) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "PersistentVolumeClaim",
+        "metadata": {
+            "name": object_name(volume),
+            "labels": { PROJECT_LABEL: project },
+        },
+        "spec": {
+            "accessModes": ["ReadWriteOnce"],
+            "resources": { "requests": { "storage": DEFAULT_STORAGE } },
+        },
+    })
+}
+
+fn protocol(binding: &PortBinding) -> String {
+    binding.protocol.to_string().to_ascii_uppercase()
+}
+
+fn volume_name(index: usize, mount: &Mount) -> String {
+    match mount.mount_type {
+        MountType::Volume if !mount.source.is_empty() => object_name(&mount.source),
+        _ => format!("volume-{}", index),
+    }
+}
+
+// マウントに対応する Pod のボリューム（バインドマウントは hostPath、tmpfs はメモリ上の emptyDir にする）
+fn pod_volume(index: usize, mount: &Mount) -> Value {
+    let name = volume_name(index, mount);
+    match mount.mount_type {
+        MountType::Volume if !mount.source.is_empty() => {
+            json!({ "name": name, "persistentVolumeClaim": { "claimName": name } })
+        }
+        MountType::Volume => json!({ "name": name, "emptyDir": {} }),
+        MountType::Bind => {
+            warn!(
+                "Bind mount {} is converted to a hostPath volume, which only works if the path exists on the node",
+                mount.source
+            );
+            json!({ "name": name, "hostPath": { "path": mount.source } })
+        }
+        MountType::Tmpfs => {
+            let mut empty_dir = json!({ "medium": "Memory" });
+            if let Some(size) = mount.tmpfs_size {
+                empty_dir["sizeLimit"] = json!(size.to_string());
+            }
+            json!({ "name": name, "emptyDir": empty_dir })
+        }
+    }
+}
+
+// cpus・メモリの制限を limits に、メモリの予約を requests にする
+fn resources(config: &ContainerConfig) -> Option<Value> {
+    let limits = &config.resource_limits;
+    let mut resources = json!({});
+    if let Some(cpus) = limits.cpus {
+        resources["limits"]["cpu"] = json!(format!("{}m", (cpus * 1000.0).round() as u64));
+    }
+    if let Some(memory) = limits.memory_bytes {
+        resources["limits"]["memory"] = json!(memory.to_string());
+    }
+    if let Some(memory) = limits.memory_reservation_bytes {
+        resources["requests"]["memory"] = json!(memory.to_string());
+    }
+    if limits.pids_limit.is_some() {
+        warn!("pids limits cannot be expressed in a Deployment and are ignored");
+    }
+    resources.as_object().is_some_and(|resources| !resources.is_empty()).then_some(resources)
+}
+
+fn security_context(service: &str, config: &ContainerConfig) -> Option<Value> {
+    let mut context = json!({});
+    if config.privileged {
+        context["privileged"] = json!(true);
+    }
+    if !config.cap_add.is_empty() {
+        context["capabilities"]["add"] = json!(config.cap_add);
+    }
+    if !config.cap_drop.is_empty() {
+        context["capabilities"]["drop"] = json!(config.cap_drop);
+    }
+    // Kubernetes は数値の UID・GID しか指定できない
+    if let Some(user) = &config.user {
+        let (uid, gid) = user.split_once(':').map_or((user.as_str(), None), |(uid, gid)| (uid, Some(gid)));
+        match (uid.parse::<u32>(), gid.map(str::parse::<u32>).transpose()) {
+            (Ok(uid), Ok(gid)) => {
+                context["runAsUser"] = json!(uid);
+                if let Some(gid) = gid {
+                    context["runAsGroup"] = json!(gid);
+                }
+            }
+            _ => warn!("User {} of service {} is not numeric and is ignored", user, service),
+        }
+    }
+    context.as_object().is_some_and(|context| !context.is_empty()).then_some(context)
+}
+
+// healthcheck を livenessProbe の exec にする（無効にしたヘルスチェックは使わない）
+fn liveness_probe(healthcheck: &HealthConfig) -> Option<Value> {
+    let command = healthcheck.command()?;
+    Some(json!({
+        "exec": { "command": command },
+        "periodSeconds": healthcheck.interval().as_secs(),
+        "timeoutSeconds": healthcheck.timeout().as_secs(),
+        "failureThreshold": healthcheck.retries(),
+        "initialDelaySeconds": healthcheck.start_period().as_secs(),
+    }))
+}
@@ -0,0 +1,111 @@
+use rocker_core::{Container, HealthStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+use tracing::info;
+
+use crate::client::Client;
+
+// 依存するコンテナの状態を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// depends_on（サービス名のリストか、サービス名ごとに condition を書くマッピング）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DependsOn {
+    List(Vec<String>),
+    Map(HashMap<String, Dependency>),
+}
+
+impl Default for DependsOn {
+    fn default() -> Self {
+        DependsOn::List(Vec::new())
+    }
+}
+
+impl DependsOn {
+    // 依存するサービスと、起動を待つ条件（名前順）
+    pub fn services(&self) -> Vec<(&String, Condition)> {
+        let mut services: Vec<(&String, Condition)> = match self {
+            DependsOn::List(list) => list.iter().map(|service| (service, Condition::ServiceStarted)).collect(),
+            DependsOn::Map(map) => map.iter().map(|(service, dependency)| (service, dependency.condition)).collect(),
+        };
+        services.sort_by_key(|(service, _)| *service);
+        services
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Dependency {
+    #[serde(default)]
+    condition: Condition,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    // コンテナが起動していればよい
+    #[default]
+    ServiceStarted,
+    // ヘルスチェックが healthy になるまで待つ
+    ServiceHealthy,
+    // コンテナが終了コード 0 で終了するまで待つ
+    ServiceCompletedSuccessfully,
+}
+
+// 依存するサービスの全てのコンテナが condition を満たすまで待つ（満たせなくなった場合はエラー）
+pub async fn wait_for(client: &Client, service: &str, containers: &[Container], condition: Condition) -> Result<(), Box<dyn Error>> {
+    if condition == Condition::ServiceStarted {
+        return Ok(());
+    }
+    if containers.is_empty() {
+        return Err(format!("Dependency {} has no containers", service).into());
+    }
+
+    for container in containers {
+        info!("Waiting for container {} ({:?})", container.name, condition);
+        loop {
+            let current: Container = client.get(&format!("/containers/{}", container.id)).await?;
+            if satisfied(&current, condition)? {
+                break;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+    Ok(())
+}
+
+fn satisfied(container: &Container, condition: Condition) -> Result<bool, Box<dyn Error>> {
+    let running = container.state.is_running();
+    match condition {
+        Condition::ServiceStarted => Ok(true),
+        Condition::ServiceHealthy => {
+            if container.config.healthcheck.is_none() {
+                return Err(format!("Container {} has no healthcheck configured", container.name).into());
+            }
+            match container.health.as_ref().map(|health| health.status) {
+                Some(HealthStatus::Healthy) if running => Ok(true),
+                Some(HealthStatus::Unhealthy) => Err(format!("Container {} is unhealthy", container.name).into()),
+                _ if !running && !container.state.is_created() => {
+                    Err(format!("Container {} exited before becoming healthy", container.name).into())
+                }
+                _ => Ok(false),
+            }
+        }
+        Condition::ServiceCompletedSuccessfully => {
+            if running || container.state.is_created() {
+                return Ok(false);
+            }
+            match container.exit_code {
+                Some(0) => Ok(true),
+                code => Err(format!(
+                    "Container {} didn't complete successfully: exit {}",
+                    container.name,
+                    code.unwrap_or(-1)
+                )
+                .into()),
+            }
+        }
+    }
+}
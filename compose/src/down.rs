@@ -0,0 +1,37 @@
+use rocker_core::ComposeError;
+use std::error::Error;
+use std::str::FromStr;
+
+// compose down のオプション
+#[derive(Debug, Clone, Default)]
+pub struct DownOptions {
+    // compose ファイルで定義したボリュームも削除する
+    pub remove_volumes: bool,
+    // サービスのイメージも削除する
+    pub remove_images: Option<RemoveImages>,
+    // compose ファイルに無くなったサービスのコンテナも削除する
+    pub remove_orphans: bool,
+    // コンテナを強制終了するまでの猶予（秒、指定しない場合はデーモンの既定値）
+    pub timeout: Option<u64>,
+}
+
+// --rmi で削除するイメージ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoveImages {
+    // 全てのサービスのイメージ
+    All,
+    // image を指定せずにビルドしたイメージだけ
+    Local,
+}
+
+impl FromStr for RemoveImages {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(RemoveImages::All),
+            "local" => Ok(RemoveImages::Local),
+            _ => Err(ComposeError::InvalidConfig(format!("Invalid --rmi value (expected all or local): {}", s)).into()),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use std::error::Error;
+
+// サービスの処理の失敗（元のエラーを source に残し、CLI がデーモンのエラーの種類で終了コードを選べるようにする）
+#[derive(Debug, thiserror::Error)]
+#[error("{message}: {source}")]
+pub struct ServiceError {
+    message: String,
+    #[source]
+    source: Box<dyn Error>,
+}
+
+impl ServiceError {
+    pub fn new(message: impl Into<String>, source: Box<dyn Error>) -> Self {
+        ServiceError {
+            message: message.into(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use rocker_core::Event;
+
+// compose events の 1 行（"2024-01-01 12:00:00.000000 container start <id> (name=..., service=...)"）
+pub(crate) fn format_event(event: &Event, service: &str) -> String {
+    let mut attributes: Vec<String> = event
+        .attributes
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect();
+    attributes.push(format!("service={}", service));
+    attributes.sort();
+    format!(
+        "{} {} {} {} ({})",
+        event.time.format("%Y-%m-%d %H:%M:%S%.6f"),
+        event.event_type,
+        event.action,
+        event.actor_id,
+        attributes.join(", ")
+    )
+}
+
+// compose events --json の 1 行
+pub(crate) fn event_json(event: &Event, service: &str) -> serde_json::Value {
+    serde_json::json!({
+        "time": event.time,
+        "type": event.event_type.to_string(),
+        "action": event.action,
+        "id": event.actor_id,
+        "service": service,
+        "attributes": event.attributes,
+    })
+}
@@ -0,0 +1,80 @@
+use rocker_core::ComposeError;
+use serde_yaml::{Mapping, Value};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::{interpolate, load_error, merge};
+
+// compose ファイルの各サービスの extends を展開する
+//
+// extends は `extends: service` か `extends: {file, service}` で書き、元のサービスの設定にこのサービスの
+// 設定を重ねる（重ね方は複数の compose ファイルと同じ）。file の相対パスは extends を書いたファイルから
+// 探し、サービスの相対パスは展開した後もプロジェクトのディレクトリから解決する。
+pub(crate) fn resolve(config: &mut Value, config_path: &Path, project_dir: &Path) -> Result<(), Box<dyn Error>> {
+    let Some(services) = config.get_mut("services").and_then(Value::as_mapping_mut) else {
+        return Ok(());
+    };
+    let originals = services.clone();
+    for (name, service) in services.iter_mut() {
+        let name = name.as_str().ok_or("Service names must be strings")?;
+        *service = extended_service(&originals, config_path, project_dir, name, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+// services の name のサービスを extends を展開して返す（chain は展開中のサービスで、循環の検出に使う）
+fn extended_service(
+    services: &Mapping,
+    config_path: &Path,
+    project_dir: &Path,
+    name: &str,
+    chain: &mut Vec<(PathBuf, String)>,
+) -> Result<Value, Box<dyn Error>> {
+    let mut service = services
+        .get(name)
+        .cloned()
+        .ok_or_else(|| {
+            let message = format!("Cannot extend service {}: not found in {}", name, config_path.display());
+            ComposeError::InvalidConfig(message)
+        })?;
+
+    let link = (config_path.to_path_buf(), name.to_string());
+    if chain.contains(&link) {
+        let message = format!("extends of service {} in {}", name, config_path.display());
+        return Err(ComposeError::CircularDependency(message).into());
+    }
+    chain.push(link);
+
+    let Some(extends) = service.as_mapping_mut().and_then(|mapping| mapping.remove("extends")) else {
+        return Ok(service);
+    };
+    let (file, base_name) = match &extends {
+        Value::String(base_name) => (None, base_name.clone()),
+        Value::Mapping(mapping) => (
+            mapping.get("file").and_then(Value::as_str),
+            mapping
+                .get("service")
+                .and_then(Value::as_str)
+                .ok_or_else(|| ComposeError::InvalidConfig(format!("extends of service {} needs a service", name)))?
+                .to_string(),
+        ),
+        _ => return Err(ComposeError::InvalidConfig(format!("Invalid extends of service {}", name)).into()),
+    };
+
+    let mut base = match file {
+        Some(file) => {
+            let base_path = config_path.parent().unwrap_or(Path::new(".")).join(file);
+            let context = format!("Failed to load {} extended by service {}", base_path.display(), name);
+            let base_config = interpolate::load_config(&base_path, project_dir).map_err(|e| load_error(context, e))?;
+            let base_services = base_config
+                .get("services")
+                .and_then(Value::as_mapping)
+                .cloned()
+                .unwrap_or_default();
+            extended_service(&base_services, &base_path, project_dir, &base_name, chain)?
+        }
+        None => extended_service(services, config_path, project_dir, &base_name, chain)?,
+    };
+    merge::merge(&mut base, service);
+    Ok(base)
+}
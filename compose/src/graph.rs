@@ -0,0 +1,119 @@
+use rocker_core::ComposeError;
+use serde_json::json;
+use std::error::Error;
+use std::fmt::Write;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::{ComposeProject, Condition};
+
+// compose config --graph の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    // Graphviz の dot 形式（dot -Tsvg などで図にする）
+    Dot,
+    Json,
+}
+
+impl FromStr for GraphFormat {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(ComposeError::InvalidConfig(format!("Invalid format: {} (expected dot or json)", s)).into()),
+        }
+    }
+}
+
+// Graphviz の文字列の中で使えるようにエスケープする
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Graphviz の ID として引用符で囲む
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", dot_escape(name))
+}
+
+fn condition_name(condition: Condition) -> &'static str {
+    match condition {
+        Condition::ServiceStarted => "service_started",
+        Condition::ServiceHealthy => "service_healthy",
+        Condition::ServiceCompletedSuccessfully => "service_completed_successfully",
+    }
+}
+
+impl ComposeProject {
+    // depends_on の依存グラフ（サービスは up が起動する順で、辺は依存するサービスから依存先へ向かう）
+    //
+    // 循環依存がある場合も警告を出して名前順で表示する（JSON の order は null）。
+    pub fn dependency_graph(&self, format: GraphFormat) -> Result<String, Box<dyn Error>> {
+        // 定義されていない依存先は辺にだけ現れる
+        let order = match self.resolve_dependencies() {
+            Ok(order) => Some(order.into_iter().filter(|name| self.config.services.contains_key(name)).collect::<Vec<_>>()),
+            Err(e) => {
+                warn!("{}", e);
+                None
+            }
+        };
+        let services: Vec<&str> = match &order {
+            Some(order) => order.iter().map(String::as_str).collect(),
+            None => self.config.services().map(|(name, _)| name).collect(),
+        };
+
+        match format {
+            GraphFormat::Dot => {
+                let mut dot = format!("digraph {} {{\n", dot_id(&self.project_name));
+                dot.push_str("  rankdir=LR;\n");
+                for name in &services {
+                    // ラベルはサービス名とイメージの 2 行
+                    writeln!(dot, "  {} [label=\"{}\\n{}\"];", dot_id(name), dot_escape(name), dot_escape(&self.image_name(name)))?;
+                }
+                for name in &services {
+                    for (dependency, condition) in self.config.services[*name].depends_on.services() {
+                        let mut attributes = Vec::new();
+                        if condition != Condition::ServiceStarted {
+                            attributes.push(format!("label={}", dot_id(condition_name(condition))));
+                        }
+                        // 定義されていないサービスへの依存は破線で示す
+                        if !self.config.services.contains_key(dependency) {
+                            attributes.push("style=dashed".to_string());
+                        }
+                        let attributes = if attributes.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" [{}]", attributes.join(", "))
+                        };
+                        writeln!(dot, "  {} -> {}{};", dot_id(name), dot_id(dependency), attributes)?;
+                    }
+                }
+                dot.push_str("}\n");
+                Ok(dot)
+            }
+            GraphFormat::Json => {
+                let nodes: Vec<_> = services
+                    .iter()
+                    .map(|name| {
+                        let depends_on: Vec<_> = self.config.services[*name]
+                            .depends_on
+                            .services()
+                            .into_iter()
+                            .map(|(dependency, condition)| {
+                                json!({
+                                    "service": dependency,
+                                    "condition": condition,
+                                    "defined": self.config.services.contains_key(dependency),
+                                })
+                            })
+                            .collect();
+                        json!({ "name": name, "image": self.image_name(name), "depends_on": depends_on })
+                    })
+                    .collect();
+                let graph = json!({ "project": self.project_name, "order": order, "services": nodes });
+                Ok(format!("{}\n", serde_json::to_string_pretty(&graph)?))
+            }
+        }
+    }
+}
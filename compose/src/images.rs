@@ -0,0 +1,91 @@
+use futures::future::join_all;
+use rocker_core::ComposeError;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::future::Future;
+use std::path::Path;
+
+use crate::client::{self, Lines, Progress};
+use crate::ServiceError;
+
+// サービスの pull_policy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullPolicy {
+    // 毎回 pull する（build のあるサービスはベースイメージを pull し直してビルドする）
+    Always,
+    // ローカルに無ければ pull（build のあるサービスはビルド）する
+    #[default]
+    #[serde(alias = "if_not_present")]
+    Missing,
+    // pull せず、ローカルに無ければエラーにする
+    Never,
+    // build のあるサービスを毎回ビルドし直す
+    Build,
+}
+
+impl PullPolicy {
+    // image だけのサービスのイメージに使う、クライアントの pull の方針
+    pub fn client_policy(self) -> client::PullPolicy {
+        match self {
+            PullPolicy::Always => client::PullPolicy::Always,
+            PullPolicy::Missing | PullPolicy::Build => client::PullPolicy::Missing,
+            PullPolicy::Never => client::PullPolicy::Never,
+        }
+    }
+}
+
+// compose build のオプション
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    // 複数のサービスを同時にビルドする
+    pub parallel: bool,
+    // ローカルにあってもベースイメージを pull し直す
+    pub pull: bool,
+}
+
+// ビルドコンテキストのディレクトリを tar にする（シンボリックリンクはリンクのまま入れる）
+//
+// エントリを名前順に並べるため、内容が同じなら同じ tar になる。
+pub async fn context_archive(context: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    if !context.is_dir() {
+        return Err(ComposeError::InvalidConfig(format!("Build context not found: {}", context.display())).into());
+    }
+    Ok(rocker_core::archive_dir(context).await?)
+}
+
+// デーモンが返す進捗を "サービス名 | " を付けて表示する（エラーの行を受け取ったらエラーを返す）
+pub async fn print_progress(prefix: &str, lines: Lines) -> Result<(), Box<dyn Error>> {
+    Progress::with_prefix(prefix).report(lines).await?;
+    Ok(())
+}
+
+// サービスごとの処理を順に（parallel の場合は同時に）行う
+//
+// 順に行う場合は最初の失敗で止め、同時に行う場合は全て終わってから失敗したサービスをまとめて返す。
+pub async fn run_all<F>(tasks: Vec<(String, F)>, parallel: bool, action: &str) -> Result<(), Box<dyn Error>>
+where
+    F: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    if !parallel {
+        for (service, task) in tasks {
+            task.await
+                .map_err(|e| ServiceError::new(format!("Failed to {} {}", action, service), e))?;
+        }
+        return Ok(());
+    }
+
+    let (services, tasks): (Vec<String>, Vec<F>) = tasks.into_iter().unzip();
+    let mut failed = Vec::new();
+    for (service, result) in services.into_iter().zip(join_all(tasks).await) {
+        if let Err(e) = result {
+            eprintln!("{} | ERROR: {}", service, e);
+            failed.push(service);
+        }
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Failed to {} {}", action, failed.join(", ")).into())
+    }
+}
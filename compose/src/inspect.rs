@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    BuildConfig, Command, ComposeConfig, ComposeProject, DeployConfig, DependsOn, DevelopConfig, EnvFile, Environment,
+    HealthcheckConfig, NetworkConfig, SecretConfig, ServiceConfig, ServicePort, ServiceSecret, ServiceVolume, VolumeConfig,
+};
+
+// compose ファイルの内容を読むためのアクセサ（リンターや構成図などのツール向け）
+//
+// 変数と extends は展開済みで、複数のファイルは重ねた後の内容を返す。名前を持つものは名前順に返す。
+
+// 名前順に並べたマップの要素
+fn sorted<V>(map: &HashMap<String, V>) -> impl Iterator<Item = (&str, &V)> {
+    let mut entries: Vec<(&str, &V)> = map.iter().map(|(name, value)| (name.as_str(), value)).collect();
+    entries.sort_by_key(|(name, _)| *name);
+    entries.into_iter()
+}
+
+impl ComposeConfig {
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    // サービスの名前と設定
+    pub fn services(&self) -> impl Iterator<Item = (&str, &ServiceConfig)> {
+        sorted(&self.services)
+    }
+
+    pub fn service(&self, name: &str) -> Option<&ServiceConfig> {
+        self.services.get(name)
+    }
+
+    // image を指定したサービスの名前とイメージ（build だけのサービスのイメージ名は ComposeProject::images）
+    pub fn images(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.services()
+            .filter_map(|(name, service)| Some((name, service.image.as_deref()?)))
+    }
+
+    // build を指定したサービスの名前とビルドコンテキスト（プロジェクトのディレクトリからの相対パス）
+    pub fn build_contexts(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.services()
+            .filter_map(|(name, service)| Some((name, service.build.as_ref()?.context())))
+    }
+
+    // トップレベルの networks（サービスが参加する default ネットワークは書かれていなければ含まない）
+    pub fn networks(&self) -> impl Iterator<Item = (&str, &NetworkConfig)> {
+        sorted(&self.networks)
+    }
+
+    pub fn volumes(&self) -> impl Iterator<Item = (&str, &VolumeConfig)> {
+        sorted(&self.volumes)
+    }
+
+    pub fn secrets(&self) -> impl Iterator<Item = (&str, &SecretConfig)> {
+        sorted(&self.secrets)
+    }
+
+    // x- で始まるトップレベルの拡張フィールド
+    pub fn extensions(&self) -> &HashMap<String, serde_yaml::Value> {
+        &self.extensions
+    }
+}
+
+impl ServiceConfig {
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    pub fn build(&self) -> Option<&BuildConfig> {
+        self.build.as_ref()
+    }
+
+    pub fn command(&self) -> Option<&Command> {
+        self.command.as_ref()
+    }
+
+    pub fn entrypoint(&self) -> Option<&Command> {
+        self.entrypoint.as_ref()
+    }
+
+    pub fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    pub fn working_dir(&self) -> Option<&str> {
+        self.working_dir.as_deref()
+    }
+
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    // env_file のパス（プロジェクトのディレクトリからの相対パス）
+    pub fn env_files(&self) -> &[String] {
+        match &self.env_file {
+            EnvFile::String(path) => std::slice::from_ref(path),
+            EnvFile::List(paths) => paths,
+        }
+    }
+
+    pub fn volumes(&self) -> &[ServiceVolume] {
+        &self.volumes
+    }
+
+    pub fn secrets(&self) -> &[ServiceSecret] {
+        &self.secrets
+    }
+
+    pub fn ports(&self) -> &[ServicePort] {
+        &self.ports
+    }
+
+    pub fn depends_on(&self) -> &DependsOn {
+        &self.depends_on
+    }
+
+    // restart（書かれていなければ None）
+    pub fn restart(&self) -> Option<&str> {
+        Some(self.restart_policy.as_str()).filter(|policy| !policy.is_empty())
+    }
+
+    // サービスが参加するネットワーク（書かれていなければ空で、default ネットワークに参加する）
+    pub fn networks(&self) -> &[String] {
+        &self.networks
+    }
+
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+
+    pub fn healthcheck(&self) -> Option<&HealthcheckConfig> {
+        self.healthcheck.as_ref()
+    }
+
+    pub fn dns(&self) -> &[String] {
+        self.dns.as_slice()
+    }
+
+    pub fn tmpfs(&self) -> &[String] {
+        self.tmpfs.as_slice()
+    }
+
+    pub fn privileged(&self) -> bool {
+        self.privileged
+    }
+
+    pub fn deploy(&self) -> Option<&DeployConfig> {
+        self.deploy.as_ref()
+    }
+
+    pub fn develop(&self) -> Option<&DevelopConfig> {
+        self.develop.as_ref()
+    }
+
+    // x- で始まる拡張フィールド（x-wait-for など）
+    pub fn extensions(&self) -> &HashMap<String, serde_yaml::Value> {
+        &self.extensions
+    }
+}
+
+impl BuildConfig {
+    pub fn context(&self) -> &str {
+        match self {
+            BuildConfig::String(context) => context,
+            BuildConfig::Object { context, .. } => context,
+        }
+    }
+
+    // ビルドコンテキストからの Rockerfile のパス（dockerfile）
+    pub fn rockerfile(&self) -> Option<&str> {
+        match self {
+            BuildConfig::String(_) => None,
+            BuildConfig::Object { rockerfile, .. } => rockerfile.as_deref(),
+        }
+    }
+
+    pub fn args(&self) -> Option<&HashMap<String, String>> {
+        match self {
+            BuildConfig::String(_) => None,
+            BuildConfig::Object { args, .. } => args.as_ref(),
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+
+    pub fn external(&self) -> bool {
+        self.external
+    }
+
+    pub fn driver_opts(&self) -> &HashMap<String, String> {
+        &self.driver_opts
+    }
+}
+
+impl VolumeConfig {
+    pub fn driver(&self) -> Option<&str> {
+        self.driver.as_deref()
+    }
+
+    pub fn external(&self) -> bool {
+        self.external
+    }
+
+    pub fn driver_opts(&self) -> &HashMap<String, String> {
+        &self.driver_opts
+    }
+}
+
+impl DeployConfig {
+    pub fn replicas(&self) -> Option<usize> {
+        self.replicas
+    }
+}
+
+impl ComposeProject {
+    // 読み込んだ compose ファイルの内容
+    pub fn config(&self) -> &ComposeConfig {
+        &self.config
+    }
+
+    pub fn name(&self) -> &str {
+        &self.project_name
+    }
+
+    // 相対パス（ビルドコンテキスト・env_file・バインドマウント）の基準のディレクトリ
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    // 全てのサービスの名前と使うイメージ（build だけのサービスは <プロジェクト名>_<サービス名>）
+    pub fn images(&self) -> impl Iterator<Item = (&str, String)> {
+        self.config.services().map(|(name, _)| (name, self.image_name(name)))
+    }
+}
@@ -0,0 +1,303 @@
+use rocker_core::ComposeError;
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::Path;
+use tracing::warn;
+
+// compose ファイルを読み込み、全ての文字列の変数を展開する
+//
+// 変数はプロセスの環境変数、無ければプロジェクトのディレクトリの .env ファイルから取る。アンカーの
+// エイリアスは読み込み時に展開され、`<<: *anchor` のマージキーもここで適用する。
+pub(crate) fn load_config(config_path: &Path, project_dir: &Path) -> Result<Value, Box<dyn Error>> {
+    let mut config = read_config(config_path)?;
+    let env_file = read_env_file(project_dir)?;
+    let lookup = |name: &str| std::env::var(name).ok().or_else(|| env_file.get(name).cloned());
+
+    interpolate_value(&mut config, &lookup)?;
+    Ok(config)
+}
+
+// compose ファイルの文字列が参照する変数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    // ${VAR:-default}・${VAR-default} の既定値（展開する前の値）
+    pub default: Option<String>,
+    // ${VAR:?err}・${VAR?err} のように値が無ければエラーになるか
+    pub required: bool,
+    // 環境変数か .env ファイルの値（未設定なら None）
+    pub value: Option<String>,
+}
+
+// compose ファイルの変数を variables に加える（既定値の中で参照する変数も含める）
+pub(crate) fn collect_variables(
+    config_path: &Path,
+    project_dir: &Path,
+    variables: &mut BTreeMap<String, Variable>,
+) -> Result<(), Box<dyn Error>> {
+    let config = read_config(config_path)?;
+    let env_file = read_env_file(project_dir)?;
+    let lookup = |name: &str| std::env::var(name).ok().or_else(|| env_file.get(name).cloned());
+
+    let mut strings = Vec::new();
+    collect_strings(&config, &mut strings);
+    for s in strings {
+        scan(s, &lookup, variables)?;
+    }
+    Ok(())
+}
+
+fn read_config(config_path: &Path) -> Result<Value, Box<dyn Error>> {
+    let config_content = std::fs::read_to_string(config_path)?;
+    let mut config: Value = serde_yaml::from_str(&config_content)?;
+    config.apply_merge()?;
+    Ok(config)
+}
+
+// .env はサービスの env_file や run --env-file と同じ形式で読む
+fn read_env_file(project_dir: &Path) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let env_path = project_dir.join(".env");
+    if !env_path.is_file() {
+        return Ok(HashMap::new());
+    }
+    Ok(rocker_core::parse_env_file(&std::fs::read_to_string(&env_path)?)
+        .map_err(|e| ComposeError::InvalidConfig(format!("Invalid env file {}: {}", env_path.display(), e)))?
+        .into_iter()
+        .collect())
+}
+
+fn collect_strings<'a>(value: &'a Value, strings: &mut Vec<&'a str>) {
+    match value {
+        Value::String(s) => strings.push(s),
+        Value::Sequence(items) => items.iter().for_each(|item| collect_strings(item, strings)),
+        Value::Mapping(mapping) => mapping.values().for_each(|item| collect_strings(item, strings)),
+        _ => {}
+    }
+}
+
+// interpolate と同じ規則で文字列の変数を探す
+fn scan(
+    input: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    variables: &mut BTreeMap<String, Variable>,
+) -> Result<(), Box<dyn Error>> {
+    let mut rest = input;
+    while let Some(pos) = rest.find('$') {
+        rest = &rest[pos + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            rest = after;
+            continue;
+        }
+        let (name, operator, argument) = if let Some(after) = rest.strip_prefix('{') {
+            let end = closing_brace(after).ok_or_else(|| {
+                ComposeError::InvalidConfig(format!("Invalid interpolation format: missing '}}' in {:?}", input))
+            })?;
+            rest = &after[end + 1..];
+            split_expression(&after[..end])?
+        } else {
+            let len = variable_name_len(rest);
+            let name = &rest[..len];
+            rest = &rest[len..];
+            if name.is_empty() {
+                continue;
+            }
+            (name, "", "")
+        };
+
+        let variable = variables.entry(name.to_string()).or_insert_with(|| Variable {
+            name: name.to_string(),
+            default: None,
+            required: false,
+            value: lookup(name),
+        });
+        match operator {
+            ":-" | "-" => {
+                variable.default.get_or_insert_with(|| argument.to_string());
+            }
+            ":?" | "?" => variable.required = true,
+            _ => {}
+        }
+        scan(argument, lookup, variables)?;
+    }
+    Ok(())
+}
+
+fn interpolate_value(value: &mut Value, lookup: &dyn Fn(&str) -> Option<String>) -> Result<(), Box<dyn Error>> {
+    match value {
+        Value::String(s) => *s = interpolate(s, lookup)?,
+        Value::Sequence(items) => {
+            for item in items {
+                interpolate_value(item, lookup)?;
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                interpolate_value(item, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// 文字列の $VAR・${VAR}・${VAR:-default}・${VAR-default}・${VAR:?err}・${VAR?err} を展開する（$$ は $ になる）
+//
+// : を付けた形は空の変数も未設定として扱う。既定値やエラーの無い未設定の変数は空文字列になる。
+fn interpolate(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, Box<dyn Error>> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            output.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = closing_brace(after).ok_or_else(|| {
+                ComposeError::InvalidConfig(format!("Invalid interpolation format: missing '}}' in {:?}", input))
+            })?;
+            output.push_str(&substitute(&after[..end], lookup)?);
+            rest = &after[end + 1..];
+        } else {
+            let len = variable_name_len(rest);
+            if len == 0 {
+                output.push('$');
+                continue;
+            }
+            output.push_str(&variable(&rest[..len], lookup));
+            rest = &rest[len..];
+        }
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+// ${ の後ろから対応する } の位置（既定値の中の ${...} も数える）
+fn closing_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn variable_name_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(s.len())
+}
+
+// ${...} の中身を変数名・演算子（:-・:?・-・? か空）・その後ろに分ける
+fn split_expression(expr: &str) -> Result<(&str, &str, &str), Box<dyn Error>> {
+    let (name, modifier) = expr.split_at(variable_name_len(expr));
+    let (operator, argument) = [":-", ":?", "-", "?"]
+        .iter()
+        .find_map(|op| modifier.strip_prefix(*op).map(|argument| (*op, argument)))
+        .unwrap_or(("", modifier));
+    if name.is_empty() || (operator.is_empty() && !argument.is_empty()) {
+        return Err(ComposeError::InvalidConfig(format!("Invalid interpolation format: ${{{}}}", expr)).into());
+    }
+    Ok((name, operator, argument))
+}
+
+// ${...} の中身を展開する
+fn substitute(expr: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, Box<dyn Error>> {
+    let (name, operator, argument) = split_expression(expr)?;
+    let value = lookup(name);
+    let unset = match operator {
+        ":-" | ":?" => value.as_deref().is_none_or(str::is_empty),
+        "-" | "?" => value.is_none(),
+        _ => return Ok(variable(name, lookup)),
+    };
+
+    if !unset {
+        return Ok(value.unwrap_or_default());
+    }
+    match operator {
+        ":-" | "-" => interpolate(argument, lookup),
+        _ => {
+            let message = interpolate(argument, lookup)?;
+            Err(ComposeError::InvalidConfig(format!("Required variable {} is missing a value: {}", name, message))
+                .into())
+        }
+    }
+}
+
+fn variable(name: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    lookup(name).unwrap_or_else(|| {
+        warn!("The {} variable is not set. Defaulting to a blank string.", name);
+        String::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "TAG" => Some("1.27".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn expand(input: &str) -> Result<String, String> {
+        interpolate(input, &lookup).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn substitutes_plain_and_braced_variables() {
+        assert_eq!(expand("nginx:$TAG").unwrap(), "nginx:1.27");
+        assert_eq!(expand("nginx:${TAG}-alpine").unwrap(), "nginx:1.27-alpine");
+        assert_eq!(expand("${MISSING}x").unwrap(), "x");
+    }
+
+    #[test]
+    fn double_dollar_is_a_literal_dollar() {
+        assert_eq!(expand("$$TAG").unwrap(), "$TAG");
+        assert_eq!(expand("echo $${TAG} $$$TAG").unwrap(), "echo ${TAG} $1.27");
+        assert_eq!(expand("costs 5$").unwrap(), "costs 5$");
+    }
+
+    #[test]
+    fn defaults_distinguish_empty_from_unset() {
+        assert_eq!(expand("${EMPTY:-fallback}").unwrap(), "fallback");
+        assert_eq!(expand("${EMPTY-fallback}").unwrap(), "");
+        assert_eq!(expand("${MISSING-fallback}").unwrap(), "fallback");
+        assert_eq!(expand("${MISSING:-${TAG}}").unwrap(), "1.27");
+    }
+
+    #[test]
+    fn required_variables_fail_with_the_message() {
+        let error = expand("${MISSING:?set MISSING to the $TAG image}").unwrap_err();
+        assert!(error.contains("MISSING"), "{}", error);
+        assert!(error.contains("set MISSING to the 1.27 image"), "{}", error);
+        assert!(expand("${EMPTY:?must not be empty}").is_err());
+        assert_eq!(expand("${EMPTY?may be empty}").unwrap(), "");
+        assert_eq!(expand("${TAG:?unused}").unwrap(), "1.27");
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        assert!(expand("${TAG").is_err());
+        assert!(expand("${}").is_err());
+        assert!(expand("${TAG oops}").is_err());
+    }
+
+    #[test]
+    fn scan_records_defaults_and_required_variables() {
+        let mut variables = BTreeMap::new();
+        scan("${A:-${B}} ${C:?needed} $$D $TAG", &lookup, &mut variables).unwrap();
+        let names: Vec<_> = variables.keys().map(String::as_str).collect();
+        assert_eq!(names, ["A", "B", "C", "TAG"]);
+        assert_eq!(variables["A"].default.as_deref(), Some("${B}"));
+        assert!(variables["C"].required);
+        assert_eq!(variables["TAG"].value.as_deref(), Some("1.27"));
+    }
+}
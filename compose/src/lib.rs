@@ -1,9 +1,78 @@
+use rocker_client as client;
+use rocker_core::{
+    ComposeError, Container, ContainerConfig, ContainerTop, Event, ExecConfig, HealthConfig, HostEntry, Image, Mount, MountType, Network,
+    EventType, NetworkMode, PortProtocol, RestartPolicy, Volume, WaitFor,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
-use std::path::Path;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use tracing::{info, error, warn};
 
+mod convert;
+mod depends;
+mod down;
+mod error;
+mod events;
+mod extends;
+mod graph;
+mod images;
+mod inspect;
+mod interpolate;
+mod logs;
+mod merge;
+mod ports;
+mod ps;
+mod resources;
+mod run;
+mod secrets;
+mod stack;
+mod up;
+mod validate;
+mod volumes;
+mod wait;
+mod watch;
+
+pub use client::{Client, ClientError};
+pub use convert::ConvertFormat;
+pub use depends::{Condition, DependsOn};
+pub use down::{DownOptions, RemoveImages};
+pub use error::ServiceError;
+pub use graph::GraphFormat;
+pub use images::{BuildOptions, PullPolicy};
+pub use interpolate::Variable;
+pub use logs::LogsOptions;
+pub use ports::{PortConfig, PublishedPort, ServicePort};
+pub use resources::{ByteSize, CpuCount, ResourceSpec, ResourcesConfig, UlimitConfig};
+pub use run::{ExecOptions, RunOptions};
+pub use secrets::{FileMode, IdValue, SecretConfig, SecretMount, ServiceSecret};
+pub use stack::{stack_deploy_command, stack_ls_command, stack_ps_command, stack_rm_command};
+pub use up::UpOptions;
+pub use volumes::{BindOptions, ServiceVolume, TmpfsOptions, VolumeMount, VolumeOptions};
+pub use watch::{DevelopConfig, WatchAction, WatchRule};
+
+use up::Recreate;
+use wait::Readiness;
+use watch::{Changes, Watcher};
+
+// compose が作成したコンテナに付けるラベル（ps・top などはこのラベルでプロジェクトのコンテナを探す）
+pub const PROJECT_LABEL: &str = "com.rocker.compose.project";
+pub const SERVICE_LABEL: &str = "com.rocker.compose.service";
+pub const CONTAINER_NUMBER_LABEL: &str = "com.rocker.compose.container-number";
+pub const ONEOFF_LABEL: &str = "com.rocker.compose.oneoff";
+pub const CONFIG_HASH_LABEL: &str = "com.rocker.compose.config-hash";
+pub const NETWORK_LABEL: &str = "com.rocker.compose.network";
+pub const VOLUME_LABEL: &str = "com.rocker.compose.volume";
+pub const SECRET_LABEL: &str = "com.rocker.compose.secret";
+
+// -f を指定しない場合に読む compose ファイル（override はあれば重ねる）
+const DEFAULT_CONFIG_FILE: &str = "rocker-compose.yaml";
+const DEFAULT_OVERRIDE_FILE: &str = "rocker-compose.override.yaml";
+
+// networks を指定しないサービスが参加するネットワーク
+const DEFAULT_NETWORK: &str = "default";
+
 // Compose設定ファイルの構造体
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ComposeConfig {
@@ -13,21 +82,35 @@ pub struct ComposeConfig {
     networks: HashMap<String, NetworkConfig>,
     #[serde(default)]
     volumes: HashMap<String, VolumeConfig>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    secrets: HashMap<String, SecretConfig>,
+    // x- で始まる拡張フィールド（compose 自体は使わないが、読み書きで失わないように残す）
+    #[serde(flatten)]
+    extensions: HashMap<String, serde_yaml::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ServiceConfig {
     image: Option<String>,
     build: Option<BuildConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pull_policy: Option<PullPolicy>,
     command: Option<Command>,
+    entrypoint: Option<Command>,
+    user: Option<String>,
+    working_dir: Option<String>,
     #[serde(default)]
     environment: Environment,
     #[serde(default)]
-    volumes: Vec<String>,
+    env_file: EnvFile,
     #[serde(default)]
-    ports: Vec<String>,
+    volumes: Vec<ServiceVolume>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    secrets: Vec<ServiceSecret>,
     #[serde(default)]
-    depends_on: Vec<String>,
+    ports: Vec<ServicePort>,
+    #[serde(default)]
+    depends_on: DependsOn,
     #[serde(rename = "restart", default)]
     restart_policy: String,
     #[serde(default)]
@@ -36,6 +119,45 @@ pub struct ServiceConfig {
     labels: HashMap<String, String>,
     #[serde(default)]
     healthcheck: Option<HealthcheckConfig>,
+    #[serde(default)]
+    extra_hosts: ExtraHosts,
+    #[serde(default)]
+    dns: StringOrList,
+    #[serde(default)]
+    tmpfs: StringOrList,
+    #[serde(default)]
+    cap_add: Vec<String>,
+    #[serde(default)]
+    cap_drop: Vec<String>,
+    #[serde(default)]
+    privileged: bool,
+    cpus: Option<CpuCount>,
+    mem_limit: Option<ByteSize>,
+    #[serde(default)]
+    ulimits: HashMap<String, UlimitConfig>,
+    stop_grace_period: Option<String>,
+    #[serde(default)]
+    deploy: Option<DeployConfig>,
+    #[serde(default)]
+    develop: Option<DevelopConfig>,
+    // x- で始まる拡張フィールド（x-wait-for だけは起動を待つ依存先として使う）
+    #[serde(flatten)]
+    extensions: HashMap<String, serde_yaml::Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DeployConfig {
+    replicas: Option<usize>,
+    resources: Option<ResourcesConfig>,
+    // stack deploy のみ
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    placement: Option<PlacementConfig>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PlacementConfig {
+    #[serde(default)]
+    constraints: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -58,6 +180,55 @@ impl Default for Environment {
     }
 }
 
+// 文字列 1 つか文字列のリスト（dns・tmpfs）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StringOrList {
+    String(String),
+    List(Vec<String>),
+}
+
+impl Default for StringOrList {
+    fn default() -> Self {
+        StringOrList::List(Vec::new())
+    }
+}
+
+impl StringOrList {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            StringOrList::String(value) => std::slice::from_ref(value),
+            StringOrList::List(values) => values,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum EnvFile {
+    String(String),
+    List(Vec<String>),
+}
+
+impl Default for EnvFile {
+    fn default() -> Self {
+        EnvFile::List(Vec::new())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExtraHosts {
+    List(Vec<String>),
+    Map(HashMap<String, String>),
+}
+
+impl Default for ExtraHosts {
+    fn default() -> Self {
+        ExtraHosts::List(Vec::new())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum BuildConfig {
@@ -104,31 +275,56 @@ pub struct VolumeConfig {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthcheckConfig {
-    test: Command,
+    test: Option<Command>,
     interval: Option<String>,
     timeout: Option<String>,
     retries: Option<u32>,
     start_period: Option<String>,
+    #[serde(default)]
+    disable: bool,
+}
+
+impl HealthcheckConfig {
+    // コンテナのヘルスチェックの設定にする（文字列の test はシェルで実行する）
+    //
+    // test を書かなければイメージの HEALTHCHECK の test をデーモンが使い、interval などだけを変える。
+    fn to_health_config(&self) -> Result<HealthConfig, Box<dyn Error>> {
+        let test = match (&self.test, self.disable) {
+            (_, true) => vec!["NONE".to_string()],
+            (None, false) => Vec::new(),
+            (Some(Command::String(command)), false) => vec!["CMD-SHELL".to_string(), command.clone()],
+            (Some(Command::List(list)), false) => list.clone(),
+        };
+        let seconds = |value: &Option<String>| value.as_deref().map(parse_duration).transpose();
+        Ok(HealthConfig {
+            test,
+            interval: seconds(&self.interval)?,
+            timeout: seconds(&self.timeout)?,
+            retries: self.retries,
+            start_period: seconds(&self.start_period)?,
+        })
+    }
 }
 
 pub struct ComposeProject {
     config: ComposeConfig,
     project_name: String,
-    project_dir: std::path::PathBuf,
+    project_dir: PathBuf,
 }
 
 impl ComposeProject {
+    // config_paths の compose ファイルを順に重ねて読む（空の場合は既定のファイル）
     pub fn new<P: AsRef<Path>>(
-        config_path: P,
+        config_paths: &[P],
         project_name: Option<String>,
     ) -> Result<Self, Box<dyn Error>> {
-        let config_path = config_path.as_ref();
-        let config_content = std::fs::read_to_string(config_path)?;
+        let config_paths = config_files(config_paths);
+        let project_dir = config_paths[0].parent().unwrap_or(Path::new(".")).to_path_buf();
         
-        let config: ComposeConfig = serde_yaml::from_str(&config_content)?;
+        // 変数を展開してから設定として読む
+        let config = parse_config(load_configs(&config_paths, &project_dir)?, &config_paths)?;
         
-        // プロジェクト名とディレクトリを取得
-        let project_dir = config_path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        // プロジェクト名を取得
         let project_name = project_name.unwrap_or_else(|| {
             project_dir
                 .file_name()
@@ -144,8 +340,26 @@ impl ComposeProject {
         })
     }
     
-    pub async fn up(&self, detached: bool) -> Result<(), Box<dyn Error>> {
+    // 指定したサービス（空なら全てのサービス）とその依存先のコンテナを設定に合わせて起動する
+    //
+    // 設定かイメージが前回の作成時から変わったコンテナは作り直す。
+    pub async fn up(&self, services: &[String], options: &UpOptions) -> Result<(), Box<dyn Error>> {
         info!("Starting project: {}", self.project_name);
+        let recreate = options.recreate()?;
+        
+        // 起動するサービス（依存関係の順）
+        let mut selected: std::collections::HashSet<String> =
+            self.select_services(services)?.into_iter().cloned().collect();
+        if !options.no_deps {
+            for service_name in selected.clone() {
+                selected.extend(self.dependencies(&service_name)?);
+            }
+        }
+        let service_order: Vec<String> = self
+            .resolve_dependencies()?
+            .into_iter()
+            .filter(|service_name| selected.contains(service_name))
+            .collect();
         
         // ネットワークの作成
         self.create_networks().await?;
@@ -153,69 +367,662 @@ impl ComposeProject {
         // ボリュームの作成
         self.create_volumes().await?;
         
-        // 依存関係グラフの構築
-        let service_order = self.resolve_dependencies()?;
+        // 秘密情報の作成
+        self.create_secrets().await?;
+        
+        if options.build {
+            self.build(&service_order, &BuildOptions::default()).await?;
+        }
+        
+        // サービスの起動（コンテナ数を合わせる）
+        for service_name in &service_order {
+            let replicas = options.scale.get(service_name).copied().unwrap_or_else(|| self.replicas(service_name));
+            self.scale_service(service_name, replicas, recreate).await?;
+        }
         
-        // サービスの起動
-        for service_name in service_order {
-            self.start_service(&service_name, detached).await?;
+        // --wait はデタッチモードで起動し、全てのコンテナの準備が整うまで待って戻る
+        let detach = options.detach || options.wait;
+        if options.wait {
+            self.wait_until_ready(&service_order, options.wait_timeout).await?;
         }
         
-        if !detached {
+        if options.watch {
+            // Ctrl+C まで develop.watch のパスの変更をコンテナに反映する
+            self.watch_changes(&service_order).await?;
+        } else if !detach {
             info!("Services started. Press Ctrl+C to stop...");
             // 非デタッチモードの場合、Ctrl+Cを待ち受ける
             tokio::signal::ctrl_c().await?;
-            self.down(false).await?;
+        }
+        if !detach {
+            self.down(&DownOptions::default()).await?;
         }
         
         Ok(())
     }
     
-    pub async fn down(&self, remove_volumes: bool) -> Result<(), Box<dyn Error>> {
+    pub async fn down(&self, options: &DownOptions) -> Result<(), Box<dyn Error>> {
         info!("Stopping project: {}", self.project_name);
         
         // サービスの停止と削除（依存関係の逆順）
         let service_order = self.resolve_dependencies()?;
         for service_name in service_order.iter().rev() {
-            self.stop_service(service_name).await?;
+            self.stop_service(service_name, options.timeout).await?;
         }
         
-        // ネットワークの削除
+        // compose ファイルに無いサービスのコンテナ（残っているとネットワークを削除できない）
+        self.remove_orphans(options).await?;
+        
+        // ネットワークと秘密情報の削除
         self.remove_networks().await?;
+        self.remove_secrets().await?;
         
         // ボリュームの削除（オプションで）
-        if remove_volumes {
+        if options.remove_volumes {
             self.remove_volumes().await?;
         }
         
+        if let Some(remove_images) = options.remove_images {
+            self.remove_images(remove_images).await?;
+        }
+        
+        Ok(())
+    }
+    
+    // 指定したサービス（空なら全てのサービス）のコンテナのログを表示する
+    pub async fn logs(&self, services: &[String], options: &LogsOptions) -> Result<(), Box<dyn Error>> {
+        let mut sources = Vec::new();
+        for service in self.select_services(services)? {
+            let containers = self.service_containers(service).await?;
+            // 複数のコンテナを持つサービスはプレフィックスに番号を付ける
+            let numbered = containers.len() > 1;
+            for (number, container) in containers {
+                sources.push(logs::LogSource {
+                    prefix: if numbered { format!("{}_{}", service, number) } else { service.clone() },
+                    container: container.id,
+                });
+            }
+        }
+        logs::print_logs(&Client::new(), sources, options).await
+    }
+    
+    // プロジェクトのコンテナを一覧する（all を付けなければ動作中のもののみ）
+    //
+    // サービスを指定しなければ、compose ファイルから消したサービスのコンテナもラベルから見つけて含める。
+    pub async fn ps(&self, services: &[String], all: bool, quiet: bool) -> Result<(), Box<dyn Error>> {
+        let selected = self.select_services(services)?;
+        let containers: Vec<Container> = self
+            .project_containers(all)
+            .await?
+            .into_iter()
+            .filter(|container| if services.is_empty() {
+                !container.config.labels.contains_key(ONEOFF_LABEL)
+            } else {
+                is_service_container(container, &selected)
+            })
+            .collect();
+        
+        if quiet {
+            for container in &containers {
+                println!("{}", container.id);
+            }
+        } else {
+            ps::print_containers(&containers);
+        }
+        Ok(())
+    }
+    
+    // 動作中のサービスのコンテナのプロセスを表示する
+    pub async fn top(&self, services: &[String]) -> Result<(), Box<dyn Error>> {
+        let services = self.select_services(services)?;
+        let client = Client::new();
+        let mut containers: Vec<Container> = self
+            .project_containers(false)
+            .await?
+            .into_iter()
+            .filter(|container| is_service_container(container, &services))
+            .collect();
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+        
+        for (index, container) in containers.iter().enumerate() {
+            let top: ContainerTop = match client.get(&format!("/containers/{}/top", container.id)).await {
+                Ok(top) => top,
+                // 一覧を取ってから停止・削除されたコンテナは飛ばす
+                Err(e) if e
+                    .downcast_ref::<ClientError>()
+                    .is_some_and(|e| matches!(e.status.as_u16(), 404 | 409)) => continue,
+                Err(e) => return Err(e),
+            };
+            if index > 0 {
+                println!();
+            }
+            println!("{}", container.name);
+            ps::print_top(&top);
+        }
+        Ok(())
+    }
+    
+    // サービスの動作中のコンテナのリソース使用量を表示する（CPU 使用率のため 1 秒空けて 2 回読む）
+    pub async fn stats(&self, services: &[String]) -> Result<(), Box<dyn Error>> {
+        let services = self.select_services(services)?;
+        let client = Client::new();
+        let mut containers: Vec<Container> = self
+            .project_containers(false)
+            .await?
+            .into_iter()
+            .filter(|container| is_service_container(container, &services))
+            .collect();
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let samples = futures::future::join_all(containers.iter().map(|container| {
+            let client = &client;
+            async move {
+                let mut stats = client.stream_container_stats(&container.id).await?;
+                let (previous, current) = (stats.next().await?, stats.next().await?);
+                Ok::<_, Box<dyn Error>>(previous.zip(current).map(|(previous, current)| (container.name.clone(), previous, current)))
+            }
+        }))
+        .await;
+
+        let mut rows = Vec::new();
+        for sample in samples {
+            match sample {
+                Ok(Some(row)) => rows.push(row),
+                Ok(None) => {}
+                // 一覧を取ってから停止・削除されたコンテナは飛ばす
+                Err(e) if e
+                    .downcast_ref::<ClientError>()
+                    .is_some_and(|e| matches!(e.status.as_u16(), 404 | 409)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        ps::print_stats(&rows);
+        Ok(())
+    }
+
+    // 指定したサービスの既存のコンテナを起動する（依存関係の順、コンテナを作成はしない）
+    pub async fn start(&self, services: &[String]) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        for service_name in self.ordered_services(services)? {
+            let containers = self.service_containers(&service_name).await?;
+            if containers.is_empty() {
+                warn!("Service {} has no container to start", service_name);
+                continue;
+            }
+            for (_, container) in containers {
+                if container.state.is_running() || container.state.is_paused() {
+                    continue;
+                }
+                info!("Starting container: {}", container.name);
+                client.post_empty(&format!("/containers/{}/start", container.id)).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    // 指定したサービスのコンテナを削除せずに停止する（依存関係の逆順）
+    pub async fn stop(&self, services: &[String], timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
+        for service_name in self.ordered_services(services)?.iter().rev() {
+            for (_, container) in self.service_containers(service_name).await? {
+                stop_container(&container, timeout).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    // 指定したサービスのコンテナを停止してから起動し直す（停止は依存関係の逆順、起動は依存関係の順）
+    pub async fn restart(&self, services: &[String], timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
+        self.stop(services, timeout).await?;
+        self.start(services).await
+    }
+    
+    // 指定したサービスの動作中のコンテナを一時停止する
+    pub async fn pause(&self, services: &[String]) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        for service_name in self.ordered_services(services)?.iter().rev() {
+            for (_, container) in self.service_containers(service_name).await? {
+                if !container.state.is_running() {
+                    continue;
+                }
+                info!("Pausing container: {}", container.name);
+                client.post_empty(&format!("/containers/{}/pause", container.id)).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    // 指定したサービスの一時停止したコンテナを再開する
+    pub async fn unpause(&self, services: &[String]) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        for service_name in self.ordered_services(services)? {
+            for (_, container) in self.service_containers(&service_name).await? {
+                if !container.state.is_paused() {
+                    continue;
+                }
+                info!("Unpausing container: {}", container.name);
+                client.post_empty(&format!("/containers/{}/unpause", container.id)).await?;
+            }
+        }
+        Ok(())
+    }
+    
+    // プロジェクトのコンテナのイベントを受け取り続けて表示する（Ctrl+C で終わる）
+    //
+    // コンテナのイベントにはラベルが付くため、プロジェクトのラベルでデーモン側で絞り込む。
+    pub async fn events(&self, services: &[String], json: bool) -> Result<(), Box<dyn Error>> {
+        let selected = self.select_services(services)?;
+        let client = Client::new();
+        let project_filter = format!("{}={}", PROJECT_LABEL, self.project_name);
+        let mut events = client.events(&[("label", project_filter.as_str())]).await?;
+        
+        loop {
+            let event = tokio::select! {
+                _ = tokio::signal::ctrl_c() => return Ok(()),
+                event = events.next() => event?.ok_or("The daemon closed the event stream")?,
+            };
+            if event.event_type != EventType::Container {
+                continue;
+            }
+            let Some(service) = event.attributes.get(SERVICE_LABEL) else {
+                continue;
+            };
+            if !services.is_empty() && !selected.contains(&service) {
+                continue;
+            }
+            if json {
+                println!("{}", events::event_json(&event, service));
+            } else {
+                println!("{}", events::format_event(&event, service));
+            }
+        }
+    }
+    
+    // サービスの index 番目のコンテナの container_port が公開されているホストのアドレスを表示する
+    pub async fn port(&self, service_name: &str, container_port: u16, protocol: PortProtocol, index: usize) -> Result<(), Box<dyn Error>> {
+        self.select_services(std::slice::from_ref(&service_name.to_string()))?;
+        let container = self.service_containers(service_name).await?
+            .into_iter()
+            .find(|(number, _)| *number == index)
+            .map(|(_, container)| container)
+            .ok_or_else(|| {
+                ComposeError::InvalidConfig(format!("Service {} has no container with index {}", service_name, index))
+            })?;
+        
+        let binding = container.ports
+            .iter()
+            .find(|binding| binding.container_port == container_port && binding.protocol == protocol)
+            .ok_or_else(|| format!("No public port {}/{} published for {}", container_port, protocol, container.name))?;
+        let host_ip = binding.host_ip.map(|ip| ip.to_string()).unwrap_or_else(|| "0.0.0.0".to_string());
+        println!("{}:{}", host_ip, binding.host_port.unwrap_or_default());
+        Ok(())
+    }
+    
+    // プロジェクトを Kubernetes のマニフェスト（--- で区切った YAML）にする
+    //
+    // サービスごとに Deployment と（ポートがあれば）Service を、名前付きボリュームごとに PersistentVolumeClaim を作る。
+    pub fn kubernetes_manifests(&self) -> Result<String, Box<dyn Error>> {
+        let mut manifests = Vec::new();
+        let mut volume_names: Vec<&String> = self.config.volumes
+            .iter()
+            .filter(|(_, volume)| !volume.external)
+            .map(|(name, _)| name)
+            .collect();
+        volume_names.sort();
+        for volume_name in volume_names {
+            let full_name = format!("{}_{}", self.project_name, volume_name);
+            manifests.push(convert::persistent_volume_claim(&self.project_name, &full_name));
+        }
+        
+        for service_name in self.resolve_dependencies()? {
+            let service = &self.config.services[&service_name];
+            if service.build.is_some() {
+                warn!(
+                    "Service {} is built locally; push {} to a registry the cluster can pull from",
+                    service_name,
+                    self.image_name(&service_name)
+                );
+            }
+            if !matches!(service.restart_policy.as_str(), "" | "always" | "unless-stopped") {
+                warn!("Deployments always restart their containers; restart of service {} is ignored", service_name);
+            }
+            let config = self.service_container_config(&service_name)?;
+            manifests.push(convert::deployment(&self.project_name, &service_name, &config, self.replicas(&service_name)));
+            manifests.extend(convert::service(&self.project_name, &service_name, &config));
+        }
+        
+        let documents = manifests
+            .iter()
+            .map(serde_yaml::to_string)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(documents.join("---\n"))
+    }
+    
+    // サービスのコンテナ数を変える（指定しなかったサービスはそのまま）
+    pub async fn scale(&self, scale: &HashMap<String, usize>) -> Result<(), Box<dyn Error>> {
+        for service_name in scale.keys() {
+            if !self.config.services.contains_key(service_name) {
+                return Err(ComposeError::ServiceNotFound(service_name.to_string()).into());
+            }
+        }
+        
+        self.create_networks().await?;
+        self.create_volumes().await?;
+        
+        for service_name in self.resolve_dependencies()? {
+            if let Some(replicas) = scale.get(&service_name) {
+                self.scale_service(&service_name, *replicas, Recreate::Never).await?;
+            }
+        }
         Ok(())
     }
     
+    // build のあるサービスのイメージをビルドする
+    pub async fn build(&self, services: &[String], options: &BuildOptions) -> Result<(), Box<dyn Error>> {
+        let services: Vec<&String> = self
+            .select_services(services)?
+            .into_iter()
+            .filter(|service| self.config.services[*service].build.is_some())
+            .collect();
+        let width = services.iter().map(|service| service.len()).max().unwrap_or(0);
+        
+        let tasks = services
+            .into_iter()
+            .map(|service| {
+                let prefix = format!("{:<width$}", service, width = width);
+                (service.clone(), async move { self.build_service(service, &prefix, options.pull).await })
+            })
+            .collect();
+        images::run_all(tasks, options.parallel, "build").await
+    }
+    
+    // image を指定したサービスのイメージを pull する（build のあるサービスはローカルでビルドするため除く）
+    pub async fn pull(&self, services: &[String], parallel: bool) -> Result<(), Box<dyn Error>> {
+        let services: Vec<&String> = self
+            .select_services(services)?
+            .into_iter()
+            .filter(|service| {
+                let service = &self.config.services[*service];
+                service.build.is_none() && service.image.is_some()
+            })
+            .collect();
+        let width = services.iter().map(|service| service.len()).max().unwrap_or(0);
+        
+        let client = Client::new();
+        let tasks = services
+            .into_iter()
+            .map(|service| {
+                let prefix = format!("{:<width$}", service, width = width);
+                let image = self.image_name(service);
+                let client = &client;
+                (service.clone(), async move {
+                    let lines = client.post_lines(&format!("/images/create?fromImage={}", client::encode(&image))).await?;
+                    images::print_progress(&prefix, lines).await
+                })
+            })
+            .collect();
+        images::run_all(tasks, parallel, "pull").await
+    }
+    
+    // build と image の両方を指定したサービスのイメージを push する
+    pub async fn push(&self, services: &[String], parallel: bool) -> Result<(), Box<dyn Error>> {
+        let services: Vec<&String> = self
+            .select_services(services)?
+            .into_iter()
+            .filter(|service| {
+                let service = &self.config.services[*service];
+                service.build.is_some() && service.image.is_some()
+            })
+            .collect();
+        let width = services.iter().map(|service| service.len()).max().unwrap_or(0);
+        
+        let client = Client::new();
+        let tasks = services
+            .into_iter()
+            .map(|service| {
+                let prefix = format!("{:<width$}", service, width = width);
+                let image = self.image_name(service);
+                let client = &client;
+                (service.clone(), async move {
+                    let lines = client.post_lines(&format!("/images/{}/push", client::encode(&image))).await?;
+                    images::print_progress(&prefix, lines).await
+                })
+            })
+            .collect();
+        images::run_all(tasks, parallel, "push").await
+    }
+    
+    // 動作中のサービスのコンテナでコマンドを実行し、終了コードを返す
+    pub async fn exec(&self, service_name: &str, cmd: Vec<String>, options: &ExecOptions) -> Result<i32, Box<dyn Error>> {
+        if !self.config.services.contains_key(service_name) {
+            return Err(ComposeError::ServiceNotFound(service_name.to_string()).into());
+        }
+        if cmd.is_empty() {
+            return Err(ComposeError::InvalidConfig("No command specified".to_string()).into());
+        }
+        
+        let client = Client::new();
+        let not_running = || format!("Service {} is not running", service_name);
+        let container_name = self.container_name(service_name, options.index.unwrap_or(1));
+        let container: Container = match client.get(&format!("/containers/{}", client::encode(&container_name))).await {
+            Ok(container) => container,
+            Err(e) if client::is_not_found(e.as_ref()) => return Err(not_running().into()),
+            Err(e) => return Err(e),
+        };
+        if !container.state.is_running() {
+            return Err(not_running().into());
+        }
+        
+        let config = ExecConfig {
+            cmd,
+            user: options.user.clone(),
+            env: run::parse_env(&options.env)?,
+            working_dir: options.workdir.clone(),
+            attach_stdin: options.interactive && !options.detach,
+        };
+        run::exec(&client, &container.id, &config, options.detach).await
+    }
+    
+    // サービスの設定（コマンドは差し替え可能）で 1 回限りのコンテナを作成して起動し、終了コードを返す
+    pub async fn run(&self, service_name: &str, cmd: Vec<String>, options: &RunOptions) -> Result<i32, Box<dyn Error>> {
+        if !self.config.services.contains_key(service_name) {
+            return Err(ComposeError::ServiceNotFound(service_name.to_string()).into());
+        }
+        
+        self.create_networks().await?;
+        self.create_volumes().await?;
+        
+        // 依存するサービスを先に起動する
+        if !options.no_deps {
+            for dependency in self.dependencies(service_name)? {
+                self.scale_service(&dependency, self.replicas(&dependency), Recreate::Never).await?;
+            }
+            self.wait_for_dependencies(service_name).await?;
+        }
+        
+        let mut config = self.container_config(service_name).await?;
+        if !cmd.is_empty() {
+            config.cmd = Some(cmd);
+        }
+        config.env.extend(run::parse_env(&options.env)?);
+        if options.user.is_some() {
+            config.user = options.user.clone();
+        }
+        if options.workdir.is_some() {
+            config.working_dir = options.workdir.clone();
+        }
+        for target in &options.wait_for {
+            config.wait_for.push(WaitFor::parse(target)?);
+        }
+        if !options.service_ports {
+            config.port_bindings.clear();
+        }
+        // 切り離した場合はデーモンに終了時の削除を任せる（one-off のコンテナは restart に関わらず再起動しない）
+        config.auto_remove = options.rm && options.detach;
+        config.restart_policy = RestartPolicy::No;
+        // one-off のコンテナはサービス名の名前解決に加えない
+        config.network_aliases.clear();
+        config.labels.insert(ONEOFF_LABEL.to_string(), "True".to_string());
+        
+        let container_name = options.name.clone().unwrap_or_else(|| {
+            let id = uuid::Uuid::new_v4().simple().to_string();
+            format!("{}_{}_run_{}", self.project_name, service_name, &id[..12])
+        });
+        let client = Client::new();
+        let container = self.create_container(&client, &container_name, service_name, &config).await?;
+        
+        if options.detach {
+            client.post_empty(&format!("/containers/{}/start", container.id)).await?;
+            println!("{}", container_name);
+            return Ok(0);
+        }
+        // Ctrl-C はコンテナに送り、終了コードを返す
+        let exit_code = match client.run_attached(&container.id, None).await? {
+            client::Attached::Exited(exit_code) => exit_code,
+            client::Attached::Detached => 0,
+        };
+        if options.rm {
+            removed_or_missing(client.delete(&format!("/containers/{}?force=1", container.id)).await)?;
+        }
+        Ok(exit_code)
+    }
+    
+    // サービスが依存するサービス（起動する順、サービス自身は含まない）
+    fn dependencies(&self, service_name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut result = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        let mut temp_mark = std::collections::HashSet::new();
+        self.visit_node(service_name, &mut visited, &mut temp_mark, &mut result)?;
+        result.pop();
+        Ok(result)
+    }
+    
+    // 指定したサービス（空なら全てのサービス）を依存関係の順に並べる
+    fn ordered_services(&self, services: &[String]) -> Result<Vec<String>, Box<dyn Error>> {
+        let selected = self.select_services(services)?;
+        Ok(self.resolve_dependencies()?
+            .into_iter()
+            .filter(|service_name| selected.contains(&service_name))
+            .collect())
+    }
+    
+    // 指定したサービス名を検証して返す（空なら全てのサービス）
+    fn select_services<'a>(&'a self, services: &'a [String]) -> Result<Vec<&'a String>, Box<dyn Error>> {
+        let mut names: Vec<&String> = if services.is_empty() {
+            self.config.services.keys().collect()
+        } else {
+            for service in services {
+                if !self.config.services.contains_key(service) {
+                    return Err(ComposeError::ServiceNotFound(service.to_string()).into());
+                }
+            }
+            services.iter().collect()
+        };
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+    
+    // プロジェクトのラベルが付いたコンテナをデーモンに問い合わせる
+    async fn project_containers(&self, all: bool) -> Result<Vec<Container>, Box<dyn Error>> {
+        self.project_containers_matching(all, &[]).await
+    }
+
+    // プロジェクトのコンテナのうち filters（label=key=value・label!=key など）にも一致するもの
+    //
+    // フィルタはデーモンで評価する（ラベルのフィルタは全てに一致する必要がある）。
+    async fn project_containers_matching(&self, all: bool, filters: &[(&str, &str)]) -> Result<Vec<Container>, Box<dyn Error>> {
+        let mut path = format!("/containers?all={}&filter={}", if all { 1 } else { 0 }, self.project_filter());
+        for (name, value) in filters {
+            path.push_str(&format!("&filter={}", client::encode(&format!("{}={}", name, value))));
+        }
+        Client::new().get(&path).await
+    }
+    
+    // プロジェクトのラベルで絞り込む filter パラメータ（エンコード済み）
+    fn project_filter(&self) -> String {
+        client::encode(&format!("label={}={}", PROJECT_LABEL, self.project_name))
+    }
+    
+    // サービスの number 番目のコンテナの名前
+    fn container_name(&self, service_name: &str, number: usize) -> String {
+        format!("{}_{}_{}", self.project_name, service_name, number)
+    }
+    
+    // サービスのコンテナ数（deploy.replicas、省略時は 1）
+    fn replicas(&self, service_name: &str) -> usize {
+        self.config
+            .services
+            .get(service_name)
+            .and_then(|service| service.deploy.as_ref())
+            .and_then(|deploy| deploy.replicas)
+            .unwrap_or(1)
+    }
+    
+    // サービスのコンテナ（停止中のものも含む、one-off は除く）を番号順に返す
+    async fn service_containers(&self, service_name: &str) -> Result<Vec<(usize, Container)>, Box<dyn Error>> {
+        let service_filter = format!("{}={}", SERVICE_LABEL, service_name);
+        let mut containers: Vec<(usize, Container)> = self
+            .project_containers_matching(true, &[("label", &service_filter), ("label!", ONEOFF_LABEL)])
+            .await?
+            .into_iter()
+            .map(|container| (container_number(&container), container))
+            .collect();
+        containers.sort_by_key(|(number, _)| *number);
+        Ok(containers)
+    }
+    
     async fn create_networks(&self) -> Result<(), Box<dyn Error>> {
         info!("Creating networks for project {}", self.project_name);
         
-        for (network_name, network_config) in &self.config.networks {
+        let client = Client::new();
+        let default_config = NetworkConfig::default();
+        for network_name in &self.project_networks() {
+            let network_config = self.config.networks.get(network_name).unwrap_or(&default_config);
+            
             // 外部ネットワークはスキップ
             if network_config.external {
                 continue;
             }
             
-            let full_name = format!("{}_{}",  self.project_name, network_name);
+            let full_name = self.network_name(network_name);
             info!("Creating network: {}", full_name);
             
-            // NetworkManager APIを使ってネットワーク作成
-            // ここでは簡易化のためプロセス実行を使用
-            // 実際の実装ではrockerデーモンのAPIを使用する
-            
-            // TODO: デーモンAPIでネットワーク作成
+            let labels = self.resource_labels(NETWORK_LABEL, network_name, network_config)?;
+            let pool = network_config.ipam
+                .as_ref()
+                .and_then(|ipam| ipam.config.as_ref())
+                .and_then(|pools| pools.first());
+            let request = serde_json::json!({
+                "name": full_name,
+                "driver": network_config.driver,
+                "subnet": pool.and_then(|pool| pool.subnet.clone()),
+                "gateway": pool.and_then(|pool| pool.gateway.clone()),
+                "options": network_config.driver_opts,
+                "labels": labels,
+            });
+            if !created(client.post::<_, serde_json::Value>("/networks/create", &request).await)? {
+                let network: Network = client.get(&format!("/networks/{}", client::encode(&full_name))).await?;
+                self.check_existing("Network", &full_name, &network.config.labels, &labels);
+            }
         }
         
         Ok(())
     }
     
+    // プロジェクトが使うネットワーク（定義されていなくても、使うサービスがあれば default を含める）
+    fn project_networks(&self) -> Vec<String> {
+        let mut networks: Vec<String> = self.config.networks.keys().cloned().collect();
+        let uses_default = self.config.services.values().any(|service| service.networks.is_empty());
+        if uses_default && !self.config.networks.contains_key(DEFAULT_NETWORK) {
+            networks.push(DEFAULT_NETWORK.to_string());
+        }
+        networks
+    }
+    
     async fn create_volumes(&self) -> Result<(), Box<dyn Error>> {
         info!("Creating volumes for project {}", self.project_name);
         
+        let client = Client::new();
         for (volume_name, volume_config) in &self.config.volumes {
             // 外部ボリュームはスキップ
             if volume_config.external {
@@ -225,21 +1032,48 @@ impl ComposeProject {
             let full_name = format!("{}_{}",  self.project_name, volume_name);
             info!("Creating volume: {}", full_name);
             
-            // VolumeManager APIを使ってボリューム作成
-            // TODO: デーモンAPIでボリューム作成
+            let labels = self.resource_labels(VOLUME_LABEL, volume_name, volume_config)?;
+            let request = serde_json::json!({
+                "name": full_name,
+                "driver": volume_config.driver,
+                "driver_opts": volume_config.driver_opts,
+                "labels": labels,
+            });
+            if !created(client.post::<_, serde_json::Value>("/volumes/create", &request).await)? {
+                let volume: Volume = client.get(&format!("/volumes/{}", client::encode(&full_name))).await?;
+                self.check_existing("Volume", &full_name, &volume.labels, &labels);
+            }
         }
         
         Ok(())
     }
     
+    // プロジェクトが作るネットワーク・ボリューム・秘密情報のラベル
+    fn resource_labels<T: Serialize>(&self, kind_label: &str, name: &str, config: &T) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let mut labels = HashMap::new();
+        labels.insert(PROJECT_LABEL.to_string(), self.project_name.clone());
+        labels.insert(kind_label.to_string(), name.to_string());
+        labels.insert(CONFIG_HASH_LABEL.to_string(), up::resource_hash(config)?);
+        Ok(labels)
+    }
+    
+    // 既にあるネットワーク・ボリューム・秘密情報がこのプロジェクトの今の設定で作られたものか確かめる（作り直しはしない）
+    fn check_existing(&self, kind: &str, name: &str, existing: &HashMap<String, String>, labels: &HashMap<String, String>) {
+        if existing.get(PROJECT_LABEL) != Some(&self.project_name) {
+            warn!("{} {} already exists but was not created by project {}", kind, name, self.project_name);
+        } else if existing.get(CONFIG_HASH_LABEL) != labels.get(CONFIG_HASH_LABEL) {
+            warn!("{} {} was created with a different configuration; run down to recreate it", kind, name);
+        }
+    }
+    
     fn resolve_dependencies(&self) -> Result<Vec<String>, Box<dyn Error>> {
         // トポロジカルソートで依存関係を解決
         let mut result = Vec::new();
         let mut visited = std::collections::HashSet::new();
         let mut temp_mark = std::collections::HashSet::new();
         
-        // すべてのサービスを処理
-        for service_name in self.config.services.keys() {
+        // すべてのサービスを名前順に処理（依存関係のないサービス同士は名前順に起動する）
+        for (service_name, _) in self.config.services() {
             if !visited.contains(service_name) {
                 self.visit_node(service_name, &mut visited, &mut temp_mark, &mut result)?;
             }
@@ -257,7 +1091,7 @@ impl ComposeProject {
     ) -> Result<(), Box<dyn Error>> {
         // 一時マークが付いている場合、循環依存がある
         if temp_mark.contains(node) {
-            return Err(format!("Circular dependency detected: {}", node).into());
+            return Err(ComposeError::CircularDependency(node.to_string()).into());
         }
         
         // 訪問済みノードはスキップ
@@ -270,7 +1104,7 @@ impl ComposeProject {
         
         // 依存関係を処理
         if let Some(service) = self.config.services.get(node) {
-            for dep in &service.depends_on {
+            for (dep, _) in service.depends_on.services() {
                 if !visited.contains(dep.as_str()) {
                     self.visit_node(dep, visited, temp_mark, result)?;
                 }
@@ -287,132 +1121,1127 @@ impl ComposeProject {
         Ok(())
     }
     
-    async fn start_service(&self, service_name: &str, detached: bool) -> Result<(), Box<dyn Error>> {
-        let service = self.config.services.get(service_name)
-            .ok_or_else(|| format!("Service not found: {}", service_name))?;
-            
-        info!("Starting service: {}", service_name);
-        
-        // イメージをビルドまたはプル
-        let image = if let Some(build_config) = &service.build {
-            self.build_image(service_name, build_config).await?
-        } else if let Some(image) = &service.image {
-            image.clone()
-        } else {
-            return Err(format!("Service {} has neither image nor build specified", service_name).into());
-        };
-        
-        // 環境変数の準備
-        let env_vars = match &service.environment {
-            Environment::List(list) => {
-                let mut env_map = HashMap::new();
-                for item in list {
-                    if let Some((key, value)) = item.split_once('=') {
-                        env_map.insert(key.to_string(), value.to_string());
-                    }
-                }
-                env_map
-            },
-            Environment::Map(map) => map.clone(),
+    // depends_on の condition（healthy・正常終了）を依存するサービスの全てのコンテナが満たすまで待つ
+    async fn wait_for_dependencies(&self, service_name: &str) -> Result<(), Box<dyn Error>> {
+        let Some(service) = self.config.services.get(service_name) else {
+            return Ok(());
         };
         
-        // コンテナ名を生成
-        let container_name = format!("{}_{}", self.project_name, service_name);
-        
-        // コンテナを作成して起動
-        // TODO: デーモンAPIでコンテナ作成と起動
-        
+        let client = Client::new();
+        for (dependency, condition) in service.depends_on.services() {
+            let containers: Vec<Container> = self
+                .service_containers(dependency)
+                .await?
+                .into_iter()
+                .map(|(_, container)| container)
+                .collect();
+            depends::wait_for(&client, dependency, &containers, condition)
+                .await
+                .map_err(|e| ServiceError::new(format!("Dependency {} of service {} failed", dependency, service_name), e))?;
+        }
         Ok(())
     }
     
-    async fn build_image(&self, service_name: &str, build_config: &BuildConfig) -> Result<String, Box<dyn Error>> {
-        info!("Building image for service: {}", service_name);
-        
-        let (context, rockerfile) = match build_config {
-            BuildConfig::String(context) => (context.clone(), None),
-            BuildConfig::Object { context, rockerfile, .. } => (context.clone(), rockerfile.clone()),
-        };
+    // サービスの全てのコンテナが動作していて、ヘルスチェックのあるコンテナが healthy になるまで待つ
+    //
+    // デーモンのイベントを受け取るたびに状態を確かめ直す。コンテナが終了するか unhealthy になった場合と、
+    // timeout 秒を過ぎた場合はサービスごとの状態を付けたエラーを返す。
+    async fn wait_until_ready(&self, services: &[String], timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        // 状態を確かめる前に購読し、その間のイベントを取りこぼさないようにする
+        let mut events = client.get_lines("/events").await?;
+        let deadline = timeout.map(|timeout| tokio::time::Instant::now() + std::time::Duration::from_secs(timeout));
         
-        // コンテキストパスを解決
-        let context_path = self.project_dir.join(context);
+        let mut container_ids = std::collections::HashSet::new();
+        for service_name in services {
+            for (_, container) in self.service_containers(service_name).await? {
+                container_ids.insert(container.id);
+            }
+        }
+        info!("Waiting for {} containers to be ready", container_ids.len());
+        
+        loop {
+            let mut statuses = Vec::new();
+            for service_name in services {
+                for (_, container) in self.service_containers(service_name).await? {
+                    statuses.push((service_name.clone(), container.name.clone(), wait::readiness(&container)));
+                }
+            }
+            if statuses.iter().any(|(_, _, readiness)| matches!(readiness, Readiness::Failed(_))) {
+                return Err(format!("Some containers failed to become ready:\n{}", wait::report(&statuses)).into());
+            }
+            if statuses.iter().all(|(_, _, readiness)| matches!(readiness, Readiness::Ready(_))) {
+                println!("{}", wait::report(&statuses));
+                return Ok(());
+            }
+            
+            // プロジェクトのコンテナのイベントが届くまで待つ
+            loop {
+                let next = match deadline {
+                    Some(deadline) => match tokio::time::timeout_at(deadline, events.next_json::<Event>()).await {
+                        Ok(next) => next?,
+                        Err(_) => {
+                            return Err(format!(
+                                "Timed out after {}s waiting for containers:\n{}",
+                                timeout.unwrap_or_default(),
+                                wait::report(&statuses)
+                            )
+                            .into())
+                        }
+                    },
+                    None => events.next_json::<Event>().await?,
+                };
+                let event = next.ok_or("The daemon closed the event stream")?;
+                if container_ids.contains(&event.actor_id) {
+                    break;
+                }
+            }
+        }
+    }
+    
+    // develop.watch のパスを監視し、変更に合わせてファイルの同期・コンテナの再起動・再ビルドを行う
+    //
+    // Ctrl+C で監視を終える。変更を反映できなかった場合もエラーを表示して監視を続ける。
+    async fn watch_changes(&self, services: &[String]) -> Result<(), Box<dyn Error>> {
+        let mut watchers = Vec::new();
+        for service_name in services {
+            let rules = self.config.services.get(service_name)
+                .and_then(|service| service.develop.as_ref())
+                .map(|develop| develop.watch.as_slice())
+                .unwrap_or_default();
+            for rule in rules {
+                watchers.push(Watcher::new(service_name, rule, &self.project_dir)?);
+            }
+        }
+        if watchers.is_empty() {
+            let message = "None of the services has a develop.watch section";
+            return Err(ComposeError::InvalidConfig(message.to_string()).into());
+        }
+        
+        info!("Watching for changes. Press Ctrl+C to stop...");
+        loop {
+            tokio::select! {
+                result = tokio::signal::ctrl_c() => return Ok(result?),
+                _ = tokio::time::sleep(watch::POLL_INTERVAL) => {}
+            }
+            
+            // 再ビルドと再起動はサービスごとに 1 回だけ行う
+            let mut rebuild = Vec::new();
+            let mut restart = Vec::new();
+            for watcher in &mut watchers {
+                let changes = watcher.poll();
+                if changes.is_empty() {
+                    continue;
+                }
+                info!(
+                    "{} changed in {} (service {})",
+                    changes.changed.len() + changes.removed.len(),
+                    watcher.rule.path,
+                    watcher.service
+                );
+                let service_name = watcher.service.clone();
+                match watcher.rule.action {
+                    WatchAction::Rebuild => rebuild.push(service_name),
+                    WatchAction::Restart => restart.push(service_name),
+                    WatchAction::Sync | WatchAction::SyncRestart => {
+                        if let Err(e) = self.sync_changes(watcher, &changes).await {
+                            error!("Failed to sync {} to service {}: {}", watcher.rule.path, service_name, e);
+                            continue;
+                        }
+                        if watcher.rule.action == WatchAction::SyncRestart {
+                            restart.push(service_name);
+                        }
+                    }
+                }
+            }
+            rebuild.dedup();
+            restart.retain(|service_name| !rebuild.contains(service_name));
+            restart.dedup();
+            
+            for service_name in &rebuild {
+                if let Err(e) = self.rebuild_service(service_name).await {
+                    error!("Failed to rebuild service {}: {}", service_name, e);
+                }
+            }
+            for service_name in &restart {
+                if let Err(e) = self.restart_service(service_name).await {
+                    error!("Failed to restart service {}: {}", service_name, e);
+                }
+            }
+        }
+    }
+    
+    // 変更したファイルをサービスの動作中のコンテナにコピーし、削除したファイルをコンテナからも削除する
+    async fn sync_changes(&self, watcher: &Watcher, changes: &Changes) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        let archive = if changes.changed.is_empty() { None } else { Some(watcher.sync_archive(&changes.changed)?) };
+        for (_, container) in self.service_containers(&watcher.service).await? {
+            if !container.state.is_running() {
+                continue;
+            }
+            if let Some((path, archive)) = &archive {
+                let path = format!("/containers/{}/archive?path={}", container.id, client::encode(path));
+                client.put_body(&path, archive.clone(), "application/x-tar").await?;
+            }
+            for relative in &changes.removed {
+                let path = watcher.target_path(relative);
+                client.delete(&format!("/containers/{}/archive?path={}", container.id, client::encode(&path))).await?;
+            }
+            info!("Synced {} to container {}", watcher.rule.path, container.name);
+        }
+        Ok(())
+    }
+    
+    // イメージをビルドし直し、イメージが変わったコンテナを作り直す
+    async fn rebuild_service(&self, service_name: &str) -> Result<(), Box<dyn Error>> {
+        self.build_service(service_name, service_name, false).await?;
+        let replicas = self.service_containers(service_name).await?.len().max(1);
+        self.scale_service(service_name, replicas, Recreate::Changed).await
+    }
+    
+    // サービスの動作中のコンテナを停止して起動し直す
+    async fn restart_service(&self, service_name: &str) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        for (_, container) in self.service_containers(service_name).await? {
+            if !container.state.is_running() {
+                continue;
+            }
+            info!("Restarting container: {}", container.name);
+            stop_container(&container, None).await?;
+            client.post_empty(&format!("/containers/{}/start", container.id)).await?;
+        }
+        Ok(())
+    }
+    
+    // サービスのコンテナを replicas 個にして全て起動する
+    //
+    // 足りないコンテナは番号の小さい方から作成し、多すぎる場合は番号の大きい方から停止して削除する。
+    // recreate に当てはまる既存のコンテナは削除して作り直す。
+    async fn scale_service(&self, service_name: &str, replicas: usize, recreate: Recreate) -> Result<(), Box<dyn Error>> {
+        info!("Starting service: {} ({} containers)", service_name, replicas);
+        
+        let client = Client::new();
+        let mut containers: HashMap<usize, Container> = self.service_containers(service_name).await?.into_iter().collect();
+        let mut surplus: Vec<usize> = containers.keys().copied().filter(|number| *number > replicas).collect();
+        surplus.sort_by(|a, b| b.cmp(a));
+        for number in surplus {
+            let container = &containers[&number];
+            info!("Removing container: {}", container.name);
+            removed_or_missing(client.delete(&format!("/containers/{}?force=1", container.id)).await)?;
+        }
+        if replicas == 0 {
+            return Ok(());
+        }
+        self.wait_for_dependencies(service_name).await?;
+        
+        let mut config = self.container_config(service_name).await?;
+        let image: Image = client.get(&format!("/images/{}", client::encode(&config.image))).await?;
+        let hash = up::config_hash(&config, &image.id)?;
+        config.labels.insert(CONFIG_HASH_LABEL.to_string(), hash.clone());
+        for number in 1..=replicas {
+            // 作り直すコンテナを削除する
+            if let Some(container) = containers.get(&number) {
+                let changed = container.config.labels.get(CONFIG_HASH_LABEL) != Some(&hash);
+                if recreate == Recreate::Always || (recreate == Recreate::Changed && changed) {
+                    info!("Recreating container: {}", container.name);
+                    removed_or_missing(client.delete(&format!("/containers/{}?force=1", container.id)).await)?;
+                    containers.remove(&number);
+                }
+            }
+            
+            // コンテナを作成して起動（既にあるコンテナはそのまま起動する）
+            let container = match containers.remove(&number) {
+                Some(container) => container,
+                None => {
+                    let container_name = self.container_name(service_name, number);
+                    info!("Creating container: {}", container_name);
+                    config.labels.insert(CONTAINER_NUMBER_LABEL.to_string(), number.to_string());
+                    self.create_container(&client, &container_name, service_name, &config).await?
+                }
+            };
+            if container.state.is_running() {
+                info!("Container {} is already running", container.name);
+                continue;
+            }
+            client.post_empty(&format!("/containers/{}/start", container.id)).await?;
+        }
+        
+        Ok(())
+    }
+    
+    // サービスの設定からコンテナの設定を作る
+    async fn container_config(&self, service_name: &str) -> Result<ContainerConfig, Box<dyn Error>> {
+        let service = self.config.services.get(service_name)
+            .ok_or_else(|| ComposeError::ServiceNotFound(service_name.to_string()))?;
+        
+        // pull_policy に従ってビルドまたはプル（既定ではイメージが無い場合だけ）
+        if service.build.is_none() && service.image.is_none() {
+            let message = format!("Service {} has neither image nor build specified", service_name);
+            return Err(ComposeError::InvalidConfig(message).into());
+        }
+        let image = self.image_name(service_name);
+        let policy = service.pull_policy.unwrap_or_default();
+        let client = Client::new();
+        if service.build.is_some() {
+            let missing = match client.inspect_image(&image).await {
+                Ok(_) => false,
+                Err(e) if client::is_not_found(e.as_ref()) => true,
+                Err(e) => return Err(e),
+            };
+            if missing || matches!(policy, PullPolicy::Always | PullPolicy::Build) {
+                self.build_service(service_name, service_name, policy == PullPolicy::Always).await?;
+            }
+        } else if let Some(progress) = client.ensure_image(&image, policy.client_policy()).await? {
+            images::print_progress(service_name, progress.into_lines()).await?;
+        }
+        
+        self.service_container_config(service_name)
+    }
+    
+    // サービスの設定から作るコンテナの設定（デーモンには問い合わせない）
+    fn service_container_config(&self, service_name: &str) -> Result<ContainerConfig, Box<dyn Error>> {
+        let service = self.config.services.get(service_name)
+            .ok_or_else(|| ComposeError::ServiceNotFound(service_name.to_string()))?;
+        let image = self.image_name(service_name);
+        
+        // 環境変数の準備（env_file を順に読み、environment の値で上書きする。値の無い KEY は compose
+        // を実行した環境から取る）
+        let mut env_vars = self.env_file_vars(service_name, &service.env_file)?;
+        match &service.environment {
+            Environment::List(list) => {
+                for item in list {
+                    let var = rocker_core::parse_env_var(item)
+                        .map_err(|e| {
+                            ComposeError::InvalidConfig(format!("{} in the environment of service {}", e, service_name))
+                        })?;
+                    env_vars.extend(var);
+                }
+            },
+            Environment::Map(map) => env_vars.extend(map.clone()),
+        }
         
-        // Rockerfileパスを解決
-        let rockerfile_path = match rockerfile {
-            Some(file) => context_path.join(file),
-            None => context_path.join("Rockerfile"),
+        // /etc/hosts に追加するエントリ
+        let extra_hosts = match &service.extra_hosts {
+            ExtraHosts::List(list) => list
+                .iter()
+                .map(|entry| HostEntry::parse(entry))
+                .collect::<Result<Vec<_>, _>>()?,
+            ExtraHosts::Map(map) => map
+                .iter()
+                .map(|(host, ip)| HostEntry::parse(&format!("{}={}", host, ip)))
+                .collect::<Result<Vec<_>, _>>()?,
         };
         
-        if !rockerfile_path.exists() {
-            return Err(format!("Rockerfile not found at {}", rockerfile_path.display()).into());
+        // ホストに公開するポート
+        let port_bindings = service.ports
+            .iter()
+            .map(ServicePort::bindings)
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        
+        // コンテナで実行するコマンド（文字列の場合はシェルで実行する）
+        let cmd = service.command.as_ref().map(|command| match command {
+            Command::String(command) => vec!["/bin/sh".to_string(), "-c".to_string(), command.clone()],
+            Command::List(list) => list.clone(),
+        });
+        
+        // サービスの labels に、プロジェクトとサービスを示すラベルを加える（同じキーはこちらを優先する）
+        let mut labels = service.labels.clone();
+        labels.insert(PROJECT_LABEL.to_string(), self.project_name.clone());
+        labels.insert(SERVICE_LABEL.to_string(), service_name.to_string());
+        
+        // ボリュームとネットワーク（最初のネットワークで作成し、残りには作成後に接続する）
+        let mounts = service.volumes
+            .iter()
+            .map(|volume| self.service_mount(volume))
+            .collect::<Result<Vec<_>, _>>()?;
+        let networks = self.service_networks(service_name)?;
+        let secrets = self.service_secrets(service_name, &service.secrets)?;
+        
+        // entrypoint の文字列はシェルを通さず空白で分ける
+        let entrypoint = service.entrypoint.as_ref().map(|entrypoint| match entrypoint {
+            Command::String(entrypoint) => entrypoint.split_whitespace().map(str::to_string).collect(),
+            Command::List(list) => list.clone(),
+        });
+        let dns = service.dns
+            .as_slice()
+            .iter()
+            .map(|server| server.parse().map_err(|_| format!("Invalid dns server of service {}: {}", service_name, server)))
+            .collect::<Result<Vec<IpAddr>, _>>()?;
+        let mut mounts = mounts;
+        for spec in service.tmpfs.as_slice() {
+            mounts.push(volumes::tmpfs_mount(spec)?);
         }
+        let mut ulimits = service.ulimits
+            .iter()
+            .map(|(name, ulimit)| ulimit.to_ulimit(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        ulimits.sort_by(|a, b| a.name.cmp(&b.name));
+        let resources = service.deploy.as_ref().and_then(|deploy| deploy.resources.as_ref());
+        let resource_limits = resources::resource_limits(resources, service.cpus.as_ref(), service.mem_limit.as_ref())?;
+        let wait_for = match service.extensions.get("x-wait-for") {
+            Some(value) => {
+                let targets: StringOrList = serde_yaml::from_value(value.clone())
+                    .map_err(|e| {
+                        ComposeError::InvalidConfig(format!("Invalid x-wait-for of service {}: {}", service_name, e))
+                    })?;
+                targets.as_slice().iter().map(|target| WaitFor::parse(target)).collect::<Result<Vec<_>, _>>()?
+            }
+            None => Vec::new(),
+        };
+        // restart（書かれていなければ再起動しない）
+        let restart_policy = match service.restart_policy.as_str() {
+            "" => RestartPolicy::No,
+            policy => RestartPolicy::parse(policy).map_err(|e| {
+                ComposeError::InvalidConfig(format!("Invalid restart of service {}: {}", service_name, e))
+            })?,
+        };
         
-        // イメージタグを生成
-        let image_tag = format!("{}_{}", self.project_name, service_name);
+        Ok(ContainerConfig {
+            image,
+            entrypoint,
+            cmd,
+            working_dir: service.working_dir.clone(),
+            user: service.user.clone(),
+            env: env_vars,
+            port_bindings,
+            mounts,
+            secrets,
+            network_mode: NetworkMode::Custom(self.network_name(&networks[0])),
+            // 同じサービスのコンテナはサービス名で順に名前解決される
+            network_aliases: vec![service_name.to_string()],
+            labels,
+            extra_hosts,
+            dns,
+            ulimits,
+            resource_limits,
+            privileged: service.privileged,
+            cap_add: service.cap_add.clone(),
+            cap_drop: service.cap_drop.clone(),
+            stop_timeout: service.stop_grace_period.as_deref().map(parse_duration).transpose()?,
+            healthcheck: service.healthcheck.as_ref().map(HealthcheckConfig::to_health_config).transpose()?,
+            restart_policy,
+            wait_for,
+            ..ContainerConfig::default()
+        })
+    }
+    
+    // サービスの env_file の変数（相対パスはプロジェクトのディレクトリから、後のファイルほど優先する）
+    fn env_file_vars(&self, service_name: &str, env_file: &EnvFile) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let paths = match env_file {
+            EnvFile::String(path) => std::slice::from_ref(path),
+            EnvFile::List(paths) => paths.as_slice(),
+        };
         
-        // イメージをビルド
-        // TODO: デーモンAPIでイメージビルド
+        let mut vars = HashMap::new();
+        for path in paths {
+            let full_path = self.project_dir.join(path);
+            let content = std::fs::read_to_string(&full_path).map_err(|e| {
+                format!("Couldn't read env file {} of service {}: {}", full_path.display(), service_name, e)
+            })?;
+            let file_vars = rocker_core::parse_env_file(&content).map_err(|e| {
+                format!("Invalid env file {} of service {}: {}", full_path.display(), service_name, e)
+            })?;
+            vars.extend(file_vars);
+        }
+        Ok(vars)
+    }
+    
+    // コンテナを作成し、サービスの 2 つ目以降のネットワークに接続する（サービス名で名前解決できるようにする）
+    async fn create_container(
+        &self,
+        client: &Client,
+        container_name: &str,
+        service_name: &str,
+        config: &ContainerConfig,
+    ) -> Result<Container, Box<dyn Error>> {
+        let container: Container = client
+            .post(&format!("/containers/create?name={}", client::encode(container_name)), config)
+            .await?;
         
-        Ok(image_tag)
+        for network in self.service_networks(service_name)?.iter().skip(1) {
+            let request = serde_json::json!({
+                "container": container.id,
+                "aliases": [service_name],
+            });
+            let _: serde_json::Value = client
+                .post(&format!("/networks/{}/connect", client::encode(&self.network_name(network))), &request)
+                .await?;
+        }
+        Ok(container)
     }
     
-    async fn stop_service(&self, service_name: &str) -> Result<(), Box<dyn Error>> {
+    // サービスの volumes の 1 つをマウントの指定にする
+    //
+    // 相対パスはプロジェクトのディレクトリからのバインドマウント、トップレベルの volumes で定義した名前は
+    // プロジェクト名を付けたボリュームになる。
+    fn service_mount(&self, volume: &ServiceVolume) -> Result<Mount, Box<dyn Error>> {
+        let spec = match volume {
+            ServiceVolume::Short(spec) => spec,
+            ServiceVolume::Long(volume) => return self.long_mount(volume),
+        };
+        let (source, rest) = match spec.split_once(':') {
+            Some((source, rest)) => (source, rest),
+            None => return Ok(Mount::parse(spec)?),
+        };
+        Ok(Mount::parse(&format!("{}:{}", self.mount_source(source)?, rest))?)
+    }
+    
+    // キーで書いた volumes の 1 つをマウントの指定にする
+    fn long_mount(&self, volume: &VolumeMount) -> Result<Mount, Box<dyn Error>> {
+        let target = &volume.target;
+        if volume.mount_type == "tmpfs" {
+            if volume.source.is_some() {
+                return Err(ComposeError::InvalidConfig(format!("tmpfs mount {} cannot have a source", target)).into());
+            }
+            let tmpfs_size = match volume.tmpfs.as_ref().and_then(|tmpfs| tmpfs.size.as_ref()) {
+                Some(size) => Some(size.bytes()?),
+                None => None,
+            };
+            return Ok(Mount {
+                mount_type: MountType::Tmpfs,
+                source: String::new(),
+                destination: target.clone(),
+                read_only: volume.read_only,
+                propagation: None,
+                relabel: None,
+                no_copy: false,
+                tmpfs_size,
+            });
+        }
+        
+        let source = match (volume.mount_type.as_str(), volume.source.as_deref()) {
+            ("bind", Some(source)) => {
+                let source = self.mount_source(source)?;
+                if !source.starts_with('/') {
+                    return Err(ComposeError::InvalidConfig(format!(
+                        "Bind mount source for {} must be a path: {}",
+                        target, source
+                    ))
+                    .into());
+                }
+                source
+            }
+            ("bind", None) => {
+                return Err(ComposeError::InvalidConfig(format!("Bind mount {} needs a source", target)).into())
+            }
+            ("volume", Some(source)) if source.starts_with(['.', '/', '~']) => {
+                return Err(ComposeError::InvalidConfig(format!(
+                    "Volume mount source for {} must be a volume name: {}",
+                    target, source
+                ))
+                .into())
+            }
+            ("volume", Some(source)) => self.mount_source(source)?,
+            // source の無いボリュームは匿名ボリュームになる
+            ("volume", None) => String::new(),
+            (mount_type, _) => {
+                return Err(
+                    ComposeError::InvalidConfig(format!("Invalid mount type for {}: {}", target, mount_type)).into(),
+                )
+            }
+        };
+        
+        let mut options = Vec::new();
+        if volume.read_only {
+            options.push("ro");
+        }
+        if let Some(bind) = &volume.bind {
+            options.extend(bind.propagation.as_deref());
+            options.extend(bind.selinux.as_deref());
+        }
+        let mut mount = Mount::parse(&format!("{}:{}:{}", source, target, options.join(",")))?;
+        mount.no_copy = volume.volume.as_ref().is_some_and(|volume| volume.nocopy);
+        Ok(mount)
+    }
+    
+    // マウント元をホストのパスかデーモン上のボリューム名にする
+    fn mount_source(&self, source: &str) -> Result<String, Box<dyn Error>> {
+        let source = if source.starts_with('.') {
+            std::path::absolute(self.project_dir.join(source))?.to_string_lossy().to_string()
+        } else if let Some(home) = source.strip_prefix("~/") {
+            let home_dir = std::env::var("HOME").map_err(|_| "HOME is not set".to_string())?;
+            Path::new(&home_dir).join(home).to_string_lossy().to_string()
+        } else if source.starts_with('/') {
+            source.to_string()
+        } else {
+            match self.config.volumes.get(source) {
+                Some(volume) if volume.external => source.to_string(),
+                Some(_) => format!("{}_{}", self.project_name, source),
+                None => {
+                    let message = format!("Named volume \"{}\" is not declared in the volumes section", source);
+                    return Err(ComposeError::InvalidConfig(message).into());
+                }
+            }
+        };
+        Ok(source)
+    }
+    
+    // サービスが参加するネットワーク（指定がなければプロジェクトの default ネットワーク）
+    fn service_networks(&self, service_name: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let service = self.config.services.get(service_name)
+            .ok_or_else(|| ComposeError::ServiceNotFound(service_name.to_string()))?;
+        if service.networks.is_empty() {
+            return Ok(vec![DEFAULT_NETWORK.to_string()]);
+        }
+        for network in &service.networks {
+            if !self.config.networks.contains_key(network) {
+                let message = format!("Service {} refers to undefined network {}", service_name, network);
+                return Err(ComposeError::InvalidConfig(message).into());
+            }
+        }
+        Ok(service.networks.clone())
+    }
+    
+    // デーモン上のネットワーク名（外部ネットワーク以外はプロジェクト名を付ける）
+    fn network_name(&self, network: &str) -> String {
+        match self.config.networks.get(network) {
+            Some(config) if config.external => network.to_string(),
+            _ => format!("{}_{}", self.project_name, network),
+        }
+    }
+    
+    // サービスのイメージ名（image が無ければ {プロジェクト名}_{サービス名}）
+    fn image_name(&self, service_name: &str) -> String {
+        match self.config.services.get(service_name).and_then(|service| service.image.clone()) {
+            Some(image) => image,
+            None => format!("{}_{}", self.project_name, service_name),
+        }
+    }
+    
+    // サービスの build の設定に従ってデーモンでイメージをビルドする
+    async fn build_service(&self, service_name: &str, prefix: &str, pull: bool) -> Result<(), Box<dyn Error>> {
+        let service = self.config.services.get(service_name)
+            .ok_or_else(|| ComposeError::ServiceNotFound(service_name.to_string()))?;
+        let build_config = service.build.as_ref()
+            .ok_or_else(|| ComposeError::InvalidConfig(format!("Service {} has no build section", service_name)))?;
+        info!("Building image for service: {}", service_name);
+        
+        let (context, rockerfile, args) = match build_config {
+            BuildConfig::String(context) => (context.clone(), None, None),
+            BuildConfig::Object { context, rockerfile, args } => (context.clone(), rockerfile.clone(), args.clone()),
+        };
+        
+        // ビルドコンテキストを tar にしてデーモンに送る
+        let archive = images::context_archive(&self.project_dir.join(context)).await?;
+        
+        let mut query = format!("t={}", client::encode(&self.image_name(service_name)));
+        if let Some(rockerfile) = rockerfile {
+            query.push_str(&format!("&rockerfile={}", client::encode(&rockerfile)));
+        }
+        let mut args: Vec<(String, String)> = args.unwrap_or_default().into_iter().collect();
+        args.sort();
+        for (key, value) in args {
+            query.push_str(&format!("&buildarg={}", client::encode(&format!("{}={}", key, value))));
+        }
+        if pull {
+            query.push_str("&pull=1");
+        }
+        
+        let lines = Client::new()
+            .post_body_lines(&format!("/build?{}", query), archive, "application/x-tar")
+            .await?;
+        images::print_progress(prefix, lines).await
+    }
+    
+    
+    async fn stop_service(&self, service_name: &str, timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
         info!("Stopping service: {}", service_name);
         
-        // コンテナ名を生成
-        let container_name = format!("{}_{}", self.project_name, service_name);
+        // サービスの全てのコンテナを停止して削除
+        for (_, container) in self.service_containers(service_name).await? {
+            stop_and_remove(&container, timeout).await?;
+        }
+        Ok(())
+    }
+    
+    // プロジェクトのラベルが付いているが compose ファイルに無いサービスのコンテナ
+    async fn remove_orphans(&self, options: &DownOptions) -> Result<(), Box<dyn Error>> {
+        let orphans: Vec<Container> = self.project_containers(true).await?
+            .into_iter()
+            .filter(|container| container.config.labels.get(SERVICE_LABEL)
+                .is_none_or(|service| !self.config.services.contains_key(service)))
+            .collect();
+        if orphans.is_empty() {
+            return Ok(());
+        }
         
-        // コンテナを停止して削除
-        // TODO: デーモンAPIでコンテナ停止と削除
+        if !options.remove_orphans {
+            let names: Vec<&str> = orphans.iter().map(|container| container.name.as_str()).collect();
+            warn!(
+                "Found orphan containers ({}) for this project. Run down with --remove-orphans to remove them.",
+                names.join(", ")
+            );
+            return Ok(());
+        }
+        for container in &orphans {
+            info!("Removing orphan container: {}", container.name);
+            stop_and_remove(container, options.timeout).await?;
+        }
+        Ok(())
+    }
+    
+    // サービスのイメージを削除する（使っているコンテナが残っているイメージは警告して残す）
+    async fn remove_images(&self, remove_images: RemoveImages) -> Result<(), Box<dyn Error>> {
+        let mut names: Vec<String> = self.config.services.iter()
+            .filter(|(_, service)| remove_images == RemoveImages::All || service.image.is_none())
+            .map(|(service_name, _)| self.image_name(service_name))
+            .collect();
+        names.sort();
+        names.dedup();
         
+        let client = Client::new();
+        for name in &names {
+            info!("Removing image: {}", name);
+            if let Err(e) = removed_or_missing(client.delete(&format!("/images/{}", client::encode(name))).await) {
+                warn!("Failed to remove image {}: {}", name, e);
+            }
+        }
         Ok(())
     }
     
+    // プロジェクトのラベルが付いたネットワークを削除する（compose ファイルから消したネットワークも含む）
     async fn remove_networks(&self) -> Result<(), Box<dyn Error>> {
         info!("Removing networks for project {}", self.project_name);
         
-        for network_name in self.config.networks.keys() {
-            let full_name = format!("{}_{}",  self.project_name, network_name);
-            info!("Removing network: {}", full_name);
+        let client = Client::new();
+        let networks: Vec<Network> = client.get(&format!("/networks?filter={}", self.project_filter())).await?;
+        for network in &networks {
+            info!("Removing network: {}", network.name);
             
-            // NetworkManager APIを使ってネットワーク削除
-            // TODO: デーモンAPIでネットワーク削除
+            // 残した孤立コンテナが接続しているネットワークは削除できない
+            if !removed(client.delete(&format!("/networks/{}", client::encode(&network.name))).await)? {
+                warn!("Network {} is still in use and was not removed", network.name);
+            }
         }
         
         Ok(())
     }
     
+    // プロジェクトのラベルが付いたボリュームを削除する（compose ファイルから消したボリュームも含む）
     async fn remove_volumes(&self) -> Result<(), Box<dyn Error>> {
         info!("Removing volumes for project {}", self.project_name);
         
-        for volume_name in self.config.volumes.keys() {
-            let full_name = format!("{}_{}",  self.project_name, volume_name);
-            info!("Removing volume: {}", full_name);
+        let client = Client::new();
+        let volumes: Vec<Volume> = client.get(&format!("/volumes?filter={}", self.project_filter())).await?;
+        for volume in &volumes {
+            info!("Removing volume: {}", volume.name);
             
-            // VolumeManager APIを使ってボリューム削除
-            // TODO: デーモンAPIでボリューム削除
+            removed_or_missing(client.delete(&format!("/volumes/{}", client::encode(&volume.name))).await)?;
         }
         
         Ok(())
     }
 }
 
+// 読む compose ファイルの一覧
+fn config_files<P: AsRef<Path>>(files: &[P]) -> Vec<PathBuf> {
+    if !files.is_empty() {
+        return files.iter().map(|file| file.as_ref().to_path_buf()).collect();
+    }
+    let mut files = vec![PathBuf::from(DEFAULT_CONFIG_FILE)];
+    if Path::new(DEFAULT_OVERRIDE_FILE).is_file() {
+        files.push(PathBuf::from(DEFAULT_OVERRIDE_FILE));
+    }
+    files
+}
+
+// compose ファイルをそれぞれ変数と extends を展開してから順に重ねる
+fn load_configs(config_paths: &[PathBuf], project_dir: &Path) -> Result<serde_yaml::Value, Box<dyn Error>> {
+    let mut config = serde_yaml::Value::Null;
+    for config_path in config_paths {
+        let overlay = interpolate::load_config(config_path, project_dir)
+            .and_then(|mut overlay| {
+                extends::resolve(&mut overlay, config_path, project_dir)?;
+                Ok(overlay)
+            })
+            .map_err(|e| load_error(format!("Failed to load {}", config_path.display()), e))?;
+        if config.is_null() {
+            config = overlay;
+        } else {
+            merge::merge(&mut config, overlay);
+        }
+    }
+    Ok(config)
+}
+
+// 重ねた設定を検証してから読む（対応していないキーは警告して無視する）
+// 読み込めなかった compose ファイルのエラー（中の設定の誤りはメッセージだけを使う）
+fn load_error(context: String, e: Box<dyn Error>) -> ComposeError {
+    match e.downcast_ref::<ComposeError>() {
+        Some(ComposeError::InvalidConfig(message)) => ComposeError::InvalidConfig(format!("{}: {}", context, message)),
+        _ => ComposeError::InvalidConfig(format!("{}: {}", context, e)),
+    }
+}
+
+fn parse_config(config: serde_yaml::Value, config_paths: &[PathBuf]) -> Result<ComposeConfig, Box<dyn Error>> {
+    let report = validate::check(&config);
+    if !report.warnings.is_empty() {
+        for warning in validate::Report::format(&report.warnings, config_paths).lines() {
+            warn!("{}", warning);
+        }
+    }
+    if !report.errors.is_empty() {
+        return Err(ComposeError::InvalidConfig(validate::Report::format(&report.errors, config_paths)).into());
+    }
+    serde_yaml::from_value(config).map_err(|e| ComposeError::InvalidConfig(e.to_string()).into())
+}
+
+// コンテナの番号（ラベルが無い古いコンテナは 1 番とみなす）
+fn container_number(container: &Container) -> usize {
+    container
+        .config
+        .labels
+        .get(CONTAINER_NUMBER_LABEL)
+        .and_then(|number| number.parse().ok())
+        .unwrap_or(1)
+}
+
+// healthcheck の 1m30s・10s・500ms などの時間を秒にする（1 秒未満は切り上げる）
+fn parse_duration(value: &str) -> Result<u64, Box<dyn Error>> {
+    let duration = rocker_core::parse_duration(value.trim())?;
+    Ok(duration.as_millis().div_ceil(1_000) as u64)
+}
+
+// --scale SERVICE=NUM の指定をサービスごとのコンテナ数にする
+pub fn parse_scale(specs: &[String]) -> Result<HashMap<String, usize>, Box<dyn Error>> {
+    let mut scale = HashMap::new();
+    for spec in specs {
+        let (service, replicas) = spec
+            .split_once('=')
+            .ok_or_else(|| ComposeError::InvalidConfig(format!("Invalid scale (expected SERVICE=NUM): {}", spec)))?;
+        let replicas = replicas
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid number of containers for {}: {}", service, replicas))?;
+        scale.insert(service.to_string(), replicas);
+    }
+    Ok(scale)
+}
+
+// 指定したサービスのコンテナか（compose run のコンテナは含めない）
+fn is_service_container(container: &Container, services: &[&String]) -> bool {
+    let labels = &container.config.labels;
+    !labels.contains_key(ONEOFF_LABEL) && labels.get(SERVICE_LABEL).is_some_and(|service| services.contains(&service))
+}
+
+// 動作中・一時停止中のコンテナを停止する（timeout が無ければデーモンの既定の猶予）
+async fn stop_container(container: &Container, timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
+    if !container.state.is_running() && !container.state.is_paused() {
+        return Ok(());
+    }
+    info!("Stopping container: {}", container.name);
+    let path = match timeout {
+        Some(timeout) => format!("/containers/{}/stop?t={}", container.id, timeout),
+        None => format!("/containers/{}/stop", container.id),
+    };
+    Client::new().post_empty(&path).await
+}
+
+// 動作中のコンテナを猶予を付けて停止してから削除する（timeout が無ければ削除と同時に停止する）
+async fn stop_and_remove(container: &Container, timeout: Option<u64>) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    if let Some(timeout) = timeout {
+        if container.state.is_running() || container.state.is_paused() {
+            removed_or_missing(client.post_empty(&format!("/containers/{}/stop?t={}", container.id, timeout)).await)?;
+        }
+    }
+    removed_or_missing(client.delete(&format!("/containers/{}?force=1", container.id)).await)
+}
+
+// 削除したか（使用中で削除できなかった場合は false、既に削除されていれば true）
+fn removed(result: Result<(), Box<dyn Error>>) -> Result<bool, Box<dyn Error>> {
+    match removed_or_missing(result) {
+        Ok(()) => Ok(true),
+        Err(e) if e
+            .downcast_ref::<ClientError>()
+            .is_some_and(|e| e.status == hyper::StatusCode::CONFLICT) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// 作成したか（作成済みのネットワーク・ボリュームは false を返してそのまま使う）
+fn created<T>(result: Result<T, Box<dyn Error>>) -> Result<bool, Box<dyn Error>> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(e) if e
+            .downcast_ref::<ClientError>()
+            .is_some_and(|e| e.status == hyper::StatusCode::CONFLICT) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// 既に削除されているネットワーク・ボリューム・コンテナは飛ばす
+fn removed_or_missing(result: Result<(), Box<dyn Error>>) -> Result<(), Box<dyn Error>> {
+    match result {
+        Err(e) if client::is_not_found(e.as_ref()) => Ok(()),
+        result => result,
+    }
+}
+
 // Composeツールのエントリーポイント
 pub async fn up_command(
-    file: Option<&str>,
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    options: &UpOptions,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.up(services, options).await
+}
+
+pub async fn scale_command(
+    files: &[String],
+    project_name: Option<&str>,
+    scale: &HashMap<String, usize>,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.scale(scale).await
+}
+
+pub async fn logs_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    options: &LogsOptions,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.logs(services, options).await
+}
+
+pub async fn ps_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    all: bool,
+    quiet: bool,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.ps(services, all, quiet).await
+}
+
+pub async fn top_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.top(services).await
+}
+
+pub async fn stats_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.stats(services).await
+}
+
+pub async fn exec_command(
+    files: &[String],
+    project_name: Option<&str>,
+    service: &str,
+    cmd: Vec<String>,
+    options: &ExecOptions,
+) -> Result<i32, Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.exec(service, cmd, options).await
+}
+
+pub async fn run_command(
+    files: &[String],
+    project_name: Option<&str>,
+    service: &str,
+    cmd: Vec<String>,
+    options: &RunOptions,
+) -> Result<i32, Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.run(service, cmd, options).await
+}
+
+pub async fn build_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    options: &BuildOptions,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.build(services, options).await
+}
+
+pub async fn pull_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    parallel: bool,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.pull(services, parallel).await
+}
+
+pub async fn push_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    parallel: bool,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.push(services, parallel).await
+}
+
+// compose ファイルが参照する変数（名前順）と、環境変数か .env の今の値
+//
+// config_paths が空の場合は既定のファイルを読む。extends で読み込むファイルの変数は含まない。
+pub fn variables<P: AsRef<Path>>(config_paths: &[P]) -> Result<Vec<Variable>, Box<dyn Error>> {
+    let config_paths = config_files(config_paths);
+    let project_dir = config_paths[0].parent().unwrap_or(Path::new("."));
+    let mut variables = BTreeMap::new();
+    for config_path in &config_paths {
+        interpolate::collect_variables(config_path, project_dir, &mut variables)
+            .map_err(|e| load_error(format!("Failed to load {}", config_path.display()), e))?;
+    }
+    Ok(variables.into_values().collect())
+}
+
+// 変数を展開して重ねた compose ファイルを検証し、キーを名前順に並べて表示する（quiet の場合は検証だけ行う）
+pub fn config_command(files: &[String], quiet: bool) -> Result<(), Box<dyn Error>> {
+    let config_paths = config_files(files);
+    let project_dir = config_paths[0].parent().unwrap_or(Path::new("."));
+    let config = load_configs(&config_paths, project_dir)?;
+    parse_config(config.clone(), &config_paths)?;
+    if !quiet {
+        print!("{}", serde_yaml::to_string(&validate::canonical(&config))?);
+    }
+    Ok(())
+}
+
+// compose config --services：サービスの名前を 1 行に 1 つずつ表示する
+pub fn config_services_command(files: &[String]) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, None)?;
+    for (name, _) in project.config().services() {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+// compose config --images：サービスが使うイメージ（build だけのサービスはプロジェクトがビルドするイメージ名）を重複なしで表示する
+pub fn config_images_command(files: &[String], project_name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    let images: std::collections::BTreeSet<String> = project.images().map(|(_, image)| image).collect();
+    for image in images {
+        println!("{}", image);
+    }
+    Ok(())
+}
+
+// compose config --graph：depends_on の依存グラフを表示する
+pub fn config_graph_command(files: &[String], project_name: Option<&str>, format: GraphFormat) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    print!("{}", project.dependency_graph(format)?);
+    Ok(())
+}
+
+pub async fn start_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.start(services).await
+}
+
+pub async fn stop_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    timeout: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.stop(services, timeout).await
+}
+
+pub async fn restart_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    timeout: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.restart(services, timeout).await
+}
+
+pub async fn pause_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.pause(services).await
+}
+
+pub async fn unpause_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.unpause(services).await
+}
+
+pub async fn events_command(
+    files: &[String],
+    project_name: Option<&str>,
+    services: &[String],
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.events(services, json).await
+}
+
+pub async fn port_command(
+    files: &[String],
+    project_name: Option<&str>,
+    service: &str,
+    port: u16,
+    protocol: PortProtocol,
+    index: usize,
+) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.port(service, port, protocol, index).await
+}
+
+// compose ファイルを別の形式に変換して表示する
+pub fn convert_command(files: &[String], project_name: Option<&str>, format: ConvertFormat) -> Result<(), Box<dyn Error>> {
+    match format {
+        ConvertFormat::Yaml => config_command(files, false),
+        ConvertFormat::Kubernetes => {
+            let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+            print!("{}", project.kubernetes_manifests()?);
+            Ok(())
+        }
+    }
+}
+
+// サービスをバックグラウンドで起動し、develop.watch のパスの変更を反映し続ける
+pub async fn watch_command(
+    files: &[String],
     project_name: Option<&str>,
-    detached: bool,
+    services: &[String],
 ) -> Result<(), Box<dyn Error>> {
-    let config_path = file.unwrap_or("rocker-compose.yaml");
-    let project = ComposeProject::new(config_path, project_name.map(|s| s.to_string()))?;
-    project.up(detached).await
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    let options = UpOptions {
+        detach: true,
+        watch: true,
+        ..UpOptions::default()
+    };
+    project.up(services, &options).await
 }
 
 pub async fn down_command(
-    file: Option<&str>,
+    files: &[String],
     project_name: Option<&str>,
-    remove_volumes: bool,
+    options: &DownOptions,
 ) -> Result<(), Box<dyn Error>> {
-    let config_path = file.unwrap_or("rocker-compose.yaml");
-    let project = ComposeProject::new(config_path, project_name.map(|s| s.to_string()))?;
-    project.down(remove_volumes).await
+    let project = ComposeProject::new(files, project_name.map(|s| s.to_string()))?;
+    project.down(options).await
 } 
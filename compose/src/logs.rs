@@ -0,0 +1,92 @@
+use futures::stream::{self, StreamExt};
+use rocker_core::LogRecord;
+use std::error::Error;
+use std::io::Write;
+
+use crate::client::{encode, is_not_found, use_color, Client, Lines};
+
+// サービスごとのプレフィックスの色（docker compose と同じ順に割り当てる）
+const COLORS: [&str; 6] = ["36", "33", "32", "35", "34", "96"];
+
+// compose logs のオプション
+#[derive(Debug, Clone, Default)]
+pub struct LogsOptions {
+    // コンテナが出力を続ける間、待ち続ける
+    pub follow: bool,
+    // コンテナごとに末尾の行数だけ表示する
+    pub tail: Option<usize>,
+    pub timestamps: bool,
+    pub no_color: bool,
+}
+
+// ログを読むコンテナ（プレフィックスはサービス名）
+pub struct LogSource {
+    pub prefix: String,
+    pub container: String,
+}
+
+// 複数のコンテナのログを 1 つにまとめ、行の先頭に "サービス名 |" を付けて表示する
+pub async fn print_logs(client: &Client, sources: Vec<LogSource>, options: &LogsOptions) -> Result<(), Box<dyn Error>> {
+    let color = !options.no_color && use_color(&std::io::stdout());
+    let width = sources.iter().map(|source| source.prefix.len()).max().unwrap_or(0);
+
+    let mut query = format!("follow={}", if options.follow { 1 } else { 0 });
+    if let Some(tail) = options.tail {
+        query.push_str(&format!("&tail={}", tail));
+    }
+
+    let mut streams = Vec::new();
+    for (index, source) in sources.into_iter().enumerate() {
+        let path = format!("/containers/{}/logs?{}", encode(&source.container), query);
+        let lines = match client.get_lines(&path).await {
+            Ok(lines) => lines,
+            // まだ作成されていないコンテナは飛ばす
+            Err(e) if is_not_found(e.as_ref()) => continue,
+            Err(e) => return Err(e),
+        };
+        let prefix = if color {
+            format!("\x1b[{}m{:<width$} |\x1b[0m", COLORS[index % COLORS.len()], source.prefix, width = width)
+        } else {
+            format!("{:<width$} |", source.prefix, width = width)
+        };
+        streams.push(records(prefix, lines).boxed_local());
+    }
+
+    // 届いた順に表示する（follow の場合は全てのコンテナが出力を閉じるまで続く）
+    let mut merged = stream::select_all(streams);
+    let stdout = std::io::stdout();
+    while let Some((prefix, record)) = merged.next().await {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!("{} error reading logs: {}", prefix, e);
+                continue;
+            }
+        };
+        let mut out = stdout.lock();
+        if options.timestamps {
+            writeln!(out, "{} {} {}", prefix, record.time.to_rfc3339(), record.line)?;
+        } else {
+            writeln!(out, "{} {}", prefix, record.line)?;
+        }
+    }
+    Ok(())
+}
+
+// ログの行を (プレフィックス, レコード) のストリームにする（読み込みに失敗したら終わる）
+fn records(
+    prefix: String,
+    lines: Lines,
+) -> impl futures::Stream<Item = (String, Result<LogRecord, String>)> {
+    stream::unfold(Some(lines), move |lines| {
+        let prefix = prefix.clone();
+        async move {
+            let mut lines = lines?;
+            match lines.next_json::<LogRecord>().await {
+                Ok(Some(record)) => Some(((prefix, Ok(record)), Some(lines))),
+                Ok(None) => None,
+                Err(e) => Some(((prefix, Err(e.to_string())), None)),
+            }
+        }
+    })
+}
@@ -0,0 +1,172 @@
+use serde_yaml::{Mapping, Value};
+
+// 後に指定した compose ファイルで前の設定を上書きする
+//
+// マッピングはキーごとに再帰的に合わせ、スカラーは置き換える。リストはキーによって扱いを変える:
+// command・entrypoint・healthcheck の test は置き換え、environment は変数名ごと、サービスの volumes は
+// コンテナ側のパスごとに上書きし、それ以外は重複を除いて後ろに追加する。
+pub(crate) fn merge(base: &mut Value, other: Value) {
+    merge_value(base, other, None);
+}
+
+fn merge_value(base: &mut Value, other: Value, key: Option<&str>) {
+    match (base, other) {
+        (Value::Mapping(base), Value::Mapping(other)) => merge_mapping(base, other),
+        (base @ Value::Sequence(_), Value::Sequence(other)) => match key {
+            Some("command" | "entrypoint" | "test") => *base = Value::Sequence(other),
+            Some("environment") => merge_by(base, other, environment_key),
+            Some("volumes") => merge_by(base, other, volume_target),
+            _ => {
+                let Value::Sequence(items) = base else { unreachable!() };
+                for item in other {
+                    if !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+            }
+        },
+        // environment はリストとマッピングのどちらでも書けるため、リストに揃えて合わせる
+        (base, other) if key == Some("environment") && base.is_mapping() != other.is_mapping() => {
+            *base = environment_list(std::mem::take(base));
+            match environment_list(other) {
+                Value::Sequence(other) => merge_by(base, other, environment_key),
+                other => *base = other,
+            }
+        }
+        (base, other) => *base = other,
+    }
+}
+
+fn merge_mapping(base: &mut Mapping, other: Mapping) {
+    for (key, value) in other {
+        match base.get_mut(&key) {
+            Some(existing) => merge_value(existing, value, key.as_str()),
+            None => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// 同じ識別子を持つ要素は後のもので置き換え、新しい識別子の要素は後ろに追加する
+fn merge_by(base: &mut Value, other: Vec<Value>, identity: fn(&Value) -> Option<String>) {
+    let Value::Sequence(items) = base else { return };
+    for item in other {
+        let id = identity(&item);
+        match items.iter().position(|existing| id.is_some() && identity(existing) == id) {
+            Some(pos) => items[pos] = item,
+            None if !items.contains(&item) => items.push(item),
+            None => {}
+        }
+    }
+}
+
+fn environment_list(value: Value) -> Value {
+    match value {
+        Value::Mapping(mapping) => Value::Sequence(
+            mapping
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let key = key.as_str()?.to_string();
+                    Some(Value::String(match scalar_string(&value) {
+                        Some(value) => format!("{}={}", key, value),
+                        None => key,
+                    }))
+                })
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+// KEY=VALUE の KEY
+fn environment_key(item: &Value) -> Option<String> {
+    let item = item.as_str()?;
+    Some(item.split_once('=').map_or(item, |(key, _)| key).to_string())
+}
+
+// SOURCE:TARGET[:MODE] の TARGET（SOURCE の無い匿名ボリュームはパスそのもの、長い書き方は target）
+fn volume_target(item: &Value) -> Option<String> {
+    if let Some(target) = item.get("target") {
+        return target.as_str().map(str::to_string);
+    }
+    let mut parts = item.as_str()?.split(':');
+    let first = parts.next()?;
+    Some(parts.next().unwrap_or(first).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(base: &str, other: &str) -> Value {
+        let mut base: Value = serde_yaml::from_str(base).unwrap();
+        merge(&mut base, serde_yaml::from_str(other).unwrap());
+        base
+    }
+
+    fn yaml(s: &str) -> Value {
+        serde_yaml::from_str(s).unwrap()
+    }
+
+    #[test]
+    fn mappings_merge_by_key_and_scalars_are_replaced() {
+        let config = merged("{image: app:1, labels: {a: x, b: y}}", "{image: app:2, labels: {b: z, c: w}}");
+        assert_eq!(config, yaml("{image: app:2, labels: {a: x, b: z, c: w}}"));
+    }
+
+    #[test]
+    fn commands_are_replaced() {
+        let config = merged("{command: [run, --debug]}", "{command: [serve]}");
+        assert_eq!(config, yaml("{command: [serve]}"));
+        let config = merged("{command: [run]}", "{command: serve --port 80}");
+        assert_eq!(config, yaml("{command: serve --port 80}"));
+    }
+
+    #[test]
+    fn other_lists_are_appended_without_duplicates() {
+        let config = merged("{ports: [\"80:80\", \"443:443\"]}", "{ports: [\"443:443\", \"8080:8080\"]}");
+        assert_eq!(config, yaml("{ports: [\"80:80\", \"443:443\", \"8080:8080\"]}"));
+    }
+
+    #[test]
+    fn environment_lists_merge_by_variable_name() {
+        let config = merged("{environment: [A=1, B=2]}", "{environment: [B=3, C]}");
+        assert_eq!(config, yaml("{environment: [A=1, B=3, C]}"));
+    }
+
+    #[test]
+    fn environment_list_and_mapping_merge_as_a_list() {
+        let config = merged("{environment: [A=1, B=2]}", "{environment: {B: 3, C: true, D: null}}");
+        assert_eq!(config, yaml("{environment: [A=1, B=3, C=true, D]}"));
+        let config = merged("{environment: {A: 1, B: 2}}", "{environment: [B=3]}");
+        assert_eq!(config, yaml("{environment: [A=1, B=3]}"));
+    }
+
+    #[test]
+    fn environment_mappings_merge_as_mappings() {
+        let config = merged("{environment: {A: 1, B: 2}}", "{environment: {B: 3}}");
+        assert_eq!(config, yaml("{environment: {A: 1, B: 3}}"));
+    }
+
+    #[test]
+    fn volumes_merge_by_container_path() {
+        let config = merged(
+            "{volumes: [\"data:/var/lib/db\", /cache, \"./conf:/etc/app:ro\"]}",
+            "{volumes: [\"other:/var/lib/db\", {type: bind, source: ./conf2, target: /etc/app}, /tmp]}",
+        );
+        assert_eq!(
+            config,
+            yaml("{volumes: [\"other:/var/lib/db\", /cache, {type: bind, source: ./conf2, target: /etc/app}, /tmp]}")
+        );
+    }
+}
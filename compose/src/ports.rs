@@ -0,0 +1,111 @@
+use rocker_core::PortBinding;
+use serde::{Deserialize, Serialize};
+
+// サービスの ports の 1 つ（"[ip:][published:]target[/protocol]" か、キーで書くマッピング）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ServicePort {
+    Short(String),
+    Long(PortConfig),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortConfig {
+    // コンテナのポート
+    target: u16,
+    // ホストのポートか範囲（指定しない場合は空いているポートを割り当てる）
+    published: Option<PublishedPort>,
+    host_ip: Option<String>,
+    protocol: Option<String>,
+    // host か ingress（どちらもホストのポートで公開する）
+    mode: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PublishedPort {
+    Number(u16),
+    Range(String),
+}
+
+impl ServicePort {
+    // デーモンに渡すポートの公開の指定（範囲は 1 ポートずつに展開する）
+    pub fn bindings(&self) -> Result<Vec<PortBinding>, String> {
+        let config = match self {
+            ServicePort::Short(spec) => return PortBinding::parse(spec),
+            ServicePort::Long(config) => config,
+        };
+        if let Some(mode) = config.mode.as_deref().filter(|mode| !matches!(*mode, "host" | "ingress")) {
+            return Err(format!("Invalid port mode (expected host or ingress): {}", mode));
+        }
+
+        let published = match &config.published {
+            Some(PublishedPort::Number(port)) => port.to_string(),
+            Some(PublishedPort::Range(range)) => range.clone(),
+            None => String::new(),
+        };
+        let mut spec = match &config.host_ip {
+            Some(host_ip) => format!("{}:{}:{}", host_ip, published, config.target),
+            None if !published.is_empty() => format!("{}:{}", published, config.target),
+            None => config.target.to_string(),
+        };
+        if let Some(protocol) = &config.protocol {
+            spec.push('/');
+            spec.push_str(protocol);
+        }
+        PortBinding::parse(&spec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocker_core::PortProtocol;
+
+    fn bindings(yaml: &str) -> Result<Vec<PortBinding>, String> {
+        serde_yaml::from_str::<ServicePort>(yaml).unwrap().bindings()
+    }
+
+    fn spec(bindings: &[PortBinding]) -> Vec<String> {
+        bindings.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn short_form_uses_the_run_syntax() {
+        assert_eq!(spec(&bindings("\"127.0.0.1:8080:80/udp\"").unwrap()), ["127.0.0.1:8080:80/udp"]);
+        assert_eq!(spec(&bindings("\"80\"").unwrap()), ["80/tcp"]);
+    }
+
+    #[test]
+    fn long_form_maps_each_key() {
+        let dns = bindings("{target: 53, published: 5353, host_ip: 127.0.0.1, protocol: udp, mode: host}").unwrap();
+        assert_eq!(dns.len(), 1);
+        assert_eq!(dns[0].host_ip, Some("127.0.0.1".parse().unwrap()));
+        assert_eq!(dns[0].host_port, Some(5353));
+        assert_eq!(dns[0].container_port, 53);
+        assert_eq!(dns[0].protocol, PortProtocol::Udp);
+
+        assert_eq!(spec(&bindings("{target: 80}").unwrap()), ["80/tcp"]);
+        assert_eq!(spec(&bindings("{target: 80, host_ip: 0.0.0.0}").unwrap()), ["0.0.0.0::80/tcp"]);
+    }
+
+    #[test]
+    fn ranges_expand_to_one_binding_per_port() {
+        assert_eq!(spec(&bindings("\"8000-8002:9000-9002\"").unwrap()), ["8000:9000/tcp", "8001:9001/tcp", "8002:9002/tcp"]);
+        assert_eq!(spec(&bindings("{target: 80, published: \"8080-8080\"}").unwrap()), ["8080:80/tcp"]);
+    }
+
+    #[test]
+    fn rejects_ranges_of_different_lengths() {
+        let error = bindings("\"8000-8002:9000-9001\"").unwrap_err();
+        assert!(error.contains("different lengths"), "{}", error);
+        let error = bindings("{target: 80, published: \"8080-8081\"}").unwrap_err();
+        assert!(error.contains("different lengths"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_unknown_modes_and_protocols() {
+        assert!(bindings("{target: 80, mode: bridge}").unwrap_err().contains("Invalid port mode"));
+        assert!(bindings("{target: 80, protocol: icmp}").is_err());
+    }
+}
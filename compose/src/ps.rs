@@ -0,0 +1,103 @@
+use rocker_core::{format_size, Container, ContainerStats, ContainerTop};
+
+use crate::SERVICE_LABEL;
+
+// compose ps の表（NAME・SERVICE・STATUS・PORTS）を表示する
+pub fn print_containers(containers: &[Container]) {
+    let rows: Vec<[String; 4]> = containers
+        .iter()
+        .map(|container| {
+            [
+                container.name.clone(),
+                container.config.labels.get(SERVICE_LABEL).cloned().unwrap_or_default(),
+                container.status(),
+                ports(container),
+            ]
+        })
+        .collect();
+    print_table(&["NAME", "SERVICE", "STATUS", "PORTS"], &rows);
+}
+
+// 公開中のポート（動作していなければ設定されたポート）を "0.0.0.0:8080->80/tcp" の形で並べる
+fn ports(container: &Container) -> String {
+    let ports = if container.ports.is_empty() {
+        &container.config.port_bindings
+    } else {
+        &container.ports
+    };
+    ports
+        .iter()
+        .map(|binding| match binding.host_port {
+            Some(host_port) => format!(
+                "{}:{}->{}/{}",
+                binding.host_ip.map_or("0.0.0.0".to_string(), |ip| ip.to_string()),
+                host_port,
+                binding.container_port,
+                binding.protocol
+            ),
+            None => format!("{}/{}", binding.container_port, binding.protocol),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// compose top の表（デーモンが返した列をそのまま表示する）
+pub fn print_top(top: &ContainerTop) {
+    let titles: Vec<&str> = top.titles.iter().map(String::as_str).collect();
+    print_table(&titles, &top.processes);
+}
+
+// compose stats の表（CPU 使用率は 2 回の統計の差から求める）
+pub fn print_stats(samples: &[(String, ContainerStats, ContainerStats)]) {
+    let rows: Vec<[String; 7]> = samples
+        .iter()
+        .map(|(name, previous, stats)| {
+            let (rx, tx) = stats.network_totals();
+            let (read, written) = stats.io_totals();
+            [
+                name.clone(),
+                format!("{:.2}%", stats.cpu_percent(previous)),
+                format!(
+                    "{} / {}",
+                    format_size(stats.memory.usage_bytes),
+                    stats.memory.limit_bytes.map_or("unlimited".to_string(), format_size)
+                ),
+                stats.memory_percent().map_or("--".to_string(), |percent| format!("{:.2}%", percent)),
+                format!("{} / {}", format_size(rx), format_size(tx)),
+                format!("{} / {}", format_size(read), format_size(written)),
+                stats.pids_current.map_or("--".to_string(), |pids| pids.to_string()),
+            ]
+        })
+        .collect();
+    print_table(&["NAME", "CPU %", "MEM USAGE / LIMIT", "MEM %", "NET I/O", "BLOCK I/O", "PIDS"], &rows);
+}
+
+// 列の幅を揃えて表示する（最後の列は詰めない）
+pub(crate) fn print_table<R: AsRef<[String]>>(titles: &[&str], rows: &[R]) {
+    let mut widths: Vec<usize> = titles.iter().map(|title| title.len()).collect();
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row.as_ref()) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let line = |values: Vec<&str>| {
+        let last = values.len().saturating_sub(1);
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                if index == last {
+                    value.to_string()
+                } else {
+                    format!("{:<width$}", value, width = widths[index])
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("   ")
+    };
+    println!("{}", line(titles.to_vec()));
+    for row in rows {
+        println!("{}", line(row.as_ref().iter().map(String::as_str).collect()));
+    }
+}
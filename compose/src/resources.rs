@@ -0,0 +1,176 @@
+use rocker_core::{ResourceLimits, Ulimit};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+// バイト数か 64m のような単位付きのサイズ
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ByteSize {
+    Bytes(u64),
+    String(String),
+}
+
+impl ByteSize {
+    // バイト数（k・m・g の単位は 1024 倍ずつ）
+    pub fn bytes(&self) -> Result<u64, String> {
+        let size = match self {
+            ByteSize::Bytes(bytes) => return Ok(*bytes),
+            ByteSize::String(size) => size.trim().to_lowercase(),
+        };
+        let digits = size.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        let multiplier = match size[digits.len()..].trim_end_matches('b') {
+            "" => 1,
+            "k" => 1 << 10,
+            "m" => 1 << 20,
+            "g" => 1 << 30,
+            _ => return Err(format!("Invalid size: {}", size)),
+        };
+        digits
+            .parse::<u64>()
+            .ok()
+            .and_then(|value| value.checked_mul(multiplier))
+            .ok_or_else(|| format!("Invalid size: {}", size))
+    }
+}
+
+// CPU 数（0.5 や "1.5" のように小数で書ける）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CpuCount {
+    Number(f64),
+    String(String),
+}
+
+impl CpuCount {
+    pub fn value(&self) -> Result<f64, String> {
+        let cpus = match self {
+            CpuCount::Number(cpus) => *cpus,
+            CpuCount::String(cpus) => cpus.trim().parse().map_err(|_| format!("Invalid number of CPUs: {}", cpus))?,
+        };
+        if cpus > 0.0 {
+            Ok(cpus)
+        } else {
+            Err(format!("Invalid number of CPUs: {}", cpus))
+        }
+    }
+}
+
+// deploy.resources
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ResourcesConfig {
+    pub limits: Option<ResourceSpec>,
+    // 確保しておく量（ホストでは memory だけを使う）
+    pub reservations: Option<ResourceSpec>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ResourceSpec {
+    pub cpus: Option<CpuCount>,
+    pub memory: Option<ByteSize>,
+    pub pids: Option<i64>,
+}
+
+// ulimits の 1 つ（上限を 1 つだけ書くか、soft と hard を分けて書く、-1 は無制限）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UlimitConfig {
+    Single(i64),
+    Pair { soft: i64, hard: i64 },
+}
+
+impl UlimitConfig {
+    pub fn to_ulimit(&self, name: &str) -> Result<Ulimit, String> {
+        match self {
+            UlimitConfig::Single(limit) => Ulimit::parse(&format!("{}={}", name, limit)),
+            UlimitConfig::Pair { soft, hard } => Ulimit::parse(&format!("{}={}:{}", name, soft, hard)),
+        }
+    }
+}
+
+// deploy.resources と サービス直下の cpus・mem_limit からリソースの制限を決める（deploy.resources を優先する）
+pub(crate) fn resource_limits(
+    resources: Option<&ResourcesConfig>,
+    cpus: Option<&CpuCount>,
+    mem_limit: Option<&ByteSize>,
+) -> Result<ResourceLimits, String> {
+    let limits = resources.and_then(|resources| resources.limits.as_ref());
+    let reservations = resources.and_then(|resources| resources.reservations.as_ref());
+    if reservations.is_some_and(|reservations| reservations.cpus.is_some() || reservations.pids.is_some()) {
+        warn!("deploy.resources.reservations supports only memory; cpus and pids are ignored");
+    }
+
+    let cpus = limits.and_then(|limits| limits.cpus.as_ref()).or(cpus);
+    let memory = limits.and_then(|limits| limits.memory.as_ref()).or(mem_limit);
+    let reservation = reservations.and_then(|reservations| reservations.memory.as_ref());
+    Ok(ResourceLimits {
+        cpus: cpus.map(CpuCount::value).transpose()?,
+        memory_bytes: memory.map(ByteSize::bytes).transpose()?,
+        memory_reservation_bytes: reservation.map(ByteSize::bytes).transpose()?,
+        pids_limit: limits.and_then(|limits| limits.pids),
+        ..ResourceLimits::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(yaml: &str) -> Result<u64, String> {
+        serde_yaml::from_str::<ByteSize>(yaml).unwrap().bytes()
+    }
+
+    fn cpus(yaml: &str) -> Result<f64, String> {
+        serde_yaml::from_str::<CpuCount>(yaml).unwrap().value()
+    }
+
+    #[test]
+    fn sizes_accept_bytes_and_binary_units() {
+        assert_eq!(size("1048576"), Ok(1 << 20));
+        assert_eq!(size("\"512\""), Ok(512));
+        assert_eq!(size("64m"), Ok(64 << 20));
+        assert_eq!(size("2GB"), Ok(2 << 30));
+        assert_eq!(size("\" 16kb \""), Ok(16 << 10));
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert!(size("1t").is_err());
+        assert!(size("m").is_err());
+        assert!(size("1.5g").is_err());
+        assert!(size("99999999999g").is_err());
+    }
+
+    #[test]
+    fn cpus_accept_numbers_and_strings() {
+        assert_eq!(cpus("0.5"), Ok(0.5));
+        assert_eq!(cpus("\"1.5\""), Ok(1.5));
+        assert!(cpus("0").is_err());
+        assert!(cpus("\"two\"").is_err());
+    }
+
+    #[test]
+    fn deploy_resources_take_precedence_over_service_keys() {
+        let resources: ResourcesConfig =
+            serde_yaml::from_str("{limits: {cpus: '2', memory: 1g, pids: 100}, reservations: {memory: 256m}}").unwrap();
+        let limits =
+            resource_limits(Some(&resources), Some(&CpuCount::Number(0.5)), Some(&ByteSize::Bytes(1024))).unwrap();
+        assert_eq!(limits.cpus, Some(2.0));
+        assert_eq!(limits.memory_bytes, Some(1 << 30));
+        assert_eq!(limits.memory_reservation_bytes, Some(256 << 20));
+        assert_eq!(limits.pids_limit, Some(100));
+
+        let limits = resource_limits(None, Some(&CpuCount::Number(0.5)), Some(&ByteSize::Bytes(1024))).unwrap();
+        assert_eq!(limits.cpus, Some(0.5));
+        assert_eq!(limits.memory_bytes, Some(1024));
+        assert_eq!(limits.memory_reservation_bytes, None);
+    }
+
+    #[test]
+    fn ulimits_accept_one_limit_or_a_pair() {
+        let nofile = serde_yaml::from_str::<UlimitConfig>("{soft: 1024, hard: 2048}").unwrap().to_ulimit("nofile").unwrap();
+        assert_eq!((nofile.soft, nofile.hard), (1024, 2048));
+        let nproc = serde_yaml::from_str::<UlimitConfig>("65535").unwrap().to_ulimit("nproc").unwrap();
+        assert_eq!((nproc.soft, nproc.hard), (65535, 65535));
+        assert!(serde_yaml::from_str::<UlimitConfig>("{soft: 2, hard: 1}").unwrap().to_ulimit("nofile").is_err());
+    }
+}
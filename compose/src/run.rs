@@ -0,0 +1,91 @@
+use rocker_core::{ExecConfig, ExecInstance, LogRecord, LogStream};
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::client::{Client, JsonStream};
+
+// 終了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// compose exec のオプション
+#[derive(Debug, Clone, Default)]
+pub struct ExecOptions {
+    // 出力を待たずに戻る
+    pub detach: bool,
+    pub user: Option<String>,
+    pub workdir: Option<String>,
+    // KEY=VALUE（KEY だけの場合は compose を実行した環境の値を使う）
+    pub env: Vec<String>,
+    // 複数のコンテナを持つサービスで対象にするコンテナの番号（省略時は 1）
+    pub index: Option<usize>,
+    // compose exec の標準入力をコマンドに渡す
+    pub interactive: bool,
+}
+
+// compose run のオプション
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    // コンテナを起動したらすぐに戻る
+    pub detach: bool,
+    // 終了したコンテナを削除する
+    pub rm: bool,
+    // コンテナ名（省略時は <project>_<service>_run_<ID>）
+    pub name: Option<String>,
+    pub user: Option<String>,
+    pub workdir: Option<String>,
+    pub env: Vec<String>,
+    // サービスの ports をホストに公開する（既定では依存するサービスと衝突しないよう公開しない）
+    pub service_ports: bool,
+    // depends_on のサービスを起動しない
+    pub no_deps: bool,
+    // サービスの x-wait-for に加えて、起動する前にデーモンが準備を待つ依存先
+    pub wait_for: Vec<String>,
+}
+
+// -e の値を環境変数にする（KEY だけの値はこのプロセスの環境変数から取り、未設定なら渡さない）
+pub fn parse_env(env: &[String]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    Ok(rocker_core::resolve_env::<&Path>(&[], env)?)
+}
+
+// 動作中のコンテナでコマンドを実行し、出力を表示して終了コードを返す
+pub async fn exec(client: &Client, container: &str, config: &ExecConfig, detach: bool) -> Result<i32, Box<dyn Error>> {
+    let created: serde_json::Value = client.post(&format!("/containers/{}/exec", container), config).await?;
+    let exec_id = created["id"]
+        .as_str()
+        .ok_or("The daemon did not return the exec ID")?
+        .to_string();
+
+    if detach {
+        client.post_empty(&format!("/exec/{}/start", exec_id)).await?;
+        return Ok(0);
+    }
+    let output = if config.attach_stdin {
+        client.attach_exec_with_stdin(&exec_id, tokio::io::stdin()).await?
+    } else {
+        client.attach_exec(&exec_id).await?
+    };
+    print_output(output).await?;
+
+    // 出力が閉じてから終了コードが記録されるまで待つ
+    loop {
+        let exec: ExecInstance = client.get(&format!("/exec/{}", exec_id)).await?;
+        if !exec.running {
+            return Ok(exec.exit_code.unwrap_or(0));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+// 出力を元のストリームに分けてそのまま表示する
+async fn print_output(mut output: JsonStream<LogRecord>) -> Result<(), Box<dyn Error>> {
+    while let Some(record) = output.next().await? {
+        match record.stream {
+            LogStream::Stdout => writeln!(std::io::stdout(), "{}", record.line)?,
+            LogStream::Stderr => writeln!(std::io::stderr(), "{}", record.line)?,
+        }
+    }
+    Ok(())
+}
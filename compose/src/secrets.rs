@@ -0,0 +1,175 @@
+use rocker_core::{ComposeError, Secret, SecretReference, DEFAULT_SECRET_MODE};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tracing::{info, warn};
+
+use crate::{created, removed, Client, ComposeProject, SECRET_LABEL};
+
+// トップレベルの secrets の 1 つ（file か environment の内容でデーモンに秘密情報を作る）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SecretConfig {
+    // プロジェクトのディレクトリからの相対パス
+    pub file: Option<String>,
+    // 内容を読む環境変数
+    pub environment: Option<String>,
+    // 既にデーモンにある秘密情報を使う（name が無ければキーの名前）
+    #[serde(default)]
+    pub external: bool,
+    pub name: Option<String>,
+}
+
+// サービスの secrets の 1 つ（トップレベルの名前か、キーで書くマッピング）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ServiceSecret {
+    Short(String),
+    Long(SecretMount),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SecretMount {
+    pub source: String,
+    // /run/secrets の下のファイル名か絶対パス（省略すると source）
+    pub target: Option<String>,
+    pub uid: Option<IdValue>,
+    pub gid: Option<IdValue>,
+    pub mode: Option<FileMode>,
+}
+
+// uid・gid（compose の仕様では文字列だが、数値も受け付ける）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IdValue {
+    Number(u32),
+    String(String),
+}
+
+// ファイルのパーミッション（YAML 1.2 では 0440 が 10 進数として読まれるため、数字をそのまま 8 進数として扱う）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FileMode {
+    Number(u32),
+    String(String),
+}
+
+impl IdValue {
+    fn to_id(&self, key: &str) -> Result<u32, Box<dyn Error>> {
+        match self {
+            IdValue::Number(id) => Ok(*id),
+            IdValue::String(id) => {
+                id.parse().map_err(|_| ComposeError::InvalidConfig(format!("Invalid {} of secret: {}", key, id)).into())
+            }
+        }
+    }
+}
+
+impl FileMode {
+    fn to_mode(&self) -> Result<u32, Box<dyn Error>> {
+        let digits = match self {
+            FileMode::Number(mode) => mode.to_string(),
+            FileMode::String(mode) => mode.trim_start_matches("0o").to_string(),
+        };
+        u32::from_str_radix(&digits, 8)
+            .ok()
+            .filter(|mode| *mode <= 0o777)
+            .ok_or_else(|| ComposeError::InvalidConfig(format!("Invalid mode of secret: {}", digits)).into())
+    }
+}
+
+impl ComposeProject {
+    // トップレベルの secrets の秘密情報を作る（外部の秘密情報はスキップし、既にあるものは作り直さない）
+    pub(crate) async fn create_secrets(&self) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        for (secret_name, secret_config) in &self.config.secrets {
+            if secret_config.external {
+                continue;
+            }
+
+            let full_name = self.secret_name(secret_name);
+            let data = self.secret_data(secret_name, secret_config)?;
+            // 内容が変わったことを config-hash で分かるようにする（ラベルにはハッシュだけを残す）
+            let labels = self.resource_labels(SECRET_LABEL, secret_name, &(secret_config, &data))?;
+            info!("Creating secret: {}", full_name);
+            if !created(client.create_secret(&full_name, &data, labels.clone()).await)? {
+                let secret = client.inspect_secret(&full_name).await?;
+                self.check_existing("Secret", &full_name, &secret.labels, &labels);
+            }
+        }
+        Ok(())
+    }
+
+    // プロジェクトのラベルが付いた秘密情報を削除する（コンテナが残っていて削除できないものは警告する）
+    pub(crate) async fn remove_secrets(&self) -> Result<(), Box<dyn Error>> {
+        let client = Client::new();
+        let secrets: Vec<Secret> = client.get(&format!("/secrets?filter={}", self.project_filter())).await?;
+        for secret in &secrets {
+            info!("Removing secret: {}", secret.name);
+            if !removed(client.remove_secret(&secret.name).await)? {
+                warn!("Secret {} is still in use and was not removed", secret.name);
+            }
+        }
+        Ok(())
+    }
+
+    // サービスの secrets をコンテナの秘密情報の参照にする
+    pub(crate) fn service_secrets(
+        &self,
+        service_name: &str,
+        secrets: &[ServiceSecret],
+    ) -> Result<Vec<SecretReference>, Box<dyn Error>> {
+        secrets
+            .iter()
+            .map(|secret| {
+                let (source, mount) = match secret {
+                    ServiceSecret::Short(source) => (source, None),
+                    ServiceSecret::Long(mount) => (&mount.source, Some(mount)),
+                };
+                if !self.config.secrets.contains_key(source) {
+                    let message = format!("Service {} refers to undefined secret {}", service_name, source);
+                    return Err(ComposeError::InvalidConfig(message).into());
+                }
+                let reference = SecretReference {
+                    source: self.secret_name(source),
+                    target: mount.and_then(|mount| mount.target.clone()).unwrap_or_else(|| source.clone()),
+                    uid: mount.and_then(|mount| mount.uid.as_ref()).map(|uid| uid.to_id("uid")).transpose()?.unwrap_or(0),
+                    gid: mount.and_then(|mount| mount.gid.as_ref()).map(|gid| gid.to_id("gid")).transpose()?.unwrap_or(0),
+                    mode: mount
+                        .and_then(|mount| mount.mode.as_ref())
+                        .map(FileMode::to_mode)
+                        .transpose()?
+                        .unwrap_or(DEFAULT_SECRET_MODE),
+                };
+                reference.validate()?;
+                Ok(reference)
+            })
+            .collect()
+    }
+
+    // デーモン上の秘密情報の名前（外部の秘密情報以外はプロジェクト名を付ける）
+    fn secret_name(&self, secret: &str) -> String {
+        match self.config.secrets.get(secret) {
+            Some(config) if config.external => config.name.clone().unwrap_or_else(|| secret.to_string()),
+            _ => format!("{}_{}", self.project_name, secret),
+        }
+    }
+
+    fn secret_data(&self, secret_name: &str, config: &SecretConfig) -> Result<Vec<u8>, Box<dyn Error>> {
+        match (&config.file, &config.environment) {
+            (Some(file), None) => {
+                let path = self.project_dir.join(file);
+                std::fs::read(&path).map_err(|e| {
+                    let message = format!("Couldn't read file {} of secret {}: {}", path.display(), secret_name, e);
+                    ComposeError::InvalidConfig(message).into()
+                })
+            }
+            (None, Some(variable)) => std::env::var(variable).map(String::into_bytes).map_err(|_| {
+                let message = format!("Environment variable {} of secret {} is not set", variable, secret_name);
+                ComposeError::InvalidConfig(message).into()
+            }),
+            _ => {
+                let message = format!("Secret {} needs either file or environment (or external: true)", secret_name);
+                Err(ComposeError::InvalidConfig(message).into())
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use rocker_core::{ComposeError, Service, ServiceSpec, STACK_LABEL};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use tracing::{info, warn};
+
+use crate::ps::print_table;
+use crate::{Client, ComposeProject, PROJECT_LABEL, SERVICE_LABEL};
+
+impl ComposeProject {
+    // compose ファイルのサービスをクラスタのサービスの定義にする（プロジェクト名がスタック名）
+    //
+    // サービス名とネットワーク名には compose と同じくスタック名を付ける。ネットワークはクラスタの
+    // オーバーレイネットワークになり、名前付きボリュームはタスクを動かす各ノードに作られる。
+    pub fn stack_services(&self) -> Result<Vec<ServiceSpec>, Box<dyn Error>> {
+        let mut specs = Vec::new();
+        for service_name in self.resolve_dependencies()? {
+            let service = &self.config.services[&service_name];
+            if service.image.is_none() {
+                return Err(ComposeError::InvalidConfig(format!(
+                    "Service {} has no image; stack deploy does not build, push an image the nodes can pull and set image",
+                    service_name
+                ))
+                .into());
+            }
+            // 秘密情報はノードごとのデーモンにあり、クラスタでは共有しない
+            if !service.secrets.is_empty() {
+                let message = format!("Service {} uses secrets, which are not supported by stack deploy", service_name);
+                return Err(ComposeError::InvalidConfig(message).into());
+            }
+            if service.build.is_some() {
+                warn!("Service {} is not built by stack deploy, the nodes pull {}", service_name, self.image_name(&service_name));
+            }
+            if !service.depends_on.services().is_empty() {
+                warn!("Tasks are started in any order; depends_on of service {} is ignored", service_name);
+            }
+            if !matches!(service.restart_policy.as_str(), "" | "always" | "unless-stopped") {
+                warn!("Swarm tasks are always restarted; restart of service {} is ignored", service_name);
+            }
+            let networks = self.service_networks(&service_name)?;
+            if networks.len() > 1 {
+                warn!(
+                    "Service {} is only attached to its first network {} in a stack",
+                    service_name,
+                    self.network_name(&networks[0])
+                );
+            }
+
+            let mut template = self.service_container_config(&service_name)?;
+            template.labels.remove(PROJECT_LABEL);
+            template.labels.remove(SERVICE_LABEL);
+            template.labels.insert(STACK_LABEL.to_string(), self.project_name.clone());
+            let constraints = service
+                .deploy
+                .as_ref()
+                .and_then(|deploy| deploy.placement.as_ref())
+                .map(|placement| placement.constraints.clone())
+                .unwrap_or_default();
+            specs.push(ServiceSpec {
+                name: format!("{}_{}", self.project_name, service_name),
+                replicas: self.replicas(&service_name) as u64,
+                template,
+                network: Some(self.network_name(&networks[0])),
+                constraints,
+                labels: HashMap::from([(STACK_LABEL.to_string(), self.project_name.clone())]),
+            });
+        }
+        Ok(specs)
+    }
+
+    // スタックのサービスを作成・更新する（prune ならファイルから消えたサービスを削除する）
+    pub async fn deploy_stack(&self, prune: bool) -> Result<(), Box<dyn Error>> {
+        let specs = self.stack_services()?;
+        let client = Client::new();
+        let existing = stack_services(&client, &self.project_name).await?;
+
+        for spec in &specs {
+            if existing.iter().any(|service| service.spec.name == spec.name) {
+                info!("Updating service {}", spec.name);
+                client.update_service(&spec.name, spec).await?;
+            } else {
+                info!("Creating service {}", spec.name);
+                client.create_service(spec).await?;
+            }
+        }
+        for service in &existing {
+            if specs.iter().any(|spec| spec.name == service.spec.name) {
+                continue;
+            }
+            if prune {
+                info!("Removing service {}", service.spec.name);
+                client.remove_service(&service.id).await?;
+            } else {
+                warn!("Service {} is no longer in the compose file (use --prune to remove it)", service.spec.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+// スタックのサービス（名前順）
+async fn stack_services(client: &Client, stack: &str) -> Result<Vec<Service>, Box<dyn Error>> {
+    Ok(client
+        .list_services()
+        .await?
+        .into_iter()
+        .filter(|service| service.spec.labels.get(STACK_LABEL).map(String::as_str) == Some(stack))
+        .collect())
+}
+
+// compose ファイルのサービスをクラスタにスタックとしてデプロイする
+pub async fn stack_deploy_command(files: &[String], stack: &str, prune: bool) -> Result<(), Box<dyn Error>> {
+    let project = ComposeProject::new(files, Some(stack.to_string()))?;
+    project.deploy_stack(prune).await
+}
+
+// スタックの一覧（NAME・SERVICES）を表示する
+pub async fn stack_ls_command() -> Result<(), Box<dyn Error>> {
+    let mut stacks: BTreeMap<String, usize> = BTreeMap::new();
+    for service in Client::new().list_services().await? {
+        if let Some(stack) = service.spec.labels.get(STACK_LABEL) {
+            *stacks.entry(stack.clone()).or_default() += 1;
+        }
+    }
+    let rows: Vec<[String; 2]> = stacks
+        .into_iter()
+        .map(|(stack, services)| [stack, services.to_string()])
+        .collect();
+    print_table(&["NAME", "SERVICES"], &rows);
+    Ok(())
+}
+
+// スタックのタスクの一覧（NAME・NODE・STATE・ERROR）を表示する
+pub async fn stack_ps_command(stack: &str) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    let services = stack_services(&client, stack).await?;
+    if services.is_empty() {
+        return Err(format!("Nothing found in stack: {}", stack).into());
+    }
+    let hostnames: HashMap<String, String> = client
+        .list_nodes()
+        .await?
+        .into_iter()
+        .map(|node| (node.id, node.hostname))
+        .collect();
+
+    let mut rows = Vec::new();
+    for service in &services {
+        for task in client.inspect_service(&service.id).await?.tasks {
+            let node = task.node_id.as_ref().map(|id| hostnames.get(id).unwrap_or(id).clone());
+            rows.push([
+                format!("{}.{}", service.spec.name, task.slot),
+                node.unwrap_or_default(),
+                task.state.to_string(),
+                task.error.unwrap_or_default(),
+            ]);
+        }
+    }
+    rows.sort();
+    print_table(&["NAME", "NODE", "STATE", "ERROR"], &rows);
+    Ok(())
+}
+
+// スタックのサービスを全て削除する（タスクのコンテナは各ノードが削除する）
+pub async fn stack_rm_command(stacks: &[String]) -> Result<(), Box<dyn Error>> {
+    let client = Client::new();
+    for stack in stacks {
+        let services = stack_services(&client, stack).await?;
+        if services.is_empty() {
+            return Err(format!("Nothing found in stack: {}", stack).into());
+        }
+        for service in services {
+            info!("Removing service {}", service.spec.name);
+            client.remove_service(&service.id).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use rocker_core::{ComposeError, ContainerConfig};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+
+// compose up のオプション
+#[derive(Debug, Clone, Default)]
+pub struct UpOptions {
+    // 起動したらすぐに戻る（指定しない場合は Ctrl+C でプロジェクトを停止する）
+    pub detach: bool,
+    // 起動する前に build のあるサービスのイメージをビルドする
+    pub build: bool,
+    // 設定が変わっていなくてもコンテナを作り直す
+    pub force_recreate: bool,
+    // 設定が変わっていても既存のコンテナを使う
+    pub no_recreate: bool,
+    // 指定したサービスの depends_on のサービスを起動しない
+    pub no_deps: bool,
+    // サービスごとのコンテナ数（deploy.replicas より優先する）
+    pub scale: HashMap<String, usize>,
+    // 起動した後、develop.watch のパスの変更をコンテナに反映し続ける
+    pub watch: bool,
+    // 全てのコンテナが動作して healthy になるまで待ってから戻る（デタッチモードになる）
+    pub wait: bool,
+    // wait で待つ最大の秒数（指定しない場合は待ち続ける）
+    pub wait_timeout: Option<u64>,
+}
+
+// 既存のコンテナを作り直す条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Recreate {
+    // 設定のハッシュが変わったコンテナだけ作り直す
+    Changed,
+    Always,
+    Never,
+}
+
+impl UpOptions {
+    pub(crate) fn recreate(&self) -> Result<Recreate, Box<dyn Error>> {
+        match (self.force_recreate, self.no_recreate) {
+            (true, true) => {
+                let message = "--force-recreate and --no-recreate cannot be combined";
+                Err(ComposeError::InvalidConfig(message.to_string()).into())
+            }
+            (true, false) => Ok(Recreate::Always),
+            (false, true) => Ok(Recreate::Never),
+            (false, false) => Ok(Recreate::Changed),
+        }
+    }
+}
+
+// コンテナの設定とイメージの ID のハッシュ（設定かイメージが変わったコンテナを作り直すために使う）
+//
+// HashMap の順序に左右されないよう、キーが整列される serde_json::Value を経由して直列化する。
+pub(crate) fn config_hash(config: &ContainerConfig, image_id: &str) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&serde_json::to_value(config)?)?);
+    hasher.update(image_id.as_bytes());
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// compose ファイルでのネットワーク・ボリュームの設定のハッシュ（作成後に設定が変わったことを知らせるために使う）
+pub(crate) fn resource_hash<T: Serialize>(config: &T) -> Result<String, Box<dyn Error>> {
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&serde_json::to_value(config)?)?);
+    Ok(format!("{:x}", hasher.finalize()))
+}
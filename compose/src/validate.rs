@@ -0,0 +1,496 @@
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// 設定の値に期待する形
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    Integer,
+    Bool,
+    // 文字列か文字列のリスト
+    StringOrList,
+    // 文字列のリスト
+    List,
+    // 値が文字列のマッピング
+    Map,
+    // KEY=VALUE のリストか、値が文字列のマッピング
+    ListOrMap,
+    // キーごとに形が決まったマッピング
+    Mapping(fn(&str) -> Option<Kind>),
+    // 名前ごとに同じ形の設定を持つマッピング（値は null でもよい）
+    Named(fn(&str) -> Option<Kind>),
+    // キーごとに形が決まったマッピングのリスト
+    MappingList(fn(&str) -> Option<Kind>),
+    // 文字列か、キーごとに形が決まったマッピングのリスト（ports・volumes の短い書き方と長い書き方）
+    EntryList(fn(&str) -> Option<Kind>),
+    // 文字列か整数
+    Scalar,
+    // ulimits（名前ごとに整数か、soft と hard のマッピング）
+    Ulimits,
+    // build（文字列かマッピング）
+    Build,
+    // depends_on（リストか、サービスごとに condition を書くマッピング）
+    DependsOn,
+}
+
+fn top_level(key: &str) -> Option<Kind> {
+    match key {
+        "version" => Some(Kind::String),
+        "services" => Some(Kind::Named(service)),
+        "networks" => Some(Kind::Named(network)),
+        "volumes" => Some(Kind::Named(volume)),
+        "secrets" => Some(Kind::Named(secret)),
+        _ => None,
+    }
+}
+
+fn service(key: &str) -> Option<Kind> {
+    match key {
+        "image" | "restart" | "user" | "working_dir" | "stop_grace_period" | "pull_policy" => Some(Kind::String),
+        "build" => Some(Kind::Build),
+        "command" | "entrypoint" | "env_file" | "dns" | "tmpfs" => Some(Kind::StringOrList),
+        "environment" | "extra_hosts" => Some(Kind::ListOrMap),
+        "networks" | "cap_add" | "cap_drop" => Some(Kind::List),
+        "privileged" => Some(Kind::Bool),
+        "cpus" | "mem_limit" => Some(Kind::Scalar),
+        "ulimits" => Some(Kind::Ulimits),
+        "ports" => Some(Kind::EntryList(port)),
+        "volumes" => Some(Kind::EntryList(service_volume)),
+        "secrets" => Some(Kind::EntryList(service_secret)),
+        "depends_on" => Some(Kind::DependsOn),
+        "labels" => Some(Kind::Map),
+        "healthcheck" => Some(Kind::Mapping(healthcheck)),
+        "deploy" => Some(Kind::Mapping(deploy)),
+        "develop" => Some(Kind::Mapping(develop)),
+        _ => None,
+    }
+}
+
+fn port(key: &str) -> Option<Kind> {
+    match key {
+        "target" => Some(Kind::Integer),
+        "published" => Some(Kind::Scalar),
+        "host_ip" | "protocol" | "mode" => Some(Kind::String),
+        _ => None,
+    }
+}
+
+fn service_volume(key: &str) -> Option<Kind> {
+    match key {
+        "type" | "source" | "target" => Some(Kind::String),
+        "read_only" => Some(Kind::Bool),
+        "bind" => Some(Kind::Mapping(bind_options)),
+        "volume" => Some(Kind::Mapping(volume_options)),
+        "tmpfs" => Some(Kind::Mapping(tmpfs_options)),
+        _ => None,
+    }
+}
+
+fn service_secret(key: &str) -> Option<Kind> {
+    match key {
+        "source" | "target" => Some(Kind::String),
+        "uid" | "gid" | "mode" => Some(Kind::Scalar),
+        _ => None,
+    }
+}
+
+fn bind_options(key: &str) -> Option<Kind> {
+    match key {
+        "propagation" | "selinux" => Some(Kind::String),
+        _ => None,
+    }
+}
+
+fn volume_options(key: &str) -> Option<Kind> {
+    match key {
+        "nocopy" => Some(Kind::Bool),
+        _ => None,
+    }
+}
+
+fn tmpfs_options(key: &str) -> Option<Kind> {
+    match key {
+        "size" => Some(Kind::Scalar),
+        _ => None,
+    }
+}
+
+fn build(key: &str) -> Option<Kind> {
+    match key {
+        "context" | "dockerfile" => Some(Kind::String),
+        "args" => Some(Kind::Map),
+        _ => None,
+    }
+}
+
+fn healthcheck(key: &str) -> Option<Kind> {
+    match key {
+        "test" => Some(Kind::StringOrList),
+        "interval" | "timeout" | "start_period" => Some(Kind::String),
+        "retries" => Some(Kind::Integer),
+        "disable" => Some(Kind::Bool),
+        _ => None,
+    }
+}
+
+fn deploy(key: &str) -> Option<Kind> {
+    match key {
+        "replicas" => Some(Kind::Integer),
+        "resources" => Some(Kind::Mapping(resources)),
+        "placement" => Some(Kind::Mapping(placement)),
+        _ => None,
+    }
+}
+
+fn placement(key: &str) -> Option<Kind> {
+    match key {
+        "constraints" => Some(Kind::List),
+        _ => None,
+    }
+}
+
+fn resources(key: &str) -> Option<Kind> {
+    match key {
+        "limits" | "reservations" => Some(Kind::Mapping(resource_spec)),
+        _ => None,
+    }
+}
+
+fn resource_spec(key: &str) -> Option<Kind> {
+    match key {
+        "cpus" | "memory" => Some(Kind::Scalar),
+        "pids" => Some(Kind::Integer),
+        _ => None,
+    }
+}
+
+fn ulimit(key: &str) -> Option<Kind> {
+    match key {
+        "soft" | "hard" => Some(Kind::Scalar),
+        _ => None,
+    }
+}
+
+fn develop(key: &str) -> Option<Kind> {
+    match key {
+        "watch" => Some(Kind::MappingList(watch_rule)),
+        _ => None,
+    }
+}
+
+fn watch_rule(key: &str) -> Option<Kind> {
+    match key {
+        "path" | "action" | "target" => Some(Kind::String),
+        "ignore" => Some(Kind::List),
+        _ => None,
+    }
+}
+
+fn dependency(key: &str) -> Option<Kind> {
+    match key {
+        "condition" => Some(Kind::String),
+        _ => None,
+    }
+}
+
+fn network(key: &str) -> Option<Kind> {
+    match key {
+        "driver" => Some(Kind::String),
+        "external" => Some(Kind::Bool),
+        "driver_opts" => Some(Kind::Map),
+        "ipam" => Some(Kind::Mapping(ipam)),
+        _ => None,
+    }
+}
+
+fn ipam(key: &str) -> Option<Kind> {
+    match key {
+        "driver" => Some(Kind::String),
+        "config" => Some(Kind::MappingList(ipam_pool)),
+        _ => None,
+    }
+}
+
+fn ipam_pool(key: &str) -> Option<Kind> {
+    match key {
+        "subnet" | "gateway" => Some(Kind::String),
+        _ => None,
+    }
+}
+
+fn volume(key: &str) -> Option<Kind> {
+    match key {
+        "driver" => Some(Kind::String),
+        "external" => Some(Kind::Bool),
+        "driver_opts" => Some(Kind::Map),
+        _ => None,
+    }
+}
+
+fn secret(key: &str) -> Option<Kind> {
+    match key {
+        "file" | "environment" | "name" => Some(Kind::String),
+        "external" => Some(Kind::Bool),
+        _ => None,
+    }
+}
+
+// 検証で見つかった問題（path は services.web.ports のようなキーの並び）
+pub(crate) struct Issue {
+    path: String,
+    message: String,
+}
+
+#[derive(Default)]
+pub(crate) struct Report {
+    // 使われないキー（無視して続ける）
+    pub(crate) warnings: Vec<Issue>,
+    // 読み込めない値
+    pub(crate) errors: Vec<Issue>,
+}
+
+// compose ファイルの設定を対応しているスキーマと照らし合わせる（x- で始まるキーは拡張として扱う）
+pub(crate) fn check(config: &Value) -> Report {
+    let mut report = Report::default();
+    match config {
+        Value::Mapping(mapping) => check_mapping(mapping, top_level, "", &mut report),
+        _ => report.error("", "the compose file must be a mapping"),
+    }
+    if config.get("services").is_none() {
+        report.error("services", "is required");
+    }
+    report
+}
+
+impl Report {
+    fn error(&mut self, path: &str, message: &str) {
+        self.errors.push(Issue {
+            path: path.to_string(),
+            message: message.to_string(),
+        });
+    }
+
+    // 問題を 1 行ずつ、キーを定義したファイルと行番号を付けて書く
+    pub(crate) fn format(issues: &[Issue], files: &[PathBuf]) -> String {
+        let locations: Vec<(&PathBuf, HashMap<String, usize>)> = files
+            .iter()
+            .map(|file| (file, key_lines(&std::fs::read_to_string(file).unwrap_or_default())))
+            .collect();
+        issues
+            .iter()
+            .map(|issue| {
+                // 後のファイルほど優先して値を決めるため、最後に定義したファイルの位置を示す
+                let location = locations
+                    .iter()
+                    .rev()
+                    .find_map(|(file, lines)| lines.get(&issue.path).map(|line| format!("{}:{}: ", file.display(), line)))
+                    .unwrap_or_default();
+                let path = if issue.path.is_empty() { String::new() } else { format!("{} ", issue.path) };
+                format!("{}{}{}", location, path, issue.message)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn check_mapping(mapping: &Mapping, schema: fn(&str) -> Option<Kind>, path: &str, report: &mut Report) {
+    for (key, value) in mapping {
+        let Some(key) = key.as_str() else {
+            report.error(path, "has a key that is not a string");
+            continue;
+        };
+        let child = join(path, key);
+        if key.starts_with("x-") {
+            continue;
+        }
+        match schema(key) {
+            Some(kind) => check_value(value, kind, &child, report),
+            None => report.warnings.push(Issue {
+                path: child,
+                message: "is not supported and will be ignored".to_string(),
+            }),
+        }
+    }
+}
+
+fn check_value(value: &Value, kind: Kind, path: &str, report: &mut Report) {
+    match kind {
+        Kind::Mapping(schema) => match value.as_mapping() {
+            Some(mapping) => check_mapping(mapping, schema, path, report),
+            None => report.error(path, expected(kind)),
+        },
+        Kind::Named(schema) => check_named(value, schema, path, report),
+        Kind::MappingList(schema) => match value.as_sequence() {
+            Some(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    match item.as_mapping() {
+                        Some(mapping) => check_mapping(mapping, schema, &join(path, &index.to_string()), report),
+                        None => report.error(path, expected(kind)),
+                    }
+                }
+            }
+            None => report.error(path, expected(kind)),
+        },
+        Kind::EntryList(schema) => match value.as_sequence() {
+            Some(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    match item {
+                        Value::String(_) => {}
+                        Value::Mapping(mapping) => check_mapping(mapping, schema, &join(path, &index.to_string()), report),
+                        _ => report.error(path, expected(kind)),
+                    }
+                }
+            }
+            None => report.error(path, expected(kind)),
+        },
+        Kind::Build => match value {
+            Value::String(_) => {}
+            Value::Mapping(mapping) => check_mapping(mapping, build, path, report),
+            _ => report.error(path, expected(kind)),
+        },
+        Kind::Ulimits => match value.as_mapping() {
+            Some(mapping) => {
+                for (name, limit) in mapping {
+                    let child = join(path, name.as_str().unwrap_or_default());
+                    match limit {
+                        Value::Number(_) => {}
+                        Value::Mapping(limit) => check_mapping(limit, ulimit, &child, report),
+                        _ => report.error(&child, "must be an integer or a mapping of soft and hard"),
+                    }
+                }
+            }
+            None => report.error(path, expected(kind)),
+        },
+        Kind::DependsOn => match value {
+            Value::Mapping(_) => check_named(value, dependency, path, report),
+            _ if is_string_list(value) => {}
+            _ => report.error(path, expected(kind)),
+        },
+        _ => {
+            let ok = match kind {
+                Kind::String => value.is_string(),
+                Kind::Integer => value.is_u64(),
+                Kind::Bool => value.is_bool(),
+                Kind::Scalar => value.is_string() || value.is_number(),
+                Kind::StringOrList => value.is_string() || is_string_list(value),
+                Kind::List => is_string_list(value),
+                Kind::Map => is_string_map(value),
+                _ => is_string_list(value) || is_string_map(value),
+            };
+            if !ok {
+                report.error(path, expected(kind));
+            }
+        }
+    }
+}
+
+// 名前ごとの設定（値が null の場合は既定の設定）
+fn check_named(value: &Value, schema: fn(&str) -> Option<Kind>, path: &str, report: &mut Report) {
+    let Some(mapping) = value.as_mapping() else {
+        report.error(path, "must be a mapping");
+        return;
+    };
+    for (name, entry) in mapping {
+        let child = join(path, name.as_str().unwrap_or_default());
+        match entry {
+            Value::Null => {}
+            Value::Mapping(entry) => check_mapping(entry, schema, &child, report),
+            _ => report.error(&child, "must be a mapping"),
+        }
+    }
+}
+
+fn expected(kind: Kind) -> &'static str {
+    match kind {
+        Kind::String => "must be a string",
+        Kind::Integer => "must be a non-negative integer",
+        Kind::Bool => "must be a boolean",
+        Kind::StringOrList => "must be a string or a list of strings",
+        Kind::List => "must be a list of strings",
+        Kind::Map => "must be a mapping of strings",
+        Kind::ListOrMap => "must be a list of strings or a mapping of strings",
+        Kind::Mapping(_) | Kind::Named(_) => "must be a mapping",
+        Kind::MappingList(_) => "must be a list of mappings",
+        Kind::EntryList(_) => "must be a list of strings or mappings",
+        Kind::Scalar => "must be a string or a number",
+        Kind::Ulimits => "must be a mapping",
+        Kind::Build => "must be a string or a mapping",
+        Kind::DependsOn => "must be a list of services or a mapping",
+    }
+}
+
+fn is_string_list(value: &Value) -> bool {
+    value.as_sequence().is_some_and(|items| items.iter().all(Value::is_string))
+}
+
+fn is_string_map(value: &Value) -> bool {
+    value.as_mapping().is_some_and(|mapping| mapping.values().all(Value::is_string))
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+// ブロック形式の YAML のキーの並び（a.b.c）ごとの最初の行番号
+//
+// インデントだけを見て組み立てるため、フロー形式の中のキーは数えない。
+fn key_lines(content: &str) -> HashMap<String, usize> {
+    let mut lines = HashMap::new();
+    // (インデント, キー)
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("- ") || trimmed == "-" {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let Some((key, _)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().trim_matches(|c| c == '"' || c == '\'');
+        while stack.last().is_some_and(|(parent, _)| *parent >= indent) {
+            stack.pop();
+        }
+        stack.push((indent, key.to_string()));
+        let path = stack.iter().map(|(_, key)| key.as_str()).collect::<Vec<_>>().join(".");
+        lines.entry(path).or_insert(number + 1);
+    }
+    lines
+}
+
+// 表示用に整えた設定（マッピングのキーを名前順に並べ、environment はマッピングに揃える）
+pub(crate) fn canonical(value: &Value) -> Value {
+    canonical_value(value, None)
+}
+
+fn canonical_value(value: &Value, key: Option<&str>) -> Value {
+    match value {
+        Value::Mapping(mapping) => {
+            let mut entries: Vec<(&Value, &Value)> = mapping.iter().collect();
+            entries.sort_by_key(|(key, _)| key.as_str().unwrap_or_default().to_string());
+            Value::Mapping(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.clone(), canonical_value(v, k.as_str())))
+                    .collect(),
+            )
+        }
+        Value::Sequence(items) if key == Some("environment") => Value::Mapping(
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|item| match item.split_once('=') {
+                    Some((name, value)) => (Value::from(name), Value::from(value)),
+                    None => (Value::from(item), Value::Null),
+                })
+                .collect(),
+        ),
+        Value::Sequence(items) => Value::Sequence(items.iter().map(|item| canonical_value(item, None)).collect()),
+        value => value.clone(),
+    }
+}
@@ -0,0 +1,75 @@
+use rocker_core::{Mount, MountType};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::resources::ByteSize;
+
+// サービスの volumes の 1 つ（"[source:]target[:mode]" か、キーで書くマッピング）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ServiceVolume {
+    Short(String),
+    Long(VolumeMount),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VolumeMount {
+    // volume・bind・tmpfs
+    #[serde(rename = "type")]
+    pub mount_type: String,
+    // ボリューム名かホストのパス（volume で省略した場合は匿名ボリューム）
+    pub source: Option<String>,
+    pub target: String,
+    #[serde(default)]
+    pub read_only: bool,
+    pub bind: Option<BindOptions>,
+    pub volume: Option<VolumeOptions>,
+    pub tmpfs: Option<TmpfsOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BindOptions {
+    pub propagation: Option<String>,
+    // z か Z
+    pub selinux: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VolumeOptions {
+    // 新しいボリュームにイメージのマウント先の内容をコピーしない
+    #[serde(default)]
+    pub nocopy: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TmpfsOptions {
+    pub size: Option<ByteSize>,
+}
+
+// tmpfs の 1 つ（"path[:options]"、options は size=64m のようにカンマで区切る）
+pub(crate) fn tmpfs_mount(spec: &str) -> Result<Mount, String> {
+    let (destination, options) = spec.split_once(':').unwrap_or((spec, ""));
+    if !destination.starts_with('/') {
+        return Err(format!("Invalid tmpfs destination (must be an absolute path): {}", spec));
+    }
+    let mut tmpfs_size = None;
+    let mut read_only = false;
+    for option in options.split(',').filter(|option| !option.is_empty()) {
+        match option.split_once('=') {
+            Some(("size", size)) => tmpfs_size = Some(ByteSize::String(size.to_string()).bytes()?),
+            None if option == "ro" => read_only = true,
+            None if option == "rw" => read_only = false,
+            _ => warn!("tmpfs option {} of {} is not supported and will be ignored", option, destination),
+        }
+    }
+    Ok(Mount {
+        mount_type: MountType::Tmpfs,
+        source: String::new(),
+        destination: destination.to_string(),
+        read_only,
+        propagation: None,
+        relabel: None,
+        no_copy: false,
+        tmpfs_size,
+    })
+}
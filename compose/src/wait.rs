@@ -0,0 +1,48 @@
+use rocker_core::{Container, HealthStatus};
+
+// compose up --wait で待つコンテナの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Readiness {
+    Ready(String),
+    Waiting(String),
+    // 待っても準備が整わない（終了した・unhealthy になった）
+    Failed(String),
+}
+
+// コンテナが動作していて、ヘルスチェックがあれば healthy になっているか（終了コード 0 で終わったコンテナも準備済みとする）
+pub(crate) fn readiness(container: &Container) -> Readiness {
+    let state = &container.state;
+    if state.is_running() {
+        return match container.health.as_ref().map(|health| health.status) {
+            None => Readiness::Ready("running".to_string()),
+            Some(HealthStatus::Healthy) => Readiness::Ready("healthy".to_string()),
+            Some(HealthStatus::Starting) => Readiness::Waiting("health: starting".to_string()),
+            Some(HealthStatus::Unhealthy) => Readiness::Failed("unhealthy".to_string()),
+        };
+    }
+    if state.is_created() || state.is_restarting() {
+        return Readiness::Waiting(format!("{:?}", state).to_lowercase());
+    }
+    match container.exit_code {
+        Some(0) => Readiness::Ready("exited (0)".to_string()),
+        Some(code) => Readiness::Failed(format!("exited ({})", code)),
+        None => Readiness::Failed(format!("{:?}", state).to_lowercase()),
+    }
+}
+
+// サービス・コンテナごとの状態を 1 行ずつ書く
+pub(crate) fn report(statuses: &[(String, String, Readiness)]) -> String {
+    let width = statuses.iter().map(|(service, _, _)| service.len()).max().unwrap_or(0);
+    statuses
+        .iter()
+        .map(|(service, container, readiness)| {
+            let status = match readiness {
+                Readiness::Ready(status) => status.clone(),
+                Readiness::Waiting(status) => format!("{} (not ready)", status),
+                Readiness::Failed(status) => format!("{} (failed)", status),
+            };
+            format!("  {:<width$}  {}  {}", service, container, status, width = width)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
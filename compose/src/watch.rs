@@ -0,0 +1,207 @@
+use rocker_core::ComposeError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// 監視するパスの変更を確認する間隔
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// サービスの develop（compose watch の設定）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DevelopConfig {
+    #[serde(default)]
+    pub watch: Vec<WatchRule>,
+}
+
+// 監視するホストのパスと、変更されたときの動作
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchRule {
+    // 監視するパス（プロジェクトのディレクトリからの相対パスでもよい）
+    pub path: String,
+    pub action: WatchAction,
+    // sync でファイルを置くコンテナ内のパス
+    pub target: Option<String>,
+    // 無視するパス（path からの相対パス、* と ** を使える）
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WatchAction {
+    // 変更したファイルを動作中のコンテナにコピーする
+    #[serde(rename = "sync")]
+    Sync,
+    // コンテナを再起動する
+    #[serde(rename = "restart")]
+    Restart,
+    // イメージをビルドし直してコンテナを作り直す
+    #[serde(rename = "rebuild")]
+    Rebuild,
+    // ファイルをコピーしてからコンテナを再起動する
+    #[serde(rename = "sync+restart")]
+    SyncRestart,
+}
+
+// 1 回の確認で見つかった変更（root からの相対パス）
+#[derive(Debug, Default)]
+pub(crate) struct Changes {
+    pub(crate) changed: Vec<PathBuf>,
+    pub(crate) removed: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+// 1 つの監視ルールについて、ファイルの更新時刻を前回の確認と比べて変更を見つける
+pub(crate) struct Watcher {
+    pub(crate) service: String,
+    pub(crate) rule: WatchRule,
+    root: PathBuf,
+    files: HashMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    pub(crate) fn new(service: &str, rule: &WatchRule, project_dir: &Path) -> Result<Self, Box<dyn Error>> {
+        if matches!(rule.action, WatchAction::Sync | WatchAction::SyncRestart) && rule.target.is_none() {
+            let message = format!("develop.watch of service {} needs a target to sync {}", service, rule.path);
+            return Err(ComposeError::InvalidConfig(message).into());
+        }
+        let root = project_dir.join(&rule.path);
+        let mut watcher = Watcher {
+            service: service.to_string(),
+            rule: rule.clone(),
+            root,
+            files: HashMap::new(),
+        };
+        watcher.files = watcher.scan();
+        Ok(watcher)
+    }
+
+    // 前回の確認から追加・更新・削除されたファイル
+    pub(crate) fn poll(&mut self) -> Changes {
+        let files = self.scan();
+        let mut changes = Changes::default();
+        for (path, modified) in &files {
+            if self.files.get(path) != Some(modified) {
+                changes.changed.push(path.clone());
+            }
+        }
+        for path in self.files.keys() {
+            if !files.contains_key(path) {
+                changes.removed.push(path.clone());
+            }
+        }
+        changes.changed.sort();
+        changes.removed.sort();
+        self.files = files;
+        changes
+    }
+
+    // root の下のファイル（root がファイルならそのファイルだけ）と更新時刻
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut files = HashMap::new();
+        match std::fs::symlink_metadata(&self.root) {
+            Ok(metadata) if metadata.is_dir() => self.scan_dir(&self.root, &mut files),
+            Ok(metadata) => {
+                files.insert(PathBuf::new(), metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+            }
+            Err(_) => {}
+        }
+        files
+    }
+
+    fn scan_dir(&self, dir: &Path, files: &mut HashMap<PathBuf, SystemTime>) {
+        // 監視中に消えたディレクトリは次の確認で削除として扱う
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+            if self.ignored(&relative) {
+                continue;
+            }
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            if metadata.is_dir() {
+                self.scan_dir(&path, files);
+            } else {
+                files.insert(relative, metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH));
+            }
+        }
+    }
+
+    fn ignored(&self, relative: &Path) -> bool {
+        let relative = relative.to_string_lossy();
+        self.rule
+            .ignore
+            .iter()
+            .any(|pattern| glob_match(pattern.trim_start_matches("./").trim_end_matches('/'), &relative))
+    }
+
+    // 変更されたファイルをコンテナ内の path に展開する tar（ファイルを 1 つだけ監視する場合は target の親に展開する）
+    pub(crate) fn sync_archive(&self, changed: &[PathBuf]) -> Result<(String, Vec<u8>), Box<dyn Error>> {
+        let target = Path::new(self.rule.target.as_deref().unwrap_or("/"));
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.follow_symlinks(false);
+        let path = if changed.iter().any(|path| path.as_os_str().is_empty()) {
+            let name = target
+                .file_name()
+                .ok_or_else(|| ComposeError::InvalidConfig(format!("Invalid sync target: {}", target.display())))?;
+            builder.append_path_with_name(&self.root, name)?;
+            target.parent().unwrap_or(Path::new("/"))
+        } else {
+            for relative in changed {
+                builder.append_path_with_name(self.root.join(relative), relative)?;
+            }
+            target
+        };
+        Ok((path.display().to_string(), builder.into_inner()?))
+    }
+
+    // 削除されたファイルのコンテナ内のパス
+    pub(crate) fn target_path(&self, relative: &Path) -> String {
+        let target = Path::new(self.rule.target.as_deref().unwrap_or("/"));
+        if relative.as_os_str().is_empty() {
+            target.display().to_string()
+        } else {
+            target.join(relative).display().to_string()
+        }
+    }
+}
+
+// パターンがパスかその親ディレクトリに一致するか（* は / 以外の文字列、** は任意の文字列、? は 1 文字）
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let mut prefix = String::new();
+    for component in path.split('/') {
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        prefix.push_str(component);
+        if glob_match_str(pattern.as_bytes(), prefix.as_bytes()) {
+            return true;
+        }
+    }
+    false
+}
+
+fn glob_match_str(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|index| glob_match_str(rest, &text[index..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|index| *index == 0 || text[index - 1] != b'/')
+            .any(|index| glob_match_str(rest, &text[index..])),
+        [b'?', rest @ ..] => text.first().is_some_and(|c| *c != b'/') && glob_match_str(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob_match_str(rest, &text[1..]),
+    }
+}
@@ -0,0 +1,343 @@
+use super::{ContainerConfig, DeviceMapping, Hook, HookStage, Mount, MountType, PropagationMode};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Directories searched for Container Device Interface (CDI) specs
+pub const CDI_SPEC_DIRS: [&str; 2] = ["/etc/cdi", "/var/run/cdi"];
+
+/// Device kinds tried in order when a GPU request does not name one
+pub const DEFAULT_GPU_KINDS: [&str; 2] = ["nvidia.com/gpu", "amd.com/gpu"];
+
+/// CdiSpec represents a CDI spec file describing the devices of one vendor/class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdiSpec {
+    /// Version of the CDI specification
+    pub cdi_version: String,
+    /// Kind of the devices (vendor/class, e.g. nvidia.com/gpu)
+    pub kind: String,
+    /// Devices described by the spec
+    pub devices: Vec<CdiDevice>,
+    /// Edits applied to every container using a device of this spec
+    #[serde(default)]
+    pub container_edits: CdiContainerEdits,
+}
+
+/// CdiDevice represents a single device of a CDI spec
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdiDevice {
+    /// Name of the device (e.g. 0, all)
+    pub name: String,
+    /// Edits applied to containers using this device
+    #[serde(default)]
+    pub container_edits: CdiContainerEdits,
+}
+
+/// CdiContainerEdits holds the changes a device requires in the container
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdiContainerEdits {
+    /// Environment variables (KEY=value)
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Device nodes to create
+    #[serde(default)]
+    pub device_nodes: Vec<CdiDeviceNode>,
+    /// Mounts (typically driver libraries and binaries)
+    #[serde(default)]
+    pub mounts: Vec<CdiMount>,
+    /// Hooks to run at container lifecycle points
+    #[serde(default)]
+    pub hooks: Vec<CdiHook>,
+}
+
+/// CdiDeviceNode represents a device node of a CDI device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdiDeviceNode {
+    /// Path of the device inside the container
+    pub path: String,
+    /// Path of the device on the host (defaults to `path`)
+    pub host_path: Option<String>,
+    /// Cgroup permissions (defaults to rwm)
+    pub permissions: Option<String>,
+}
+
+/// CdiMount represents a host path mounted into the container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdiMount {
+    /// Path on the host
+    pub host_path: String,
+    /// Path inside the container
+    pub container_path: String,
+    /// Mount options (e.g. ro, nosuid, bind)
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+/// CdiHook represents an executable run on the host at a lifecycle point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CdiHook {
+    /// Lifecycle point (e.g. createContainer)
+    pub hook_name: String,
+    /// Path of the executable
+    pub path: String,
+    /// Arguments, including argv[0]
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables (KEY=value)
+    #[serde(default)]
+    pub env: Vec<String>,
+}
+
+impl CdiContainerEdits {
+    /// Append the edits of another spec or device
+    pub fn merge(&mut self, other: &CdiContainerEdits) {
+        self.env.extend(other.env.iter().cloned());
+        self.device_nodes.extend(other.device_nodes.iter().cloned());
+        self.mounts.extend(other.mounts.iter().cloned());
+        self.hooks.extend(other.hooks.iter().cloned());
+    }
+
+    /// Apply the edits to a container configuration as devices, mounts, environment variables and hooks
+    pub fn apply_to(&self, config: &mut ContainerConfig) -> Result<(), String> {
+        for env in &self.env {
+            if let Some((key, value)) = env.split_once('=') {
+                config.env.insert(key.to_string(), value.to_string());
+            }
+        }
+
+        for node in &self.device_nodes {
+            config.devices.push(DeviceMapping {
+                path_on_host: node.host_path.clone().unwrap_or_else(|| node.path.clone()),
+                path_in_container: node.path.clone(),
+                cgroup_permissions: node.permissions.clone().unwrap_or_else(|| "rwm".to_string()),
+            });
+        }
+
+        for mount in &self.mounts {
+            config.mounts.push(Mount {
+                mount_type: MountType::Bind,
+                source: mount.host_path.clone(),
+                destination: mount.container_path.clone(),
+                read_only: mount.options.iter().any(|option| option == "ro"),
+                propagation: mount.options.iter().find_map(|option| PropagationMode::parse(option).ok()),
+                relabel: None,
+                no_copy: false,
+                tmpfs_size: None,
+            });
+        }
+
+        // args includes argv[0], which hooks do not take
+        for hook in &self.hooks {
+            config.hooks.push(Hook {
+                stage: HookStage::parse(&hook.hook_name)?,
+                path: hook.path.clone(),
+                args: hook.args.iter().skip(1).cloned().collect(),
+                env: hook.env.clone(),
+                timeout: None,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// CdiRegistry holds the CDI specs installed on the host
+#[derive(Debug, Clone, Default)]
+pub struct CdiRegistry {
+    /// Loaded specs
+    pub specs: Vec<CdiSpec>,
+}
+
+impl CdiRegistry {
+    /// Load the specs from the default CDI directories
+    pub fn load_default() -> Result<Self, String> {
+        Self::load(&CDI_SPEC_DIRS)
+    }
+
+    /// Load all .json, .yaml and .yml specs from the given directories, skipping missing ones
+    pub fn load<P: AsRef<Path>>(dirs: &[P]) -> Result<Self, String> {
+        let mut specs = Vec::new();
+
+        for dir in dirs {
+            let entries = match std::fs::read_dir(dir.as_ref()) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            let mut paths: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+            paths.sort();
+
+            for path in paths {
+                let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                let content = match extension {
+                    "json" | "yaml" | "yml" => std::fs::read_to_string(&path)
+                        .map_err(|e| format!("Failed to read CDI spec {}: {}", path.display(), e))?,
+                    _ => continue,
+                };
+
+                let spec: CdiSpec = if extension == "json" {
+                    serde_json::from_str(&content).map_err(|e| e.to_string())
+                } else {
+                    serde_yaml::from_str(&content).map_err(|e| e.to_string())
+                }
+                .map_err(|e| format!("Invalid CDI spec {}: {}", path.display(), e))?;
+
+                specs.push(spec);
+            }
+        }
+
+        Ok(CdiRegistry { specs })
+    }
+
+    /// Returns the fully-qualified names (kind=name) of all devices of a kind
+    pub fn device_names(&self, kind: &str) -> Vec<String> {
+        self.specs
+            .iter()
+            .filter(|spec| spec.kind == kind)
+            .flat_map(|spec| spec.devices.iter())
+            .map(|device| format!("{}={}", kind, device.name))
+            .collect()
+    }
+
+    /// Resolve fully-qualified device names (e.g. nvidia.com/gpu=0) into container edits
+    pub fn resolve(&self, names: &[String]) -> Result<CdiContainerEdits, String> {
+        let mut edits = CdiContainerEdits::default();
+        let mut applied_specs = Vec::new();
+
+        for name in names {
+            let (kind, device_name) = name
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid CDI device name: {}", name))?;
+
+            let (index, spec, device) = self
+                .specs
+                .iter()
+                .enumerate()
+                .filter(|(_, spec)| spec.kind == kind)
+                .find_map(|(index, spec)| {
+                    spec.devices
+                        .iter()
+                        .find(|device| device.name == device_name)
+                        .map(|device| (index, spec, device))
+                })
+                .ok_or_else(|| format!("CDI device not found: {}", name))?;
+
+            // Spec-wide edits are applied once no matter how many of its devices are used
+            if !applied_specs.contains(&index) {
+                edits.merge(&spec.container_edits);
+                applied_specs.push(index);
+            }
+            edits.merge(&device.container_edits);
+        }
+
+        Ok(edits)
+    }
+
+    /// Resolve a `--gpus` request into fully-qualified CDI device names
+    pub fn resolve_gpus(&self, request: &GpuRequest) -> Result<Vec<String>, String> {
+        let kind = match &request.kind {
+            Some(kind) => kind.clone(),
+            None => DEFAULT_GPU_KINDS
+                .iter()
+                .find(|kind| !self.device_names(kind).is_empty())
+                .map(|kind| kind.to_string())
+                .ok_or_else(|| "No CDI specs for GPUs found on the host".to_string())?,
+        };
+
+        // "all" is a vendor-provided aggregate device, not an individual GPU
+        let available: Vec<String> = self
+            .device_names(&kind)
+            .into_iter()
+            .filter(|name| !name.ends_with("=all"))
+            .collect();
+
+        if !request.device_ids.is_empty() {
+            return Ok(request
+                .device_ids
+                .iter()
+                .map(|id| format!("{}={}", kind, id))
+                .collect());
+        }
+
+        match request.count {
+            GpuCount::All => Ok(available),
+            GpuCount::Count(count) if count as usize <= available.len() => {
+                Ok(available.into_iter().take(count as usize).collect())
+            }
+            GpuCount::Count(count) => Err(format!(
+                "Requested {} GPUs but only {} are available",
+                count,
+                available.len()
+            )),
+        }
+    }
+}
+
+/// Number of GPUs requested with `--gpus`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GpuCount {
+    /// All GPUs on the host
+    All,
+    /// A fixed number of GPUs
+    Count(u32),
+}
+
+/// GpuRequest represents the value of `--gpus`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuRequest {
+    /// Number of GPUs (ignored when device IDs are given)
+    pub count: GpuCount,
+    /// Specific devices by CDI device name (e.g. 0, 1 or a UUID)
+    pub device_ids: Vec<String>,
+    /// CDI kind to use (defaults to the first vendor with specs installed)
+    pub kind: Option<String>,
+}
+
+impl GpuRequest {
+    /// Parse a `--gpus` value: `all`, `2`, `count=2`, `device=0,1` or `kind=amd.com/gpu,all`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim().trim_matches(|c| c == '"' || c == '\'');
+        let mut request = GpuRequest {
+            count: GpuCount::All,
+            device_ids: Vec::new(),
+            kind: None,
+        };
+
+        // The device= value is itself a comma-separated list, so it must come last
+        let (options, devices) = match spec.find("device=") {
+            Some(pos) => (&spec[..pos], Some(&spec[pos + "device=".len()..])),
+            None => (spec, None),
+        };
+
+        for option in options.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+            if option == "all" {
+                request.count = GpuCount::All;
+            } else if let Some(kind) = option.strip_prefix("kind=") {
+                request.kind = Some(kind.to_string());
+            } else if let Ok(count) = option.strip_prefix("count=").unwrap_or(option).parse::<u32>() {
+                request.count = GpuCount::Count(count);
+            } else {
+                return Err(format!("Invalid --gpus option: {}", option));
+            }
+        }
+
+        if let Some(devices) = devices {
+            request.device_ids = devices
+                .split(',')
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(|d| d.to_string())
+                .collect();
+            if request.device_ids.is_empty() {
+                return Err(format!("No devices given in --gpus {}", spec));
+            }
+        }
+
+        Ok(request)
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Root of the cgroup v2 hierarchy used for containers
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup/rocker";
+
+/// Returns the cgroup directory of the given container
+pub fn cgroup_path(container_id: &str) -> PathBuf {
+    Path::new(CGROUP_ROOT).join(container_id)
+}
+
+/// Read a single-value cgroup file (e.g. memory.current), treating "max" as None
+pub fn read_cgroup_value<P: AsRef<Path>>(path: P) -> std::io::Result<Option<u64>> {
+    let content = std::fs::read_to_string(path)?;
+    let value = content.trim();
+    if value == "max" {
+        return Ok(None);
+    }
+
+    value
+        .parse::<u64>()
+        .map(Some)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+/// Read a flat-keyed cgroup file (e.g. cpu.stat) into a map of key to value
+pub fn read_cgroup_keyed<P: AsRef<Path>>(path: P) -> std::io::Result<HashMap<String, u64>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_keyed(&content))
+}
+
+/// Parse "key value" lines, ignoring lines whose value is not a number
+fn parse_keyed(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?;
+            let value = parts.next()?.parse().ok()?;
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Returns how many processes of the cgroup were killed by the OOM killer
+pub fn read_oom_kill_count<P: AsRef<Path>>(cgroup_dir: P) -> u64 {
+    read_cgroup_keyed(cgroup_dir.as_ref().join("memory.events"))
+        .ok()
+        .and_then(|events| events.get("oom_kill").copied())
+        .unwrap_or(0)
+}
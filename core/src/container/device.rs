@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+
+/// DeviceMapping represents a host device made available inside a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceMapping {
+    /// Path of the device on the host
+    pub path_on_host: String,
+    /// Path of the device inside the container
+    pub path_in_container: String,
+    /// Cgroup permissions for the device (any combination of r, w and m)
+    pub cgroup_permissions: String,
+}
+
+impl DeviceMapping {
+    /// Parse a device mapping in the form `host[:container][:permissions]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = spec.split(':').collect();
+
+        let (path_on_host, path_in_container, permissions) = match parts.as_slice() {
+            [host] => (*host, *host, "rwm"),
+            [host, perms] if is_valid_permissions(perms) => (*host, *host, *perms),
+            [host, container] => (*host, *container, "rwm"),
+            [host, container, perms] => (*host, *container, *perms),
+            _ => return Err(format!("Invalid device specification: {}", spec)),
+        };
+
+        if !path_on_host.starts_with('/') || !path_in_container.starts_with('/') {
+            return Err(format!("Device paths must be absolute: {}", spec));
+        }
+
+        if !is_valid_permissions(permissions) {
+            return Err(format!("Invalid device permissions: {}", permissions));
+        }
+
+        Ok(DeviceMapping {
+            path_on_host: path_on_host.to_string(),
+            path_in_container: path_in_container.to_string(),
+            cgroup_permissions: permissions.to_string(),
+        })
+    }
+}
+
+/// Type of device matched by a device cgroup rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceType {
+    /// All devices
+    All,
+    /// Character devices
+    Char,
+    /// Block devices
+    Block,
+}
+
+impl std::fmt::Display for DeviceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let type_str = match self {
+            DeviceType::All => "a",
+            DeviceType::Char => "c",
+            DeviceType::Block => "b",
+        };
+        write!(f, "{}", type_str)
+    }
+}
+
+/// DeviceCgroupRule allows access to devices in the container's device controller
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceCgroupRule {
+    /// Type of the device
+    pub device_type: DeviceType,
+    /// Major number (None matches any)
+    pub major: Option<u32>,
+    /// Minor number (None matches any)
+    pub minor: Option<u32>,
+    /// Allowed access (any combination of r, w and m)
+    pub permissions: String,
+}
+
+impl DeviceCgroupRule {
+    /// Create a rule for a single device
+    pub fn new(device_type: DeviceType, major: u32, minor: u32, permissions: &str) -> Self {
+        DeviceCgroupRule {
+            device_type,
+            major: Some(major),
+            minor: Some(minor),
+            permissions: permissions.to_string(),
+        }
+    }
+
+    /// Parse a rule in the form `type major:minor permissions` (e.g. `c 1:3 rwm` or `b 8:* r`)
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = rule.split_whitespace().collect();
+        let (type_str, numbers, permissions) = match parts.as_slice() {
+            [type_str, numbers, permissions] => (*type_str, *numbers, *permissions),
+            [type_str, numbers] => (*type_str, *numbers, "rwm"),
+            _ => return Err(format!("Invalid device cgroup rule: {}", rule)),
+        };
+
+        let device_type = match type_str {
+            "a" => DeviceType::All,
+            "c" => DeviceType::Char,
+            "b" => DeviceType::Block,
+            _ => return Err(format!("Invalid device type in rule: {}", rule)),
+        };
+
+        let (major, minor) = numbers
+            .split_once(':')
+            .ok_or_else(|| format!("Invalid device numbers in rule: {}", rule))?;
+
+        if !is_valid_permissions(permissions) {
+            return Err(format!("Invalid device permissions: {}", permissions));
+        }
+
+        Ok(DeviceCgroupRule {
+            device_type,
+            major: parse_device_number(major)?,
+            minor: parse_device_number(minor)?,
+            permissions: permissions.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for DeviceCgroupRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = |n: Option<u32>| n.map_or("*".to_string(), |n| n.to_string());
+        write!(
+            f,
+            "{} {}:{} {}",
+            self.device_type,
+            number(self.major),
+            number(self.minor),
+            self.permissions
+        )
+    }
+}
+
+/// Device rules every container is granted (null, zero, full, tty, random, urandom, pts, ptmx, tun)
+pub fn default_device_rules() -> Vec<DeviceCgroupRule> {
+    let any = |device_type| DeviceCgroupRule {
+        device_type,
+        major: None,
+        minor: None,
+        permissions: "m".to_string(),
+    };
+
+    vec![
+        any(DeviceType::Char),
+        any(DeviceType::Block),
+        DeviceCgroupRule::new(DeviceType::Char, 1, 3, "rwm"),
+        DeviceCgroupRule::new(DeviceType::Char, 1, 5, "rwm"),
+        DeviceCgroupRule::new(DeviceType::Char, 1, 7, "rwm"),
+        DeviceCgroupRule::new(DeviceType::Char, 5, 0, "rwm"),
+        DeviceCgroupRule::new(DeviceType::Char, 1, 8, "rwm"),
+        DeviceCgroupRule::new(DeviceType::Char, 1, 9, "rwm"),
+        DeviceCgroupRule {
+            device_type: DeviceType::Char,
+            major: Some(136),
+            minor: None,
+            permissions: "rwm".to_string(),
+        },
+        DeviceCgroupRule::new(DeviceType::Char, 5, 2, "rwm"),
+        DeviceCgroupRule::new(DeviceType::Char, 10, 200, "rwm"),
+    ]
+}
+
+fn parse_device_number(s: &str) -> Result<Option<u32>, String> {
+    if s == "*" {
+        return Ok(None);
+    }
+    s.parse::<u32>()
+        .map(Some)
+        .map_err(|_| format!("Invalid device number: {}", s))
+}
+
+fn is_valid_permissions(permissions: &str) -> bool {
+    !permissions.is_empty() && permissions.chars().all(|c| matches!(c, 'r' | 'w' | 'm'))
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// ExecConfig holds the configuration of a command executed in a running container
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecConfig {
+    /// Command to run
+    pub cmd: Vec<String>,
+    /// User to run the command as (user:group), defaults to the container's user
+    pub user: Option<String>,
+    /// Additional environment variables as key-value pairs
+    pub env: HashMap<String, String>,
+    /// Working directory inside the container, defaults to the container's working directory
+    pub working_dir: Option<String>,
+    /// Keep the command's stdin open and feed it from the body of the attached start request
+    #[serde(default)]
+    pub attach_stdin: bool,
+}
+
+/// ExecInstance represents an exec session inside a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecInstance {
+    /// Exec ID (UUID)
+    pub id: String,
+    /// ID of the container the command runs in
+    pub container_id: String,
+    /// Exec configuration
+    pub config: ExecConfig,
+    /// True while the command is running
+    pub running: bool,
+    /// Exit code of the command (if finished)
+    pub exit_code: Option<i32>,
+    /// Process ID of the command on the host (if running)
+    pub pid: Option<i32>,
+    /// Time when the exec session was created
+    pub created_at: DateTime<Utc>,
+    /// Time when the command was started (if applicable)
+    pub started_at: Option<DateTime<Utc>>,
+    /// Time when the command finished (if applicable)
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ExecInstance {
+    /// Create a new exec session for the given container
+    pub fn new(container_id: String, config: ExecConfig) -> Self {
+        ExecInstance {
+            id: Uuid::new_v4().to_string(),
+            container_id,
+            config,
+            running: false,
+            exit_code: None,
+            pid: None,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    /// Record that the command has been started with the given process ID
+    pub fn mark_started(&mut self, pid: i32) {
+        self.running = true;
+        self.pid = Some(pid);
+        self.started_at = Some(Utc::now());
+    }
+
+    /// Record that the command has finished with the given exit code
+    pub fn mark_finished(&mut self, exit_code: i32) {
+        self.running = false;
+        self.pid = None;
+        self.exit_code = Some(exit_code);
+        self.finished_at = Some(Utc::now());
+    }
+
+    /// Returns true if the command has been started at least once
+    pub fn is_started(&self) -> bool {
+        self.started_at.is_some()
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Default number of seconds between health checks
+pub const DEFAULT_HEALTH_INTERVAL: u64 = 30;
+/// Default number of seconds a health check may run before it counts as failed
+pub const DEFAULT_HEALTH_TIMEOUT: u64 = 30;
+/// Default number of consecutive failures needed to report the container as unhealthy
+pub const DEFAULT_HEALTH_RETRIES: u32 = 3;
+/// Number of health check results kept in the container state
+pub const HEALTH_LOG_SIZE: usize = 5;
+
+/// HealthConfig describes a check run periodically inside a running container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthConfig {
+    /// Test to run: `["CMD", arg...]`, `["CMD-SHELL", command]`, or `["NONE"]` to disable the check
+    pub test: Vec<String>,
+    /// Seconds between checks
+    pub interval: Option<u64>,
+    /// Seconds a check may run before it counts as failed
+    pub timeout: Option<u64>,
+    /// Consecutive failures needed to report the container as unhealthy
+    pub retries: Option<u32>,
+    /// Seconds after the start during which failures are not counted
+    pub start_period: Option<u64>,
+}
+
+impl HealthConfig {
+    /// Take the test and the options that are not set from the image's health check, so that
+    /// `--health-interval` alone changes only the interval of the image's `HEALTHCHECK`
+    pub fn merge_image(&mut self, image: &HealthConfig) {
+        if self.test.is_empty() {
+            self.test = image.test.clone();
+        }
+        self.interval = self.interval.or(image.interval);
+        self.timeout = self.timeout.or(image.timeout);
+        self.retries = self.retries.or(image.retries);
+        self.start_period = self.start_period.or(image.start_period);
+    }
+
+    /// Command to run for the check, or None if the check is disabled
+    pub fn command(&self) -> Option<Vec<String>> {
+        match self.test.split_first() {
+            Some((kind, args)) if kind == "CMD" && !args.is_empty() => Some(args.to_vec()),
+            Some((kind, args)) if kind == "CMD-SHELL" && !args.is_empty() => {
+                Some(vec!["/bin/sh".to_string(), "-c".to_string(), args.join(" ")])
+            }
+            _ => None,
+        }
+    }
+
+    /// Time between checks
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval.unwrap_or(DEFAULT_HEALTH_INTERVAL))
+    }
+
+    /// Time a check may run before it counts as failed
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(DEFAULT_HEALTH_TIMEOUT))
+    }
+
+    /// Consecutive failures needed to report the container as unhealthy
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(DEFAULT_HEALTH_RETRIES).max(1)
+    }
+
+    /// Time after the start during which failures are not counted
+    pub fn start_period(&self) -> Duration {
+        Duration::from_secs(self.start_period.unwrap_or(0))
+    }
+}
+
+/// HealthStatus is the result of the health checks of a container
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthStatus {
+    /// No check has passed or failed enough times yet
+    Starting,
+    /// The last check passed
+    Healthy,
+    /// The check failed the configured number of times in a row
+    Unhealthy,
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            HealthStatus::Starting => "starting",
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Unhealthy => "unhealthy",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// HealthCheckResult records one run of the health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheckResult {
+    /// Time when the check was started
+    pub start: DateTime<Utc>,
+    /// Time when the check finished
+    pub end: DateTime<Utc>,
+    /// Exit code of the check (-1 if it could not run or timed out)
+    pub exit_code: i32,
+    /// Output of the check (truncated)
+    pub output: String,
+}
+
+/// Health is the health state of a running container with a health check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    /// Current status
+    pub status: HealthStatus,
+    /// Number of consecutive failed checks
+    pub failing_streak: u32,
+    /// Latest results (at most HEALTH_LOG_SIZE)
+    pub log: Vec<HealthCheckResult>,
+}
+
+impl Health {
+    /// Health state of a container that has just started
+    pub fn starting() -> Self {
+        Health {
+            status: HealthStatus::Starting,
+            failing_streak: 0,
+            log: Vec::new(),
+        }
+    }
+
+    /// Record a check result; failures during the start period do not count while the container is starting
+    ///
+    /// Returns true if the status changed.
+    pub fn record(&mut self, result: HealthCheckResult, config: &HealthConfig, in_start_period: bool) -> bool {
+        let previous = self.status;
+        if result.exit_code == 0 {
+            self.status = HealthStatus::Healthy;
+            self.failing_streak = 0;
+        } else if !(in_start_period && self.status == HealthStatus::Starting) {
+            self.failing_streak += 1;
+            if self.failing_streak >= config.retries() {
+                self.status = HealthStatus::Unhealthy;
+            }
+        }
+
+        self.log.push(result);
+        if self.log.len() > HEALTH_LOG_SIZE {
+            self.log.remove(0);
+        }
+        self.status != previous
+    }
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Version of the OCI runtime specification the hook state follows
+pub const OCI_VERSION: &str = "1.0.2";
+
+/// Directory holding daemon-wide hooks, one JSON file per hook
+pub const HOOKS_DIR: &str = "/etc/rocker/hooks.d";
+
+/// Lifecycle point at which a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookStage {
+    /// After the container's namespaces are created, before the user process is executed
+    Prestart,
+    /// After the user process has been executed
+    Poststart,
+    /// After the container process has exited
+    Poststop,
+}
+
+impl HookStage {
+    /// Parse a stage name, accepting the OCI and CDI names of equivalent points
+    pub fn parse(stage: &str) -> Result<Self, String> {
+        match stage {
+            "prestart" | "createRuntime" | "createContainer" => Ok(HookStage::Prestart),
+            "poststart" | "startContainer" => Ok(HookStage::Poststart),
+            "poststop" => Ok(HookStage::Poststop),
+            _ => Err(format!("Invalid hook stage: {}", stage)),
+        }
+    }
+}
+
+impl std::fmt::Display for HookStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stage_str = match self {
+            HookStage::Prestart => "prestart",
+            HookStage::Poststart => "poststart",
+            HookStage::Poststop => "poststop",
+        };
+        write!(f, "{}", stage_str)
+    }
+}
+
+/// Hook represents an executable run on the host at a lifecycle point of a container
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Lifecycle point
+    pub stage: HookStage,
+    /// Absolute path of the executable
+    pub path: String,
+    /// Arguments, not including the executable
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables (KEY=value)
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Seconds after which the hook is killed
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+impl Hook {
+    /// Parse a hook in the form `stage=/path/to/executable [args...]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (stage, command) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid hook (expected stage=command): {}", spec))?;
+
+        let mut parts = command.split_whitespace();
+        let path = parts
+            .next()
+            .ok_or_else(|| format!("No executable given for hook: {}", spec))?;
+        if !path.starts_with('/') {
+            return Err(format!("Hook path must be absolute: {}", path));
+        }
+
+        Ok(Hook {
+            stage: HookStage::parse(stage.trim())?,
+            path: path.to_string(),
+            args: parts.map(|arg| arg.to_string()).collect(),
+            env: Vec::new(),
+            timeout: None,
+        })
+    }
+}
+
+/// HookState is the container state passed to hooks on stdin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookState {
+    /// Version of the OCI runtime specification
+    pub oci_version: String,
+    /// Container ID
+    pub id: String,
+    /// Container status (creating, created, running, stopped)
+    pub status: String,
+    /// Host PID of the container process (if it exists)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<i32>,
+    /// Directory holding the container's state
+    pub bundle: String,
+    /// Container labels
+    pub annotations: HashMap<String, String>,
+}
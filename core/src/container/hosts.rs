@@ -0,0 +1,157 @@
+use super::{Container, ContainerConfig, NetworkMode};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Address of the DNS server embedded in the daemon, used by containers on user-defined networks
+pub const EMBEDDED_DNS_SERVER: &str = "127.0.0.11";
+
+/// Nameservers used when the host only has loopback nameservers, which are unreachable from a container
+pub const FALLBACK_NAMESERVERS: [&str; 2] = ["8.8.8.8", "8.8.4.4"];
+
+/// Entries every generated /etc/hosts starts with
+const DEFAULT_HOSTS: &str = "127.0.0.1\tlocalhost
+::1\tlocalhost ip6-localhost ip6-loopback
+fe00::0\tip6-localnet
+ff00::0\tip6-mcastprefix
+ff02::1\tip6-allnodes
+ff02::2\tip6-allrouters
+";
+
+/// HostEntry represents an extra /etc/hosts entry added with `--add-host`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostEntry {
+    /// Hostname to resolve
+    pub hostname: String,
+    /// Address the hostname resolves to
+    pub ip: IpAddr,
+}
+
+impl HostEntry {
+    /// Parse an entry in the form `name:ip` or `name=ip` (the address may be IPv6)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (hostname, ip) = spec
+            .split_once('=')
+            .or_else(|| spec.split_once(':'))
+            .ok_or_else(|| format!("Invalid host entry: {}", spec))?;
+
+        if hostname.is_empty() || hostname.contains(char::is_whitespace) {
+            return Err(format!("Invalid hostname in host entry: {}", spec));
+        }
+
+        let ip = ip
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<IpAddr>()
+            .map_err(|_| format!("Invalid IP address in host entry: {}", spec))?;
+
+        Ok(HostEntry {
+            hostname: hostname.to_string(),
+            ip,
+        })
+    }
+}
+
+/// Build the content of the container's /etc/hosts
+///
+/// With host networking the host's file is reused so that the container resolves names like the host does.
+pub fn build_hosts_file(container: &Container, host_hosts: &str) -> String {
+    let mut content = match container.config.network_mode {
+        NetworkMode::Host => host_hosts.to_string(),
+        _ => DEFAULT_HOSTS.to_string(),
+    };
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+
+    let hostname = container.hostname();
+    let mut names = vec![hostname.clone()];
+    if let Some(domainname) = &container.config.domainname {
+        names.insert(0, format!("{}.{}", hostname, domainname));
+    }
+
+    if !matches!(container.config.network_mode, NetworkMode::Host) {
+        let mut addresses: Vec<(&str, Vec<String>)> = Vec::new();
+        if let Some(ip) = &container.ip_address {
+            addresses.push((ip, names.clone()));
+        }
+        for endpoint in container.networks.values() {
+            if endpoint.ip_address.is_empty() {
+                continue;
+            }
+            let mut endpoint_names = names.clone();
+            endpoint_names.push(container.name.clone());
+            endpoint_names.extend(endpoint.aliases.iter().cloned());
+
+            match addresses.iter_mut().find(|(ip, _)| *ip == endpoint.ip_address) {
+                Some((_, existing)) => existing.extend(endpoint_names),
+                None => addresses.push((&endpoint.ip_address, endpoint_names)),
+            }
+        }
+
+        for (ip, mut names) in addresses {
+            let mut seen = Vec::new();
+            names.retain(|name| {
+                let new = !seen.contains(name);
+                seen.push(name.clone());
+                new
+            });
+            content.push_str(&format!("{}\t{}\n", ip, names.join(" ")));
+        }
+    }
+
+    for entry in &container.config.extra_hosts {
+        content.push_str(&format!("{}\t{}\n", entry.ip, entry.hostname));
+    }
+
+    content
+}
+
+/// Build the content of the container's /etc/resolv.conf from the host's one
+///
+/// Containers on user-defined networks use the embedded DNS server. Others get the host's
+/// nameservers with loopback addresses removed, since those cannot be reached from the container.
+/// Servers and search domains given with `--dns` and `--dns-search` replace those of the host.
+pub fn build_resolv_conf(config: &ContainerConfig, host_resolv: &str) -> String {
+    let network_mode = &config.network_mode;
+    let host_network = matches!(network_mode, NetworkMode::Host);
+    if host_network && config.dns.is_empty() && config.dns_search.is_empty() {
+        return host_resolv.to_string();
+    }
+
+    let mut nameservers = Vec::new();
+    let mut other_lines = Vec::new();
+    for line in host_resolv.lines() {
+        let mut fields = line.split_whitespace();
+        match (fields.next(), fields.next()) {
+            (Some("nameserver"), Some(address)) => {
+                let loopback = address.parse::<IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false);
+                if !loopback || host_network {
+                    nameservers.push(address.to_string());
+                }
+            }
+            (Some("search"), _) | (Some("domain"), _) if !config.dns_search.is_empty() => {}
+            (Some("search"), _) | (Some("domain"), _) | (Some("options"), _) => other_lines.push(line.trim().to_string()),
+            _ => {}
+        }
+    }
+    if !config.dns_search.is_empty() {
+        other_lines.insert(0, format!("search {}", config.dns_search.join(" ")));
+    }
+
+    if !config.dns.is_empty() {
+        nameservers = config.dns.iter().map(|ip| ip.to_string()).collect();
+    } else if matches!(network_mode, NetworkMode::Custom(_)) {
+        nameservers = vec![EMBEDDED_DNS_SERVER.to_string()];
+    } else if nameservers.is_empty() {
+        nameservers = FALLBACK_NAMESERVERS.iter().map(|s| s.to_string()).collect();
+    }
+
+    let mut content = String::new();
+    for nameserver in nameservers {
+        content.push_str(&format!("nameserver {}\n", nameserver));
+    }
+    for line in other_lines {
+        content.push_str(&line);
+        content.push('\n');
+    }
+    content
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Output stream of the container process a log line was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// LogRecord is one line of container output, as returned by the logs API (one JSON object per line)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Stream the line was written to
+    pub stream: LogStream,
+    /// Time when the daemon read the line
+    pub time: DateTime<Utc>,
+    /// Content of the line without the trailing newline
+    pub line: String,
+}
+
+/// Logging driver used when neither the container nor the daemon configuration selects one
+pub const DEFAULT_LOG_DRIVER: &str = "json-file";
+
+/// LogConfig selects where the output of a container is sent (`--log-driver` and `--log-opt`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// Name of the logging driver (json-file, fluentd, gelf, none)
+    pub driver: String,
+    /// Options of the driver, such as max-size and max-file of json-file
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            driver: DEFAULT_LOG_DRIVER.to_string(),
+            options: HashMap::new(),
+        }
+    }
+}
@@ -1,10 +1,44 @@
+use crate::image::ImageConfig;
+use crate::secret::SecretReference;
+use crate::utils::Identifiable;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
 use uuid::Uuid;
 
+mod cdi;
+mod cgroup;
+mod device;
+mod exec;
+mod health;
+mod hooks;
+mod hosts;
+mod logs;
+mod ports;
+mod shaping;
 mod state;
+mod stats;
+mod sysctl;
+mod top;
+mod ulimit;
+mod wait_for;
+pub use cdi::*;
+pub use cgroup::*;
+pub use device::*;
+pub use exec::*;
+pub use health::*;
+pub use hooks::*;
+pub use hosts::*;
+pub use logs::*;
+pub use ports::*;
+pub use shaping::*;
 pub use state::*;
+pub use stats::*;
+pub use sysctl::*;
+pub use top::*;
+pub use ulimit::*;
+pub use wait_for::*;
 
 /// Mount represents a mounted volume
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +53,105 @@ pub struct Mount {
     pub read_only: bool,
     /// Mount propagation mode
     pub propagation: Option<PropagationMode>,
+    /// SELinux relabeling of the source (`z` or `Z`)
+    pub relabel: Option<SelinuxRelabel>,
+    /// Don't copy the content at the destination in the image into a newly created volume
+    #[serde(default)]
+    pub no_copy: bool,
+    /// Size limit of a tmpfs mount in bytes (unlimited if not set)
+    #[serde(default)]
+    pub tmpfs_size: Option<u64>,
+}
+
+impl Mount {
+    /// Parse a `-v` specification: `dst`, `src:dst` or `src:dst:options`
+    ///
+    /// An absolute source is bind mounted, any other source names a volume. Without a source an
+    /// anonymous volume is created when the container starts. Options are comma separated: `ro` or
+    /// `rw`, `z` or `Z` to relabel the source for SELinux, and a propagation mode for bind mounts
+    /// (`private`, `rprivate`, `shared`, `rshared`, `slave`, `rslave`).
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = spec.split(':').collect();
+        let (source, destination, options) = match parts.as_slice() {
+            [destination] => ("", *destination, ""),
+            [source, destination] => (*source, *destination, ""),
+            [source, destination, options] => (*source, *destination, *options),
+            _ => return Err(format!("Invalid volume specification: {}", spec)),
+        };
+        if !destination.starts_with('/') {
+            return Err(format!("Invalid volume destination (must be an absolute path): {}", spec));
+        }
+        let mount_type = if source.starts_with('/') {
+            MountType::Bind
+        } else {
+            MountType::Volume
+        };
+
+        let mut read_only = None;
+        let mut relabel = None;
+        let mut propagation = None;
+        for option in options.split(',').filter(|option| !option.is_empty()) {
+            let duplicate = match option {
+                "ro" | "rw" => read_only.replace(option == "ro").is_some(),
+                "z" => relabel.replace(SelinuxRelabel::Shared).is_some(),
+                "Z" => relabel.replace(SelinuxRelabel::Private).is_some(),
+                _ => {
+                    let mode = PropagationMode::parse(option).map_err(|_| format!("Invalid volume option: {}", option))?;
+                    if !matches!(mount_type, MountType::Bind) {
+                        return Err(format!("Propagation mode {} is only supported for bind mounts", option));
+                    }
+                    propagation.replace(mode).is_some()
+                }
+            };
+            if duplicate {
+                return Err(format!("Conflicting volume options: {}", options));
+            }
+        }
+
+        Ok(Mount {
+            mount_type,
+            source: source.to_string(),
+            destination: destination.to_string(),
+            read_only: read_only.unwrap_or(false),
+            propagation,
+            relabel,
+            no_copy: false,
+            tmpfs_size: None,
+        })
+    }
+
+    /// Whether this is a volume mount whose volume has not been created yet
+    pub fn is_anonymous_volume(&self) -> bool {
+        matches!(self.mount_type, MountType::Volume) && self.source.is_empty()
+    }
+}
+
+/// Container whose mounts are copied into a new container (`--volumes-from`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumesFrom {
+    /// ID or name of the container
+    pub container: String,
+    /// Override the read-only flag of every copied mount (`:ro` or `:rw`), keep it when not set
+    pub read_only: Option<bool>,
+}
+
+impl VolumesFrom {
+    /// Parse `container[:ro|rw]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (container, read_only) = match spec.rsplit_once(':') {
+            Some((container, "ro")) => (container, Some(true)),
+            Some((container, "rw")) => (container, Some(false)),
+            Some((_, mode)) => return Err(format!("Invalid mode for --volumes-from: {}", mode)),
+            None => (spec, None),
+        };
+        if container.is_empty() {
+            return Err(format!("Invalid --volumes-from: {}", spec));
+        }
+        Ok(VolumesFrom {
+            container: container.to_string(),
+            read_only,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,11 +161,57 @@ pub enum MountType {
     Tmpfs,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PropagationMode {
     Private,
     Shared,
     Slave,
+    RPrivate,
+    RShared,
+    RSlave,
+}
+
+impl PropagationMode {
+    /// Parse a propagation mode as written in `-v` options
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "private" => Ok(PropagationMode::Private),
+            "shared" => Ok(PropagationMode::Shared),
+            "slave" => Ok(PropagationMode::Slave),
+            "rprivate" => Ok(PropagationMode::RPrivate),
+            "rshared" => Ok(PropagationMode::RShared),
+            "rslave" => Ok(PropagationMode::RSlave),
+            _ => Err(format!("Invalid propagation mode: {}", s)),
+        }
+    }
+
+    /// Whether the mode also applies to the mounts below the mount point
+    pub fn is_recursive(&self) -> bool {
+        matches!(self, PropagationMode::RPrivate | PropagationMode::RShared | PropagationMode::RSlave)
+    }
+}
+
+impl std::fmt::Display for PropagationMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PropagationMode::Private => "private",
+            PropagationMode::Shared => "shared",
+            PropagationMode::Slave => "slave",
+            PropagationMode::RPrivate => "rprivate",
+            PropagationMode::RShared => "rshared",
+            PropagationMode::RSlave => "rslave",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// SELinux relabeling of a mount source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelinuxRelabel {
+    /// `z`: the content is shared with other containers
+    Shared,
+    /// `Z`: the content is private to the container
+    Private,
 }
 
 /// NetworkEndpoint represents a container's connection to a network
@@ -42,8 +221,20 @@ pub struct NetworkEndpoint {
     pub network_id: String,
     /// IP address assigned to the container in this network
     pub ip_address: String,
+    /// MAC address of the container's interface in this network
+    #[serde(default)]
+    pub mac_address: String,
+    /// Name of the interface inside the container (eth0, eth1, ...)
+    #[serde(default)]
+    pub interface: String,
+    /// Address requested with `--ip`, kept across restarts
+    #[serde(default)]
+    pub requested_ip: Option<String>,
     /// Network aliases for the container
     pub aliases: Vec<String>,
+    /// Bandwidth limits and priority of the endpoint
+    #[serde(default)]
+    pub shaping: TrafficShaping,
 }
 
 /// ContainerConfig holds the configuration of a container
@@ -53,16 +244,25 @@ pub struct ContainerConfig {
     pub image: String,
     /// Command to run in the container
     pub cmd: Option<Vec<String>>,
+    /// Entrypoint replacing the image's ENTRYPOINT (the image's CMD is not used when set)
+    pub entrypoint: Option<Vec<String>>,
     /// Working directory inside the container
     pub working_dir: Option<String>,
     /// Environment variables as key-value pairs
     pub env: HashMap<String, String>,
     /// Exposed ports
-    pub exposed_ports: Vec<u16>,
-    /// Host to container port mappings
-    pub port_bindings: HashMap<u16, u16>,
+    pub exposed_ports: Vec<ExposedPort>,
+    /// Container ports published on the host
+    pub port_bindings: Vec<PortBinding>,
+    /// Publish all exposed ports on random host ports
+    pub publish_all: bool,
     /// Volume mounts
     pub mounts: Vec<Mount>,
+    /// Containers whose mounts are copied when the container starts
+    pub volumes_from: Vec<VolumesFrom>,
+    /// Daemon-managed secrets mounted as files on a tmpfs when the container starts
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub secrets: Vec<SecretReference>,
     /// Restart policy
     pub restart_policy: RestartPolicy,
     /// Resource limits
@@ -83,18 +283,73 @@ pub struct ContainerConfig {
     pub domainname: Option<String>,
     /// Container labels
     pub labels: HashMap<String, String>,
+    /// Signal to stop the container (defaults to the image's STOPSIGNAL, then SIGTERM)
+    pub stop_signal: Option<String>,
+    /// Seconds to wait for the container to stop before killing it
+    pub stop_timeout: Option<u64>,
+    /// Host devices to add to the container
+    pub devices: Vec<DeviceMapping>,
+    /// Additional rules for the device cgroup controller
+    pub device_cgroup_rules: Vec<DeviceCgroupRule>,
+    /// GPUs to add to the container through CDI
+    pub gpus: Option<GpuRequest>,
+    /// Namespaced kernel parameters to set in the container
+    pub sysctls: HashMap<String, String>,
+    /// Resource limits set on the container process
+    pub ulimits: Vec<Ulimit>,
+    /// Extra entries for the container's /etc/hosts
+    pub extra_hosts: Vec<HostEntry>,
+    /// Static IPv4 address on the network given by the network mode
+    pub ip_address: Option<Ipv4Addr>,
+    /// Aliases of the container on the network given by the network mode
+    pub network_aliases: Vec<String>,
+    /// DNS servers written to the container's /etc/resolv.conf (replace the host's ones)
+    pub dns: Vec<IpAddr>,
+    /// DNS search domains written to the container's /etc/resolv.conf
+    pub dns_search: Vec<String>,
+    /// Traffic shaping of the endpoint on the network given by the network mode (`--network-opt`)
+    pub traffic_shaping: TrafficShaping,
+    /// Adjustment of the OOM killer score of the container process (-1000 to 1000)
+    pub oom_score_adj: Option<i32>,
+    /// Executables run on the host at lifecycle points of the container
+    pub hooks: Vec<Hook>,
+    /// Remove the container and its anonymous volumes when it exits (`--rm`)
+    pub auto_remove: bool,
+    /// Health check run periodically while the container is running
+    pub healthcheck: Option<HealthConfig>,
+    /// Restart the container when its health check reports it as unhealthy (`--health-restart`)
+    #[serde(default)]
+    pub health_restart: bool,
+    /// Dependencies that must be ready before the main process is started (`--wait-for`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wait_for: Vec<WaitFor>,
+    /// Seconds a start waits for `wait_for` before failing ([`DEFAULT_WAIT_TIMEOUT`] when not given)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_timeout: Option<u64>,
+    /// Logging driver receiving the container's output (the daemon's default when not given)
+    pub log_config: Option<LogConfig>,
 }
 
+/// Default number of seconds to wait for a container to stop before killing it
+pub const DEFAULT_STOP_TIMEOUT: u64 = 10;
+
+/// Label opting a container in to being recreated when its image tag gets a new image (`true`)
+pub const AUTO_UPDATE_LABEL: &str = "com.rocker.auto-update";
+
 impl Default for ContainerConfig {
     fn default() -> Self {
         ContainerConfig {
             image: String::new(),
             cmd: None,
+            entrypoint: None,
             working_dir: None,
             env: HashMap::new(),
             exposed_ports: Vec::new(),
-            port_bindings: HashMap::new(),
+            port_bindings: Vec::new(),
+            publish_all: false,
             mounts: Vec::new(),
+            volumes_from: Vec::new(),
+            secrets: Vec::new(),
             restart_policy: RestartPolicy::No,
             resource_limits: ResourceLimits::default(),
             network_mode: NetworkMode::Bridge,
@@ -105,8 +360,161 @@ impl Default for ContainerConfig {
             hostname: None,
             domainname: None,
             labels: HashMap::new(),
+            stop_signal: None,
+            stop_timeout: None,
+            devices: Vec::new(),
+            device_cgroup_rules: Vec::new(),
+            gpus: None,
+            sysctls: HashMap::new(),
+            ulimits: Vec::new(),
+            extra_hosts: Vec::new(),
+            ip_address: None,
+            network_aliases: Vec::new(),
+            dns: Vec::new(),
+            dns_search: Vec::new(),
+            traffic_shaping: TrafficShaping::default(),
+            oom_score_adj: None,
+            hooks: Vec::new(),
+            auto_remove: false,
+            healthcheck: None,
+            health_restart: false,
+            wait_for: Vec::new(),
+            wait_timeout: None,
+            log_config: None,
+        }
+    }
+}
+
+impl ContainerConfig {
+    /// Fill settings that were not given explicitly from the image configuration
+    pub fn apply_image_defaults(&mut self, image_config: &ImageConfig) {
+        if self.stop_signal.is_none() {
+            self.stop_signal = image_config.stop_signal.clone();
+        }
+        if let Some(image_healthcheck) = &image_config.healthcheck {
+            match &mut self.healthcheck {
+                Some(healthcheck) => healthcheck.merge_image(image_healthcheck),
+                None => self.healthcheck = Some(image_healthcheck.clone()),
+            }
+        }
+        // The command runs as arguments of ENTRYPOINT, and falls back to CMD when not given
+        let (entrypoint, cmd) = match &self.entrypoint {
+            Some(entrypoint) => (entrypoint.clone(), self.cmd.clone().unwrap_or_default()),
+            None => (
+                image_config.entrypoint.clone().unwrap_or_default(),
+                self.cmd.clone().or_else(|| image_config.cmd.clone()).unwrap_or_default(),
+            ),
+        };
+        let mut command = entrypoint;
+        command.extend(cmd);
+        if !command.is_empty() {
+            self.cmd = Some(command);
+        }
+        for entry in &image_config.env {
+            if let Some((key, value)) = entry.split_once('=') {
+                self.env.entry(key.to_string()).or_insert_with(|| value.to_string());
+            }
+        }
+        if self.working_dir.is_none() {
+            self.working_dir = image_config.working_dir.clone().filter(|dir| !dir.is_empty());
+        }
+        if self.user.is_none() {
+            self.user = image_config.user.clone().filter(|user| !user.is_empty());
+        }
+        for spec in image_config.exposed_ports.keys() {
+            if let Ok(port) = ExposedPort::parse(spec) {
+                if !self.exposed_ports.contains(&port) {
+                    self.exposed_ports.push(port);
+                }
+            }
+        }
+        // Paths declared with VOLUME get an anonymous volume unless something is mounted there
+        let mut volumes: Vec<&String> = image_config.volumes.keys().collect();
+        volumes.sort();
+        for destination in volumes {
+            if !self.mounts.iter().any(|m| &m.destination == destination) {
+                self.mounts.push(Mount {
+                    mount_type: MountType::Volume,
+                    source: String::new(),
+                    destination: destination.clone(),
+                    read_only: false,
+                    propagation: None,
+                    relabel: None,
+                    no_copy: false,
+                    tmpfs_size: None,
+                });
+            }
         }
     }
+
+    /// Undo `apply_image_defaults` for the settings that still hold the values of `image_config`, so
+    /// that the configuration can be applied to a newer image of the same tag
+    ///
+    /// Volume mounts are kept, so a recreated container keeps the volumes created for `VOLUME`.
+    pub fn strip_image_defaults(&mut self, image_config: &ImageConfig) {
+        if self.stop_signal == image_config.stop_signal {
+            self.stop_signal = None;
+        }
+        if self.healthcheck.is_some() && self.healthcheck == image_config.healthcheck {
+            self.healthcheck = None;
+        }
+        // The command holds the entrypoint followed by the arguments
+        let entrypoint = self
+            .entrypoint
+            .clone()
+            .or_else(|| image_config.entrypoint.clone())
+            .unwrap_or_default();
+        if let Some(command) = self.cmd.take() {
+            self.cmd = match command.strip_prefix(entrypoint.as_slice()) {
+                Some([]) => None,
+                Some(args) if self.entrypoint.is_none() && Some(args) == image_config.cmd.as_deref() => None,
+                Some(args) => Some(args.to_vec()),
+                None => Some(command),
+            };
+        }
+        for entry in &image_config.env {
+            if let Some((key, value)) = entry.split_once('=') {
+                if self.env.get(key).map(String::as_str) == Some(value) {
+                    self.env.remove(key);
+                }
+            }
+        }
+        if self.working_dir.is_some() && self.working_dir == image_config.working_dir {
+            self.working_dir = None;
+        }
+        if self.user.is_some() && self.user == image_config.user {
+            self.user = None;
+        }
+        let image_ports: Vec<ExposedPort> = image_config
+            .exposed_ports
+            .keys()
+            .filter_map(|spec| ExposedPort::parse(spec).ok())
+            .collect();
+        self.exposed_ports.retain(|port| !image_ports.contains(port));
+    }
+
+    /// Ports to publish on the host: the explicit bindings, plus every exposed port that is not
+    /// bound yet when `publish_all` is set
+    pub fn published_ports(&self) -> Vec<PortBinding> {
+        let mut bindings = self.port_bindings.clone();
+        if self.publish_all {
+            for exposed in &self.exposed_ports {
+                let bound = self
+                    .port_bindings
+                    .iter()
+                    .any(|b| b.container_port == exposed.port && b.protocol == exposed.protocol);
+                if !bound {
+                    bindings.push(PortBinding {
+                        host_ip: None,
+                        host_port: None,
+                        container_port: exposed.port,
+                        protocol: exposed.protocol,
+                    });
+                }
+            }
+        }
+        bindings
+    }
 }
 
 /// Resource limits for a container
@@ -114,28 +522,54 @@ impl Default for ContainerConfig {
 pub struct ResourceLimits {
     /// CPU limit in percentage (0-100)
     pub cpu_percent: Option<u8>,
+    /// Number of CPUs the container may use (e.g. 1.5)
+    #[serde(default)]
+    pub cpus: Option<f64>,
     /// Memory limit in bytes
     pub memory_bytes: Option<u64>,
+    /// Memory the container is guaranteed to keep under memory pressure, in bytes
+    #[serde(default)]
+    pub memory_reservation_bytes: Option<u64>,
     /// Swap limit in bytes
     pub memory_swap_bytes: Option<u64>,
     /// IO read limit in bytes per second
     pub io_read_bps: Option<u64>,
     /// IO write limit in bytes per second
     pub io_write_bps: Option<u64>,
+    /// Maximum number of processes (None or a negative value means unlimited)
+    pub pids_limit: Option<i64>,
+    /// Disable the OOM killer for the container (only supported on cgroup v1)
+    pub oom_kill_disable: bool,
 }
 
 impl Default for ResourceLimits {
     fn default() -> Self {
         ResourceLimits {
             cpu_percent: None,
+            cpus: None,
             memory_bytes: None,
+            memory_reservation_bytes: None,
             memory_swap_bytes: None,
             io_read_bps: None,
             io_write_bps: None,
+            pids_limit: None,
+            oom_kill_disable: false,
         }
     }
 }
 
+impl ResourceLimits {
+    /// CPUs the container reserves on the host (its `cpus` limit)
+    pub fn reserved_cpus(&self) -> f64 {
+        self.cpus.unwrap_or(0.0)
+    }
+
+    /// Memory the container reserves on the host in bytes: its memory reservation, or its limit without one
+    pub fn reserved_memory_bytes(&self) -> u64 {
+        self.memory_reservation_bytes.or(self.memory_bytes).unwrap_or(0)
+    }
+}
+
 /// Network mode for a container
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NetworkMode {
@@ -151,6 +585,30 @@ pub enum NetworkMode {
     Custom(String),
 }
 
+impl NetworkMode {
+    /// Parse a `--network` value (bridge, host, none, container:<name|id> or a network name)
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        match mode {
+            "" => Err("Network mode must not be empty".to_string()),
+            "bridge" | "default" => Ok(NetworkMode::Bridge),
+            "host" => Ok(NetworkMode::Host),
+            "none" => Ok(NetworkMode::None),
+            _ => match mode.strip_prefix("container:") {
+                Some("") => Err(format!("Invalid network mode: {}", mode)),
+                Some(container) => Ok(NetworkMode::Container(container.to_string())),
+                None => Ok(NetworkMode::Custom(mode.to_string())),
+            },
+        }
+    }
+}
+
+/// Exit of a container, returned by the wait endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContainerExit {
+    /// Exit code of the container's process (128 + the signal number if a signal ended it)
+    pub exit_code: i32,
+}
+
 /// Restart policy for a container
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum RestartPolicy {
@@ -167,6 +625,27 @@ pub enum RestartPolicy {
     UnlessStopped,
 }
 
+impl RestartPolicy {
+    /// Parse a `--restart` value (no, always, unless-stopped or on-failure[:max-retries])
+    pub fn parse(policy: &str) -> Result<Self, String> {
+        match policy {
+            "no" => Ok(RestartPolicy::No),
+            "always" => Ok(RestartPolicy::Always),
+            "unless-stopped" => Ok(RestartPolicy::UnlessStopped),
+            "on-failure" => Ok(RestartPolicy::OnFailure { max_retry: None }),
+            _ => match policy.strip_prefix("on-failure:") {
+                Some(count) => {
+                    let max_retry = count
+                        .parse()
+                        .map_err(|_| format!("Invalid maximum retry count of restart policy: {}", count))?;
+                    Ok(RestartPolicy::OnFailure { max_retry: Some(max_retry) })
+                }
+                None => Err(format!("Invalid restart policy: {}", policy)),
+            },
+        }
+    }
+}
+
 /// Container represents a running or stopped container
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Container {
@@ -176,6 +655,9 @@ pub struct Container {
     pub name: String,
     /// Container configuration
     pub config: ContainerConfig,
+    /// Image reference (`repo:tag`) the container was created from, kept when the tag moves to another image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_name: Option<String>,
     /// Current state of the container
     pub state: ContainerState,
     /// Time when the container was created
@@ -192,6 +674,25 @@ pub struct Container {
     pub ip_address: Option<String>,
     /// Networks that the container is connected to
     pub networks: HashMap<String, NetworkEndpoint>,
+    /// IDs of the exec sessions created in this container
+    pub exec_ids: Vec<String>,
+    /// Whether a process of the container was killed by the OOM killer
+    pub oom_killed: bool,
+    /// Times the daemon restarted the container after it exited, by its restart policy (reset by a start)
+    #[serde(default)]
+    pub restart_count: u32,
+    /// Ports published on the host while the container is running (with the assigned host ports)
+    #[serde(default)]
+    pub ports: Vec<PortBinding>,
+    /// Health of the container while it runs with a health check
+    #[serde(default)]
+    pub health: Option<Health>,
+    /// Bytes of the files the container created or changed, only returned when asked for (`ps -s`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_rw: Option<u64>,
+    /// Size of the image plus `size_rw`, only returned when asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_root_fs: Option<u64>,
 }
 
 impl Container {
@@ -201,6 +702,7 @@ impl Container {
             id: Uuid::new_v4().to_string(),
             name,
             config,
+            image_name: None,
             state: ContainerState::Created,
             created_at: Utc::now(),
             started_at: None,
@@ -209,6 +711,32 @@ impl Container {
             pid: None,
             ip_address: None,
             networks: HashMap::new(),
+            exec_ids: Vec::new(),
+            oom_killed: false,
+            restart_count: 0,
+            ports: Vec::new(),
+            health: None,
+            size_rw: None,
+            size_root_fs: None,
+        }
+    }
+
+    /// Hostname of the container (the configured one, or the short container ID)
+    pub fn hostname(&self) -> String {
+        self.config
+            .hostname
+            .clone()
+            .unwrap_or_else(|| self.id.chars().take(12).collect())
+    }
+
+    /// Status shown by `ps`: the state with the health while running, or with the exit code once exited
+    pub fn status(&self) -> String {
+        match (&self.health, self.exit_code) {
+            (Some(health), _) if self.state.is_running() => format!("{} ({})", self.state, health.status),
+            (_, Some(code)) if !self.state.is_running() && !self.state.is_paused() => {
+                format!("{} ({})", self.state, code)
+            }
+            _ => self.state.to_string(),
         }
     }
 
@@ -216,16 +744,47 @@ impl Container {
     pub fn auto_restart(&self) -> bool {
         match &self.config.restart_policy {
             RestartPolicy::Always => true,
-            RestartPolicy::OnFailure { max_retry: _ } => {
-                if let Some(exit_code) = self.exit_code {
-                    if exit_code != 0 {
-                        return true;
-                    }
-                }
-                false
+            RestartPolicy::OnFailure { max_retry } => {
+                let failed = self.exit_code.is_some_and(|exit_code| exit_code != 0);
+                failed && max_retry.is_none_or(|max_retry| self.restart_count < max_retry)
             }
             RestartPolicy::UnlessStopped => self.state != ContainerState::Stopped,
             RestartPolicy::No => false,
         }
     }
 } 
+
+impl Identifiable for Container {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Docker-style names may be given with a leading slash
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name.trim_start_matches('/')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_restart_policies() {
+        assert!(matches!(RestartPolicy::parse("no"), Ok(RestartPolicy::No)));
+        assert!(matches!(RestartPolicy::parse("always"), Ok(RestartPolicy::Always)));
+        assert!(matches!(RestartPolicy::parse("unless-stopped"), Ok(RestartPolicy::UnlessStopped)));
+        assert!(matches!(RestartPolicy::parse("on-failure"), Ok(RestartPolicy::OnFailure { max_retry: None })));
+        assert!(matches!(RestartPolicy::parse("on-failure:5"), Ok(RestartPolicy::OnFailure { max_retry: Some(5) })));
+        assert!(matches!(RestartPolicy::parse("on-failure:0"), Ok(RestartPolicy::OnFailure { max_retry: Some(0) })));
+    }
+
+    #[test]
+    fn rejects_invalid_restart_policies() {
+        for policy in ["", "never", "Always", "on-failure:", "on-failure:-1", "on-failure:x", "always:3"] {
+            assert!(RestartPolicy::parse(policy).is_err(), "{:?}", policy);
+        }
+        let error = RestartPolicy::parse("on-failure:many").unwrap_err();
+        assert!(error.contains("many"), "{}", error);
+    }
+}
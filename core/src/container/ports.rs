@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+/// Transport protocol of a published port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortProtocol {
+    Tcp,
+    Udp,
+    Sctp,
+}
+
+impl PortProtocol {
+    /// Parse a protocol name (tcp, udp or sctp)
+    pub fn parse(protocol: &str) -> Result<Self, String> {
+        match protocol.to_ascii_lowercase().as_str() {
+            "tcp" => Ok(PortProtocol::Tcp),
+            "udp" => Ok(PortProtocol::Udp),
+            "sctp" => Ok(PortProtocol::Sctp),
+            _ => Err(format!("Invalid protocol: {}", protocol)),
+        }
+    }
+}
+
+impl std::fmt::Display for PortProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let protocol_str = match self {
+            PortProtocol::Tcp => "tcp",
+            PortProtocol::Udp => "udp",
+            PortProtocol::Sctp => "sctp",
+        };
+        write!(f, "{}", protocol_str)
+    }
+}
+
+/// ExposedPort is a container port declared with EXPOSE, published on a random host port with `-P`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExposedPort {
+    /// Port in the container
+    pub port: u16,
+    /// Transport protocol
+    pub protocol: PortProtocol,
+}
+
+impl ExposedPort {
+    /// Parse a port in the form `port[/protocol]` (as used in image configurations)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (port, protocol) = match spec.split_once('/') {
+            Some((port, protocol)) => (port, PortProtocol::parse(protocol)?),
+            None => (spec, PortProtocol::Tcp),
+        };
+        Ok(ExposedPort {
+            port: parse_port(port)?,
+            protocol,
+        })
+    }
+}
+
+impl std::fmt::Display for ExposedPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.port, self.protocol)
+    }
+}
+
+/// PortBinding publishes a container port on the host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortBinding {
+    /// Host address to listen on (all addresses if not set)
+    pub host_ip: Option<Ipv4Addr>,
+    /// Port on the host (a free ephemeral port is assigned when publishing if not set)
+    pub host_port: Option<u16>,
+    /// Port in the container
+    pub container_port: u16,
+    /// Transport protocol
+    pub protocol: PortProtocol,
+}
+
+impl PortBinding {
+    /// Parse a `-p` value in the form `[[ip:][host_port]:]container_port[/protocol]`
+    ///
+    /// Either port may be a range such as `8000-8010`, which expands to one binding per port.
+    /// A host range must be as long as the container range.
+    pub fn parse(spec: &str) -> Result<Vec<Self>, String> {
+        let (ports, protocol) = match spec.rsplit_once('/') {
+            Some((ports, protocol)) => (ports, PortProtocol::parse(protocol)?),
+            None => (spec, PortProtocol::Tcp),
+        };
+
+        let parts: Vec<&str> = ports.split(':').collect();
+        let (host_ip, host_ports, container_ports) = match parts.as_slice() {
+            [container_ports] => (None, "", *container_ports),
+            [host_ports, container_ports] => (None, *host_ports, *container_ports),
+            [host_ip, host_ports, container_ports] => {
+                let host_ip = host_ip
+                    .parse::<Ipv4Addr>()
+                    .map_err(|_| format!("Invalid host IP: {}", host_ip))?;
+                (Some(host_ip), *host_ports, *container_ports)
+            }
+            _ => {
+                return Err(format!(
+                    "Invalid port binding (expected [[ip:][host]:]container[/proto]): {}",
+                    spec
+                ))
+            }
+        };
+
+        let (container_start, container_end) = parse_port_range(container_ports)?;
+        let host_start = match host_ports {
+            "" => None,
+            _ => {
+                let (host_start, host_end) = parse_port_range(host_ports)?;
+                if host_end - host_start != container_end - container_start {
+                    return Err(format!("Host and container port ranges have different lengths: {}", spec));
+                }
+                Some(host_start)
+            }
+        };
+
+        Ok((container_start..=container_end)
+            .map(|container_port| PortBinding {
+                host_ip,
+                host_port: host_start.map(|start| start + (container_port - container_start)),
+                container_port,
+                protocol,
+            })
+            .collect())
+    }
+}
+
+impl std::fmt::Display for PortBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.host_ip, self.host_port) {
+            (Some(host_ip), Some(host_port)) => write!(f, "{}:{}:", host_ip, host_port)?,
+            (Some(host_ip), None) => write!(f, "{}::", host_ip)?,
+            (None, Some(host_port)) => write!(f, "{}:", host_port)?,
+            (None, None) => {}
+        }
+        write!(f, "{}/{}", self.container_port, self.protocol)
+    }
+}
+
+fn parse_port(port: &str) -> Result<u16, String> {
+    match port.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(format!("Invalid port: {}", port)),
+    }
+}
+
+/// Parse `start-end` or a single port, which is a range of one
+fn parse_port_range(range: &str) -> Result<(u16, u16), String> {
+    match range.split_once('-') {
+        Some((start, end)) => {
+            let (start, end) = (parse_port(start)?, parse_port(end)?);
+            if start > end {
+                return Err(format!("Invalid port range: {}", range));
+            }
+            Ok((start, end))
+        }
+        None => {
+            let port = parse_port(range)?;
+            Ok((port, port))
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// TrafficShaping limits the bandwidth and sets the priority of a container's network endpoint
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficShaping {
+    /// Maximum rate of traffic sent by the container, in bits per second
+    pub egress_rate: Option<u64>,
+    /// Maximum rate of traffic received by the container, in bits per second
+    pub ingress_rate: Option<u64>,
+    /// Priority given to the traffic sent by the container (skb priority on the host)
+    pub priority: Option<u32>,
+}
+
+impl TrafficShaping {
+    /// Whether any limit or priority is set
+    pub fn is_empty(&self) -> bool {
+        self.egress_rate.is_none() && self.ingress_rate.is_none() && self.priority.is_none()
+    }
+
+    /// Apply a `--network-opt` value: `rate=`, `egress-rate=`, `ingress-rate=` or `priority=`
+    ///
+    /// `rate` limits both directions. Rates use tc units (e.g. `500kbit`, `10mbit`, `1gbit`, `2mbps`).
+    pub fn apply_option(&mut self, option: &str) -> Result<(), String> {
+        let (key, value) = option
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid network option (expected key=value): {}", option))?;
+        match key {
+            "rate" => {
+                let rate = parse_rate(value)?;
+                self.egress_rate = Some(rate);
+                self.ingress_rate = Some(rate);
+            }
+            "egress-rate" => self.egress_rate = Some(parse_rate(value)?),
+            "ingress-rate" => self.ingress_rate = Some(parse_rate(value)?),
+            "priority" => {
+                self.priority = Some(value.parse().map_err(|_| format!("Invalid priority: {}", value))?);
+            }
+            _ => return Err(format!("Unknown network option: {}", key)),
+        }
+        Ok(())
+    }
+}
+
+/// Parse a rate with a tc unit into bits per second
+///
+/// `bit`, `kbit`, `mbit` and `gbit` are bits per second, `bps`, `kbps`, `mbps` and `gbps` are bytes
+/// per second (SI prefixes as in tc). A number without a unit is bits per second.
+pub fn parse_rate(rate: &str) -> Result<u64, String> {
+    let lower = rate.trim().to_ascii_lowercase();
+    let split = lower.find(|c: char| !c.is_ascii_digit()).unwrap_or(lower.len());
+    let (number, unit) = lower.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("Invalid rate: {}", rate))?;
+
+    let multiplier: u64 = match unit {
+        "" | "bit" => 1,
+        "kbit" => 1_000,
+        "mbit" => 1_000_000,
+        "gbit" => 1_000_000_000,
+        "bps" => 8,
+        "kbps" => 8_000,
+        "mbps" => 8_000_000,
+        "gbps" => 8_000_000_000,
+        _ => return Err(format!("Invalid rate unit in {}", rate)),
+    };
+    match number.checked_mul(multiplier) {
+        Some(bits) if bits > 0 => Ok(bits),
+        _ => Err(format!("Invalid rate: {}", rate)),
+    }
+}
@@ -0,0 +1,239 @@
+use super::cgroup::{read_cgroup_keyed, read_cgroup_value};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// ContainerStats holds a snapshot of the resource usage of a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerStats {
+    /// Container ID
+    pub container_id: String,
+    /// Time when the statistics were read
+    pub read_at: DateTime<Utc>,
+    /// CPU usage
+    pub cpu: CpuStats,
+    /// Memory usage
+    pub memory: MemoryStats,
+    /// Block IO usage per device
+    pub io: Vec<BlockIoStats>,
+    /// Network usage per interface
+    pub networks: HashMap<String, NetworkStats>,
+    /// Number of processes in the container
+    pub pids_current: Option<u64>,
+}
+
+/// CPU usage from cpu.stat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CpuStats {
+    /// Total CPU time consumed in microseconds
+    pub usage_usec: u64,
+    /// CPU time consumed in user mode in microseconds
+    pub user_usec: u64,
+    /// CPU time consumed in kernel mode in microseconds
+    pub system_usec: u64,
+    /// Number of enforcement periods elapsed
+    pub nr_periods: u64,
+    /// Number of periods in which the container was throttled
+    pub nr_throttled: u64,
+    /// Total time the container was throttled in microseconds
+    pub throttled_usec: u64,
+}
+
+/// Memory usage from memory.current and memory.max
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MemoryStats {
+    /// Current memory usage in bytes
+    pub usage_bytes: u64,
+    /// Memory limit in bytes (None means unlimited)
+    pub limit_bytes: Option<u64>,
+    /// Memory used by the page cache in bytes
+    pub cache_bytes: u64,
+}
+
+/// Block IO usage of a device from io.stat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockIoStats {
+    /// Device major number
+    pub major: u64,
+    /// Device minor number
+    pub minor: u64,
+    /// Bytes read
+    pub read_bytes: u64,
+    /// Bytes written
+    pub write_bytes: u64,
+    /// Read operations
+    pub read_ios: u64,
+    /// Write operations
+    pub write_ios: u64,
+}
+
+/// Network usage of an interface from /proc/<pid>/net/dev
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkStats {
+    /// Bytes received
+    pub rx_bytes: u64,
+    /// Packets received
+    pub rx_packets: u64,
+    /// Receive errors
+    pub rx_errors: u64,
+    /// Received packets dropped
+    pub rx_dropped: u64,
+    /// Bytes transmitted
+    pub tx_bytes: u64,
+    /// Packets transmitted
+    pub tx_packets: u64,
+    /// Transmit errors
+    pub tx_errors: u64,
+    /// Transmitted packets dropped
+    pub tx_dropped: u64,
+}
+
+impl ContainerStats {
+    /// Collect the statistics of a container from its cgroup directory and main process
+    ///
+    /// Network counters are read from the network namespace of `pid`, and are left
+    /// empty when the container has no running process.
+    pub fn collect<P: AsRef<Path>>(
+        container_id: &str,
+        cgroup_dir: P,
+        pid: Option<i32>,
+    ) -> std::io::Result<Self> {
+        let cgroup_dir = cgroup_dir.as_ref();
+
+        let cpu_stat = read_cgroup_keyed(cgroup_dir.join("cpu.stat"))?;
+        let cpu = CpuStats {
+            usage_usec: cpu_stat.get("usage_usec").copied().unwrap_or_default(),
+            user_usec: cpu_stat.get("user_usec").copied().unwrap_or_default(),
+            system_usec: cpu_stat.get("system_usec").copied().unwrap_or_default(),
+            nr_periods: cpu_stat.get("nr_periods").copied().unwrap_or_default(),
+            nr_throttled: cpu_stat.get("nr_throttled").copied().unwrap_or_default(),
+            throttled_usec: cpu_stat.get("throttled_usec").copied().unwrap_or_default(),
+        };
+
+        let memory_stat = read_cgroup_keyed(cgroup_dir.join("memory.stat")).unwrap_or_default();
+        let memory = MemoryStats {
+            usage_bytes: read_cgroup_value(cgroup_dir.join("memory.current"))?.unwrap_or_default(),
+            limit_bytes: read_cgroup_value(cgroup_dir.join("memory.max")).unwrap_or(None),
+            cache_bytes: memory_stat.get("file").copied().unwrap_or_default(),
+        };
+
+        let io = match std::fs::read_to_string(cgroup_dir.join("io.stat")) {
+            Ok(content) => parse_io_stat(&content),
+            Err(_) => Vec::new(),
+        };
+
+        let networks = match pid {
+            Some(pid) => match std::fs::read_to_string(format!("/proc/{}/net/dev", pid)) {
+                Ok(content) => parse_net_dev(&content),
+                Err(_) => HashMap::new(),
+            },
+            None => HashMap::new(),
+        };
+
+        Ok(ContainerStats {
+            container_id: container_id.to_string(),
+            read_at: Utc::now(),
+            cpu,
+            memory,
+            io,
+            networks,
+            pids_current: read_cgroup_value(cgroup_dir.join("pids.current")).unwrap_or(None),
+        })
+    }
+
+    /// CPU usage in percent of one CPU between a previous snapshot and this one
+    pub fn cpu_percent(&self, previous: &ContainerStats) -> f64 {
+        let elapsed_usec = (self.read_at - previous.read_at)
+            .num_microseconds()
+            .unwrap_or_default();
+        if elapsed_usec <= 0 {
+            return 0.0;
+        }
+
+        let used_usec = self.cpu.usage_usec.saturating_sub(previous.cpu.usage_usec);
+        used_usec as f64 / elapsed_usec as f64 * 100.0
+    }
+
+    /// Memory usage in percent of the limit (None if the container is unlimited)
+    pub fn memory_percent(&self) -> Option<f64> {
+        match self.memory.limit_bytes {
+            Some(limit) if limit > 0 => Some(self.memory.usage_bytes as f64 / limit as f64 * 100.0),
+            _ => None,
+        }
+    }
+
+    /// Total bytes received and transmitted over all interfaces except loopback
+    pub fn network_totals(&self) -> (u64, u64) {
+        self.networks
+            .iter()
+            .filter(|(name, _)| name.as_str() != "lo")
+            .fold((0, 0), |(rx, tx), (_, stats)| (rx + stats.rx_bytes, tx + stats.tx_bytes))
+    }
+
+    /// Total bytes read and written over all block devices
+    pub fn io_totals(&self) -> (u64, u64) {
+        self.io
+            .iter()
+            .fold((0, 0), |(read, write), stats| (read + stats.read_bytes, write + stats.write_bytes))
+    }
+}
+
+/// Parse io.stat lines such as "8:0 rbytes=1024 wbytes=0 rios=1 wios=0"
+fn parse_io_stat(content: &str) -> Vec<BlockIoStats> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (device, fields) = line.split_once(' ')?;
+            let (major, minor) = device.split_once(':')?;
+            let fields: HashMap<&str, u64> = fields
+                .split_whitespace()
+                .filter_map(|field| {
+                    let (key, value) = field.split_once('=')?;
+                    Some((key, value.parse().ok()?))
+                })
+                .collect();
+
+            Some(BlockIoStats {
+                major: major.parse().ok()?,
+                minor: minor.parse().ok()?,
+                read_bytes: fields.get("rbytes").copied().unwrap_or_default(),
+                write_bytes: fields.get("wbytes").copied().unwrap_or_default(),
+                read_ios: fields.get("rios").copied().unwrap_or_default(),
+                write_ios: fields.get("wios").copied().unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+/// Parse the interface table of /proc/<pid>/net/dev
+fn parse_net_dev(content: &str) -> HashMap<String, NetworkStats> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .filter_map(|value| value.parse().ok())
+                .collect();
+            if counters.len() < 12 {
+                return None;
+            }
+
+            Some((
+                name.trim().to_string(),
+                NetworkStats {
+                    rx_bytes: counters[0],
+                    rx_packets: counters[1],
+                    rx_errors: counters[2],
+                    rx_dropped: counters[3],
+                    tx_bytes: counters[8],
+                    tx_packets: counters[9],
+                    tx_errors: counters[10],
+                    tx_dropped: counters[11],
+                },
+            ))
+        })
+        .collect()
+}
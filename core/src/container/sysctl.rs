@@ -0,0 +1,42 @@
+use super::NetworkMode;
+
+/// IPC sysctls that are namespaced and safe to set per container
+const IPC_SYSCTLS: [&str; 8] = [
+    "kernel.msgmax",
+    "kernel.msgmnb",
+    "kernel.msgmni",
+    "kernel.sem",
+    "kernel.shmall",
+    "kernel.shmmax",
+    "kernel.shmmni",
+    "kernel.shm_rmid_forced",
+];
+
+/// Check that a sysctl is namespaced and may be set for a container with the given network mode
+///
+/// Only IPC and network sysctls are isolated per container; anything else would change
+/// the host kernel. Network sysctls are rejected when the container shares the host network.
+pub fn validate_sysctl(key: &str, network_mode: &NetworkMode) -> Result<(), String> {
+    if IPC_SYSCTLS.contains(&key) || key.starts_with("fs.mqueue.") {
+        return Ok(());
+    }
+
+    if key.starts_with("net.") {
+        return match network_mode {
+            NetworkMode::Host => Err(format!(
+                "Sysctl {} is not allowed when using the host network",
+                key
+            )),
+            _ => Ok(()),
+        };
+    }
+
+    if key == "kernel.hostname" || key == "kernel.domainname" {
+        return Err(format!(
+            "Sysctl {} is not allowed, use --hostname or --domainname instead",
+            key
+        ));
+    }
+
+    Err(format!("Sysctl {} is not namespaced and cannot be set in a container", key))
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Clock ticks per second used by the times in /proc/<pid>/stat
+const USER_HZ: u64 = 100;
+
+/// ContainerTop lists the processes running in a container, one row per process
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContainerTop {
+    /// Column names: UID, PID, PPID, STAT, TIME and CMD
+    pub titles: Vec<String>,
+    /// Column values for each process
+    pub processes: Vec<Vec<String>>,
+}
+
+impl ContainerTop {
+    /// Read the processes of the container's cgroup from /proc
+    ///
+    /// Processes that exit while the list is being read are skipped.
+    pub fn collect<P: AsRef<Path>>(cgroup_dir: P) -> std::io::Result<Self> {
+        let procs = std::fs::read_to_string(cgroup_dir.as_ref().join("cgroup.procs"))?;
+        let mut pids: Vec<u32> = procs.lines().filter_map(|line| line.trim().parse().ok()).collect();
+        pids.sort_unstable();
+
+        let processes = pids.into_iter().filter_map(read_process).collect();
+        Ok(ContainerTop {
+            titles: ["UID", "PID", "PPID", "STAT", "TIME", "CMD"]
+                .iter()
+                .map(|title| title.to_string())
+                .collect(),
+            processes,
+        })
+    }
+}
+
+fn read_process(pid: u32) -> Option<Vec<String>> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // comm may contain spaces and parentheses, the other fields follow the last ')'
+    let (head, rest) = stat.rsplit_once(')')?;
+    let comm = head.split_once('(')?.1;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let state = fields.first()?;
+    let ppid = fields.get(1)?;
+    let ticks = fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+
+    let uid = std::fs::read_to_string(format!("/proc/{}/status", pid))
+        .ok()
+        .and_then(|status| {
+            status
+                .lines()
+                .find_map(|line| line.strip_prefix("Uid:"))
+                .and_then(|uids| uids.split_whitespace().next().map(str::to_string))
+        })
+        .unwrap_or_default();
+
+    let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+    let cmd = if cmdline.is_empty() {
+        format!("[{}]", comm)
+    } else {
+        cmdline
+            .split(|b| *b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let seconds = ticks / USER_HZ;
+    Some(vec![
+        uid,
+        pid.to_string(),
+        ppid.to_string(),
+        state.to_string(),
+        format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60),
+        cmd,
+    ])
+}
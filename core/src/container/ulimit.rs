@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+/// Resource names accepted by `--ulimit`
+pub const ULIMIT_NAMES: [&str; 16] = [
+    "as", "core", "cpu", "data", "fsize", "locks", "memlock", "msgqueue", "nice", "nofile",
+    "nproc", "rss", "rtprio", "rttime", "sigpending", "stack",
+];
+
+/// Ulimit represents a resource limit set with setrlimit in the container process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ulimit {
+    /// Name of the resource (e.g. nofile)
+    pub name: String,
+    /// Soft limit
+    pub soft: u64,
+    /// Hard limit
+    pub hard: u64,
+}
+
+impl Ulimit {
+    /// Parse a ulimit in the form `name=soft[:hard]` (e.g. `nofile=1024:2048`), -1 meaning unlimited
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, limits) = spec
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid ulimit: {}", spec))?;
+
+        if !ULIMIT_NAMES.contains(&name) {
+            return Err(format!("Invalid ulimit type: {}", name));
+        }
+
+        let (soft, hard) = match limits.split_once(':') {
+            Some((soft, hard)) => (parse_limit(soft)?, parse_limit(hard)?),
+            None => {
+                let limit = parse_limit(limits)?;
+                (limit, limit)
+            }
+        };
+
+        if soft > hard {
+            return Err(format!(
+                "Ulimit soft limit must be less than or equal to hard limit: {}",
+                spec
+            ));
+        }
+
+        Ok(Ulimit {
+            name: name.to_string(),
+            soft,
+            hard,
+        })
+    }
+}
+
+fn parse_limit(s: &str) -> Result<u64, String> {
+    if s == "-1" || s == "unlimited" {
+        return Ok(u64::MAX);
+    }
+    s.parse::<u64>()
+        .map_err(|_| format!("Invalid ulimit value: {}", s))
+}
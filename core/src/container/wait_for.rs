@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+/// Seconds a start waits for the `--wait-for` dependencies when no timeout is given
+pub const DEFAULT_WAIT_TIMEOUT: u64 = 60;
+
+/// Dependency the daemon waits for before starting the main process of a container (`--wait-for`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WaitFor {
+    /// A container, ready once it runs and its health check (if any) reports it as healthy
+    Container(String),
+    /// A TCP endpoint, ready once it accepts connections
+    ///
+    /// The host is a container name or network alias on a network of the waiting container, or else a
+    /// host name or address resolved by the daemon.
+    Tcp { host: String, port: u16 },
+}
+
+impl WaitFor {
+    /// Parse `tcp://<host>:<port>` or a container name (`container:<name>` or the name alone)
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if let Some(address) = spec.strip_prefix("tcp://") {
+            let (host, port) = address
+                .rsplit_once(':')
+                .ok_or_else(|| format!("Invalid wait target (expected tcp://host:port): {}", spec))?;
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            let port = port
+                .parse::<u16>()
+                .ok()
+                .filter(|port| *port != 0)
+                .ok_or_else(|| format!("Invalid port in wait target: {}", spec))?;
+            if host.is_empty() {
+                return Err(format!("Invalid wait target (expected tcp://host:port): {}", spec));
+            }
+            return Ok(WaitFor::Tcp {
+                host: host.to_string(),
+                port,
+            });
+        }
+        let name = spec.strip_prefix("container:").unwrap_or(spec);
+        if name.is_empty() || name.contains(|c: char| c == '/' || c.is_whitespace()) {
+            return Err(format!("Invalid wait target: {}", spec));
+        }
+        Ok(WaitFor::Container(name.to_string()))
+    }
+}
+
+impl std::fmt::Display for WaitFor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WaitFor::Container(name) => write!(f, "container:{}", name),
+            WaitFor::Tcp { host, port } if host.contains(':') => write!(f, "tcp://[{}]:{}", host, port),
+            WaitFor::Tcp { host, port } => write!(f, "tcp://{}:{}", host, port),
+        }
+    }
+}
@@ -1,24 +1,41 @@
+use std::error::Error as StdError;
 use thiserror::Error;
 
 /// RockerError represents all possible errors in the Rocker container engine
 #[derive(Error, Debug)]
 pub enum RockerError {
     /// Container errors
-    #[error("Container error: {0}")]
+    #[error(transparent)]
     Container(#[from] ContainerError),
 
     /// Image errors
-    #[error("Image error: {0}")]
+    #[error(transparent)]
     Image(#[from] ImageError),
 
     /// Network errors
-    #[error("Network error: {0}")]
+    #[error(transparent)]
     Network(#[from] NetworkError),
 
     /// Volume errors
-    #[error("Volume error: {0}")]
+    #[error(transparent)]
     Volume(#[from] VolumeError),
 
+    /// Swarm errors
+    #[error(transparent)]
+    Swarm(#[from] SwarmError),
+
+    /// Secret errors
+    #[error(transparent)]
+    Secret(#[from] SecretError),
+
+    /// Schedule errors
+    #[error(transparent)]
+    Schedule(#[from] ScheduleError),
+
+    /// Compose project errors
+    #[error(transparent)]
+    Compose(#[from] ComposeError),
+
     /// Daemon errors
     #[error("Daemon error: {0}")]
     Daemon(String),
@@ -31,6 +48,10 @@ pub enum RockerError {
     #[error("Serialization error: {0}")]
     Serde(#[from] serde_json::Error),
 
+    /// A blocking task panicked or was cancelled
+    #[error("Task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+
     /// Generic errors
     #[error("{0}")]
     Generic(String),
@@ -43,6 +64,10 @@ pub enum ContainerError {
     #[error("Container not found: {0}")]
     NotFound(String),
 
+    /// Identifier is a prefix of more than one ID
+    #[error("Multiple containers found with prefix: {0}")]
+    Ambiguous(String),
+
     /// Container already exists
     #[error("Container already exists: {0}")]
     AlreadyExists(String),
@@ -71,17 +96,45 @@ pub enum ContainerError {
     #[error("Container is not running: {0}")]
     NotRunning(String),
 
+    /// Container is paused
+    #[error("Container is paused: {0}")]
+    Paused(String),
+
+    /// Container is not paused
+    #[error("Container is not paused: {0}")]
+    NotPaused(String),
+
     /// Failed to execute command in container
     #[error("Failed to execute command in container: {0}")]
     Exec(String),
 
+    /// Exec session not found
+    #[error("Exec session not found: {0}")]
+    ExecNotFound(String),
+
     /// Failed to get container logs
     #[error("Failed to get container logs: {0}")]
     Logs(String),
 
+    /// Logging driver or its options are invalid
+    #[error("Invalid log configuration: {0}")]
+    InvalidLogConfig(String),
+
     /// Container runtime error
     #[error("Container runtime error: {0}")]
     Runtime(String),
+
+    /// Path in the container filesystem is invalid
+    #[error("Invalid path in container: {0}")]
+    InvalidPath(String),
+
+    /// Reserving the container's CPUs or memory would exceed what the host can allocate
+    #[error("Insufficient resources: {0}")]
+    InsufficientResources(String),
+
+    /// The `--wait-for` dependencies were not ready within the wait timeout
+    #[error("Dependencies not ready: {0}")]
+    DependenciesNotReady(String),
 }
 
 /// ImageError represents image-related errors
@@ -91,6 +144,10 @@ pub enum ImageError {
     #[error("Image not found: {0}")]
     NotFound(String),
 
+    /// Identifier is a prefix of more than one ID
+    #[error("Multiple images found with prefix: {0}")]
+    Ambiguous(String),
+
     /// Image already exists
     #[error("Image already exists: {0}")]
     AlreadyExists(String),
@@ -130,6 +187,18 @@ pub enum ImageError {
     /// Registry error
     #[error("Registry error: {0}")]
     Registry(String),
+
+    /// The registry rejected the credentials
+    #[error("Authentication failed: {0}")]
+    Unauthorized(String),
+
+    /// Failed to generate an SBOM or to scan an image
+    #[error("Failed to scan image: {0}")]
+    Scan(String),
+
+    /// The build cache cannot be pruned while builds are running
+    #[error("Cannot prune the build cache: {0}")]
+    CacheBusy(String),
 }
 
 /// NetworkError represents network-related errors
@@ -139,6 +208,10 @@ pub enum NetworkError {
     #[error("Network not found: {0}")]
     NotFound(String),
 
+    /// Identifier is a prefix of more than one ID
+    #[error("Multiple networks found with prefix: {0}")]
+    Ambiguous(String),
+
     /// Network already exists
     #[error("Network already exists: {0}")]
     AlreadyExists(String),
@@ -163,6 +236,10 @@ pub enum NetworkError {
     #[error("IP allocation error: {0}")]
     IpAllocation(String),
 
+    /// The host port is published by another container or used by another process
+    #[error("Port is already allocated: {0}")]
+    PortAllocated(String),
+
     /// Invalid network configuration
     #[error("Invalid network configuration: {0}")]
     InvalidConfig(String),
@@ -175,6 +252,10 @@ pub enum VolumeError {
     #[error("Volume not found: {0}")]
     NotFound(String),
 
+    /// Identifier is a prefix of more than one ID
+    #[error("Multiple volumes found with prefix: {0}")]
+    Ambiguous(String),
+
     /// Volume already exists
     #[error("Volume already exists: {0}")]
     AlreadyExists(String),
@@ -202,4 +283,416 @@ pub enum VolumeError {
     /// Invalid volume driver
     #[error("Invalid volume driver: {0}")]
     InvalidDriver(String),
-} 
+
+    /// Snapshot not found
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
+    /// Failed to clone, snapshot or restore volume
+    #[error("Failed to copy volume data: {0}")]
+    Snapshot(String),
+}
+
+/// SwarmError represents errors of the swarm mode (nodes and services)
+#[derive(Error, Debug)]
+pub enum SwarmError {
+    /// The daemon is not part of a swarm
+    #[error("This node is not part of a swarm")]
+    NotInSwarm,
+
+    /// The daemon is already part of a swarm
+    #[error("This node is already part of a swarm")]
+    AlreadyInSwarm,
+
+    /// The request needs the manager
+    #[error("This node is not a swarm manager, run the command on the manager")]
+    NotManager,
+
+    /// Service not found
+    #[error("Service not found: {0}")]
+    ServiceNotFound(String),
+
+    /// Service already exists
+    #[error("Service already exists: {0}")]
+    ServiceAlreadyExists(String),
+
+    /// Node not found
+    #[error("Node not found: {0}")]
+    NodeNotFound(String),
+
+    /// Invalid service spec, address or token
+    #[error("Invalid swarm configuration: {0}")]
+    InvalidConfig(String),
+
+    /// The manager rejected the node
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    /// Failed to reach the manager or to set up its certificates
+    #[error("Swarm communication failed: {0}")]
+    Communication(String),
+}
+
+/// SecretError represents errors of the daemon-managed secrets
+#[derive(Error, Debug)]
+pub enum SecretError {
+    /// Secret not found
+    #[error("Secret not found: {0}")]
+    NotFound(String),
+
+    /// Identifier is a prefix of more than one ID
+    #[error("Multiple secrets found with prefix: {0}")]
+    Ambiguous(String),
+
+    /// Secret already exists
+    #[error("Secret already exists: {0}")]
+    AlreadyExists(String),
+
+    /// Invalid name, data or reference
+    #[error("Invalid secret: {0}")]
+    Invalid(String),
+
+    /// Secret is used by containers
+    #[error("Secret is in use: {0}")]
+    InUse(String),
+
+    /// Failed to encrypt, decrypt or store the secret
+    #[error("Secret store error: {0}")]
+    Store(String),
+}
+
+/// ScheduleError represents errors of the schedules running containers on cron expressions
+#[derive(Error, Debug)]
+pub enum ScheduleError {
+    /// Schedule not found
+    #[error("Schedule not found: {0}")]
+    NotFound(String),
+
+    /// Identifier is a prefix of more than one ID
+    #[error("Multiple schedules found with prefix: {0}")]
+    Ambiguous(String),
+
+    /// Schedule already exists
+    #[error("Schedule already exists: {0}")]
+    AlreadyExists(String),
+
+    /// Invalid cron expression, policy or container configuration
+    #[error("Invalid schedule: {0}")]
+    Invalid(String),
+
+    /// Containers of the schedule are still running
+    #[error("Schedule is running: {0}")]
+    Running(String),
+}
+
+/// ComposeError represents errors of compose projects
+#[derive(Error, Debug)]
+pub enum ComposeError {
+    /// The project has no service of this name
+    #[error("No such service: {0}")]
+    ServiceNotFound(String),
+
+    /// Invalid compose file
+    #[error("Invalid compose file: {0}")]
+    InvalidConfig(String),
+
+    /// Services depend on each other in a cycle
+    #[error("Circular dependency detected: {0}")]
+    CircularDependency(String),
+}
+
+/// Broad category of an error, which the API maps to an HTTP status and the CLI to an exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The object does not exist
+    NotFound,
+    /// The request or its arguments are invalid
+    Invalid,
+    /// The request conflicts with the current state
+    Conflict,
+    /// The registry rejected the credentials
+    Unauthorized,
+    /// The request is not allowed
+    Forbidden,
+    /// The request cannot be served now (e.g. the node is not a swarm manager)
+    Unavailable,
+    /// Any other failure
+    Internal,
+}
+
+/// Class, stable code and object kind of an error, as sent in the daemon's error responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub class: ErrorClass,
+    /// Identifier of the error that stays the same across releases, such as `container_not_found`
+    pub code: &'static str,
+    /// Kind of object the error is about (`container`, `image`, `network`, `volume`, `secret`, `schedule`,
+    /// `service`, `node`, `swarm` or `compose`)
+    pub kind: Option<&'static str>,
+}
+
+impl ErrorCode {
+    fn new(class: ErrorClass, code: &'static str, kind: &'static str) -> Self {
+        ErrorCode {
+            class,
+            code,
+            kind: Some(kind),
+        }
+    }
+
+    fn internal(code: &'static str) -> Self {
+        ErrorCode {
+            class: ErrorClass::Internal,
+            code,
+            kind: None,
+        }
+    }
+}
+
+impl RockerError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            RockerError::Container(e) => e.code(),
+            RockerError::Image(e) => e.code(),
+            RockerError::Network(e) => e.code(),
+            RockerError::Volume(e) => e.code(),
+            RockerError::Swarm(e) => e.code(),
+            RockerError::Secret(e) => e.code(),
+            RockerError::Schedule(e) => e.code(),
+            RockerError::Compose(e) => e.code(),
+            RockerError::Daemon(_) => ErrorCode::internal("daemon_error"),
+            RockerError::Io(_) => ErrorCode::internal("io_error"),
+            RockerError::Serde(_) => ErrorCode::internal("serialization_error"),
+            RockerError::Task(_) => ErrorCode::internal("task_failed"),
+            RockerError::Generic(_) => ErrorCode::internal("internal_error"),
+        }
+    }
+
+    /// Code of the first `RockerError`, or error of one of its variants, in the source chain of `e`
+    ///
+    /// The CLI and the compose library return boxed errors, so this finds the structured error behind them and
+    /// behind errors that wrap them with more context.
+    pub fn code_of(e: &(dyn StdError + 'static)) -> Option<ErrorCode> {
+        let mut current = Some(e);
+        while let Some(e) = current {
+            let code = if let Some(e) = e.downcast_ref::<RockerError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<ContainerError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<ImageError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<NetworkError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<VolumeError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<SwarmError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<SecretError>() {
+                Some(e.code())
+            } else if let Some(e) = e.downcast_ref::<ScheduleError>() {
+                Some(e.code())
+            } else {
+                e.downcast_ref::<ComposeError>().map(ComposeError::code)
+            };
+            if code.is_some() {
+                return code;
+            }
+            current = e.source();
+        }
+        None
+    }
+}
+
+impl ContainerError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            ContainerError::NotFound(_) => (NotFound, "container_not_found"),
+            ContainerError::ExecNotFound(_) => (NotFound, "exec_not_found"),
+            ContainerError::Ambiguous(_) => (Invalid, "container_ambiguous"),
+            ContainerError::InvalidPath(_) => (Invalid, "container_invalid_path"),
+            ContainerError::InvalidLogConfig(_) => (Invalid, "container_invalid_log_config"),
+            ContainerError::AlreadyExists(_) => (Conflict, "container_already_exists"),
+            ContainerError::AlreadyRunning(_) => (Conflict, "container_already_running"),
+            ContainerError::NotRunning(_) => (Conflict, "container_not_running"),
+            ContainerError::Paused(_) => (Conflict, "container_paused"),
+            ContainerError::NotPaused(_) => (Conflict, "container_not_paused"),
+            ContainerError::Remove(_) => (Conflict, "container_remove_failed"),
+            ContainerError::InsufficientResources(_) => (Conflict, "container_insufficient_resources"),
+            ContainerError::DependenciesNotReady(_) => (Conflict, "container_dependencies_not_ready"),
+            ContainerError::Start(_) => (Internal, "container_start_failed"),
+            ContainerError::Stop(_) => (Internal, "container_stop_failed"),
+            ContainerError::Create(_) => (Internal, "container_create_failed"),
+            ContainerError::Exec(_) => (Internal, "container_exec_failed"),
+            ContainerError::Logs(_) => (Internal, "container_logs_failed"),
+            ContainerError::Runtime(_) => (Internal, "container_runtime_error"),
+        };
+        ErrorCode::new(class, code, "container")
+    }
+}
+
+impl ImageError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            ImageError::NotFound(_) => (NotFound, "image_not_found"),
+            ImageError::Ambiguous(_) => (Invalid, "image_ambiguous"),
+            ImageError::Reference(_) => (Invalid, "image_invalid_reference"),
+            ImageError::Remove(_) => (Conflict, "image_remove_failed"),
+            ImageError::Unauthorized(_) => (Unauthorized, "registry_unauthorized"),
+            ImageError::AlreadyExists(_) => (Internal, "image_already_exists"),
+            ImageError::Pull(_) => (Internal, "image_pull_failed"),
+            ImageError::Push(_) => (Internal, "image_push_failed"),
+            ImageError::Build(_) => (Internal, "image_build_failed"),
+            ImageError::Tag(_) => (Internal, "image_tag_failed"),
+            ImageError::Save(_) => (Internal, "image_save_failed"),
+            ImageError::Load(_) => (Internal, "image_load_failed"),
+            ImageError::Registry(_) => (Internal, "registry_error"),
+            ImageError::Scan(_) => (Internal, "image_scan_failed"),
+            ImageError::CacheBusy(_) => (Conflict, "build_cache_busy"),
+        };
+        ErrorCode::new(class, code, "image")
+    }
+}
+
+impl NetworkError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            NetworkError::NotFound(_) => (NotFound, "network_not_found"),
+            NetworkError::Ambiguous(_) => (Invalid, "network_ambiguous"),
+            NetworkError::InvalidConfig(_) => (Invalid, "network_invalid_config"),
+            NetworkError::IpAllocation(_) => (Invalid, "network_ip_allocation_failed"),
+            NetworkError::AlreadyExists(_) => (Conflict, "network_already_exists"),
+            NetworkError::Remove(_) => (Conflict, "network_remove_failed"),
+            NetworkError::PortAllocated(_) => (Conflict, "port_already_allocated"),
+            NetworkError::Create(_) => (Internal, "network_create_failed"),
+            NetworkError::Connect(_) => (Internal, "network_connect_failed"),
+            NetworkError::Disconnect(_) => (Internal, "network_disconnect_failed"),
+        };
+        ErrorCode::new(class, code, "network")
+    }
+}
+
+impl VolumeError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            VolumeError::NotFound(_) => (NotFound, "volume_not_found"),
+            VolumeError::SnapshotNotFound(_) => (NotFound, "snapshot_not_found"),
+            VolumeError::Ambiguous(_) => (Invalid, "volume_ambiguous"),
+            VolumeError::Create(_) => (Invalid, "volume_create_failed"),
+            VolumeError::InvalidDriver(_) => (Invalid, "volume_invalid_driver"),
+            VolumeError::AlreadyExists(_) => (Conflict, "volume_already_exists"),
+            VolumeError::InUse(_) => (Conflict, "volume_in_use"),
+            VolumeError::Remove(_) => (Internal, "volume_remove_failed"),
+            VolumeError::Mount(_) => (Internal, "volume_mount_failed"),
+            VolumeError::Unmount(_) => (Internal, "volume_unmount_failed"),
+            VolumeError::Snapshot(_) => (Internal, "volume_snapshot_failed"),
+        };
+        ErrorCode::new(class, code, "volume")
+    }
+}
+
+impl SwarmError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        match self {
+            SwarmError::ServiceNotFound(_) => ErrorCode::new(NotFound, "service_not_found", "service"),
+            SwarmError::NodeNotFound(_) => ErrorCode::new(NotFound, "node_not_found", "node"),
+            SwarmError::ServiceAlreadyExists(_) => ErrorCode::new(Conflict, "service_already_exists", "service"),
+            SwarmError::AlreadyInSwarm => ErrorCode::new(Conflict, "swarm_already_joined", "swarm"),
+            SwarmError::NotInSwarm => ErrorCode::new(Unavailable, "swarm_not_joined", "swarm"),
+            SwarmError::NotManager => ErrorCode::new(Unavailable, "swarm_not_manager", "swarm"),
+            SwarmError::InvalidConfig(_) => ErrorCode::new(Invalid, "swarm_invalid_config", "swarm"),
+            SwarmError::PermissionDenied(_) => ErrorCode::new(Forbidden, "swarm_permission_denied", "swarm"),
+            SwarmError::Communication(_) => ErrorCode::new(Internal, "swarm_communication_failed", "swarm"),
+        }
+    }
+}
+
+impl SecretError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            SecretError::NotFound(_) => (NotFound, "secret_not_found"),
+            SecretError::Ambiguous(_) => (Invalid, "secret_ambiguous"),
+            SecretError::Invalid(_) => (Invalid, "secret_invalid"),
+            SecretError::AlreadyExists(_) => (Conflict, "secret_already_exists"),
+            SecretError::InUse(_) => (Conflict, "secret_in_use"),
+            SecretError::Store(_) => (Internal, "secret_store_error"),
+        };
+        ErrorCode::new(class, code, "secret")
+    }
+}
+
+impl ScheduleError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            ScheduleError::NotFound(_) => (NotFound, "schedule_not_found"),
+            ScheduleError::Ambiguous(_) => (Invalid, "schedule_ambiguous"),
+            ScheduleError::Invalid(_) => (Invalid, "schedule_invalid"),
+            ScheduleError::AlreadyExists(_) => (Conflict, "schedule_already_exists"),
+            ScheduleError::Running(_) => (Conflict, "schedule_running"),
+        };
+        ErrorCode::new(class, code, "schedule")
+    }
+}
+
+impl ComposeError {
+    /// Class, code and kind of the error
+    pub fn code(&self) -> ErrorCode {
+        use ErrorClass::*;
+        let (class, code) = match self {
+            ComposeError::ServiceNotFound(_) => (NotFound, "compose_service_not_found"),
+            ComposeError::InvalidConfig(_) => (Invalid, "compose_invalid_config"),
+            ComposeError::CircularDependency(_) => (Invalid, "compose_circular_dependency"),
+        };
+        ErrorCode::new(class, code, "compose")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_comes_from_the_variant() {
+        let e = RockerError::from(ContainerError::NotFound("web".to_string()));
+        assert_eq!(e.code().class, ErrorClass::NotFound);
+        assert_eq!(e.code().code, "container_not_found");
+        assert_eq!(e.code().kind, Some("container"));
+
+        let e = RockerError::from(ComposeError::InvalidConfig(format!("Service {} has no image", "web")));
+        assert_eq!(e.code().class, ErrorClass::Invalid);
+        assert_eq!(e.code().code, "compose_invalid_config");
+
+        let e = RockerError::Generic("boom".to_string());
+        assert_eq!(e.code().class, ErrorClass::Internal);
+        assert_eq!(e.code().kind, None);
+    }
+
+    #[test]
+    fn wrapped_errors_keep_their_message() {
+        let e = RockerError::from(ContainerError::NotFound("web".to_string()));
+        assert_eq!(e.to_string(), ContainerError::NotFound("web".to_string()).to_string());
+    }
+
+    #[test]
+    fn code_of_finds_the_error_behind_a_box() {
+        let e: Box<dyn StdError> = Box::new(RockerError::from(ImageError::NotFound("alpine".to_string())));
+        assert_eq!(RockerError::code_of(e.as_ref()).map(|code| code.class), Some(ErrorClass::NotFound));
+
+        let e: Box<dyn StdError> = "not structured".into();
+        assert_eq!(RockerError::code_of(e.as_ref()), None);
+    }
+}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Type of object an event is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventType {
+    Container,
+    Image,
+    Network,
+    Volume,
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let type_str = match self {
+            EventType::Container => "container",
+            EventType::Image => "image",
+            EventType::Network => "network",
+            EventType::Volume => "volume",
+        };
+        write!(f, "{}", type_str)
+    }
+}
+
+/// Event represents something that happened to an object managed by the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    /// Type of the object
+    pub event_type: EventType,
+    /// What happened (e.g. start, die, oom)
+    pub action: String,
+    /// ID of the object
+    pub actor_id: String,
+    /// Additional information (e.g. name, image, exitCode)
+    pub attributes: HashMap<String, String>,
+    /// Time when the event happened
+    pub time: DateTime<Utc>,
+}
+
+impl Event {
+    /// Create a new event without attributes
+    pub fn new(event_type: EventType, action: &str, actor_id: &str) -> Self {
+        Event {
+            event_type,
+            action: action.to_string(),
+            actor_id: actor_id.to_string(),
+            attributes: HashMap::new(),
+            time: Utc::now(),
+        }
+    }
+
+    /// Add an attribute to the event
+    pub fn with_attribute(mut self, key: &str, value: &str) -> Self {
+        self.attributes.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Add the labels of the object as attributes, so that events can be filtered by label
+    ///
+    /// Attributes added later (such as `name`) take precedence over labels with the same key.
+    pub fn with_labels(mut self, labels: &HashMap<String, String>) -> Self {
+        self.attributes
+            .extend(labels.iter().map(|(key, value)| (key.clone(), value.clone())));
+        self
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// The recorded result of a build step in the daemon's build cache (`rocker builder ls`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildCacheEntry {
+    /// Cache key of the step (a hash of the instruction, the layers below it and the files it copies)
+    pub id: String,
+    /// Instruction of the step, as in the image history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Diff ID of the layer the step created (None if it did not change the filesystem)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_id: Option<String>,
+    /// Size of the layer in bytes
+    pub size: u64,
+    /// Whether an image uses the layer, in which case pruning the entry frees no space
+    pub in_use: bool,
+    /// Time the step was recorded (None for entries recorded by older daemons)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Time a build last used the entry instead of running the step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Number of builds that used the entry
+    #[serde(default)]
+    pub usage_count: u64,
+}
+
+impl BuildCacheEntry {
+    /// Time of the last use, or of the recording if the entry was never used
+    pub fn last_activity(&self) -> Option<DateTime<Utc>> {
+        self.last_used_at.or(self.created_at)
+    }
+}
+
+/// Result of pruning the build cache
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildCachePruneReport {
+    /// IDs of the removed entries
+    pub caches_deleted: Vec<String>,
+    /// Bytes freed by removing the layers no image or other entry uses
+    pub space_reclaimed: u64,
+}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable event of a build, carried by the [`ProgressMessage`](crate::ProgressMessage) it
+/// happens in
+///
+/// Steps are numbered from 1 across all stages, as in the `Step 2/5 : ...` status lines, and the events
+/// of a step come in order: started, then a cache hit if any, then finished or failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuildEvent {
+    /// A step of the Rockerfile started
+    StepStarted {
+        step: usize,
+        /// Number of steps of the build
+        total: usize,
+        /// Position of the stage of the step, from 0
+        stage: usize,
+        /// Name of the stage (`FROM ... AS <name>`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stage_name: Option<String>,
+        instruction: String,
+    },
+    /// The result of the step was taken from the build cache instead of running it
+    CacheHit {
+        step: usize,
+        /// Cache key of the step (an entry of `rocker builder ls`)
+        key: String,
+    },
+    /// A step finished
+    StepFinished {
+        step: usize,
+        /// Whether the result came from the build cache
+        cached: bool,
+        /// Diff ID of the layer the step added (None if it did not change the filesystem)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        diff_id: Option<String>,
+        /// Size of that layer in bytes
+        #[serde(default)]
+        size: u64,
+        duration_ms: u64,
+    },
+    /// A step failed, ending the build with the error of the last message
+    StepFailed { step: usize, error: String },
+    /// A layer was written to the cache export (`--cache-to`)
+    LayerExported {
+        diff_id: String,
+        /// Digest and size of the compressed layer in the export
+        digest: String,
+        size: u64,
+        /// Where the cache was exported to
+        destination: String,
+    },
+    /// The image was built (the last event of a successful build)
+    Finished(BuildMetadata),
+}
+
+/// Summary of a finished build, written by `rocker build --metadata-file`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildMetadata {
+    /// ID of the built image, the digest of its configuration and layers
+    pub image_id: String,
+    /// Name the image was tagged with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Diff IDs of the layers of the image, bottom first
+    pub layers: Vec<String>,
+    /// Size of the image in bytes
+    pub size: u64,
+    /// Number of steps of the build, and how many of them came from the build cache
+    pub steps: usize,
+    pub cached_steps: usize,
+    /// Where the build cache was exported to (`--cache-to`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_to: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+/// ImageDiff lists the files that differ between two images, or between an image and a container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageDiff {
+    /// Image compared against
+    pub from: String,
+    /// Image or container compared
+    pub to: String,
+    /// Number of bottom layers both have in common, which are not listed
+    pub shared_layers: usize,
+    /// Changes of each layer of `to` above the shared layers, bottom first (a container's writable layer is
+    /// the last one)
+    pub layers: Vec<LayerChanges>,
+    /// Changes between the files of `from` and of `to` as a whole
+    pub changes: Vec<FileChange>,
+    /// Bytes of files in `to` minus bytes of files in `from`
+    pub size_delta: i64,
+}
+
+/// Changes a layer makes to the files of the layers below it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerChanges {
+    /// Diff ID of the layer (None for the writable layer of a container)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_id: Option<String>,
+    /// Command that created the layer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Changed paths, sorted by path
+    pub changes: Vec<FileChange>,
+    /// Bytes of files after the layer minus bytes of files before it
+    pub size_delta: i64,
+}
+
+/// A path that was added, removed or changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Absolute path in the image
+    pub path: String,
+    pub kind: ChangeKind,
+    /// Size of the file after the change (before it for removed files, 0 for directories)
+    pub size: u64,
+}
+
+/// Kind of a [`FileChange`]
+///
+/// The contents of a removed directory are not listed separately. Directories count as modified only when
+/// their mode or owner changes, not when their entries do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl ChangeKind {
+    /// One-letter marker as in `A /etc/app.conf`
+    pub fn marker(&self) -> char {
+        match self {
+            ChangeKind::Added => 'A',
+            ChangeKind::Removed => 'D',
+            ChangeKind::Modified => 'C',
+        }
+    }
+}
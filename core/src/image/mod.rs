@@ -3,6 +3,22 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::container::HealthConfig;
+use crate::utils::Identifiable;
+
+mod build_cache;
+mod build_event;
+mod diff;
+mod progress;
+mod registry;
+mod scan;
+pub use build_cache::*;
+pub use build_event::*;
+pub use diff::*;
+pub use progress::*;
+pub use registry::*;
+pub use scan::*;
+
 /// Image represents a container image
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Image {
@@ -24,6 +40,12 @@ pub struct Image {
     pub parent_id: Option<String>,
     /// Labels
     pub labels: HashMap<String, String>,
+    /// Result of the last vulnerability scan (`rocker image scan`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan: Option<ImageScan>,
+    /// Manifest digests of the image in registries (`repo@sha256:...`), recorded by pull and push
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub repo_digests: Vec<String>,
 }
 
 impl Image {
@@ -64,16 +86,24 @@ impl Image {
 
     /// Returns true if the image matches the given name (repo:tag)
     pub fn matches_name(&self, name: &str) -> bool {
-        if let Some(colon_pos) = name.find(':') {
-            let repo = &name[0..colon_pos];
-            let tag = &name[colon_pos + 1..];
-            self.matches(repo, Some(tag))
-        } else {
-            self.matches(name, None)
+        // The tag follows the last ':' unless that ':' is the port of a registry host
+        match name.rsplit_once(':') {
+            Some((repo, tag)) if !tag.contains('/') => self.matches(repo, Some(tag)),
+            _ => self.matches(name, None),
         }
     }
 }
 
+impl Identifiable for Image {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.matches_name(name)
+    }
+}
+
 /// ImageLayer represents a layer of an image
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageLayer {
@@ -116,6 +146,14 @@ pub struct ImageConfig {
     pub architecture: String,
     /// Operating system
     pub os: String,
+    /// Signal to stop containers created from the image
+    pub stop_signal: Option<String>,
+    /// Health check of containers created from the image (`HEALTHCHECK`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<HealthConfig>,
+    /// Instructions run by builds that use the image as their base (`ONBUILD`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_build: Vec<String>,
 }
 
 impl Default for ImageConfig {
@@ -131,6 +169,9 @@ impl Default for ImageConfig {
             labels: HashMap::new(),
             architecture: "amd64".to_string(),
             os: "linux".to_string(),
+            stop_signal: None,
+            healthcheck: None,
+            on_build: Vec::new(),
         }
     }
 }
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+use super::BuildEvent;
+
+/// ProgressMessage is one line of progress reported while pulling, pushing or building an image
+/// (the image APIs return one JSON object per line)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProgressMessage {
+    /// Layer digest or build step the message is about
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Progress of the operation
+    #[serde(default)]
+    pub status: String,
+    /// Bytes of the layer transferred so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<u64>,
+    /// Size in bytes of the layer being transferred
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+    /// Set on the last message when the operation failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// ID of the resulting image, set on the last message when the operation succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<String>,
+    /// Structured event of a build (messages carrying only an event have an empty status)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<BuildEvent>,
+}
+
+impl ProgressMessage {
+    /// Create a message about the operation as a whole
+    pub fn status(status: impl Into<String>) -> Self {
+        ProgressMessage {
+            status: status.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a message about a layer or a build step
+    pub fn with_id(id: impl Into<String>, status: impl Into<String>) -> Self {
+        ProgressMessage {
+            id: Some(id.into()),
+            status: status.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a message carrying only a build event
+    pub fn event(event: BuildEvent) -> Self {
+        ProgressMessage {
+            event: Some(event),
+            ..Default::default()
+        }
+    }
+
+    /// Attach a build event to a message
+    pub fn with_event(mut self, event: BuildEvent) -> Self {
+        self.event = Some(event);
+        self
+    }
+
+    /// Add the number of bytes transferred out of the total to a message about a layer
+    pub fn with_progress(mut self, current: u64, total: u64) -> Self {
+        self.current = Some(current);
+        self.total = Some(total);
+        self
+    }
+
+    /// Create the last message of a failed operation
+    pub fn error(message: impl Into<String>) -> Self {
+        ProgressMessage {
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Create the last message of a successful operation
+    pub fn done(image_id: impl Into<String>) -> Self {
+        let image_id = image_id.into();
+        ProgressMessage {
+            status: format!("Image {}", image_id),
+            image_id: Some(image_id),
+            ..Default::default()
+        }
+    }
+}
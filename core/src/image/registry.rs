@@ -0,0 +1,166 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Registry used for image names without a registry host
+pub const DEFAULT_REGISTRY: &str = "docker.io";
+/// Host serving the registry API of the default registry
+pub const DEFAULT_REGISTRY_HOST: &str = "registry-1.docker.io";
+/// Header carrying the [`RegistryAuth`] of a pull or push
+pub const REGISTRY_AUTH_HEADER: &str = "X-Registry-Auth";
+/// Header carrying the [`RegistryAuth`]s of a build, by registry
+pub const REGISTRY_CONFIG_HEADER: &str = "X-Registry-Config";
+
+/// RegistryReference is an image name resolved to the registry and repository it is stored in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryReference {
+    /// Registry host with an optional port (`docker.io` for the default registry)
+    pub registry: String,
+    /// Repository in the registry (`library/nginx` for official images)
+    pub repository: String,
+    /// Tag (`latest` if neither a tag nor a digest is given)
+    pub tag: Option<String>,
+    /// Digest of the manifest
+    pub digest: Option<String>,
+}
+
+impl RegistryReference {
+    /// Parse `[registry/]repository[:tag][@digest]`
+    ///
+    /// The first path component is a registry if it contains a `.` or a `:`, or is `localhost`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let (rest, digest) = match name.split_once('@') {
+            Some((rest, digest)) => {
+                if !digest.starts_with("sha256:") {
+                    return Err(format!("Invalid digest in image name: {}", name));
+                }
+                (rest, Some(digest.to_string()))
+            }
+            None => (name, None),
+        };
+        // A tag comes after the last '/', unlike the ':' of a registry port
+        let (path, tag) = match rest.rsplit_once(':') {
+            Some((path, tag)) if !tag.contains('/') => (path, Some(tag.to_string())),
+            _ => (rest, None),
+        };
+
+        let (registry, repository) = match path.split_once('/') {
+            Some((first, repository)) if first.contains('.') || first.contains(':') || first == "localhost" => {
+                (first.to_string(), repository.to_string())
+            }
+            _ => (DEFAULT_REGISTRY.to_string(), path.to_string()),
+        };
+        if repository.is_empty()
+            || !repository
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-' | '/'))
+        {
+            return Err(format!("Invalid image name: {}", name));
+        }
+        if tag.as_deref() == Some("") {
+            return Err(format!("Invalid tag in image name: {}", name));
+        }
+
+        let repository = if registry == DEFAULT_REGISTRY && !repository.contains('/') {
+            format!("library/{}", repository)
+        } else {
+            repository
+        };
+        let tag = match (&tag, &digest) {
+            (None, None) => Some("latest".to_string()),
+            _ => tag,
+        };
+        Ok(RegistryReference {
+            registry,
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    /// Base URL of the registry API (plain HTTP for registries on the local host)
+    pub fn api_url(&self) -> String {
+        let host = if self.registry == DEFAULT_REGISTRY {
+            DEFAULT_REGISTRY_HOST
+        } else {
+            self.registry.as_str()
+        };
+        let local = ["localhost", "127.0.0.1", "[::1]"]
+            .iter()
+            .any(|local| host == *local || host.starts_with(&format!("{}:", local)));
+        format!("{}://{}/v2", if local { "http" } else { "https" }, host)
+    }
+
+    /// Digest, or else tag, to request the manifest with
+    pub fn reference(&self) -> &str {
+        self.digest
+            .as_deref()
+            .or(self.tag.as_deref())
+            .unwrap_or("latest")
+    }
+
+    /// Repository name as written by users (`nginx` instead of `docker.io/library/nginx`)
+    pub fn familiar_name(&self) -> String {
+        if self.registry != DEFAULT_REGISTRY {
+            return format!("{}/{}", self.registry, self.repository);
+        }
+        self.repository
+            .strip_prefix("library/")
+            .unwrap_or(&self.repository)
+            .to_string()
+    }
+}
+
+/// RegistryAuth holds the credentials the daemon uses to log in to a registry
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryAuth {
+    pub username: String,
+    /// Password or access token
+    pub password: String,
+    /// Registry the credentials are for (`docker.io` for the default registry)
+    #[serde(rename = "serveraddress", default)]
+    pub server_address: String,
+}
+
+impl RegistryAuth {
+    /// Encode as the value of the X-Registry-Auth header (URL-safe base64 of the JSON)
+    pub fn to_header(&self) -> String {
+        encode_header(self)
+    }
+
+    /// Decode the value of the X-Registry-Auth header
+    pub fn from_header(value: &str) -> Result<Self, String> {
+        decode_header(value)
+    }
+
+    /// Encode the credentials of several registries as the value of the X-Registry-Config header
+    pub fn to_config_header(auths: &HashMap<String, RegistryAuth>) -> String {
+        encode_header(auths)
+    }
+
+    /// Decode the value of the X-Registry-Config header
+    pub fn from_config_header(value: &str) -> Result<HashMap<String, RegistryAuth>, String> {
+        decode_header(value)
+    }
+}
+
+// The password is left out so that credentials never end up in logs
+impl std::fmt::Debug for RegistryAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistryAuth")
+            .field("username", &self.username)
+            .field("server_address", &self.server_address)
+            .finish_non_exhaustive()
+    }
+}
+
+fn encode_header<T: Serialize>(value: &T) -> String {
+    general_purpose::URL_SAFE.encode(serde_json::to_vec(value).unwrap_or_default())
+}
+
+fn decode_header<T: serde::de::DeserializeOwned>(value: &str) -> Result<T, String> {
+    let json = general_purpose::URL_SAFE
+        .decode(value.trim())
+        .map_err(|e| format!("Invalid registry credentials: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("Invalid registry credentials: {}", e))
+}
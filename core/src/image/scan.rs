@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// ImageScan is the result of scanning an image for known vulnerabilities, stored with the image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageScan {
+    /// Name of the scanner that produced the result
+    pub scanner: String,
+    /// Time when the scan finished
+    pub scanned_at: DateTime<Utc>,
+    /// Vulnerabilities found in the packages of the image
+    #[serde(default)]
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+impl ImageScan {
+    /// Number of vulnerabilities with the given severity (e.g. CRITICAL, HIGH)
+    pub fn count(&self, severity: &str) -> usize {
+        self.vulnerabilities
+            .iter()
+            .filter(|vulnerability| vulnerability.severity.eq_ignore_ascii_case(severity))
+            .count()
+    }
+}
+
+/// Vulnerability is a known vulnerability of an installed package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vulnerability {
+    /// Identifier such as CVE-2024-1234
+    pub id: String,
+    /// Name of the affected package
+    pub package: String,
+    /// Version of the package installed in the image
+    pub installed_version: String,
+    /// First version that fixes the vulnerability, if there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed_version: Option<String>,
+    /// Severity reported by the scanner (UNKNOWN, LOW, MEDIUM, HIGH or CRITICAL)
+    pub severity: String,
+    /// Short description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
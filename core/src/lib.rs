@@ -2,7 +2,11 @@ pub mod container;
 pub mod image;
 pub mod network;
 pub mod volume;
+pub mod secret;
+pub mod schedule;
+pub mod swarm;
 pub mod errors;
+pub mod events;
 pub mod utils;
 
 // Re-export modules
@@ -10,5 +14,9 @@ pub use crate::container::*;
 pub use crate::image::*;
 pub use crate::network::*;
 pub use crate::volume::*;
+pub use crate::secret::*;
+pub use crate::schedule::*;
+pub use crate::swarm::*;
 pub use crate::errors::*;
+pub use crate::events::*;
 pub use crate::utils::*; 
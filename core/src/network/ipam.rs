@@ -0,0 +1,114 @@
+use std::net::Ipv4Addr;
+
+use super::Network;
+use crate::errors::NetworkError;
+
+/// Parse an IPv4 CIDR such as `172.17.0.0/16` into its network address and prefix length
+pub fn parse_cidr(cidr: &str) -> Result<(Ipv4Addr, u8), NetworkError> {
+    let invalid = || NetworkError::InvalidConfig(format!("Invalid subnet: {}", cidr));
+
+    let (address, prefix_len) = cidr.split_once('/').ok_or_else(invalid)?;
+    let address: Ipv4Addr = address.parse().map_err(|_| invalid())?;
+    let prefix_len: u8 = prefix_len.parse().map_err(|_| invalid())?;
+    if prefix_len > 32 {
+        return Err(invalid());
+    }
+
+    Ok((Ipv4Addr::from(u32::from(address) & prefix_mask(prefix_len)), prefix_len))
+}
+
+/// Whether an address lies within an IPv4 CIDR
+pub fn cidr_contains(cidr: &str, address: Ipv4Addr) -> Result<bool, NetworkError> {
+    let (network, prefix_len) = parse_cidr(cidr)?;
+    Ok(u32::from(address) & prefix_mask(prefix_len) == u32::from(network))
+}
+
+/// Whether two IPv4 CIDRs share any address
+pub fn cidr_overlaps(a: &str, b: &str) -> Result<bool, NetworkError> {
+    let (a_network, a_len) = parse_cidr(a)?;
+    let (b_network, b_len) = parse_cidr(b)?;
+    let mask = prefix_mask(a_len.min(b_len));
+    Ok(u32::from(a_network) & mask == u32::from(b_network) & mask)
+}
+
+/// First usable host address of an IPv4 CIDR, used as the default gateway
+pub fn first_host(cidr: &str) -> Result<Ipv4Addr, NetworkError> {
+    let (network, _) = parse_cidr(cidr)?;
+    Ok(Ipv4Addr::from(u32::from(network) + 1))
+}
+
+/// MAC address derived from an IPv4 address, using the locally administered 02:42 prefix
+pub fn mac_address_for(address: Ipv4Addr) -> [u8; 6] {
+    let [a, b, c, d] = address.octets();
+    [0x02, 0x42, a, b, c, d]
+}
+
+/// Format a MAC address as colon separated hex
+pub fn format_mac_address(mac: &[u8; 6]) -> String {
+    mac.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
+}
+
+fn prefix_mask(prefix_len: u8) -> u32 {
+    if prefix_len == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix_len)
+    }
+}
+
+impl Network {
+    /// Allocate the lowest free address of the network's IP range (or subnet)
+    ///
+    /// The network and broadcast addresses, the gateway, addresses already assigned to
+    /// connected containers and `reserved` addresses are never handed out.
+    pub fn allocate_ip(&self, reserved: &[Ipv4Addr]) -> Result<Ipv4Addr, NetworkError> {
+        let (subnet, subnet_len) = parse_cidr(&self.config.subnet)?;
+        let (range, range_len) = match &self.config.ip_range {
+            Some(ip_range) => parse_cidr(ip_range)?,
+            None => (subnet, subnet_len),
+        };
+
+        let subnet_start = u32::from(subnet);
+        let subnet_end = subnet_start | !prefix_mask(subnet_len);
+        let range_start = u32::from(range).max(subnet_start + 1);
+        let range_end = (u32::from(range) | !prefix_mask(range_len)).min(subnet_end.saturating_sub(1));
+
+        let gateway = self.config.gateway.parse::<Ipv4Addr>().ok().map(u32::from);
+        let used: Vec<u32> = self
+            .containers
+            .values()
+            .filter_map(|c| c.ip_address.parse::<Ipv4Addr>().ok())
+            .chain(reserved.iter().copied())
+            .map(u32::from)
+            .collect();
+
+        (range_start..=range_end)
+            .find(|candidate| Some(*candidate) != gateway && !used.contains(candidate))
+            .map(Ipv4Addr::from)
+            .ok_or_else(|| NetworkError::IpAllocation(format!("No free addresses in {}", self.name)))
+    }
+
+    /// Check that a requested address can be assigned to a container on this network
+    pub fn reserve_ip(&self, address: Ipv4Addr, reserved: &[Ipv4Addr]) -> Result<Ipv4Addr, NetworkError> {
+        let (subnet, prefix_len) = parse_cidr(&self.config.subnet)?;
+        let broadcast = u32::from(subnet) | !prefix_mask(prefix_len);
+        if !cidr_contains(&self.config.subnet, address)? || address == subnet || u32::from(address) == broadcast {
+            return Err(NetworkError::IpAllocation(format!(
+                "{} is not a usable address in subnet {}",
+                address, self.config.subnet
+            )));
+        }
+
+        let in_use = self.config.gateway.parse::<Ipv4Addr>().ok() == Some(address)
+            || reserved.contains(&address)
+            || self
+                .containers
+                .values()
+                .any(|c| c.ip_address.parse::<Ipv4Addr>().ok() == Some(address));
+        if in_use {
+            return Err(NetworkError::IpAllocation(format!("Address already in use: {}", address)));
+        }
+
+        Ok(address)
+    }
+}
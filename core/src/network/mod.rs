@@ -3,6 +3,11 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::utils::Identifiable;
+
+mod ipam;
+pub use ipam::*;
+
 /// Network represents a container network
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Network {
@@ -37,6 +42,16 @@ impl Network {
     }
 }
 
+impl Identifiable for Network {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name
+    }
+}
+
 /// Network driver
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum NetworkDriver {
@@ -50,6 +65,23 @@ pub enum NetworkDriver {
     Overlay,
     /// Macvlan network
     Macvlan,
+    /// Driver registered with the daemon under this name
+    Plugin(String),
+}
+
+impl NetworkDriver {
+    /// Parse a driver name (names other than the built-in drivers refer to plugins)
+    pub fn parse(driver: &str) -> Result<Self, String> {
+        match driver {
+            "bridge" => Ok(NetworkDriver::Bridge),
+            "host" => Ok(NetworkDriver::Host),
+            "none" => Ok(NetworkDriver::None),
+            "overlay" => Ok(NetworkDriver::Overlay),
+            "macvlan" => Ok(NetworkDriver::Macvlan),
+            "" => Err("Network driver name must not be empty".to_string()),
+            _ => Ok(NetworkDriver::Plugin(driver.to_string())),
+        }
+    }
 }
 
 impl std::fmt::Display for NetworkDriver {
@@ -60,6 +92,7 @@ impl std::fmt::Display for NetworkDriver {
             NetworkDriver::None => "none",
             NetworkDriver::Overlay => "overlay",
             NetworkDriver::Macvlan => "macvlan",
+            NetworkDriver::Plugin(name) => name,
         };
         write!(f, "{}", driver_str)
     }
@@ -0,0 +1,222 @@
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+use crate::errors::ScheduleError;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Years searched for the next matching time before giving up (e.g. `0 0 30 2 *` never matches)
+const SEARCH_YEARS: i32 = 5;
+
+/// Cron expression: `minute hour day-of-month month day-of-week`, or one of `@yearly` (`@annually`),
+/// `@monthly`, `@weekly`, `@daily` (`@midnight`) and `@hourly`
+///
+/// Fields take `*`, values, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `0-30/10`); months and
+/// weekdays also take their first three letters, and weekday 7 is Sunday like 0. As in cron, a time
+/// matches when either day field matches if both are restricted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // 日と曜日のどちらかが * で始まれば両方を満たす時刻、どちらも指定されていればいずれかを満たす時刻にする
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl CronExpr {
+    /// Parse a cron expression
+    pub fn parse(expr: &str) -> Result<Self, ScheduleError> {
+        let expr = expr.trim();
+        let expanded = match expr {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            _ if expr.starts_with('@') => {
+                return Err(ScheduleError::Invalid(format!("Unsupported cron expression: {}", expr)))
+            }
+            _ => expr,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(ScheduleError::Invalid(format!(
+                "Invalid cron expression {:?} (expected 5 fields: minute hour day month weekday)",
+                expr
+            )));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES, 0)?;
+        // 7 も日曜日
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(CronExpr {
+            minutes: parse_field(fields[0], 0, 59, &[], 0)?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)?,
+            days: parse_field(fields[2], 1, 31, &[], 0)?,
+            months: parse_field(fields[3], 1, 12, &MONTH_NAMES, 1)?,
+            weekdays,
+            days_restricted: !fields[2].starts_with('*'),
+            weekdays_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// First matching time after `after` (the minute after it at the earliest), in the time zone of `after`
+    ///
+    /// Times skipped by a daylight saving change are not run, and a time repeated by one runs once.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut time = start;
+        while time.year() <= start.year() + SEARCH_YEARS {
+            if !matches(self.months, time.month()) {
+                time = first_of_next_month(time)?;
+                continue;
+            }
+            if !self.matches_day(time.date()) {
+                time = time.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !matches(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !matches(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+                continue;
+            }
+            match after.timezone().from_local_datetime(&time) {
+                LocalResult::Single(next) | LocalResult::Ambiguous(next, _) => return Some(next),
+                LocalResult::None => time += Duration::minutes(1),
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = matches(self.days, date.day());
+        let weekday = matches(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+}
+
+fn matches(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+fn first_of_next_month(time: NaiveDateTime) -> Option<NaiveDateTime> {
+    let (year, month) = if time.month() == 12 {
+        (time.year() + 1, 1)
+    } else {
+        (time.year(), time.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)
+}
+
+// フィールドを値のビット集合にする（names[i] は first + i の名前）
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], first: u32) -> Result<u64, ScheduleError> {
+    let invalid = || ScheduleError::Invalid(format!("Invalid cron field {:?} (values are {}-{})", field, min, max));
+    let value = |s: &str| -> Result<u32, ScheduleError> {
+        let value = match names.iter().position(|name| name.eq_ignore_ascii_case(s)) {
+            Some(index) => index as u32 + first,
+            None => s.parse().map_err(|_| invalid())?,
+        };
+        if value < min || value > max {
+            return Err(invalid());
+        }
+        Ok(value)
+    };
+
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0).ok_or_else(invalid)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (value(start)?, value(end)?),
+            // 5/10 は 5 から最大値まで 10 ごと
+            None if part.contains('/') => (value(range)?, max),
+            None => (value(range)?, value(range)?),
+        };
+        if start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expr: &str, after: &str) -> Option<String> {
+        CronExpr::parse(expr).unwrap().next_after(&at(after)).map(|next| next.to_rfc3339())
+    }
+
+    #[test]
+    fn next_run_is_at_least_a_minute_later() {
+        assert_eq!(next("* * * * *", "2024-01-01T10:00:30Z").unwrap(), "2024-01-01T10:01:00+00:00");
+        assert_eq!(next("30 10 * * *", "2024-01-01T10:30:00Z").unwrap(), "2024-01-02T10:30:00+00:00");
+        assert_eq!(next("*/15 * * * *", "2024-01-01T10:16:00Z").unwrap(), "2024-01-01T10:30:00+00:00");
+    }
+
+    #[test]
+    fn rolls_over_months_and_years() {
+        assert_eq!(next("0 0 1 * *", "2024-01-31T12:00:00Z").unwrap(), "2024-02-01T00:00:00+00:00");
+        assert_eq!(next("@yearly", "2024-06-01T00:00:00Z").unwrap(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(next("0 0 29 feb *", "2024-03-01T00:00:00Z").unwrap(), "2028-02-29T00:00:00+00:00");
+        assert_eq!(next("0 0 30 2 *", "2024-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_restricted() {
+        // 2024-01-01 は月曜日
+        assert_eq!(next("0 0 15 * fri", "2024-01-01T00:00:00Z").unwrap(), "2024-01-05T00:00:00+00:00");
+        assert_eq!(next("0 0 * * fri", "2024-01-01T00:00:00Z").unwrap(), "2024-01-05T00:00:00+00:00");
+        assert_eq!(next("0 0 */10 * *", "2024-01-01T00:00:00Z").unwrap(), "2024-01-11T00:00:00+00:00");
+    }
+
+    #[test]
+    fn weekday_seven_is_sunday() {
+        assert_eq!(CronExpr::parse("0 0 * * 7").unwrap(), CronExpr::parse("0 0 * * 0").unwrap());
+        assert_eq!(CronExpr::parse("0 0 * * 5-7").unwrap(), CronExpr::parse("0 0 * * 0,fri,SAT").unwrap());
+        assert_eq!(CronExpr::parse("@weekly").unwrap(), CronExpr::parse("0 0 * * sun").unwrap());
+    }
+
+    #[test]
+    fn parses_lists_ranges_and_steps() {
+        assert_eq!(parse_field("1,15", 0, 59, &[], 0).unwrap(), (1 << 1) | (1 << 15));
+        assert_eq!(parse_field("0-30/10", 0, 59, &[], 0).unwrap(), 1 | (1 << 10) | (1 << 20) | (1 << 30));
+        assert_eq!(parse_field("50/5", 0, 59, &[], 0).unwrap(), (1 << 50) | (1 << 55));
+        assert_eq!(parse_field("jan-mar", 1, 12, &MONTH_NAMES, 1).unwrap(), 0b1110);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expr in ["", "* * * *", "* * * * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "* * * 13 *", "* * * * 8"] {
+            assert!(CronExpr::parse(expr).is_err(), "{:?}", expr);
+        }
+        for expr in ["*/0 * * * *", "5-1 * * * *", "x * * * *", "@reboot"] {
+            assert!(CronExpr::parse(expr).is_err(), "{:?}", expr);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::container::ContainerConfig;
+use crate::errors::ScheduleError;
+use crate::utils::Identifiable;
+
+mod cron;
+pub use cron::*;
+
+/// Label with the schedule ID, set on the containers of its runs
+pub const SCHEDULE_LABEL: &str = "com.rocker.schedule";
+
+/// Finished runs kept in the history of a schedule when not given
+pub const DEFAULT_SCHEDULE_HISTORY: usize = 10;
+
+/// What a schedule does when a run is due while an earlier run is still running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlapPolicy {
+    /// Start the new run alongside the running ones
+    Allow,
+    /// Skip the new run
+    #[default]
+    Forbid,
+    /// Stop the running ones, then start the new run
+    Replace,
+}
+
+impl std::fmt::Display for OverlapPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverlapPolicy::Allow => write!(f, "allow"),
+            OverlapPolicy::Forbid => write!(f, "forbid"),
+            OverlapPolicy::Replace => write!(f, "replace"),
+        }
+    }
+}
+
+impl std::str::FromStr for OverlapPolicy {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(OverlapPolicy::Allow),
+            "forbid" => Ok(OverlapPolicy::Forbid),
+            "replace" => Ok(OverlapPolicy::Replace),
+            _ => Err(ScheduleError::Invalid(format!(
+                "Invalid overlap policy {:?} (expected allow, forbid or replace)",
+                s
+            ))),
+        }
+    }
+}
+
+/// State of a run of a schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    /// The container of the run is running
+    Running,
+    /// The container exited with code 0
+    Succeeded,
+    /// The container exited with another code, or could not be created or started
+    Failed,
+    /// The run was not started because an earlier run was still running
+    Skipped,
+}
+
+impl std::fmt::Display for RunState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunState::Running => write!(f, "running"),
+            RunState::Succeeded => write!(f, "succeeded"),
+            RunState::Failed => write!(f, "failed"),
+            RunState::Skipped => write!(f, "skipped"),
+        }
+    }
+}
+
+/// Run of a schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    /// Time the run was due
+    pub scheduled_at: DateTime<Utc>,
+    /// State of the run
+    pub state: RunState,
+    /// ID of the container of the run (none if it was skipped or could not be created)
+    pub container_id: Option<String>,
+    /// Name of the container of the run
+    pub container_name: Option<String>,
+    /// Time the container was started
+    pub started_at: Option<DateTime<Utc>>,
+    /// Time the container exited
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Exit code of the container
+    pub exit_code: Option<i32>,
+    /// Why the run was skipped, failed to start or was stopped
+    pub error: Option<String>,
+}
+
+impl ScheduleRun {
+    /// Run whose container was started
+    pub fn started(scheduled_at: DateTime<Utc>, container_id: String, container_name: String) -> Self {
+        ScheduleRun {
+            scheduled_at,
+            state: RunState::Running,
+            container_id: Some(container_id),
+            container_name: Some(container_name),
+            started_at: Some(Utc::now()),
+            finished_at: None,
+            exit_code: None,
+            error: None,
+        }
+    }
+
+    /// Run that did not start, `Skipped` or `Failed`
+    pub fn not_started(scheduled_at: DateTime<Utc>, state: RunState, error: String) -> Self {
+        ScheduleRun {
+            scheduled_at,
+            state,
+            container_id: None,
+            container_name: None,
+            started_at: None,
+            finished_at: None,
+            exit_code: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Schedule creating a container from `config` each time the cron expression matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// Schedule ID (UUID)
+    pub id: String,
+    /// Schedule name, also the prefix of the names of its containers
+    pub name: String,
+    /// Cron expression, evaluated in the daemon's time zone
+    pub cron: String,
+    /// Configuration of the containers of the runs
+    pub config: ContainerConfig,
+    /// What to do when a run is due while an earlier run is still running
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// Finished runs kept in the history; the containers of older runs are removed
+    pub history_limit: usize,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+    /// Time of the next run
+    pub next_run: Option<DateTime<Utc>>,
+    /// Runs, oldest first
+    #[serde(default)]
+    pub history: Vec<ScheduleRun>,
+}
+
+impl Schedule {
+    /// Create a new schedule without runs
+    pub fn new(name: String, cron: String, config: ContainerConfig, overlap: OverlapPolicy, history_limit: usize) -> Self {
+        Schedule {
+            id: Uuid::new_v4().to_string(),
+            name,
+            cron,
+            config,
+            overlap,
+            history_limit,
+            created_at: Utc::now(),
+            next_run: None,
+            history: Vec::new(),
+        }
+    }
+
+    /// The most recent run
+    pub fn last_run(&self) -> Option<&ScheduleRun> {
+        self.history.last()
+    }
+
+    /// IDs of the containers of the runs that are running
+    pub fn running_containers(&self) -> Vec<String> {
+        self.history
+            .iter()
+            .filter(|run| run.state == RunState::Running)
+            .filter_map(|run| run.container_id.clone())
+            .collect()
+    }
+}
+
+impl Identifiable for Schedule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name
+    }
+}
+
+/// Body of `POST /schedules/create`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleCreateRequest {
+    /// Schedule name (the short ID when empty)
+    #[serde(default)]
+    pub name: String,
+    /// Cron expression
+    pub cron: String,
+    /// Configuration of the containers of the runs
+    pub config: ContainerConfig,
+    /// What to do when a run is due while an earlier run is still running
+    #[serde(default)]
+    pub overlap: OverlapPolicy,
+    /// Finished runs kept in the history (`DEFAULT_SCHEDULE_HISTORY` when not given)
+    #[serde(default)]
+    pub history_limit: Option<usize>,
+}
@@ -0,0 +1,173 @@
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::utils::Identifiable;
+
+/// Directory inside containers that secrets with a relative target are mounted in
+pub const SECRETS_DIR: &str = "/run/secrets";
+
+/// Largest secret the daemon accepts (500 KiB)
+pub const MAX_SECRET_SIZE: usize = 500 * 1024;
+
+/// Default permissions of the file a secret is mounted as
+pub const DEFAULT_SECRET_MODE: u32 = 0o444;
+
+/// Secret kept encrypted by the daemon; the data itself is never returned by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Secret {
+    /// Secret ID (UUID)
+    pub id: String,
+    /// Secret name
+    pub name: String,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+    /// Size of the data in bytes
+    pub size: usize,
+    /// Labels
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+impl Secret {
+    /// Create the metadata of a new secret
+    pub fn new(name: String, size: usize, labels: HashMap<String, String>) -> Self {
+        Secret {
+            id: Uuid::new_v4().to_string(),
+            name,
+            created_at: Utc::now(),
+            size,
+            labels,
+        }
+    }
+}
+
+impl Identifiable for Secret {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name
+    }
+}
+
+/// Body of `POST /secrets/create`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecretCreateRequest {
+    /// Secret name
+    pub name: String,
+    /// Secret data, base64-encoded
+    pub data: String,
+    /// Labels
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+}
+
+impl SecretCreateRequest {
+    /// Build a request carrying `data`
+    pub fn new(name: &str, data: &[u8], labels: HashMap<String, String>) -> Self {
+        SecretCreateRequest {
+            name: name.to_string(),
+            data: general_purpose::STANDARD.encode(data),
+            labels,
+        }
+    }
+
+    /// Decoded secret data
+    pub fn decode_data(&self) -> Result<Vec<u8>, String> {
+        general_purpose::STANDARD
+            .decode(&self.data)
+            .map_err(|e| format!("Invalid secret data (expected base64): {}", e))
+    }
+}
+
+/// Secret mounted into a container (`--secret`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretReference {
+    /// ID or name of the secret
+    pub source: String,
+    /// File name under `/run/secrets`, or an absolute path in the container
+    pub target: String,
+    /// Owner of the file
+    pub uid: u32,
+    /// Group of the file
+    pub gid: u32,
+    /// Permissions of the file
+    pub mode: u32,
+}
+
+impl SecretReference {
+    /// Reference mounting `source` as `/run/secrets/<source>`, readable by everyone in the container
+    pub fn new(source: &str) -> Self {
+        SecretReference {
+            source: source.to_string(),
+            target: source.to_string(),
+            uid: 0,
+            gid: 0,
+            mode: DEFAULT_SECRET_MODE,
+        }
+    }
+
+    /// Parse `name` or `source=name[,target=path][,uid=N][,gid=N][,mode=0400]`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if !spec.contains('=') {
+            let reference = SecretReference::new(spec);
+            reference.validate()?;
+            return Ok(reference);
+        }
+
+        let mut options = HashMap::new();
+        for option in spec.split(',') {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid --secret option (expected key=value): {}", option))?;
+            options.insert(key, value);
+        }
+        let source = options
+            .remove("source")
+            .or_else(|| options.remove("src"))
+            .ok_or_else(|| format!("Invalid --secret: source is required: {}", spec))?;
+        let mut reference = SecretReference::new(source);
+        for (key, value) in options {
+            let number = |radix| {
+                u32::from_str_radix(value, radix).map_err(|_| format!("Invalid {} for --secret: {}", key, value))
+            };
+            match key {
+                "target" => reference.target = value.to_string(),
+                "uid" => reference.uid = number(10)?,
+                "gid" => reference.gid = number(10)?,
+                "mode" => reference.mode = number(8)?,
+                _ => return Err(format!("Unknown --secret option: {}", key)),
+            }
+        }
+        reference.validate()?;
+        Ok(reference)
+    }
+
+    /// Check the source and the target path
+    pub fn validate(&self) -> Result<(), String> {
+        if self.source.is_empty() {
+            return Err("Secret source must not be empty".to_string());
+        }
+        let target = self.target_path();
+        if target == SECRETS_DIR || target.split('/').any(|part| part == "..") || target.ends_with('/') {
+            return Err(format!("Invalid secret target: {}", self.target));
+        }
+        if self.mode > 0o777 {
+            return Err(format!("Invalid secret mode: {:o}", self.mode));
+        }
+        Ok(())
+    }
+
+    /// Path of the file in the container
+    pub fn target_path(&self) -> String {
+        if self.target.starts_with('/') {
+            self.target.clone()
+        } else {
+            format!("{}/{}", SECRETS_DIR, self.target)
+        }
+    }
+}
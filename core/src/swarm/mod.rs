@@ -0,0 +1,323 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::container::ContainerConfig;
+use crate::errors::SwarmError;
+
+/// Port the manager listens on for nodes joining and reporting their tasks
+pub const DEFAULT_SWARM_PORT: u16 = 2377;
+
+/// Label with the service name, set on the containers running its tasks
+pub const SERVICE_LABEL: &str = "com.rocker.swarm.service";
+/// Label with the task ID, set on the container running the task
+pub const TASK_LABEL: &str = "com.rocker.swarm.task";
+/// Label set on the overlay networks created for services
+pub const SWARM_NETWORK_LABEL: &str = "com.rocker.swarm.network";
+/// Service label with the name of the stack that deployed the service
+pub const STACK_LABEL: &str = "com.rocker.stack.namespace";
+
+/// Role of a node in the swarm
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+    /// Keeps the desired state and schedules tasks (it also runs tasks)
+    Manager,
+    /// Runs the tasks the manager assigns to it
+    Worker,
+}
+
+impl std::fmt::Display for NodeRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeRole::Manager => write!(f, "manager"),
+            NodeRole::Worker => write!(f, "worker"),
+        }
+    }
+}
+
+/// Whether the manager hears from a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeStatus {
+    /// The node reported recently and gets tasks
+    Ready,
+    /// The node stopped reporting, its tasks are moved to other nodes
+    Down,
+}
+
+impl std::fmt::Display for NodeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeStatus::Ready => write!(f, "ready"),
+            NodeStatus::Down => write!(f, "down"),
+        }
+    }
+}
+
+/// Whether the scheduler places tasks on a node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeAvailability {
+    /// The node gets new tasks
+    #[default]
+    Active,
+    /// The node keeps its tasks but gets no new ones
+    Pause,
+    /// The node's tasks are moved to other nodes and it gets no new ones
+    Drain,
+}
+
+impl std::fmt::Display for NodeAvailability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeAvailability::Active => write!(f, "active"),
+            NodeAvailability::Pause => write!(f, "pause"),
+            NodeAvailability::Drain => write!(f, "drain"),
+        }
+    }
+}
+
+impl std::str::FromStr for NodeAvailability {
+    type Err = SwarmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(NodeAvailability::Active),
+            "pause" => Ok(NodeAvailability::Pause),
+            "drain" => Ok(NodeAvailability::Drain),
+            _ => Err(SwarmError::InvalidConfig(format!(
+                "Invalid availability {:?} (expected active, pause or drain)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Node of the swarm, as known by the manager
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    /// Node ID, also the common name of its certificate
+    pub id: String,
+    /// Host name of the node
+    pub hostname: String,
+    /// Role of the node
+    pub role: NodeRole,
+    /// Address other nodes reach the node at (the host of it is used for overlay networks)
+    pub address: String,
+    /// Status of the node
+    pub status: NodeStatus,
+    /// Whether the node gets tasks
+    #[serde(default)]
+    pub availability: NodeAvailability,
+    /// Labels set by the operator, matched by placement constraints
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Index of the node, used to give each node its own range of overlay network addresses
+    pub index: u32,
+    /// Time the node joined
+    pub joined_at: DateTime<Utc>,
+    /// Last time the node reported
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Body of `POST /nodes/{id}/update`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeUpdate {
+    /// New availability of the node
+    #[serde(default)]
+    pub availability: Option<NodeAvailability>,
+    /// Labels to add or change
+    #[serde(default)]
+    pub labels_add: HashMap<String, String>,
+    /// Keys of the labels to remove
+    #[serde(default)]
+    pub labels_rm: Vec<String>,
+}
+
+/// Placement constraint of a service, `<attribute>==<value>` or `<attribute>!=<value>`
+///
+/// The attributes are `node.id`, `node.hostname`, `node.role` and `node.labels.<key>`. A node without
+/// the label does not match `==` and matches `!=`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    attribute: String,
+    equal: bool,
+    value: String,
+}
+
+impl Constraint {
+    pub fn parse(constraint: &str) -> Result<Self, SwarmError> {
+        let invalid = || SwarmError::InvalidConfig(format!("Invalid constraint: {:?}", constraint));
+        let (attribute, value, equal) = if let Some((attribute, value)) = constraint.split_once("==") {
+            (attribute, value, true)
+        } else if let Some((attribute, value)) = constraint.split_once("!=") {
+            (attribute, value, false)
+        } else {
+            return Err(invalid());
+        };
+        let attribute = attribute.trim();
+        let known = matches!(attribute, "node.id" | "node.hostname" | "node.role")
+            || attribute.strip_prefix("node.labels.").is_some_and(|key| !key.is_empty());
+        if !known {
+            return Err(SwarmError::InvalidConfig(format!(
+                "Invalid constraint {:?}: unknown attribute {:?} (expected node.id, node.hostname, node.role or node.labels.<key>)",
+                constraint, attribute
+            )));
+        }
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Constraint {
+            attribute: attribute.to_string(),
+            equal,
+            value: value.to_string(),
+        })
+    }
+
+    /// Whether tasks with this constraint may run on the node
+    pub fn matches(&self, node: &Node) -> bool {
+        let actual = match self.attribute.as_str() {
+            "node.id" => Some(node.id.clone()),
+            "node.hostname" => Some(node.hostname.clone()),
+            "node.role" => Some(node.role.to_string()),
+            attribute => attribute
+                .strip_prefix("node.labels.")
+                .and_then(|key| node.labels.get(key).cloned()),
+        };
+        (actual.as_deref() == Some(self.value.as_str())) == self.equal
+    }
+}
+
+/// What a service runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceSpec {
+    /// Service name, unique in the swarm
+    pub name: String,
+    /// Number of tasks to run
+    pub replicas: u64,
+    /// Configuration of the containers running the tasks
+    pub template: ContainerConfig,
+    /// Overlay network the tasks are attached to, created across the swarm when first used
+    #[serde(default)]
+    pub network: Option<String>,
+    /// Placement constraints the nodes running the tasks must satisfy (see [`Constraint`])
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<String>,
+    /// Labels of the service itself (the containers get the template's labels)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub labels: HashMap<String, String>,
+}
+
+/// Service of the swarm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Service {
+    /// Service ID
+    pub id: String,
+    /// Desired state of the service
+    pub spec: ServiceSpec,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+    /// Last time the spec changed
+    pub updated_at: DateTime<Utc>,
+    /// Number of tasks reported as running, filled in by the manager
+    #[serde(default)]
+    pub running: u64,
+    /// Tasks of the service, only filled in when inspecting it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<Task>,
+}
+
+/// State of a task as reported by the node running it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    /// No ready node satisfies the service's constraints
+    Pending,
+    /// The task is assigned to a node that has not started it yet
+    Assigned,
+    /// The container of the task is running
+    Running,
+    /// The container of the task could not be started or exited
+    Failed,
+}
+
+impl std::fmt::Display for TaskState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskState::Pending => write!(f, "pending"),
+            TaskState::Assigned => write!(f, "assigned"),
+            TaskState::Running => write!(f, "running"),
+            TaskState::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// One replica of a service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Task {
+    /// Task ID
+    pub id: String,
+    /// ID of the service
+    pub service_id: String,
+    /// Name of the service
+    pub service_name: String,
+    /// Replica number, starting at 1
+    pub slot: u64,
+    /// Node the task is assigned to
+    pub node_id: Option<String>,
+    /// State reported by the node
+    pub state: TaskState,
+    /// ID of the container running the task on the node
+    #[serde(default)]
+    pub container_id: Option<String>,
+    /// Why the task failed
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Last time the state changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Task {
+    /// Name of the container running the task (`<service>.<slot>.<task ID prefix>`)
+    pub fn container_name(&self) -> String {
+        format!("{}.{}.{}", self.service_name, self.slot, &self.id[..12.min(self.id.len())])
+    }
+}
+
+/// Swarm state of the daemon, returned by `GET /swarm`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwarmInfo {
+    /// ID of this node
+    pub node_id: String,
+    /// Role of this node
+    pub role: NodeRole,
+    /// Address of the manager
+    pub manager_address: String,
+    /// Token for other nodes to join with, only returned by the manager (`POST /swarm/rotate-token`
+    /// replaces it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join_token: Option<String>,
+}
+
+/// Body of `POST /swarm/init`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SwarmInitRequest {
+    /// Address to listen on for nodes (default `0.0.0.0:2377`)
+    #[serde(default)]
+    pub listen_address: Option<String>,
+    /// Address other nodes reach this node at (IP, with the listen port when no port is given)
+    pub advertise_address: String,
+}
+
+/// Body of `POST /swarm/join`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SwarmJoinRequest {
+    /// Address of the manager (the swarm port when no port is given)
+    pub remote_address: String,
+    /// Join token printed by `swarm init`
+    pub token: String,
+    /// Address other nodes reach this node at
+    pub advertise_address: String,
+}
@@ -0,0 +1,92 @@
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use super::digest::{HashingWriter, STREAM_BUFFER_SIZE};
+
+/// Chunks the blocking packer of [`pack_dir_async`] may get ahead of the writer
+const CHUNKS_IN_FLIGHT: usize = 8;
+
+/// Write the contents of `dir` to `writer` as an uncompressed tar
+///
+/// Entries are sorted by name and symlinks are stored as links, so the same tree always gives the same
+/// archive (and the same digest). The names are relative to `dir`, without a leading `./`.
+pub fn pack_dir<W: Write>(dir: &Path, writer: W) -> std::io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+    append_dir(&mut builder, dir, Path::new(""))?;
+    builder.into_inner()
+}
+
+fn append_dir<W: Write>(builder: &mut tar::Builder<W>, dir: &Path, prefix: &Path) -> std::io::Result<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = prefix.join(entry.file_name());
+        builder.append_path_with_name(entry.path(), &name)?;
+        if entry.file_type()?.is_dir() {
+            append_dir(builder, &entry.path(), &name)?;
+        }
+    }
+    Ok(())
+}
+
+/// SHA256 digest and size of the tar [`pack_dir`] writes for `dir`, without storing the tar
+pub fn calculate_dir_hash(dir: &Path) -> std::io::Result<(String, u64)> {
+    let writer = pack_dir(dir, HashingWriter::new(std::io::sink()))?;
+    let (_, digest, size) = writer.finish();
+    Ok((digest, size))
+}
+
+/// [`calculate_dir_hash`] on the blocking thread pool
+pub async fn hash_dir(dir: impl Into<PathBuf>) -> std::io::Result<(String, u64)> {
+    let dir = dir.into();
+    tokio::task::spawn_blocking(move || calculate_dir_hash(&dir))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Stream the tar [`pack_dir`] writes for `dir` into an async writer
+///
+/// The tree is read on the blocking thread pool in [`STREAM_BUFFER_SIZE`] chunks while earlier chunks are
+/// written, so that large trees neither block the runtime nor need to fit in memory.
+pub async fn pack_dir_async<W: AsyncWrite + Unpin>(dir: impl Into<PathBuf>, mut writer: W) -> std::io::Result<W> {
+    let dir = dir.into();
+    let (chunks, mut received) = mpsc::channel(CHUNKS_IN_FLIGHT);
+    let packer = tokio::task::spawn_blocking(move || {
+        let writer = BufWriter::with_capacity(STREAM_BUFFER_SIZE, ChunkWriter { chunks });
+        pack_dir(&dir, writer)?.into_inner().map_err(|e| e.into_error())?;
+        Ok::<_, std::io::Error>(())
+    });
+    while let Some(chunk) = received.recv().await {
+        // Returning drops the receiver, which stops the packer with a broken pipe
+        writer.write_all(&chunk).await?;
+    }
+    packer.await.map_err(std::io::Error::other)??;
+    writer.flush().await?;
+    Ok(writer)
+}
+
+/// The tar [`pack_dir`] writes for `dir`, for build contexts and other trees sent in one request
+pub async fn archive_dir(dir: impl Into<PathBuf>) -> std::io::Result<Vec<u8>> {
+    pack_dir_async(dir, Vec::new()).await
+}
+
+/// Hands the chunks of the blocking packer to the async side of pack_dir_async
+struct ChunkWriter {
+    chunks: mpsc::Sender<Vec<u8>>,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.chunks.blocking_send(buf.to_vec()).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::BrokenPipe, "The archive writer stopped")
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
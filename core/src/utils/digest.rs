@@ -0,0 +1,100 @@
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/// Size of the buffers used to stream files into digests and archives
+pub const STREAM_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Writer passing bytes through to `inner` while computing their SHA256 and counting them
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: Sha256::new(),
+            size: 0,
+        }
+    }
+
+    /// The inner writer, the `sha256:<hex>` digest and the number of bytes written
+    pub fn finish(self) -> (W, String, u64) {
+        (self.inner, format!("sha256:{:x}", self.hasher.finalize()), self.size)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader computing the SHA256 of the bytes read from `inner` and counting them
+pub struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<R: Read> HashingReader<R> {
+    pub fn new(inner: R) -> Self {
+        HashingReader {
+            inner,
+            hasher: Sha256::new(),
+            size: 0,
+        }
+    }
+
+    /// The inner reader, the `sha256:<hex>` digest and the number of bytes read
+    pub fn finish(self) -> (R, String, u64) {
+        (self.inner, format!("sha256:{:x}", self.hasher.finalize()), self.size)
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+}
+
+/// SHA256 digest (`sha256:<hex>`) and size of everything `reader` returns, read in
+/// [`STREAM_BUFFER_SIZE`] chunks
+pub fn calculate_reader_hash<R: Read>(mut reader: R) -> std::io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; STREAM_BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buffer[..n]);
+        size += n as u64;
+    }
+    Ok((format!("sha256:{:x}", hasher.finalize()), size))
+}
+
+/// SHA256 digest and size of a file, computed on the blocking thread pool so that async callers do not
+/// stall their runtime
+pub async fn hash_file(path: impl Into<PathBuf>) -> std::io::Result<(String, u64)> {
+    let path = path.into();
+    tokio::task::spawn_blocking(move || calculate_reader_hash(std::fs::File::open(path)?))
+        .await
+        .map_err(std::io::Error::other)?
+}
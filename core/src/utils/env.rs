@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Variables of an env file, in file order
+///
+/// Lines are `KEY=VALUE`, or `KEY` alone to take the value from the environment of this process (the
+/// line is skipped when it is unset there). Blank lines and lines starting with `#` are ignored, an
+/// `export ` prefix is dropped and so are quotes around the value.
+pub fn parse_env_file(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let var = match line.split_once('=') {
+            Some((key, value)) => {
+                let value = value.trim();
+                let value = ['"', '\'']
+                    .iter()
+                    .find_map(|quote| value.strip_prefix(*quote).and_then(|v| v.strip_suffix(*quote)))
+                    .unwrap_or(value);
+                env_var(key.trim(), Some(value))
+            }
+            None => env_var(line, None),
+        };
+        match var {
+            Ok(Some(var)) => vars.push(var),
+            Ok(None) => {}
+            Err(_) => return Err(format!("Invalid variable on line {}: {}", index + 1, line)),
+        }
+    }
+    Ok(vars)
+}
+
+/// Parse one `-e` value: `KEY=VALUE`, or `KEY` to take the value from the environment of this process
+/// (`None` when it is unset there)
+pub fn parse_env_var(entry: &str) -> Result<Option<(String, String)>, String> {
+    let var = match entry.split_once('=') {
+        Some((key, value)) => env_var(key, Some(value)),
+        None => env_var(entry, None),
+    };
+    var.map_err(|_| format!("Invalid environment variable: {}", entry))
+}
+
+/// Environment of a container from `--env-file` files and `-e` values
+///
+/// Files are read in order and a later file overrides an earlier one, then `-e` values override the
+/// files, so `--env-file defaults.env -e DEBUG=1` changes only DEBUG.
+pub fn resolve_env<P: AsRef<Path>>(env_files: &[P], env: &[String]) -> Result<HashMap<String, String>, String> {
+    let mut vars = HashMap::new();
+    for path in env_files {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read env file {}: {}", path.display(), e))?;
+        let file_vars = parse_env_file(&content).map_err(|e| format!("Invalid env file {}: {}", path.display(), e))?;
+        vars.extend(file_vars);
+    }
+    for entry in env {
+        if let Some((key, value)) = parse_env_var(entry)? {
+            vars.insert(key, value);
+        }
+    }
+    Ok(vars)
+}
+
+/// Err when the key is empty or contains whitespace
+fn env_var(key: &str, value: Option<&str>) -> Result<Option<(String, String)>, ()> {
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return Err(());
+    }
+    let value = match value {
+        Some(value) => value.to_string(),
+        None => match std::env::var(key) {
+            Ok(value) => value,
+            Err(_) => return Ok(None),
+        },
+    };
+    Ok(Some((key.to_string(), value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let content = "# database\n\nDB_HOST=db\n   \n  # indented comment\nDB_PORT = 5432\n";
+        assert_eq!(
+            parse_env_file(content).unwrap(),
+            vec![("DB_HOST".to_string(), "db".to_string()), ("DB_PORT".to_string(), "5432".to_string())]
+        );
+    }
+
+    #[test]
+    fn strips_quotes_and_export() {
+        let content = "export A=\"quoted value\"\nB='single'\nC=\"unbalanced\nD=a=b\nE=";
+        let vars: HashMap<_, _> = parse_env_file(content).unwrap().into_iter().collect();
+        assert_eq!(vars["A"], "quoted value");
+        assert_eq!(vars["B"], "single");
+        assert_eq!(vars["C"], "\"unbalanced");
+        assert_eq!(vars["D"], "a=b");
+        assert_eq!(vars["E"], "");
+    }
+
+    #[test]
+    fn passes_through_set_variables_and_skips_unset_ones() {
+        std::env::set_var("ROCKER_TEST_ENV_PASSTHROUGH", "from shell");
+        std::env::remove_var("ROCKER_TEST_ENV_UNSET");
+        assert_eq!(
+            parse_env_file("ROCKER_TEST_ENV_PASSTHROUGH\nROCKER_TEST_ENV_UNSET\n").unwrap(),
+            vec![("ROCKER_TEST_ENV_PASSTHROUGH".to_string(), "from shell".to_string())]
+        );
+        assert_eq!(
+            parse_env_var("ROCKER_TEST_ENV_PASSTHROUGH").unwrap(),
+            Some(("ROCKER_TEST_ENV_PASSTHROUGH".to_string(), "from shell".to_string()))
+        );
+        assert_eq!(parse_env_var("ROCKER_TEST_ENV_UNSET").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert_eq!(parse_env_file("A=1\n=2\n").unwrap_err(), "Invalid variable on line 2: =2");
+        assert!(parse_env_file("MY VAR=1").is_err());
+        assert!(parse_env_var("=value").is_err());
+    }
+
+    #[test]
+    fn later_files_and_values_take_precedence() {
+        let dir = std::env::temp_dir().join(format!("rocker-env-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("defaults.env"), "A=1\nB=1\nC=1\n").unwrap();
+        std::fs::write(dir.join("local.env"), "B=2\nC=2\n").unwrap();
+
+        let env = resolve_env(&[dir.join("defaults.env"), dir.join("local.env")], &["C=3".to_string()]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((env["A"].as_str(), env["B"].as_str(), env["C"].as_str()), ("1", "2", "3"));
+        assert!(resolve_env(&[dir.join("missing.env")], &[]).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+/// One `name=value` filter, negated when written as `name!=value`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub name: String,
+    pub value: String,
+    pub negated: bool,
+}
+
+/// Filters of the list, prune and events endpoints, given as `filter=name=value` query parameters
+///
+/// Filters of different names must all match. `label` filters must all match too (as in Docker, so that
+/// `label=a` and `label=b` select objects with both labels), while other filters of the same name match if
+/// any of them matches. Negated filters (`label!=env=staging`) exclude every object they match.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filters {
+    filters: Vec<Filter>,
+}
+
+impl Filters {
+    /// Parse `name=value` and `name!=value` filters, accepting only the names in `allowed`
+    pub fn parse<'a>(specs: impl IntoIterator<Item = &'a str>, allowed: &[&str]) -> Result<Self, String> {
+        let mut filters = Vec::new();
+        for spec in specs {
+            let (name, value) = spec
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid filter (expected key=value): {}", spec))?;
+            let (name, negated) = match name.strip_suffix('!') {
+                Some(name) => (name, true),
+                None => (name, false),
+            };
+            if !allowed.contains(&name) {
+                return Err(format!("Invalid filter: {}", name));
+            }
+            filters.push(Filter {
+                name: name.to_string(),
+                value: value.to_string(),
+                negated,
+            });
+        }
+        Ok(Filters { filters })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Filter> {
+        self.filters.iter()
+    }
+
+    /// Values of the filters named `name` that are not negated
+    pub fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.filters
+            .iter()
+            .filter(move |filter| filter.name == name && !filter.negated)
+            .map(|filter| filter.value.as_str())
+    }
+
+    /// Whether an object passes the filters, given whether it matches one filter's name and value
+    pub fn matches(&self, matches: impl Fn(&str, &str) -> bool) -> bool {
+        let mut any_of: HashMap<&str, bool> = HashMap::new();
+        for filter in &self.filters {
+            let matched = matches(&filter.name, &filter.value);
+            if filter.negated {
+                if matched {
+                    return false;
+                }
+            } else if filter.name == "label" {
+                if !matched {
+                    return false;
+                }
+            } else {
+                *any_of.entry(&filter.name).or_default() |= matched;
+            }
+        }
+        any_of.values().all(|matched| *matched)
+    }
+}
+
+/// Whether labels match a label filter (`key` or `key=value`)
+pub fn matches_label(labels: &HashMap<String, String>, filter: &str) -> bool {
+    match filter.split_once('=') {
+        Some((key, value)) => labels.get(key).map(String::as_str) == Some(value),
+        None => labels.contains_key(filter),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED: [&str; 3] = ["label", "name", "status"];
+
+    fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    // name は値と一致するか、label は matches_label で比べる
+    fn passes(filters: &Filters, name: &str, object_labels: &HashMap<String, String>) -> bool {
+        filters.matches(|filter, value| match filter {
+            "name" => value == name,
+            "label" => matches_label(object_labels, value),
+            _ => false,
+        })
+    }
+
+    #[test]
+    fn parses_key_value_and_negated_filters() {
+        let filters = Filters::parse(["name=web", "label!=env=staging"], &ALLOWED).unwrap();
+        assert_eq!(
+            filters.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                Filter { name: "name".to_string(), value: "web".to_string(), negated: false },
+                Filter { name: "label".to_string(), value: "env=staging".to_string(), negated: true },
+            ]
+        );
+        assert_eq!(filters.values("name").collect::<Vec<_>>(), vec!["web"]);
+        assert_eq!(filters.values("label").count(), 0);
+    }
+
+    #[test]
+    fn rejects_invalid_filters() {
+        assert_eq!(
+            Filters::parse(["status"], &ALLOWED).unwrap_err(),
+            "Invalid filter (expected key=value): status"
+        );
+        assert_eq!(Filters::parse(["driver=local"], &ALLOWED).unwrap_err(), "Invalid filter: driver");
+        assert_eq!(Filters::parse(["driver!=local"], &ALLOWED).unwrap_err(), "Invalid filter: driver");
+    }
+
+    #[test]
+    fn label_filters_must_all_match() {
+        let filters = Filters::parse(["label=team", "label=env=prod"], &ALLOWED).unwrap();
+        assert!(passes(&filters, "web", &labels(&[("team", "web"), ("env", "prod")])));
+        assert!(!passes(&filters, "web", &labels(&[("team", "web"), ("env", "staging")])));
+        assert!(!passes(&filters, "web", &labels(&[("env", "prod")])));
+    }
+
+    #[test]
+    fn other_filters_of_the_same_name_match_any() {
+        let filters = Filters::parse(["name=web", "name=db"], &ALLOWED).unwrap();
+        assert!(passes(&filters, "web", &labels(&[])));
+        assert!(passes(&filters, "db", &labels(&[])));
+        assert!(!passes(&filters, "cache", &labels(&[])));
+        assert!(passes(&Filters::default(), "cache", &labels(&[])));
+    }
+
+    #[test]
+    fn negated_filters_exclude_matches() {
+        let filters = Filters::parse(["name=web", "label!=env=staging"], &ALLOWED).unwrap();
+        assert!(passes(&filters, "web", &labels(&[("env", "prod")])));
+        assert!(!passes(&filters, "web", &labels(&[("env", "staging")])));
+        assert!(!passes(&filters, "db", &labels(&[("env", "prod")])));
+    }
+}
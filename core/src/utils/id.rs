@@ -1,6 +1,7 @@
 use rand::{distributions::Alphanumeric, Rng};
 use uuid::Uuid;
 use base64::{Engine as _, engine::general_purpose};
+use sha2::{Digest, Sha256};
 
 /// Generate a UUID string
 pub fn generate_uuid() -> String {
@@ -16,9 +17,11 @@ pub fn generate_short_id() -> String {
     general_purpose::URL_SAFE_NO_PAD.encode(&bytes[0..6])
 }
 
-/// Generate a container name
-pub fn generate_container_name() -> String {
-    // Generate a random adjective and noun combination
+/// Generate a container name such as `focused_turing` for the container `id`
+///
+/// The name is derived from the ID, so the same ID and `retry` always give the same name. Callers try again
+/// with an increasing `retry` when the name is taken; from the first retry on a digit is appended, as Docker does.
+pub fn generate_container_name(id: &str, retry: usize) -> String {
     let adjectives = [
         "admiring", "adoring", "affectionate", "agitated", "amazing",
         "angry", "awesome", "beautiful", "blissful", "bold",
@@ -93,11 +96,20 @@ pub fn generate_container_name() -> String {
         "wu", "yalow", "yonath", "zhukovsky", "zuse"
     ];
 
-    let mut rng = rand::thread_rng();
-    let adjective = adjectives[rng.gen_range(0..adjectives.len())];
-    let noun = nouns[rng.gen_range(0..nouns.len())];
+    let hash = Sha256::digest(format!("{}:{}", id, retry));
+    let pick = |offset: usize, len: usize| u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap()) as usize % len;
+    let adjective = adjectives[pick(0, adjectives.len())];
+    let mut noun = nouns[pick(4, nouns.len())];
+    // Steve Wozniak is not boring
+    if adjective == "boring" && noun == "wozniak" {
+        noun = nouns[(pick(4, nouns.len()) + 1) % nouns.len()];
+    }
 
-    format!("{}_{}", adjective, noun)
+    if retry == 0 {
+        format!("{}_{}", adjective, noun)
+    } else {
+        format!("{}_{}{}", adjective, noun, pick(8, 10))
+    }
 }
 
 /// Generate a random string of the given length
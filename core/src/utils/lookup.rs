@@ -0,0 +1,172 @@
+/// Identifiable is implemented by objects that can be looked up by ID, ID prefix or name
+pub trait Identifiable {
+    /// Full ID of the object
+    fn id(&self) -> &str;
+
+    /// Returns true if the object has the given name
+    fn has_name(&self, name: &str) -> bool;
+}
+
+/// Error returned when an identifier does not resolve to exactly one object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LookupError {
+    /// No object matches the identifier
+    NotFound,
+    /// The identifier is a prefix of several IDs
+    Ambiguous(Vec<String>),
+}
+
+/// Resolve an identifier by exact ID, then exact name, then unique ID prefix
+///
+/// IDs are compared without a leading `sha256:` so that image digests and their short forms both match.
+pub fn lookup<'a, T, I>(items: I, identifier: &str) -> Result<&'a T, LookupError>
+where
+    T: Identifiable + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let items: Vec<&T> = items.into_iter().collect();
+    let wanted = strip_digest(identifier);
+    if wanted.is_empty() {
+        return Err(LookupError::NotFound);
+    }
+
+    if let Some(item) = items.iter().find(|item| strip_digest(item.id()) == wanted) {
+        return Ok(item);
+    }
+
+    if let Some(item) = items.iter().find(|item| item.has_name(identifier)) {
+        return Ok(item);
+    }
+
+    let matches: Vec<&T> = items
+        .into_iter()
+        .filter(|item| strip_digest(item.id()).starts_with(wanted))
+        .collect();
+
+    match matches.as_slice() {
+        [] => Err(LookupError::NotFound),
+        [item] => Ok(item),
+        _ => Err(LookupError::Ambiguous(
+            matches.iter().map(|item| item.id().to_string()).collect(),
+        )),
+    }
+}
+
+fn strip_digest(id: &str) -> &str {
+    id.strip_prefix("sha256:").unwrap_or(id)
+}
+
+/// Resolve a `user[:group]` spec to a uid and gid with the contents of a container's `/etc/passwd`
+/// and `/etc/group`
+///
+/// The user is a name or uid from passwd; a numeric uid missing from passwd is used as is, with a gid
+/// of the same number. The group is a gid or a name from group and replaces the user's primary group.
+pub fn lookup_user(spec: &str, passwd: &str, group: &str) -> Result<(u32, u32), String> {
+    let (user, group_name) = match spec.split_once(':') {
+        Some((user, group)) => (user, Some(group)),
+        None => (spec, None),
+    };
+
+    let entry = passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 4 && (fields[0] == user || fields[2] == user));
+
+    let (uid, primary_gid) = match (entry, user.parse::<u32>()) {
+        (Some(fields), _) => (
+            fields[2].parse().unwrap_or_default(),
+            fields[3].parse().unwrap_or_default(),
+        ),
+        (None, Ok(uid)) => (uid, uid),
+        (None, Err(_)) => return Err(format!("Unable to find user {}", user)),
+    };
+
+    let gid = match group_name {
+        Some(name) => match name.parse::<u32>() {
+            Ok(gid) => gid,
+            Err(_) => group
+                .lines()
+                .map(|line| line.split(':').collect::<Vec<_>>())
+                .find(|fields| fields.len() >= 3 && fields[0] == name)
+                .and_then(|fields| fields[2].parse().ok())
+                .ok_or_else(|| format!("Unable to find group {}", name))?,
+        },
+        None => primary_gid,
+    };
+
+    Ok((uid, gid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Item {
+        id: &'static str,
+        name: &'static str,
+    }
+
+    impl Identifiable for Item {
+        fn id(&self) -> &str {
+            self.id
+        }
+
+        fn has_name(&self, name: &str) -> bool {
+            self.name == name
+        }
+    }
+
+    const ITEMS: [Item; 3] = [
+        Item { id: "sha256:abc123", name: "web" },
+        Item { id: "sha256:abd456", name: "db" },
+        Item { id: "ffe789", name: "abc" },
+    ];
+
+    const PASSWD: &str = "root:x:0:0:root:/root:/bin/sh\nnobody:x:65534:65534:nobody:/:/sbin/nologin\napp:x:1000:1001::/home/app:/bin/sh\n";
+    const GROUP: &str = "root:x:0:\nnogroup:x:65534:\napp:x:1001:\nstaff:x:50:app\n";
+
+    #[test]
+    fn resolves_ids_names_and_unique_prefixes() {
+        assert_eq!(lookup(&ITEMS, "sha256:abc123").unwrap().name, "web");
+        assert_eq!(lookup(&ITEMS, "abd456").unwrap().name, "db");
+        // 名前は ID の前方一致より優先する
+        assert_eq!(lookup(&ITEMS, "abc").unwrap().id, "ffe789");
+        assert_eq!(lookup(&ITEMS, "abd").unwrap().name, "db");
+        assert_eq!(lookup(&ITEMS, "ffe").unwrap().name, "abc");
+    }
+
+    #[test]
+    fn reports_missing_and_ambiguous_identifiers() {
+        assert_eq!(lookup(&ITEMS, "999").err(), Some(LookupError::NotFound));
+        assert_eq!(lookup(&ITEMS, "sha256:").err(), Some(LookupError::NotFound));
+        assert_eq!(
+            lookup(&ITEMS, "ab").err(),
+            Some(LookupError::Ambiguous(vec!["sha256:abc123".to_string(), "sha256:abd456".to_string()]))
+        );
+    }
+
+    #[test]
+    fn resolves_users_by_name_or_uid() {
+        assert_eq!(lookup_user("app", PASSWD, GROUP), Ok((1000, 1001)));
+        assert_eq!(lookup_user("1000", PASSWD, GROUP), Ok((1000, 1001)));
+        assert_eq!(lookup_user("nobody", PASSWD, GROUP), Ok((65534, 65534)));
+        // passwd に無い uid はそのまま使い、gid も同じ値にする
+        assert_eq!(lookup_user("4242", PASSWD, GROUP), Ok((4242, 4242)));
+        assert_eq!(lookup_user("4242", "", ""), Ok((4242, 4242)));
+    }
+
+    #[test]
+    fn resolves_user_and_group() {
+        assert_eq!(lookup_user("app:staff", PASSWD, GROUP), Ok((1000, 50)));
+        assert_eq!(lookup_user("app:0", PASSWD, GROUP), Ok((1000, 0)));
+        assert_eq!(lookup_user("0:nogroup", PASSWD, GROUP), Ok((0, 65534)));
+        assert_eq!(lookup_user("4242:4343", "", ""), Ok((4242, 4343)));
+    }
+
+    #[test]
+    fn rejects_unknown_names() {
+        assert_eq!(lookup_user("missing", PASSWD, GROUP), Err("Unable to find user missing".to_string()));
+        assert_eq!(lookup_user("app:missing", PASSWD, GROUP), Err("Unable to find group missing".to_string()));
+        assert_eq!(lookup_user("app", "", GROUP), Err("Unable to find user app".to_string()));
+    }
+}
@@ -1,26 +1,26 @@
+use chrono::{DateTime, Duration, Utc};
 use sha2::{Digest, Sha256};
-use std::io::Read;
 use std::path::Path;
 
+mod archive;
+mod digest;
+mod env;
+mod filter;
 mod id;
+mod lookup;
+mod signal;
+pub use archive::*;
+pub use digest::*;
+pub use env::*;
+pub use filter::*;
 pub use id::*;
+pub use lookup::*;
+pub use signal::*;
 
-/// Calculate the SHA256 hash of a file
+/// Calculate the SHA256 hash of a file (blocking; async code should use [`hash_file`])
 pub fn calculate_file_hash<P: AsRef<Path>>(path: P) -> Result<String, std::io::Error> {
-    let mut file = std::fs::File::open(path)?;
-    let mut hasher = Sha256::new();
-    let mut buffer = [0; 1024];
-
-    loop {
-        let bytes_read = file.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        hasher.update(&buffer[..bytes_read]);
-    }
-
-    let hash = hasher.finalize();
-    Ok(format!("sha256:{:x}", hash))
+    let (digest, _) = calculate_reader_hash(std::fs::File::open(path)?)?;
+    Ok(digest)
 }
 
 /// Calculate the SHA256 hash of a string
@@ -99,32 +99,76 @@ pub fn parse_memory_size(s: &str) -> Result<u64, String> {
     Ok((num * multiplier as f64) as u64)
 }
 
-/// Generate a random port number
-pub fn random_port() -> u16 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-        
-    let mut num = (now & 0xFFFF) as u16;
-    
-    // Ensure the port is in the dynamic range (49152-65535)
-    if num < 49152 {
-        num += 49152;
+/// Parse a duration given as seconds (`10`) or with units (`500ms`, `30s`, `1m30s`, `2h`)
+pub fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    use std::time::Duration;
+
+    if let Ok(seconds) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(seconds));
     }
-    
-    num
+
+    let invalid = || format!("Invalid duration (expected seconds or a duration such as 1m30s): {}", s);
+    let mut duration = Duration::ZERO;
+    let mut rest = s;
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let number: u64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(1),
+            "s" => Duration::from_secs(1),
+            "m" => Duration::from_secs(60),
+            "h" => Duration::from_secs(3600),
+            _ => return Err(invalid()),
+        };
+        duration = u32::try_from(number)
+            .ok()
+            .and_then(|number| unit.checked_mul(number))
+            .and_then(|value| duration.checked_add(value))
+            .ok_or_else(invalid)?;
+        rest = &rest[unit_len..];
+    }
+    Ok(duration)
 }
 
-/// Check if a port is available
-pub fn is_port_available(port: u16) -> bool {
-    use std::net::{SocketAddrV4, TcpListener};
-    use std::str::FromStr;
-    
-    let addr = SocketAddrV4::from_str(&format!("127.0.0.1:{}", port))
-        .expect("Failed to parse socket address");
-        
-    TcpListener::bind(addr).is_ok()
-} 
+/// Parse a point in time given as an RFC 3339 timestamp (`2024-01-02T15:04:05Z`), Unix seconds
+/// (`1704207845` or `1704207845.5`) or a duration before `now` (`10m`, `1h30m`, `500ms`)
+pub fn parse_timestamp(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let s = s.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(seconds) = s.parse::<f64>() {
+        return DateTime::from_timestamp_millis((seconds * 1000.0) as i64)
+            .ok_or_else(|| format!("Timestamp out of range: {}", s));
+    }
+
+    let invalid = || format!("Invalid timestamp (expected RFC 3339, Unix seconds or a duration): {}", s);
+    let mut millis: i64 = 0;
+    let mut rest = s;
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let number: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            _ => return Err(invalid()),
+        };
+        millis = number.checked_mul(unit).and_then(|m| m.checked_add(millis)).ok_or_else(invalid)?;
+        rest = &rest[unit_len..];
+    }
+    Duration::try_milliseconds(millis)
+        .and_then(|duration| now.checked_sub_signed(duration))
+        .ok_or_else(invalid)
+}
@@ -0,0 +1,20 @@
+use nix::sys::signal::Signal;
+use std::str::FromStr;
+
+/// Parse a signal given by name or number (e.g. "SIGTERM", "TERM" or "15")
+pub fn parse_signal(s: &str) -> Result<Signal, String> {
+    let s = s.trim();
+
+    if let Ok(num) = s.parse::<i32>() {
+        return Signal::try_from(num).map_err(|_| format!("Invalid signal number: {}", num));
+    }
+
+    let name = s.to_uppercase();
+    let name = if name.starts_with("SIG") {
+        name
+    } else {
+        format!("SIG{}", name)
+    };
+
+    Signal::from_str(&name).map_err(|_| format!("Invalid signal: {}", s))
+}
@@ -4,6 +4,12 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use uuid::Uuid;
 
+use crate::container::{Mount, MountType, PropagationMode};
+use crate::utils::Identifiable;
+
+/// Label set on volumes created for a container's `VOLUME` paths and `-v /path` mounts
+pub const ANONYMOUS_VOLUME_LABEL: &str = "com.rocker.volume.anonymous";
+
 /// Volume represents a container volume
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Volume {
@@ -25,6 +31,30 @@ pub struct Volume {
     pub status: HashMap<String, String>,
     /// Labels
     pub labels: HashMap<String, String>,
+    /// Disk usage, only filled in when requested (inspect, `volume ls` with sizes and `system df`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<VolumeUsage>,
+    /// Point-in-time copies of the data of the volume
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snapshots: Vec<VolumeSnapshot>,
+}
+
+/// Snapshot of the data of a volume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeSnapshot {
+    /// Snapshot name, unique within the volume
+    pub name: String,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+}
+
+/// Disk usage of a volume
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumeUsage {
+    /// Bytes used by the data of the volume
+    pub size: u64,
+    /// Number of containers that reference the volume
+    pub ref_count: usize,
 }
 
 impl Volume {
@@ -40,8 +70,25 @@ impl Volume {
             scope: VolumeScope::Local,
             status: HashMap::new(),
             labels: HashMap::new(),
+            usage: None,
+            snapshots: Vec::new(),
         }
     }
+
+    /// Whether the volume was created for a container rather than by name
+    pub fn is_anonymous(&self) -> bool {
+        self.labels.contains_key(ANONYMOUS_VOLUME_LABEL)
+    }
+}
+
+impl Identifiable for Volume {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name
+    }
 }
 
 /// Volume driver
@@ -53,6 +100,17 @@ pub enum VolumeDriver {
     Custom(String),
 }
 
+impl VolumeDriver {
+    /// Parse a driver name
+    pub fn parse(driver: &str) -> Result<Self, String> {
+        match driver {
+            "local" => Ok(VolumeDriver::Local),
+            "" => Err("Volume driver name must not be empty".to_string()),
+            _ => Ok(VolumeDriver::Custom(driver.to_string())),
+        }
+    }
+}
+
 impl std::fmt::Display for VolumeDriver {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -115,4 +173,33 @@ pub struct MountOptions {
     pub bind_propagation: Option<String>,
     /// If the source does not exist, should it be created?
     pub create_source: bool,
-} 
+}
+
+impl MountOptions {
+    /// Convert to the mount of a container, parsing the mount type and bind propagation
+    pub fn to_mount(&self) -> Result<Mount, String> {
+        let mount_type = match self.mount_type.as_str() {
+            "bind" => MountType::Bind,
+            "volume" => MountType::Volume,
+            "tmpfs" => MountType::Tmpfs,
+            other => return Err(format!("Invalid mount type: {}", other)),
+        };
+        let propagation = match &self.bind_propagation {
+            Some(_) if !matches!(mount_type, MountType::Bind) => {
+                return Err("bind-propagation is only supported for bind mounts".to_string())
+            }
+            Some(propagation) => Some(PropagationMode::parse(propagation)?),
+            None => None,
+        };
+        Ok(Mount {
+            mount_type,
+            source: self.source.clone(),
+            destination: self.target.clone(),
+            read_only: self.read_only,
+            propagation,
+            relabel: None,
+            no_copy: false,
+            tmpfs_size: None,
+        })
+    }
+}
@@ -0,0 +1,447 @@
+use chrono::Utc;
+use hyper::{Body, Request, Response, StatusCode};
+use nix::sys::signal::Signal;
+use rocker_core::{
+    matches_label, parse_signal, parse_timestamp, Container, ContainerConfig, ContainerExit, ContainerState, EventType,
+    RockerError, STREAM_BUFFER_SIZE,
+};
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex};
+use tracing::warn;
+
+use super::{empty_response, json_response, ndjson_response, parse_filters, query_params, read_json, stream_response, ApiError};
+use crate::container::wait_for_dependencies;
+use crate::logging::ReadOptions;
+use crate::RockerDaemon;
+
+// stats の stream で統計を送る間隔
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+
+// GET /containers?all=1&size=1&filter=key=value
+//
+// all を付けない場合は動作中のコンテナのみを返す。フィルタは Filters の規則（label は AND、他の同じキーは OR、
+// key!=value は除外）で評価する。size を付けると書き込み層と全体の大きさ（size_rw・size_root_fs）も返す。
+pub async fn list(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let params = query_params(&req);
+    let all = params
+        .iter()
+        .any(|(key, value)| key == "all" && matches!(value.as_str(), "1" | "true"));
+    let size = params
+        .iter()
+        .any(|(key, value)| key == "size" && matches!(value.as_str(), "1" | "true"));
+
+    let filters = parse_filters(&req, &["health", "id", "label", "name", "status"])?;
+
+    let daemon = daemon.lock().await;
+    let mut containers: Vec<Container> = daemon
+        .container_manager
+        .list_all()
+        .await?
+        .into_iter()
+        .filter(|container| all || container.state.is_running() || container.state.is_paused())
+        .filter(|container| filters.matches(|name, value| matches_filter(container, name, value)))
+        .collect();
+    containers.sort_by_key(|container| Reverse(container.created_at));
+    if size {
+        let mut sized = Vec::with_capacity(containers.len());
+        for container in &containers {
+            sized.push(with_size(&daemon, container).await);
+        }
+        containers = sized;
+    }
+
+    Ok(json_response(StatusCode::OK, &containers))
+}
+
+// 大きさを埋めたコンテナ（イメージが削除されていればイメージの大きさは 0 とする）
+pub(super) async fn with_size(daemon: &RockerDaemon, container: &Container) -> Container {
+    let image_size = daemon
+        .image_manager
+        .get(&container.config.image)
+        .map(|image| image.size)
+        .unwrap_or(0);
+    daemon.container_manager.with_size(container, image_size).await
+}
+
+fn matches_filter(container: &Container, name: &str, value: &str) -> bool {
+    match name {
+        "id" => container.id.starts_with(value),
+        "name" => container.name.contains(value),
+        "status" => container.state.to_string() == value,
+        "label" => matches_label(&container.config.labels, value),
+        // ヘルスチェックのないコンテナは none
+        "health" => match &container.health {
+            Some(health) => health.status.to_string() == value,
+            None => value == "none",
+        },
+        _ => false,
+    }
+}
+
+// POST /containers/create?name=<name>&replace=1（ボディは ContainerConfig、image はイメージの ID か repo:tag）
+//
+// replace を指定すると、同じ名前のコンテナを削除して置き換える。
+pub async fn create(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let params = query_params(&req);
+    let name = params
+        .iter()
+        .find(|(key, _)| key == "name")
+        .map(|(_, value)| value.clone())
+        .unwrap_or_default();
+    let replace = params
+        .iter()
+        .any(|(key, value)| key == "replace" && matches!(value.as_str(), "1" | "true"));
+    if replace && name.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "replace requires a name"));
+    }
+    let config: ContainerConfig = read_json(req).await?;
+
+    let container = if replace {
+        RockerDaemon::replace_container(&daemon, &name, config).await?
+    } else {
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        let image = daemon
+            .image_manager
+            .get(&config.image)
+            .map_err(RockerError::from)?;
+        daemon
+            .container_manager
+            .create(&name, config, &image, &daemon.network_manager)
+            .await?
+    };
+
+    Ok(json_response(StatusCode::CREATED, &container))
+}
+
+// GET /containers/{id}?size=1
+pub async fn inspect(
+    req: &Request<Body>,
+    container: &str,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let size = query_params(req)
+        .iter()
+        .any(|(key, value)| key == "size" && matches!(value.as_str(), "1" | "true"));
+    let daemon = daemon.lock().await;
+    let container = daemon.container_manager.get(container).map_err(RockerError::from)?;
+    if size {
+        return Ok(json_response(StatusCode::OK, &with_size(&daemon, container).await));
+    }
+
+    Ok(json_response(StatusCode::OK, container))
+}
+
+// POST /containers/{id}/start
+pub async fn start(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    // --wait-for の依存先が準備できるまで、ロックを持たずに待つ
+    wait_for_dependencies(&daemon, container).await.map_err(RockerError::from)?;
+    let mut daemon = daemon.lock().await;
+    let daemon = &mut *daemon;
+    daemon
+        .container_manager
+        .start(container, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+        .await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /containers/{id}/stop?t=<秒>
+pub async fn stop(
+    container: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let timeout = match query_params(&req).into_iter().find(|(key, _)| key == "t") {
+        Some((_, value)) => Some(
+            value
+                .parse::<u64>()
+                .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid timeout: {}", value)))?,
+        ),
+        None => None,
+    };
+
+    RockerDaemon::stop_container(&daemon, container, timeout).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /containers/{id}/kill?signal=<名前か番号>（既定は SIGKILL）
+pub async fn kill(container: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let signal = match query_params(&req).into_iter().find(|(key, _)| key == "signal") {
+        Some((_, value)) => parse_signal(&value).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?,
+        None => Signal::SIGKILL,
+    };
+    daemon.lock().await.container_manager.kill(container, signal)?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /containers/{id}/wait
+//
+// 次にコンテナが終了するまで待ち、終了コードを {"exit_code": N} の 1 行で返す（終了済みならすぐに返す）。
+// ヘッダーは待ち始めた時点で返すため、受け取ってから start すれば、すぐに終了して --rm で削除される
+// コンテナの終了コードも取りこぼさない。終了せずに削除された場合は何も返さずに終わる。
+pub async fn wait(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let container = daemon.container_manager.get(container).map_err(RockerError::from)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    if matches!(container.state, ContainerState::Exited | ContainerState::Stopped) {
+        let _ = tx.send(ContainerExit {
+            exit_code: container.exit_code.unwrap_or(0),
+        });
+        return Ok(ndjson_response(rx));
+    }
+
+    let id = container.id.clone();
+    let mut events = daemon.events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Wait for container {} skipped {} events", id, skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => return,
+            };
+            if !matches!(event.event_type, EventType::Container) || event.actor_id != id {
+                continue;
+            }
+            match event.action.as_str() {
+                "die" | "stop" => {
+                    let exit_code = event
+                        .attributes
+                        .get("exitCode")
+                        .and_then(|code| code.parse().ok())
+                        .unwrap_or(0);
+                    let _ = tx.send(ContainerExit { exit_code });
+                    return;
+                }
+                "destroy" => return,
+                _ => {}
+            }
+        }
+    });
+    Ok(ndjson_response(rx))
+}
+
+// POST /containers/{id}/pause
+pub async fn pause(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    daemon.lock().await.container_manager.pause(container).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /containers/{id}/unpause
+pub async fn unpause(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    daemon.lock().await.container_manager.unpause(container).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// DELETE /containers/{id}?v=1&force=1
+//
+// force の場合は動作中のコンテナを停止してから削除し、v の場合は匿名ボリュームも削除する。
+pub async fn remove(
+    container: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let params = query_params(&req);
+    let flag = |name: &str| {
+        params
+            .iter()
+            .any(|(key, value)| key == name && matches!(value.as_str(), "1" | "true"))
+    };
+    let (force, remove_volumes) = (flag("force"), flag("v"));
+
+    let state = daemon
+        .lock()
+        .await
+        .container_manager
+        .get(container)
+        .map_err(RockerError::from)?
+        .state
+        .clone();
+    let stopped = force && (state.is_running() || state.is_paused());
+    if stopped {
+        RockerDaemon::stop_container(&daemon, container, None).await?;
+    }
+
+    let mut daemon = daemon.lock().await;
+    let daemon = &mut *daemon;
+    // --rm のコンテナは停止と同時に削除される
+    if stopped && daemon.container_manager.get(container).is_err() {
+        return Ok(empty_response(StatusCode::NO_CONTENT));
+    }
+    daemon
+        .container_manager
+        .remove(container, remove_volumes, &mut daemon.volume_manager)
+        .await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// PUT /containers/{id}/archive?path=<ディレクトリ>（ボディは tar）
+pub async fn put_archive(
+    container: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let path = path_param(&req)?;
+    let archive = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_vec();
+
+    let daemon = daemon.lock().await;
+    daemon.container_manager.extract_archive(container, &path, archive).await?;
+
+    Ok(empty_response(StatusCode::OK))
+}
+
+// DELETE /containers/{id}/archive?path=<パス>
+pub async fn remove_path(
+    container: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let path = path_param(&req)?;
+
+    let daemon = daemon.lock().await;
+    daemon.container_manager.remove_path(container, &path).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+fn path_param(req: &Request<Body>) -> Result<String, ApiError> {
+    query_params(req)
+        .into_iter()
+        .find(|(key, _)| key == "path")
+        .map(|(_, value)| value)
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "path is required"))
+}
+
+// GET /containers/{id}/top
+pub async fn top(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let top = daemon.container_manager.top(container).await?;
+
+    Ok(json_response(StatusCode::OK, &top))
+}
+
+// GET /containers/{id}/stats?stream=0
+//
+// stream=0 の場合は 1 回だけ、それ以外は STATS_INTERVAL ごとに ContainerStats の NDJSON を返し続け、
+// コンテナが止まると終わる。
+pub async fn stats(container: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let stream = !query_params(&req)
+        .iter()
+        .any(|(key, value)| key == "stream" && matches!(value.as_str(), "0" | "false"));
+
+    let (id, stats) = {
+        let daemon = daemon.lock().await;
+        let id = daemon.container_manager.get(container).map_err(RockerError::from)?.id.clone();
+        let stats = daemon.container_manager.stats(&id).await?;
+        (id, stats)
+    };
+    if !stream {
+        return Ok(json_response(StatusCode::OK, &stats));
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let _ = tx.send(stats);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(STATS_INTERVAL).await;
+            let stats = daemon.lock().await.container_manager.stats(&id).await.ok();
+            // コンテナが止まるか、クライアントが切断したら終わる
+            if stats.is_none_or(|stats| tx.send(stats).is_err()) {
+                break;
+            }
+        }
+    });
+    Ok(ndjson_response(rx))
+}
+
+// GET /containers/{id}/export（コンテナのファイルシステムの tar）
+//
+// 書き出しを始めた後のエラーは返せないため、ログに残して tar を途中で終える。
+pub async fn export(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let export = daemon
+        .lock()
+        .await
+        .container_manager
+        .export(container)
+        .map_err(RockerError::from)?;
+
+    let (writer, response) = stream_response("application/x-tar");
+    let container = container.to_string();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = export.write_to(std::io::BufWriter::with_capacity(STREAM_BUFFER_SIZE, writer)) {
+            warn!("Failed to export container {}: {}", container, e);
+        }
+    });
+    Ok(response)
+}
+
+// GET /containers/{id}/diff?from=<image>
+//
+// コンテナの rootfs をイメージ（既定はコンテナのイメージ）と比べ、書き込み層を最後のレイヤーとして返す。
+pub async fn diff(container: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let from = query_params(&req).into_iter().find(|(key, _)| key == "from").map(|(_, value)| value);
+    let (name, image, rootfs, mounts, image_manager) = {
+        let daemon = daemon.lock().await;
+        let found = daemon.container_manager.get(container).map_err(RockerError::from)?;
+        let (rootfs, mounts) = daemon.container_manager.rootfs(container).map_err(RockerError::from)?;
+        (found.name.clone(), found.config.image.clone(), rootfs, mounts, daemon.image_manager.clone())
+    };
+
+    // rootfs を読む間はデーモンのロックを持たない
+    let from = from.unwrap_or_else(|| image.clone());
+    let diff = image_manager.diff_container(&image, &from, &name, rootfs, mounts).await?;
+
+    Ok(json_response(StatusCode::OK, &diff))
+}
+
+// GET /containers/{id}/logs?follow=1&tail=<行数|all>&since=<時刻>&until=<時刻>
+//
+// ログを LogRecord の NDJSON で返す。follow の場合は動作中のコンテナの出力を、コンテナが終了するか until
+// の時刻になるまで返し続ける。
+pub async fn logs(container: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut follow = false;
+    let mut options = ReadOptions::default();
+    let now = Utc::now();
+    for (key, value) in query_params(&req) {
+        match key.as_str() {
+            "follow" => follow = matches!(value.as_str(), "1" | "true"),
+            "tail" if value == "all" => options.tail = None,
+            "tail" => {
+                options.tail = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid tail: {}", value)))?,
+                )
+            }
+            // RFC 3339・Unix 秒・現在からの時間（10m など）
+            "since" => {
+                options.since =
+                    Some(parse_timestamp(&value, now).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?)
+            }
+            "until" => {
+                options.until =
+                    Some(parse_timestamp(&value, now).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?)
+            }
+            _ => {}
+        }
+    }
+
+    let daemon = daemon.lock().await;
+    let rx = daemon.container_manager.logs(container, follow, options)?;
+
+    Ok(ndjson_response(rx))
+}
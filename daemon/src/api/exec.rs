@@ -0,0 +1,69 @@
+use hyper::body::HttpBody;
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::ExecConfig;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+use super::{empty_response, json_response, ndjson_response, query_params, read_json, ApiError};
+use crate::RockerDaemon;
+
+// POST /containers/{id}/exec（ボディは ExecConfig、作成した exec セッションの ID を返す）
+pub async fn create(
+    container: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let config: ExecConfig = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let id = daemon.container_manager.create_exec(container, config).await?;
+
+    Ok(json_response(StatusCode::CREATED, &serde_json::json!({ "id": id })))
+}
+
+// GET /exec/{id}
+pub async fn inspect(exec: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let exec = daemon.container_manager.inspect_exec(exec).await?;
+
+    Ok(json_response(StatusCode::OK, &exec))
+}
+
+// GET /containers/{id}/exec（コンテナの exec セッションの一覧）
+pub async fn list(container: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let execs = daemon.container_manager.list_execs(container).await?;
+
+    Ok(json_response(StatusCode::OK, &execs))
+}
+
+// POST /exec/{id}/start?attach=1
+//
+// attach の場合はコマンドの出力を LogRecord の NDJSON で返し続ける。終了コードは出力が終わってから
+// GET /exec/{id} で取得する。attach_stdin の exec では、リクエストのボディをそのまま標準入力に書き込む
+// （ボディが終わると標準入力を閉じる）。
+pub async fn start(exec: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let attach = query_params(&req)
+        .iter()
+        .any(|(key, value)| key == "attach" && matches!(value.as_str(), "1" | "true"));
+
+    let daemon = daemon.lock().await;
+    if !attach {
+        daemon.container_manager.start_exec(exec, None, None).await?;
+        return Ok(empty_response(StatusCode::NO_CONTENT));
+    }
+
+    let (input_tx, input_rx) = mpsc::unbounded_channel();
+    let mut body = req.into_body();
+    tokio::spawn(async move {
+        while let Some(Ok(chunk)) = body.data().await {
+            if input_tx.send(chunk.to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    daemon.container_manager.start_exec(exec, Some(tx), Some(input_rx)).await?;
+    Ok(ndjson_response(rx))
+}
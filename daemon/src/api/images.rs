@@ -0,0 +1,355 @@
+use chrono::{DateTime, Utc};
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::{
+    matches_label, parse_timestamp, Image, ProgressMessage, RegistryAuth, RockerError, REGISTRY_AUTH_HEADER,
+    REGISTRY_CONFIG_HEADER,
+};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex};
+
+use super::{json_response, ndjson_response, parse_filters, percent_decode, query_params, read_json, ApiError};
+use crate::image::{wildcard_match, BuildOptions, CachePruneOptions, CacheSpec, SbomFormat};
+use crate::RockerDaemon;
+
+// GET /images?filter=key=value
+//
+// dangling=true|false・reference=<repo:tag のパターン>・label=key[=value]・before=<image>・since=<image>。
+// Filters の規則（label は AND、他の同じキーは OR、key!=value は除外）で評価する。
+pub async fn list(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = parse_filters(&req, &["dangling", "reference", "label", "before", "since"])?;
+    if let Some(filter) = filters
+        .iter()
+        .find(|filter| filter.name == "dangling" && !matches!(filter.value.as_str(), "true" | "false" | "1" | "0"))
+    {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid dangling filter: {}", filter.value)));
+    }
+
+    let daemon = daemon.lock().await;
+    // before・since は指定したイメージの作成時刻と比べる
+    let mut times: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for filter in filters.iter().filter(|filter| matches!(filter.name.as_str(), "before" | "since")) {
+        let image = daemon.image_manager.get(&filter.value).map_err(RockerError::from)?;
+        times.insert(filter.value.clone(), image.created_at);
+    }
+    let mut images: Vec<Image> = daemon
+        .image_manager
+        .list_all()
+        .await?
+        .into_iter()
+        .filter(|image| filters.matches(|name, value| matches_filter(image, name, value, &times)))
+        .collect();
+    images.sort_by_key(|image| Reverse(image.created_at));
+
+    Ok(json_response(StatusCode::OK, &images))
+}
+
+fn matches_filter(image: &Image, name: &str, value: &str, times: &HashMap<String, DateTime<Utc>>) -> bool {
+    match name {
+        // 名前の無いイメージ（同じ repo:tag で作り直されたものなど）
+        "dangling" => image.repo.is_none() == matches!(value, "true" | "1"),
+        "reference" => matches_reference(image, value),
+        "label" => matches_label(&image.labels, value),
+        "before" => times.get(value).is_some_and(|time| image.created_at < *time),
+        "since" => times.get(value).is_some_and(|time| image.created_at > *time),
+        _ => false,
+    }
+}
+
+// repo:tag か repo（タグを省略したパターン）が一致するか（* と ? を使える）
+fn matches_reference(image: &Image, pattern: &str) -> bool {
+    let (Some(repo), Some(name)) = (&image.repo, image.full_name()) else {
+        return false;
+    };
+    wildcard_match(pattern, &name) || wildcard_match(pattern, repo)
+}
+
+// GET /images/{name}（名前の / は %2F にする）
+pub async fn inspect(name: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let image = daemon
+        .image_manager
+        .get(&percent_decode(name))
+        .map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, &image))
+}
+
+// DELETE /images/{name}
+//
+// イメージから作ったコンテナが残っている場合は 409 を返す。
+pub async fn remove(name: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let name = percent_decode(name);
+    let daemon = daemon.lock().await;
+    let image = daemon.image_manager.get(&name).map_err(RockerError::from)?;
+    let used_by: Vec<String> = daemon
+        .container_manager
+        .list_all()
+        .await?
+        .into_iter()
+        .filter(|container| container.config.image == image.id)
+        .map(|container| container.name)
+        .collect();
+    let image = daemon.image_manager.remove(&name, &used_by).await?;
+
+    Ok(json_response(StatusCode::OK, &image))
+}
+
+// POST /auth
+//
+// RegistryAuth でレジストリにログインできるか確かめる。認証情報は保存しない（クライアントが保存し、
+// pull などの度に X-Registry-Auth で送る）。
+pub async fn auth(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let auth: RegistryAuth = read_json(req).await?;
+    let image_manager = daemon.lock().await.image_manager.clone();
+    image_manager.login(&auth).await?;
+
+    Ok(json_response(StatusCode::OK, &serde_json::json!({ "status": "Login Succeeded" })))
+}
+
+// POST /images/import?repo=<name:tag>&change=<命令>&message=<説明>（ボディは tar、gzip 圧縮も可）
+//
+// レイヤーが 1 つのイメージを作成して返す。change は複数指定でき、Rockerfile の命令として設定に反映する。
+pub async fn import(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let (mut reference, mut changes, mut message) = (None, Vec::new(), None);
+    for (key, value) in query_params(&req) {
+        match key.as_str() {
+            "repo" => reference = Some(value),
+            "change" => changes.push(value),
+            "message" => message = Some(value),
+            _ => {}
+        }
+    }
+    let archive = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let image = image_manager
+        .import(Box::new(std::io::Cursor::new(archive)), reference.as_deref(), &changes, message.as_deref())
+        .await?;
+
+    Ok(json_response(StatusCode::CREATED, &image))
+}
+
+// POST /images/create?fromImage=<name>
+//
+// pull の進捗を ProgressMessage の NDJSON で返し続ける。失敗した場合は error を持つ行で終わる。
+// X-Registry-Auth があればその認証情報でレジストリにログインする（push も同じ）。
+pub async fn pull(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let name = query_params(&req)
+        .into_iter()
+        .find(|(key, _)| key == "fromImage")
+        .map(|(_, value)| value)
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "fromImage is required"))?;
+
+    let auth = registry_auth(&req)?;
+
+    // pull の間はデーモンのロックを持たない
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let result = image_manager.pull(&name, auth.as_ref(), &tx).await.map(|image| image.id);
+        finish(&tx, result);
+    });
+    Ok(ndjson_response(rx))
+}
+
+// POST /images/{name}/push
+pub async fn push(name: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let name = percent_decode(name);
+    let auth = registry_auth(&req)?;
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let image = image_manager.get(&name).map_err(RockerError::from)?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let result = image_manager.push(&name, auth.as_ref(), &tx).await.map(|_| image.id);
+        finish(&tx, result);
+    });
+    Ok(ndjson_response(rx))
+}
+
+// GET /images/{name}/diff?from=<image>
+//
+// from のイメージと比べて追加・削除・変更されたファイルを、共有しないレイヤーごとと全体で返す。
+pub async fn diff(name: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let from = query_params(&req)
+        .into_iter()
+        .find(|(key, _)| key == "from")
+        .map(|(_, value)| value)
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "from is required"))?;
+
+    // レイヤーを読む間はデーモンのロックを持たない
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let diff = image_manager.diff(&percent_decode(name), &from).await?;
+
+    Ok(json_response(StatusCode::OK, &diff))
+}
+
+// GET /images/{name}/sbom?format=<spdx-json|cyclonedx-json>
+//
+// イメージのパッケージの SBOM を返す（既定は SPDX）。
+pub async fn sbom(name: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let format = query_params(&req)
+        .into_iter()
+        .find(|(key, _)| key == "format")
+        .map(|(_, value)| SbomFormat::parse(&value))
+        .transpose()
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?
+        .unwrap_or(SbomFormat::Spdx);
+
+    // レイヤーを読む間はデーモンのロックを持たない
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let document = image_manager.sbom(&percent_decode(name), format).await?;
+
+    Ok(json_response(StatusCode::OK, &document))
+}
+
+// POST /images/{name}/scan
+//
+// 設定されたスキャナでイメージを調べ、イメージに保存した ImageScan を返す。
+pub async fn scan(name: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let scan = image_manager.scan(&percent_decode(name)).await?;
+
+    Ok(json_response(StatusCode::OK, &scan))
+}
+
+// POST /images/{name}/squash?tag=<repo:tag>
+//
+// レイヤーを 1 つにまとめたイメージを作り、進捗を NDJSON で返す（tag が無ければ元の名前を付け替える）。
+pub async fn squash(name: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let name = percent_decode(name);
+    let tag = query_params(&req)
+        .into_iter()
+        .find(|(key, _)| key == "tag")
+        .map(|(_, value)| value);
+    let image_manager = daemon.lock().await.image_manager.clone();
+    image_manager.get(&name).map_err(RockerError::from)?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let result = image_manager.squash(&name, tag.as_deref(), &tx).await.map(|image| image.id);
+        finish(&tx, result);
+    });
+    Ok(ndjson_response(rx))
+}
+
+// POST /build?t=<repo:tag>&rockerfile=<path>&target=<stage>&buildarg=K=V&label=K=V&pull=1&squash=1
+//             &nocache=1&cachefrom=<spec>&cacheto=<spec>
+//
+// ボディはビルドコンテキストの tar（gzip 圧縮も可）。ビルドの各ステップと RUN の出力を
+// ProgressMessage の NDJSON で返し続け、成功した場合は image_id を持つ行で終わる。
+// X-Registry-Config（レジストリごとの RegistryAuth）はベースイメージの pull に使う。
+pub async fn build(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut options = BuildOptions::default();
+    for (key, value) in query_params(&req) {
+        match key.as_str() {
+            "t" => options.tag = Some(value),
+            "rockerfile" => options.rockerfile = Some(value),
+            "target" => options.target = Some(value),
+            "pull" => options.pull = matches!(value.as_str(), "1" | "true"),
+            "squash" => options.squash = matches!(value.as_str(), "1" | "true"),
+            "nocache" => options.no_cache = matches!(value.as_str(), "1" | "true"),
+            "cachefrom" => options
+                .cache_from
+                .push(CacheSpec::parse(&value, "src").map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?),
+            "cacheto" => {
+                options.cache_to =
+                    Some(CacheSpec::parse(&value, "dest").map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?)
+            }
+            "buildarg" | "label" => {
+                let (name, value) = value.split_once('=').ok_or_else(|| {
+                    ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid {} (expected key=value): {}", key, value))
+                })?;
+                let map = if key == "buildarg" { &mut options.build_args } else { &mut options.labels };
+                map.insert(name.to_string(), value.to_string());
+            }
+            _ => {}
+        }
+    }
+    if let Some(value) = req.headers().get(REGISTRY_CONFIG_HEADER) {
+        let value = value.to_str().map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+        options.registry_auths =
+            RegistryAuth::from_config_header(value).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+    }
+    let archive = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?
+        .to_vec();
+
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let result = image_manager
+            .build_archive(archive, &options, &tx)
+            .await
+            .map(|image: Image| image.id);
+        finish(&tx, result);
+    });
+    Ok(ndjson_response(rx))
+}
+
+// GET /build/cache
+//
+// このホストのビルドキャッシュの記録（最後に使われたものから順に）
+pub async fn build_cache(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let entries = image_manager.build_cache().await?;
+
+    Ok(json_response(StatusCode::OK, &entries))
+}
+
+// POST /build/prune?all=1&filter=until=<時刻>&keep-storage=<バイト数>
+//
+// ビルド中は 409 を返す。
+pub async fn prune_build_cache(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let now = Utc::now();
+    let mut options = CachePruneOptions::default();
+    for filter in parse_filters(&req, &["until"])?.iter() {
+        if filter.negated {
+            return Err(ApiError::new(StatusCode::BAD_REQUEST, "The until filter cannot be negated"));
+        }
+        let time = parse_timestamp(&filter.value, now).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+        options.until = options.until.max(Some(time));
+    }
+    for (key, value) in query_params(&req) {
+        match key.as_str() {
+            "all" => options.all = matches!(value.as_str(), "1" | "true"),
+            "keep-storage" => {
+                let bytes = value.parse().map_err(|_| {
+                    ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid keep-storage (expected bytes): {}", value))
+                })?;
+                options.keep_storage = Some(bytes);
+            }
+            _ => {}
+        }
+    }
+
+    let image_manager = daemon.lock().await.image_manager.clone();
+    let report = image_manager.prune_build_cache(&options).await?;
+
+    Ok(json_response(StatusCode::OK, &report))
+}
+
+// X-Registry-Auth の認証情報（無ければ匿名）
+fn registry_auth(req: &Request<Body>) -> Result<Option<RegistryAuth>, ApiError> {
+    let Some(value) = req.headers().get(REGISTRY_AUTH_HEADER) else {
+        return Ok(None);
+    };
+    let value = value.to_str().map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    RegistryAuth::from_header(value)
+        .map(Some)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))
+}
+
+// 進捗の最後の行（成功した場合はイメージの ID、失敗した場合はエラー）を送る
+fn finish(progress: &mpsc::UnboundedSender<ProgressMessage>, result: Result<String, RockerError>) {
+    let message = match result {
+        Ok(image_id) => ProgressMessage::done(image_id),
+        Err(e) => ProgressMessage::error(e.to_string()),
+    };
+    let _ = progress.send(message);
+}
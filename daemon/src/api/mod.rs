@@ -0,0 +1,361 @@
+use chrono::{DateTime, Utc};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use rocker_core::{matches_label, parse_timestamp, ErrorClass, Filters, RockerError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{error, info};
+
+use crate::RockerDaemon;
+
+mod containers;
+mod exec;
+mod images;
+mod networks;
+mod schedules;
+mod secrets;
+mod swarm;
+mod system;
+mod volumes;
+
+// TCP で API を公開するアドレスを指定する環境変数
+const API_ADDR_ENV: &str = "ROCKER_API_ADDR";
+
+// API のエラー（ステータスコードとメッセージ、エラーコード、マネージャのエラーならその種類）
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+    // リリースをまたいで変わらないエラーの識別子（container_not_found など）
+    code: &'static str,
+    // エラーの対象（container・image・network・volume・secret・schedule・service・node・swarm）。CLI がメッセージとヒントを選ぶのに使う
+    kind: Option<&'static str>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        ApiError {
+            status,
+            message: message.into(),
+            code: match status {
+                StatusCode::BAD_REQUEST => "invalid_request",
+                StatusCode::NOT_FOUND => "not_found",
+                StatusCode::CONFLICT => "conflict",
+                _ => "internal_error",
+            },
+            kind: None,
+        }
+    }
+
+    fn into_response(self) -> Response<Body> {
+        let mut body = serde_json::json!({ "message": self.message, "code": self.code });
+        if let Some(kind) = self.kind {
+            body["kind"] = kind.into();
+        }
+        json_response(self.status, &body)
+    }
+}
+
+// マネージャのエラーを、そのバリアントに応じたステータスコードとエラーコードに変換する
+impl From<RockerError> for ApiError {
+    fn from(e: RockerError) -> Self {
+        let code = e.code();
+        let status = match code.class {
+            ErrorClass::NotFound => StatusCode::NOT_FOUND,
+            ErrorClass::Invalid => StatusCode::BAD_REQUEST,
+            ErrorClass::Conflict => StatusCode::CONFLICT,
+            ErrorClass::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorClass::Forbidden => StatusCode::FORBIDDEN,
+            ErrorClass::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorClass::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError {
+            status,
+            message: e.to_string(),
+            code: code.code,
+            kind: code.kind,
+        }
+    }
+}
+
+// 環境変数で指定された場合のみ TCP でも API を公開する（認証がないため既定では Unix ソケットのみ）
+pub async fn start_http_server(daemon: Arc<Mutex<RockerDaemon>>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let addr = match std::env::var(API_ADDR_ENV) {
+        Ok(addr) => addr,
+        Err(_) => return Ok(()),
+    };
+
+    let listener = TcpListener::bind(&addr).await?;
+    info!("Listening on tcp://{}", addr);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let daemon = Arc::clone(&daemon);
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, daemon).await {
+                error!("Error handling connection: {}", e);
+            }
+        });
+    }
+}
+
+// 1 つの接続で HTTP のリクエストを処理する
+pub async fn serve_connection<S>(stream: S, daemon: Arc<Mutex<RockerDaemon>>) -> Result<(), hyper::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    Http::new()
+        .serve_connection(stream, service_fn(move |req| handle(req, Arc::clone(&daemon))))
+        .await
+}
+
+async fn handle(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, Infallible> {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match (&method, segments.as_slice()) {
+        (&Method::GET, ["containers"]) => containers::list(req, daemon).await,
+        (&Method::POST, ["containers", "create"]) => containers::create(req, daemon).await,
+        (&Method::GET, ["containers", id]) => containers::inspect(&req, id, daemon).await,
+        (&Method::DELETE, ["containers", id]) => containers::remove(id, req, daemon).await,
+        (&Method::POST, ["containers", id, "start"]) => containers::start(id, daemon).await,
+        (&Method::POST, ["containers", id, "stop"]) => containers::stop(id, req, daemon).await,
+        (&Method::POST, ["containers", id, "kill"]) => containers::kill(id, req, daemon).await,
+        (&Method::POST, ["containers", id, "wait"]) => containers::wait(id, daemon).await,
+        (&Method::POST, ["containers", id, "pause"]) => containers::pause(id, daemon).await,
+        (&Method::POST, ["containers", id, "unpause"]) => containers::unpause(id, daemon).await,
+        (&Method::GET, ["containers", id, "top"]) => containers::top(id, daemon).await,
+        (&Method::GET, ["containers", id, "stats"]) => containers::stats(id, req, daemon).await,
+        (&Method::GET, ["containers", id, "export"]) => containers::export(id, daemon).await,
+        (&Method::GET, ["containers", id, "diff"]) => containers::diff(id, req, daemon).await,
+        (&Method::GET, ["containers", id, "logs"]) => containers::logs(id, req, daemon).await,
+        (&Method::PUT, ["containers", id, "archive"]) => containers::put_archive(id, req, daemon).await,
+        (&Method::DELETE, ["containers", id, "archive"]) => containers::remove_path(id, req, daemon).await,
+        (&Method::GET, ["containers", id, "exec"]) => exec::list(id, daemon).await,
+        (&Method::POST, ["containers", id, "exec"]) => exec::create(id, req, daemon).await,
+        (&Method::GET, ["exec", id]) => exec::inspect(id, daemon).await,
+        (&Method::POST, ["exec", id, "start"]) => exec::start(id, req, daemon).await,
+        (&Method::POST, ["auth"]) => images::auth(req, daemon).await,
+        (&Method::POST, ["build"]) => images::build(req, daemon).await,
+        (&Method::GET, ["build", "cache"]) => images::build_cache(daemon).await,
+        (&Method::POST, ["build", "prune"]) => images::prune_build_cache(req, daemon).await,
+        (&Method::GET, ["images"]) => images::list(req, daemon).await,
+        (&Method::POST, ["images", "create"]) => images::pull(req, daemon).await,
+        (&Method::POST, ["images", "import"]) => images::import(req, daemon).await,
+        (&Method::GET, ["images", name]) => images::inspect(name, daemon).await,
+        (&Method::POST, ["images", name, "push"]) => images::push(name, req, daemon).await,
+        (&Method::GET, ["images", name, "diff"]) => images::diff(name, req, daemon).await,
+        (&Method::GET, ["images", name, "sbom"]) => images::sbom(name, req, daemon).await,
+        (&Method::POST, ["images", name, "scan"]) => images::scan(name, daemon).await,
+        (&Method::POST, ["images", name, "squash"]) => images::squash(name, req, daemon).await,
+        (&Method::DELETE, ["images", name]) => images::remove(name, daemon).await,
+        (&Method::GET, ["networks"]) => networks::list(req, daemon).await,
+        (&Method::POST, ["networks", "create"]) => networks::create(req, daemon).await,
+        (&Method::POST, ["networks", "prune"]) => networks::prune(req, daemon).await,
+        (&Method::GET, ["networks", id]) => networks::inspect(id, daemon).await,
+        (&Method::DELETE, ["networks", id]) => networks::remove(id, daemon).await,
+        (&Method::POST, ["networks", id, "connect"]) => networks::connect(id, req, daemon).await,
+        (&Method::POST, ["networks", id, "disconnect"]) => networks::disconnect(id, req, daemon).await,
+        (&Method::GET, ["events"]) => system::events(req, daemon).await,
+        (&Method::GET, ["info"]) => system::info(daemon).await,
+        (&Method::GET, ["system", "df"]) => system::df(daemon).await,
+        (&Method::GET, ["secrets"]) => secrets::list(req, daemon).await,
+        (&Method::POST, ["secrets", "create"]) => secrets::create(req, daemon).await,
+        (&Method::GET, ["secrets", id]) => secrets::inspect(id, daemon).await,
+        (&Method::DELETE, ["secrets", id]) => secrets::remove(id, daemon).await,
+        (&Method::GET, ["schedules"]) => schedules::list(req, daemon).await,
+        (&Method::POST, ["schedules", "create"]) => schedules::create(req, daemon).await,
+        (&Method::GET, ["schedules", id]) => schedules::inspect(id, daemon).await,
+        (&Method::DELETE, ["schedules", id]) => schedules::remove(id, req, daemon).await,
+        (&Method::GET, ["swarm"]) => swarm::inspect(daemon).await,
+        (&Method::POST, ["swarm", "init"]) => swarm::init(req, daemon).await,
+        (&Method::POST, ["swarm", "join"]) => swarm::join(req, daemon).await,
+        (&Method::POST, ["swarm", "leave"]) => swarm::leave(req, daemon).await,
+        (&Method::POST, ["swarm", "rotate-token"]) => swarm::rotate_token(daemon).await,
+        (&Method::GET, ["nodes"]) => swarm::list_nodes(daemon).await,
+        (&Method::GET, ["nodes", id]) => swarm::inspect_node(id, daemon).await,
+        (&Method::POST, ["nodes", id, "update"]) => swarm::update_node(id, req, daemon).await,
+        (&Method::DELETE, ["nodes", id]) => swarm::remove_node(id, daemon).await,
+        (&Method::GET, ["services"]) => swarm::list_services(daemon).await,
+        (&Method::POST, ["services", "create"]) => swarm::create_service(req, daemon).await,
+        (&Method::GET, ["services", id]) => swarm::inspect_service(id, daemon).await,
+        (&Method::POST, ["services", id, "update"]) => swarm::update_service(id, req, daemon).await,
+        (&Method::POST, ["services", id, "scale"]) => swarm::scale_service(id, req, daemon).await,
+        (&Method::DELETE, ["services", id]) => swarm::remove_service(id, daemon).await,
+        (&Method::GET, ["volumes"]) => volumes::list(req, daemon).await,
+        (&Method::POST, ["volumes", "create"]) => volumes::create(req, daemon).await,
+        (&Method::POST, ["volumes", "prune"]) => volumes::prune(req, daemon).await,
+        (&Method::GET, ["volumes", id]) => volumes::inspect(id, daemon).await,
+        (&Method::DELETE, ["volumes", id]) => volumes::remove(id, daemon).await,
+        (&Method::POST, ["volumes", id, "clone"]) => volumes::clone(id, req, daemon).await,
+        (&Method::POST, ["volumes", id, "snapshots"]) => volumes::snapshot(id, req, daemon).await,
+        (&Method::POST, ["volumes", id, "snapshots", name, "restore"]) => volumes::restore(id, name, daemon).await,
+        (&Method::DELETE, ["volumes", id, "snapshots", name]) => volumes::remove_snapshot(id, name, daemon).await,
+        _ => Err(ApiError::new(StatusCode::NOT_FOUND, format!("No route for {} {}", method, path))),
+    };
+
+    Ok(result.unwrap_or_else(ApiError::into_response))
+}
+
+// filter=name=value・filter=name!=value のクエリパラメータ（allowed にない名前は 400 にする）
+fn parse_filters(req: &Request<Body>, allowed: &[&str]) -> Result<Filters, ApiError> {
+    let params = query_params(req);
+    let specs = params.iter().filter(|(key, _)| key == "filter").map(|(_, value)| value.as_str());
+    Filters::parse(specs, allowed).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))
+}
+
+// prune と events の filter=label=key[=value]・filter=label!=key[=value]・filter=until=<時刻>
+//
+// until は RFC 3339・Unix 秒・現在からの時間（24h など）で指定する。ラベルのフィルタは全てに一致する必要がある。
+struct LabelFilters {
+    filters: Filters,
+    until: Option<DateTime<Utc>>,
+}
+
+impl LabelFilters {
+    fn parse(req: &Request<Body>) -> Result<Self, ApiError> {
+        let now = Utc::now();
+        let filters = parse_filters(req, &["label", "until"])?;
+        let mut until = None;
+        for filter in filters.iter().filter(|filter| filter.name == "until") {
+            if filter.negated {
+                return Err(ApiError::new(StatusCode::BAD_REQUEST, "The until filter cannot be negated"));
+            }
+            let time = parse_timestamp(&filter.value, now).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+            until = until.max(Some(time));
+        }
+        Ok(LabelFilters { filters, until })
+    }
+
+    fn matches_labels(&self, labels: &HashMap<String, String>) -> bool {
+        self.filters.matches(|name, value| name != "label" || matches_label(labels, value))
+    }
+
+    // ラベルが一致し、until より前に作成されたか
+    fn matches(&self, labels: &HashMap<String, String>, created_at: DateTime<Utc>) -> bool {
+        self.matches_labels(labels) && self.until.is_none_or(|until| created_at < until)
+    }
+}
+
+// クエリ文字列を (キー, 値) の一覧にする（同じキーが複数回現れてもよい）
+fn query_params(req: &Request<Body>) -> Vec<(String, String)> {
+    req.uri()
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (percent_decode(key), percent_decode(value)),
+            None => (percent_decode(pair), String::new()),
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => match (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                (Some(high), Some(low)) => {
+                    decoded.push(high << 4 | low);
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+// リクエストボディを JSON として読み込む
+async fn read_json<T: DeserializeOwned>(req: Request<Body>) -> Result<T, ApiError> {
+    let body = hyper::body::to_bytes(req.into_body())
+        .await
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    serde_json::from_slice(&body).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid request: {}", e)))
+}
+
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response<Body> {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap_or_default()
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    Response::builder().status(status).body(Body::empty()).unwrap_or_default()
+}
+
+// 書き込まれたデータをそのまま返すボディ（spawn_blocking の中で tar を書き出す export などに使う）
+struct BodyWriter(mpsc::Sender<Vec<u8>>);
+
+impl std::io::Write for BodyWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(buf.to_vec())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "The client disconnected"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn stream_response(content_type: &str) -> (BodyWriter, Response<Body>) {
+    let (tx, mut rx) = mpsc::channel::<Vec<u8>>(16);
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        while let Some(data) = rx.recv().await {
+            // クライアントが切断したら受信側を閉じ、書き込みを BrokenPipe で失敗させる
+            if sender.send_data(data.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", content_type)
+        .body(body)
+        .unwrap_or_default();
+    (BodyWriter(tx), response)
+}
+
+// チャネルに届いた値を 1 行に 1 つの JSON として返し続ける（送信側が全て閉じるとボディも終わる）
+fn ndjson_response<T: Serialize + Send + 'static>(mut rx: mpsc::UnboundedReceiver<T>) -> Response<Body> {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        while let Some(value) = rx.recv().await {
+            let mut line = serde_json::to_vec(&value).unwrap_or_default();
+            line.push(b'\n');
+            // クライアントが切断したら送るのをやめる
+            if sender.send_data(line.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/x-ndjson")
+        .body(body)
+        .unwrap_or_default()
+}
@@ -0,0 +1,176 @@
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::{matches_label, Network, NetworkConfig, NetworkDriver, RockerError};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::{empty_response, json_response, parse_filters, read_json, ApiError, LabelFilters};
+use crate::network::DEFAULT_NETWORK_NAME;
+use crate::RockerDaemon;
+
+#[derive(Deserialize)]
+struct CreateRequest {
+    name: String,
+    #[serde(default)]
+    driver: Option<String>,
+    #[serde(default)]
+    subnet: Option<String>,
+    #[serde(default)]
+    gateway: Option<String>,
+    #[serde(default)]
+    ip_range: Option<String>,
+    #[serde(default)]
+    internal: bool,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+    #[serde(default)]
+    options: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct ConnectRequest {
+    container: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    #[serde(default)]
+    ip_address: Option<Ipv4Addr>,
+}
+
+#[derive(Deserialize)]
+struct DisconnectRequest {
+    container: String,
+}
+
+// GET /networks?filter=key=value
+//
+// Filters の規則（label は AND、他の同じキーは OR、key!=value は除外）で評価する
+pub async fn list(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = parse_filters(&req, &["driver", "id", "label", "name", "type"])?;
+
+    let daemon = daemon.lock().await;
+    let mut networks: Vec<Network> = daemon
+        .network_manager
+        .list_all()
+        .await?
+        .into_iter()
+        .filter(|network| filters.matches(|name, value| matches_filter(network, name, value)))
+        .collect();
+    networks.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(json_response(StatusCode::OK, &networks))
+}
+
+fn matches_filter(network: &Network, name: &str, value: &str) -> bool {
+    match name {
+        "driver" => network.driver.to_string() == value,
+        "id" => network.id.starts_with(value),
+        "name" => network.name.contains(value),
+        "label" => matches_label(&network.config.labels, value),
+        "type" => match value {
+            "builtin" => network.name == DEFAULT_NETWORK_NAME,
+            "custom" => network.name != DEFAULT_NETWORK_NAME,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// POST /networks/create
+pub async fn create(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let request: CreateRequest = read_json(req).await?;
+    let driver = match request.driver.as_deref() {
+        Some(driver) => NetworkDriver::parse(driver).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?,
+        None => NetworkDriver::Bridge,
+    };
+    let config = NetworkConfig {
+        subnet: request.subnet.unwrap_or_default(),
+        gateway: request.gateway.unwrap_or_default(),
+        ip_range: request.ip_range,
+        internal: request.internal,
+        labels: request.labels,
+        ..Default::default()
+    };
+
+    let mut daemon = daemon.lock().await;
+    let network = daemon
+        .network_manager
+        .create(&request.name, driver, config, request.options)
+        .await?;
+
+    Ok(json_response(StatusCode::CREATED, &network))
+}
+
+// GET /networks/{id}
+pub async fn inspect(network: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let network = daemon
+        .network_manager
+        .get(network)
+        .map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, network))
+}
+
+// DELETE /networks/{id}
+pub async fn remove(network: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    daemon.network_manager.remove(network).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /networks/prune?filter=label=key[=value]&filter=until=<時刻>
+pub async fn prune(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = LabelFilters::parse(&req)?;
+    let mut daemon = daemon.lock().await;
+    let removed = daemon
+        .network_manager
+        .prune(|network| filters.matches(&network.config.labels, network.created_at))
+        .await?;
+
+    Ok(json_response(StatusCode::OK, &serde_json::json!({ "networks_deleted": removed })))
+}
+
+// POST /networks/{id}/connect
+pub async fn connect(
+    network: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let request: ConnectRequest = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let daemon = &mut *daemon;
+    let endpoint = daemon
+        .container_manager
+        .connect_network(
+            &request.container,
+            network,
+            request.aliases,
+            request.ip_address,
+            &mut daemon.network_manager,
+        )
+        .await?;
+
+    Ok(json_response(StatusCode::OK, &endpoint))
+}
+
+// POST /networks/{id}/disconnect
+pub async fn disconnect(
+    network: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let request: DisconnectRequest = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let daemon = &mut *daemon;
+    daemon
+        .container_manager
+        .disconnect_network(&request.container, network, &mut daemon.network_manager)
+        .await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
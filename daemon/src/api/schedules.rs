@@ -0,0 +1,106 @@
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::{Container, RockerError, Schedule, ScheduleCreateRequest, ScheduleError, SCHEDULE_LABEL};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::{empty_response, json_response, parse_filters, query_params, read_json, ApiError};
+use crate::RockerDaemon;
+
+// GET /schedules?filter=name=value（Filters の規則で評価する）
+pub async fn list(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = parse_filters(&req, &["name"])?;
+
+    let daemon = daemon.lock().await;
+    let mut schedules: Vec<Schedule> = daemon
+        .schedule_manager
+        .list_all()
+        .into_iter()
+        .filter(|schedule| filters.matches(|_, name| schedule.name.contains(name)))
+        .collect();
+    schedules.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(json_response(StatusCode::OK, &schedules))
+}
+
+// POST /schedules/create（ボディは ScheduleCreateRequest）
+pub async fn create(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let request: ScheduleCreateRequest = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let schedule = daemon.schedule_manager.create(request).await?;
+
+    Ok(json_response(StatusCode::CREATED, &schedule))
+}
+
+// GET /schedules/{id}（実行の履歴も返す）
+pub async fn inspect(schedule: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let schedule = daemon
+        .schedule_manager
+        .get(schedule)
+        .map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, schedule))
+}
+
+// DELETE /schedules/{id}?force=1
+//
+// 実行のコンテナも削除する。動作中のコンテナがあれば、force の場合だけ停止してから削除する。
+pub async fn remove(
+    schedule: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let force = query_params(&req)
+        .iter()
+        .any(|(key, value)| key == "force" && matches!(value.as_str(), "1" | "true"));
+
+    let mut daemon_guard = daemon.lock().await;
+    let schedule = daemon_guard
+        .schedule_manager
+        .get(schedule)
+        .map_err(RockerError::from)?
+        .clone();
+    let containers: Vec<_> = daemon_guard
+        .container_manager
+        .list_all()
+        .await?
+        .into_iter()
+        .filter(|container| container.config.labels.get(SCHEDULE_LABEL) == Some(&schedule.id))
+        .collect();
+    let running: Vec<&Container> = containers
+        .iter()
+        .filter(|container| container.state.is_running() || container.state.is_paused())
+        .collect();
+    if !running.is_empty() && !force {
+        let running: Vec<&str> = running.iter().map(|container| container.name.as_str()).collect();
+        return Err(RockerError::from(ScheduleError::Running(format!(
+            "{} has running container(s) {}, stop them or use force",
+            schedule.name,
+            running.join(", ")
+        )))
+        .into());
+    }
+
+    // 先にスケジュールを削除し、停止を待つ間に新しい実行が始まらないようにする
+    daemon_guard.schedule_manager.remove(&schedule.id).await?;
+    drop(daemon_guard);
+    for container in running {
+        RockerDaemon::stop_container(&daemon, &container.id, None).await?;
+    }
+
+    let mut daemon_guard = daemon.lock().await;
+    let daemon = &mut *daemon_guard;
+    for container in &containers {
+        // --rm のコンテナは停止と同時に削除されている
+        if daemon.container_manager.get(&container.id).is_err() {
+            continue;
+        }
+        daemon
+            .container_manager
+            .remove(&container.id, true, &mut daemon.volume_manager)
+            .await?;
+    }
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
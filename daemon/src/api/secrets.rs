@@ -0,0 +1,65 @@
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::{matches_label, RockerError, Secret, SecretCreateRequest};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::{empty_response, json_response, parse_filters, read_json, ApiError};
+use crate::RockerDaemon;
+
+// GET /secrets?filter=key=value（キーは label・name、Filters の規則で評価する）
+pub async fn list(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = parse_filters(&req, &["label", "name"])?;
+
+    let daemon = daemon.lock().await;
+    let mut secrets: Vec<Secret> = daemon
+        .secret_manager
+        .list_all()
+        .into_iter()
+        .filter(|secret| {
+            filters.matches(|name, value| match name {
+                "name" => secret.name.contains(value),
+                _ => matches_label(&secret.labels, value),
+            })
+        })
+        .collect();
+    secrets.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(json_response(StatusCode::OK, &secrets))
+}
+
+// POST /secrets/create（ボディは SecretCreateRequest、内容は base64）
+pub async fn create(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let request: SecretCreateRequest = read_json(req).await?;
+    let data = request
+        .decode_data()
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?;
+
+    let mut daemon = daemon.lock().await;
+    let secret = daemon
+        .secret_manager
+        .create(&request.name, &data, request.labels)
+        .await?;
+
+    Ok(json_response(StatusCode::CREATED, &secret))
+}
+
+// GET /secrets/{id}（内容は返さない）
+pub async fn inspect(secret: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let secret = daemon
+        .secret_manager
+        .get(secret)
+        .map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, secret))
+}
+
+// DELETE /secrets/{id}
+pub async fn remove(secret: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    let daemon = &mut *daemon;
+    let references = daemon.container_manager.secret_references();
+    daemon.secret_manager.remove(secret, &references).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
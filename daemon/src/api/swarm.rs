@@ -0,0 +1,161 @@
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::{NodeUpdate, RockerError, ServiceSpec, SwarmInitRequest, SwarmJoinRequest};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::{empty_response, json_response, query_params, read_json, ApiError};
+use crate::RockerDaemon;
+
+// GET /swarm
+pub async fn inspect(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let info = daemon.swarm_manager.info().map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, &info))
+}
+
+// POST /swarm/init（ボディは SwarmInitRequest）
+pub async fn init(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let request: SwarmInitRequest = read_json(req).await?;
+
+    let mut guard = daemon.lock().await;
+    let info = guard.swarm_manager.init_swarm(request, &daemon).await?;
+
+    Ok(json_response(StatusCode::OK, &info))
+}
+
+// POST /swarm/join（ボディは SwarmJoinRequest）
+pub async fn join(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let request: SwarmJoinRequest = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let info = daemon.swarm_manager.join_swarm(request).await?;
+
+    Ok(json_response(StatusCode::OK, &info))
+}
+
+// POST /swarm/leave?force=1
+pub async fn leave(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let force = query_params(&req)
+        .iter()
+        .any(|(key, value)| key == "force" && matches!(value.as_str(), "1" | "true"));
+
+    let mut daemon = daemon.lock().await;
+    daemon.swarm_manager.leave(force).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /swarm/rotate-token（新しい参加トークンを返す）
+pub async fn rotate_token(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    let info = daemon.swarm_manager.rotate_join_token().await?;
+
+    Ok(json_response(StatusCode::OK, &info))
+}
+
+// GET /nodes
+pub async fn list_nodes(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let nodes = daemon.swarm_manager.nodes().map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, &nodes))
+}
+
+// GET /nodes/{id}
+pub async fn inspect_node(node: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let node = daemon.swarm_manager.node(node).map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, &node))
+}
+
+// POST /nodes/{id}/update（ボディは NodeUpdate）
+pub async fn update_node(node: &str, req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let update: NodeUpdate = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let node = daemon.swarm_manager.update_node(node, &update).await?;
+
+    Ok(json_response(StatusCode::OK, &node))
+}
+
+// DELETE /nodes/{id}
+pub async fn remove_node(node: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    daemon.swarm_manager.remove_node(node).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// GET /services
+pub async fn list_services(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let services = daemon.swarm_manager.services().map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, &services))
+}
+
+// POST /services/create（ボディは ServiceSpec）
+pub async fn create_service(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let spec: ServiceSpec = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let service = daemon.swarm_manager.create_service(spec).await?;
+
+    Ok(json_response(StatusCode::CREATED, &service))
+}
+
+// GET /services/{id}（タスクの一覧も返す）
+pub async fn inspect_service(service: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let service = daemon
+        .swarm_manager
+        .service(service)
+        .map_err(RockerError::from)?;
+
+    Ok(json_response(StatusCode::OK, &service))
+}
+
+// POST /services/{id}/update（ボディは ServiceSpec）
+pub async fn update_service(
+    service: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let spec: ServiceSpec = read_json(req).await?;
+
+    let mut daemon = daemon.lock().await;
+    let service = daemon.swarm_manager.update_service(service, spec).await?;
+
+    Ok(json_response(StatusCode::OK, &service))
+}
+
+// POST /services/{id}/scale?replicas=<数>
+pub async fn scale_service(
+    service: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let replicas = query_params(&req)
+        .into_iter()
+        .find(|(key, _)| key == "replicas")
+        .map(|(_, value)| value)
+        .ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "replicas is required"))?;
+    let replicas = replicas
+        .parse::<u64>()
+        .map_err(|_| ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid replicas: {}", replicas)))?;
+
+    let mut daemon = daemon.lock().await;
+    let service = daemon.swarm_manager.scale_service(service, replicas).await?;
+
+    Ok(json_response(StatusCode::OK, &service))
+}
+
+// DELETE /services/{id}
+pub async fn remove_service(service: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    daemon.swarm_manager.remove_service(service).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
@@ -0,0 +1,133 @@
+use chrono::Utc;
+use hyper::{Body, Request, Response, StatusCode};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex};
+use tracing::warn;
+
+use super::{json_response, ndjson_response, ApiError, LabelFilters};
+use crate::{resources, RockerDaemon};
+
+// GET /info
+//
+// コンテナとイメージの数、ホストの CPU とメモリ、コンテナに割り当てられる分と動作中のコンテナが予約している分
+pub async fn info(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let containers = daemon.container_manager.list_all().await?;
+    let running = containers.iter().filter(|container| container.state.is_running()).count();
+    let paused = containers.iter().filter(|container| container.state.is_paused()).count();
+    let images = daemon.image_manager.list_all().await?;
+    let capacity = daemon.container_manager.capacity();
+    let reserved = daemon.container_manager.reserved(None);
+
+    Ok(json_response(
+        StatusCode::OK,
+        &serde_json::json!({
+            "containers": containers.len(),
+            "containers_running": running,
+            "containers_paused": paused,
+            "containers_stopped": containers.len() - running - paused,
+            "images": images.len(),
+            "ncpu": resources::host_cpus(),
+            "mem_total": resources::host_memory_bytes(),
+            "resources": {
+                "allocatable_cpus": capacity.cpus,
+                "allocatable_memory": capacity.memory_bytes,
+                "reserved_cpus": reserved.cpus,
+                "reserved_memory": reserved.memory_bytes,
+                "reject_oversubscription": capacity.enforce,
+            },
+        }),
+    ))
+}
+
+// GET /system/df
+//
+// イメージ・コンテナ（書き込み層）・ボリューム・ビルドキャッシュの使用量（ボリュームはマウントしているコンテナの数も返す）
+//
+// ビルドキャッシュの大きさはイメージが使っていないレイヤーの分だけを（同じレイヤーは 1 度）数える。
+pub async fn df(daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let references = daemon.container_manager.volume_references();
+
+    let mut images = daemon.image_manager.list_all().await?;
+    images.sort_by_key(|image| Reverse(image.created_at));
+    let mut volumes = daemon.volume_manager.list_with_usage(&references).await;
+    volumes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut containers = Vec::new();
+    let all = daemon.container_manager.list_all().await?;
+    for container in &all {
+        containers.push(super::containers::with_size(&daemon, container).await);
+    }
+    containers.sort_by_key(|container| std::cmp::Reverse(container.created_at));
+
+    let images_size: u64 = images.iter().map(|image| image.size).sum();
+    let containers_size: u64 = containers.iter().filter_map(|container| container.size_rw).sum();
+    let build_cache = daemon.image_manager.build_cache().await?;
+    let mut layers = HashSet::new();
+    let build_cache_size: u64 = build_cache
+        .iter()
+        .filter(|entry| !entry.in_use)
+        .filter_map(|entry| Some((entry.diff_id.as_deref()?, entry.size)))
+        .filter(|(diff_id, _)| layers.insert(*diff_id))
+        .map(|(_, size)| size)
+        .sum();
+    let volumes_size: u64 = volumes
+        .iter()
+        .filter_map(|volume| volume.usage.as_ref())
+        .map(|usage| usage.size)
+        .sum();
+
+    Ok(json_response(
+        StatusCode::OK,
+        &serde_json::json!({
+            "images": images,
+            "images_size": images_size,
+            "containers": containers,
+            "containers_size": containers_size,
+            "volumes": volumes,
+            "volumes_size": volumes_size,
+            "build_cache": build_cache.len(),
+            "build_cache_size": build_cache_size,
+        }),
+    ))
+}
+
+// GET /events?filter=label=key[=value]&filter=until=<時刻>
+//
+// 接続してから発生したイベントを 1 行に 1 つの JSON で返し続ける。label はイベントの属性（コンテナや
+// ネットワークのラベルを含む）と比べ、until を指定した場合はその時刻にストリームを終える。
+pub async fn events(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = LabelFilters::parse(&req)?;
+    let mut events = daemon.lock().await.events.subscribe();
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        loop {
+            let next = match filters.until {
+                Some(until) => {
+                    let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+                    match tokio::time::timeout(remaining, events.recv()).await {
+                        Ok(next) => next,
+                        Err(_) => return,
+                    }
+                }
+                None => events.recv().await,
+            };
+            match next {
+                Ok(event) if !filters.matches_labels(&event.attributes) => continue,
+                Ok(event) => {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+                // 受信が追いつかなかったイベントは飛ばして続ける
+                Err(RecvError::Lagged(skipped)) => warn!("Event subscriber skipped {} events", skipped),
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+    Ok(ndjson_response(rx))
+}
@@ -0,0 +1,175 @@
+use hyper::{Body, Request, Response, StatusCode};
+use rocker_core::{matches_label, RockerError, Volume, VolumeConfig, VolumeDriver};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::{empty_response, json_response, parse_filters, query_params, read_json, ApiError, LabelFilters};
+use crate::RockerDaemon;
+
+#[derive(Deserialize)]
+struct CreateRequest {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    driver: Option<String>,
+    #[serde(default)]
+    driver_opts: HashMap<String, String>,
+    #[serde(default)]
+    labels: HashMap<String, String>,
+}
+
+// GET /volumes?filter=key=value
+//
+// Filters の規則（label は AND、他の同じキーは OR、key!=value は除外）で評価する
+pub async fn list(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = parse_filters(&req, &["dangling", "driver", "label", "name"])?;
+    if let Some(filter) = filters
+        .iter()
+        .find(|filter| filter.name == "dangling" && !matches!(filter.value.as_str(), "true" | "false" | "1" | "0"))
+    {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid dangling filter: {}", filter.value)));
+    }
+
+    // size=1 の場合のみ使用量を付ける（ボリュームのディレクトリを走査するため）
+    let with_size = query_params(&req)
+        .iter()
+        .any(|(key, value)| key == "size" && matches!(value.as_str(), "1" | "true"));
+
+    let daemon = daemon.lock().await;
+    let references = daemon.container_manager.volume_references();
+    let volumes = if with_size {
+        daemon.volume_manager.list_with_usage(&references).await
+    } else {
+        daemon.volume_manager.list_all().await?
+    };
+    let mut volumes: Vec<Volume> = volumes
+        .into_iter()
+        .filter(|volume| {
+            let dangling = !references.contains_key(&volume.name) && !references.contains_key(&volume.id);
+            filters.matches(|name, value| matches_filter(volume, dangling, name, value))
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(json_response(StatusCode::OK, &volumes))
+}
+
+fn matches_filter(volume: &Volume, dangling: bool, name: &str, value: &str) -> bool {
+    match name {
+        "dangling" => dangling == matches!(value, "true" | "1"),
+        "driver" => volume.driver.to_string() == value,
+        "name" => volume.name.contains(value),
+        "label" => matches_label(&volume.labels, value),
+        _ => false,
+    }
+}
+
+// POST /volumes/create
+pub async fn create(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let request: CreateRequest = read_json(req).await?;
+    let driver = match request.driver.as_deref() {
+        Some(driver) => VolumeDriver::parse(driver).map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e))?,
+        None => VolumeDriver::Local,
+    };
+    let config = VolumeConfig {
+        driver_opts: request.driver_opts,
+        labels: request.labels,
+    };
+
+    let mut daemon = daemon.lock().await;
+    let volume = daemon.volume_manager.create(&request.name, driver, config).await?;
+
+    Ok(json_response(StatusCode::CREATED, &volume))
+}
+
+// GET /volumes/{id}
+pub async fn inspect(volume: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let daemon = daemon.lock().await;
+    let references = daemon.container_manager.volume_references();
+    let mut volume = daemon
+        .volume_manager
+        .get(volume)
+        .map_err(RockerError::from)?
+        .clone();
+    volume.usage = Some(daemon.volume_manager.usage(&volume, &references).await);
+
+    Ok(json_response(StatusCode::OK, &volume))
+}
+
+// DELETE /volumes/{id}
+pub async fn remove(volume: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    let references = daemon.container_manager.volume_references();
+    daemon.volume_manager.remove(volume, &references).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// POST /volumes/prune?filter=label=key[=value]&filter=until=<時刻>
+pub async fn prune(req: Request<Body>, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let filters = LabelFilters::parse(&req)?;
+    let mut daemon = daemon.lock().await;
+    let references = daemon.container_manager.volume_references();
+    let (removed, reclaimed) = daemon
+        .volume_manager
+        .prune(&references, |volume| filters.matches(&volume.labels, volume.created_at))
+        .await?;
+
+    Ok(json_response(
+        StatusCode::OK,
+        &serde_json::json!({ "volumes_deleted": removed, "space_reclaimed": reclaimed }),
+    ))
+}
+
+#[derive(Deserialize)]
+struct NameRequest {
+    name: String,
+}
+
+// POST /volumes/{id}/clone
+pub async fn clone(
+    volume: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let request: NameRequest = read_json(req).await?;
+    let mut daemon = daemon.lock().await;
+    let volume = daemon.volume_manager.clone_volume(volume, &request.name).await?;
+
+    Ok(json_response(StatusCode::CREATED, &volume))
+}
+
+// POST /volumes/{id}/snapshots
+pub async fn snapshot(
+    volume: &str,
+    req: Request<Body>,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let request: NameRequest = read_json(req).await?;
+    let mut daemon = daemon.lock().await;
+    let snapshot = daemon.volume_manager.snapshot(volume, &request.name).await?;
+
+    Ok(json_response(StatusCode::CREATED, &snapshot))
+}
+
+// POST /volumes/{id}/snapshots/{name}/restore
+pub async fn restore(volume: &str, snapshot: &str, daemon: Arc<Mutex<RockerDaemon>>) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    daemon.volume_manager.restore(volume, snapshot).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
+
+// DELETE /volumes/{id}/snapshots/{name}
+pub async fn remove_snapshot(
+    volume: &str,
+    snapshot: &str,
+    daemon: Arc<Mutex<RockerDaemon>>,
+) -> Result<Response<Body>, ApiError> {
+    let mut daemon = daemon.lock().await;
+    daemon.volume_manager.remove_snapshot(volume, snapshot).await?;
+
+    Ok(empty_response(StatusCode::NO_CONTENT))
+}
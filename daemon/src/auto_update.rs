@@ -0,0 +1,361 @@
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime};
+use rocker_core::{
+    Container, ContainerError, Event, EventType, Image, RockerError, AUTO_UPDATE_LABEL, SCHEDULE_LABEL, TASK_LABEL,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+use crate::{image, RockerDaemon, DAEMON_CONFIG_PATH};
+
+// 更新を確かめる間隔の既定値（秒）
+const DEFAULT_INTERVAL: u64 = 3600;
+
+// デーモンの設定ファイルの auto-update（ラベル com.rocker.auto-update=true のコンテナのイメージの更新を確かめる）
+#[derive(Debug, Clone, Deserialize)]
+pub struct AutoUpdateConfig {
+    // 確かめる間隔（秒）
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    // コンテナを作り直してよい時間帯（デーモンのタイムゾーンの "HH:MM-HH:MM"、日をまたいでもよい）
+    #[serde(default, rename = "maintenance-window")]
+    pub maintenance_window: Option<String>,
+    // 作り直した後、どのコンテナも使わずタグも無くなった古いイメージを削除する
+    #[serde(default)]
+    pub cleanup: bool,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_INTERVAL
+}
+
+#[derive(Deserialize)]
+struct DaemonConfig {
+    #[serde(rename = "auto-update")]
+    auto_update: Option<AutoUpdateConfig>,
+}
+
+// デーモンの設定ファイルから自動更新の設定を読む（無ければ自動更新しない）
+pub fn load_config() -> Option<AutoUpdateConfig> {
+    let content = std::fs::read_to_string(DAEMON_CONFIG_PATH).ok()?;
+    match serde_json::from_str::<DaemonConfig>(&content) {
+        Ok(config) => config.auto_update,
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", DAEMON_CONFIG_PATH, e);
+            None
+        }
+    }
+}
+
+// コンテナを作り直してよい時間帯
+struct MaintenanceWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid maintenance-window {:?} (expected HH:MM-HH:MM)", s);
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+        if start == end {
+            return Err(invalid());
+        }
+        Ok(MaintenanceWindow { start, end })
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            // 22:00-04:00 のように日をまたぐ
+            time >= self.start || time < self.end
+        }
+    }
+
+    // 次に時間帯が始まるまでの時間
+    fn until_open(&self, now: DateTime<Local>) -> Duration {
+        let now = now.naive_local();
+        let mut start = now.date().and_time(self.start);
+        if start <= now {
+            start += ChronoDuration::days(1);
+        }
+        (start - now).to_std().unwrap_or_default()
+    }
+}
+
+// 設定の間隔ごとに、ラベルを付けたコンテナのイメージのタグがレジストリで新しいイメージを指していないかを確かめ、
+// 指していれば取得して同じ設定でコンテナを作り直す
+//
+// スウォームのタスクとスケジュールの実行のコンテナは、それぞれの仕組みがイメージを決めるので対象にしない。
+pub fn spawn_watcher(daemon: Arc<Mutex<RockerDaemon>>) {
+    let Some(config) = load_config() else {
+        return;
+    };
+    if config.interval == 0 {
+        warn!("Ignoring auto-update in {}: interval must be at least 1 second", DAEMON_CONFIG_PATH);
+        return;
+    }
+    let window = match config.maintenance_window.as_deref().map(MaintenanceWindow::parse).transpose() {
+        Ok(window) => window,
+        Err(e) => {
+            warn!("Ignoring auto-update in {}: {}", DAEMON_CONFIG_PATH, e);
+            return;
+        }
+    };
+    info!(
+        "Checking images of containers labeled {}=true every {}s",
+        AUTO_UPDATE_LABEL, config.interval
+    );
+
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(config.interval)).await;
+            if let Some(window) = &window {
+                let now = Local::now();
+                if !window.contains(now.time()) {
+                    let wait = window.until_open(now);
+                    info!("Waiting {}s for the maintenance window to check for image updates", wait.as_secs());
+                    tokio::time::sleep(wait).await;
+                }
+            }
+            if let Err(e) = run_once(&daemon, &config, window.as_ref()).await {
+                warn!("Auto-update: {}", e);
+            }
+        }
+    });
+}
+
+async fn run_once(
+    daemon: &Arc<Mutex<RockerDaemon>>,
+    config: &AutoUpdateConfig,
+    window: Option<&MaintenanceWindow>,
+) -> Result<(), RockerError> {
+    let (containers, image_manager) = {
+        let daemon = daemon.lock().await;
+        let containers = daemon.container_manager.list_all().await?;
+        (containers, daemon.image_manager.clone())
+    };
+
+    // 作成したときのタグごとにまとめる（同じタグは一度だけ確かめる）
+    let mut by_reference: BTreeMap<String, Vec<Container>> = BTreeMap::new();
+    for container in containers {
+        let labels = &container.config.labels;
+        if !container.state.is_running()
+            || labels.get(AUTO_UPDATE_LABEL).map(String::as_str) != Some("true")
+            || labels.contains_key(TASK_LABEL)
+            || labels.contains_key(SCHEDULE_LABEL)
+        {
+            continue;
+        }
+        let reference = container
+            .image_name
+            .clone()
+            .or_else(|| image_manager.get(&container.config.image).ok().and_then(|image| image.full_name()));
+        match reference {
+            Some(reference) => by_reference.entry(reference).or_default().push(container),
+            None => warn!("Container {} has no image tag to follow for updates", container.name),
+        }
+    }
+
+    let mut replaced_images = HashSet::new();
+    'references: for (reference, containers) in by_reference {
+        let latest = match latest_image(&image_manager, &reference).await {
+            Ok(image) => image,
+            Err(e) => {
+                // レジストリに届かなくても、手元でタグが移っていれば作り直す
+                warn!("Failed to check {} for updates: {}", reference, e);
+                match image_manager.get(&reference) {
+                    Ok(image) => image,
+                    Err(_) => continue,
+                }
+            }
+        };
+
+        for container in containers.iter().filter(|container| container.config.image != latest.id) {
+            if window.is_some_and(|window| !window.contains(Local::now().time())) {
+                info!("The maintenance window closed, leaving the remaining updates for the next one");
+                break 'references;
+            }
+            match recreate(daemon, container, &latest, &reference).await {
+                Ok(()) => {
+                    replaced_images.insert(container.config.image.clone());
+                }
+                Err(e) => {
+                    warn!("Failed to update container {} to {}: {}", container.name, reference, e);
+                    daemon.lock().await.events.publish(
+                        Event::new(EventType::Container, "update-failed", &container.id)
+                            .with_labels(&container.config.labels)
+                            .with_attribute("name", &container.name)
+                            .with_attribute("image", &reference)
+                            .with_attribute("error", &e.to_string()),
+                    );
+                }
+            }
+        }
+    }
+
+    if config.cleanup {
+        remove_unused_images(daemon, &replaced_images).await;
+    }
+    Ok(())
+}
+
+// タグの最新のイメージ（レジストリのダイジェストが手元のイメージのものと違えば取得する）
+//
+// 手元でビルドしたイメージ（レジストリのダイジェストが無い）は、手元でタグが移ったかだけを見る。
+async fn latest_image(images: &image::Manager, reference: &str) -> Result<Image, RockerError> {
+    let local = images.get(reference).ok();
+    if let Some(image) = local.as_ref().filter(|image| image.repo_digests.is_empty()) {
+        return Ok(image.clone());
+    }
+    let digest = images.remote_digest(reference, None).await?;
+    match local {
+        Some(image) if image.repo_digests.contains(&digest) => Ok(image),
+        _ => {
+            info!("Pulling {} ({}) for auto-update", reference, digest);
+            let (tx, _rx) = mpsc::unbounded_channel();
+            images.pull(reference, None, &tx).await
+        }
+    }
+}
+
+// コンテナを同じ設定で新しいイメージから作り直す
+//
+// 古いコンテナは停止して名前を譲り、新しいコンテナが起動してから削除する（ボリュームは新しいコンテナが引き継ぐ）。
+// 新しいコンテナを起動できなければ、それを削除して古いコンテナを元の名前で起動し直す。古いコンテナの停止を待つ間は
+// デーモンのロックを外す。
+async fn recreate(
+    daemon: &Arc<Mutex<RockerDaemon>>,
+    container: &Container,
+    latest: &Image,
+    reference: &str,
+) -> Result<(), RockerError> {
+    let (old, config) = {
+        let daemon = daemon.lock().await;
+        // ロックを待つ間に変わっていないかを確かめる
+        let old = daemon.container_manager.get(&container.id)?.clone();
+        if !old.state.is_running() {
+            return Err(ContainerError::NotRunning(old.name.clone()).into());
+        }
+
+        let mut config = old.config.clone();
+        if let Ok(old_image) = daemon.image_manager.get(&old.config.image) {
+            config.strip_image_defaults(&old_image.config);
+        }
+        config.image = latest.id.clone();
+        (old, config)
+    };
+
+    info!("Updating container {} to {} ({})", old.name, reference, latest.id);
+    RockerDaemon::stop_container(daemon, &old.id, None).await?;
+
+    let mut daemon_guard = daemon.lock().await;
+    let daemon = &mut *daemon_guard;
+    // 停止を待つ間に起動し直されたコンテナは作り直さない
+    if daemon.container_manager.get(&old.id)?.state.is_running() {
+        let message = format!("{} was started again while it was stopping", old.name);
+        return Err(ContainerError::AlreadyRunning(message).into());
+    }
+    let temporary_name = format!("{}-old-{}", old.name, old.id.chars().take(12).collect::<String>());
+    daemon.container_manager.rename(&old.id, &temporary_name).await?;
+
+    let result = start_replacement(daemon, &old, config, latest).await;
+    let new_id = match result {
+        Ok(new_id) => new_id,
+        Err((new_id, e)) => {
+            if let Some(new_id) = new_id {
+                if let Err(e) = daemon
+                    .container_manager
+                    .remove(&new_id, false, &mut daemon.volume_manager)
+                    .await
+                {
+                    warn!("Failed to remove container {} after a failed update: {}", new_id, e);
+                }
+            }
+            daemon.container_manager.rename(&old.id, &old.name).await?;
+            daemon
+                .container_manager
+                .start(&old.id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+                .await?;
+            return Err(e);
+        }
+    };
+
+    daemon
+        .container_manager
+        .remove(&old.id, false, &mut daemon.volume_manager)
+        .await?;
+    let new = daemon.container_manager.get(&new_id)?;
+    daemon.events.publish(
+        Event::new(EventType::Container, "update", &new.id)
+            .with_labels(&new.config.labels)
+            .with_attribute("name", &new.name)
+            .with_attribute("image", reference)
+            .with_attribute("oldImage", &old.config.image)
+            .with_attribute("newImage", &latest.id)
+            .with_attribute("oldContainer", &old.id),
+    );
+    info!("Updated container {} to {} ({})", new.name, reference, new.id);
+    Ok(())
+}
+
+// 新しいコンテナを作成し、古いコンテナの追加のネットワークに接続してから起動する
+//
+// 失敗したときは作成したコンテナの ID（あれば）とエラーを返す。
+async fn start_replacement(
+    daemon: &mut RockerDaemon,
+    old: &Container,
+    config: rocker_core::ContainerConfig,
+    latest: &Image,
+) -> Result<String, (Option<String>, RockerError)> {
+    let new = daemon
+        .container_manager
+        .create(&old.name, config, latest, &daemon.network_manager)
+        .await
+        .map_err(|e| (None, e))?;
+    let failed = |e: RockerError| (Some(new.id.clone()), e);
+
+    // eth0 は network_mode のネットワークで、起動のときに接続される
+    for (network, endpoint) in old.networks.iter().filter(|(_, endpoint)| endpoint.interface != "eth0") {
+        let ip_address = endpoint.requested_ip.as_deref().and_then(|ip| ip.parse::<Ipv4Addr>().ok());
+        daemon
+            .container_manager
+            .connect_network(&new.id, network, endpoint.aliases.clone(), ip_address, &mut daemon.network_manager)
+            .await
+            .map_err(failed)?;
+    }
+    daemon
+        .container_manager
+        .start(&new.id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+        .await
+        .map_err(failed)?;
+    Ok(new.id)
+}
+
+// 置き換えたイメージのうち、タグが無くなりどのコンテナも使わなくなったものを削除する
+async fn remove_unused_images(daemon: &Arc<Mutex<RockerDaemon>>, candidates: &HashSet<String>) {
+    let daemon = daemon.lock().await;
+    let used: HashSet<String> = match daemon.container_manager.list_all().await {
+        Ok(containers) => containers.into_iter().map(|container| container.config.image).collect(),
+        Err(e) => {
+            warn!("Failed to list containers for image cleanup: {}", e);
+            return;
+        }
+    };
+    for id in candidates.iter().filter(|id| !used.contains(*id)) {
+        match daemon.image_manager.get(id) {
+            Ok(image) if image.repo.is_none() => {}
+            _ => continue,
+        }
+        match daemon.image_manager.remove(id, &[]).await {
+            Ok(_) => info!("Removed image {} replaced by auto-update", id),
+            Err(e) => warn!("Failed to remove image {} replaced by auto-update: {}", id, e),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use rocker_core::{default_device_rules, ContainerConfig, ContainerError, DeviceCgroupRule, DeviceType};
+use nix::libc;
+use nix::sys::stat::{major, makedev, minor, mknod, Mode, SFlag};
+use std::fs::File;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+use super::rootfs::secure_join;
+
+// cgroup v2 にはデバイス用のファイルが無いため、eBPF プログラムでアクセスを制御する
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_PROG_ATTACH: libc::c_long = 8;
+const BPF_PROG_TYPE_CGROUP_DEVICE: u32 = 15;
+const BPF_CGROUP_DEVICE: u32 = 6;
+
+const BPF_DEVCG_ACC_MKNOD: i32 = 1;
+const BPF_DEVCG_ACC_READ: i32 = 2;
+const BPF_DEVCG_ACC_WRITE: i32 = 4;
+const BPF_DEVCG_DEV_BLOCK: i32 = 1;
+const BPF_DEVCG_DEV_CHAR: i32 = 2;
+
+// 命令コード
+const LDX_MEM_W: u8 = 0x61;
+const ALU_AND_K: u8 = 0x54;
+const ALU_RSH_K: u8 = 0x74;
+const ALU_MOV_K: u8 = 0xb4;
+const ALU_MOV_X: u8 = 0xbc;
+const JMP_JNE_K: u8 = 0x55;
+const JMP_JNE_X: u8 = 0x5d;
+const JMP_EXIT: u8 = 0x95;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BpfInsn {
+    code: u8,
+    regs: u8,
+    off: i16,
+    imm: i32,
+}
+
+fn insn(code: u8, dst: u8, src: u8, off: i16, imm: i32) -> BpfInsn {
+    BpfInsn {
+        code,
+        regs: (src << 4) | dst,
+        off,
+        imm,
+    }
+}
+
+#[repr(C)]
+struct BpfProgLoadAttr {
+    prog_type: u32,
+    insn_cnt: u32,
+    insns: u64,
+    license: u64,
+    log_level: u32,
+    log_size: u32,
+    log_buf: u64,
+    kern_version: u32,
+    prog_flags: u32,
+}
+
+#[repr(C)]
+struct BpfProgAttachAttr {
+    target_fd: u32,
+    attach_bpf_fd: u32,
+    attach_type: u32,
+    attach_flags: u32,
+}
+
+// --device で指定されたデバイスノードを rootfs 内に作成する
+pub fn create_device_nodes(rootfs: &Path, config: &ContainerConfig) -> Result<(), ContainerError> {
+    for device in &config.devices {
+        let metadata = std::fs::metadata(&device.path_on_host).map_err(|e| {
+            ContainerError::Create(format!("Failed to stat device {}: {}", device.path_on_host, e))
+        })?;
+
+        let kind = if metadata.file_type().is_char_device() {
+            SFlag::S_IFCHR
+        } else if metadata.file_type().is_block_device() {
+            SFlag::S_IFBLK
+        } else {
+            return Err(ContainerError::Create(format!("Not a device: {}", device.path_on_host)));
+        };
+
+        // イメージの dev -> /dev のようなシンボリックリンクを辿ってホストに作らないよう、親ディレクトリを rootfs の
+        // 中で解決する（最後の要素がリンクならリンク自体を置き換える）
+        let path = Path::new(&device.path_in_container);
+        let name = path
+            .file_name()
+            .ok_or_else(|| ContainerError::Create(format!("Invalid device path: {}", device.path_in_container)))?;
+        let parent = secure_join(rootfs, path.parent().unwrap_or(Path::new("/")))
+            .map_err(|e| ContainerError::Create(format!("{}: {}", device.path_in_container, e)))?;
+        std::fs::create_dir_all(&parent).map_err(|e| ContainerError::Create(e.to_string()))?;
+        let target = parent.join(name);
+        if std::fs::symlink_metadata(&target).is_ok() {
+            std::fs::remove_file(&target).map_err(|e| ContainerError::Create(e.to_string()))?;
+        }
+
+        let mode = Mode::from_bits_truncate(metadata.permissions().mode() & 0o7777);
+        let dev = makedev(major(metadata.rdev()), minor(metadata.rdev()));
+        mknod(&target, kind, mode, dev).map_err(|e| {
+            ContainerError::Create(format!("Failed to create device {}: {}", target.display(), e))
+        })?;
+        std::os::unix::fs::chown(&target, Some(metadata.uid()), Some(metadata.gid()))
+            .map_err(|e| ContainerError::Create(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+// コンテナに許可するデバイスのルール一覧（既定のルール + --device + --device-cgroup-rule）
+pub fn device_rules(config: &ContainerConfig) -> Result<Vec<DeviceCgroupRule>, ContainerError> {
+    if config.privileged {
+        return Ok(vec![DeviceCgroupRule {
+            device_type: DeviceType::All,
+            major: None,
+            minor: None,
+            permissions: "rwm".to_string(),
+        }]);
+    }
+
+    let mut rules = default_device_rules();
+
+    for device in &config.devices {
+        let metadata = std::fs::metadata(&device.path_on_host).map_err(|e| {
+            ContainerError::Create(format!("Failed to stat device {}: {}", device.path_on_host, e))
+        })?;
+        let device_type = if metadata.file_type().is_block_device() {
+            DeviceType::Block
+        } else {
+            DeviceType::Char
+        };
+
+        rules.push(DeviceCgroupRule::new(
+            device_type,
+            major(metadata.rdev()) as u32,
+            minor(metadata.rdev()) as u32,
+            &device.cgroup_permissions,
+        ));
+    }
+
+    rules.extend(config.device_cgroup_rules.iter().cloned());
+    Ok(rules)
+}
+
+// ルールから eBPF プログラムを生成して cgroup にアタッチする
+pub fn apply_device_filter(cgroup_dir: &Path, rules: &[DeviceCgroupRule]) -> Result<(), ContainerError> {
+    let program = build_device_filter(rules);
+    let license = b"Apache\0";
+
+    let load_attr = BpfProgLoadAttr {
+        prog_type: BPF_PROG_TYPE_CGROUP_DEVICE,
+        insn_cnt: program.len() as u32,
+        insns: program.as_ptr() as u64,
+        license: license.as_ptr() as u64,
+        log_level: 0,
+        log_size: 0,
+        log_buf: 0,
+        kern_version: 0,
+        prog_flags: 0,
+    };
+
+    let prog_fd = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            BPF_PROG_LOAD,
+            &load_attr as *const BpfProgLoadAttr,
+            std::mem::size_of::<BpfProgLoadAttr>(),
+        )
+    };
+    if prog_fd < 0 {
+        return Err(ContainerError::Runtime(format!(
+            "Failed to load device filter: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+
+    let cgroup = File::open(cgroup_dir);
+    let result = match cgroup {
+        Ok(cgroup) => {
+            let attach_attr = BpfProgAttachAttr {
+                target_fd: cgroup.as_raw_fd() as u32,
+                attach_bpf_fd: prog_fd as u32,
+                attach_type: BPF_CGROUP_DEVICE,
+                attach_flags: 0,
+            };
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_bpf,
+                    BPF_PROG_ATTACH,
+                    &attach_attr as *const BpfProgAttachAttr,
+                    std::mem::size_of::<BpfProgAttachAttr>(),
+                )
+            };
+            if ret < 0 {
+                Err(ContainerError::Runtime(format!(
+                    "Failed to attach device filter: {}",
+                    std::io::Error::last_os_error()
+                )))
+            } else {
+                Ok(())
+            }
+        }
+        Err(e) => Err(ContainerError::Runtime(format!(
+            "Failed to open cgroup {}: {}",
+            cgroup_dir.display(),
+            e
+        ))),
+    };
+
+    // アタッチ後はカーネルがプログラムを保持するため fd は閉じてよい
+    unsafe {
+        libc::close(prog_fd as i32);
+    }
+
+    result
+}
+
+// ルールに一致すれば 1（許可）、どれにも一致しなければ 0（拒否）を返すプログラム
+fn build_device_filter(rules: &[DeviceCgroupRule]) -> Vec<BpfInsn> {
+    // r2 = デバイス種別, r3 = アクセス種別, r4 = major, r5 = minor
+    let mut program = vec![
+        insn(LDX_MEM_W, 2, 1, 0, 0),
+        insn(ALU_AND_K, 2, 0, 0, 0xFFFF),
+        insn(LDX_MEM_W, 3, 1, 0, 0),
+        insn(ALU_RSH_K, 3, 0, 0, 16),
+        insn(LDX_MEM_W, 4, 1, 4, 0),
+        insn(LDX_MEM_W, 5, 1, 8, 0),
+    ];
+
+    for rule in rules {
+        let mut block = Vec::new();
+
+        match rule.device_type {
+            DeviceType::Char => block.push(insn(JMP_JNE_K, 2, 0, 0, BPF_DEVCG_DEV_CHAR)),
+            DeviceType::Block => block.push(insn(JMP_JNE_K, 2, 0, 0, BPF_DEVCG_DEV_BLOCK)),
+            DeviceType::All => {}
+        }
+
+        let access = rule.permissions.chars().fold(0, |access, c| match c {
+            'r' => access | BPF_DEVCG_ACC_READ,
+            'w' => access | BPF_DEVCG_ACC_WRITE,
+            'm' => access | BPF_DEVCG_ACC_MKNOD,
+            _ => access,
+        });
+        if access != BPF_DEVCG_ACC_READ | BPF_DEVCG_ACC_WRITE | BPF_DEVCG_ACC_MKNOD {
+            // 要求されたアクセスがすべて許可に含まれるか
+            block.push(insn(ALU_MOV_X, 1, 3, 0, 0));
+            block.push(insn(ALU_AND_K, 1, 0, 0, access));
+            block.push(insn(JMP_JNE_X, 1, 3, 0, 0));
+        }
+
+        if let Some(major) = rule.major {
+            block.push(insn(JMP_JNE_K, 4, 0, 0, major as i32));
+        }
+        if let Some(minor) = rule.minor {
+            block.push(insn(JMP_JNE_K, 5, 0, 0, minor as i32));
+        }
+
+        block.push(insn(ALU_MOV_K, 0, 0, 0, 1));
+        block.push(insn(JMP_EXIT, 0, 0, 0, 0));
+
+        // 条件不一致時は次のルールの先頭へジャンプ
+        let len = block.len();
+        for (i, instruction) in block.iter_mut().enumerate() {
+            if instruction.code == JMP_JNE_K || instruction.code == JMP_JNE_X {
+                instruction.off = (len - i - 1) as i16;
+            }
+        }
+        program.extend(block);
+    }
+
+    program.push(insn(ALU_MOV_K, 0, 0, 0, 0));
+    program.push(insn(JMP_EXIT, 0, 0, 0, 0));
+    program
+}
@@ -0,0 +1,66 @@
+use chrono::Utc;
+use nix::unistd::Pid;
+use rocker_core::{ContainerError, ContainerState, RockerError};
+use tracing::info;
+
+use super::{
+    connect_endpoints, container_event, create_volumes, mount_volumes, release_endpoints, release_volumes, Manager,
+};
+use crate::{network, volume};
+
+impl Manager {
+    // --dry-run のコンテナを起動したことにする
+    //
+    // ボリュームの作成とネットワークのアドレスの割り当て、状態の変更とイベントは通常の起動と同じように
+    // 行うが、プロセス・名前空間・cgroup・マウントは作らず、フックも実行しない。コンテナは stop される
+    // まで動作中のままになる。
+    pub(super) async fn start_simulated(
+        &mut self,
+        id: &str,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+    ) -> Result<(), RockerError> {
+        let rootfs = self.rootfs_dir(id);
+        let inherited_mounts = self.volumes_from(id)?;
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+
+        if container.state.is_running() {
+            return Err(ContainerError::AlreadyRunning(id.to_string()).into());
+        }
+
+        container.config.mounts.extend(inherited_mounts);
+        create_volumes(container, &rootfs, volumes).await?;
+
+        let mut config = container.config.clone();
+        if let Err(e) = mount_volumes(id, &mut config, volumes).await {
+            release_volumes(container, volumes);
+            return Err(e);
+        }
+        // プロセスが無いため pid 0 で接続する（--dry-run のネットワークドライバは pid を使わない）
+        if let Err(e) = connect_endpoints(container, &config, Pid::from_raw(0), networks).await {
+            release_endpoints(container, networks).await;
+            release_volumes(container, volumes);
+            return Err(e);
+        }
+        info!("Started container {} (dry run)", id);
+
+        container.state = ContainerState::Running;
+        container.pid = None;
+        container.exit_code = None;
+        container.oom_killed = false;
+        container.restart_count = 0;
+        container.started_at = Some(Utc::now());
+        container.finished_at = None;
+        self.events.publish(container_event("start", container));
+
+        self.save(id).await
+    }
+}
+
+// --dry-run ではプロセスや cgroup が無いため行えない操作のエラー
+pub(super) fn unsupported(operation: &str) -> ContainerError {
+    ContainerError::Runtime(format!("{} is not available in dry-run mode", operation))
+}
@@ -0,0 +1,283 @@
+use chrono::Utc;
+use rocker_core::{lookup_user, ContainerError, ExecConfig, ExecInstance, LogRecord, LogStream, RockerError};
+#[cfg(target_os = "linux")]
+use nix::errno::Errno;
+#[cfg(target_os = "linux")]
+use nix::libc;
+#[cfg(target_os = "linux")]
+use nix::sched::{setns, CloneFlags};
+#[cfg(target_os = "linux")]
+use nix::sys::prctl;
+#[cfg(target_os = "linux")]
+use nix::sys::signal::Signal;
+#[cfg(target_os = "linux")]
+use nix::sys::wait::{waitpid, WaitStatus};
+#[cfg(target_os = "linux")]
+use nix::unistd::{fork, setgid, setgroups, setuid, ForkResult, Gid, Uid};
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::fs::File;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::info;
+
+use super::Manager;
+
+// 参加する名前空間（mnt はルートが切り替わるため最後に参加する）
+#[cfg(target_os = "linux")]
+const NAMESPACES: [(&str, CloneFlags); 5] = [
+    ("ipc", CloneFlags::CLONE_NEWIPC),
+    ("uts", CloneFlags::CLONE_NEWUTS),
+    ("net", CloneFlags::CLONE_NEWNET),
+    ("pid", CloneFlags::CLONE_NEWPID),
+    ("mnt", CloneFlags::CLONE_NEWNS),
+];
+
+impl Manager {
+    // exec セッションを作成する（起動は start_exec で行う）
+    pub async fn create_exec(&mut self, id_or_name: &str, config: ExecConfig) -> Result<String, RockerError> {
+        let container_id = &self.get(id_or_name)?.id.clone();
+        let container = self
+            .containers
+            .get_mut(container_id)
+            .ok_or_else(|| ContainerError::NotFound(container_id.clone()))?;
+
+        if !container.state.is_running() {
+            return Err(ContainerError::NotRunning(container_id.clone()).into());
+        }
+
+        if config.cmd.is_empty() {
+            return Err(ContainerError::Exec("No command specified".to_string()).into());
+        }
+
+        let exec = ExecInstance::new(container.id.clone(), config);
+        let exec_id = exec.id.clone();
+        container.exec_ids.push(exec_id.clone());
+        self.execs.lock().await.insert(exec_id.clone(), exec);
+
+        self.save(container_id).await?;
+
+        Ok(exec_id)
+    }
+
+    // exec セッションのコマンドをコンテナ内で起動する
+    //
+    // output を渡すと標準出力と標準エラー出力を 1 行ずつ送る（両方が閉じると送信側も閉じる）。
+    // attach_stdin の exec に input を渡すと、受け取ったデータを標準入力に書き込み、送信側が閉じたら標準入力を閉じる。
+    pub async fn start_exec(
+        &self,
+        exec_id: &str,
+        output: Option<mpsc::UnboundedSender<LogRecord>>,
+        input: Option<mpsc::UnboundedReceiver<Vec<u8>>>,
+    ) -> Result<(), RockerError> {
+        let mut execs = self.execs.lock().await;
+        let exec = execs
+            .get_mut(exec_id)
+            .ok_or_else(|| ContainerError::ExecNotFound(exec_id.to_string()))?;
+
+        if exec.is_started() {
+            return Err(ContainerError::Exec(format!("Exec session already started: {}", exec_id)).into());
+        }
+
+        let container = self.get(&exec.container_id)?;
+        // --dry-run ではコマンドを実行せず、出力の無いまま終了コード 0 で終わったことにする
+        if self.dry_run && container.state.is_running() {
+            exec.mark_started(0);
+            exec.mark_finished(0);
+            info!("Simulated exec {} in container {} (dry run)", exec.id, exec.container_id);
+            return Ok(());
+        }
+        let pid = match (container.state.is_running(), container.pid) {
+            (true, Some(pid)) => pid,
+            _ => return Err(ContainerError::NotRunning(container.id.clone()).into()),
+        };
+
+        // exec の設定が無い項目はコンテナの設定を引き継ぐ
+        let mut env: HashMap<String, String> = container.config.env.clone();
+        env.extend(exec.config.env.clone());
+        let user = exec.config.user.clone().or_else(|| container.config.user.clone());
+        let working_dir = exec
+            .config
+            .working_dir
+            .clone()
+            .or_else(|| container.config.working_dir.clone())
+            .unwrap_or_else(|| "/".to_string());
+
+        let mut command = container_command(pid, &exec.config.cmd, &env, user.as_deref(), working_dir)?;
+        let input = input.filter(|_| exec.config.attach_stdin);
+        if input.is_some() {
+            command.stdin(Stdio::piped());
+        } else {
+            command.stdin(Stdio::null());
+        }
+        if output.is_some() {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        } else {
+            command.stdout(Stdio::null()).stderr(Stdio::null());
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| ContainerError::Exec(e.to_string()))?;
+        exec.mark_started(child.id().map(|id| id as i32).unwrap_or_default());
+        info!("Started exec {} in container {}", exec.id, exec.container_id);
+        drop(execs);
+
+        if let (Some(mut input), Some(mut stdin)) = (input, child.stdin.take()) {
+            tokio::spawn(async move {
+                while let Some(data) = input.recv().await {
+                    // コマンドが標準入力を閉じた場合は残りを捨てる
+                    if stdin.write_all(&data).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        if let Some(output) = output {
+            if let Some(stdout) = child.stdout.take() {
+                tokio::spawn(forward_lines(stdout, LogStream::Stdout, output.clone()));
+            }
+            if let Some(stderr) = child.stderr.take() {
+                tokio::spawn(forward_lines(stderr, LogStream::Stderr, output));
+            }
+        }
+
+        // 終了コードを記録して inspect_exec から参照できるようにする
+        let execs = Arc::clone(&self.execs);
+        let exec_id = exec_id.to_string();
+        tokio::spawn(async move {
+            let exit_code = match child.wait().await {
+                Ok(status) => status
+                    .code()
+                    .or_else(|| status.signal().map(|signal| 128 + signal))
+                    .unwrap_or(-1),
+                Err(_) => -1,
+            };
+
+            if let Some(exec) = execs.lock().await.get_mut(&exec_id) {
+                exec.mark_finished(exit_code);
+                info!("Exec {} exited with code {}", exec_id, exit_code);
+            }
+        });
+
+        Ok(())
+    }
+
+    pub async fn inspect_exec(&self, exec_id: &str) -> Result<ExecInstance, RockerError> {
+        self.execs
+            .lock()
+            .await
+            .get(exec_id)
+            .cloned()
+            .ok_or_else(|| ContainerError::ExecNotFound(exec_id.to_string()).into())
+    }
+
+    // コンテナに属する exec セッションの一覧
+    pub async fn list_execs(&self, id_or_name: &str) -> Result<Vec<ExecInstance>, RockerError> {
+        let container = self.get(id_or_name)?;
+        let execs = self.execs.lock().await;
+        Ok(container
+            .exec_ids
+            .iter()
+            .filter_map(|id| execs.get(id).cloned())
+            .collect())
+    }
+}
+
+// pid のプロセスの名前空間に入り、user で cmd を実行する Command を作る（標準入出力は呼び出し側で設定する）
+//
+// exec とヘルスチェックで使う。
+#[cfg(target_os = "linux")]
+pub(super) fn container_command(
+    pid: i32,
+    cmd: &[String],
+    env: &HashMap<String, String>,
+    user: Option<&str>,
+    working_dir: String,
+) -> Result<Command, ContainerError> {
+    let proc_root = Path::new("/proc").join(pid.to_string());
+    let (uid, gid) = match user {
+        Some(user) => resolve_user(&proc_root.join("root"), user)?,
+        None => (0, 0),
+    };
+
+    // 名前空間のファイルは fork 前に開いておく
+    let mut namespaces = Vec::new();
+    for (name, flag) in NAMESPACES {
+        let file = File::open(proc_root.join("ns").join(name))
+            .map_err(|e| ContainerError::Exec(format!("Failed to open {} namespace: {}", name, e)))?;
+        namespaces.push((file, flag));
+    }
+
+    let mut command = Command::new(&cmd[0]);
+    command.args(&cmd[1..]).env_clear().envs(env).current_dir("/");
+
+    unsafe {
+        command.pre_exec(move || {
+            for (file, flag) in &namespaces {
+                setns(file, *flag)?;
+            }
+            // pid 名前空間への参加は子プロセス以降にのみ反映されるため、もう一度 fork して子でコマンドを実行する。
+            // 親はコマンドの終了を待って同じ終了コードで終わる。
+            if let ForkResult::Parent { child } = fork()? {
+                let code = loop {
+                    match waitpid(child, None) {
+                        Ok(WaitStatus::Exited(_, code)) => break code,
+                        Ok(WaitStatus::Signaled(_, signal, _)) => break 128 + signal as i32,
+                        Ok(_) | Err(Errno::EINTR) => continue,
+                        Err(_) => break 127,
+                    }
+                };
+                libc::_exit(code);
+            }
+            // 親が kill された場合はコマンドも止める
+            prctl::set_pdeathsig(Signal::SIGKILL)?;
+            nix::unistd::chdir(working_dir.as_str())?;
+            setgroups(&[Gid::from_raw(gid)])?;
+            setgid(Gid::from_raw(gid))?;
+            setuid(Uid::from_raw(uid))?;
+            Ok(())
+        });
+    }
+    Ok(command)
+}
+
+// Linux 以外には参加できる名前空間が無い（--dry-run のコンテナにはプロセスが無いため呼ばれない）
+#[cfg(not(target_os = "linux"))]
+pub(super) fn container_command(
+    _pid: i32,
+    _cmd: &[String],
+    _env: &HashMap<String, String>,
+    _user: Option<&str>,
+    _working_dir: String,
+) -> Result<Command, ContainerError> {
+    Err(ContainerError::Exec("Running commands in containers is only available on Linux".to_string()))
+}
+
+// 出力を 1 行ずつ LogRecord にして送る（受信側が閉じてもコマンドが止まらないよう最後まで読む）
+async fn forward_lines<R>(reader: R, stream: LogStream, output: mpsc::UnboundedSender<LogRecord>)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let record = LogRecord {
+            stream,
+            time: Utc::now(),
+            line,
+        };
+        let _ = output.send(record);
+    }
+}
+
+// "user[:group]" をコンテナ内の /etc/passwd と /etc/group で uid/gid に解決する
+pub(crate) fn resolve_user(rootfs: &Path, spec: &str) -> Result<(u32, u32), ContainerError> {
+    let passwd = std::fs::read_to_string(rootfs.join("etc/passwd")).unwrap_or_default();
+    let group = std::fs::read_to_string(rootfs.join("etc/group")).unwrap_or_default();
+    lookup_user(spec, &passwd, &group).map_err(ContainerError::Exec)
+}
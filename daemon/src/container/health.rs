@@ -0,0 +1,147 @@
+use chrono::Utc;
+use rocker_core::{Container, Health, HealthCheckResult, HealthStatus, RockerError};
+use nix::unistd::Pid;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use super::exec::container_command;
+use super::{container_event, process_exited, Manager};
+
+// 結果に残すヘルスチェックの出力の最大バイト数
+const MAX_OUTPUT_SIZE: usize = 4096;
+
+// ヘルスチェックの結果の通知
+pub struct HealthReport {
+    pub container_id: String,
+    // チェックしたときの init プロセス（再起動前の結果を反映しないため）
+    pub pid: i32,
+    pub result: HealthCheckResult,
+}
+
+impl Manager {
+    // ヘルスチェックの結果をコンテナの状態に反映する（状態が変わったら health_status イベントを発行する）
+    //
+    // --health-restart のコンテナが unhealthy になった場合は true を返し、呼び出し側で再起動させる。
+    pub async fn handle_health(&mut self, report: HealthReport) -> Result<bool, RockerError> {
+        let id = report.container_id.as_str();
+        let Some(container) = self.containers.get_mut(id) else {
+            return Ok(false);
+        };
+        if !container.state.is_running() || container.pid != Some(report.pid) {
+            return Ok(false);
+        }
+        let (Some(config), Some(health)) = (&container.config.healthcheck, container.health.as_mut()) else {
+            return Ok(false);
+        };
+
+        let in_start_period = container
+            .started_at
+            .and_then(|started_at| chrono::Duration::from_std(config.start_period()).ok().map(|period| started_at + period))
+            .is_some_and(|end| report.result.start < end);
+        debug!("Health check of container {} exited with code {}", id, report.result.exit_code);
+        let mut restart = false;
+        if health.record(report.result, config, in_start_period) {
+            info!("Container {} is {}", id, health.status);
+            restart = health.status == HealthStatus::Unhealthy && container.config.health_restart;
+            self.events.publish(
+                container_event(&format!("health_status: {}", health.status), container),
+            );
+        }
+
+        self.save(id).await?;
+        Ok(restart)
+    }
+}
+
+// ヘルスチェックがあれば状態を starting にし、init プロセスが終了するまで定期的にチェックする
+pub(super) fn watch(container: &mut Container, pid: i32, health_tx: mpsc::UnboundedSender<HealthReport>) {
+    container.health = None;
+    let Some(config) = container.config.healthcheck.clone() else {
+        return;
+    };
+    let Some(command) = config.command() else {
+        return;
+    };
+    container.health = Some(Health::starting());
+
+    let check = Check {
+        pid,
+        command,
+        env: container.config.env.clone(),
+        user: container.config.user.clone(),
+        working_dir: container.config.working_dir.clone().unwrap_or_else(|| "/".to_string()),
+    };
+    let container_id = container.id.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(config.interval()).await;
+            if process_exited(Pid::from_raw(pid)) {
+                return;
+            }
+            let result = check.run(config.timeout()).await;
+            let report = HealthReport {
+                container_id: container_id.clone(),
+                pid,
+                result,
+            };
+            if health_tx.send(report).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+// コンテナ内で実行するチェックのコマンド
+struct Check {
+    pid: i32,
+    command: Vec<String>,
+    env: HashMap<String, String>,
+    user: Option<String>,
+    working_dir: String,
+}
+
+impl Check {
+    async fn run(&self, timeout: Duration) -> HealthCheckResult {
+        let start = Utc::now();
+        let (exit_code, output) = match self.execute(timeout).await {
+            Ok(result) => result,
+            Err(message) => (-1, message),
+        };
+        HealthCheckResult {
+            start,
+            end: Utc::now(),
+            exit_code,
+            output,
+        }
+    }
+
+    // 終了コードと、標準出力と標準エラー出力を合わせた出力を返す（時間切れのコマンドは強制終了する）
+    async fn execute(&self, timeout: Duration) -> Result<(i32, String), String> {
+        let mut command = container_command(self.pid, &self.command, &self.env, self.user.as_deref(), self.working_dir.clone())
+            .map_err(|e| e.to_string())?;
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let child = command.spawn().map_err(|e| e.to_string())?;
+
+        let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => output.map_err(|e| e.to_string())?,
+            Err(_) => return Err(format!("Health check exceeded timeout ({}s)", timeout.as_secs())),
+        };
+        let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+        if text.len() > MAX_OUTPUT_SIZE {
+            let mut end = MAX_OUTPUT_SIZE;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            text.truncate(end);
+        }
+        Ok((output.status.code().unwrap_or(-1), text))
+    }
+}
@@ -0,0 +1,89 @@
+use rocker_core::{ContainerError, Hook, HookStage, HookState, RockerError, HOOKS_DIR};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+// timeout が指定されていないフックの実行時間の上限
+const DEFAULT_HOOK_TIMEOUT: u64 = 30;
+
+// デーモン全体のフックを読み込む（ファイル名順）
+pub fn load_default_hooks() -> Vec<Hook> {
+    let entries = match std::fs::read_dir(HOOKS_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    paths.sort();
+
+    let mut hooks = Vec::new();
+    for path in paths {
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str::<Hook>(&content).map_err(|e| e.to_string()))
+        {
+            Ok(hook) => hooks.push(hook),
+            Err(e) => warn!("Skipping invalid hook {}: {}", path.display(), e),
+        }
+    }
+
+    info!("Loaded {} default hooks", hooks.len());
+    hooks
+}
+
+// 指定されたステージのフックを順に実行し、最初に失敗したフックのエラーを返す
+pub async fn run_hooks(hooks: &[Hook], stage: HookStage, state: &HookState) -> Result<(), RockerError> {
+    let state_json = serde_json::to_vec(state)?;
+
+    for hook in hooks.iter().filter(|hook| hook.stage == stage) {
+        run_hook(hook, &state_json)
+            .await
+            .map_err(|e| ContainerError::Runtime(format!("{} hook {} failed: {}", stage, hook.path, e)))?;
+    }
+
+    Ok(())
+}
+
+// コンテナの状態を標準入力に渡してフックを実行する
+async fn run_hook(hook: &Hook, state_json: &[u8]) -> Result<(), String> {
+    let mut command = Command::new(&hook.path);
+    command
+        .args(&hook.args)
+        .env_clear()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    for env in &hook.env {
+        if let Some((key, value)) = env.split_once('=') {
+            command.env(key, value);
+        }
+    }
+    if let Some(dir) = Path::new(&hook.path).parent() {
+        command.current_dir(dir);
+    }
+
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(state_json).await.map_err(|e| e.to_string())?;
+    }
+
+    let timeout = Duration::from_secs(hook.timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT));
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {}s", timeout.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{}: {}", output.status, stderr.trim()));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use rocker_core::{build_hosts_file, build_resolv_conf, Container, ContainerError, Mount, MountType, NetworkMode};
+use std::path::Path;
+
+// コンテナの /etc/hosts を書き直す（ネットワークへの接続でアドレスが決まった後に呼ぶ）
+pub fn update_hosts_file(container_dir: &Path, container: &Container) -> Result<(), ContainerError> {
+    let host_hosts = std::fs::read_to_string("/etc/hosts").unwrap_or_default();
+    let path = container_dir.join("hosts");
+    std::fs::write(&path, build_hosts_file(container, &host_hosts))
+        .map_err(|e| ContainerError::Start(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+// /etc/hosts・/etc/hostname・/etc/resolv.conf をコンテナのディレクトリに生成し、バインドマウントの一覧を返す
+pub fn prepare(container_dir: &Path, container: &Container) -> Result<Vec<Mount>, ContainerError> {
+    let host_hosts = std::fs::read_to_string("/etc/hosts").unwrap_or_default();
+    let host_resolv = std::fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+
+    let mut files = vec![
+        ("hosts", build_hosts_file(container, &host_hosts)),
+        ("hostname", format!("{}\n", container.hostname())),
+    ];
+    // ネットワークを持たないコンテナでは名前解決の設定は不要
+    if !matches!(container.config.network_mode, NetworkMode::None) {
+        files.push(("resolv.conf", build_resolv_conf(&container.config, &host_resolv)));
+    }
+
+    let mut names = Vec::new();
+    for (name, content) in files {
+        let path = container_dir.join(name);
+        std::fs::write(&path, content)
+            .map_err(|e| ContainerError::Start(format!("Failed to write {}: {}", path.display(), e)))?;
+        names.push(name);
+    }
+
+    Ok(bind_mounts(container_dir, &names, container))
+}
+
+// 他のコンテナのネットワーク名前空間に参加する場合は、そのコンテナが生成したファイルを共有する
+pub fn share(target_dir: &Path, container: &Container) -> Vec<Mount> {
+    let names: Vec<&str> = ["hosts", "hostname", "resolv.conf"]
+        .into_iter()
+        .filter(|name| target_dir.join(name).exists())
+        .collect();
+    bind_mounts(target_dir, &names, container)
+}
+
+fn bind_mounts(dir: &Path, names: &[&str], container: &Container) -> Vec<Mount> {
+    let mut mounts = Vec::new();
+    for name in names {
+        // ユーザーが同じパスをマウントしている場合はそちらを優先する
+        let destination = format!("/etc/{}", name);
+        if container.config.mounts.iter().any(|m| m.destination == destination) {
+            continue;
+        }
+
+        mounts.push(Mount {
+            mount_type: MountType::Bind,
+            source: dir.join(name).to_string_lossy().to_string(),
+            destination,
+            read_only: false,
+            propagation: None,
+            relabel: None,
+            no_copy: false,
+            tmpfs_size: None,
+        });
+    }
+    mounts
+}
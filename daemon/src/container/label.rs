@@ -0,0 +1,78 @@
+use rocker_core::{ContainerConfig, ContainerError, MountType, SelinuxRelabel};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+// コンテナのプロセスが読み書きできるファイルのラベル
+const CONTAINER_FILE_LABEL: &str = "system_u:object_r:container_file_t:s0";
+// Z で使う MCS カテゴリの数（c0〜c1023）
+const CATEGORIES: u32 = 1024;
+
+// ラベルを付け替えるとホストが動かなくなるディレクトリ
+const PROTECTED_PATHS: [&str; 18] = [
+    "/", "/bin", "/boot", "/dev", "/etc", "/home", "/lib", "/lib64", "/opt", "/proc", "/root", "/run", "/sbin", "/srv",
+    "/sys", "/tmp", "/usr", "/var",
+];
+
+// -v の z・Z が指定されたマウント元のラベルを付け替える（SELinux が無効なら何もしない）
+//
+// z は全てのコンテナで共有するラベルを、Z はコンテナ ID から決めたカテゴリを付けたコンテナ専用の
+// ラベルを付ける。ボリュームの source は mount_volumes でホスト側のディレクトリに置き換えてある。
+pub(super) async fn relabel_mounts(container_id: &str, config: &ContainerConfig) -> Result<(), ContainerError> {
+    if !Path::new("/sys/fs/selinux/enforce").exists() {
+        return Ok(());
+    }
+    for mount in &config.mounts {
+        let relabel = match mount.relabel {
+            Some(relabel) if matches!(mount.mount_type, MountType::Bind | MountType::Volume) => relabel,
+            _ => continue,
+        };
+        let source = Path::new(&mount.source);
+        if PROTECTED_PATHS.iter().any(|path| source == Path::new(path)) {
+            return Err(ContainerError::Start(format!(
+                "Relabeling content in {} is not allowed",
+                source.display()
+            )));
+        }
+        let label = match relabel {
+            SelinuxRelabel::Shared => CONTAINER_FILE_LABEL.to_string(),
+            SelinuxRelabel::Private => format!("{}:{}", CONTAINER_FILE_LABEL, categories(container_id)),
+        };
+        chcon(source, &label).await?;
+    }
+    Ok(())
+}
+
+// コンテナ ID の先頭 8 文字から重ならない 2 つのカテゴリを決める
+fn categories(container_id: &str) -> String {
+    let hash = container_id
+        .get(..8)
+        .and_then(|prefix| u32::from_str_radix(prefix, 16).ok())
+        .unwrap_or(0);
+    let first = hash % CATEGORIES;
+    let mut second = (hash / CATEGORIES) % CATEGORIES;
+    if second == first {
+        second = (second + 1) % CATEGORIES;
+    }
+    format!("c{},c{}", first.min(second), first.max(second))
+}
+
+async fn chcon(path: &Path, label: &str) -> Result<(), ContainerError> {
+    let output = Command::new("chcon")
+        .arg("-R")
+        .arg("-h")
+        .arg(label)
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| ContainerError::Start(format!("Failed to run chcon: {}", e)))?;
+    if !output.status.success() {
+        return Err(ContainerError::Start(format!(
+            "Failed to relabel {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,1604 @@
+use rocker_core::{
+    cgroup_path, generate_container_name, lookup, parse_signal, read_oom_kill_count, validate_sysctl, CdiRegistry, Container, ContainerConfig,
+    ContainerTop, Image,
+    ContainerError, ContainerState, ContainerStats, Event, EventType, ExecInstance, Hook, HookStage, HookState,
+    LogConfig, LogRecord, LookupError, Mount, MountType, RestartPolicy, RockerError, VolumeConfig, WaitFor, VolumeDriver, VolumeError, NetworkEndpoint, NetworkError, NetworkMode, PortBinding, TrafficShaping, DEFAULT_STOP_TIMEOUT, OCI_VERSION,
+};
+use chrono::Utc;
+use nix::errno::Errno;
+use nix::sys::signal::{kill, Signal};
+use nix::sys::wait::{waitpid, WaitStatus};
+use nix::unistd::Pid;
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, Mutex};
+use tracing::{info, warn};
+
+use crate::events::EventBus;
+use crate::logging;
+use crate::network::{self, EndpointOptions};
+use crate::proxy;
+use crate::resources;
+use crate::secret;
+use crate::volume;
+
+#[cfg(target_os = "linux")]
+mod device;
+mod dry_run;
+mod exec;
+mod health;
+mod hooks;
+mod hosts;
+mod label;
+mod pause;
+mod rootfs;
+#[cfg(target_os = "linux")]
+mod runtime;
+mod secret_mounts;
+mod size;
+#[cfg(not(target_os = "linux"))]
+mod unsupported;
+mod wait_for;
+
+#[cfg(not(target_os = "linux"))]
+use unsupported::{device, runtime};
+
+pub(crate) use exec::resolve_user;
+pub use health::HealthReport;
+pub(crate) use rootfs::{create_rootfs, secure_join, OPAQUE_WHITEOUT, WHITEOUT_PREFIX};
+pub use wait_for::{spawn_start, wait_for_dependencies};
+
+// 名前を生成して使われていなかった場合に選び直す回数（全て使われていれば ID の先頭を使う）
+const GENERATED_NAME_ATTEMPTS: usize = 6;
+
+// 再起動ポリシーで再起動するまで待つ時間（すぐに終了を繰り返すと倍にしていき、長く動けば最初に戻す）
+const MIN_RESTART_DELAY: Duration = Duration::from_millis(100);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+const RESTART_DELAY_RESET_AFTER: Duration = Duration::from_secs(10);
+
+// コンテナの init プロセスの終了通知
+pub struct ExitStatus {
+    pub container_id: String,
+    pub exit_code: i32,
+    pub oom_killed: bool,
+}
+
+// stop でシグナルを送ったコンテナの終了待ち（デーモンのロックを外して待てるよう Manager から切り離す）
+pub struct StopRequest {
+    id: String,
+    pid: Option<Pid>,
+    signal: Signal,
+    timeout: Duration,
+    exited: Option<watch::Receiver<Option<i32>>>,
+}
+
+impl StopRequest {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // init プロセスの終了を待って終了コードを返す（タイムアウトしたら SIGKILL で強制終了する）
+    pub async fn wait(mut self) -> Result<i32, ContainerError> {
+        let pid = match self.pid {
+            Some(pid) => pid,
+            None => return Ok(0),
+        };
+        if let Some(code) = self.wait_exit(pid, self.signal, self.timeout).await {
+            return Ok(code);
+        }
+
+        warn!("Container {} did not stop within {}s, killing it", self.id, self.timeout.as_secs());
+        kill(pid, Signal::SIGKILL).map_err(|e| ContainerError::Stop(e.to_string()))?;
+        Ok(self
+            .wait_exit(pid, Signal::SIGKILL, Duration::from_secs(DEFAULT_STOP_TIMEOUT))
+            .await
+            .unwrap_or(128 + Signal::SIGKILL as i32))
+    }
+
+    // 終了を待つ（デーモンが起動したプロセスは waitpid の結果を、前回のデーモンから引き継いだプロセスは待てないため
+    // 最後に送ったシグナルで終了したものとする）
+    async fn wait_exit(&mut self, pid: Pid, signal: Signal, timeout: Duration) -> Option<i32> {
+        match &mut self.exited {
+            Some(exited) => match tokio::time::timeout(timeout, exited.wait_for(Option::is_some)).await {
+                Ok(Ok(code)) => *code,
+                Ok(Err(_)) => Some(-1),
+                Err(_) => None,
+            },
+            None => wait_for_exit(pid, timeout).await.then_some(128 + signal as i32),
+        }
+    }
+}
+
+// --replace で置き換える古いコンテナ（begin_replace が返し、古いコンテナの停止を待ってから finish_replace に渡す）
+pub struct Replacement {
+    old_id: Option<String>,
+    was_running: bool,
+    stop: Option<StopRequest>,
+}
+
+impl Replacement {
+    // 古いコンテナの停止の終了待ち（動作していなければ None）
+    pub fn take_stop(&mut self) -> Option<StopRequest> {
+        self.stop.take()
+    }
+}
+
+// export するコンテナの rootfs（デーモンのロックを外して書き出せるよう Manager から切り離す）
+pub struct RootfsExport {
+    rootfs: PathBuf,
+}
+
+impl RootfsExport {
+    // rootfs を tar として書き出す（ブロックするため spawn_blocking から呼ぶ）
+    pub fn write_to<W: std::io::Write>(self, writer: W) -> std::io::Result<()> {
+        let mut builder = tar::Builder::new(writer);
+        // シンボリックリンクはリンク先を辿らずそのまま格納する
+        builder.follow_symlinks(false);
+        builder.append_dir_all(".", &self.rootfs)?;
+        std::io::Write::flush(&mut builder.into_inner()?)
+    }
+}
+
+// コンテナを管理する構造体
+pub struct Manager {
+    containers: HashMap<String, Container>,
+    // exec セッションは完了待ちのタスクからも更新されるため共有する
+    execs: Arc<Mutex<HashMap<String, ExecInstance>>>,
+    state_dir: PathBuf,
+    // /etc/rocker/hooks.d で定義された、全コンテナに適用するフック
+    default_hooks: Vec<Hook>,
+    // --log-driver を指定しないコンテナのログドライバ（/etc/rocker/daemon.json の log-driver）
+    default_log_config: LogConfig,
+    // 書き込み層の大きさ（ps -s などで求めたもの）
+    sizes: size::SizeCache,
+    // 環境変数で上書きしていないコンテナに渡すプロキシ（/etc/rocker/daemon.json の proxies）
+    proxy_env: Vec<(String, String)>,
+    // コンテナに割り当てられる CPU とメモリ（/etc/rocker/daemon.json の resources）
+    capacity: resources::Capacity,
+    log_followers: logging::Followers,
+    events: EventBus,
+    exit_tx: mpsc::UnboundedSender<ExitStatus>,
+    exit_rx: Option<mpsc::UnboundedReceiver<ExitStatus>>,
+    health_tx: mpsc::UnboundedSender<HealthReport>,
+    health_rx: Option<mpsc::UnboundedReceiver<HealthReport>>,
+    // 再起動ポリシーで再起動を待っているコンテナと、コンテナごとの前回待った時間
+    pending_restarts: HashSet<String>,
+    restart_delays: HashMap<String, Duration>,
+    // このデーモンが起動した init プロセスの終了コード（waitpid の結果）
+    exits: HashMap<String, watch::Receiver<Option<i32>>>,
+    // stop で終了を待っているコンテナ（終了しても handle_exit では Exited にしない）
+    stopping: HashSet<String>,
+    // --dry-run ではプロセスを作らず状態だけを変える
+    dry_run: bool,
+}
+
+impl Manager {
+    pub fn new(events: EventBus, data_root: &Path, dry_run: bool) -> Self {
+        let (exit_tx, exit_rx) = mpsc::unbounded_channel();
+        let (health_tx, health_rx) = mpsc::unbounded_channel();
+        Manager {
+            containers: HashMap::new(),
+            execs: Arc::new(Mutex::new(HashMap::new())),
+            state_dir: data_root.join("containers"),
+            default_hooks: Vec::new(),
+            default_log_config: LogConfig::default(),
+            sizes: size::SizeCache::default(),
+            proxy_env: Vec::new(),
+            capacity: resources::Capacity::default(),
+            log_followers: logging::Followers::default(),
+            events,
+            exit_tx,
+            exit_rx: Some(exit_rx),
+            health_tx,
+            health_rx: Some(health_rx),
+            pending_restarts: HashSet::new(),
+            restart_delays: HashMap::new(),
+            exits: HashMap::new(),
+            stopping: HashSet::new(),
+            dry_run,
+        }
+    }
+
+    // 終了通知の受信側を取り出す（一度だけ）
+    pub fn take_exit_receiver(&mut self) -> Option<mpsc::UnboundedReceiver<ExitStatus>> {
+        self.exit_rx.take()
+    }
+
+    // ヘルスチェックの結果の受信側を取り出す（一度だけ）
+    pub fn take_health_receiver(&mut self) -> Option<mpsc::UnboundedReceiver<HealthReport>> {
+        self.health_rx.take()
+    }
+
+    // 保存済みのコンテナ情報を読み込む
+    pub async fn init(&mut self) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        if !self.dry_run {
+            runtime::init_cgroup_root()?;
+            self.default_hooks = hooks::load_default_hooks();
+        }
+        self.default_log_config = logging::load_default_config();
+        self.proxy_env = proxy::load_config().env();
+        self.capacity = resources::load_capacity();
+
+        let mut entries = tokio::fs::read_dir(&self.state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let config_path = entry.path().join("config.json");
+            if !config_path.exists() {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&config_path).await?;
+            match serde_json::from_str::<Container>(&content) {
+                Ok(container) => {
+                    self.containers.insert(container.id.clone(), container);
+                }
+                Err(e) => warn!("Skipping invalid container state {}: {}", config_path.display(), e),
+            }
+        }
+
+        info!("Loaded {} containers", self.containers.len());
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> &resources::Capacity {
+        &self.capacity
+    }
+
+    // 動作中（一時停止・再起動中を含む）のコンテナが予約している CPU とメモリ（except のコンテナを除く）
+    pub fn reserved(&self, except: Option<&str>) -> resources::Reserved {
+        let mut reserved = resources::Reserved::default();
+        for container in self.containers.values() {
+            let state = &container.state;
+            if Some(container.id.as_str()) != except && (state.is_running() || state.is_paused() || state.is_restarting()) {
+                reserved.add(&container.config.resource_limits);
+            }
+        }
+        reserved
+    }
+
+    // 作成しただけのコンテナ（except 以外）が起動したときに公開する、ホスト側のポートを指定したバインディング
+    //
+    // まだ network::Manager に公開ポートとして記録されていないため、作成時の衝突の確認に別に渡す。
+    fn created_port_bindings(&self, except: Option<&str>) -> Vec<PortBinding> {
+        self.containers
+            .values()
+            .filter(|container| Some(container.id.as_str()) != except && container.state == ContainerState::Created)
+            .filter(|container| network_name_of(&container.config.network_mode).is_some())
+            .flat_map(|container| container.config.published_ports())
+            .filter(|binding| binding.host_port.is_some())
+            .collect()
+    }
+
+    // init プロセスが動作しているコンテナの ID（デーモンの再起動後も動き続けているものを含む）
+    //
+    // --dry-run のコンテナはプロセスを持たないため、動作中の状態であれば動き続けているものとする。
+    pub fn live_containers(&self) -> HashSet<String> {
+        self.containers
+            .values()
+            .filter(|c| c.state.is_running() || c.state.is_paused())
+            .filter(|c| match c.pid {
+                Some(pid) => kill(Pid::from_raw(pid), None).is_ok(),
+                None => self.dry_run,
+            })
+            .map(|c| c.id.clone())
+            .collect()
+    }
+
+    // コンテナがマウントしているボリューム（名前か ID）ごとのコンテナ ID（停止中のコンテナも含む）
+    pub fn volume_references(&self) -> HashMap<String, Vec<String>> {
+        let mut references: HashMap<String, Vec<String>> = HashMap::new();
+        for container in self.containers.values() {
+            for mount in &container.config.mounts {
+                if matches!(mount.mount_type, MountType::Volume) {
+                    references.entry(mount.source.clone()).or_default().push(container.id.clone());
+                }
+            }
+        }
+        references
+    }
+
+    // コンテナが参照している秘密情報（名前か ID）ごとのコンテナ ID（停止中のコンテナも含む）
+    pub fn secret_references(&self) -> HashMap<String, Vec<String>> {
+        let mut references: HashMap<String, Vec<String>> = HashMap::new();
+        for container in self.containers.values() {
+            for secret in &container.config.secrets {
+                references.entry(secret.source.clone()).or_default().push(container.id.clone());
+            }
+        }
+        references
+    }
+
+    // イメージのレイヤーから rootfs を作り、Created のコンテナを登録する（名前が空の場合は生成する）
+    pub async fn create(
+        &mut self,
+        name: &str,
+        config: ContainerConfig,
+        image: &Image,
+        networks: &network::Manager,
+    ) -> Result<Container, RockerError> {
+        let container = self.prepare(name, config, image, networks, None)?;
+        self.register(container, image).await
+    }
+
+    // name のコンテナの置き換えを始める（新しいコンテナの設定を確かめ、古いコンテナが動作中なら停止のシグナルを送る）
+    //
+    // 停止の終了はデーモンのロックを外して Replacement::take_stop で待ち、finish_stop してから finish_replace を呼ぶ。
+    pub async fn begin_replace(
+        &mut self,
+        name: &str,
+        config: &ContainerConfig,
+        image: &Image,
+        networks: &network::Manager,
+    ) -> Result<Replacement, RockerError> {
+        let old = match self.containers.values().find(|c| c.name == name) {
+            Some(old) => old.clone(),
+            None => return Ok(Replacement { old_id: None, was_running: false, stop: None }),
+        };
+        self.prepare(name, config.clone(), image, networks, Some(&old.id))?;
+
+        let was_running = old.state.is_running() || old.state.is_paused();
+        let stop = if was_running { self.begin_stop(&old.id, None).await? } else { None };
+        Ok(Replacement { old_id: Some(old.id), was_running, stop })
+    }
+
+    // 停止した古いコンテナを新しいコンテナで置き換える（古いコンテナが無ければ作成するだけ）
+    //
+    // 古いコンテナを別名にしてから新しいコンテナを登録し、古いコンテナを削除する。登録に失敗した場合は古い
+    // コンテナを元の名前に戻し、動作していたなら起動し直す。
+    #[allow(clippy::too_many_arguments)]
+    pub async fn finish_replace(
+        &mut self,
+        replacement: Replacement,
+        name: &str,
+        config: ContainerConfig,
+        image: &Image,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+        secrets: &secret::Manager,
+    ) -> Result<Container, RockerError> {
+        // --rm のコンテナは停止と同時に削除される
+        let old = match replacement.old_id.as_deref().and_then(|id| self.containers.get(id)) {
+            Some(old) => old.clone(),
+            None => return self.create(name, config, image, networks).await,
+        };
+        // 停止を待つ間に起動し直されたコンテナは置き換えない
+        if old.state.is_running() || old.state.is_paused() {
+            return Err(ContainerError::Create(format!("Container {} was started while it was being replaced", old.id)).into());
+        }
+        let container = self.prepare(name, config, image, networks, Some(&old.id))?;
+        // 同じ名前のコンテナが 2 つある状態を保存しないよう、登録の前に別名にしておく
+        let temporary_name = format!("{}-replaced-{}", name, &old.id[..12]);
+        self.rename(&old.id, &temporary_name).await?;
+
+        match self.register(container, image).await {
+            Ok(container) => {
+                if let Err(e) = self.remove(&old.id, false, volumes).await {
+                    warn!("Failed to remove replaced container {}: {}", old.id, e);
+                }
+                info!("Replaced container {} ({}) with {}", name, old.id, container.id);
+                Ok(container)
+            }
+            Err(e) => {
+                self.rename(&old.id, name).await?;
+                if replacement.was_running {
+                    self.start(&old.id, networks, volumes, secrets).await?;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    // 作成するコンテナの設定を確かめ、イメージの既定値を補ったコンテナを返す（まだ登録しない）
+    //
+    // replacing は置き換えるコンテナの ID で、名前・リソース・ポートの衝突の確認から除く。
+    fn prepare(
+        &self,
+        name: &str,
+        mut config: ContainerConfig,
+        image: &Image,
+        networks: &network::Manager,
+        replacing: Option<&str>,
+    ) -> Result<Container, RockerError> {
+        if !name.is_empty() && self.name_taken(name, replacing) {
+            return Err(ContainerError::AlreadyExists(name.to_string()).into());
+        }
+        config.image = image.id.clone();
+        config.apply_image_defaults(&image.config);
+        proxy::add_env(&mut config.env, &self.proxy_env);
+        if config.cmd.as_ref().is_none_or(|cmd| cmd.is_empty()) {
+            return Err(ContainerError::Create("No command specified".to_string()).into());
+        }
+        // 再起動のために停止した時点で削除されてしまうため併用できない
+        if config.health_restart && config.auto_remove {
+            return Err(ContainerError::Create("--health-restart cannot be used with --rm".to_string()).into());
+        }
+        if !matches!(config.restart_policy, RestartPolicy::No) && config.auto_remove {
+            return Err(ContainerError::Create("--restart cannot be used with --rm".to_string()).into());
+        }
+        if !name.is_empty() && config.wait_for.contains(&WaitFor::Container(name.to_string())) {
+            return Err(ContainerError::Create("A container cannot wait for itself".to_string()).into());
+        }
+        // 作成時のデーモンの既定値を記録し、後で既定値を変えても同じドライバを使い続ける
+        let log_config = config.log_config.get_or_insert_with(|| self.default_log_config.clone());
+        logging::validate(log_config)?;
+        self.capacity.admit(self.reserved(replacing), &config.resource_limits)?;
+        // ポートの衝突は起動を待たずに作成時に知らせる
+        if network_name_of(&config.network_mode).is_some() {
+            networks.check_ports(&config.published_ports(), &self.created_port_bindings(replacing), replacing)?;
+        }
+
+        let mut container = Container::new(name.to_string(), config);
+        container.image_name = image.full_name();
+        if container.name.is_empty() {
+            container.name = self.generate_name(&container.id);
+        }
+        Ok(container)
+    }
+
+    // prepare したコンテナの rootfs を作って登録する
+    async fn register(&mut self, container: Container, image: &Image) -> Result<Container, RockerError> {
+        let id = container.id.clone();
+        let rootfs = self.rootfs_dir(&id);
+        if let Err(e) = rootfs::create_rootfs(&rootfs, &image.layers).await {
+            let _ = tokio::fs::remove_dir_all(self.state_dir.join(&id)).await;
+            return Err(e.into());
+        }
+        size::mark_baseline(&self.state_dir.join(&id))?;
+
+        self.containers.insert(id.clone(), container.clone());
+        self.save(&id).await?;
+        info!("Created container {} ({})", container.name, id);
+        self.events.publish(container_event("create", &container));
+        Ok(container)
+    }
+
+    fn name_taken(&self, name: &str, except: Option<&str>) -> bool {
+        self.containers
+            .values()
+            .any(|c| c.name == name && Some(c.id.as_str()) != except)
+    }
+
+    // 名前を指定されなかったコンテナの名前（Docker と同じく、使われていれば数字を付けて選び直し、
+    // それでも空かなければ ID の先頭を使う）
+    fn generate_name(&self, id: &str) -> String {
+        (0..GENERATED_NAME_ATTEMPTS)
+            .map(|retry| generate_container_name(id, retry))
+            .find(|name| !self.name_taken(name, None))
+            .unwrap_or_else(|| id.chars().take(12).collect())
+    }
+
+    // ログドライバが保存したログを読む（follow の場合は動作中のコンテナの出力を送り続ける）
+    pub fn logs(
+        &self,
+        id_or_name: &str,
+        follow: bool,
+        options: logging::ReadOptions,
+    ) -> Result<mpsc::UnboundedReceiver<LogRecord>, RockerError> {
+        let container = self.get(id_or_name)?;
+        let log_config = container.config.log_config.as_ref().unwrap_or(&self.default_log_config);
+        let followers = follow.then_some(&self.log_followers);
+        Ok(logging::read(log_config, &self.state_dir.join(&container.id), &container.id, options, followers)?)
+    }
+
+    // コンテナの cgroup に属するプロセスの一覧
+    pub async fn top(&self, id_or_name: &str) -> Result<ContainerTop, RockerError> {
+        let container = self.get(id_or_name)?;
+        if !container.state.is_running() && !container.state.is_paused() {
+            return Err(ContainerError::NotRunning(container.id.clone()).into());
+        }
+        if self.dry_run {
+            return Err(dry_run::unsupported("Listing the processes of a container").into());
+        }
+
+        let cgroup_dir = cgroup_path(&container.id);
+        let top = tokio::task::spawn_blocking(move || ContainerTop::collect(cgroup_dir)).await??;
+        Ok(top)
+    }
+
+    // コンテナのマウント（匿名ボリュームは起動後に作成した名前になる）
+    pub fn mounts(&self, id_or_name: &str) -> Result<Vec<Mount>, ContainerError> {
+        Ok(self.get(id_or_name)?.config.mounts.clone())
+    }
+
+    // コンテナの rootfs と、その中のマウント先（ボリュームやバインドマウントの中身は rootfs に含めない）
+    pub fn rootfs(&self, id_or_name: &str) -> Result<(PathBuf, HashSet<PathBuf>), ContainerError> {
+        let container = self.get(id_or_name)?;
+        let rootfs = self.rootfs_dir(&container.id);
+        let mounts = container
+            .config
+            .mounts
+            .iter()
+            .map(|mount| rootfs.join(mount.destination.trim_start_matches('/')))
+            .collect();
+        Ok((rootfs, mounts))
+    }
+
+    pub async fn list_all(&self) -> Result<Vec<Container>, RockerError> {
+        Ok(self.containers.values().cloned().collect())
+    }
+
+    // 書き込み層の大きさ（size_rw）と、image_size を足した全体の大きさ（size_root_fs）を埋めたコンテナ
+    pub async fn with_size(&self, container: &Container, image_size: u64) -> Container {
+        let mut container = container.clone();
+        let size_rw = self.sizes.size_rw(&container, &self.state_dir.join(&container.id)).await;
+        container.size_rw = Some(size_rw);
+        container.size_root_fs = Some(image_size + size_rw);
+        container
+    }
+
+    // ID・ID の前方一致・名前のいずれかでコンテナを探す
+    pub fn get(&self, id_or_name: &str) -> Result<&Container, ContainerError> {
+        lookup(self.containers.values(), id_or_name).map_err(|e| match e {
+            LookupError::NotFound => ContainerError::NotFound(id_or_name.to_string()),
+            LookupError::Ambiguous(_) => ContainerError::Ambiguous(id_or_name.to_string()),
+        })
+    }
+
+    pub async fn start(
+        &mut self,
+        id_or_name: &str,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+        secrets: &secret::Manager,
+    ) -> Result<(), RockerError> {
+        let id = &self.get(id_or_name)?.id.clone();
+        // 再起動を待っている間に起動されたコンテナは、待っていた再起動をしない
+        self.pending_restarts.remove(id);
+        self.restart_delays.remove(id);
+        self.capacity.admit(self.reserved(Some(id)), &self.get(id)?.config.resource_limits)?;
+        // 参照している秘密情報が全て復号できることを、何かを用意する前に確かめる
+        let secret_files = secret_mounts::resolve(&self.get(id)?.config.secrets, secrets)?;
+        if self.dry_run {
+            return self.start_simulated(id, networks, volumes).await;
+        }
+        let rootfs = self.rootfs_dir(id);
+        let shared_network = self.shared_network(id)?;
+        let inherited_mounts = self.volumes_from(id)?;
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+
+        if container.state.is_running() {
+            return Err(ContainerError::AlreadyRunning(id.to_string()).into());
+        }
+
+        // --volumes-from のマウントは設定に書き戻し、コピー元のコンテナを削除しても使い続ける
+        container.config.mounts.extend(inherited_mounts);
+
+        // 匿名ボリュームと未作成の名前付きボリュームを作成する
+        create_volumes(container, &rootfs, volumes).await?;
+
+        // --gpus で要求された GPU を CDI の定義に従ってデバイス・マウント・環境変数に展開する
+        let mut config = container.config.clone();
+        if let Some(gpus) = &container.config.gpus {
+            let registry = CdiRegistry::load_default().map_err(ContainerError::Create)?;
+            let names = registry.resolve_gpus(gpus).map_err(ContainerError::Create)?;
+            info!("Adding CDI devices to container {}: {}", id, names.join(", "));
+            registry
+                .resolve(&names)
+                .map_err(ContainerError::Create)?
+                .apply_to(&mut config)
+                .map_err(ContainerError::Create)?;
+        }
+
+        for key in config.sysctls.keys() {
+            validate_sysctl(key, &config.network_mode).map_err(ContainerError::Start)?;
+        }
+
+        // cgroup の作成とデバイスの設定
+        let cgroup_dir = cgroup_path(&container.id);
+        tokio::fs::create_dir_all(&cgroup_dir).await?;
+        runtime::configure_cgroup(&cgroup_dir, &config)?;
+        device::apply_device_filter(&cgroup_dir, &device::device_rules(&config)?)?;
+        device::create_device_nodes(&rootfs, &config)?;
+
+        // 名前解決用のファイルを生成してマウントに加える（名前空間を共有する場合は相手のファイルを使う）
+        let network_namespace = match &shared_network {
+            Some((target_id, pid)) => {
+                config.mounts.extend(hosts::share(&self.state_dir.join(target_id), container));
+                runtime::NetworkNamespace::Container(*pid)
+            }
+            None => {
+                config.mounts.extend(hosts::prepare(&self.state_dir.join(id), container)?);
+                match config.network_mode {
+                    NetworkMode::Host => runtime::NetworkNamespace::Host,
+                    _ => runtime::NetworkNamespace::New,
+                }
+            }
+        };
+
+        // 秘密情報は tmpfs に書き、ファイルごとに読み取り専用でバインドマウントする
+        let bundle = self.state_dir.join(id);
+        if !secret_files.is_empty() {
+            config.mounts.extend(secret_mounts::prepare(&bundle, &secret_files)?);
+        }
+
+        // ボリュームを用意し、ホスト側のディレクトリをバインドマウントする
+        if let Err(e) = mount_volumes(id, &mut config, volumes).await {
+            release_volumes(container, volumes);
+            secret_mounts::release(&bundle);
+            return Err(e);
+        }
+        if let Err(e) = label::relabel_mounts(id, &config).await {
+            release_volumes(container, volumes);
+            secret_mounts::release(&bundle);
+            return Err(e.into());
+        }
+
+        let log_config = config.log_config.clone().unwrap_or_else(|| self.default_log_config.clone());
+        let log_driver = match logging::open(&log_config, &bundle, container) {
+            Ok(log_driver) => log_driver,
+            Err(e) => {
+                release_volumes(container, volumes);
+                secret_mounts::release(&bundle);
+                return Err(e.into());
+            }
+        };
+        let container_hooks = merge_hooks(&self.default_hooks, &config);
+        let process = match runtime::create(&container.hostname(), &network_namespace, &config, &rootfs, &cgroup_dir) {
+            Ok(process) => process,
+            Err(e) => {
+                release_volumes(container, volumes);
+                secret_mounts::release(&bundle);
+                return Err(e.into());
+            }
+        };
+
+        // プロセスが同期パイプで待機している間にネットワーク名前空間へ veth を用意する
+        if let Err(e) = connect_endpoints(container, &config, process.pid, networks).await {
+            process.abort();
+            release_endpoints(container, networks).await;
+            release_volumes(container, volumes);
+            secret_mounts::release(&bundle);
+            return Err(e);
+        }
+        hosts::update_hosts_file(&bundle, container)?;
+
+        // prestart フックが失敗した場合はコンテナを起動しない
+        let state = hook_state(container, "created", Some(process.pid), &bundle);
+        if let Err(e) = hooks::run_hooks(&container_hooks, HookStage::Prestart, &state).await {
+            process.abort();
+            release_endpoints(container, networks).await;
+            release_volumes(container, volumes);
+            secret_mounts::release(&bundle);
+            return Err(e);
+        }
+
+        let (pid, output) = match process.start() {
+            Ok(started) => started,
+            Err(e) => {
+                release_endpoints(container, networks).await;
+                release_volumes(container, volumes);
+                secret_mounts::release(&bundle);
+                return Err(e.into());
+            }
+        };
+        info!("Started container {} with pid {}", id, pid);
+        if let Err(e) = logging::attach(id, log_driver, output.stdout, output.stderr, &self.log_followers) {
+            warn!("Failed to collect logs of container {}: {}", id, e);
+        }
+
+        container.state = ContainerState::Running;
+        container.pid = Some(pid.as_raw());
+        container.exit_code = None;
+        container.oom_killed = false;
+        container.restart_count = 0;
+        container.started_at = Some(Utc::now());
+        container.finished_at = None;
+        self.events.publish(container_event("start", container));
+
+        // poststart フックの失敗はコンテナの起動を妨げない
+        let state = hook_state(container, "running", Some(pid), &bundle);
+        if let Err(e) = hooks::run_hooks(&container_hooks, HookStage::Poststart, &state).await {
+            warn!("{}", e);
+        }
+
+        tokio::spawn(watch_oom(container_event("oom", container), cgroup_dir.clone(), pid, self.events.clone()));
+        health::watch(container, pid.as_raw(), self.health_tx.clone());
+
+        // init プロセスの終了を待ち、終了コードを通知する
+        let exit_tx = self.exit_tx.clone();
+        let (exited_tx, exited_rx) = watch::channel(None);
+        self.exits.insert(id.to_string(), exited_rx);
+        let container_id = id.to_string();
+        tokio::task::spawn_blocking(move || {
+            let exit_code = match waitpid(pid, None) {
+                Ok(WaitStatus::Exited(_, code)) => code,
+                Ok(WaitStatus::Signaled(_, signal, _)) => 128 + signal as i32,
+                _ => -1,
+            };
+            let _ = exited_tx.send(Some(exit_code));
+            let _ = exit_tx.send(ExitStatus {
+                container_id,
+                exit_code,
+                oom_killed: read_oom_kill_count(&cgroup_dir) > 0,
+            });
+        });
+
+        self.save(id).await
+    }
+
+    // network_mode が container:<id> の場合に、参加先のコンテナの ID と init プロセスを返す
+    fn shared_network(&self, id: &str) -> Result<Option<(String, Pid)>, RockerError> {
+        let container = self.get(id)?;
+        let config = &container.config;
+        let target = match &config.network_mode {
+            NetworkMode::Container(target) => target,
+            _ => return Ok(None),
+        };
+
+        // ホスト名・DNS・アドレスは参加先のコンテナのものを使う
+        if config.hostname.is_some() || !config.dns.is_empty() || !config.dns_search.is_empty() {
+            return Err(ContainerError::Start(
+                "Conflicting options: hostname and DNS settings cannot be used with container network mode".to_string(),
+            )
+            .into());
+        }
+        if config.ip_address.is_some() || !config.published_ports().is_empty() || !config.extra_hosts.is_empty() {
+            return Err(ContainerError::Start(
+                "Conflicting options: IP address, published ports and extra hosts cannot be used with container network mode"
+                    .to_string(),
+            )
+            .into());
+        }
+
+        let target = self.get(target)?;
+        if target.id == container.id {
+            return Err(ContainerError::Start("Cannot join the network of the container itself".to_string()).into());
+        }
+        match target.pid {
+            Some(pid) if target.state.is_running() || target.state.is_paused() => {
+                Ok(Some((target.id.clone(), Pid::from_raw(pid))))
+            }
+            _ => Err(ContainerError::NotRunning(target.id.clone()).into()),
+        }
+    }
+
+    // --volumes-from で指定したコンテナのマウントのうち、まだ持っていないマウント先のものを返す
+    //
+    // :ro・:rw を付けた場合は全てのマウントの読み取り専用を上書きし、付けなければコピー元に従う。
+    // tmpfs はコンテナごとのものなのでコピーしない。
+    fn volumes_from(&self, id: &str) -> Result<Vec<Mount>, RockerError> {
+        let container = self.get(id)?;
+        let mut destinations: HashSet<String> =
+            container.config.mounts.iter().map(|m| m.destination.clone()).collect();
+        let mut mounts = Vec::new();
+        for spec in &container.config.volumes_from {
+            let source = self.get(&spec.container)?;
+            if source.id == container.id {
+                return Err(ContainerError::Start("Cannot copy the volumes of the container itself".to_string()).into());
+            }
+            for mount in self.mounts(&source.id)? {
+                if matches!(mount.mount_type, MountType::Tmpfs) || !destinations.insert(mount.destination.clone()) {
+                    continue;
+                }
+                if mount.is_anonymous_volume() {
+                    return Err(ContainerError::Start(format!(
+                        "Container {} has not created its volumes yet (start it once before using --volumes-from)",
+                        source.id
+                    ))
+                    .into());
+                }
+                mounts.push(Mount {
+                    read_only: spec.read_only.unwrap_or(mount.read_only),
+                    ..mount
+                });
+            }
+        }
+        Ok(mounts)
+    }
+
+    // init プロセスが終了したコンテナを Exited にする（stop による停止は stop 側で Stopped にする）
+    //
+    // 再起動ポリシーで再起動する場合は、restart_exited を呼ぶまでに待つ時間を返す。
+    pub async fn handle_exit(
+        &mut self,
+        status: ExitStatus,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+    ) -> Result<Option<Duration>, RockerError> {
+        let id = status.container_id.as_str();
+        let container = match self.containers.get_mut(id) {
+            Some(container) => container,
+            None => return Ok(None),
+        };
+
+        // stop で停止した場合も OOM による終了は記録する
+        container.oom_killed |= status.oom_killed;
+
+        let mut poststop = None;
+        if (container.state.is_running() || container.state.is_paused()) && !self.stopping.contains(id) {
+            self.exits.remove(id);
+            info!("Container {} exited with code {}", id, status.exit_code);
+            container.state = ContainerState::Exited;
+            container.pid = None;
+            container.exit_code = Some(status.exit_code);
+            container.finished_at = Some(Utc::now());
+            release_endpoints(container, networks).await;
+            release_volumes(container, volumes);
+            secret_mounts::release(&self.state_dir.join(id));
+            self.events.publish(
+                container_event("die", container).with_attribute("exitCode", &status.exit_code.to_string()),
+            );
+            poststop = Some((
+                merge_hooks(&self.default_hooks, &container.config),
+                hook_state(container, "stopped", None, &self.state_dir.join(id)),
+            ));
+        }
+
+        self.save(id).await?;
+
+        let mut restart = None;
+        if let Some((container_hooks, state)) = poststop {
+            if let Err(e) = hooks::run_hooks(&container_hooks, HookStage::Poststop, &state).await {
+                warn!("{}", e);
+            }
+            match self.containers.get(id) {
+                Some(container) if container.config.auto_remove => self.remove(id, true, volumes).await?,
+                Some(container) if container.auto_restart() => {
+                    let ran = match (container.started_at, container.finished_at) {
+                        (Some(started_at), Some(finished_at)) => (finished_at - started_at).to_std().unwrap_or_default(),
+                        _ => Duration::ZERO,
+                    };
+                    let delay = match self.restart_delays.get(id) {
+                        Some(previous) if ran < RESTART_DELAY_RESET_AFTER => (*previous * 2).min(MAX_RESTART_DELAY),
+                        _ => MIN_RESTART_DELAY,
+                    };
+                    self.restart_delays.insert(id.to_string(), delay);
+                    self.pending_restarts.insert(id.to_string());
+                    restart = Some(delay);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(restart)
+    }
+
+    // 再起動ポリシーで再起動を待っていたコンテナを起動する（待つ間に start・stop・rm されたものはそのままにする）
+    pub async fn restart_exited(
+        &mut self,
+        id: &str,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+        secrets: &secret::Manager,
+    ) -> Result<(), RockerError> {
+        if !self.pending_restarts.remove(id) {
+            return Ok(());
+        }
+        let restart_count = match self.containers.get(id) {
+            Some(container) => container.restart_count,
+            None => return Ok(()),
+        };
+        let delay = self.restart_delays.get(id).copied();
+        info!("Restarting container {} by its restart policy", id);
+        // start は手動の起動として回数と待つ時間を戻すため、起動した後に引き継ぐ
+        self.start(id, networks, volumes, secrets).await?;
+        if let Some(delay) = delay {
+            self.restart_delays.insert(id.to_string(), delay);
+        }
+        if let Some(container) = self.containers.get_mut(id) {
+            container.restart_count = restart_count + 1;
+            self.events.publish(container_event("restart", container));
+        }
+        self.save(id).await
+    }
+
+    // 停止しているコンテナを削除する（remove_volumes が true なら匿名ボリュームも削除する）
+    pub async fn remove(
+        &mut self,
+        id_or_name: &str,
+        remove_volumes: bool,
+        volumes: &mut volume::Manager,
+    ) -> Result<(), RockerError> {
+        let container = self.get(id_or_name)?;
+        if container.state.is_running() || container.state.is_paused() {
+            return Err(ContainerError::Remove(format!(
+                "Container {} is running, stop it before removing it",
+                container.id
+            ))
+            .into());
+        }
+
+        let id = container.id.clone();
+        let name = container.name.clone();
+        let event = container_event("destroy", container);
+        let container_volumes: Vec<String> = container
+            .config
+            .mounts
+            .iter()
+            .filter(|m| matches!(m.mount_type, MountType::Volume) && !m.source.is_empty())
+            .map(|m| m.source.clone())
+            .collect();
+
+        let dir = self.state_dir.join(&id);
+        secret_mounts::release(&dir);
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .map_err(|e| ContainerError::Remove(format!("{}: {}", dir.display(), e)))?;
+        }
+        let cgroup_dir = cgroup_path(&id);
+        if cgroup_dir.exists() {
+            if let Err(e) = tokio::fs::remove_dir(&cgroup_dir).await {
+                warn!("Failed to remove cgroup of container {}: {}", id, e);
+            }
+        }
+        self.containers.remove(&id);
+        self.sizes.invalidate(&id);
+        self.pending_restarts.remove(&id);
+        self.restart_delays.remove(&id);
+        info!("Removed container {} ({})", name, id);
+        self.events.publish(event);
+
+        // 名前を付けて作成したボリュームは他のコンテナで使えるよう残す
+        if remove_volumes {
+            let references = self.volume_references();
+            for volume in container_volumes {
+                if !volumes.get(&volume).is_ok_and(|v| v.is_anonymous()) {
+                    continue;
+                }
+                if let Err(e) = volumes.remove(&volume, &references).await {
+                    warn!("Failed to remove volume {} of container {}: {}", volume, id, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // STOPSIGNAL（既定は SIGTERM）を送り、終了を待つための StopRequest を返す（再起動を待っていただけのコンテナは None）
+    //
+    // StopRequest::wait はタイムアウト後に SIGKILL で強制終了する。デーモンのロックを外して待ち、finish_stop を呼ぶ。
+    pub async fn begin_stop(
+        &mut self,
+        id_or_name: &str,
+        timeout: Option<u64>,
+    ) -> Result<Option<StopRequest>, RockerError> {
+        let id = &self.get(id_or_name)?.id.clone();
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+
+        if !container.state.is_running() && !container.state.is_paused() {
+            // 再起動を待っているコンテナは、再起動を取りやめて停止したことにする
+            if self.pending_restarts.remove(id) {
+                container.state = ContainerState::Stopped;
+                self.events.publish(container_event("stop", container));
+                self.save(id).await?;
+                return Ok(None);
+            }
+            return Err(ContainerError::NotRunning(id.to_string()).into());
+        }
+
+        let signal = match &container.config.stop_signal {
+            Some(signal) => parse_signal(signal).map_err(ContainerError::Stop)?,
+            None => Signal::SIGTERM,
+        };
+        let timeout = timeout
+            .or(container.config.stop_timeout)
+            .unwrap_or(DEFAULT_STOP_TIMEOUT);
+
+        let pid = container.pid.map(Pid::from_raw);
+        if let Some(pid) = pid {
+            // 一時停止中のプロセスはシグナルを処理できないため再開させる
+            if container.state.is_paused() {
+                if let Err(e) = pause::freeze(&cgroup_path(id), false).await {
+                    warn!("Failed to unpause container {}: {}", id, e);
+                }
+            }
+
+            info!("Stopping container {} with {}", id, signal);
+            // 既に終了して回収されたプロセスには送れないが、終了コードは StopRequest::wait で受け取れる
+            match kill(pid, signal) {
+                Ok(()) | Err(Errno::ESRCH) => {}
+                Err(e) => return Err(ContainerError::Stop(e.to_string()).into()),
+            }
+        }
+        self.stopping.insert(id.clone());
+
+        Ok(Some(StopRequest {
+            id: id.clone(),
+            pid,
+            signal,
+            timeout: Duration::from_secs(timeout),
+            exited: self.exits.get(id).cloned(),
+        }))
+    }
+
+    // StopRequest::wait で終了したコンテナを Stopped にし、ネットワークとボリュームを解放する
+    pub async fn finish_stop(
+        &mut self,
+        id: &str,
+        exit_code: Result<i32, ContainerError>,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+    ) -> Result<(), RockerError> {
+        // 同時に stop した場合は先に終わった方が状態を更新する
+        if !self.stopping.remove(id) {
+            return exit_code.map(|_| ()).map_err(Into::into);
+        }
+        let exit_code = exit_code?;
+        self.exits.remove(id);
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+
+        container.state = ContainerState::Stopped;
+        container.pid = None;
+        container.exit_code = Some(exit_code);
+        container.finished_at = Some(Utc::now());
+        release_endpoints(container, networks).await;
+        release_volumes(container, volumes);
+        secret_mounts::release(&self.state_dir.join(id));
+        self.events
+            .publish(container_event("stop", container).with_attribute("exitCode", &exit_code.to_string()));
+
+        // --dry-run ではフックを実行しない
+        let container_hooks = if self.dry_run {
+            Vec::new()
+        } else {
+            merge_hooks(&self.default_hooks, &container.config)
+        };
+        let state = hook_state(container, "stopped", None, &self.state_dir.join(id));
+        self.save(id).await?;
+
+        if let Err(e) = hooks::run_hooks(&container_hooks, HookStage::Poststop, &state).await {
+            warn!("{}", e);
+        }
+        if self.containers.get(id).is_some_and(|c| c.config.auto_remove) {
+            self.remove(id, true, volumes).await?;
+        }
+
+        Ok(())
+    }
+
+    // 動作中のコンテナの init プロセスにシグナルを送る（終了した場合は handle_exit で状態を更新する）
+    pub fn kill(&mut self, id_or_name: &str, signal: Signal) -> Result<(), RockerError> {
+        let container = self.get(id_or_name)?;
+        let id = container.id.clone();
+        if !container.state.is_running() && !container.state.is_paused() {
+            return Err(ContainerError::NotRunning(id).into());
+        }
+        self.events
+            .publish(container_event("kill", container).with_attribute("signal", signal.as_str()));
+
+        match container.pid {
+            Some(pid) => {
+                info!("Sending {} to container {}", signal, id);
+                kill(Pid::from_raw(pid), signal).map_err(|e| ContainerError::Runtime(format!("kill: {}", e)))?;
+            }
+            // --dry-run ではプロセスが無いため、シグナルで終了したものとして扱う
+            None => {
+                let _ = self.exit_tx.send(ExitStatus {
+                    container_id: id,
+                    exit_code: 128 + signal as i32,
+                    oom_killed: false,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // コンテナの名前を変える（イメージの更新で作り直すとき、新しいコンテナに名前を譲るのに使う）
+    pub async fn rename(&mut self, id_or_name: &str, name: &str) -> Result<(), RockerError> {
+        let id = self.get(id_or_name)?.id.clone();
+        if self.containers.values().any(|c| c.name == name && c.id != id) {
+            return Err(ContainerError::AlreadyExists(name.to_string()).into());
+        }
+        let container = self
+            .containers
+            .get_mut(&id)
+            .ok_or_else(|| ContainerError::NotFound(id.clone()))?;
+        let old_name = std::mem::replace(&mut container.name, name.to_string());
+        self.events
+            .publish(container_event("rename", container).with_attribute("oldName", &old_name));
+        self.save(&id).await?;
+        info!("Renamed container {} to {} ({})", old_name, name, id);
+        Ok(())
+    }
+
+    // 再起動のために停止したコンテナを起動し直す（unhealthy になった --health-restart のコンテナに使う）
+    //
+    // 停止は RockerDaemon::restart_container がデーモンのロックを外して待つ。待つ間に起動・削除されたコンテナは
+    // そのままにする。
+    pub async fn restart(
+        &mut self,
+        id: &str,
+        networks: &mut network::Manager,
+        volumes: &mut volume::Manager,
+        secrets: &secret::Manager,
+    ) -> Result<(), RockerError> {
+        match self.containers.get(id) {
+            Some(container) if !container.state.is_running() && !container.state.is_paused() => {}
+            _ => return Ok(()),
+        }
+        self.start(id, networks, volumes, secrets).await?;
+        if let Some(container) = self.containers.get(id) {
+            self.events.publish(container_event("restart", container));
+        }
+        Ok(())
+    }
+
+    // コンテナを追加のネットワークに接続する（停止中のコンテナは次回の起動時に接続される）
+    pub async fn connect_network(
+        &mut self,
+        id_or_name: &str,
+        network_id_or_name: &str,
+        aliases: Vec<String>,
+        ip_address: Option<Ipv4Addr>,
+        networks: &mut network::Manager,
+    ) -> Result<NetworkEndpoint, RockerError> {
+        let id = &self.get(id_or_name)?.id.clone();
+        let network = networks.get(network_id_or_name)?;
+        let (network_id, network_name) = (network.id.clone(), network.name.clone());
+        let network_labels = network.config.labels.clone();
+        if let Some(address) = ip_address {
+            network.reserve_ip(address, &[])?;
+        }
+
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+        if network_name_of(&container.config.network_mode).is_none() {
+            return Err(NetworkError::Connect(format!(
+                "Container {} uses {:?} network mode",
+                id, container.config.network_mode
+            ))
+            .into());
+        }
+        if container.networks.contains_key(&network_name) {
+            return Err(NetworkError::Connect(format!("Container {} is already connected to {}", id, network_name)).into());
+        }
+
+        let interface = next_interface(container);
+        // --dry-run のコンテナはプロセスが無くても動作中なら接続する
+        let pid = container.pid.or(self.dry_run.then_some(0));
+        let endpoint = match pid {
+            Some(pid) if container.state.is_running() || container.state.is_paused() => {
+                let options = EndpointOptions {
+                    name: container.name.clone(),
+                    interface,
+                    aliases,
+                    ip_address,
+                    shaping: TrafficShaping::default(),
+                };
+                let endpoint = networks.connect(&network_id, id, pid, options).await?;
+                container.networks.insert(network_name.clone(), endpoint.clone());
+                hosts::update_hosts_file(&self.state_dir.join(id), container)?;
+                endpoint
+            }
+            _ => {
+                let endpoint = NetworkEndpoint {
+                    network_id: network_id.clone(),
+                    ip_address: String::new(),
+                    mac_address: String::new(),
+                    interface,
+                    requested_ip: ip_address.map(|ip| ip.to_string()),
+                    aliases,
+                    shaping: TrafficShaping::default(),
+                };
+                container.networks.insert(network_name.clone(), endpoint.clone());
+                endpoint
+            }
+        };
+
+        self.events.publish(
+            Event::new(EventType::Network, "connect", &network_id)
+                .with_labels(&network_labels)
+                .with_attribute("name", &network_name)
+                .with_attribute("container", id),
+        );
+        self.save(id).await?;
+        Ok(endpoint)
+    }
+
+    // コンテナをネットワークから切断する
+    pub async fn disconnect_network(
+        &mut self,
+        id_or_name: &str,
+        network_id_or_name: &str,
+        networks: &mut network::Manager,
+    ) -> Result<(), RockerError> {
+        let id = &self.get(id_or_name)?.id.clone();
+        let network = networks.get(network_id_or_name)?;
+        let (network_id, network_name) = (network.id.clone(), network.name.clone());
+        let network_labels = network.config.labels.clone();
+
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+        let endpoint = container.networks.remove(&network_name).ok_or_else(|| {
+            NetworkError::Disconnect(format!("Container {} is not connected to {}", id, network_name))
+        })?;
+
+        if !endpoint.ip_address.is_empty() {
+            // ポートは network_mode のネットワークで公開している
+            if network_name_of(&container.config.network_mode) == Some(network_name.as_str())
+                || container.ip_address.as_deref() == Some(endpoint.ip_address.as_str())
+            {
+                networks.unpublish_ports(id).await?;
+                container.ip_address = None;
+                container.ports.clear();
+            }
+            networks.disconnect(&network_id, id).await?;
+            hosts::update_hosts_file(&self.state_dir.join(id), container)?;
+        }
+
+        self.events.publish(
+            Event::new(EventType::Network, "disconnect", &network_id)
+                .with_labels(&network_labels)
+                .with_attribute("name", &network_name)
+                .with_attribute("container", id),
+        );
+        self.save(id).await
+    }
+
+    // cgroup からリソース使用量を取得する
+    pub async fn stats(&self, id_or_name: &str) -> Result<ContainerStats, RockerError> {
+        let container = self.get(id_or_name)?;
+        if !container.state.is_running() && !container.state.is_paused() {
+            return Err(ContainerError::NotRunning(container.id.clone()).into());
+        }
+        if self.dry_run {
+            return Err(dry_run::unsupported("Resource usage statistics").into());
+        }
+
+        let container_id = container.id.clone();
+        let pid = container.pid;
+        let stats = tokio::task::spawn_blocking(move || {
+            ContainerStats::collect(&container_id, cgroup_path(&container_id), pid)
+        })
+        .await??;
+
+        Ok(stats)
+    }
+
+    // コンテナのファイルシステムを tar として書き出すための RootfsExport を返す
+    pub fn export(&self, id_or_name: &str) -> Result<RootfsExport, ContainerError> {
+        let container = self.get(id_or_name)?;
+        let rootfs = self.rootfs_dir(&container.id);
+        if !rootfs.exists() {
+            return Err(ContainerError::Runtime(format!("Root filesystem not found: {}", rootfs.display())));
+        }
+        Ok(RootfsExport { rootfs })
+    }
+
+    // tar をコンテナのファイルシステムの path に展開する（path のディレクトリが無ければ作る）
+    //
+    // rootfs に直接書き込むため、ボリュームやバインドマウントの下のパスはコンテナから見えない。
+    pub async fn extract_archive(&self, id_or_name: &str, path: &str, archive: Vec<u8>) -> Result<(), RockerError> {
+        let container = self.get(id_or_name)?;
+        let rootfs = self.rootfs_dir(&container.id);
+        let target = rootfs_path(&rootfs, path)?;
+
+        tokio::task::spawn_blocking(move || -> Result<(), RockerError> {
+            check_inside(&rootfs, &target, path_display(&target, &rootfs))?;
+            std::fs::create_dir_all(&target)?;
+            tar::Archive::new(archive.as_slice()).unpack(&target)?;
+            Ok(())
+        })
+        .await??;
+
+        self.sizes.invalidate(&container.id);
+        Ok(())
+    }
+
+    // コンテナのファイルシステムから path のファイルかディレクトリを削除する（無ければ何もしない）
+    pub async fn remove_path(&self, id_or_name: &str, path: &str) -> Result<(), RockerError> {
+        let container = self.get(id_or_name)?;
+        let rootfs = self.rootfs_dir(&container.id);
+        let target = rootfs_path(&rootfs, path)?;
+        let Some(parent) = target.parent() else {
+            return Ok(());
+        };
+        check_inside(&rootfs, parent, path_display(&target, &rootfs))?;
+
+        match tokio::fs::symlink_metadata(&target).await {
+            Ok(metadata) if metadata.is_dir() => tokio::fs::remove_dir_all(&target).await?,
+            Ok(_) => tokio::fs::remove_file(&target).await?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.sizes.invalidate(&container.id);
+        Ok(())
+    }
+
+    fn rootfs_dir(&self, id: &str) -> PathBuf {
+        self.state_dir.join(id).join("rootfs")
+    }
+
+    // コンテナの状態をディスクに保存する
+    async fn save(&self, id: &str) -> Result<(), RockerError> {
+        let container = self.get(id)?;
+        let dir = self.state_dir.join(&container.id);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join("config.json"), serde_json::to_vec_pretty(container)?).await?;
+        Ok(())
+    }
+}
+
+// コンテナ内の絶対パスを rootfs の下のパスにする（.. を含むパスは受け付けない）
+fn rootfs_path(rootfs: &Path, path: &str) -> Result<PathBuf, ContainerError> {
+    let relative = Path::new(path.trim_start_matches('/'));
+    if !rootfs.exists() {
+        return Err(ContainerError::Runtime(format!("Root filesystem not found: {}", rootfs.display())));
+    }
+    if relative
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(ContainerError::InvalidPath(path.to_string()));
+    }
+    Ok(rootfs.join(relative))
+}
+
+// シンボリックリンクを辿った先が rootfs の中にあるか（コンテナ内のリンクでホストのファイルを書き換えない）
+//
+// path がまだ無い場合は、存在する一番深い親ディレクトリで確かめる。
+fn check_inside(rootfs: &Path, path: &Path, display: String) -> Result<(), ContainerError> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists()).unwrap_or(rootfs);
+    let inside = match (rootfs.canonicalize(), existing.canonicalize()) {
+        (Ok(rootfs), Ok(path)) => path.starts_with(rootfs),
+        _ => false,
+    };
+    if inside {
+        Ok(())
+    } else {
+        Err(ContainerError::InvalidPath(display))
+    }
+}
+
+// エラーに出すコンテナ内のパス
+fn path_display(path: &Path, rootfs: &Path) -> String {
+    format!("/{}", path.strip_prefix(rootfs).unwrap_or(path).display())
+}
+
+// network_mode で指定されたネットワーク（独自のネットワーク名前空間を持つ場合のみ）
+fn network_name_of(network_mode: &NetworkMode) -> Option<&str> {
+    match network_mode {
+        NetworkMode::Bridge => Some(network::DEFAULT_NETWORK_NAME),
+        NetworkMode::Custom(name) => Some(name),
+        NetworkMode::Host | NetworkMode::None | NetworkMode::Container(_) => None,
+    }
+}
+
+// 起動するコンテナを network_mode のネットワーク（eth0）と追加で接続されたネットワークに接続する
+async fn connect_endpoints(
+    container: &mut Container,
+    config: &ContainerConfig,
+    pid: Pid,
+    networks: &mut network::Manager,
+) -> Result<(), RockerError> {
+    let primary = match network_name_of(&config.network_mode) {
+        Some(name) => networks.get(name)?.name.clone(),
+        None => {
+            if !config.published_ports().is_empty() {
+                warn!("Published ports are discarded when using {:?} network mode", config.network_mode);
+            }
+            return Ok(());
+        }
+    };
+    // 固定アドレスはサブネットを利用者が決めたネットワークでのみ指定できる
+    if config.ip_address.is_some() && primary == network::DEFAULT_NETWORK_NAME {
+        let message = "User specified IP address is supported on user defined networks only";
+        return Err(NetworkError::InvalidConfig(message.to_string()).into());
+    }
+    let endpoint = container.networks.entry(primary.clone()).or_insert_with(|| NetworkEndpoint {
+        network_id: String::new(),
+        ip_address: String::new(),
+        mac_address: String::new(),
+        interface: "eth0".to_string(),
+        requested_ip: None,
+        aliases: config.network_aliases.clone(),
+        shaping: TrafficShaping::default(),
+    });
+    if let Some(ip_address) = config.ip_address {
+        endpoint.requested_ip = Some(ip_address.to_string());
+    }
+    endpoint.shaping = config.traffic_shaping.clone();
+
+    let mut names: Vec<String> = container.networks.keys().filter(|name| **name != primary).cloned().collect();
+    names.sort_by_key(|name| container.networks[name].interface.clone());
+    names.insert(0, primary.clone());
+
+    for name in names {
+        if container.networks[&name].interface.is_empty() {
+            let interface = next_interface(container);
+            if let Some(endpoint) = container.networks.get_mut(&name) {
+                endpoint.interface = interface;
+            }
+        }
+
+        let endpoint = &container.networks[&name];
+        let ip_address = match &endpoint.requested_ip {
+            Some(ip) => Some(
+                ip.parse::<Ipv4Addr>()
+                    .map_err(|_| NetworkError::InvalidConfig(format!("Invalid IP address: {}", ip)))?,
+            ),
+            None => None,
+        };
+        let options = EndpointOptions {
+            name: container.name.clone(),
+            interface: endpoint.interface.clone(),
+            aliases: endpoint.aliases.clone(),
+            ip_address,
+            shaping: endpoint.shaping.clone(),
+        };
+
+        let endpoint = networks.connect(&name, &container.id, pid.as_raw(), options).await?;
+        if name == primary {
+            container.ip_address = Some(endpoint.ip_address.clone());
+        }
+        container.networks.insert(name, endpoint);
+    }
+
+    container.ports = networks.publish_ports(&container.id, &primary, &config.published_ports()).await?;
+    Ok(())
+}
+
+// 使われていない最小の ethN
+fn next_interface(container: &Container) -> String {
+    (0..)
+        .map(|n| format!("eth{}", n))
+        .find(|name| !container.networks.values().any(|e| e.interface == *name))
+        .unwrap_or_default()
+}
+
+// 停止したコンテナのネットワークへの接続を解除する（接続するネットワークは次回の起動のために残す）
+async fn release_endpoints(container: &mut Container, networks: &mut network::Manager) {
+    if let Err(e) = networks.unpublish_ports(&container.id).await {
+        warn!("Failed to unpublish ports of container {}: {}", container.id, e);
+    }
+    for (name, endpoint) in container.networks.iter_mut() {
+        if endpoint.ip_address.is_empty() {
+            continue;
+        }
+        if let Err(e) = networks.disconnect(&endpoint.network_id, &container.id).await {
+            warn!("Failed to disconnect container {} from {}: {}", container.id, name, e);
+        }
+        endpoint.ip_address.clear();
+        endpoint.mac_address.clear();
+    }
+    container.ip_address = None;
+    container.ports.clear();
+}
+
+// 匿名ボリュームと、まだ存在しない名前のボリュームを作成し、イメージのマウント先にあった内容をコピーする
+//
+// 匿名ボリュームの名前はコンテナの設定に書き戻し、再起動後も同じボリュームを使う。
+async fn create_volumes(
+    container: &mut Container,
+    rootfs: &Path,
+    volumes: &mut volume::Manager,
+) -> Result<(), RockerError> {
+    for mount in container.config.mounts.iter_mut() {
+        if !matches!(mount.mount_type, MountType::Volume) {
+            continue;
+        }
+        let volume = if mount.is_anonymous_volume() {
+            volumes.create_anonymous().await?
+        } else {
+            match volumes.get(&mount.source) {
+                Err(VolumeError::NotFound(_)) => {
+                    volumes
+                        .create(&mount.source, VolumeDriver::Local, VolumeConfig::default())
+                        .await?
+                }
+                _ => continue,
+            }
+        };
+        mount.source = volume.name.clone();
+        if mount.no_copy {
+            continue;
+        }
+        // イメージのシンボリックリンクを辿ってホストのファイルをボリュームにコピーしないよう、rootfs の中で解決する
+        let source = rootfs::secure_join(rootfs, Path::new(&mount.destination))?;
+        volumes.seed(&volume.name, source).await?;
+    }
+    Ok(())
+}
+
+// ボリュームのマウントの source を、ボリュームのホスト側のディレクトリに置き換える
+async fn mount_volumes(
+    container_id: &str,
+    config: &mut ContainerConfig,
+    volumes: &mut volume::Manager,
+) -> Result<(), RockerError> {
+    for mount in config.mounts.iter_mut() {
+        if matches!(mount.mount_type, MountType::Volume) {
+            let mountpoint = volumes.mount(&mount.source, container_id).await?;
+            mount.source = mountpoint.to_string_lossy().to_string();
+        }
+    }
+    Ok(())
+}
+
+// 停止したコンテナが使っていたボリュームを解放する
+fn release_volumes(container: &Container, volumes: &mut volume::Manager) {
+    for mount in &container.config.mounts {
+        if !matches!(mount.mount_type, MountType::Volume) {
+            continue;
+        }
+        match volumes.unmount(&mount.source, &container.id) {
+            Ok(()) | Err(VolumeError::NotFound(_)) => {}
+            Err(e) => warn!("Failed to release volume {} of container {}: {}", mount.source, container.id, e),
+        }
+    }
+}
+
+// デーモン全体のフックの後にコンテナ固有のフックを実行する
+fn merge_hooks(default_hooks: &[Hook], config: &ContainerConfig) -> Vec<Hook> {
+    default_hooks.iter().chain(config.hooks.iter()).cloned().collect()
+}
+
+// フックの標準入力に渡すコンテナの状態
+fn hook_state(container: &Container, status: &str, pid: Option<Pid>, bundle: &Path) -> HookState {
+    HookState {
+        oci_version: OCI_VERSION.to_string(),
+        id: container.id.clone(),
+        status: status.to_string(),
+        pid: pid.map(|pid| pid.as_raw()),
+        bundle: bundle.to_string_lossy().to_string(),
+        annotations: container.config.labels.clone(),
+    }
+}
+
+// コンテナのイベント（ラベルで絞り込めるよう属性にコンテナのラベルも付ける）
+pub(super) fn container_event(action: &str, container: &Container) -> Event {
+    Event::new(EventType::Container, action, &container.id)
+        .with_labels(&container.config.labels)
+        .with_attribute("name", &container.name)
+}
+
+// memory.events の oom_kill の増加を監視し、oom イベントを発行する
+async fn watch_oom(event: Event, cgroup_dir: PathBuf, pid: Pid, events: EventBus) {
+    let mut oom_kills = read_oom_kill_count(&cgroup_dir);
+
+    while !process_exited(pid) {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        let current = read_oom_kill_count(&cgroup_dir);
+        if current > oom_kills {
+            warn!("OOM killer invoked in container {}", event.actor_id);
+            events.publish(Event {
+                time: Utc::now(),
+                ..event.clone()
+            });
+            oom_kills = current;
+        }
+    }
+}
+
+// プロセスが終了（またはゾンビ化）するまで待つ。タイムアウトした場合は false
+async fn wait_for_exit(pid: Pid, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    loop {
+        if process_exited(pid) {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+fn process_exited(pid: Pid) -> bool {
+    // /proc/<pid>/stat の状態フィールドが Z ならゾンビ
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => stat
+            .rsplit_once(')')
+            .map(|(_, rest)| rest.trim_start().starts_with('Z'))
+            .unwrap_or(false),
+        Err(_) => true,
+    }
+}
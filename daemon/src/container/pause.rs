@@ -0,0 +1,82 @@
+use rocker_core::{cgroup_path, ContainerError, ContainerState, RockerError};
+use std::path::Path;
+use std::time::Duration;
+use tracing::info;
+
+use super::{container_event, Manager};
+
+// cgroup.freeze の完了を待つ最大の時間と確認の間隔
+const FREEZE_TIMEOUT: Duration = Duration::from_secs(10);
+const FREEZE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl Manager {
+    // コンテナの全てのプロセスを cgroup の freezer で一時停止する
+    pub async fn pause(&mut self, id_or_name: &str) -> Result<(), RockerError> {
+        let id = &self.get(id_or_name)?.id.clone();
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+        if container.state.is_paused() {
+            return Err(ContainerError::Paused(id.to_string()).into());
+        }
+        if !container.state.is_running() {
+            return Err(ContainerError::NotRunning(id.to_string()).into());
+        }
+
+        info!("Pausing container {}", id);
+        if !self.dry_run {
+            freeze(&cgroup_path(id), true).await?;
+        }
+        container.state = ContainerState::Paused;
+        self.events.publish(container_event("pause", container));
+        self.save(id).await
+    }
+
+    // 一時停止したコンテナのプロセスを再開する
+    pub async fn unpause(&mut self, id_or_name: &str) -> Result<(), RockerError> {
+        let id = &self.get(id_or_name)?.id.clone();
+        let container = self
+            .containers
+            .get_mut(id)
+            .ok_or_else(|| ContainerError::NotFound(id.to_string()))?;
+        if !container.state.is_paused() {
+            return Err(ContainerError::NotPaused(id.to_string()).into());
+        }
+
+        info!("Unpausing container {}", id);
+        if !self.dry_run {
+            freeze(&cgroup_path(id), false).await?;
+        }
+        container.state = ContainerState::Running;
+        self.events.publish(container_event("unpause", container));
+        self.save(id).await
+    }
+}
+
+// cgroup.freeze を書き換え、cgroup.events の frozen が反映されるまで待つ
+pub(super) async fn freeze(cgroup_dir: &Path, frozen: bool) -> Result<(), ContainerError> {
+    let value = if frozen { "1" } else { "0" };
+    tokio::fs::write(cgroup_dir.join("cgroup.freeze"), value)
+        .await
+        .map_err(|e| ContainerError::Runtime(format!("Failed to write cgroup.freeze: {}", e)))?;
+
+    let expected = format!("frozen {}", value);
+    let deadline = tokio::time::Instant::now() + FREEZE_TIMEOUT;
+    loop {
+        let events = tokio::fs::read_to_string(cgroup_dir.join("cgroup.events"))
+            .await
+            .map_err(|e| ContainerError::Runtime(format!("Failed to read cgroup.events: {}", e)))?;
+        if events.lines().any(|line| line == expected) {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(ContainerError::Runtime(format!(
+                "cgroup did not become {} within {}s",
+                if frozen { "frozen" } else { "thawed" },
+                FREEZE_TIMEOUT.as_secs()
+            )));
+        }
+        tokio::time::sleep(FREEZE_POLL_INTERVAL).await;
+    }
+}
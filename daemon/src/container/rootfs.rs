@@ -0,0 +1,158 @@
+use rocker_core::{ContainerError, ImageLayer};
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::reflink;
+
+// whiteout ファイルの接頭辞（下のレイヤーにあるファイルを削除したことを表す）
+pub(crate) const WHITEOUT_PREFIX: &str = ".wh.";
+// ディレクトリの中身を全て削除したことを表す whiteout
+pub(crate) const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+// イメージのレイヤーを下から順にコピーしてコンテナの rootfs を作る
+//
+// レイヤーを共有できるファイルシステムでは cp の reflink でデータをコピーせずに済ませる。
+pub(crate) async fn create_rootfs(rootfs: &Path, layers: &[ImageLayer]) -> Result<(), ContainerError> {
+    tokio::fs::create_dir_all(rootfs)
+        .await
+        .map_err(|e| ContainerError::Create(format!("{}: {}", rootfs.display(), e)))?;
+
+    for layer in layers.iter().filter(|layer| !layer.empty_layer) {
+        // 下のレイヤーのファイルを whiteout に従って消してからレイヤーを重ねる
+        let (layer_dir, target) = (layer.path.clone(), rootfs.to_path_buf());
+        let whiteouts = tokio::task::spawn_blocking(move || {
+            let mut whiteouts = Vec::new();
+            apply_whiteouts(&layer_dir, &target, &mut whiteouts).map(|_| whiteouts)
+        })
+        .await
+        .map_err(|e| ContainerError::Create(e.to_string()))?
+        .map_err(|e| ContainerError::Create(format!("Failed to apply whiteouts of layer {}: {}", layer.diff_id, e)))?;
+
+        let output = Command::new("cp")
+            .arg("-a")
+            .args(reflink::cp_arg())
+            .arg("--")
+            .arg(layer.path.join("."))
+            .arg(rootfs)
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| ContainerError::Create(format!("Failed to run cp: {}", e)))?;
+        if !output.status.success() {
+            return Err(ContainerError::Create(format!(
+                "Failed to copy layer {}: {}",
+                layer.diff_id,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        // 一緒にコピーされた whiteout 自体を取り除く
+        for whiteout in whiteouts {
+            let _ = tokio::fs::remove_file(&whiteout).await;
+        }
+    }
+    Ok(())
+}
+
+// レイヤーを重ねる前に、whiteout が指すファイルとレイヤーが置き換えるファイルを rootfs から削除し、rootfs 側の
+// whiteout のパスを集める
+//
+// .wh.<name> は下のレイヤーの <name> を、.wh..wh..opq はディレクトリの中身を全て削除する。レイヤーと同じパスに
+// あるファイルは、どちらもディレクトリで中身を重ねる場合を除いて先に削除し、cp が下のレイヤーのシンボリックリンクを
+// 辿ってその先に書き込まないようにする。降りるのは rootfs の本物のディレクトリだけなので、下のレイヤーの
+// etc -> /etc のようなリンクを辿って rootfs の外のファイルを消すことはない。
+fn apply_whiteouts(layer_dir: &Path, dir: &Path, whiteouts: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(layer_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if name == OPAQUE_WHITEOUT {
+            if let Ok(entries) = std::fs::read_dir(dir) {
+                for existing in entries.flatten() {
+                    remove_path(&existing.path())?;
+                }
+            }
+            whiteouts.push(dir.join(&name));
+        } else if let Some(target) = name.strip_prefix(WHITEOUT_PREFIX) {
+            // . や .. の whiteout はディレクトリの外を指すので無視する
+            if !matches!(target, "" | "." | "..") {
+                remove_path(&dir.join(target))?;
+            }
+            whiteouts.push(dir.join(&name));
+        } else {
+            let path = dir.join(&name);
+            let replaced = match std::fs::symlink_metadata(&path) {
+                Ok(existing) => !(existing.is_dir() && entry.file_type()?.is_dir()),
+                Err(_) => false,
+            };
+            if replaced {
+                remove_path(&path)?;
+            }
+            // 新しく作られるディレクトリの中の whiteout も集める
+            if entry.file_type()?.is_dir() {
+                apply_whiteouts(&entry.path(), &path, whiteouts)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// rootfs の中のパスを、シンボリックリンクを rootfs をルートとして解決しながら辿る（securejoin と同じ考え方）
+//
+// リンク先の絶対パスは rootfs からのパスとして、.. は rootfs より上に出ないように扱うため、返すパスは常に rootfs
+// の中にある（まだ無い部分はそのまま繋げる）。最後の要素のリンクも解決するので、返したパスに作成・マウントしてよい。
+pub(crate) fn secure_join(rootfs: &Path, path: &Path) -> std::io::Result<PathBuf> {
+    // 辿るシンボリックリンクの数の上限（Linux の MAXSYMLINKS と同じ）
+    const MAX_SYMLINKS: usize = 40;
+
+    let mut pending: VecDeque<OsString> = components(path).collect();
+    let mut resolved = PathBuf::new();
+    let mut links = 0;
+    while let Some(name) = pending.pop_front() {
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+        let candidate = rootfs.join(&resolved).join(&name);
+        match std::fs::symlink_metadata(&candidate) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return Err(std::io::Error::other(format!(
+                        "Too many levels of symbolic links: {}",
+                        path.display()
+                    )));
+                }
+                let target = std::fs::read_link(&candidate)?;
+                if target.is_absolute() {
+                    resolved.clear();
+                }
+                for component in components(&target).rev() {
+                    pending.push_front(component);
+                }
+            }
+            _ => resolved.push(&name),
+        }
+    }
+    Ok(rootfs.join(resolved))
+}
+
+// パスの名前と .. の要素（/ と . は除く）
+fn components(path: &Path) -> impl DoubleEndedIterator<Item = OsString> + '_ {
+    path.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_os_string()),
+        Component::ParentDir => Some(OsString::from("..")),
+        Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+    })
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(_) => Ok(()),
+    }
+}
@@ -0,0 +1,639 @@
+use rocker_core::{ContainerConfig, ContainerError, Mount, MountType, PropagationMode, Ulimit, CGROUP_ROOT};
+use nix::errno::Errno;
+use nix::fcntl::{open, OFlag};
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use nix::sched::{clone, setns, CloneFlags};
+use nix::sys::resource::{setrlimit, Resource};
+use nix::sys::signal::{kill, Signal};
+use nix::sys::stat::Mode;
+use nix::sys::statvfs::{statvfs, FsFlags};
+use nix::unistd::{chdir, close, dup2, execvpe, pipe2, pivot_root, read, sethostname, setgid, setgroups, setuid, write, Gid, Pid, Uid};
+use std::ffi::CString;
+use std::fs::File;
+use std::os::unix::ffi::OsStrExt;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+use tracing::warn;
+
+use super::exec::resolve_user;
+use super::rootfs::secure_join;
+
+const STACK_SIZE: usize = 1024 * 1024;
+
+// イメージで PATH が指定されていない場合の既定値
+const DEFAULT_PATH: &str = "PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+// コンテナ用 cgroup で有効にするコントローラ
+const CGROUP_CONTROLLERS: &str = "+cpu +memory +io +pids";
+// cpu.max の周期（マイクロ秒）
+const CPU_PERIOD_US: u64 = 100_000;
+
+// コンテナプロセスのネットワーク名前空間
+pub enum NetworkNamespace {
+    // 新しい名前空間を作成する
+    New,
+    // ホストの名前空間（とホスト名）をそのまま使う
+    Host,
+    // 他のコンテナのプロセスの名前空間（とホスト名）に参加する
+    Container(Pid),
+}
+
+// 子プロセスで使う値は clone 前にすべて用意しておく（clone 後のメモリ確保を避ける）
+struct ChildSetup {
+    rootfs: CString,
+    proc_dir: CString,
+    cgroup_procs: CString,
+    mounts: Vec<ChildMount>,
+    root_propagation: MsFlags,
+    oom_score_adj: Option<Vec<u8>>,
+    namespaces: Vec<(File, CloneFlags)>,
+    hostname: Option<String>,
+    sysctls: Vec<(CString, Vec<u8>)>,
+    rlimits: Vec<(Resource, u64, u64)>,
+    working_dir: CString,
+    uid: u32,
+    gid: u32,
+    program: CString,
+    args: Vec<CString>,
+    env: Vec<CString>,
+    sync_fd: RawFd,
+    sync_write_fd: RawFd,
+    error_fd: RawFd,
+    // 標準出力・標準エラー出力にするパイプの書き込み側
+    stdout_fd: RawFd,
+    stderr_fd: RawFd,
+}
+
+// clone 済みで、start の合図を待っているコンテナプロセス
+pub struct CreatedProcess {
+    pub pid: Pid,
+    sync_fd: RawFd,
+    error_fd: RawFd,
+    output: ProcessOutput,
+}
+
+// コンテナプロセスの標準出力・標準エラー出力を読むパイプ
+pub struct ProcessOutput {
+    pub stdout: OwnedFd,
+    pub stderr: OwnedFd,
+}
+
+impl CreatedProcess {
+    // プロセスにセットアップと exec を続行させ、exec の成否を待つ
+    pub fn start(self) -> Result<(Pid, ProcessOutput), ContainerError> {
+        let written = write(self.sync_fd, b"1");
+        let _ = close(self.sync_fd);
+        if let Err(e) = written {
+            let _ = close(self.error_fd);
+            let _ = nix::sys::wait::waitpid(self.pid, None);
+            return Err(ContainerError::Start(format!("Failed to resume container process: {}", e)));
+        }
+
+        // exec に成功するとパイプが閉じられ、失敗した場合はエラー内容が書き込まれる
+        let mut message = Vec::new();
+        let mut buffer = [0u8; 512];
+        loop {
+            match read(self.error_fd, &mut buffer) {
+                Ok(0) => break,
+                Ok(n) => message.extend_from_slice(&buffer[..n]),
+                Err(Errno::EINTR) => continue,
+                Err(_) => break,
+            }
+        }
+        let _ = close(self.error_fd);
+
+        if !message.is_empty() {
+            let _ = nix::sys::wait::waitpid(self.pid, None);
+            return Err(ContainerError::Start(String::from_utf8_lossy(&message).to_string()));
+        }
+
+        Ok((self.pid, self.output))
+    }
+
+    // exec させずにプロセスを終了させる
+    pub fn abort(self) {
+        let _ = close(self.sync_fd);
+        let _ = close(self.error_fd);
+        let _ = kill(self.pid, Signal::SIGKILL);
+        let _ = nix::sys::wait::waitpid(self.pid, None);
+    }
+}
+
+struct ChildMount {
+    source: CString,
+    target: CString,
+    fstype: Option<CString>,
+    flags: MsFlags,
+    // ファイルシステムに渡すオプション（tmpfs の size など）
+    data: Option<CString>,
+    // 読み取り専用にする再マウントのフラグ（元のマウントの nosuid などを含める）
+    remount: Option<MsFlags>,
+    propagation: Option<MsFlags>,
+}
+
+// コンテナ用 cgroup の親ディレクトリを作成し、子 cgroup でコントローラを使えるようにする
+pub fn init_cgroup_root() -> Result<(), ContainerError> {
+    std::fs::create_dir_all(CGROUP_ROOT).map_err(|e| ContainerError::Runtime(e.to_string()))?;
+
+    if let Err(e) = std::fs::write(Path::new(CGROUP_ROOT).join("cgroup.subtree_control"), CGROUP_CONTROLLERS) {
+        warn!("Failed to enable cgroup controllers in {}: {}", CGROUP_ROOT, e);
+    }
+
+    Ok(())
+}
+
+// cgroup にリソース制限を書き込む
+pub fn configure_cgroup(cgroup_dir: &Path, config: &ContainerConfig) -> Result<(), ContainerError> {
+    let limits = &config.resource_limits;
+    if let Some(bytes) = limits.memory_bytes {
+        write_cgroup_file(cgroup_dir, "memory.max", &bytes.to_string())?;
+    }
+    if let Some(bytes) = limits.memory_reservation_bytes {
+        write_cgroup_file(cgroup_dir, "memory.low", &bytes.to_string())?;
+    }
+    // CPU 数は周期あたりに使える時間にする
+    if let Some(cpus) = limits.cpus {
+        if cpus <= 0.0 {
+            return Err(ContainerError::Start(format!("Invalid number of CPUs: {}", cpus)));
+        }
+        let quota = (cpus * CPU_PERIOD_US as f64).round() as u64;
+        write_cgroup_file(cgroup_dir, "cpu.max", &format!("{} {}", quota, CPU_PERIOD_US))?;
+    }
+
+    if let Some(limit) = config.resource_limits.pids_limit {
+        let value = if limit > 0 { limit.to_string() } else { "max".to_string() };
+        write_cgroup_file(cgroup_dir, "pids.max", &value)?;
+    }
+
+    // cgroup v2 には OOM killer を無効にするインターフェースが無い
+    if config.resource_limits.oom_kill_disable {
+        warn!("--oom-kill-disable is not supported on cgroup v2 and is ignored");
+    }
+
+    Ok(())
+}
+
+fn write_cgroup_file(cgroup_dir: &Path, name: &str, value: &str) -> Result<(), ContainerError> {
+    std::fs::write(cgroup_dir.join(name), value)
+        .map_err(|e| ContainerError::Runtime(format!("Failed to write {}: {}", name, e)))
+}
+
+// 新しい名前空間でコンテナの init プロセスを作成する（exec は CreatedProcess::start まで待たせる）
+pub fn create(
+    hostname: &str,
+    network_namespace: &NetworkNamespace,
+    config: &ContainerConfig,
+    rootfs: &Path,
+    cgroup_dir: &Path,
+) -> Result<CreatedProcess, ContainerError> {
+    let cmd = match &config.cmd {
+        Some(cmd) if !cmd.is_empty() => cmd,
+        _ => return Err(ContainerError::Start("No command specified".to_string())),
+    };
+
+    let (uid, gid) = match &config.user {
+        Some(user) => resolve_user(rootfs, user)?,
+        None => (0, 0),
+    };
+
+    let proc_dir = secure_join(rootfs, Path::new("/proc")).map_err(|e| ContainerError::Start(e.to_string()))?;
+    std::fs::create_dir_all(&proc_dir).map_err(|e| ContainerError::Start(e.to_string()))?;
+
+    if let Some(score) = config.oom_score_adj {
+        if !(-1000..=1000).contains(&score) {
+            return Err(ContainerError::Start(format!(
+                "Invalid oom_score_adj {}: must be between -1000 and 1000",
+                score
+            )));
+        }
+    }
+
+    let mut mounts = Vec::new();
+    for mount in &config.mounts {
+        if let Some(child_mount) = prepare_mount(rootfs, mount)? {
+            mounts.push(child_mount);
+        }
+    }
+
+    // shared・slave のマウントがあればホストからの伝播を受けられるよう、/ を private ではなく slave にする
+    let receives_propagation = config.mounts.iter().any(|m| {
+        matches!(
+            m.propagation,
+            Some(PropagationMode::Shared | PropagationMode::RShared | PropagationMode::Slave | PropagationMode::RSlave)
+        )
+    });
+    let root_propagation = if receives_propagation {
+        MsFlags::MS_REC | MsFlags::MS_SLAVE
+    } else {
+        MsFlags::MS_REC | MsFlags::MS_PRIVATE
+    };
+
+    let mut env: Vec<CString> = config
+        .env
+        .iter()
+        .map(|(key, value)| cstring(&format!("{}={}", key, value)))
+        .collect::<Result<_, _>>()?;
+    if !config.env.contains_key("PATH") {
+        env.push(cstring(DEFAULT_PATH)?);
+    }
+
+    let sysctls = config
+        .sysctls
+        .iter()
+        .map(|(key, value)| {
+            let path = format!("/proc/sys/{}", key.replace('.', "/"));
+            Ok((cstring(&path)?, value.as_bytes().to_vec()))
+        })
+        .collect::<Result<Vec<_>, ContainerError>>()?;
+
+    let rlimits = config
+        .ulimits
+        .iter()
+        .map(|ulimit| Ok((rlimit_resource(ulimit)?, ulimit.soft, ulimit.hard)))
+        .collect::<Result<Vec<_>, ContainerError>>()?;
+
+    // UTS 名前空間はネットワーク名前空間と同じ相手と共有する
+    let mut flags = CloneFlags::CLONE_NEWNS | CloneFlags::CLONE_NEWIPC | CloneFlags::CLONE_NEWPID;
+    let mut namespaces = Vec::new();
+    match network_namespace {
+        NetworkNamespace::New => flags |= CloneFlags::CLONE_NEWNET | CloneFlags::CLONE_NEWUTS,
+        NetworkNamespace::Host => {}
+        NetworkNamespace::Container(pid) => {
+            for (name, flag) in [("net", CloneFlags::CLONE_NEWNET), ("uts", CloneFlags::CLONE_NEWUTS)] {
+                let path = format!("/proc/{}/ns/{}", pid, name);
+                let file = File::open(&path)
+                    .map_err(|e| ContainerError::Start(format!("Failed to open {}: {}", path, e)))?;
+                namespaces.push((file, flag));
+            }
+        }
+    }
+    let hostname = match network_namespace {
+        NetworkNamespace::New => Some(hostname.to_string()),
+        NetworkNamespace::Host | NetworkNamespace::Container(_) => None,
+    };
+
+    let (stdout_read, stdout_write) = output_pipe()?;
+    let (stderr_read, stderr_write) = output_pipe()?;
+    let (read_fd, write_fd) = pipe2(OFlag::O_CLOEXEC).map_err(|e| ContainerError::Start(e.to_string()))?;
+    let (sync_read_fd, sync_write_fd) = match pipe2(OFlag::O_CLOEXEC) {
+        Ok(fds) => fds,
+        Err(e) => {
+            let _ = close(read_fd);
+            let _ = close(write_fd);
+            return Err(ContainerError::Start(e.to_string()));
+        }
+    };
+
+    let setup = ChildSetup {
+        rootfs: path_cstring(rootfs)?,
+        proc_dir: path_cstring(&proc_dir)?,
+        cgroup_procs: path_cstring(&cgroup_dir.join("cgroup.procs"))?,
+        mounts,
+        root_propagation,
+        oom_score_adj: config.oom_score_adj.map(|score| score.to_string().into_bytes()),
+        namespaces,
+        hostname,
+        sysctls,
+        rlimits,
+        working_dir: cstring(config.working_dir.as_deref().unwrap_or("/"))?,
+        uid,
+        gid,
+        program: cstring(&cmd[0])?,
+        args: cmd.iter().map(|arg| cstring(arg)).collect::<Result<_, _>>()?,
+        env,
+        sync_fd: sync_read_fd,
+        sync_write_fd,
+        error_fd: write_fd,
+        stdout_fd: stdout_write.as_raw_fd(),
+        stderr_fd: stderr_write.as_raw_fd(),
+    };
+
+    let mut stack = vec![0u8; STACK_SIZE];
+    let result = unsafe {
+        clone(
+            Box::new(|| child_main(&setup)),
+            &mut stack,
+            flags,
+            Some(Signal::SIGCHLD as i32),
+        )
+    };
+    let _ = close(write_fd);
+    let _ = close(sync_read_fd);
+    drop((stdout_write, stderr_write));
+
+    match result {
+        Ok(pid) => Ok(CreatedProcess {
+            pid,
+            sync_fd: sync_write_fd,
+            error_fd: read_fd,
+            output: ProcessOutput {
+                stdout: stdout_read,
+                stderr: stderr_read,
+            },
+        }),
+        Err(e) => {
+            let _ = close(read_fd);
+            let _ = close(sync_write_fd);
+            Err(ContainerError::Start(format!("clone: {}", e)))
+        }
+    }
+}
+
+fn child_main(setup: &ChildSetup) -> isize {
+    // デーモンが終了した場合に EOF を受け取れるよう、書き込み側は閉じておく
+    let _ = close(setup.sync_write_fd);
+
+    // prestart フックの完了を待つ（何も受け取れなければ中止）
+    let mut buffer = [0u8; 1];
+    loop {
+        match read(setup.sync_fd, &mut buffer) {
+            Ok(1) => break,
+            Err(Errno::EINTR) => continue,
+            _ => return 1,
+        }
+    }
+    let _ = close(setup.sync_fd);
+
+    if let Err((step, errno)) = setup_child(setup) {
+        report_error(setup.error_fd, step, errno);
+        return 127;
+    }
+
+    let errno = match execvpe(&setup.program, &setup.args, &setup.env) {
+        Ok(_) => unreachable!(),
+        Err(errno) => errno,
+    };
+    report_error(setup.error_fd, "exec", errno);
+    127
+}
+
+// コンテナの出力を読むパイプ（読み込み側, 書き込み側）
+fn output_pipe() -> Result<(OwnedFd, OwnedFd), ContainerError> {
+    let (read_fd, write_fd) = pipe2(OFlag::O_CLOEXEC).map_err(|e| ContainerError::Start(e.to_string()))?;
+    // pipe2 が返した fd は他で所有されていない
+    Ok(unsafe { (OwnedFd::from_raw_fd(read_fd), OwnedFd::from_raw_fd(write_fd)) })
+}
+
+fn report_error(fd: RawFd, step: &str, errno: Errno) {
+    let message = format!("{}: {}", step, errno);
+    let _ = write(fd, message.as_bytes());
+}
+
+fn setup_child(setup: &ChildSetup) -> Result<(), (&'static str, Errno)> {
+    // 標準出力・標準エラー出力をログドライバへのパイプにつなぐ（dup2 した fd は exec 後も残る）
+    dup2(setup.stdout_fd, 1).map_err(|e| ("redirect stdout", e))?;
+    dup2(setup.stderr_fd, 2).map_err(|e| ("redirect stderr", e))?;
+
+    // cgroup に参加する（"0" は書き込んだプロセス自身を意味する）
+    let fd = open(setup.cgroup_procs.as_c_str(), OFlag::O_WRONLY, Mode::empty()).map_err(|e| ("join cgroup", e))?;
+    write(fd, b"0").map_err(|e| ("join cgroup", e))?;
+    let _ = close(fd);
+
+    if let Some(score) = &setup.oom_score_adj {
+        let fd = open("/proc/self/oom_score_adj", OFlag::O_WRONLY, Mode::empty()).map_err(|e| ("oom_score_adj", e))?;
+        write(fd, score).map_err(|e| ("oom_score_adj", e))?;
+        let _ = close(fd);
+    }
+
+    for (file, flag) in &setup.namespaces {
+        setns(file, *flag).map_err(|e| ("setns", e))?;
+    }
+
+    // マウントの変更がホストへ伝播しないようにする
+    mount(None::<&str>, "/", None::<&str>, setup.root_propagation, None::<&str>)
+        .map_err(|e| ("set root propagation", e))?;
+
+    // rootfs をマウントポイントにしてから pivot_root する
+    mount(
+        Some(setup.rootfs.as_c_str()),
+        setup.rootfs.as_c_str(),
+        None::<&str>,
+        MsFlags::MS_BIND | MsFlags::MS_REC,
+        None::<&str>,
+    )
+    .map_err(|e| ("bind mount rootfs", e))?;
+    mount(
+        Some("proc"),
+        setup.proc_dir.as_c_str(),
+        Some("proc"),
+        MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC | MsFlags::MS_NODEV,
+        None::<&str>,
+    )
+    .map_err(|e| ("mount proc", e))?;
+
+    for child_mount in &setup.mounts {
+        mount(
+            Some(child_mount.source.as_c_str()),
+            child_mount.target.as_c_str(),
+            child_mount.fstype.as_deref(),
+            child_mount.flags,
+            child_mount.data.as_deref(),
+        )
+        .map_err(|e| ("mount", e))?;
+
+        // バインドマウントの読み取り専用指定は再マウントしないと反映されない
+        if let Some(flags) = child_mount.remount {
+            mount(None::<&str>, child_mount.target.as_c_str(), None::<&str>, flags, None::<&str>)
+                .map_err(|e| ("remount read-only", e))?;
+        }
+
+        if let Some(propagation) = child_mount.propagation {
+            mount(None::<&str>, child_mount.target.as_c_str(), None::<&str>, propagation, None::<&str>)
+                .map_err(|e| ("set mount propagation", e))?;
+        }
+    }
+
+    chdir(setup.rootfs.as_c_str()).map_err(|e| ("chdir rootfs", e))?;
+    pivot_root(".", ".").map_err(|e| ("pivot_root", e))?;
+    umount2(".", MntFlags::MNT_DETACH).map_err(|e| ("detach old root", e))?;
+    chdir("/").map_err(|e| ("chdir /", e))?;
+
+    if let Some(hostname) = &setup.hostname {
+        sethostname(hostname).map_err(|e| ("sethostname", e))?;
+    }
+
+    for (path, value) in &setup.sysctls {
+        let fd = open(path.as_c_str(), OFlag::O_WRONLY | OFlag::O_TRUNC, Mode::empty()).map_err(|e| ("sysctl", e))?;
+        write(fd, value).map_err(|e| ("sysctl", e))?;
+        let _ = close(fd);
+    }
+
+    for (resource, soft, hard) in &setup.rlimits {
+        setrlimit(*resource, *soft, *hard).map_err(|e| ("setrlimit", e))?;
+    }
+
+    chdir(setup.working_dir.as_c_str()).map_err(|e| ("chdir working directory", e))?;
+
+    setgroups(&[Gid::from_raw(setup.gid)]).map_err(|e| ("setgroups", e))?;
+    setgid(Gid::from_raw(setup.gid)).map_err(|e| ("setgid", e))?;
+    setuid(Uid::from_raw(setup.uid)).map_err(|e| ("setuid", e))?;
+
+    Ok(())
+}
+
+// マウント先を rootfs 内に用意し、子プロセスでのマウントに必要な値を作る
+fn prepare_mount(rootfs: &Path, mount: &Mount) -> Result<Option<ChildMount>, ContainerError> {
+    // イメージのシンボリックリンクは rootfs をルートとして解決し、マウント先が rootfs の外にならないようにする
+    let target = secure_join(rootfs, Path::new(&mount.destination))
+        .map_err(|e| ContainerError::Start(format!("{}: {}", mount.destination, e)))?;
+
+    let (source, fstype, mut flags) = match mount.mount_type {
+        // ボリュームの source は Manager::start でボリュームのディレクトリに置き換えてある
+        MountType::Bind | MountType::Volume => {
+            let source = Path::new(&mount.source);
+            // ファイルのバインドマウントにはマウント先のファイルが必要
+            if source.is_dir() {
+                std::fs::create_dir_all(&target).map_err(|e| ContainerError::Start(e.to_string()))?;
+            } else {
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent).map_err(|e| ContainerError::Start(e.to_string()))?;
+                }
+                if std::fs::symlink_metadata(&target).is_err() {
+                    std::fs::File::create(&target).map_err(|e| ContainerError::Start(e.to_string()))?;
+                }
+            }
+            (path_cstring(source)?, None, MsFlags::MS_BIND | MsFlags::MS_REC)
+        }
+        MountType::Tmpfs => {
+            std::fs::create_dir_all(&target).map_err(|e| ContainerError::Start(e.to_string()))?;
+            (cstring("tmpfs")?, Some(cstring("tmpfs")?), MsFlags::MS_NOSUID | MsFlags::MS_NODEV)
+        }
+    };
+
+    let is_bind = matches!(mount.mount_type, MountType::Bind | MountType::Volume);
+    let remount = if mount.read_only && is_bind {
+        // 元のマウントの nosuid・nodev・noexec などは外せない（外そうとすると EPERM になる）
+        Some(MsFlags::MS_BIND | MsFlags::MS_REMOUNT | MsFlags::MS_RDONLY | locked_flags(Path::new(&mount.source)))
+    } else {
+        if mount.read_only {
+            flags |= MsFlags::MS_RDONLY;
+        }
+        None
+    };
+
+    if let Some(propagation) = mount.propagation {
+        if is_bind {
+            check_source_propagation(Path::new(&mount.source), propagation)?;
+        }
+    }
+    let propagation = mount.propagation.map(|propagation| {
+        let flag = match propagation {
+            PropagationMode::Private | PropagationMode::RPrivate => MsFlags::MS_PRIVATE,
+            PropagationMode::Shared | PropagationMode::RShared => MsFlags::MS_SHARED,
+            PropagationMode::Slave | PropagationMode::RSlave => MsFlags::MS_SLAVE,
+        };
+        if propagation.is_recursive() {
+            flag | MsFlags::MS_REC
+        } else {
+            flag
+        }
+    });
+
+    let data = match (&mount.mount_type, mount.tmpfs_size) {
+        (MountType::Tmpfs, Some(size)) => Some(cstring(&format!("size={}", size))?),
+        _ => None,
+    };
+
+    Ok(Some(ChildMount {
+        source,
+        target: path_cstring(&target)?,
+        fstype,
+        flags,
+        data,
+        remount,
+        propagation,
+    }))
+}
+
+// バインドマウント元のマウントに付いている、再マウントで維持するフラグ
+fn locked_flags(source: &Path) -> MsFlags {
+    let stat = match statvfs(source) {
+        Ok(stat) => stat,
+        Err(_) => return MsFlags::empty(),
+    };
+    [
+        (FsFlags::ST_NOSUID, MsFlags::MS_NOSUID),
+        (FsFlags::ST_NODEV, MsFlags::MS_NODEV),
+        (FsFlags::ST_NOEXEC, MsFlags::MS_NOEXEC),
+        (FsFlags::ST_NOATIME, MsFlags::MS_NOATIME),
+        (FsFlags::ST_NODIRATIME, MsFlags::MS_NODIRATIME),
+        (FsFlags::ST_RELATIME, MsFlags::MS_RELATIME),
+    ]
+    .iter()
+    .filter(|(fs_flag, _)| stat.flags().contains(*fs_flag))
+    .fold(MsFlags::empty(), |flags, (_, ms_flag)| flags | *ms_flag)
+}
+
+// shared・slave の伝播には、マウント元がホストで shared（slave は shared か slave）である必要がある
+//
+// /proc/self/mountinfo で source を含む最も深いマウントを探し、7 番目以降の shared:N・master:N を見る。
+fn check_source_propagation(source: &Path, propagation: PropagationMode) -> Result<(), ContainerError> {
+    let allow_slave = match propagation {
+        PropagationMode::Shared | PropagationMode::RShared => false,
+        PropagationMode::Slave | PropagationMode::RSlave => true,
+        PropagationMode::Private | PropagationMode::RPrivate => return Ok(()),
+    };
+
+    let source = source
+        .canonicalize()
+        .map_err(|e| ContainerError::Start(format!("{}: {}", source.display(), e)))?;
+    let mountinfo =
+        std::fs::read_to_string("/proc/self/mountinfo").map_err(|e| ContainerError::Start(e.to_string()))?;
+    let mount = mountinfo
+        .lines()
+        .map(|line| line.split(' ').collect::<Vec<_>>())
+        .filter(|fields| fields.len() > 5 && source.starts_with(fields[4]))
+        .max_by_key(|fields| fields[4].len());
+    let (mount_point, optional) = match &mount {
+        Some(fields) => {
+            let end = fields.iter().position(|field| *field == "-").unwrap_or(fields.len());
+            (fields[4], &fields[6.min(end)..end])
+        }
+        None => return Ok(()),
+    };
+
+    let shared = optional.iter().any(|field| field.starts_with("shared:"));
+    let slave = optional.iter().any(|field| field.starts_with("master:"));
+    if shared || (allow_slave && slave) {
+        return Ok(());
+    }
+    Err(ContainerError::Start(format!(
+        "Path {} is mounted on {} but it is not a {} mount (run mount --make-rshared {} on the host)",
+        source.display(),
+        mount_point,
+        if allow_slave { "shared or slave" } else { "shared" },
+        mount_point
+    )))
+}
+
+fn rlimit_resource(ulimit: &Ulimit) -> Result<Resource, ContainerError> {
+    let resource = match ulimit.name.as_str() {
+        "as" => Resource::RLIMIT_AS,
+        "core" => Resource::RLIMIT_CORE,
+        "cpu" => Resource::RLIMIT_CPU,
+        "data" => Resource::RLIMIT_DATA,
+        "fsize" => Resource::RLIMIT_FSIZE,
+        "locks" => Resource::RLIMIT_LOCKS,
+        "memlock" => Resource::RLIMIT_MEMLOCK,
+        "msgqueue" => Resource::RLIMIT_MSGQUEUE,
+        "nice" => Resource::RLIMIT_NICE,
+        "nofile" => Resource::RLIMIT_NOFILE,
+        "nproc" => Resource::RLIMIT_NPROC,
+        "rss" => Resource::RLIMIT_RSS,
+        "rtprio" => Resource::RLIMIT_RTPRIO,
+        "rttime" => Resource::RLIMIT_RTTIME,
+        "sigpending" => Resource::RLIMIT_SIGPENDING,
+        "stack" => Resource::RLIMIT_STACK,
+        _ => return Err(ContainerError::Start(format!("Invalid ulimit type: {}", ulimit.name))),
+    };
+    Ok(resource)
+}
+
+fn cstring(s: &str) -> Result<CString, ContainerError> {
+    CString::new(s).map_err(|e| ContainerError::Start(e.to_string()))
+}
+
+fn path_cstring(path: &Path) -> Result<CString, ContainerError> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|e| ContainerError::Start(e.to_string()))
+}
@@ -0,0 +1,125 @@
+#[cfg(target_os = "linux")]
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use nix::unistd::{chown, Gid, Uid};
+use rocker_core::{ContainerError, Mount, MountType, SecretError, SecretReference};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::Path;
+use tracing::warn;
+
+use crate::secret;
+
+// コンテナのディレクトリの下で秘密情報のファイルを置く tmpfs
+const SECRETS_DIR_NAME: &str = "secrets";
+
+// tmpfs の 1 ページ（ファイルごとに最低 1 ページを使う）
+const PAGE_SIZE: usize = 4096;
+
+// 秘密情報を全て復号する（起動の前に、参照している秘密情報が全てあることを確かめる）
+pub fn resolve(
+    references: &[SecretReference],
+    secrets: &secret::Manager,
+) -> Result<Vec<(SecretReference, Vec<u8>)>, SecretError> {
+    let mut targets = Vec::new();
+    let mut files = Vec::new();
+    for reference in references {
+        reference.validate().map_err(SecretError::Invalid)?;
+        let target = reference.target_path();
+        if targets.contains(&target) {
+            return Err(SecretError::Invalid(format!("Duplicate secret target: {}", target)));
+        }
+        targets.push(target);
+        files.push((reference.clone(), secrets.reveal(&reference.source)?));
+    }
+    Ok(files)
+}
+
+// コンテナのディレクトリに tmpfs をマウントして秘密情報を書き、ファイルごとに読み取り専用のバインドマウントを返す
+//
+// 復号した内容はディスクに書かない。tmpfs は停止・削除の際に release で外す。
+pub fn prepare(container_dir: &Path, files: &[(SecretReference, Vec<u8>)]) -> Result<Vec<Mount>, ContainerError> {
+    let dir = container_dir.join(SECRETS_DIR_NAME);
+    // 前回のデーモンが外さずに終了した場合に備えて作り直す
+    release(container_dir);
+    std::fs::create_dir_all(&dir).map_err(|e| start_error(&dir, e))?;
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700)).map_err(|e| start_error(&dir, e))?;
+
+    let size: usize = files.iter().map(|(_, data)| (data.len() / PAGE_SIZE + 1) * PAGE_SIZE).sum();
+    mount_tmpfs(&dir, size.max(PAGE_SIZE))
+        .map_err(|e| ContainerError::Start(format!("Failed to mount tmpfs for secrets on {}: {}", dir.display(), e)))?;
+
+    let mut mounts = Vec::new();
+    for (index, (reference, data)) in files.iter().enumerate() {
+        let path = dir.join(index.to_string());
+        let written = std::fs::write(&path, data)
+            .and_then(|_| std::fs::set_permissions(&path, std::fs::Permissions::from_mode(reference.mode)))
+            .map_err(|e| start_error(&path, e))
+            .and_then(|_| {
+                chown(&path, Some(Uid::from_raw(reference.uid)), Some(Gid::from_raw(reference.gid)))
+                    .map_err(|e| ContainerError::Start(format!("Failed to chown {}: {}", path.display(), e)))
+            });
+        if let Err(e) = written {
+            release(container_dir);
+            return Err(e);
+        }
+        mounts.push(Mount {
+            mount_type: MountType::Bind,
+            source: path.to_string_lossy().to_string(),
+            destination: reference.target_path(),
+            read_only: true,
+            propagation: None,
+            relabel: None,
+            no_copy: false,
+            tmpfs_size: None,
+        });
+    }
+    Ok(mounts)
+}
+
+// 秘密情報の tmpfs を外して消す（マウントしていなければ何もしない）
+pub fn release(container_dir: &Path) {
+    let dir = container_dir.join(SECRETS_DIR_NAME);
+    let mounted = match (std::fs::metadata(&dir), std::fs::metadata(container_dir)) {
+        (Ok(dir), Ok(parent)) => dir.dev() != parent.dev(),
+        _ => return,
+    };
+    if mounted {
+        if let Err(e) = unmount(&dir) {
+            warn!("Failed to unmount secrets on {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        warn!("Failed to remove {}: {}", dir.display(), e);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn mount_tmpfs(dir: &Path, size: usize) -> nix::Result<()> {
+    mount(
+        Some("tmpfs"),
+        dir,
+        Some("tmpfs"),
+        MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+        Some(format!("mode=0700,size={}", size).as_str()),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn unmount(dir: &Path) -> nix::Result<()> {
+    umount2(dir, MntFlags::MNT_DETACH)
+}
+
+// Linux 以外では tmpfs をマウントできない（--dry-run のコンテナには秘密情報を書かない）
+#[cfg(not(target_os = "linux"))]
+fn mount_tmpfs(_dir: &Path, _size: usize) -> nix::Result<()> {
+    Err(nix::errno::Errno::ENOSYS)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unmount(dir: &Path) -> nix::Result<()> {
+    nix::mount::unmount(dir, nix::mount::MntFlags::empty())
+}
+
+fn start_error(path: &Path, e: std::io::Error) -> ContainerError {
+    ContainerError::Start(format!("Failed to write {}: {}", path.display(), e))
+}
@@ -0,0 +1,114 @@
+use rocker_core::Container;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// rootfs を作り終えた時刻を ctime で記録するファイル（コンテナの状態のディレクトリに置く）
+const BASELINE_FILE: &str = "rootfs.baseline";
+
+// 動作中のコンテナの走査結果を使い回す時間
+const RUNNING_TTL: Duration = Duration::from_secs(30);
+
+// コンテナの書き込み層（作成後に書き込まれたファイル）の大きさ
+//
+// rootfs はイメージのレイヤーをコピーして作るため、コンテナが作成・変更したファイルは ctime が
+// rootfs.baseline より新しい。ファイルの中身は読まずに stat だけで数える。停止中のコンテナの rootfs は
+// デーモンを通してしか変わらないため、結果は invalidate されるまで使い回す。
+#[derive(Default)]
+pub struct SizeCache {
+    // コンテナ ID ごとの大きさと、動作中に走査した場合はその時刻
+    entries: Mutex<HashMap<String, (u64, Option<Instant>)>>,
+}
+
+impl SizeCache {
+    pub async fn size_rw(&self, container: &Container, container_dir: &Path) -> u64 {
+        let running = container.state.is_running() || container.state.is_paused();
+        if let Some((size, scanned_at)) = self.entries.lock().unwrap().get(&container.id) {
+            let fresh = match scanned_at {
+                Some(scanned_at) => scanned_at.elapsed() < RUNNING_TTL,
+                None => !running,
+            };
+            if fresh {
+                return *size;
+            }
+        }
+
+        // ボリュームやバインドマウントの中身はコンテナの書き込み層に含めない
+        let rootfs = container_dir.join("rootfs");
+        let skip: HashSet<PathBuf> = container
+            .config
+            .mounts
+            .iter()
+            .map(|mount| rootfs.join(mount.destination.trim_start_matches('/')))
+            .collect();
+        let baseline = container_dir.join(BASELINE_FILE);
+        let size = tokio::task::spawn_blocking(move || writable_size(&rootfs, &baseline, &skip))
+            .await
+            .unwrap_or(0);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(container.id.clone(), (size, running.then(Instant::now)));
+        size
+    }
+
+    pub fn invalidate(&self, id: &str) {
+        self.entries.lock().unwrap().remove(id);
+    }
+}
+
+// rootfs を作り終えたことを記録する（この後に書き込まれたファイルが書き込み層になる）
+pub fn mark_baseline(container_dir: &Path) -> std::io::Result<()> {
+    std::fs::write(container_dir.join(BASELINE_FILE), b"")
+}
+
+// 記録が無い（以前のデーモンで作成した）コンテナは、今から書き込まれたファイルを数える
+fn writable_size(rootfs: &Path, baseline: &Path, skip: &HashSet<PathBuf>) -> u64 {
+    let baseline = match std::fs::metadata(baseline) {
+        Ok(metadata) => (metadata.ctime(), metadata.ctime_nsec()),
+        Err(_) => {
+            let _ = std::fs::write(baseline, b"");
+            return 0;
+        }
+    };
+    let root = match std::fs::symlink_metadata(rootfs) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    let mut seen = HashSet::new();
+    scan(rootfs, root.dev(), baseline, skip, &mut seen)
+}
+
+// ハードリンクは 1 度だけ数え、別のファイルシステム（/proc など）とマウント先には入らない
+fn scan(dir: &Path, dev: u64, baseline: (i64, i64), skip: &HashSet<PathBuf>, seen: &mut HashSet<u64>) -> u64 {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let metadata = match path.symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.dev() != dev || skip.contains(&path) {
+            continue;
+        }
+        if metadata.is_dir() {
+            total += scan(&path, dev, baseline, skip, seen);
+            continue;
+        }
+        if (metadata.ctime(), metadata.ctime_nsec()) <= baseline {
+            continue;
+        }
+        if metadata.nlink() > 1 && !seen.insert(metadata.ino()) {
+            continue;
+        }
+        total += metadata.blocks() * 512;
+    }
+    total
+}
@@ -0,0 +1,84 @@
+// Linux 以外の OS で runtime と device の代わりに使うモジュール
+//
+// 名前空間・cgroup・デバイスファイルが無いため、コンテナは --dry-run でしか起動できない。--dry-run の
+// コンテナはここを通らないので、呼ばれた場合は常にエラーを返す。
+
+use rocker_core::{ContainerError, DeviceCgroupRule};
+
+fn unsupported(operation: &str) -> ContainerError {
+    ContainerError::Runtime(format!("{} is only available on Linux (use rockerd --dry-run)", operation))
+}
+
+pub mod runtime {
+    use rocker_core::{ContainerConfig, ContainerError};
+    use nix::unistd::Pid;
+    use std::os::fd::OwnedFd;
+    use std::path::Path;
+
+    use super::unsupported;
+
+    pub enum NetworkNamespace {
+        New,
+        Host,
+        Container(Pid),
+    }
+
+    pub struct CreatedProcess {
+        pub pid: Pid,
+    }
+
+    pub struct ProcessOutput {
+        pub stdout: OwnedFd,
+        pub stderr: OwnedFd,
+    }
+
+    impl CreatedProcess {
+        pub fn start(self) -> Result<(Pid, ProcessOutput), ContainerError> {
+            Err(unsupported("Running containers"))
+        }
+
+        pub fn abort(self) {}
+    }
+
+    pub fn init_cgroup_root() -> Result<(), ContainerError> {
+        Err(unsupported("cgroups"))
+    }
+
+    pub fn configure_cgroup(_cgroup_dir: &Path, _config: &ContainerConfig) -> Result<(), ContainerError> {
+        Err(unsupported("cgroups"))
+    }
+
+    pub fn create(
+        _hostname: &str,
+        network_namespace: &NetworkNamespace,
+        _config: &ContainerConfig,
+        _rootfs: &Path,
+        _cgroup_dir: &Path,
+    ) -> Result<CreatedProcess, ContainerError> {
+        let operation = match network_namespace {
+            NetworkNamespace::New => "Running containers".to_string(),
+            NetworkNamespace::Host => "Running containers in the host network".to_string(),
+            NetworkNamespace::Container(pid) => format!("Joining the network namespace of process {}", pid),
+        };
+        Err(unsupported(&operation))
+    }
+}
+
+pub mod device {
+    use rocker_core::{ContainerConfig, ContainerError};
+    use std::path::Path;
+
+    use super::{unsupported, DeviceCgroupRule};
+
+    pub fn create_device_nodes(_rootfs: &Path, _config: &ContainerConfig) -> Result<(), ContainerError> {
+        Err(unsupported("Device files"))
+    }
+
+    pub fn device_rules(_config: &ContainerConfig) -> Result<Vec<DeviceCgroupRule>, ContainerError> {
+        Err(unsupported("Device cgroup rules"))
+    }
+
+    pub fn apply_device_filter(_cgroup_dir: &Path, _rules: &[DeviceCgroupRule]) -> Result<(), ContainerError> {
+        Err(unsupported("Device cgroup rules"))
+    }
+}
@@ -0,0 +1,178 @@
+use rocker_core::{Container, ContainerError, HealthStatus, WaitFor, DEFAULT_WAIT_TIMEOUT};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+
+use super::{network_name_of, Manager};
+use crate::RockerDaemon;
+
+// 依存先の状態を確認する間隔と、TCP の接続を諦めるまでの時間
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// --wait-for の条件を確認した結果
+enum Readiness {
+    Ready,
+    // 待ち続ける（理由はタイムアウトのエラーに含める）
+    Pending(String),
+    // ロックを外して接続を試す
+    Connect(String, u16),
+}
+
+// コンテナの --wait-for の条件が全て満たされるまで待つ
+//
+// ロックは確認のたびに取り直すため、待っている間も依存先のコンテナを起動・ヘルスチェックできる。
+// wait_timeout（既定は DEFAULT_WAIT_TIMEOUT 秒）を過ぎても満たされない条件があればエラーにする。
+pub async fn wait_for_dependencies(daemon: &Arc<Mutex<RockerDaemon>>, id_or_name: &str) -> Result<(), ContainerError> {
+    let (id, mut pending, timeout) = {
+        let daemon = daemon.lock().await;
+        let container = daemon.container_manager.get(id_or_name)?;
+        let timeout = Duration::from_secs(container.config.wait_timeout.unwrap_or(DEFAULT_WAIT_TIMEOUT));
+        (container.id.clone(), container.config.wait_for.clone(), timeout)
+    };
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    info!("Container {} is waiting for {} dependencies", id, pending.len());
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let checks: Vec<Readiness> = {
+            let daemon = daemon.lock().await;
+            let manager = &daemon.container_manager;
+            let container = manager.get(&id)?;
+            pending.iter().map(|target| manager.readiness(container, target)).collect()
+        };
+
+        let mut reasons = Vec::new();
+        let mut still_pending = Vec::new();
+        for (target, check) in pending.into_iter().zip(checks) {
+            let reason = match check {
+                Readiness::Ready => None,
+                Readiness::Pending(reason) => Some(reason),
+                Readiness::Connect(host, port) => {
+                    match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port))).await {
+                        Ok(Ok(_)) => None,
+                        Ok(Err(e)) => Some(e.to_string()),
+                        Err(_) => Some("connection timed out".to_string()),
+                    }
+                }
+            };
+            match reason {
+                Some(reason) => {
+                    reasons.push(format!("{} ({})", target, reason));
+                    still_pending.push(target);
+                }
+                None => info!("Dependency {} of container {} is ready", target, id),
+            }
+        }
+        pending = still_pending;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(ContainerError::DependenciesNotReady(format!(
+                "timed out after {}s waiting for {}",
+                timeout.as_secs(),
+                reasons.join(", ")
+            )));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+// 依存先を待ってからコンテナを起動する（デーモンの起動時に復元するコンテナ用）
+pub fn spawn_start(daemon: Arc<Mutex<RockerDaemon>>, id: String) {
+    tokio::spawn(async move {
+        if let Err(e) = wait_for_dependencies(&daemon, &id).await {
+            error!("Failed to restore container {}: {}", id, e);
+            return;
+        }
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        match daemon
+            .container_manager
+            .start(&id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+            .await
+        {
+            Ok(_) => info!("Restored container: {}", id),
+            Err(e) => error!("Failed to restore container {}: {}", id, e),
+        }
+    });
+}
+
+impl Manager {
+    // 起動するコンテナから見た --wait-for の条件の状態
+    fn readiness(&self, container: &Container, target: &WaitFor) -> Readiness {
+        match target {
+            WaitFor::Container(name) => match self.dependency(container, name) {
+                Some(dependency) => container_readiness(dependency),
+                None => Readiness::Pending(ContainerError::NotFound(name.clone()).to_string()),
+            },
+            // 依存先のコンテナの名前なら、同じネットワークでのアドレスに接続する
+            WaitFor::Tcp { host, port } => match self.dependency(container, host) {
+                Some(dependency) if !dependency.state.is_running() => {
+                    Readiness::Pending(format!("container is {}", dependency.state))
+                }
+                Some(dependency) => match endpoint_address(container, dependency) {
+                    Some(address) => Readiness::Connect(address, *port),
+                    None => Readiness::Pending("container has no address on a shared network".to_string()),
+                },
+                None => Readiness::Connect(host.clone(), *port),
+            },
+        }
+    }
+
+    // その名前のコンテナ、起動するコンテナのネットワークでその別名を持つコンテナ、ID の前方一致の順に探す
+    //
+    // compose のサービス名は別名になっているため、サービス名でも依存先を指定できる。
+    fn dependency(&self, container: &Container, name: &str) -> Option<&Container> {
+        let networks = network_names(container);
+        let has_alias = |other: &Container| {
+            other
+                .networks
+                .iter()
+                .any(|(network, endpoint)| networks.contains(network.as_str()) && endpoint.aliases.iter().any(|alias| alias == name))
+        };
+        self.containers
+            .values()
+            .find(|other| other.name == name)
+            .or_else(|| self.containers.values().find(|other| other.id != container.id && has_alias(other)))
+            .or_else(|| self.get(name).ok())
+    }
+}
+
+fn container_readiness(dependency: &Container) -> Readiness {
+    if !dependency.state.is_running() {
+        return Readiness::Pending(format!("container is {}", dependency.state));
+    }
+    if dependency.config.healthcheck.is_none() {
+        return Readiness::Ready;
+    }
+    match dependency.health.as_ref().map(|health| health.status) {
+        Some(HealthStatus::Healthy) => Readiness::Ready,
+        Some(status) => Readiness::Pending(format!("container is {}", status)),
+        None => Readiness::Pending("container has no health status yet".to_string()),
+    }
+}
+
+// 依存先のコンテナの、起動するコンテナと共有するネットワークでのアドレス（なければ主なアドレス）
+fn endpoint_address(container: &Container, dependency: &Container) -> Option<String> {
+    let networks = network_names(container);
+    dependency
+        .networks
+        .iter()
+        .find(|(network, endpoint)| networks.contains(network.as_str()) && !endpoint.ip_address.is_empty())
+        .map(|(_, endpoint)| endpoint.ip_address.clone())
+        .or_else(|| dependency.ip_address.clone())
+}
+
+// 起動するコンテナが接続されるネットワークの名前
+fn network_names(container: &Container) -> HashSet<&str> {
+    let mut names: HashSet<&str> = container.networks.keys().map(String::as_str).collect();
+    names.extend(network_name_of(&container.config.network_mode));
+    names
+}
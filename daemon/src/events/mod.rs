@@ -0,0 +1,29 @@
+use rocker_core::Event;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+// 購読者が受信しきれなかった場合に保持するイベント数
+const EVENT_BUFFER_SIZE: usize = 256;
+
+// デーモン内で発生したイベントを購読者に配信する
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        EventBus { sender }
+    }
+
+    pub fn publish(&self, event: Event) {
+        debug!("Event: {} {} {}", event.event_type, event.action, event.actor_id);
+        // 購読者がいない場合の送信エラーは無視する
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
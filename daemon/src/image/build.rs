@@ -0,0 +1,1290 @@
+use chrono::Utc;
+#[cfg(target_os = "linux")]
+use nix::mount::{mount, MsFlags};
+#[cfg(target_os = "linux")]
+use nix::sched::{unshare, CloneFlags};
+#[cfg(target_os = "linux")]
+use nix::unistd::{chroot, setgid, setgroups, setuid, Gid, Uid};
+use rocker_core::{
+    calculate_dir_hash, calculate_string_hash, parse_duration, BuildEvent, BuildMetadata, HealthConfig, Image, ImageConfig, ImageError, ImageLayer, ProgressMessage, RegistryAuth, RegistryReference, RockerError,
+};
+use rockerfile_parser::{Instruction, RockerfileParser, Stage};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+use super::cache::{content_hash, BuildCache, CacheRecord, CacheSpec};
+use super::diff::{write_diff, Snapshot, COPY_CHUNK};
+use super::import::unpack_layer;
+use super::Manager;
+use crate::container::{create_rootfs, resolve_user, secure_join};
+use crate::proxy::{self, PROXY_BUILD_ARGS};
+use crate::reflink;
+
+// Rockerfile を指定しなかった場合に探すファイル名
+const DEFAULT_ROCKERFILES: [&str; 2] = ["Rockerfile", "Dockerfile"];
+// SHELL を指定しなかった場合のシェル
+const DEFAULT_SHELL: [&str; 2] = ["/bin/sh", "-c"];
+// RUN の環境変数に PATH が無い場合の値
+const DEFAULT_PATH: &str = "PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+// イメージのビルドのオプション
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    // ビルドコンテキストからの Rockerfile のパス（省略時は Rockerfile か Dockerfile）
+    pub rockerfile: Option<String>,
+    // 作成したイメージに付ける repo:tag
+    pub tag: Option<String>,
+    // ビルドを終えるステージの名前
+    pub target: Option<String>,
+    pub build_args: HashMap<String, String>,
+    pub labels: HashMap<String, String>,
+    // ローカルにあってもベースイメージを pull し直す
+    pub pull: bool,
+    // ベースイメージの pull に使う認証情報（レジストリごと）
+    pub registry_auths: HashMap<String, RegistryAuth>,
+    // ビルドしたイメージのレイヤーを 1 つにまとめる
+    pub squash: bool,
+    // ビルドキャッシュを使わずに全ての命令を実行する
+    pub no_cache: bool,
+    // キャッシュを読み込む場所（先に指定したものを優先する）
+    pub cache_from: Vec<CacheSpec>,
+    // このビルドのキャッシュを書き出す場所
+    pub cache_to: Option<CacheSpec>,
+}
+
+// ビルド中のステージの状態
+struct StageState {
+    name: Option<String>,
+    rootfs: PathBuf,
+    // 最後にレイヤーを作った時点の rootfs のスナップショット
+    snapshot: Snapshot,
+    layers: Vec<ImageLayer>,
+    config: ImageConfig,
+    // ステージで宣言された ARG の値
+    args: HashMap<String, String>,
+    shell: Vec<String>,
+    parent_id: Option<String>,
+    // ここまでの命令から決まるビルドキャッシュのキー
+    cache_key: String,
+}
+
+impl StageState {
+    // 変数の展開に使う値（ENV が ARG より優先される）
+    fn variables(&self) -> HashMap<String, String> {
+        let mut variables = self.args.clone();
+        for env in &self.config.env {
+            if let Some((key, value)) = env.split_once('=') {
+                variables.insert(key.to_string(), value.to_string());
+            }
+        }
+        variables
+    }
+
+    fn expand(&self, value: &str) -> String {
+        expand(value, &self.variables())
+    }
+
+    // 命令のビルドキャッシュのキー（ここまでのキー・命令の内容・ARG の値から決める）
+    fn step_key(&self, input: &str) -> String {
+        let args: BTreeMap<&String, &String> = self.args.iter().collect();
+        let args = serde_json::to_string(&args).unwrap_or_default();
+        calculate_string_hash(&format!("{}\n{}\n{}", self.cache_key, input, args))
+    }
+
+    // rootfs 内のパス（相対パスは WORKDIR から、シンボリックリンクは rootfs の中で解決する）
+    fn container_path(&self, path: &str) -> std::io::Result<PathBuf> {
+        let base = self.config.working_dir.as_deref().unwrap_or("/");
+        secure_join(&self.rootfs, &Path::new(base).join(path))
+    }
+
+    // 設定だけを変える命令の履歴
+    fn record(&mut self, created_by: String) {
+        self.layers.push(ImageLayer {
+            id: String::new(),
+            diff_id: String::new(),
+            size: 0,
+            path: PathBuf::new(),
+            created_at: Utc::now(),
+            created_by: Some(created_by),
+            empty_layer: true,
+        });
+    }
+}
+
+impl Manager {
+    // tar（gzip 圧縮も可）で受け取ったビルドコンテキストを展開してビルドする
+    pub async fn build_archive(
+        &self,
+        archive: Vec<u8>,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Image, RockerError> {
+        let context_dir = self.build_dir.join(format!("context-{}", uuid::Uuid::new_v4()));
+        let unpack_dir = context_dir.clone();
+        let unpacked = tokio::task::spawn_blocking(move || {
+            unpack_layer(Box::new(std::io::Cursor::new(archive)), &unpack_dir)
+        })
+        .await?;
+        let result = match unpacked {
+            Ok(_) => self.build(&context_dir, options, progress).await,
+            Err(e) => Err(ImageError::Build(format!("Invalid build context: {}", e)).into()),
+        };
+        let _ = std::fs::remove_dir_all(&context_dir);
+        result
+    }
+
+    // context_dir の Rockerfile からイメージをビルドして登録する（進捗は progress に送る）
+    pub async fn build(
+        &self,
+        context_dir: &Path,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Image, RockerError> {
+        let _build = self.track_build();
+        let started_at = Utc::now();
+        let rockerfile = match &options.rockerfile {
+            Some(path) => secure_join(context_dir, Path::new(path))?,
+            None => {
+                let candidates = DEFAULT_ROCKERFILES
+                    .iter()
+                    .map(|name| secure_join(context_dir, Path::new(name)))
+                    .collect::<std::io::Result<Vec<_>>>()?;
+                candidates.iter().find(|path| path.exists()).unwrap_or(&candidates[0]).clone()
+            }
+        };
+        let content = tokio::fs::read_to_string(&rockerfile).await.map_err(|e| {
+            ImageError::Build(format!("Cannot read {}: {}", rockerfile.strip_prefix(context_dir).unwrap_or(&rockerfile).display(), e))
+        })?;
+
+        let mut parser = RockerfileParser::new();
+        let mut stages: Vec<Stage> = parser
+            .parse_content(&content)
+            .map_err(|e| ImageError::Build(e.to_string()))?
+            .to_vec();
+
+        // 最初の FROM より前の ARG は FROM の中でだけ使える
+        let mut global_args = HashMap::new();
+        if stages.first().is_some_and(|stage| stage.base_image.is_none()) {
+            for instruction in stages.remove(0).instructions {
+                match instruction {
+                    Instruction::Arg { name, default_value } => {
+                        let value = options.build_args.get(&name).cloned().or(default_value).unwrap_or_default();
+                        global_args.insert(name, value);
+                    }
+                    other => {
+                        return Err(ImageError::Build(format!("{} must come after FROM", other.name())).into())
+                    }
+                }
+            }
+        }
+        if stages.is_empty() {
+            return Err(ImageError::Build("The Rockerfile has no FROM instruction".to_string()).into());
+        }
+        let last = match &options.target {
+            Some(target) => stages
+                .iter()
+                .position(|stage| stage.has_name(target))
+                .ok_or_else(|| ImageError::Build(format!("Target stage {} not found", target)))?,
+            None => stages.len() - 1,
+        };
+        stages.truncate(last + 1);
+
+        let mut cache = self.open_cache(options, progress).await;
+        let work_dir = self.build_dir.join(uuid::Uuid::new_v4().to_string());
+        let result = self
+            .build_stages(&stages, &global_args, &work_dir, context_dir, &mut cache, options, progress)
+            .await;
+        // エラーを持ったまま await しないよう同期的に消す
+        let _ = std::fs::remove_dir_all(&work_dir);
+        let mut state = result?;
+        if options.squash {
+            state.layers = self.squash_layers(&state.layers, progress).await?;
+            state.parent_id = None;
+        }
+        if let Some(spec) = &options.cache_to {
+            self.export_cache(&cache, spec, options, progress).await?;
+        }
+
+        // イメージ ID は設定とレイヤーの内容から決める
+        state.config.labels.extend(options.labels.clone());
+        let diff_ids: Vec<&str> = state
+            .layers
+            .iter()
+            .filter(|layer| !layer.empty_layer)
+            .map(|layer| layer.diff_id.as_str())
+            .collect();
+        let id = calculate_string_hash(&format!("{}\n{}", serde_json::to_string(&state.config)?, diff_ids.join("\n")));
+
+        let (repo, tag) = match &options.tag {
+            Some(tag) => {
+                let reference = RegistryReference::parse(tag).map_err(ImageError::Reference)?;
+                (Some(reference.familiar_name()), reference.tag)
+            }
+            None => (None, None),
+        };
+        let image = Image {
+            id,
+            repo,
+            tag,
+            created_at: Utc::now(),
+            size: state.layers.iter().map(|layer| layer.size).sum(),
+            layers: state.layers,
+            labels: state.config.labels.clone(),
+            config: state.config,
+            parent_id: state.parent_id,
+            scan: None,
+            repo_digests: Vec::new(),
+        };
+        let image = self.store(image).await?;
+
+        let metadata = BuildMetadata {
+            image_id: image.id.clone(),
+            tag: image.full_name(),
+            layers: image
+                .layers
+                .iter()
+                .filter(|layer| !layer.empty_layer)
+                .map(|layer| layer.diff_id.clone())
+                .collect(),
+            size: image.size,
+            steps: stages.iter().map(|stage| stage.instructions.len()).sum(),
+            cached_steps: cache.hits(),
+            cache_to: options.cache_to.as_ref().map(CacheSpec::to_string),
+            started_at,
+            finished_at: Utc::now(),
+        };
+        let _ = progress.send(
+            ProgressMessage::status(format!(
+                "Successfully built {}",
+                image.id.trim_start_matches("sha256:").chars().take(12).collect::<String>()
+            ))
+            .with_event(BuildEvent::Finished(metadata)),
+        );
+        if let Some(name) = image.full_name() {
+            let _ = progress.send(ProgressMessage::status(format!("Successfully tagged {}", name)));
+        }
+        Ok(image)
+    }
+
+    // ステージを順にビルドし、最後のステージの状態を返す
+    //
+    // 命令ごとに開始・キャッシュの使用・終了（失敗）の BuildEvent を送る。
+    #[allow(clippy::too_many_arguments)]
+    async fn build_stages(
+        &self,
+        stages: &[Stage],
+        global_args: &HashMap<String, String>,
+        work_dir: &Path,
+        context_dir: &Path,
+        cache: &mut BuildCache,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<StageState, RockerError> {
+        let total: usize = stages.iter().map(|stage| stage.instructions.len()).sum();
+        let mut step = 0;
+        let mut built: Vec<StageState> = Vec::new();
+
+        for (index, stage) in stages.iter().enumerate() {
+            let mut state: Option<StageState> = None;
+            for instruction in &stage.instructions {
+                step += 1;
+                let started = Instant::now();
+                let _ = progress.send(
+                    ProgressMessage::status(format!("Step {}/{} : {}", step, total, instruction.to_string())).with_event(
+                        BuildEvent::StepStarted {
+                            step,
+                            total,
+                            stage: index,
+                            stage_name: stage.name.clone(),
+                            instruction: instruction.to_string(),
+                        },
+                    ),
+                );
+
+                let Some(state) = state.as_mut() else {
+                    let Instruction::From { image, .. } = instruction else {
+                        let error = ImageError::Build(format!("{} must come after FROM", instruction.name()));
+                        return step_failed(progress, step, Err(error.into()));
+                    };
+                    let base = expand(image, global_args);
+                    let rootfs = work_dir.join(format!("stage-{}", index));
+                    let started_stage = self
+                        .start_stage(&base, stage.name.clone(), rootfs, &built, global_args, options, progress)
+                        .await;
+                    let mut started_stage = step_failed(progress, step, started_stage)?;
+                    let layers = started_stage.layers.len();
+                    let triggered = self
+                        .run_triggers(&mut started_stage, &built, work_dir, context_dir, cache, options, progress)
+                        .await;
+                    step_failed(progress, step, triggered)?;
+                    let layer = started_stage.layers[layers..].last().filter(|layer| !layer.empty_layer);
+                    step_finished(progress, step, false, layer, started);
+                    state = Some(started_stage);
+                    continue;
+                };
+
+                let (hits, layers) = (cache.hits(), state.layers.len());
+                let applied = self
+                    .apply_instruction(state, instruction, &built, work_dir, context_dir, cache, options, progress)
+                    .await;
+                step_failed(progress, step, applied)?;
+                let cached = cache.hits() > hits;
+                if cached {
+                    let _ = progress.send(ProgressMessage::event(BuildEvent::CacheHit {
+                        step,
+                        key: state.cache_key.clone(),
+                    }));
+                }
+                let layer = state.layers[layers..].last().filter(|layer| !layer.empty_layer);
+                step_finished(progress, step, cached, layer, started);
+            }
+
+            let mut state = state.ok_or_else(|| ImageError::Build("Empty build stage".to_string()))?;
+            // 最後のレイヤーより後の変更（WORKDIR のディレクトリなど）もレイヤーにする
+            self.commit_layer(&mut state, None).await?;
+            built.push(state);
+        }
+
+        Ok(built.pop().expect("at least one stage was built"))
+    }
+
+    // FROM のイメージ（scratch・前のステージ・イメージ）の rootfs を用意する
+    #[allow(clippy::too_many_arguments)]
+    async fn start_stage(
+        &self,
+        base: &str,
+        name: Option<String>,
+        rootfs: PathBuf,
+        built: &[StageState],
+        global_args: &HashMap<String, String>,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<StageState, RockerError> {
+        tokio::fs::create_dir_all(&rootfs).await?;
+
+        let (layers, config, parent_id, cache_key) = if base == "scratch" {
+            (Vec::new(), ImageConfig::default(), None, calculate_string_hash("FROM scratch"))
+        } else if let Some(previous) = find_stage(built, base) {
+            copy_tree(&previous.rootfs, &rootfs).await?;
+            (
+                previous.layers.clone(),
+                previous.config.clone(),
+                previous.parent_id.clone(),
+                previous.cache_key.clone(),
+            )
+        } else {
+            let image = self.base_image(base, options.pull, &options.registry_auths, progress).await?;
+            create_rootfs(&rootfs, &image.layers).await?;
+            (image.layers, image.config, Some(image.id.clone()), image.id)
+        };
+
+        // RUN で /proc と /dev をマウントする場所（スナップショットより前に作りレイヤーには含めない）
+        for dir in ["/proc", "/dev"] {
+            tokio::fs::create_dir_all(secure_join(&rootfs, Path::new(dir))?).await?;
+        }
+        let root = rootfs.clone();
+        let snapshot = tokio::task::spawn_blocking(move || Snapshot::take(&root)).await??;
+
+        Ok(StageState {
+            name,
+            rootfs,
+            snapshot,
+            layers,
+            config,
+            args: global_args.clone(),
+            shell: DEFAULT_SHELL.iter().map(|s| s.to_string()).collect(),
+            parent_id,
+            cache_key,
+        })
+    }
+
+    // ベースイメージの ONBUILD の命令を FROM の直後に順に実行する（ビルドしたイメージには引き継がない）
+    #[allow(clippy::too_many_arguments)]
+    async fn run_triggers(
+        &self,
+        state: &mut StageState,
+        built: &[StageState],
+        work_dir: &Path,
+        context_dir: &Path,
+        cache: &mut BuildCache,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<(), RockerError> {
+        for trigger in std::mem::take(&mut state.config.on_build) {
+            let instruction = RockerfileParser::new()
+                .parse_content(&trigger)
+                .map_err(|e| ImageError::Build(format!("Invalid ONBUILD trigger {}: {}", trigger, e)))?[0]
+                .instructions
+                .first()
+                .cloned()
+                .ok_or_else(|| ImageError::Build(format!("Invalid ONBUILD trigger: {}", trigger)))?;
+            let _ = progress.send(ProgressMessage::status(format!(" ---> Running ONBUILD trigger {}", trigger)));
+            self.apply_instruction(state, &instruction, built, work_dir, context_dir, cache, options, progress)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // ベースイメージを探し、無い場合（pull を指定した場合は常に）レジストリから取得する
+    async fn base_image(
+        &self,
+        name: &str,
+        pull: bool,
+        auths: &HashMap<String, RegistryAuth>,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Image, RockerError> {
+        if !pull {
+            if let Ok(image) = self.get(name) {
+                return Ok(image);
+            }
+        }
+        let auth = RegistryReference::parse(name)
+            .ok()
+            .and_then(|reference| auths.get(&reference.registry));
+        self.pull(name, auth, progress).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn apply_instruction(
+        &self,
+        state: &mut StageState,
+        instruction: &Instruction,
+        built: &[StageState],
+        work_dir: &Path,
+        context_dir: &Path,
+        cache: &mut BuildCache,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<(), RockerError> {
+        match instruction {
+            Instruction::From { .. } => {
+                return Err(ImageError::Build("FROM must start a new stage".to_string()).into());
+            }
+            Instruction::Run { command } => {
+                let argv = if command.trim_start().starts_with('[') {
+                    serde_json::from_str::<Vec<String>>(command)
+                        .map_err(|e| ImageError::Build(format!("Invalid RUN {}: {}", command, e)))?
+                } else {
+                    let mut argv = state.shell.clone();
+                    argv.push(command.clone());
+                    argv
+                };
+                let created_by = format!("RUN {}", command);
+                let key = state.step_key(&format!("RUN {}", serde_json::to_string(&argv)?));
+                if !self.use_cache(state, cache, &key, &created_by, progress).await? {
+                    self.run_command(state, &argv, options, progress).await?;
+                    self.commit_step(state, cache, &key, created_by).await?;
+                }
+                state.cache_key = key;
+            }
+            Instruction::Copy {
+                sources,
+                destination,
+                from,
+                chown,
+                chmod,
+            } => {
+                let from = from.as_deref().map(|from| state.expand(from));
+                let source_root = match &from {
+                    Some(from) => match find_stage(built, from) {
+                        Some(stage) => stage.rootfs.clone(),
+                        None => {
+                            // ステージでなければイメージとして扱う
+                            let image = self.base_image(from, false, &options.registry_auths, progress).await?;
+                            let rootfs = work_dir.join(format!("from-{}", uuid::Uuid::new_v4()));
+                            create_rootfs(&rootfs, &image.layers).await?;
+                            rootfs
+                        }
+                    },
+                    None => context_dir.to_path_buf(),
+                };
+                let created_by = instruction.to_string();
+                let key = self.source_key(state, &source_root, sources, &created_by).await?;
+                if !self.use_cache(state, cache, &key, &created_by, progress).await? {
+                    self.copy_files(state, &source_root, sources, destination, chown.as_deref(), chmod.as_deref(), false)
+                        .await?;
+                    self.commit_step(state, cache, &key, created_by).await?;
+                }
+                state.cache_key = key;
+            }
+            Instruction::Add {
+                sources,
+                destination,
+                chown,
+                chmod,
+            } => {
+                let created_by = instruction.to_string();
+                let key = self.source_key(state, context_dir, sources, &created_by).await?;
+                // URL の内容は取得するまで分からないため、キャッシュを使わずに取得し、作ったレイヤーを後の命令のキーに含める
+                if sources.iter().any(|source| is_url(&state.expand(source))) {
+                    self.copy_files(state, context_dir, sources, destination, chown.as_deref(), chmod.as_deref(), true)
+                        .await?;
+                    self.commit_layer(state, Some(created_by)).await?;
+                    let diff_id = state.layers.last().map(|layer| layer.diff_id.clone()).unwrap_or_default();
+                    state.cache_key = calculate_string_hash(&format!("{}\n{}", key, diff_id));
+                    return Ok(());
+                }
+                if !self.use_cache(state, cache, &key, &created_by, progress).await? {
+                    self.copy_files(state, context_dir, sources, destination, chown.as_deref(), chmod.as_deref(), true)
+                        .await?;
+                    self.commit_step(state, cache, &key, created_by).await?;
+                }
+                state.cache_key = key;
+            }
+            Instruction::Workdir { path } => {
+                let path = state.expand(path);
+                let base = state.config.working_dir.clone().unwrap_or_else(|| "/".to_string());
+                let path = normalize(&Path::new(&base).join(path));
+                tokio::fs::create_dir_all(secure_join(&state.rootfs, &path)?).await?;
+                state.config.working_dir = Some(path.to_string_lossy().to_string());
+                state.record(instruction.to_string());
+            }
+            Instruction::Env { variables } => {
+                let mut variables: Vec<(&String, &String)> = variables.iter().collect();
+                variables.sort();
+                for (key, value) in variables {
+                    let value = state.expand(value);
+                    let prefix = format!("{}=", key);
+                    state.config.env.retain(|env| !env.starts_with(&prefix));
+                    state.config.env.push(format!("{}={}", key, value));
+                }
+                state.record(instruction.to_string());
+            }
+            Instruction::Arg { name, default_value } => {
+                let value = options
+                    .build_args
+                    .get(name)
+                    .cloned()
+                    .or_else(|| default_value.as_deref().map(|value| state.expand(value)))
+                    .or_else(|| state.args.get(name).cloned())
+                    .unwrap_or_default();
+                state.args.insert(name.clone(), value);
+            }
+            Instruction::Expose { ports, protocol } => {
+                let protocol = protocol.as_deref().unwrap_or("tcp");
+                for port in ports {
+                    state.config.exposed_ports.insert(format!("{}/{}", port, protocol), HashMap::new());
+                }
+                state.record(instruction.to_string());
+            }
+            Instruction::Label { labels } => {
+                for (key, value) in labels {
+                    let value = state.expand(value);
+                    state.config.labels.insert(key.clone(), value);
+                }
+                state.record(instruction.to_string());
+            }
+            Instruction::User { user, group } => {
+                let user = state.expand(user);
+                state.config.user = Some(match group {
+                    Some(group) => format!("{}:{}", user, state.expand(group)),
+                    None => user,
+                });
+                state.record(instruction.to_string());
+            }
+            Instruction::Volume { paths } => {
+                for path in paths {
+                    state.config.volumes.insert(state.expand(path), HashMap::new());
+                }
+                state.record(instruction.to_string());
+            }
+            Instruction::Cmd { command } => {
+                state.config.cmd = Some(shell_form(&state.shell, command));
+                state.record(instruction.to_string());
+            }
+            Instruction::Entrypoint { command } => {
+                state.config.entrypoint = Some(shell_form(&state.shell, command));
+                // ENTRYPOINT を変えると継承した CMD は使われない
+                state.config.cmd = None;
+                state.record(instruction.to_string());
+            }
+            Instruction::Shell { shell } => {
+                if shell.is_empty() {
+                    return Err(ImageError::Build("SHELL requires at least one argument".to_string()).into());
+                }
+                state.shell = shell.clone();
+                state.record(instruction.to_string());
+            }
+            Instruction::StopSignal { signal } => {
+                state.config.stop_signal = Some(state.expand(signal));
+                state.record(instruction.to_string());
+            }
+            Instruction::Healthcheck {
+                command,
+                interval,
+                timeout,
+                retries,
+                start_period,
+            } => {
+                // HEALTHCHECK NONE は継承したヘルスチェックを無効にする
+                let test = if command.is_empty() {
+                    vec!["NONE".to_string()]
+                } else {
+                    let mut test = vec!["CMD".to_string()];
+                    test.extend(shell_form(&state.shell, command));
+                    test
+                };
+                let seconds = |value: &Option<String>| -> Result<Option<u64>, ImageError> {
+                    let Some(value) = value else {
+                        return Ok(None);
+                    };
+                    let duration = parse_duration(&state.expand(value))
+                        .map_err(|e| ImageError::Build(format!("Invalid HEALTHCHECK option: {}", e)))?;
+                    // 1 秒未満の時間は切り上げる
+                    Ok(Some(duration.as_millis().div_ceil(1_000) as u64))
+                };
+                let healthcheck = HealthConfig {
+                    test,
+                    interval: seconds(interval)?,
+                    timeout: seconds(timeout)?,
+                    retries: *retries,
+                    start_period: seconds(start_period)?,
+                };
+                state.config.healthcheck = Some(healthcheck);
+                state.record(instruction.to_string());
+            }
+            // このイメージを FROM で使うビルドが run_triggers で実行する
+            Instruction::OnBuild { instruction: trigger } => {
+                state.config.on_build.push(trigger.to_string());
+                state.record(instruction.to_string());
+            }
+        }
+        // 設定だけを変える命令はキャッシュのキーを進めるだけにする（RUN・COPY・ADD は上で進めた）
+        if !matches!(
+            instruction,
+            Instruction::Run { .. } | Instruction::Copy { .. } | Instruction::Add { .. }
+        ) {
+            state.cache_key = state.step_key(&instruction.to_string());
+        }
+        Ok(())
+    }
+
+    // rootfs に chroot してコマンドを実行し、出力を進捗として送る
+    async fn run_command(
+        &self,
+        state: &StageState,
+        argv: &[String],
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<(), RockerError> {
+        // --dry-run ではコマンドを実行せず、ファイルシステムを変えないまま成功したことにする
+        if self.dry_run {
+            let _ = progress.send(ProgressMessage::status(format!("Skipping {} (dry run)", argv.join(" "))));
+            return Ok(());
+        }
+
+        // プロキシのビルド引数は ARG で宣言しなくても渡す（イメージには残さない）
+        let mut env = self.proxy_env(options);
+        for (key, value) in &state.args {
+            env.retain(|(existing, _)| existing != key);
+            env.push((key.clone(), value.clone()));
+        }
+        let mut path_set = false;
+        for entry in &state.config.env {
+            if let Some((key, value)) = entry.split_once('=') {
+                path_set |= key == "PATH";
+                env.retain(|(existing, _)| existing != key);
+                env.push((key.to_string(), value.to_string()));
+            }
+        }
+        if !path_set {
+            let (key, value) = DEFAULT_PATH.split_once('=').expect("DEFAULT_PATH has a key");
+            env.push((key.to_string(), value.to_string()));
+        }
+
+        let (uid, gid) = match &state.config.user {
+            Some(user) => resolve_user(&state.rootfs, user)?,
+            None => (0, 0),
+        };
+        let working_dir = state.config.working_dir.clone().unwrap_or_else(|| "/".to_string());
+        let rootfs = state.rootfs.clone();
+        // ホストの resolv.conf をイメージのファイルに重ねて名前解決できるようにする
+        let resolv_conf = secure_join(&rootfs, Path::new("/etc/resolv.conf"))?;
+        let resolv_conf = (resolv_conf.is_file() && Path::new("/etc/resolv.conf").exists()).then_some(resolv_conf);
+        // マウント先はイメージの中のシンボリックリンクでホストを指せないよう先に解決しておく
+        let (dev, proc) = (secure_join(&rootfs, Path::new("/dev"))?, secure_join(&rootfs, Path::new("/proc"))?);
+
+        let mut command = Command::new(&argv[0]);
+        command
+            .args(&argv[1..])
+            .env_clear()
+            .envs(env)
+            .current_dir("/")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        chroot_command(&mut command, rootfs, (dev, proc), resolv_conf, working_dir, (uid, gid));
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| ImageError::Build(format!("Failed to run {}: {}", argv.join(" "), e)))?;
+        let mut forwarders = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            forwarders.push(tokio::spawn(forward_lines(stdout, progress.clone())));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            forwarders.push(tokio::spawn(forward_lines(stderr, progress.clone())));
+        }
+        let status = child.wait().await?;
+        for forwarder in forwarders {
+            let _ = forwarder.await;
+        }
+
+        if !status.success() {
+            return Err(ImageError::Build(format!(
+                "The command '{}' returned a non-zero code: {}",
+                argv.join(" "),
+                status.code().unwrap_or(-1)
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
+    // RUN に渡すプロキシ（ビルド引数で指定したもの、無ければデーモンの設定）
+    fn proxy_env(&self, options: &BuildOptions) -> Vec<(String, String)> {
+        let mut env: HashMap<String, String> = PROXY_BUILD_ARGS
+            .iter()
+            .filter_map(|name| options.build_args.get(*name).map(|value| (name.to_string(), value.clone())))
+            .collect();
+        proxy::add_env(&mut env, &self.proxy.env());
+        let mut env: Vec<(String, String)> = env.into_iter().collect();
+        env.sort();
+        env
+    }
+
+    // COPY・ADD のソースを rootfs にコピーする
+    //
+    // ADD の場合は URL からのダウンロードとローカルの tar の展開も行う。
+    #[allow(clippy::too_many_arguments)]
+    async fn copy_files(
+        &self,
+        state: &StageState,
+        source_root: &Path,
+        sources: &[String],
+        destination: &str,
+        chown: Option<&str>,
+        chmod: Option<&str>,
+        add: bool,
+    ) -> Result<(), RockerError> {
+        let destination = state.expand(destination);
+        let target = state.container_path(&destination)?;
+        let mut paths = Vec::new();
+        let mut urls = Vec::new();
+        for source in sources {
+            let source = state.expand(source);
+            if add && is_url(&source) {
+                urls.push(source);
+                continue;
+            }
+            let matched = glob(source_root, &source)?;
+            if matched.is_empty() {
+                return Err(ImageError::Build(format!("COPY failed: no source files were specified by {}", source)).into());
+            }
+            paths.extend(matched);
+        }
+
+        // ソースが複数か / で終わる場合はディレクトリにコピーする
+        let into_dir = destination.ends_with('/') || paths.len() + urls.len() > 1 || target.is_dir();
+        if into_dir {
+            tokio::fs::create_dir_all(&target).await?;
+        } else if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut copied = Vec::new();
+        // ディレクトリにコピーするファイルはまとめて cp に渡す（ファイルごとに cp を起動しない）
+        let mut pending = Vec::new();
+        for path in paths {
+            if into_dir && !path.is_dir() && !(add && is_tar(&path)) {
+                copied.push(target.join(path.file_name().unwrap_or_default()));
+                pending.push(path);
+                continue;
+            }
+            // 後のソースが同じパスに書く場合に備え、指定された順にコピーする
+            copy_into(&std::mem::take(&mut pending), &target).await?;
+            if add && is_tar(&path) {
+                let (archive, dir) = (path.clone(), target.clone());
+                tokio::task::spawn_blocking(move || extract_tar(&archive, &dir)).await??;
+                continue;
+            }
+            if path.is_dir() {
+                tokio::fs::create_dir_all(&target).await?;
+                let mut entries = tokio::fs::read_dir(&path).await?;
+                while let Some(entry) = entries.next_entry().await? {
+                    copied.push(target.join(entry.file_name()));
+                }
+                copy_tree(&path, &target).await?;
+            } else {
+                copy_path(&path, &target).await?;
+                copied.push(target.clone());
+            }
+        }
+        copy_into(&pending, &target).await?;
+        let download_failed = |e: reqwest::Error| ImageError::Build(format!("ADD failed: {}", e));
+        let http = self
+            .proxy
+            .apply(reqwest::Client::builder())
+            .and_then(|builder| builder.build())
+            .map_err(download_failed)?;
+        for url in urls {
+            let name = url.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("index.html");
+            let file_target = if into_dir {
+                state.container_path(&format!("{}/{}", destination.trim_end_matches('/'), name))?
+            } else {
+                target.clone()
+            };
+            let response = http
+                .get(&url)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(download_failed)?;
+            tokio::fs::write(&file_target, response.bytes().await.map_err(download_failed)?).await?;
+            copied.push(file_target);
+        }
+
+        if let Some(chown) = chown {
+            let (uid, gid) = resolve_user(&state.rootfs, &state.expand(chown))?;
+            // -h でコピーしたシンボリックリンクの先（ホストのファイルになりうる）ではなくリンク自体を変える
+            run_tool("chown", &["-h".to_string(), format!("{}:{}", uid, gid)], &copied).await?;
+        }
+        if let Some(chmod) = chmod {
+            if u32::from_str_radix(chmod, 8).is_err() {
+                return Err(ImageError::Build(format!("Invalid --chmod: {}", chmod)).into());
+            }
+            // chmod は引数のシンボリックリンクを辿るため、リンクは除く（リンク自体のパーミッションは使われない）
+            copied.retain(|path| !path.is_symlink());
+            run_tool("chmod", &[chmod.to_string()], &copied).await?;
+        }
+        Ok(())
+    }
+
+    // 前回のスナップショットからの変更をレイヤーとして保存する
+    //
+    // created_by が None の場合は変更が無ければ何も記録しない。
+    async fn commit_layer(&self, state: &mut StageState, created_by: Option<String>) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.layers_dir).await?;
+        let staging_dir = self.layers_dir.join(format!("tmp-{}", uuid::Uuid::new_v4()));
+        let previous = std::mem::replace(&mut state.snapshot, Snapshot::empty());
+        let (root, dir) = (state.rootfs.clone(), staging_dir.clone());
+        let written = tokio::task::spawn_blocking(move || {
+            let (snapshot, count) = write_diff(&root, &previous, &dir)?;
+            let packed = if count > 0 { Some(calculate_dir_hash(&dir)?) } else { None };
+            Ok::<_, std::io::Error>((snapshot, packed))
+        })
+        .await?;
+        let (snapshot, packed) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+                return Err(ImageError::Build(format!("Failed to create layer: {}", e)).into());
+            }
+        };
+        state.snapshot = snapshot;
+
+        let Some((diff_id, size)) = packed else {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+            if let Some(created_by) = created_by {
+                state.record(created_by);
+            }
+            return Ok(());
+        };
+        let layer_dir = self.layers_dir.join(diff_id.trim_start_matches("sha256:"));
+        if layer_dir.exists() {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+        } else {
+            tokio::fs::rename(&staging_dir, &layer_dir).await?;
+        }
+        state.layers.push(ImageLayer {
+            id: diff_id.clone(),
+            diff_id,
+            size,
+            path: layer_dir,
+            created_at: Utc::now(),
+            created_by,
+            empty_layer: false,
+        });
+        Ok(())
+    }
+
+    // COPY・ADD のキャッシュのキー（ソースのファイルの内容も含める）
+    async fn source_key(
+        &self,
+        state: &StageState,
+        source_root: &Path,
+        sources: &[String],
+        created_by: &str,
+    ) -> Result<String, RockerError> {
+        let mut paths = Vec::new();
+        for source in sources {
+            let source = state.expand(source);
+            // 見つからないソースは copy_files がエラーにする
+            if !is_url(&source) {
+                paths.extend(glob(source_root, &source)?);
+            }
+        }
+        let root = source_root.to_path_buf();
+        let hash = tokio::task::spawn_blocking(move || content_hash(&root, &paths)).await??;
+        Ok(state.step_key(&format!("{}\n{}", created_by, hash)))
+    }
+
+    // キャッシュにある命令の結果を rootfs に重ねる（無ければ false を返し、呼び出し側が命令を実行する）
+    async fn use_cache(
+        &self,
+        state: &mut StageState,
+        cache: &mut BuildCache,
+        key: &str,
+        created_by: &str,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<bool, RockerError> {
+        let Some(record) = self.cached(cache, key, progress).await? else {
+            return Ok(false);
+        };
+        match record.diff_id {
+            None => state.record(created_by.to_string()),
+            Some(diff_id) => {
+                let layer = ImageLayer {
+                    id: diff_id.clone(),
+                    path: self.layers_dir.join(diff_id.trim_start_matches("sha256:")),
+                    diff_id,
+                    size: record.size,
+                    created_at: Utc::now(),
+                    created_by: Some(created_by.to_string()),
+                    empty_layer: false,
+                };
+                create_rootfs(&state.rootfs, std::slice::from_ref(&layer)).await?;
+                let root = state.rootfs.clone();
+                state.snapshot = tokio::task::spawn_blocking(move || Snapshot::take(&root)).await??;
+                state.layers.push(layer);
+            }
+        }
+        let _ = progress.send(ProgressMessage::status(" ---> Using cache"));
+        Ok(true)
+    }
+
+    // 命令の結果をレイヤーにし、キャッシュに記録する
+    async fn commit_step(
+        &self,
+        state: &mut StageState,
+        cache: &mut BuildCache,
+        key: &str,
+        created_by: String,
+    ) -> Result<(), RockerError> {
+        self.commit_layer(state, Some(created_by.clone())).await?;
+        let layer = state.layers.last().expect("commit_layer records the instruction");
+        let diff_id = (!layer.empty_layer).then(|| layer.diff_id.clone());
+        let record = CacheRecord::new(key, diff_id, layer.size, created_by);
+        self.record_cache(cache, record).await
+    }
+}
+
+// 命令が失敗した場合は StepFailed を送ってからエラーを返す
+fn step_failed<T>(
+    progress: &mpsc::UnboundedSender<ProgressMessage>,
+    step: usize,
+    result: Result<T, RockerError>,
+) -> Result<T, RockerError> {
+    if let Err(e) = &result {
+        let _ = progress.send(ProgressMessage::event(BuildEvent::StepFailed {
+            step,
+            error: e.to_string(),
+        }));
+    }
+    result
+}
+
+// layer は命令が追加したレイヤー（ファイルシステムを変えなかった場合は無し）
+fn step_finished(
+    progress: &mpsc::UnboundedSender<ProgressMessage>,
+    step: usize,
+    cached: bool,
+    layer: Option<&ImageLayer>,
+    started: Instant,
+) {
+    let _ = progress.send(ProgressMessage::event(BuildEvent::StepFinished {
+        step,
+        cached,
+        diff_id: layer.map(|layer| layer.diff_id.clone()),
+        size: layer.map_or(0, |layer| layer.size),
+        duration_ms: started.elapsed().as_millis() as u64,
+    }));
+}
+
+// 名前か番号でビルド済みのステージを探す
+fn find_stage<'a>(built: &'a [StageState], name: &str) -> Option<&'a StageState> {
+    match name.parse::<usize>() {
+        Ok(index) => built.get(index),
+        Err(_) => built.iter().find(|stage| stage.name.as_deref() == Some(name)),
+    }
+}
+
+// CMD・ENTRYPOINT のシェル形式をシェルの引数にする
+fn shell_form(shell: &[String], command: &[String]) -> Vec<String> {
+    match command {
+        [command] if command.contains(char::is_whitespace) => {
+            let mut argv = shell.to_vec();
+            argv.push(command.clone());
+            argv
+        }
+        _ => command.to_vec(),
+    }
+}
+
+// $VAR・${VAR}・${VAR:-default}・${VAR:+value} を展開する（\$ はそのまま残す）
+fn expand(value: &str, variables: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'$') => {
+                result.push('$');
+                chars.next();
+            }
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut expression = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    expression.push(c);
+                }
+                let value = if let Some((name, default)) = expression.split_once(":-") {
+                    variables.get(name).filter(|value| !value.is_empty()).cloned().unwrap_or_else(|| default.to_string())
+                } else if let Some((name, alternative)) = expression.split_once(":+") {
+                    match variables.get(name) {
+                        Some(value) if !value.is_empty() => alternative.to_string(),
+                        _ => String::new(),
+                    }
+                } else {
+                    variables.get(&expression).cloned().unwrap_or_default()
+                };
+                result.push_str(&value);
+            }
+            '$' if chars.peek().is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') => {
+                let mut name = String::new();
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_') {
+                    name.push(*c);
+                    chars.next();
+                }
+                result.push_str(variables.get(&name).map(String::as_str).unwrap_or_default());
+            }
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+// .. を解決した絶対パス
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+    normalized
+}
+
+// 最後の要素の * と ? を展開したソースのパス
+//
+// パスは root（ビルドコンテキストか前のステージの rootfs）の中で解決するため、.. やシンボリックリンクで外は指せない。
+fn glob(root: &Path, source: &str) -> std::io::Result<Vec<PathBuf>> {
+    let path = secure_join(root, Path::new(source))?;
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return Ok(if path.exists() || path.is_symlink() { vec![path] } else { Vec::new() });
+    }
+
+    let dir = path.parent().unwrap_or(root);
+    let mut matched: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| wildcard_match(&name, &entry.file_name().to_string_lossy()))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    matched.sort();
+    Ok(matched)
+}
+
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+fn is_tar(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    path.is_file() && [".tar", ".tar.gz", ".tgz"].iter().any(|suffix| name.ends_with(suffix))
+}
+
+fn extract_tar(archive: &Path, dir: &Path) -> std::io::Result<()> {
+    let file = std::fs::File::open(archive)?;
+    let reader: Box<dyn std::io::Read> = if archive.to_string_lossy().ends_with(".tar") {
+        Box::new(file)
+    } else {
+        Box::new(flate2::read::GzDecoder::new(file))
+    };
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.unpack(dir)
+}
+
+// ディレクトリの中身を属性ごと target にコピーする
+async fn copy_tree(source: &Path, target: &Path) -> Result<(), RockerError> {
+    tokio::fs::create_dir_all(target).await?;
+    copy_path(&source.join("."), target).await
+}
+
+async fn copy_path(source: &Path, target: &Path) -> Result<(), RockerError> {
+    run_cp(std::slice::from_ref(&source.to_path_buf()), target).await
+}
+
+// ファイルを属性ごとディレクトリ target の中にコピーする
+async fn copy_into(sources: &[PathBuf], target: &Path) -> Result<(), RockerError> {
+    for chunk in sources.chunks(COPY_CHUNK) {
+        run_cp(chunk, target).await?;
+    }
+    Ok(())
+}
+
+// cp は reflink を、できなければ copy_file_range を使うため、CoW なファイルシステムでは大きなコンテキストもすぐにコピーできる
+async fn run_cp(sources: &[PathBuf], target: &Path) -> Result<(), RockerError> {
+    if sources.is_empty() {
+        return Ok(());
+    }
+    // コピー先に既にあるシンボリックリンクは辿らずに置き換える（リンクの先はホストのパスとして解釈されてしまう）
+    let output = Command::new("cp")
+        .arg("-a")
+        .arg("--remove-destination")
+        .args(reflink::cp_arg())
+        .arg("--")
+        .args(sources)
+        .arg(target)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() {
+        let source = match sources {
+            [source] => source.display().to_string(),
+            _ => format!("{} files", sources.len()),
+        };
+        return Err(ImageError::Build(format!(
+            "Failed to copy {}: {}",
+            source,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+// chown・chmod を再帰的に適用する
+async fn run_tool(tool: &str, args: &[String], paths: &[PathBuf]) -> Result<(), RockerError> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let output = Command::new(tool)
+        .arg("-R")
+        .args(args)
+        .arg("--")
+        .args(paths)
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(ImageError::Build(format!("{} failed: {}", tool, String::from_utf8_lossy(&output.stderr).trim())).into());
+    }
+    Ok(())
+}
+
+async fn forward_lines<R>(reader: R, progress: mpsc::UnboundedSender<ProgressMessage>)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let _ = progress.send(ProgressMessage::status(line));
+    }
+}
+
+// RUN のコマンドを rootfs に chroot し、uid・gid で動かすよう設定する
+//
+// /dev・/proc と resolv.conf のマウントはコマンドの終了と同時に消えるよう、新しいマウント名前空間の中で行う。
+#[cfg(target_os = "linux")]
+fn chroot_command(
+    command: &mut Command,
+    rootfs: PathBuf,
+    (dev, proc): (PathBuf, PathBuf),
+    resolv_conf: Option<PathBuf>,
+    working_dir: String,
+    (uid, gid): (u32, u32),
+) {
+    unsafe {
+        command.pre_exec(move || {
+            unshare(CloneFlags::CLONE_NEWNS)?;
+            mount(None::<&str>, "/", None::<&str>, MsFlags::MS_REC | MsFlags::MS_PRIVATE, None::<&str>)?;
+            mount(Some("/dev"), &dev, None::<&str>, MsFlags::MS_BIND | MsFlags::MS_REC, None::<&str>)?;
+            mount(Some("proc"), &proc, Some("proc"), MsFlags::empty(), None::<&str>)?;
+            if let Some(resolv_conf) = &resolv_conf {
+                mount(Some("/etc/resolv.conf"), resolv_conf, None::<&str>, MsFlags::MS_BIND, None::<&str>)?;
+            }
+            chroot(&rootfs)?;
+            nix::unistd::chdir(working_dir.as_str())?;
+            setgroups(&[Gid::from_raw(gid)])?;
+            setgid(Gid::from_raw(gid))?;
+            setuid(Uid::from_raw(uid))?;
+            Ok(())
+        });
+    }
+}
+
+// Linux 以外にはマウント名前空間が無いため RUN を実行できない（--dry-run では実行しない）
+#[cfg(not(target_os = "linux"))]
+fn chroot_command(
+    command: &mut Command,
+    _rootfs: PathBuf,
+    _mounts: (PathBuf, PathBuf),
+    _resolv_conf: Option<PathBuf>,
+    _working_dir: String,
+    _user: (u32, u32),
+) {
+    unsafe {
+        command.pre_exec(|| {
+            Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "RUN is only available on Linux"))
+        });
+    }
+}
@@ -0,0 +1,751 @@
+use chrono::{DateTime, Utc};
+use rocker_core::{
+    BuildCacheEntry, BuildCachePruneReport, BuildEvent, ImageError, ProgressMessage, RegistryReference, RockerError,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::build::BuildOptions;
+use super::import::unpack_layer;
+use super::layer::compress_layer;
+use super::registry::{Descriptor, Manifest, RegistryClient, OCI_LAYER, OCI_MANIFEST};
+use super::Manager;
+
+// レジストリに置くキャッシュのマニフェストの config の種類（中身はキャッシュの索引）
+const CACHE_INDEX: &str = "application/vnd.rocker.buildcache.index.v1+json";
+// ディレクトリに書き出したキャッシュの索引のファイル名（レイヤーは blobs/sha256/ に置く）
+const INDEX_FILE: &str = "index.json";
+
+// --cache-from・--cache-to に指定するキャッシュの置き場所
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheSpec {
+    // デーモンのホストのディレクトリ（type=local,src=<dir> / type=local,dest=<dir>）
+    Local(PathBuf),
+    // レジストリのイメージ（type=registry,ref=<repo:tag>、または repo:tag だけ）
+    Registry(String),
+}
+
+impl CacheSpec {
+    // path_key はディレクトリを指定するキー（--cache-from は src、--cache-to は dest）
+    pub fn parse(value: &str, path_key: &str) -> Result<Self, String> {
+        if !value.contains('=') {
+            return CacheSpec::registry(value);
+        }
+        let (mut kind, mut path, mut reference) = (None, None, None);
+        for option in value.split(',') {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid cache option (expected key=value): {}", option))?;
+            match key.trim() {
+                "type" => kind = Some(value),
+                "ref" => reference = Some(value),
+                key if key == path_key => path = Some(value),
+                // mode・compression などの BuildKit のオプションは使わない
+                _ => {}
+            }
+        }
+        match kind {
+            Some("local") => {
+                let path = PathBuf::from(path.ok_or_else(|| format!("type=local needs {}=<directory>", path_key))?);
+                if !path.is_absolute() {
+                    return Err(format!(
+                        "The cache directory must be an absolute path on the daemon's host: {}",
+                        path.display()
+                    ));
+                }
+                Ok(CacheSpec::Local(path))
+            }
+            Some("registry") => CacheSpec::registry(reference.ok_or("type=registry needs ref=<repository:tag>")?),
+            Some(kind) => Err(format!("Unsupported cache type {} (expected local or registry)", kind)),
+            None => Err(format!("Missing type in cache option: {}", value)),
+        }
+    }
+
+    fn registry(reference: &str) -> Result<Self, String> {
+        let parsed = RegistryReference::parse(reference).map_err(|e| format!("Invalid cache reference {}: {}", reference, e))?;
+        if parsed.digest.is_some() {
+            return Err(format!("Cache references need a tag, not a digest: {}", reference));
+        }
+        Ok(CacheSpec::Registry(reference.to_string()))
+    }
+}
+
+impl std::fmt::Display for CacheSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheSpec::Local(dir) => write!(f, "{}", dir.display()),
+            CacheSpec::Registry(reference) => write!(f, "{}", reference),
+        }
+    }
+}
+
+// 1 つの命令の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct CacheRecord {
+    pub key: String,
+    // 命令が作ったレイヤーの diff ID（ファイルシステムを変えなかった場合は無し）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_id: Option<String>,
+    #[serde(default)]
+    pub size: u64,
+    // 書き出したレイヤーの tar.gz のダイジェストとサイズ（エクスポートしたキャッシュの索引だけが持つ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub blob_size: u64,
+    // 命令（イメージの履歴と同じ形）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    // 記録した時刻・最後にビルドで使われた時刻と使われた回数（このホストの記録だけが持つ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub usage_count: u64,
+}
+
+impl CacheRecord {
+    // 命令を実行した結果の記録
+    pub fn new(key: &str, diff_id: Option<String>, size: u64, created_by: String) -> Self {
+        CacheRecord {
+            key: key.to_string(),
+            diff_id,
+            size,
+            blob: None,
+            blob_size: 0,
+            created_by: Some(created_by),
+            created_at: Some(Utc::now()),
+            last_used_at: None,
+            usage_count: 0,
+        }
+    }
+
+    // 最後に使われた時刻（使われていなければ記録した時刻）
+    fn last_activity(&self) -> Option<DateTime<Utc>> {
+        self.last_used_at.or(self.created_at)
+    }
+
+    // 使われた記録（インポートした記録は初めて使われたものとして記録する）
+    fn used(self) -> Self {
+        CacheRecord {
+            created_at: self.created_at.or_else(|| Some(Utc::now())),
+            last_used_at: Some(Utc::now()),
+            usage_count: self.usage_count + 1,
+            ..self
+        }
+    }
+
+    // 書き出すキャッシュの索引にはこのホストでの使われ方を含めない
+    fn exported(&self) -> Self {
+        CacheRecord {
+            created_at: None,
+            last_used_at: None,
+            usage_count: 0,
+            ..self.clone()
+        }
+    }
+}
+
+// POST /build/prune で消すキャッシュの記録
+//
+// all でなければイメージが使っていないレイヤーの記録だけを消す。until はそれより前に最後に使われた記録に絞り、
+// keep_storage は残す記録のレイヤーの合計がそのバイト数以下になるまで、古いものから消す。
+#[derive(Debug, Clone, Default)]
+pub struct CachePruneOptions {
+    pub all: bool,
+    pub until: Option<DateTime<Utc>>,
+    pub keep_storage: Option<u64>,
+}
+
+// 実行中のビルドの数を数える（drop で減らす）
+pub(super) struct BuildGuard(Arc<AtomicUsize>);
+
+impl Drop for BuildGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn is_zero(size: &u64) -> bool {
+    *size == 0
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheIndex {
+    records: Vec<CacheRecord>,
+}
+
+// インポートしたキャッシュのレイヤーの取得元
+enum CacheSource {
+    Local(PathBuf),
+    Registry(RegistryClient),
+}
+
+// 1 回のビルドで使うキャッシュ
+//
+// 命令の結果は <data_root>/build-cache/<キー>.json に記録し、レイヤーはイメージと同じ layers_dir に置く。
+// --cache-from の索引は最初に読み込み、レイヤーはキャッシュが当たった時にだけ取得する。
+pub(super) struct BuildCache {
+    // --no-cache では結果を記録するだけで使わない
+    disabled: bool,
+    // キーごとのインポートした結果と、取得元（sources の位置）
+    imported: HashMap<String, (CacheRecord, usize)>,
+    sources: Vec<CacheSource>,
+    // このビルドの命令の結果（--cache-to に書き出す）
+    used: Vec<CacheRecord>,
+    // キャッシュが当たった命令の数
+    hits: usize,
+}
+
+impl BuildCache {
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
+
+impl Manager {
+    // --cache-from の索引を読み込む（読み込めないものは警告して使わない）
+    pub(super) async fn open_cache(
+        &self,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> BuildCache {
+        let mut cache = BuildCache {
+            disabled: options.no_cache,
+            imported: HashMap::new(),
+            sources: Vec::new(),
+            used: Vec::new(),
+            hits: 0,
+        };
+        if options.no_cache {
+            return cache;
+        }
+        for spec in &options.cache_from {
+            match self.import_index(spec, options).await {
+                Ok((source, index)) => {
+                    let _ = progress.send(ProgressMessage::status(format!(
+                        "Importing cache from {} ({} steps)",
+                        spec,
+                        index.records.len()
+                    )));
+                    // 先に指定したインポート元を優先する
+                    let position = cache.sources.len();
+                    for record in index.records {
+                        cache.imported.entry(record.key.clone()).or_insert((record, position));
+                    }
+                    cache.sources.push(source);
+                }
+                Err(e) => {
+                    let _ = progress.send(ProgressMessage::status(format!(
+                        " ---> Ignoring cache from {}: {}",
+                        spec, e
+                    )));
+                }
+            }
+        }
+        cache
+    }
+
+    async fn import_index(
+        &self,
+        spec: &CacheSpec,
+        options: &BuildOptions,
+    ) -> Result<(CacheSource, CacheIndex), RockerError> {
+        match spec {
+            CacheSpec::Local(dir) => {
+                let content = tokio::fs::read(dir.join(INDEX_FILE)).await?;
+                Ok((CacheSource::Local(dir.clone()), serde_json::from_slice(&content)?))
+            }
+            CacheSpec::Registry(name) => {
+                let mut client = self.cache_client(name, false, options)?;
+                let (_, manifest) = client.manifest().await?;
+                if manifest.config.media_type != CACHE_INDEX {
+                    return Err(ImageError::Pull(format!("{} is not a build cache", name)).into());
+                }
+                let index = client.blob(&manifest.config.digest).await?;
+                Ok((CacheSource::Registry(client), serde_json::from_slice(&index)?))
+            }
+        }
+    }
+
+    fn cache_client(&self, name: &str, push: bool, options: &BuildOptions) -> Result<RegistryClient, ImageError> {
+        let reference = RegistryReference::parse(name).map_err(ImageError::Reference)?;
+        let auth = options.registry_auths.get(&reference.registry).cloned();
+        RegistryClient::new(reference, push, auth, &self.proxy)
+    }
+
+    // key の命令の結果がキャッシュにあれば返す（レイヤーがこのホストに無ければインポート元から取得する）
+    pub(super) async fn cached(
+        &self,
+        cache: &mut BuildCache,
+        key: &str,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Option<CacheRecord>, RockerError> {
+        if cache.disabled {
+            return Ok(None);
+        }
+        if let Some(record) = self.local_record(key).await {
+            if record.diff_id.as_deref().is_none_or(|diff_id| self.layer_path(diff_id).exists()) {
+                let record = record.used();
+                self.save_record(&record).await?;
+                cache.used.push(record.clone());
+                cache.hits += 1;
+                return Ok(Some(record));
+            }
+        }
+
+        let Some((record, position)) = cache.imported.get(key).cloned() else {
+            return Ok(None);
+        };
+        if let Some(diff_id) = &record.diff_id {
+            if !self.layer_path(diff_id).exists() {
+                let fetched = self.import_layer(&cache.sources[position], &record, diff_id, progress).await;
+                if let Err(e) = fetched {
+                    // 取得できないレイヤーはキャッシュに無いものとして命令を実行する
+                    let _ = progress.send(ProgressMessage::status(format!(" ---> Ignoring cached layer: {}", e)));
+                    return Ok(None);
+                }
+            }
+        }
+        let record = record.used();
+        self.save_record(&record).await?;
+        cache.used.push(record.clone());
+        cache.hits += 1;
+        Ok(Some(record))
+    }
+
+    async fn import_layer(
+        &self,
+        source: &CacheSource,
+        record: &CacheRecord,
+        diff_id: &str,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<(), ImageError> {
+        let blob = record
+            .blob
+            .as_deref()
+            .ok_or_else(|| ImageError::Pull(format!("The cache has no layer {}", diff_id)))?;
+        let layer_dir = self.layer_path(diff_id);
+        match source {
+            CacheSource::Registry(client) => {
+                let descriptor = Descriptor {
+                    media_type: OCI_LAYER.to_string(),
+                    digest: blob.to_string(),
+                    size: record.blob_size,
+                    platform: None,
+                };
+                self.pull_layer(client.clone(), &descriptor, diff_id, &layer_dir, progress)
+                    .await
+                    .map(|_| ())
+            }
+            CacheSource::Local(dir) => {
+                let path = dir.join("blobs/sha256").join(blob.trim_start_matches("sha256:"));
+                let staging_dir = self.layers_dir.join(format!("tmp-{}", uuid::Uuid::new_v4()));
+                let unpack_dir = staging_dir.clone();
+                let unpacked = tokio::task::spawn_blocking(move || {
+                    let file = std::fs::File::open(&path)?;
+                    unpack_layer(Box::new(file), &unpack_dir)
+                })
+                .await
+                .map_err(|e| ImageError::Pull(e.to_string()))?;
+                let result = match unpacked {
+                    Ok((actual, _)) if actual == diff_id => {
+                        if tokio::fs::rename(&staging_dir, &layer_dir).await.is_err() && !layer_dir.exists() {
+                            Err(ImageError::Pull(format!("Failed to store layer {}", diff_id)))
+                        } else {
+                            Ok(())
+                        }
+                    }
+                    Ok(_) => Err(ImageError::Pull(format!("Layer {} does not match its diff ID {}", blob, diff_id))),
+                    Err(e) => Err(ImageError::Pull(format!("Failed to extract layer {}: {}", blob, e))),
+                };
+                if staging_dir.exists() {
+                    let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+                }
+                result
+            }
+        }
+    }
+
+    // 実行した命令の結果を記録する
+    pub(super) async fn record_cache(&self, cache: &mut BuildCache, record: CacheRecord) -> Result<(), RockerError> {
+        self.save_record(&record).await?;
+        cache.used.push(record);
+        Ok(())
+    }
+
+    async fn local_record(&self, key: &str) -> Option<CacheRecord> {
+        let content = tokio::fs::read(self.record_path(key)).await.ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+
+    async fn save_record(&self, record: &CacheRecord) -> Result<(), RockerError> {
+        let record = CacheRecord {
+            blob: None,
+            blob_size: 0,
+            ..record.clone()
+        };
+        tokio::fs::create_dir_all(&self.cache_dir).await?;
+        tokio::fs::write(self.record_path(&record.key), serde_json::to_vec_pretty(&record)?).await?;
+        Ok(())
+    }
+
+    fn record_path(&self, key: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", key.trim_start_matches("sha256:")))
+    }
+
+    fn layer_path(&self, diff_id: &str) -> PathBuf {
+        self.layers_dir.join(diff_id.trim_start_matches("sha256:"))
+    }
+
+    // ビルドの間持っておく（持っている間は prune_build_cache がエラーになる）
+    pub(super) fn track_build(&self) -> BuildGuard {
+        self.builds.fetch_add(1, Ordering::SeqCst);
+        BuildGuard(Arc::clone(&self.builds))
+    }
+
+    // このホストのビルドキャッシュの記録（最後に使われたものから順に）
+    //
+    // レイヤーが消えた記録（イメージと一緒に削除されたもの）はもう使われないため含めない。
+    pub async fn build_cache(&self) -> Result<Vec<BuildCacheEntry>, RockerError> {
+        let in_use = self.image_layers();
+        let (records, _) = self.local_records().await?;
+        let mut entries: Vec<BuildCacheEntry> = records
+            .into_iter()
+            .map(|record| BuildCacheEntry {
+                in_use: record.diff_id.as_ref().is_some_and(|diff_id| in_use.contains(diff_id)),
+                id: record.key,
+                created_by: record.created_by,
+                diff_id: record.diff_id,
+                size: record.size,
+                created_at: record.created_at,
+                last_used_at: record.last_used_at,
+                usage_count: record.usage_count,
+            })
+            .collect();
+        entries.sort_by_key(|entry| Reverse(entry.last_activity()));
+        Ok(entries)
+    }
+
+    // ビルドキャッシュの記録を消し、イメージと残りの記録が使っていないレイヤーを削除する
+    //
+    // レイヤーが消えた記録は options によらず消す。
+    pub async fn prune_build_cache(&self, options: &CachePruneOptions) -> Result<BuildCachePruneReport, RockerError> {
+        if self.builds.load(Ordering::SeqCst) > 0 {
+            return Err(ImageError::CacheBusy("builds are running".to_string()).into());
+        }
+        let in_use = self.image_layers();
+        let (mut records, mut removed) = self.local_records().await?;
+        records.sort_by_key(CacheRecord::last_activity);
+
+        let mut kept_size: u64 = records.iter().map(|record| record.size).sum();
+        let mut kept = Vec::new();
+        for record in records {
+            let unused = options.all || !record.diff_id.as_ref().is_some_and(|diff_id| in_use.contains(diff_id));
+            // 使われた時刻の無い古いデーモンの記録は until より前とみなす
+            let old = options
+                .until
+                .is_none_or(|until| record.last_activity().is_none_or(|time| time < until));
+            let over = options.keep_storage.is_none_or(|keep| kept_size > keep);
+            if unused && old && over {
+                kept_size -= record.size;
+                removed.push(record);
+            } else {
+                kept.push(record);
+            }
+        }
+
+        let mut report = BuildCachePruneReport::default();
+        let mut freed = HashSet::new();
+        for record in removed {
+            if let Err(e) = tokio::fs::remove_file(self.record_path(&record.key)).await {
+                warn!("Failed to remove build cache record {}: {}", record.key, e);
+                continue;
+            }
+            report.caches_deleted.push(record.key);
+            let Some(diff_id) = record.diff_id else {
+                continue;
+            };
+            let layer_dir = self.layer_path(&diff_id);
+            let shared = in_use.contains(&diff_id) || kept.iter().any(|other| other.diff_id.as_ref() == Some(&diff_id));
+            if shared || !layer_dir.exists() || !freed.insert(diff_id.clone()) {
+                continue;
+            }
+            match tokio::fs::remove_dir_all(&layer_dir).await {
+                Ok(()) => report.space_reclaimed += record.size,
+                Err(e) => warn!("Failed to remove layer {}: {}", layer_dir.display(), e),
+            }
+        }
+        info!(
+            "Pruned {} build cache records ({} bytes)",
+            report.caches_deleted.len(),
+            report.space_reclaimed
+        );
+        Ok(report)
+    }
+
+    // イメージが使っているレイヤーの diff ID
+    fn image_layers(&self) -> HashSet<String> {
+        let images = self.images.lock().unwrap();
+        images
+            .values()
+            .flat_map(|image| image.layers.iter().map(|layer| layer.diff_id.clone()))
+            .collect()
+    }
+
+    // <data_root>/build-cache の記録を、レイヤーがあるものと消えたものに分けて返す（読めない記録は飛ばす）
+    async fn local_records(&self) -> Result<(Vec<CacheRecord>, Vec<CacheRecord>), RockerError> {
+        let (mut records, mut stale) = (Vec::new(), Vec::new());
+        let mut entries = match tokio::fs::read_dir(&self.cache_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((records, stale)),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.path().extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let Ok(content) = tokio::fs::read(entry.path()).await else {
+                continue;
+            };
+            let Ok(record) = serde_json::from_slice::<CacheRecord>(&content) else {
+                continue;
+            };
+            if record.diff_id.as_deref().is_some_and(|diff_id| !self.layer_path(diff_id).exists()) {
+                stale.push(record);
+            } else {
+                records.push(record);
+            }
+        }
+        Ok((records, stale))
+    }
+
+    // このビルドの命令の結果とレイヤーを --cache-to に書き出す
+    pub(super) async fn export_cache(
+        &self,
+        cache: &BuildCache,
+        spec: &CacheSpec,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<(), RockerError> {
+        let _ = progress.send(ProgressMessage::status(format!("Exporting cache to {}", spec)));
+        let mut keys = HashSet::new();
+        let records: Vec<CacheRecord> = cache
+            .used
+            .iter()
+            .filter(|record| keys.insert(record.key.clone()))
+            .map(CacheRecord::exported)
+            .collect();
+        // 同じ場所からインポートしたレイヤーは圧縮し直さない
+        let known: HashMap<String, (String, u64)> = cache
+            .imported
+            .values()
+            .filter_map(|(record, _)| Some((record.diff_id.clone()?, (record.blob.clone()?, record.blob_size))))
+            .collect();
+        let records = match spec {
+            CacheSpec::Local(dir) => self.export_local(records, dir).await?,
+            CacheSpec::Registry(name) => self.export_registry(records, known, name, options, progress).await?,
+        };
+
+        let mut exported = HashSet::new();
+        for record in records {
+            if let (Some(diff_id), Some(digest)) = (record.diff_id, record.blob) {
+                if exported.insert(diff_id.clone()) {
+                    let _ = progress.send(ProgressMessage::event(BuildEvent::LayerExported {
+                        diff_id,
+                        digest,
+                        size: record.blob_size,
+                        destination: spec.to_string(),
+                    }));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn export_local(&self, mut records: Vec<CacheRecord>, dir: &Path) -> Result<Vec<CacheRecord>, RockerError> {
+        let blobs_dir = dir.join("blobs/sha256");
+        tokio::fs::create_dir_all(&blobs_dir).await?;
+
+        // 前回書き出したレイヤーで、ファイルが残っているものは使い回す
+        let mut blobs: HashMap<String, (String, u64)> = HashMap::new();
+        if let Ok(content) = tokio::fs::read(dir.join(INDEX_FILE)).await {
+            if let Ok(index) = serde_json::from_slice::<CacheIndex>(&content) {
+                for record in index.records {
+                    if let (Some(diff_id), Some(blob)) = (record.diff_id, record.blob) {
+                        if blobs_dir.join(blob.trim_start_matches("sha256:")).exists() {
+                            blobs.insert(diff_id, (blob, record.blob_size));
+                        }
+                    }
+                }
+            }
+        }
+
+        for record in &mut records {
+            let Some(diff_id) = record.diff_id.clone() else {
+                continue;
+            };
+            if !blobs.contains_key(&diff_id) {
+                let archive = blobs_dir.join(format!("tmp-{}", uuid::Uuid::new_v4()));
+                let (layer_dir, path) = (self.layer_path(&diff_id), archive.clone());
+                let packed = tokio::task::spawn_blocking(move || compress_layer(&layer_dir, &path)).await?;
+                let (_, digest, size) = match packed {
+                    Ok(packed) => packed,
+                    Err(e) => {
+                        let _ = std::fs::remove_file(&archive);
+                        return Err(ImageError::Build(format!("Failed to archive layer {}: {}", diff_id, e)).into());
+                    }
+                };
+                tokio::fs::rename(&archive, blobs_dir.join(digest.trim_start_matches("sha256:"))).await?;
+                blobs.insert(diff_id.clone(), (digest, size));
+            }
+            let (blob, blob_size) = blobs[&diff_id].clone();
+            record.blob = Some(blob);
+            record.blob_size = blob_size;
+        }
+
+        // 索引は書き終えてから置き換え、索引から指されなくなったレイヤーを消す
+        let referenced: HashSet<String> = records
+            .iter()
+            .filter_map(|record| record.blob.as_deref())
+            .map(|blob| blob.trim_start_matches("sha256:").to_string())
+            .collect();
+        let index = CacheIndex { records };
+        let staging = dir.join(format!("{}.tmp", INDEX_FILE));
+        tokio::fs::write(&staging, serde_json::to_vec_pretty(&index)?).await?;
+        tokio::fs::rename(&staging, dir.join(INDEX_FILE)).await?;
+        let mut entries = tokio::fs::read_dir(&blobs_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if !referenced.contains(entry.file_name().to_string_lossy().as_ref()) {
+                let _ = tokio::fs::remove_file(entry.path()).await;
+            }
+        }
+        Ok(index.records)
+    }
+
+    async fn export_registry(
+        &self,
+        mut records: Vec<CacheRecord>,
+        known: HashMap<String, (String, u64)>,
+        name: &str,
+        options: &BuildOptions,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Vec<CacheRecord>, RockerError> {
+        let mut client = self.cache_client(name, true, options)?;
+        let mut layers: Vec<Descriptor> = Vec::new();
+        for record in &mut records {
+            let Some(diff_id) = record.diff_id.clone() else {
+                continue;
+            };
+            let reusable = match known.get(&diff_id) {
+                Some((digest, size)) if layers.iter().any(|layer| &layer.digest == digest) => Some((digest.clone(), *size)),
+                Some((digest, size)) => client.blob_exists(digest).await?.then(|| (digest.clone(), *size)),
+                None => None,
+            };
+            let (digest, size) = match reusable {
+                Some(blob) => blob,
+                None => self.upload_cache_layer(&mut client, &diff_id, progress).await?,
+            };
+            if !layers.iter().any(|layer| layer.digest == digest) {
+                layers.push(Descriptor {
+                    media_type: OCI_LAYER.to_string(),
+                    digest: digest.clone(),
+                    size,
+                    platform: None,
+                });
+            }
+            record.blob = Some(digest);
+            record.blob_size = size;
+        }
+
+        let index = CacheIndex { records };
+        let content = serde_json::to_vec(&index)?;
+        let index_digest = format!("sha256:{:x}", Sha256::digest(&content));
+        let index_size = content.len() as u64;
+        if !client.blob_exists(&index_digest).await? {
+            client.upload_blob(&index_digest, content).await?;
+        }
+        let manifest = Manifest {
+            schema_version: 2,
+            media_type: Some(OCI_MANIFEST.to_string()),
+            config: Descriptor {
+                media_type: CACHE_INDEX.to_string(),
+                digest: index_digest,
+                size: index_size,
+                platform: None,
+            },
+            layers,
+        };
+        client.put_manifest(&manifest).await?;
+        Ok(index.records)
+    }
+
+    // レイヤーを tar.gz にしてアップロードし、ダイジェストとサイズを返す
+    async fn upload_cache_layer(
+        &self,
+        client: &mut RegistryClient,
+        diff_id: &str,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<(String, u64), RockerError> {
+        let archive = self.layers_dir.join(format!("tmp-{}.tar.gz", uuid::Uuid::new_v4()));
+        let (layer_dir, path) = (self.layer_path(diff_id), archive.clone());
+        let packed = tokio::task::spawn_blocking(move || compress_layer(&layer_dir, &path)).await?;
+        let result = async {
+            let (_, digest, size) =
+                packed.map_err(|e| ImageError::Push(format!("Failed to archive layer {}: {}", diff_id, e)))?;
+            if !client.blob_exists(&digest).await? {
+                let short_id = digest.trim_start_matches("sha256:").chars().take(12).collect::<String>();
+                let _ = progress.send(ProgressMessage::with_id(short_id.clone(), "Pushing").with_progress(0, size));
+                client.upload_blob(&digest, tokio::fs::read(&archive).await?).await?;
+                let _ = progress.send(ProgressMessage::with_id(short_id, "Pushed"));
+            }
+            Ok::<_, RockerError>((digest, size))
+        }
+        .await;
+        let _ = std::fs::remove_file(&archive);
+        result
+    }
+}
+
+// COPY・ADD のソースの内容のハッシュ（パス・種類・モード・所有者・中身から決め、時刻は含めない）
+pub(super) fn content_hash(root: &Path, paths: &[PathBuf]) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    for path in paths {
+        hash_path(&mut hasher, root, path)?;
+    }
+    Ok(format!("sha256:{:x}", hasher.finalize()))
+}
+
+fn hash_path(hasher: &mut Sha256, root: &Path, path: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    let name = path.strip_prefix(root).unwrap_or(path);
+    hasher.update(name.as_os_str().as_bytes());
+    hasher.update(format!("\0{:o} {}:{}\0", metadata.mode(), metadata.uid(), metadata.gid()).as_bytes());
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+        hasher.update(std::fs::read_link(path)?.as_os_str().as_bytes());
+    } else if file_type.is_file() {
+        hasher.update(metadata.len().to_string().as_bytes());
+        std::io::copy(&mut std::fs::File::open(path)?, hasher)?;
+    } else if file_type.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        entries.sort();
+        for entry in entries {
+            hash_path(hasher, root, &entry)?;
+        }
+    }
+    hasher.update(b"\n");
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use rocker_core::{ChangeKind, FileChange, Image, ImageDiff, ImageLayer, LayerChanges, RockerError};
+use std::collections::{BTreeMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use super::Manager;
+use crate::container::{OPAQUE_WHITEOUT, WHITEOUT_PREFIX};
+
+// 変更を判定するパスの属性
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileState {
+    is_dir: bool,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    size: u64,
+    mtime: (i64, i64),
+    link: Option<PathBuf>,
+}
+
+impl FileState {
+    fn read(path: &Path, metadata: &std::fs::Metadata) -> Self {
+        let is_dir = metadata.is_dir();
+        FileState {
+            is_dir,
+            mode: metadata.mode(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            size: if is_dir { 0 } else { metadata.size() },
+            mtime: (metadata.mtime(), metadata.mtime_nsec()),
+            link: metadata.is_symlink().then(|| std::fs::read_link(path).ok()).flatten(),
+        }
+    }
+
+    // ディレクトリは中身が変わると mtime も変わるため、モードと所有者だけを比べる
+    fn changed(&self, other: &FileState) -> bool {
+        if self.is_dir && other.is_dir {
+            return (self.mode, self.uid, self.gid) != (other.mode, other.uid, other.gid);
+        }
+        self != other
+    }
+}
+
+// rootfs の相対パスごとの属性（パスの順に並べ、ディレクトリの下のパスはディレクトリの直後に続く）
+type Tree = BTreeMap<PathBuf, FileState>;
+
+impl Manager {
+    // イメージ to の、イメージ from からの変更
+    pub async fn diff(&self, to: &str, from: &str) -> Result<ImageDiff, RockerError> {
+        let (to, from) = (self.get(to)?, self.get(from)?);
+        Ok(tokio::task::spawn_blocking(move || compare(&from, &to, display_name(&to), None)).await??)
+    }
+
+    // コンテナの rootfs の、イメージ from からの変更（書き込み層を最後のレイヤーとして返す）
+    //
+    // skip のパス（ボリュームやバインドマウントのマウント先）と別のファイルシステムの中は比べない。
+    pub async fn diff_container(
+        &self,
+        image: &str,
+        from: &str,
+        container: &str,
+        rootfs: PathBuf,
+        skip: HashSet<PathBuf>,
+    ) -> Result<ImageDiff, RockerError> {
+        let (image, from) = (self.get(image)?, self.get(from)?);
+        let container = container.to_string();
+        Ok(tokio::task::spawn_blocking(move || compare(&from, &image, container, Some((&rootfs, &skip)))).await??)
+    }
+}
+
+fn compare(from: &Image, to: &Image, to_name: String, writable: Option<(&Path, &HashSet<PathBuf>)>) -> std::io::Result<ImageDiff> {
+    let from_layers: Vec<&ImageLayer> = from.layers.iter().filter(|layer| !layer.empty_layer).collect();
+    let to_layers: Vec<&ImageLayer> = to.layers.iter().filter(|layer| !layer.empty_layer).collect();
+    let shared = from_layers
+        .iter()
+        .zip(&to_layers)
+        .take_while(|(a, b)| a.diff_id == b.diff_id)
+        .count();
+
+    let mut base = Tree::new();
+    for layer in &to_layers[..shared] {
+        apply_layer(&mut base, &layer.path, Path::new(""))?;
+    }
+    let mut from_tree = base.clone();
+    for layer in &from_layers[shared..] {
+        apply_layer(&mut from_tree, &layer.path, Path::new(""))?;
+    }
+
+    let mut tree = base;
+    let mut layers = Vec::new();
+    for layer in &to_layers[shared..] {
+        let before = tree.clone();
+        apply_layer(&mut tree, &layer.path, Path::new(""))?;
+        layers.push(LayerChanges {
+            diff_id: Some(layer.diff_id.clone()),
+            created_by: layer.created_by.clone(),
+            changes: changes(&before, &tree),
+            size_delta: size_delta(&before, &tree),
+        });
+    }
+    if let Some((rootfs, skip)) = writable {
+        let before = tree;
+        tree = Tree::new();
+        let dev = std::fs::symlink_metadata(rootfs)?.dev();
+        walk_rootfs(&mut tree, &before, rootfs, Path::new(""), dev, skip)?;
+        layers.push(LayerChanges {
+            diff_id: None,
+            created_by: None,
+            changes: changes(&before, &tree),
+            size_delta: size_delta(&before, &tree),
+        });
+    }
+
+    Ok(ImageDiff {
+        from: display_name(from),
+        to: to_name,
+        shared_layers: shared,
+        changes: changes(&from_tree, &tree),
+        size_delta: size_delta(&from_tree, &tree),
+        layers,
+    })
+}
+
+// レイヤーの whiteout が指すパスを消してから、レイヤーのパスを重ねる
+fn apply_layer(tree: &mut Tree, layer_dir: &Path, relative: &Path) -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(layer_dir.join(relative))? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == OPAQUE_WHITEOUT {
+            remove_under(tree, relative, false);
+        } else if let Some(target) = name.strip_prefix(WHITEOUT_PREFIX) {
+            remove_under(tree, &relative.join(target), true);
+        } else {
+            entries.push(entry);
+        }
+    }
+    for entry in entries {
+        let path = relative.join(entry.file_name());
+        let metadata = entry.path().symlink_metadata()?;
+        // ディレクトリ以外に置き換わったパスの下は残らない
+        if !metadata.is_dir() {
+            remove_under(tree, &path, false);
+        }
+        tree.insert(path.clone(), FileState::read(&entry.path(), &metadata));
+        if metadata.is_dir() {
+            apply_layer(tree, layer_dir, &path)?;
+        }
+    }
+    Ok(())
+}
+
+// コンテナの rootfs の全てのパス
+//
+// マウント先と別のファイルシステム（/proc など）の中には入らず、イメージのレイヤーにあったパスをそのまま残す。
+fn walk_rootfs(
+    tree: &mut Tree,
+    lower: &Tree,
+    rootfs: &Path,
+    relative: &Path,
+    dev: u64,
+    skip: &HashSet<PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(rootfs.join(relative))? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        let metadata = entry.path().symlink_metadata()?;
+        if metadata.dev() != dev || skip.contains(&entry.path()) {
+            let kept = lower.range(path.clone()..).take_while(|(other, _)| other.starts_with(&path));
+            tree.extend(kept.map(|(other, state)| (other.clone(), state.clone())));
+            continue;
+        }
+        tree.insert(path.clone(), FileState::read(&entry.path(), &metadata));
+        if metadata.is_dir() {
+            walk_rootfs(tree, lower, rootfs, &path, dev, skip)?;
+        }
+    }
+    Ok(())
+}
+
+// path の下のパスを消す（with_self ならば path 自体も）
+fn remove_under(tree: &mut Tree, path: &Path, with_self: bool) {
+    let removed: Vec<PathBuf> = tree
+        .range(path.to_path_buf()..)
+        .take_while(|(other, _)| other.starts_with(path))
+        .filter(|(other, _)| with_self || other.as_path() != path)
+        .map(|(other, _)| other.clone())
+        .collect();
+    for other in removed {
+        tree.remove(&other);
+    }
+}
+
+// 削除されたディレクトリの中身は、ディレクトリの削除だけで表す
+fn changes(before: &Tree, after: &Tree) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+    for (path, state) in after {
+        let kind = match before.get(path) {
+            None => ChangeKind::Added,
+            Some(old) if old.changed(state) => ChangeKind::Modified,
+            Some(_) => continue,
+        };
+        changes.push(file_change(path, kind, state));
+    }
+    for (path, state) in before {
+        let parent_removed = path
+            .parent()
+            .is_some_and(|parent| before.contains_key(parent) && !after.contains_key(parent));
+        if !after.contains_key(path) && !parent_removed {
+            changes.push(file_change(path, ChangeKind::Removed, state));
+        }
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn file_change(path: &Path, kind: ChangeKind, state: &FileState) -> FileChange {
+    FileChange {
+        path: Path::new("/").join(path).to_string_lossy().to_string(),
+        kind,
+        size: state.size,
+    }
+}
+
+fn size_delta(before: &Tree, after: &Tree) -> i64 {
+    let total = |tree: &Tree| tree.values().map(|state| state.size as i64).sum::<i64>();
+    total(after) - total(before)
+}
+
+fn display_name(image: &Image) -> String {
+    image
+        .full_name()
+        .unwrap_or_else(|| image.id.trim_start_matches("sha256:").chars().take(12).collect())
+}
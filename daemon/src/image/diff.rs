@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::container::WHITEOUT_PREFIX;
+use crate::reflink;
+
+// cp 1 回に渡すパスの数
+pub(super) const COPY_CHUNK: usize = 256;
+
+// ファイルが変わったかを判定するためのメタデータ
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    is_dir: bool,
+    ino: u64,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    size: u64,
+    mtime: (i64, i64),
+    ctime: (i64, i64),
+}
+
+// rootfs の全てのパスのメタデータ（ビルドの命令の前後で比較してレイヤーを作る）
+pub struct Snapshot {
+    entries: HashMap<PathBuf, Entry>,
+}
+
+impl Snapshot {
+    pub fn empty() -> Self {
+        Snapshot { entries: HashMap::new() }
+    }
+
+    pub fn take(root: &Path) -> std::io::Result<Self> {
+        let mut entries = HashMap::new();
+        walk(root, Path::new(""), &mut entries)?;
+        Ok(Snapshot { entries })
+    }
+}
+
+fn walk(root: &Path, relative: &Path, entries: &mut HashMap<PathBuf, Entry>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(root.join(relative))? {
+        let entry = entry?;
+        let path = relative.join(entry.file_name());
+        let metadata = entry.metadata()?;
+        let is_dir = metadata.is_dir();
+        entries.insert(
+            path.clone(),
+            Entry {
+                is_dir,
+                ino: metadata.ino(),
+                mode: metadata.mode(),
+                uid: metadata.uid(),
+                gid: metadata.gid(),
+                size: metadata.size(),
+                mtime: (metadata.mtime(), metadata.mtime_nsec()),
+                ctime: (metadata.ctime(), metadata.ctime_nsec()),
+            },
+        );
+        if is_dir {
+            walk(root, &path, entries)?;
+        }
+    }
+    Ok(())
+}
+
+// before から変わったファイルを layer_dir に書き出し、書き出した後のスナップショットと変更したパスの数を返す
+//
+// 削除されたパスは .wh.<name> の whiteout として残す。中身だけ変わったディレクトリは
+// 属性を保ったまま空のディレクトリとして作る。
+pub fn write_diff(root: &Path, before: &Snapshot, layer_dir: &Path) -> std::io::Result<(Snapshot, usize)> {
+    std::fs::create_dir_all(layer_dir)?;
+    let after = Snapshot::take(root)?;
+
+    let mut changed: Vec<&PathBuf> = after
+        .entries
+        .iter()
+        .filter(|(path, entry)| before.entries.get(*path) != Some(entry))
+        .map(|(path, _)| path)
+        .collect();
+    changed.sort();
+
+    let mut files = Vec::new();
+    for path in &changed {
+        // 変わったパスの親ディレクトリが無ければ属性ごと作る
+        for ancestor in path.ancestors().skip(1).collect::<Vec<_>>().into_iter().rev() {
+            if !ancestor.as_os_str().is_empty() && !layer_dir.join(ancestor).exists() {
+                copy_dir(root, ancestor, layer_dir)?;
+            }
+        }
+        if after.entries[*path].is_dir {
+            if !layer_dir.join(path).exists() {
+                copy_dir(root, path, layer_dir)?;
+            }
+        } else {
+            files.push(*path);
+        }
+    }
+
+    for chunk in files.chunks(COPY_CHUNK) {
+        // cp は reflink を、できなければ copy_file_range を使い、データをユーザー空間に読み出さない
+        let output = Command::new("cp")
+            .arg("-d")
+            .arg("--preserve=all")
+            .arg("--parents")
+            .args(reflink::cp_arg())
+            .arg("--")
+            .args(chunk.iter())
+            .arg(layer_dir)
+            .current_dir(root)
+            .output()?;
+        if !output.status.success() {
+            return Err(std::io::Error::other(format!(
+                "cp failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+    }
+
+    // 親ごと削除されたパスは親の whiteout だけで足りる
+    let mut removed: Vec<&PathBuf> = before
+        .entries
+        .keys()
+        .filter(|path| !after.entries.contains_key(*path))
+        .collect();
+    removed.sort();
+    let mut whiteouts = 0;
+    for path in &removed {
+        if path.parent().is_some_and(|parent| removed.binary_search(&&parent.to_path_buf()).is_ok()) {
+            continue;
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let whiteout = layer_dir.join(path.with_file_name(format!("{}{}", WHITEOUT_PREFIX, name)));
+        if let Some(parent) = whiteout.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::File::create(whiteout)?;
+        whiteouts += 1;
+    }
+
+    let count = changed.len() + whiteouts;
+    Ok((after, count))
+}
+
+// ディレクトリを中身なしで作り、所有者とモードを写す
+fn copy_dir(root: &Path, relative: &Path, layer_dir: &Path) -> std::io::Result<()> {
+    let target = layer_dir.join(relative);
+    std::fs::create_dir_all(&target)?;
+    let metadata = std::fs::metadata(root.join(relative))?;
+    std::os::unix::fs::chown(&target, Some(metadata.uid()), Some(metadata.gid()))?;
+    std::fs::set_permissions(&target, metadata.permissions())?;
+    Ok(())
+}
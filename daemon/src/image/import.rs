@@ -0,0 +1,166 @@
+use rocker_core::{
+    calculate_string_hash, HashingReader, Image, ImageConfig, ImageError, ImageLayer, ImageReference, RockerError,
+    STREAM_BUFFER_SIZE,
+};
+use rockerfile_parser::{Instruction, RockerfileParser};
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use tracing::info;
+
+use super::Manager;
+
+// gzip ファイルの先頭 2 バイト
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+impl Manager {
+    // tar（gzip 圧縮も可）からレイヤーが 1 つのイメージを作成する
+    pub async fn import(
+        &self,
+        source: Box<dyn Read + Send>,
+        reference: Option<&str>,
+        changes: &[String],
+        message: Option<&str>,
+    ) -> Result<Image, RockerError> {
+        let reference = match reference {
+            Some(reference) => Some(
+                ImageReference::parse(reference).ok_or_else(|| ImageError::Reference(reference.to_string()))?,
+            ),
+            None => None,
+        };
+
+        let mut config = ImageConfig::default();
+        for change in changes {
+            apply_change(&mut config, change)?;
+        }
+
+        // 展開が終わるまで diff ID が分からないため、一時ディレクトリに展開してから移動する
+        tokio::fs::create_dir_all(&self.layers_dir).await?;
+        let staging_dir = self.layers_dir.join(format!("tmp-{}", uuid::Uuid::new_v4()));
+        let unpack_dir = staging_dir.clone();
+        let unpacked = tokio::task::spawn_blocking(move || unpack_layer(source, &unpack_dir)).await?;
+        let (diff_id, size) = match unpacked {
+            Ok(result) => result,
+            Err(e) => {
+                let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+                return Err(ImageError::Load(e.to_string()).into());
+            }
+        };
+
+        let layer_dir = self.layers_dir.join(diff_id.trim_start_matches("sha256:"));
+        if layer_dir.exists() {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+        } else {
+            tokio::fs::rename(&staging_dir, &layer_dir).await?;
+        }
+
+        let now = Utc::now();
+        let layer = ImageLayer {
+            id: diff_id.clone(),
+            diff_id: diff_id.clone(),
+            size,
+            path: layer_dir,
+            created_at: now,
+            created_by: Some(message.unwrap_or("Imported from tarball").to_string()),
+            empty_layer: false,
+        };
+
+        // イメージ ID は設定とレイヤーの内容から決める
+        let id = calculate_string_hash(&format!("{}\n{}", serde_json::to_string(&config)?, diff_id));
+        let image = Image {
+            id: id.clone(),
+            repo: reference.as_ref().map(|r| r.repo.clone()),
+            tag: reference.as_ref().map(|r| r.tag.clone().unwrap_or_else(|| "latest".to_string())),
+            created_at: now,
+            size,
+            layers: vec![layer],
+            labels: config.labels.clone(),
+            config,
+            parent_id: None,
+            scan: None,
+            repo_digests: Vec::new(),
+        };
+
+        info!("Imported image {}", id);
+        self.images.lock().unwrap().insert(id.clone(), image.clone());
+        self.save(&id).await?;
+
+        Ok(image)
+    }
+}
+
+// レイヤーを展開し、非圧縮の tar の diff ID とサイズを返す
+pub(super) fn unpack_layer(source: Box<dyn Read + Send>, dir: &Path) -> std::io::Result<(String, u64)> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut source = BufReader::with_capacity(STREAM_BUFFER_SIZE, source);
+    let compressed = source.fill_buf()?.starts_with(&GZIP_MAGIC);
+    let decoded: Box<dyn Read> = if compressed {
+        Box::new(GzDecoder::new(source))
+    } else {
+        Box::new(source)
+    };
+
+    let mut archive = tar::Archive::new(HashingReader::new(decoded));
+    archive.set_preserve_permissions(true);
+    archive.set_unpack_xattrs(true);
+    archive.unpack(dir)?;
+
+    // tar の終端ブロックの後ろも含めてハッシュを計算する
+    let mut reader = archive.into_inner();
+    std::io::copy(&mut reader, &mut std::io::sink())?;
+
+    let (_, diff_id, size) = reader.finish();
+    Ok((diff_id, size))
+}
+
+// --change で指定された Rockerfile の命令をイメージの設定に反映する
+fn apply_change(config: &mut ImageConfig, change: &str) -> Result<(), ImageError> {
+    let mut parser = RockerfileParser::new();
+    let stages = parser
+        .parse_content(change)
+        .map_err(|e| ImageError::Load(format!("Invalid change {}: {}", change, e)))?;
+
+    for instruction in stages.iter().flat_map(|stage| stage.instructions.iter()) {
+        match instruction {
+            Instruction::Cmd { command } => config.cmd = Some(command.clone()),
+            Instruction::Entrypoint { command } => config.entrypoint = Some(command.clone()),
+            Instruction::Env { variables } => {
+                for (key, value) in variables {
+                    let prefix = format!("{}=", key);
+                    config.env.retain(|env| !env.starts_with(&prefix));
+                    config.env.push(format!("{}={}", key, value));
+                }
+            }
+            Instruction::Expose { ports, protocol } => {
+                let protocol = protocol.as_deref().unwrap_or("tcp");
+                for port in ports {
+                    config.exposed_ports.insert(format!("{}/{}", port, protocol), HashMap::new());
+                }
+            }
+            Instruction::Label { labels } => config.labels.extend(labels.clone()),
+            Instruction::User { user, group } => {
+                config.user = Some(match group {
+                    Some(group) => format!("{}:{}", user, group),
+                    None => user.clone(),
+                });
+            }
+            Instruction::Volume { paths } => {
+                for path in paths {
+                    config.volumes.insert(path.clone(), HashMap::new());
+                }
+            }
+            Instruction::Workdir { path } => config.working_dir = Some(path.clone()),
+            other => {
+                return Err(ImageError::Load(format!(
+                    "{} is not supported by --change",
+                    other.name()
+                )))
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rocker_core::{pack_dir, HashingWriter, STREAM_BUFFER_SIZE};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// レイヤーのディレクトリを gzip 圧縮した tar にして path に書き出し、diff ID・圧縮後のダイジェストとサイズを返す
+//
+// tar はエントリを名前順に並べるため、同じ内容からは同じ diff ID になる（push の度に変わらない）。
+pub fn compress_layer(dir: &Path, path: &Path) -> std::io::Result<(String, String, u64)> {
+    let file = BufWriter::with_capacity(STREAM_BUFFER_SIZE, std::fs::File::create(path)?);
+    let encoder = GzEncoder::new(HashingWriter::new(file), Compression::default());
+    let (encoder, diff_id, _) = pack_dir(dir, HashingWriter::new(encoder))?.finish();
+    let (mut file, digest, size) = encoder.finish()?.finish();
+    file.flush()?;
+    Ok((diff_id, digest, size))
+}
@@ -0,0 +1,27 @@
+use rocker_core::{RegistryAuth, RegistryReference, RockerError, DEFAULT_REGISTRY};
+use tracing::info;
+
+use super::registry::RegistryClient;
+use super::Manager;
+
+impl Manager {
+    // 認証情報でレジストリにログインできるか確かめる（保存はクライアントが行う）
+    pub async fn login(&self, auth: &RegistryAuth) -> Result<(), RockerError> {
+        let registry = match auth.server_address.trim_end_matches('/') {
+            "" => DEFAULT_REGISTRY.to_string(),
+            address => address
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .to_string(),
+        };
+        let reference = RegistryReference {
+            registry: registry.clone(),
+            repository: String::new(),
+            tag: None,
+            digest: None,
+        };
+        RegistryClient::new(reference, false, Some(auth.clone()), &self.proxy)?.login().await?;
+        info!("Logged in to {} as {}", registry, auth.username);
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use rocker_core::{lookup, Image, ImageError, LookupError, RockerError};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::proxy::{self, ProxyConfig};
+
+mod build;
+mod cache;
+mod compare;
+mod diff;
+mod import;
+mod layer;
+mod login;
+mod pull;
+mod push;
+mod registry;
+mod remove;
+mod sbom;
+mod scan;
+mod squash;
+
+pub use build::BuildOptions;
+pub use cache::{CachePruneOptions, CacheSpec};
+pub(crate) use build::wildcard_match;
+pub use sbom::SbomFormat;
+
+// イメージを管理する構造体
+//
+// pull・push・ビルドはデーモンのロックを外して進めるため、複製したマネージャでも同じイメージの一覧を共有する。
+#[derive(Clone)]
+pub struct Manager {
+    images: Arc<Mutex<HashMap<String, Image>>>,
+    state_dir: PathBuf,
+    layers_dir: PathBuf,
+    // ビルドの作業ディレクトリ
+    build_dir: PathBuf,
+    // ビルドの命令ごとの結果（ビルドキャッシュ）
+    cache_dir: PathBuf,
+    // 実行中のビルドの数（ビルド中の命令のレイヤーはまだイメージが使っていないため、その間は prune を断る）
+    builds: Arc<AtomicUsize>,
+    // レジストリへのアクセスとビルドの RUN に使うプロキシ（/etc/rocker/daemon.json の proxies）
+    proxy: ProxyConfig,
+    // pull で同時に取得するレイヤーの数（/etc/rocker/daemon.json の max-concurrent-downloads）
+    max_concurrent_downloads: usize,
+    // --dry-run ではビルドの RUN を実行しない
+    dry_run: bool,
+}
+
+impl Manager {
+    pub fn new(data_root: &Path, dry_run: bool) -> Self {
+        Manager {
+            images: Arc::new(Mutex::new(HashMap::new())),
+            state_dir: data_root.join("images"),
+            layers_dir: data_root.join("layers"),
+            build_dir: data_root.join("build"),
+            cache_dir: data_root.join("build-cache"),
+            builds: Arc::new(AtomicUsize::new(0)),
+            proxy: ProxyConfig::default(),
+            max_concurrent_downloads: 1,
+            dry_run,
+        }
+    }
+
+    // 保存済みのイメージ情報を読み込む
+    pub async fn init(&mut self) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        self.proxy = proxy::load_config();
+        self.max_concurrent_downloads = pull::load_max_concurrent_downloads();
+
+        let mut images = HashMap::new();
+        let mut entries = tokio::fs::read_dir(&self.state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let config_path = entry.path().join("image.json");
+            if !config_path.exists() {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&config_path).await?;
+            match serde_json::from_str::<Image>(&content) {
+                Ok(image) => {
+                    images.insert(image.id.clone(), image);
+                }
+                Err(e) => warn!("Skipping invalid image metadata {}: {}", config_path.display(), e),
+            }
+        }
+
+        info!("Loaded {} images", images.len());
+        *self.images.lock().unwrap() = images;
+
+        // 前回のデーモンが途中で終了したビルドの作業ディレクトリを消す
+        if self.build_dir.exists() {
+            tokio::fs::remove_dir_all(&self.build_dir).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn list_all(&self) -> Result<Vec<Image>, RockerError> {
+        Ok(self.images.lock().unwrap().values().cloned().collect())
+    }
+
+    // ID・ID の前方一致・repo:tag のいずれかでイメージを探す
+    pub fn get(&self, id_or_name: &str) -> Result<Image, ImageError> {
+        let images = self.images.lock().unwrap();
+        lookup(images.values(), id_or_name)
+            .cloned()
+            .map_err(|e| match e {
+                LookupError::NotFound => ImageError::NotFound(id_or_name.to_string()),
+                LookupError::Ambiguous(_) => ImageError::Ambiguous(id_or_name.to_string()),
+            })
+    }
+
+    // pull やビルドで作ったイメージを登録する（同じ repo:tag の既存のイメージからは名前を外す）
+    async fn store(&self, mut image: Image) -> Result<Image, RockerError> {
+        let untagged: Vec<String> = {
+            let mut images = self.images.lock().unwrap();
+            // 同じ内容のイメージを作り直した場合はスキャンの結果を引き継ぐ
+            if let Some(existing) = images.get(&image.id) {
+                image.scan = image.scan.or_else(|| existing.scan.clone());
+                for digest in &existing.repo_digests {
+                    if !image.repo_digests.contains(digest) {
+                        image.repo_digests.push(digest.clone());
+                    }
+                }
+            }
+            let mut untagged = Vec::new();
+            if image.repo.is_some() {
+                for other in images.values_mut() {
+                    if other.id != image.id && other.repo == image.repo && other.tag == image.tag {
+                        other.repo = None;
+                        other.tag = None;
+                        untagged.push(other.id.clone());
+                    }
+                }
+            }
+            images.insert(image.id.clone(), image.clone());
+            untagged
+        };
+
+        for id in untagged.iter().chain(std::iter::once(&image.id)) {
+            self.save(id).await?;
+        }
+        Ok(image)
+    }
+
+    // push したレジストリでのダイジェスト（repo@sha256:...）を記録する
+    async fn add_repo_digest(&self, id: &str, repo_digest: String) -> Result<(), RockerError> {
+        {
+            let mut images = self.images.lock().unwrap();
+            let Some(image) = images.get_mut(id) else {
+                return Ok(());
+            };
+            // 同じリポジトリの古いダイジェストは置き換える
+            let repo = repo_digest.split_once('@').map_or("", |(repo, _)| repo).to_string();
+            image.repo_digests.retain(|digest| !digest.starts_with(&format!("{}@", repo)));
+            image.repo_digests.push(repo_digest);
+        }
+        self.save(id).await
+    }
+
+    // イメージのメタデータをディスクに保存する
+    async fn save(&self, id: &str) -> Result<(), RockerError> {
+        let image = self.get(id)?;
+        let dir = self.state_dir.join(image.id.trim_start_matches("sha256:"));
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join("image.json"), serde_json::to_vec_pretty(&image)?).await?;
+        Ok(())
+    }
+}
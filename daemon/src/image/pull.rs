@@ -0,0 +1,283 @@
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use hyper::body::Bytes;
+use rocker_core::{Image, ImageError, ImageLayer, ProgressMessage, RegistryAuth, RegistryReference, RockerError};
+use serde::Deserialize;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use super::import::unpack_layer;
+use super::registry::{Descriptor, RegistryClient, RemoteImage};
+use super::Manager;
+use crate::DAEMON_CONFIG_PATH;
+
+// ダウンロード中のレイヤーの進捗を送る間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+// 同時に取得するレイヤーの数の既定値
+const DEFAULT_MAX_CONCURRENT_DOWNLOADS: usize = 3;
+// 展開を待たずに受け取っておくチャンクの数（これを超えるとダウンロードが展開を待つ）
+const CHUNK_BUFFER: usize = 64;
+
+#[derive(Deserialize)]
+struct DaemonConfig {
+    #[serde(rename = "max-concurrent-downloads")]
+    max_concurrent_downloads: Option<usize>,
+}
+
+// デーモンの設定ファイルの max-concurrent-downloads（無ければ 3）
+pub(super) fn load_max_concurrent_downloads() -> usize {
+    let Ok(content) = std::fs::read_to_string(DAEMON_CONFIG_PATH) else {
+        return DEFAULT_MAX_CONCURRENT_DOWNLOADS;
+    };
+    match serde_json::from_str::<DaemonConfig>(&content) {
+        Ok(DaemonConfig { max_concurrent_downloads: Some(0) }) => {
+            warn!("Ignoring max-concurrent-downloads in {}: it must be at least 1", DAEMON_CONFIG_PATH);
+            DEFAULT_MAX_CONCURRENT_DOWNLOADS
+        }
+        Ok(config) => config.max_concurrent_downloads.unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS),
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", DAEMON_CONFIG_PATH, e);
+            DEFAULT_MAX_CONCURRENT_DOWNLOADS
+        }
+    }
+}
+
+impl Manager {
+    // レジストリからイメージを取得して登録する（auth が無ければ匿名で取得し、進捗は progress に送る）
+    pub async fn pull(
+        &self,
+        name: &str,
+        auth: Option<&RegistryAuth>,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Image, RockerError> {
+        let reference = RegistryReference::parse(name).map_err(ImageError::Reference)?;
+        let mut client = RegistryClient::new(reference.clone(), false, auth.cloned(), &self.proxy)?;
+        let _ = progress.send(ProgressMessage::status(format!(
+            "{}: Pulling from {}",
+            reference.reference(),
+            reference.familiar_name()
+        )));
+
+        let (digest, manifest) = client.manifest().await?;
+        let config = client.blob(&manifest.config.digest).await?;
+        let remote: RemoteImage =
+            serde_json::from_slice(&config).map_err(|e| ImageError::Pull(format!("Invalid image config: {}", e)))?;
+        if remote.rootfs.diff_ids.len() != manifest.layers.len() {
+            return Err(ImageError::Pull(format!(
+                "The image config lists {} layers but the manifest has {}",
+                remote.rootfs.diff_ids.len(),
+                manifest.layers.len()
+            ))
+            .into());
+        }
+
+        tokio::fs::create_dir_all(&self.layers_dir).await?;
+        let created_at = remote.created.unwrap_or_else(Utc::now);
+        // 空のレイヤーを除いた履歴がレイヤーに対応する
+        let mut history = remote.history.iter().filter(|history| !history.empty_layer);
+        let created_by: Vec<Option<String>> = manifest
+            .layers
+            .iter()
+            .map(|_| history.next().and_then(|history| history.created_by.clone()))
+            .collect();
+
+        // レイヤーはそれぞれのディレクトリに展開するので、互いを待たずに max-concurrent-downloads 個ずつ取得する
+        //
+        // 失敗したレイヤーがあれば残りは始めないが、取得中のものは終わるまで待つ（途中で捨てると、展開の
+        // スレッドが一時ディレクトリに書き続けて残る）。
+        let (client, failed) = (&client, &AtomicBool::new(false));
+        // 記述子と diff ID は、参照のままでは tokio::spawn の中で Send と推論されないため複製して渡す
+        let pending = manifest.layers.iter().cloned().zip(remote.rootfs.diff_ids.iter().cloned());
+        let results: Vec<Result<ImageLayer, ImageError>> = stream::iter(pending.zip(created_by))
+            .map(|((descriptor, diff_id), created_by)| async move {
+                if failed.load(Ordering::Relaxed) {
+                    return Err(ImageError::Pull("Cancelled".to_string()));
+                }
+                let layer_dir = self.layers_dir.join(diff_id.trim_start_matches("sha256:"));
+                let size = self
+                    .pull_layer(client.clone(), &descriptor, &diff_id, &layer_dir, progress)
+                    .await
+                    .inspect_err(|_| failed.store(true, Ordering::Relaxed))?;
+                Ok(ImageLayer {
+                    id: descriptor.digest,
+                    diff_id,
+                    size,
+                    path: layer_dir,
+                    created_at,
+                    created_by,
+                    empty_layer: false,
+                })
+            })
+            .buffered(self.max_concurrent_downloads)
+            .collect()
+            .await;
+        // 始めたレイヤーは順に始めているので、順番で最初のエラーが取り消しではない本当の失敗になる
+        let layers = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+        // ダイジェストで指定した場合はタグを付けない
+        let tagged = reference.digest.is_none();
+        let config = remote.image_config();
+        let image = Image {
+            id: manifest.config.digest.clone(),
+            repo: if tagged { Some(reference.familiar_name()) } else { None },
+            tag: if tagged { reference.tag.clone() } else { None },
+            created_at,
+            size: layers.iter().map(|layer| layer.size).sum(),
+            layers,
+            labels: config.labels.clone(),
+            config,
+            parent_id: None,
+            scan: None,
+            repo_digests: vec![format!("{}@{}", reference.familiar_name(), digest)],
+        };
+        let image = self.store(image).await?;
+        let _ = progress.send(ProgressMessage::status(format!(
+            "Downloaded image for {}",
+            image.full_name().unwrap_or_else(|| name.to_string())
+        )));
+        Ok(image)
+    }
+
+    // タグがレジストリで今指しているイメージのダイジェスト（repo@sha256:...、イメージは取得しない）
+    pub async fn remote_digest(&self, name: &str, auth: Option<&RegistryAuth>) -> Result<String, RockerError> {
+        let reference = RegistryReference::parse(name).map_err(ImageError::Reference)?;
+        let mut client = RegistryClient::new(reference.clone(), false, auth.cloned(), &self.proxy)?;
+        let digest = client.manifest_digest().await?;
+        Ok(format!("{}@{}", reference.familiar_name(), digest))
+    }
+
+    // レイヤーが無ければ取得し、レイヤーのサイズを返す
+    pub(super) async fn pull_layer(
+        &self,
+        mut client: RegistryClient,
+        descriptor: &Descriptor,
+        diff_id: &str,
+        layer_dir: &Path,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<u64, ImageError> {
+        let short_id = short_digest(&descriptor.digest);
+        if layer_dir.exists() {
+            let _ = progress.send(ProgressMessage::with_id(short_id, "Already exists"));
+            return Ok(self.layer_size(diff_id).unwrap_or(descriptor.size));
+        }
+        let _ = progress.send(ProgressMessage::with_id(short_id.clone(), "Downloading").with_progress(0, descriptor.size));
+        let size = self
+            .fetch_layer(&mut client, descriptor, diff_id, layer_dir, &short_id, progress)
+            .await
+            .map_err(|e| match e {
+                RockerError::Image(e) => e,
+                e => ImageError::Pull(format!("Failed to pull layer {}: {}", descriptor.digest, e)),
+            })?;
+        let _ = progress.send(ProgressMessage::with_id(short_id, "Pull complete"));
+        Ok(size)
+    }
+
+    // レイヤーをダウンロードしながら展開し、diff ID を検証してからレイヤーのディレクトリに移す
+    async fn fetch_layer(
+        &self,
+        client: &mut RegistryClient,
+        descriptor: &Descriptor,
+        diff_id: &str,
+        layer_dir: &Path,
+        short_id: &str,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<u64, RockerError> {
+        if descriptor.media_type.contains("zstd") {
+            return Err(ImageError::Pull(format!("Unsupported layer type: {}", descriptor.media_type)).into());
+        }
+
+        let staging_dir = self.layers_dir.join(format!("tmp-{}", uuid::Uuid::new_v4()));
+        let result = async {
+            // 受け取ったチャンクを別のスレッドで解凍・展開する
+            let (chunks, received) = mpsc::channel(CHUNK_BUFFER);
+            let unpack_dir = staging_dir.clone();
+            let unpack = tokio::task::spawn_blocking(move || {
+                let reader = ChunkReader {
+                    chunks: received,
+                    current: Bytes::new(),
+                };
+                unpack_layer(Box::new(reader), &unpack_dir)
+            });
+
+            // 進捗は PROGRESS_INTERVAL ごとに送る
+            let mut last_sent = Instant::now();
+            let downloaded = client
+                .stream_blob(&descriptor.digest, chunks, |received| {
+                    if last_sent.elapsed() >= PROGRESS_INTERVAL {
+                        last_sent = Instant::now();
+                        let message = ProgressMessage::with_id(short_id, "Downloading").with_progress(received, descriptor.size);
+                        let _ = progress.send(message);
+                    }
+                })
+                .await;
+            if downloaded.is_ok() {
+                let _ = progress.send(ProgressMessage::with_id(short_id, "Extracting"));
+            }
+            let unpacked = unpack
+                .await?
+                .map_err(|e| ImageError::Pull(format!("Failed to extract layer {}: {}", descriptor.digest, e)));
+            let (actual, size) = match downloaded {
+                Ok(()) => unpacked?,
+                // 展開が先に失敗すると、ダウンロードも送り先が無くなって止まる
+                Err(ImageError::Pull(_)) if unpacked.is_err() => unpacked?,
+                Err(e) => return Err(e.into()),
+            };
+            if actual != diff_id {
+                return Err::<u64, RockerError>(
+                    ImageError::Pull(format!("Layer {} does not match its diff ID {}", descriptor.digest, diff_id)).into(),
+                );
+            }
+            // 同じレイヤーを並行して pull した場合は先に置いた方を使う
+            if tokio::fs::rename(&staging_dir, layer_dir).await.is_err() && !layer_dir.exists() {
+                return Err(ImageError::Pull(format!("Failed to store layer {}", diff_id)).into());
+            }
+            Ok(size)
+        }
+        .await;
+
+        // エラーを持ったまま await しないよう同期的に消す
+        if staging_dir.exists() {
+            let _ = std::fs::remove_dir_all(&staging_dir);
+        }
+        result
+    }
+
+    // 既存のイメージが記録しているレイヤーのサイズ
+    fn layer_size(&self, diff_id: &str) -> Option<u64> {
+        let images = self.images.lock().unwrap();
+        images
+            .values()
+            .flat_map(|image| image.layers.iter())
+            .find(|layer| layer.diff_id == diff_id)
+            .map(|layer| layer.size)
+    }
+}
+
+// 受け取ったチャンクを順に読ませる（spawn_blocking の中で使う。送る側が閉じると終端になる）
+struct ChunkReader {
+    chunks: mpsc::Receiver<Bytes>,
+    current: Bytes,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.current.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(chunk) => self.current = chunk,
+                None => return Ok(0),
+            }
+        }
+        let chunk = self.current.split_to(buf.len().min(self.current.len()));
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
+
+// 進捗の表示に使う短いダイジェスト
+pub(super) fn short_digest(digest: &str) -> String {
+    digest.trim_start_matches("sha256:").chars().take(12).collect()
+}
@@ -0,0 +1,95 @@
+use rocker_core::{ImageError, ProgressMessage, RegistryAuth, RegistryReference, RockerError};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use super::layer::compress_layer;
+use super::pull::short_digest;
+use super::registry::{Descriptor, Manifest, RegistryClient, RemoteImage, DOCKER_CONFIG, DOCKER_LAYER, DOCKER_MANIFEST};
+use super::Manager;
+
+impl Manager {
+    // イメージをレジストリに送り、マニフェストのダイジェストを返す（進捗は progress に送る）
+    pub async fn push(
+        &self,
+        name: &str,
+        auth: Option<&RegistryAuth>,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<String, RockerError> {
+        let image = self.get(name)?;
+        // ID で指定した場合はイメージの名前で push する
+        let name = if image.id.trim_start_matches("sha256:").starts_with(name.trim_start_matches("sha256:")) {
+            image
+                .full_name()
+                .ok_or_else(|| ImageError::Push(format!("Image {} has no name to push it as", name)))?
+        } else {
+            name.to_string()
+        };
+        let reference = RegistryReference::parse(&name).map_err(ImageError::Reference)?;
+        if reference.digest.is_some() {
+            return Err(ImageError::Push("Cannot push to a digest, use a tag".to_string()).into());
+        }
+        let mut client = RegistryClient::new(reference.clone(), true, auth.cloned(), &self.proxy)?;
+        let _ = progress.send(ProgressMessage::status(format!(
+            "The push refers to repository [{}/{}]",
+            reference.registry, reference.repository
+        )));
+
+        let mut layers = Vec::new();
+        let mut diff_ids = Vec::new();
+        for layer in image.layers.iter().filter(|layer| !layer.empty_layer) {
+            let short_id = short_digest(&layer.diff_id);
+            let _ = progress.send(ProgressMessage::with_id(short_id.clone(), "Preparing"));
+
+            // レイヤーの tar.gz を一時ファイルに作ってからアップロードする
+            let archive = self.layers_dir.join(format!("tmp-{}.tar.gz", uuid::Uuid::new_v4()));
+            let (dir, path) = (layer.path.clone(), archive.clone());
+            let packed = tokio::task::spawn_blocking(move || compress_layer(&dir, &path)).await?;
+            let result = async {
+                let (diff_id, digest, size) =
+                    packed.map_err(|e| ImageError::Push(format!("Failed to archive layer {}: {}", layer.diff_id, e)))?;
+                if client.blob_exists(&digest).await? {
+                    let _ = progress.send(ProgressMessage::with_id(short_id.clone(), "Layer already exists"));
+                } else {
+                    let _ = progress.send(ProgressMessage::with_id(short_id.clone(), "Pushing").with_progress(0, size));
+                    client.upload_blob(&digest, tokio::fs::read(&archive).await?).await?;
+                    let _ = progress.send(ProgressMessage::with_id(short_id.clone(), "Pushed"));
+                }
+                Ok::<_, RockerError>((diff_id, digest, size))
+            }
+            .await;
+            let _ = std::fs::remove_file(&archive);
+
+            let (diff_id, digest, size) = result?;
+            layers.push(Descriptor {
+                media_type: DOCKER_LAYER.to_string(),
+                digest,
+                size,
+                platform: None,
+            });
+            diff_ids.push(diff_id);
+        }
+
+        let config = serde_json::to_vec(&RemoteImage::from_image(&image, diff_ids))?;
+        let config_digest = format!("sha256:{:x}", Sha256::digest(&config));
+        let config_size = config.len() as u64;
+        if !client.blob_exists(&config_digest).await? {
+            client.upload_blob(&config_digest, config).await?;
+        }
+
+        let manifest = Manifest {
+            schema_version: 2,
+            media_type: Some(DOCKER_MANIFEST.to_string()),
+            config: Descriptor {
+                media_type: DOCKER_CONFIG.to_string(),
+                digest: config_digest,
+                size: config_size,
+                platform: None,
+            },
+            layers,
+        };
+        let digest = client.put_manifest(&manifest).await?;
+        self.add_repo_digest(&image.id, format!("{}@{}", reference.familiar_name(), digest)).await?;
+        let _ = progress.send(ProgressMessage::status(format!("{}: digest: {}", reference.reference(), digest)));
+        Ok(digest)
+    }
+}
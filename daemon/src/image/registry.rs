@@ -0,0 +1,663 @@
+use chrono::{DateTime, Utc};
+use hyper::body::Bytes;
+use reqwest::header::{HeaderMap, ACCEPT, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use rocker_core::{HealthConfig, Image, ImageConfig, ImageError, RegistryAuth, RegistryReference};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+use crate::proxy::ProxyConfig;
+
+// マニフェストの種類
+pub const DOCKER_MANIFEST: &str = "application/vnd.docker.distribution.manifest.v2+json";
+const DOCKER_MANIFEST_LIST: &str = "application/vnd.docker.distribution.manifest.list.v2+json";
+pub const OCI_MANIFEST: &str = "application/vnd.oci.image.manifest.v1+json";
+const OCI_INDEX: &str = "application/vnd.oci.image.index.v1+json";
+// push するイメージの設定とレイヤーの種類
+pub const DOCKER_CONFIG: &str = "application/vnd.docker.container.image.v1+json";
+pub const DOCKER_LAYER: &str = "application/vnd.docker.image.rootfs.diff.tar.gzip";
+pub const OCI_LAYER: &str = "application/vnd.oci.image.layer.v1.tar+gzip";
+
+// マニフェストやレイヤーを指す記述子
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Descriptor {
+    #[serde(rename = "mediaType")]
+    pub media_type: String,
+    pub digest: String,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<Platform>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Platform {
+    pub architecture: String,
+    pub os: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+}
+
+// 1 つのプラットフォームのイメージのマニフェスト
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: u32,
+    #[serde(rename = "mediaType", default, skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    pub config: Descriptor,
+    pub layers: Vec<Descriptor>,
+}
+
+// 複数のプラットフォームのマニフェストの一覧
+#[derive(Debug, Deserialize)]
+struct ManifestIndex {
+    manifests: Vec<Descriptor>,
+}
+
+// レジストリに保存されるイメージの設定（Docker と OCI で共通の形式）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RemoteImage {
+    #[serde(default)]
+    pub architecture: String,
+    #[serde(default)]
+    pub os: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub config: RemoteConfig,
+    #[serde(default)]
+    pub rootfs: RootFs,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<History>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RemoteConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmd: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exposed_ports: Option<HashMap<String, serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<HashMap<String, serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub labels: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_signal: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub healthcheck: Option<RemoteHealthcheck>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_build: Option<Vec<String>>,
+}
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+// イメージの設定のヘルスチェック（時間はナノ秒で、0 は未設定）
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RemoteHealthcheck {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub interval: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub timeout: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub start_period: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u64,
+}
+
+impl RemoteHealthcheck {
+    fn health_config(&self) -> HealthConfig {
+        // 1 秒未満の時間は切り上げる
+        let seconds = |nanos: u64| (nanos > 0).then(|| nanos.div_ceil(NANOS_PER_SEC));
+        HealthConfig {
+            test: self.test.clone(),
+            interval: seconds(self.interval),
+            timeout: seconds(self.timeout),
+            retries: (self.retries > 0).then(|| u32::try_from(self.retries).unwrap_or(u32::MAX)),
+            start_period: seconds(self.start_period),
+        }
+    }
+
+    fn from_health_config(config: &HealthConfig) -> Self {
+        let nanos = |seconds: Option<u64>| seconds.unwrap_or(0).saturating_mul(NANOS_PER_SEC);
+        RemoteHealthcheck {
+            test: config.test.clone(),
+            interval: nanos(config.interval),
+            timeout: nanos(config.timeout),
+            start_period: nanos(config.start_period),
+            retries: config.retries.map_or(0, u64::from),
+        }
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RootFs {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub diff_ids: Vec<String>,
+}
+
+impl Default for RootFs {
+    fn default() -> Self {
+        RootFs {
+            kind: "layers".to_string(),
+            diff_ids: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub empty_layer: bool,
+}
+
+impl RemoteImage {
+    // ローカルのイメージの設定にする（空の値は未設定として扱う）
+    pub fn image_config(&self) -> ImageConfig {
+        let keys = |map: &Option<HashMap<String, serde_json::Value>>| {
+            map.iter()
+                .flat_map(|map| map.keys())
+                .map(|key| (key.clone(), HashMap::new()))
+                .collect()
+        };
+        let config = &self.config;
+        ImageConfig {
+            user: config.user.clone().filter(|user| !user.is_empty()),
+            working_dir: config.working_dir.clone().filter(|dir| !dir.is_empty()),
+            env: config.env.clone().unwrap_or_default(),
+            cmd: config.cmd.clone(),
+            entrypoint: config.entrypoint.clone(),
+            exposed_ports: keys(&config.exposed_ports),
+            volumes: keys(&config.volumes),
+            labels: config.labels.clone().unwrap_or_default(),
+            architecture: self.architecture.clone(),
+            os: self.os.clone(),
+            stop_signal: config.stop_signal.clone(),
+            healthcheck: config.healthcheck.as_ref().map(RemoteHealthcheck::health_config),
+            on_build: config.on_build.clone().unwrap_or_default(),
+        }
+    }
+
+    // ローカルのイメージからレジストリに保存する設定を作る（diff_ids はレイヤーの順）
+    pub fn from_image(image: &Image, diff_ids: Vec<String>) -> Self {
+        let keys = |map: &HashMap<String, HashMap<(), ()>>| {
+            Some(map.keys().map(|key| (key.clone(), serde_json::json!({}))).collect())
+        };
+        let config = &image.config;
+        RemoteImage {
+            architecture: config.architecture.clone(),
+            os: config.os.clone(),
+            created: Some(image.created_at),
+            config: RemoteConfig {
+                user: config.user.clone(),
+                env: Some(config.env.clone()),
+                cmd: config.cmd.clone(),
+                entrypoint: config.entrypoint.clone(),
+                working_dir: config.working_dir.clone(),
+                exposed_ports: keys(&config.exposed_ports),
+                volumes: keys(&config.volumes),
+                labels: Some(config.labels.clone()),
+                stop_signal: config.stop_signal.clone(),
+                healthcheck: config.healthcheck.as_ref().map(RemoteHealthcheck::from_health_config),
+                on_build: Some(config.on_build.clone()).filter(|triggers| !triggers.is_empty()),
+            },
+            rootfs: RootFs {
+                kind: "layers".to_string(),
+                diff_ids,
+            },
+            history: image
+                .layers
+                .iter()
+                .map(|layer| History {
+                    created: Some(layer.created_at),
+                    created_by: layer.created_by.clone(),
+                    empty_layer: layer.empty_layer,
+                })
+                .collect(),
+        }
+    }
+}
+
+// トークンサーバの応答（サーバによって token と access_token のどちらかを返す）
+#[derive(Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+// レジストリの HTTP API のクライアント（1 つのリポジトリを操作する）
+//
+// 複製したクライアントは取得済みのトークンを引き継ぐ（レイヤーを並行して取得するのに使う）。
+#[derive(Clone)]
+pub struct RegistryClient {
+    http: reqwest::Client,
+    reference: RegistryReference,
+    // トークンに要求する権限
+    actions: &'static str,
+    token: Option<String>,
+    // ログインに使う認証情報（無ければ匿名）
+    auth: Option<RegistryAuth>,
+    // Bearer ではなく Basic 認証を求めるレジストリでは、全てのリクエストに認証情報を付ける
+    basic: bool,
+}
+
+impl RegistryClient {
+    pub fn new(
+        reference: RegistryReference,
+        push: bool,
+        auth: Option<RegistryAuth>,
+        proxy: &ProxyConfig,
+    ) -> Result<Self, ImageError> {
+        let builder = reqwest::Client::builder().user_agent(concat!("rocker/", env!("CARGO_PKG_VERSION")));
+        let http = proxy
+            .apply(builder)
+            .and_then(|builder| builder.build())
+            .map_err(|e| ImageError::Registry(e.to_string()))?;
+        Ok(RegistryClient {
+            http,
+            reference,
+            actions: if push { "pull,push" } else { "pull" },
+            token: None,
+            auth,
+            basic: false,
+        })
+    }
+
+    // 認証情報でレジストリにログインできるか確かめる（リポジトリを指定しない GET /v2/）
+    pub async fn login(&mut self) -> Result<(), ImageError> {
+        let url = format!("{}/", self.reference.api_url());
+        let response = self.send(|http| http.get(&url)).await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ImageError::Unauthorized(format!(
+                "invalid username or password for {}",
+                self.reference.registry
+            ))),
+            status => Err(ImageError::Registry(format!(
+                "Unexpected response from {}: {}",
+                self.reference.registry, status
+            ))),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}/{}", self.reference.api_url(), self.reference.repository, path)
+    }
+
+    // リクエストを送る（401 の場合はトークンを取得して 1 度だけやり直す）
+    async fn send<F>(&mut self, build: F) -> Result<Response, ImageError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let response = self.send_once(&build).await?;
+        if response.status() != StatusCode::UNAUTHORIZED || self.token.is_some() || self.basic {
+            return Ok(response);
+        }
+        self.authenticate(response.headers()).await?;
+        self.send_once(&build).await
+    }
+
+    async fn send_once<F>(&self, build: &F) -> Result<Response, ImageError>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let mut request = build(&self.http);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        } else if let (true, Some(auth)) = (self.basic, &self.auth) {
+            request = request.basic_auth(&auth.username, Some(&auth.password));
+        }
+        request
+            .send()
+            .await
+            .map_err(|e| ImageError::Registry(format!("{}: {}", self.reference.registry, e)))
+    }
+
+    // WWW-Authenticate: Bearer realm="...",service="..." のトークンサーバからトークンを取得する
+    //
+    // 認証情報があればトークンサーバに Basic 認証でログインし、無ければ匿名のトークンを取得する。
+    // WWW-Authenticate: Basic のレジストリには認証情報をそのまま送る。
+    async fn authenticate(&mut self, headers: &HeaderMap) -> Result<(), ImageError> {
+        let challenge = headers
+            .get(WWW_AUTHENTICATE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("");
+        let params = match challenge.strip_prefix("Bearer ") {
+            Some(params) => parse_challenge(params),
+            None if challenge.starts_with("Basic") && self.auth.is_some() => {
+                self.basic = true;
+                return Ok(());
+            }
+            None => {
+                return Err(ImageError::Registry(format!(
+                    "{} requires authentication (log in with 'rocker login {}')",
+                    self.reference.registry, self.reference.registry
+                )))
+            }
+        };
+        let realm = params
+            .iter()
+            .find(|(key, _)| key == "realm")
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| ImageError::Registry("Authentication challenge without realm".to_string()))?;
+
+        // ログインの確認ではリポジトリの権限を求めない
+        let mut query = Vec::new();
+        if !self.reference.repository.is_empty() {
+            query.push((
+                "scope".to_string(),
+                format!("repository:{}:{}", self.reference.repository, self.actions),
+            ));
+        }
+        if let Some((_, service)) = params.iter().find(|(key, _)| key == "service") {
+            query.push(("service".to_string(), service.clone()));
+        }
+        let mut request = self.http.get(&realm).query(&query);
+        if let Some(auth) = &self.auth {
+            request = request.basic_auth(&auth.username, Some(&auth.password));
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ImageError::Registry(format!("{}: {}", realm, e)))?;
+        if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) && self.auth.is_some() {
+            return Err(ImageError::Unauthorized(format!(
+                "invalid username or password for {}",
+                self.reference.registry
+            )));
+        }
+        if !response.status().is_success() {
+            return Err(ImageError::Registry(format!(
+                "Failed to get a token for {} ({})",
+                self.reference.repository,
+                response.status()
+            )));
+        }
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| ImageError::Registry(format!("Invalid token response: {}", e)))?;
+        self.token = token.token.or(token.access_token);
+        if self.token.is_none() {
+            return Err(ImageError::Registry("The token server returned no token".to_string()));
+        }
+        Ok(())
+    }
+
+    // 参照が指すマニフェストを取得する（マニフェストの一覧の場合はこのホストのプラットフォームを選ぶ）
+    pub async fn manifest(&mut self) -> Result<(String, Manifest), ImageError> {
+        let reference = self.reference.reference().to_string();
+        let (media_type, body) = self.get_manifest(&reference).await?;
+        let digest = format!("sha256:{:x}", Sha256::digest(&body));
+        if media_type != DOCKER_MANIFEST_LIST && media_type != OCI_INDEX {
+            return Ok((digest, parse_manifest(&body)?));
+        }
+
+        let index: ManifestIndex =
+            serde_json::from_slice(&body).map_err(|e| ImageError::Registry(format!("Invalid manifest list: {}", e)))?;
+        let (os, architecture) = ("linux", host_architecture());
+        let descriptor = index
+            .manifests
+            .iter()
+            .find(|descriptor| {
+                descriptor
+                    .platform
+                    .as_ref()
+                    .is_some_and(|platform| platform.os == os && platform.architecture == architecture)
+            })
+            .ok_or_else(|| {
+                ImageError::Pull(format!(
+                    "{} has no image for {}/{}",
+                    self.reference.familiar_name(),
+                    os,
+                    architecture
+                ))
+            })?;
+        let (_, body) = self.get_manifest(&descriptor.digest.clone()).await?;
+        Ok((digest, parse_manifest(&body)?))
+    }
+
+    // 参照が指すマニフェストのダイジェスト（マニフェストの一覧ならその一覧のもので、pull が記録するものと同じ）
+    //
+    // HEAD の Docker-Content-Digest を使い、返さないレジストリではマニフェストを取得して計算する。
+    pub async fn manifest_digest(&mut self) -> Result<String, ImageError> {
+        let reference = self.reference.reference().to_string();
+        let url = self.url(&format!("manifests/{}", reference));
+        let accept = [DOCKER_MANIFEST, DOCKER_MANIFEST_LIST, OCI_MANIFEST, OCI_INDEX].join(", ");
+        let response = self.send(|http| http.head(&url).header(ACCEPT, &accept)).await?;
+        if response.status().is_success() {
+            if let Some(digest) = response
+                .headers()
+                .get("docker-content-digest")
+                .and_then(|value| value.to_str().ok())
+            {
+                return Ok(digest.to_string());
+            }
+        }
+        let (_, body) = self.get_manifest(&reference).await?;
+        Ok(format!("sha256:{:x}", Sha256::digest(&body)))
+    }
+
+    async fn get_manifest(&mut self, reference: &str) -> Result<(String, Vec<u8>), ImageError> {
+        let url = self.url(&format!("manifests/{}", reference));
+        let accept = [DOCKER_MANIFEST, DOCKER_MANIFEST_LIST, OCI_MANIFEST, OCI_INDEX].join(", ");
+        let response = self.send(|http| http.get(&url).header(ACCEPT, &accept)).await?;
+        match response.status() {
+            status if status.is_success() => {}
+            StatusCode::NOT_FOUND => {
+                return Err(ImageError::NotFound(format!("{}:{}", self.reference.familiar_name(), reference)))
+            }
+            status => return Err(self.status_error("manifest", status)),
+        }
+
+        let media_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("")
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ImageError::Registry(e.to_string()))?
+            .to_vec();
+        Ok((media_type, body))
+    }
+
+    // ブロブをメモリに読み込む（イメージの設定など小さいもの）
+    pub async fn blob(&mut self, digest: &str) -> Result<Vec<u8>, ImageError> {
+        let url = self.url(&format!("blobs/{}", digest));
+        let response = self.send(|http| http.get(&url)).await?;
+        if !response.status().is_success() {
+            return Err(self.status_error("blob", response.status()));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| ImageError::Registry(e.to_string()))?
+            .to_vec();
+        verify_digest(digest, &format!("sha256:{:x}", Sha256::digest(&body)))?;
+        Ok(body)
+    }
+
+    // ブロブをファイルに書き出し、ダイジェストを検証する（受け取ったバイト数を chunk を受け取る度に on_progress に渡す）
+    // ブロブを受け取りながら chunks に送る（受け取る側が並行して展開する）
+    //
+    // ダイジェストは全て受け取った後で検証するため、受け取る側は Ok が返るまで結果を確定させないこと。
+    pub async fn stream_blob(
+        &mut self,
+        digest: &str,
+        chunks: mpsc::Sender<Bytes>,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<(), ImageError> {
+        let url = self.url(&format!("blobs/{}", digest));
+        let mut response = self.send(|http| http.get(&url)).await?;
+        if !response.status().is_success() {
+            return Err(self.status_error("blob", response.status()));
+        }
+
+        let mut hasher = Sha256::new();
+        let mut received = 0;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| ImageError::Registry(format!("Failed to download {}: {}", digest, e)))?
+        {
+            hasher.update(&chunk);
+            received += chunk.len() as u64;
+            if chunks.send(chunk).await.is_err() {
+                return Err(ImageError::Pull(format!("Extraction of {} stopped", digest)));
+            }
+            on_progress(received);
+        }
+        verify_digest(digest, &format!("sha256:{:x}", hasher.finalize()))
+    }
+
+    pub async fn blob_exists(&mut self, digest: &str) -> Result<bool, ImageError> {
+        let url = self.url(&format!("blobs/{}", digest));
+        let response = self.send(|http| http.head(&url)).await?;
+        Ok(response.status().is_success())
+    }
+
+    // ブロブを 1 回のリクエストでアップロードする
+    pub async fn upload_blob(&mut self, digest: &str, data: Vec<u8>) -> Result<(), ImageError> {
+        let url = self.url("blobs/uploads/");
+        let response = self.send(|http| http.post(&url)).await?;
+        if response.status() != StatusCode::ACCEPTED {
+            return Err(self.status_error("upload", response.status()));
+        }
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| ImageError::Push("The registry did not return an upload location".to_string()))?;
+        // 相対パスで返すレジストリもある
+        let location = if location.starts_with('/') {
+            let api_url = self.reference.api_url();
+            format!("{}{}", api_url.trim_end_matches("/v2"), location)
+        } else {
+            location.to_string()
+        };
+        let separator = if location.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}digest={}", location, separator, digest);
+
+        let response = self
+            .send(|http| {
+                http.put(&url)
+                    .header(CONTENT_TYPE, "application/octet-stream")
+                    .body(data.clone())
+            })
+            .await?;
+        if response.status() != StatusCode::CREATED {
+            return Err(self.status_error("upload", response.status()));
+        }
+        Ok(())
+    }
+
+    // マニフェストをタグに登録し、マニフェストのダイジェストを返す
+    pub async fn put_manifest(&mut self, manifest: &Manifest) -> Result<String, ImageError> {
+        let body = serde_json::to_vec(manifest).map_err(|e| ImageError::Push(e.to_string()))?;
+        let digest = format!("sha256:{:x}", Sha256::digest(&body));
+        let url = self.url(&format!("manifests/{}", self.reference.reference()));
+        let media_type = manifest.media_type.clone().unwrap_or_else(|| DOCKER_MANIFEST.to_string());
+        let response = self
+            .send(|http| http.put(&url).header(CONTENT_TYPE, &media_type).body(body.clone()))
+            .await?;
+        if !response.status().is_success() {
+            return Err(self.status_error("manifest", response.status()));
+        }
+        Ok(digest)
+    }
+
+    fn status_error(&self, what: &str, status: StatusCode) -> ImageError {
+        let message = match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => format!(
+                "Access to {} denied (the repository does not exist or requires authentication)",
+                self.reference.familiar_name()
+            ),
+            status => format!("Unexpected response for {} of {}: {}", what, self.reference.familiar_name(), status),
+        };
+        ImageError::Registry(message)
+    }
+}
+
+// key="value",key="value" を分解する
+fn parse_challenge(params: &str) -> Vec<(String, String)> {
+    let mut result = Vec::new();
+    let mut rest = params.trim();
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some((value, next)) => (value, next),
+                None => (quoted, ""),
+            },
+            None => value.split_once(',').map_or((value, ""), |(value, next)| (value, next)),
+        };
+        result.push((key, value.to_string()));
+        rest = next.trim_start_matches(',');
+    }
+    result
+}
+
+fn parse_manifest(body: &[u8]) -> Result<Manifest, ImageError> {
+    let manifest: Manifest =
+        serde_json::from_slice(body).map_err(|e| ImageError::Registry(format!("Invalid manifest: {}", e)))?;
+    if manifest.schema_version != 2 {
+        return Err(ImageError::Registry(format!(
+            "Unsupported manifest schema version {}",
+            manifest.schema_version
+        )));
+    }
+    Ok(manifest)
+}
+
+fn verify_digest(expected: &str, actual: &str) -> Result<(), ImageError> {
+    if expected != actual {
+        return Err(ImageError::Registry(format!(
+            "Digest mismatch (expected {}, got {})",
+            expected, actual
+        )));
+    }
+    Ok(())
+}
+
+// レジストリで使うこのホストのアーキテクチャ名
+pub fn host_architecture() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "powerpc64" => "ppc64le",
+        arch => arch,
+    }
+}
@@ -0,0 +1,53 @@
+use rocker_core::{Image, ImageError, RockerError};
+use std::collections::HashSet;
+use tracing::{info, warn};
+
+use super::Manager;
+
+impl Manager {
+    // イメージを削除する（used_by はこのイメージから作ったコンテナの名前）
+    //
+    // コンテナの rootfs はレイヤーをコピーして作るため、削除できないのはコンテナが残っている場合だけ。
+    // 他のイメージが使っていないレイヤーも合わせて削除する。
+    pub async fn remove(&self, id_or_name: &str, used_by: &[String]) -> Result<Image, RockerError> {
+        let image = self.get(id_or_name)?;
+        if !used_by.is_empty() {
+            return Err(ImageError::Remove(format!(
+                "image {} is being used by container {}",
+                id_or_name,
+                used_by.join(", ")
+            ))
+            .into());
+        }
+
+        let unused_layers: Vec<_> = {
+            let mut images = self.images.lock().unwrap();
+            images.remove(&image.id);
+            let in_use: HashSet<&str> = images
+                .values()
+                .flat_map(|other| other.layers.iter().map(|layer| layer.diff_id.as_str()))
+                .collect();
+            image
+                .layers
+                .iter()
+                .filter(|layer| !layer.empty_layer && !in_use.contains(layer.diff_id.as_str()))
+                .map(|layer| self.layers_dir.join(layer.diff_id.trim_start_matches("sha256:")))
+                .collect()
+        };
+
+        let dir = self.state_dir.join(image.id.trim_start_matches("sha256:"));
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir).await?;
+        }
+        for layer_dir in unused_layers {
+            if layer_dir.exists() {
+                if let Err(e) = tokio::fs::remove_dir_all(&layer_dir).await {
+                    warn!("Failed to remove layer {}: {}", layer_dir.display(), e);
+                }
+            }
+        }
+
+        info!("Removed image {}", image.id);
+        Ok(image)
+    }
+}
@@ -0,0 +1,325 @@
+use chrono::Utc;
+use rocker_core::{Image, ImageError, ImageLayer, RockerError};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::Manager;
+use crate::container::{OPAQUE_WHITEOUT, WHITEOUT_PREFIX};
+
+// パッケージのデータベース
+const DPKG_STATUS: &str = "var/lib/dpkg/status";
+const APK_INSTALLED: &str = "lib/apk/db/installed";
+const OS_RELEASE: [&str; 2] = ["etc/os-release", "usr/lib/os-release"];
+
+// SBOM の形式
+#[derive(Clone, Copy)]
+pub enum SbomFormat {
+    Spdx,
+    CycloneDx,
+}
+
+impl SbomFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format {
+            "spdx" | "spdx-json" => Ok(SbomFormat::Spdx),
+            "cyclonedx" | "cyclonedx-json" => Ok(SbomFormat::CycloneDx),
+            _ => Err(format!("Unknown SBOM format (expected spdx-json or cyclonedx-json): {}", format)),
+        }
+    }
+}
+
+// イメージの OS（os-release の ID と VERSION_ID）
+struct OperatingSystem {
+    id: String,
+    version: Option<String>,
+}
+
+// パッケージマネージャがインストールしたパッケージ
+struct Package {
+    name: String,
+    version: String,
+    arch: Option<String>,
+    license: Option<String>,
+    // purl の種類（deb・apk）
+    kind: &'static str,
+}
+
+impl Package {
+    // Package URL（スキャナはこれでパッケージを特定する）
+    fn purl(&self, os: Option<&OperatingSystem>) -> String {
+        let namespace = os.map(|os| os.id.as_str()).unwrap_or(match self.kind {
+            "apk" => "alpine",
+            _ => "debian",
+        });
+        let mut purl = format!("pkg:{}/{}/{}@{}", self.kind, namespace, encode(&self.name), encode(&self.version));
+        let mut qualifiers = Vec::new();
+        if let Some(arch) = &self.arch {
+            qualifiers.push(format!("arch={}", encode(arch)));
+        }
+        if let Some(version) = os.and_then(|os| os.version.as_ref()) {
+            qualifiers.push(format!("distro={}-{}", namespace, encode(version)));
+        }
+        if !qualifiers.is_empty() {
+            purl.push('?');
+            purl.push_str(&qualifiers.join("&"));
+        }
+        purl
+    }
+}
+
+impl Manager {
+    // イメージのレイヤーのパッケージのデータベースから SBOM を作る
+    //
+    // dpkg（Debian・Ubuntu）と apk（Alpine）のデータベースを読む。rpm のデータベースは読まないため、その
+    // パッケージは含まれない。
+    pub async fn sbom(&self, id_or_name: &str, format: SbomFormat) -> Result<Value, RockerError> {
+        let image = self.get(id_or_name)?;
+        Ok(tokio::task::spawn_blocking(move || generate(&image, format)).await??)
+    }
+}
+
+fn generate(image: &Image, format: SbomFormat) -> Result<Value, ImageError> {
+    let read = |path: &str| -> Result<Option<String>, ImageError> {
+        match find_file(&image.layers, path) {
+            Some(path) => std::fs::read_to_string(&path)
+                .map(Some)
+                .map_err(|e| ImageError::Scan(format!("Failed to read {}: {}", path.display(), e))),
+            None => Ok(None),
+        }
+    };
+
+    let mut os = None;
+    for path in OS_RELEASE {
+        if let Some(content) = read(path)? {
+            os = parse_os_release(&content);
+            break;
+        }
+    }
+    let mut packages = Vec::new();
+    if let Some(content) = read(DPKG_STATUS)? {
+        packages.extend(parse_dpkg_status(&content));
+    }
+    if let Some(content) = read(APK_INSTALLED)? {
+        packages.extend(parse_apk_installed(&content));
+    }
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let name = image
+        .full_name()
+        .unwrap_or_else(|| image.id.trim_start_matches("sha256:").chars().take(12).collect());
+    Ok(match format {
+        SbomFormat::Spdx => spdx(image, &name, os.as_ref(), &packages),
+        SbomFormat::CycloneDx => cyclonedx(image, &name, os.as_ref(), &packages),
+    })
+}
+
+fn spdx(image: &Image, name: &str, os: Option<&OperatingSystem>, packages: &[Package]) -> Value {
+    let mut spdx_packages = vec![json!({
+        "SPDXID": "SPDXRef-Image",
+        "name": name,
+        "versionInfo": image.id,
+        "downloadLocation": "NONE",
+        "filesAnalyzed": false,
+        "primaryPackagePurpose": "CONTAINER",
+    })];
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": "SPDXRef-Image",
+    })];
+    if let Some(os) = os {
+        spdx_packages.push(json!({
+            "SPDXID": "SPDXRef-OperatingSystem",
+            "name": os.id,
+            "versionInfo": os.version.clone().unwrap_or_default(),
+            "downloadLocation": "NONE",
+            "filesAnalyzed": false,
+            "primaryPackagePurpose": "OPERATING-SYSTEM",
+        }));
+        relationships.push(json!({
+            "spdxElementId": "SPDXRef-Image",
+            "relationshipType": "CONTAINS",
+            "relatedSpdxElement": "SPDXRef-OperatingSystem",
+        }));
+    }
+    for (index, package) in packages.iter().enumerate() {
+        let id = format!("SPDXRef-Package-{}", index + 1);
+        spdx_packages.push(json!({
+            "SPDXID": id,
+            "name": package.name,
+            "versionInfo": package.version,
+            "downloadLocation": "NONE",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": package.license.clone().unwrap_or_else(|| "NOASSERTION".to_string()),
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": package.purl(os),
+            }],
+        }));
+        relationships.push(json!({
+            "spdxElementId": "SPDXRef-Image",
+            "relationshipType": "CONTAINS",
+            "relatedSpdxElement": id,
+        }));
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/rocker/{}-{}", encode(name), uuid::Uuid::new_v4()),
+        "creationInfo": {
+            "created": Utc::now().to_rfc3339(),
+            "creators": [format!("Tool: rocker-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": spdx_packages,
+        "relationships": relationships,
+    })
+}
+
+fn cyclonedx(image: &Image, name: &str, os: Option<&OperatingSystem>, packages: &[Package]) -> Value {
+    let mut components = Vec::new();
+    if let Some(os) = os {
+        components.push(json!({
+            "bom-ref": "operating-system",
+            "type": "operating-system",
+            "name": os.id,
+            "version": os.version.clone().unwrap_or_default(),
+        }));
+    }
+    for package in packages {
+        let purl = package.purl(os);
+        let mut component = json!({
+            "bom-ref": purl,
+            "type": "library",
+            "name": package.name,
+            "version": package.version,
+            "purl": purl,
+        });
+        if let Some(license) = &package.license {
+            component["licenses"] = json!([{ "expression": license }]);
+        }
+        components.push(component);
+    }
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+        "version": 1,
+        "metadata": {
+            "timestamp": Utc::now().to_rfc3339(),
+            "tools": {
+                "components": [{ "type": "application", "name": "rocker", "version": env!("CARGO_PKG_VERSION") }],
+            },
+            "component": {
+                "bom-ref": image.id,
+                "type": "container",
+                "name": name,
+                "version": image.id,
+            },
+        },
+        "components": components,
+    })
+}
+
+// レイヤーを上から順に探し、最も上のレイヤーにあるファイルを返す（whiteout で消されていれば None）
+fn find_file(layers: &[ImageLayer], path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    for layer in layers.iter().rev().filter(|layer| !layer.empty_layer) {
+        let candidate = layer.path.join(path);
+        // 他のレイヤーへのシンボリックリンクは辿らない
+        if std::fs::symlink_metadata(&candidate).is_ok_and(|metadata| metadata.is_file()) {
+            return Some(candidate);
+        }
+        // ファイルかその親ディレクトリが削除されたか、親ディレクトリの中身が消されていれば下のレイヤーは見ない
+        for ancestor in path.ancestors().filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+            let Some(name) = ancestor.file_name() else {
+                continue;
+            };
+            let dir = layer.path.join(ancestor.parent().unwrap_or(Path::new("")));
+            if dir.join(format!("{}{}", WHITEOUT_PREFIX, name.to_string_lossy())).exists() {
+                return None;
+            }
+            if ancestor != path && layer.path.join(ancestor).join(OPAQUE_WHITEOUT).exists() {
+                return None;
+            }
+        }
+    }
+    None
+}
+
+fn parse_os_release(content: &str) -> Option<OperatingSystem> {
+    let values: HashMap<&str, String> = content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim().trim_matches('"').to_string()))
+        .collect();
+    Some(OperatingSystem {
+        id: values.get("ID")?.clone(),
+        version: values.get("VERSION_ID").cloned(),
+    })
+}
+
+// dpkg の status ファイル（空行で区切った段落ごとに 1 パッケージ）
+fn parse_dpkg_status(content: &str) -> Vec<Package> {
+    content
+        .split("\n\n")
+        .filter_map(|paragraph| {
+            let mut fields = HashMap::new();
+            for line in paragraph.lines() {
+                // 空白で始まる行は前のフィールドの続き
+                if line.starts_with([' ', '\t']) {
+                    continue;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    fields.insert(key, value.trim());
+                }
+            }
+            // 削除済みで設定ファイルだけ残っているパッケージは除く
+            if !fields.get("Status").is_some_and(|status| status.ends_with(" installed")) {
+                return None;
+            }
+            Some(Package {
+                name: fields.get("Package")?.to_string(),
+                version: fields.get("Version")?.to_string(),
+                arch: fields.get("Architecture").map(|arch| arch.to_string()),
+                license: None,
+                kind: "deb",
+            })
+        })
+        .collect()
+}
+
+// apk の installed ファイル（"P:名前" などの行を空行で区切る）
+fn parse_apk_installed(content: &str) -> Vec<Package> {
+    content
+        .split("\n\n")
+        .filter_map(|entry| {
+            let fields: HashMap<&str, &str> = entry.lines().filter_map(|line| line.split_once(':')).collect();
+            Some(Package {
+                name: fields.get("P")?.to_string(),
+                version: fields.get("V")?.to_string(),
+                arch: fields.get("A").map(|arch| arch.to_string()),
+                license: fields.get("L").map(|license| license.to_string()),
+                kind: "apk",
+            })
+        })
+        .collect()
+}
+
+// purl と URI に使えない文字をパーセントエンコードする
+fn encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
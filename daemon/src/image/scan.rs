@@ -0,0 +1,134 @@
+use chrono::Utc;
+use rocker_core::{ImageError, ImageScan, RockerError, Vulnerability};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+use tracing::info;
+
+use super::sbom::SbomFormat;
+use super::Manager;
+use crate::DAEMON_CONFIG_PATH;
+
+#[derive(Deserialize)]
+struct DaemonConfig {
+    // trivy と同じインターフェースのスキャナの実行ファイル
+    #[serde(rename = "image-scanner")]
+    image_scanner: Option<PathBuf>,
+}
+
+// trivy の JSON レポート（使う部分のみ）
+#[derive(Deserialize)]
+struct Report {
+    #[serde(rename = "Results", default)]
+    results: Vec<ReportResult>,
+}
+
+#[derive(Deserialize)]
+struct ReportResult {
+    #[serde(rename = "Vulnerabilities", default)]
+    vulnerabilities: Vec<ReportVulnerability>,
+}
+
+#[derive(Deserialize)]
+struct ReportVulnerability {
+    #[serde(rename = "VulnerabilityID")]
+    id: String,
+    #[serde(rename = "PkgName")]
+    package: String,
+    #[serde(rename = "InstalledVersion", default)]
+    installed_version: String,
+    #[serde(rename = "FixedVersion", default)]
+    fixed_version: Option<String>,
+    #[serde(rename = "Severity", default)]
+    severity: String,
+    #[serde(rename = "Title", default)]
+    title: Option<String>,
+}
+
+impl Manager {
+    // イメージの CycloneDX の SBOM をスキャナに渡して脆弱性を調べ、結果をイメージのメタデータに保存する
+    //
+    // スキャナはデーモンの設定ファイルの image-scanner で指定し、trivy と同じく
+    // `<scanner> sbom --format json --quiet <file>` で呼び出して JSON のレポートを受け取る。
+    pub async fn scan(&self, id_or_name: &str) -> Result<ImageScan, RockerError> {
+        let scanner = configured_scanner()?;
+        let image = self.get(id_or_name)?;
+
+        let document = self.sbom(&image.id, SbomFormat::CycloneDx).await?;
+        tokio::fs::create_dir_all(&self.build_dir).await?;
+        let sbom_path = self
+            .build_dir
+            .join(format!("sbom-{}.cdx.json", image.id.trim_start_matches("sha256:")));
+        tokio::fs::write(&sbom_path, serde_json::to_vec(&document)?).await?;
+
+        info!("Scanning image {} with {}", image.id, scanner.display());
+        let output = Command::new(&scanner)
+            .args(["sbom", "--format", "json", "--quiet"])
+            .arg(&sbom_path)
+            .stdin(Stdio::null())
+            .output()
+            .await;
+        let _ = tokio::fs::remove_file(&sbom_path).await;
+        let output = output.map_err(|e| ImageError::Scan(format!("Failed to run {}: {}", scanner.display(), e)))?;
+        if !output.status.success() {
+            return Err(ImageError::Scan(format!(
+                "{} exited with {}: {}",
+                scanner.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+            .into());
+        }
+        let report: Report = serde_json::from_slice(&output.stdout)
+            .map_err(|e| ImageError::Scan(format!("Invalid report from {}: {}", scanner.display(), e)))?;
+
+        let scan = ImageScan {
+            scanner: scanner_name(&scanner),
+            scanned_at: Utc::now(),
+            vulnerabilities: report
+                .results
+                .into_iter()
+                .flat_map(|result| result.vulnerabilities)
+                .map(|vulnerability| Vulnerability {
+                    id: vulnerability.id,
+                    package: vulnerability.package,
+                    installed_version: vulnerability.installed_version,
+                    fixed_version: vulnerability.fixed_version.filter(|version| !version.is_empty()),
+                    severity: vulnerability.severity,
+                    title: vulnerability.title,
+                })
+                .collect(),
+        };
+        info!("Found {} vulnerabilities in image {}", scan.vulnerabilities.len(), image.id);
+
+        // スキャンの間に削除されたイメージには保存しない
+        let stored = match self.images.lock().unwrap().get_mut(&image.id) {
+            Some(stored) => {
+                stored.scan = Some(scan.clone());
+                true
+            }
+            None => false,
+        };
+        if stored {
+            self.save(&image.id).await?;
+        }
+        Ok(scan)
+    }
+}
+
+fn configured_scanner() -> Result<PathBuf, ImageError> {
+    let not_configured =
+        || ImageError::Scan(format!("No image scanner configured (set image-scanner in {})", DAEMON_CONFIG_PATH));
+    let content = std::fs::read_to_string(DAEMON_CONFIG_PATH).map_err(|_| not_configured())?;
+    let config: DaemonConfig = serde_json::from_str(&content)
+        .map_err(|e| ImageError::Scan(format!("Invalid {}: {}", DAEMON_CONFIG_PATH, e)))?;
+    config.image_scanner.ok_or_else(not_configured)
+}
+
+fn scanner_name(scanner: &Path) -> String {
+    scanner
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| scanner.display().to_string())
+}
@@ -0,0 +1,116 @@
+use chrono::Utc;
+use rocker_core::{
+    calculate_string_hash, hash_dir, Image, ImageError, ImageLayer, ProgressMessage, RegistryReference, RockerError,
+};
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tracing::info;
+
+use super::Manager;
+use crate::container::create_rootfs;
+
+impl Manager {
+    // イメージのレイヤーを 1 つにまとめたイメージを作って登録する（設定はそのまま）
+    //
+    // tag が無ければ元のイメージの repo:tag を新しいイメージに付け替える。元のイメージとレイヤーは残す。
+    pub async fn squash(
+        &self,
+        id_or_name: &str,
+        tag: Option<&str>,
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Image, RockerError> {
+        let image = self.get(id_or_name)?;
+        let (repo, tag) = match tag {
+            Some(tag) => {
+                let reference = RegistryReference::parse(tag).map_err(ImageError::Reference)?;
+                (Some(reference.familiar_name()), reference.tag)
+            }
+            None => (image.repo.clone(), image.tag.clone()),
+        };
+
+        let layers = self.squash_layers(&image.layers, progress).await?;
+        let diff_ids: Vec<&str> = layers
+            .iter()
+            .filter(|layer| !layer.empty_layer)
+            .map(|layer| layer.diff_id.as_str())
+            .collect();
+        let id = calculate_string_hash(&format!("{}\n{}", serde_json::to_string(&image.config)?, diff_ids.join("\n")));
+        let squashed = Image {
+            id,
+            repo,
+            tag,
+            created_at: Utc::now(),
+            size: layers.iter().map(|layer| layer.size).sum(),
+            layers,
+            labels: image.labels.clone(),
+            config: image.config.clone(),
+            // 元のイメージのレイヤーを共有しないため、親のイメージも持たない
+            parent_id: None,
+            scan: None,
+            repo_digests: Vec::new(),
+        };
+        let squashed = self.store(squashed).await?;
+
+        info!("Squashed image {} into {}", image.id, squashed.id);
+        let _ = progress.send(ProgressMessage::status(format!(
+            "Squashed {} into {}",
+            image.id.trim_start_matches("sha256:").chars().take(12).collect::<String>(),
+            squashed.id.trim_start_matches("sha256:").chars().take(12).collect::<String>()
+        )));
+        Ok(squashed)
+    }
+
+    // レイヤーを重ねた rootfs を 1 つのレイヤーにし、履歴は空のレイヤーとして残す
+    pub(super) async fn squash_layers(
+        &self,
+        layers: &[ImageLayer],
+        progress: &mpsc::UnboundedSender<ProgressMessage>,
+    ) -> Result<Vec<ImageLayer>, RockerError> {
+        let count = layers.iter().filter(|layer| !layer.empty_layer).count();
+        let _ = progress.send(ProgressMessage::status(format!("Squashing {} layers", count)));
+
+        // 重ねた結果を diff ID が分かるまで一時ディレクトリに置き、レイヤーのディレクトリに移動する
+        tokio::fs::create_dir_all(&self.layers_dir).await?;
+        let staging_dir = self.layers_dir.join(format!("tmp-{}", uuid::Uuid::new_v4()));
+        let packed = match create_rootfs(&staging_dir, layers).await {
+            Ok(()) => hash_dir(&staging_dir).await.map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let (diff_id, size) = match packed {
+            Ok(packed) => packed,
+            Err(e) => {
+                let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+                return Err(ImageError::Build(format!("Failed to squash layers: {}", e)).into());
+            }
+        };
+        let layer_dir = self.layers_dir.join(diff_id.trim_start_matches("sha256:"));
+        if layer_dir.exists() {
+            tokio::fs::remove_dir_all(&staging_dir).await?;
+        } else {
+            tokio::fs::rename(&staging_dir, &layer_dir).await?;
+        }
+
+        let mut squashed: Vec<ImageLayer> = layers
+            .iter()
+            .map(|layer| ImageLayer {
+                id: String::new(),
+                diff_id: String::new(),
+                size: 0,
+                path: PathBuf::new(),
+                created_at: layer.created_at,
+                created_by: layer.created_by.clone(),
+                empty_layer: true,
+            })
+            .collect();
+        squashed.push(ImageLayer {
+            id: diff_id.clone(),
+            diff_id,
+            size,
+            path: layer_dir,
+            created_at: Utc::now(),
+            created_by: Some(format!("squash of {} layers", count)),
+            empty_layer: false,
+        });
+        Ok(squashed)
+    }
+}
@@ -0,0 +1,133 @@
+use async_trait::async_trait;
+use rocker_core::{Container, ContainerError, LogRecord, LogStream};
+use std::collections::HashMap;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+
+use super::remote::{self, Forwarder, Transport};
+
+const DEFAULT_ADDRESS: &str = "localhost:24224";
+
+// fluentd のオプション
+pub(super) struct Options {
+    // host:port（tcp:// を付けてもよい）
+    address: String,
+    // 既定はコンテナ ID の先頭 12 文字
+    tag: Option<String>,
+    buffer_limit: usize,
+}
+
+impl Options {
+    pub(super) fn parse(options: &HashMap<String, String>) -> Result<Self, ContainerError> {
+        for key in options.keys() {
+            if !matches!(key.as_str(), "address" | "tag" | "buffer-limit") {
+                return Err(ContainerError::InvalidLogConfig(format!(
+                    "Unknown log option {} for driver fluentd",
+                    key
+                )));
+            }
+        }
+        let address = match options.get("address") {
+            Some(address) => {
+                let address = address.strip_prefix("tcp://").unwrap_or(address);
+                if !address.rsplit_once(':').is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok()) {
+                    return Err(ContainerError::InvalidLogConfig(format!(
+                        "Invalid fluentd address (expected host:port): {}",
+                        address
+                    )));
+                }
+                address.to_string()
+            }
+            None => DEFAULT_ADDRESS.to_string(),
+        };
+        Ok(Options {
+            address,
+            tag: options.get("tag").cloned(),
+            buffer_limit: remote::buffer_limit(options)?,
+        })
+    }
+}
+
+// Forward プロトコルの Message モード（[tag, time, record] の MessagePack）で fluentd に送る
+struct Fluentd {
+    options: Options,
+    tag: String,
+    container_id: String,
+    container_name: String,
+    stream: Option<TcpStream>,
+}
+
+pub(super) fn open(options: Options, container: &Container) -> Forwarder {
+    let tag = options.tag.clone().unwrap_or_else(|| container.id.chars().take(12).collect());
+    let buffer_limit = options.buffer_limit;
+    let transport = Fluentd {
+        options,
+        tag,
+        container_id: container.id.clone(),
+        container_name: container.name.clone(),
+        stream: None,
+    };
+    Forwarder::spawn("fluentd", &container.id, transport, buffer_limit)
+}
+
+#[async_trait]
+impl Transport for Fluentd {
+    async fn connect(&mut self) -> std::io::Result<()> {
+        self.stream = Some(TcpStream::connect(&self.options.address).await?);
+        Ok(())
+    }
+
+    async fn send(&mut self, record: &LogRecord) -> std::io::Result<()> {
+        let message = self.encode(record);
+        let stream = self.stream.as_mut().ok_or(std::io::ErrorKind::NotConnected)?;
+        let result = stream.write_all(&message).await;
+        if result.is_err() {
+            self.stream = None;
+        }
+        result
+    }
+}
+
+impl Fluentd {
+    fn encode(&self, record: &LogRecord) -> Vec<u8> {
+        let source = match record.stream {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        };
+        let mut message = Vec::new();
+        message.push(0x93);
+        write_str(&mut message, &self.tag);
+        // EventTime（拡張型 0、秒とナノ秒の 32 ビット整数）
+        message.extend_from_slice(&[0xd7, 0x00]);
+        message.extend_from_slice(&(record.time.timestamp() as u32).to_be_bytes());
+        message.extend_from_slice(&record.time.timestamp_subsec_nanos().to_be_bytes());
+        message.push(0x84);
+        for (key, value) in [
+            ("container_id", self.container_id.as_str()),
+            ("container_name", self.container_name.as_str()),
+            ("source", source),
+            ("log", record.line.as_str()),
+        ] {
+            write_str(&mut message, key);
+            write_str(&mut message, value);
+        }
+        message
+    }
+}
+
+// MessagePack の str
+fn write_str(buffer: &mut Vec<u8>, value: &str) {
+    let len = value.len();
+    if len < 32 {
+        buffer.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        buffer.extend_from_slice(&[0xd9, len as u8]);
+    } else if len <= u16::MAX as usize {
+        buffer.push(0xda);
+        buffer.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        buffer.push(0xdb);
+        buffer.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    buffer.extend_from_slice(value.as_bytes());
+}
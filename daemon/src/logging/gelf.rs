@@ -0,0 +1,190 @@
+use async_trait::async_trait;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression as Level;
+use rocker_core::{Container, ContainerError, LogRecord, LogStream};
+use std::collections::HashMap;
+use std::io::Write;
+use tokio::net::UdpSocket;
+
+use super::remote::{self, Forwarder, Transport};
+
+// 1 つのデータグラムの大きさ（超えるメッセージはチャンクに分ける）
+const MAX_DATAGRAM: usize = 1420;
+// チャンクのヘッダ（マジック 2 バイト、メッセージ ID 8 バイト、番号と総数 1 バイトずつ）
+const CHUNK_HEADER: usize = 12;
+// GELF で 1 つのメッセージに使えるチャンクの数
+const MAX_CHUNKS: usize = 128;
+
+#[derive(Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zlib,
+    None,
+}
+
+// gelf のオプション
+pub(super) struct Options {
+    // host:port
+    address: String,
+    compression: Compression,
+    tag: Option<String>,
+    buffer_limit: usize,
+}
+
+impl Options {
+    pub(super) fn parse(options: &HashMap<String, String>) -> Result<Self, ContainerError> {
+        for key in options.keys() {
+            if !matches!(key.as_str(), "address" | "compression-type" | "tag" | "buffer-limit") {
+                return Err(ContainerError::InvalidLogConfig(format!("Unknown log option {} for driver gelf", key)));
+            }
+        }
+        let address = options
+            .get("address")
+            .ok_or_else(|| ContainerError::InvalidLogConfig("The gelf logging driver requires address".to_string()))?;
+        let host_port = address.strip_prefix("udp://").unwrap_or(address);
+        if address.contains("://") && !address.starts_with("udp://")
+            || !host_port.rsplit_once(':').is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        {
+            return Err(ContainerError::InvalidLogConfig(format!(
+                "Invalid gelf address (expected udp://host:port): {}",
+                address
+            )));
+        }
+        let compression = match options.get("compression-type").map(String::as_str) {
+            None | Some("gzip") => Compression::Gzip,
+            Some("zlib") => Compression::Zlib,
+            Some("none") => Compression::None,
+            Some(other) => {
+                return Err(ContainerError::InvalidLogConfig(format!(
+                    "Invalid compression-type (expected gzip, zlib or none): {}",
+                    other
+                )))
+            }
+        };
+        Ok(Options {
+            address: host_port.to_string(),
+            compression,
+            tag: options.get("tag").cloned(),
+            buffer_limit: remote::buffer_limit(options)?,
+        })
+    }
+}
+
+// GELF 1.1 のメッセージを UDP で送る
+struct Gelf {
+    options: Options,
+    host: String,
+    tag: String,
+    container_id: String,
+    container_name: String,
+    image_id: String,
+    socket: Option<UdpSocket>,
+}
+
+pub(super) fn open(options: Options, container: &Container) -> Forwarder {
+    let host = nix::unistd::gethostname()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "localhost".to_string());
+    let tag = options.tag.clone().unwrap_or_else(|| container.id.chars().take(12).collect());
+    let buffer_limit = options.buffer_limit;
+    let transport = Gelf {
+        options,
+        host,
+        tag,
+        container_id: container.id.clone(),
+        container_name: container.name.clone(),
+        image_id: container.config.image.clone(),
+        socket: None,
+    };
+    Forwarder::spawn("gelf", &container.id, transport, buffer_limit)
+}
+
+#[async_trait]
+impl Transport for Gelf {
+    // 接続の度に名前を解決し直す
+    async fn connect(&mut self) -> std::io::Result<()> {
+        let address = tokio::net::lookup_host(&self.options.address)
+            .await?
+            .next()
+            .ok_or(std::io::ErrorKind::AddrNotAvailable)?;
+        let socket = UdpSocket::bind(if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }).await?;
+        socket.connect(address).await?;
+        self.socket = Some(socket);
+        Ok(())
+    }
+
+    async fn send(&mut self, record: &LogRecord) -> std::io::Result<()> {
+        let message = compress(self.options.compression, &self.encode(record))?;
+        let socket = self.socket.as_ref().ok_or(std::io::ErrorKind::NotConnected)?;
+        let mut result = Ok(());
+        for datagram in chunks(&message) {
+            result = socket.send(&datagram).await.map(|_| ());
+            if result.is_err() {
+                break;
+            }
+        }
+        if result.is_err() {
+            self.socket = None;
+        }
+        result
+    }
+}
+
+impl Gelf {
+    fn encode(&self, record: &LogRecord) -> Vec<u8> {
+        // syslog の重要度（stdout は info、stderr は error）
+        let level = match record.stream {
+            LogStream::Stdout => 6,
+            LogStream::Stderr => 3,
+        };
+        let message = serde_json::json!({
+            "version": "1.1",
+            "host": self.host,
+            "short_message": record.line,
+            "timestamp": record.time.timestamp_millis() as f64 / 1000.0,
+            "level": level,
+            "_container_id": self.container_id,
+            "_container_name": self.container_name,
+            "_image_id": self.image_id,
+            "_tag": self.tag,
+        });
+        serde_json::to_vec(&message).unwrap_or_default()
+    }
+}
+
+fn compress(compression: Compression, message: &[u8]) -> std::io::Result<Vec<u8>> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+            encoder.write_all(message)?;
+            encoder.finish()
+        }
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Level::default());
+            encoder.write_all(message)?;
+            encoder.finish()
+        }
+        Compression::None => Ok(message.to_vec()),
+    }
+}
+
+// データグラムに収まらないメッセージをチャンクに分ける（チャンクの数の上限を超える分は送らない）
+fn chunks(message: &[u8]) -> Vec<Vec<u8>> {
+    if message.len() <= MAX_DATAGRAM {
+        return vec![message.to_vec()];
+    }
+    let id = uuid::Uuid::new_v4();
+    let parts: Vec<&[u8]> = message.chunks(MAX_DATAGRAM - CHUNK_HEADER).take(MAX_CHUNKS).collect();
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let mut datagram = Vec::with_capacity(CHUNK_HEADER + part.len());
+            datagram.extend_from_slice(&[0x1e, 0x0f]);
+            datagram.extend_from_slice(&id.as_bytes()[..8]);
+            datagram.extend_from_slice(&[index as u8, parts.len() as u8]);
+            datagram.extend_from_slice(part);
+            datagram
+        })
+        .collect()
+}
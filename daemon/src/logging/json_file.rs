@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use rocker_core::{parse_memory_size, ContainerError, LogRecord};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use super::{LogDriver, ReadOptions};
+
+// json-file のオプション（max-file は max-size を超えたときに残すファイルの数）
+pub(super) struct Options {
+    max_size: Option<u64>,
+    max_file: usize,
+}
+
+impl Options {
+    pub(super) fn parse(options: &HashMap<String, String>) -> Result<Self, ContainerError> {
+        let mut parsed = Options {
+            max_size: None,
+            max_file: 1,
+        };
+        for (key, value) in options {
+            match key.as_str() {
+                "max-size" => {
+                    let size = parse_memory_size(value)
+                        .ok()
+                        .filter(|size| *size > 0)
+                        .ok_or_else(|| ContainerError::InvalidLogConfig(format!("Invalid max-size: {}", value)))?;
+                    parsed.max_size = Some(size);
+                }
+                "max-file" => {
+                    parsed.max_file = value
+                        .parse()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or_else(|| ContainerError::InvalidLogConfig(format!("Invalid max-file: {}", value)))?;
+                }
+                _ => {
+                    return Err(ContainerError::InvalidLogConfig(format!(
+                        "Unknown log option {} for driver json-file",
+                        key
+                    )))
+                }
+            }
+        }
+        if parsed.max_file > 1 && parsed.max_size.is_none() {
+            return Err(ContainerError::InvalidLogConfig("max-file requires max-size".to_string()));
+        }
+        Ok(parsed)
+    }
+}
+
+// 1 行に 1 つの LogRecord の JSON を書くファイル
+//
+// max-size を超える行を書く前に、ファイルを <path>.1・<path>.2 ... にずらして max-file 個まで残す
+// （max-file が 1 の場合は空にする）。
+pub(super) struct JsonFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    options: Options,
+}
+
+impl JsonFile {
+    pub(super) fn open(path: &Path, options: Options) -> Result<Self, ContainerError> {
+        let file = open_append(path)
+            .map_err(|e| ContainerError::Logs(format!("Failed to open {}: {}", path.display(), e)))?;
+        // デーモンの再起動後も同じファイルに書き足す
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        Ok(JsonFile {
+            path: path.to_path_buf(),
+            file,
+            size,
+            options,
+        })
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.options.max_file > 1 {
+            for index in (1..self.options.max_file).rev() {
+                let from = if index == 1 {
+                    self.path.clone()
+                } else {
+                    rotated_path(&self.path, index - 1)
+                };
+                if from.exists() {
+                    std::fs::rename(&from, rotated_path(&self.path, index))?;
+                }
+            }
+            self.file = open_append(&self.path)?;
+        } else {
+            self.file.set_len(0)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl LogDriver for JsonFile {
+    fn log(&mut self, record: &LogRecord) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        if let Some(max_size) = self.options.max_size {
+            if self.size > 0 && self.size + line.len() as u64 > max_size {
+                self.rotate()?;
+            }
+        }
+        self.file.write_all(&line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), index))
+}
+
+// ずらしたファイルを含めて古い順にログを読む（範囲の指定は ReadOptions）
+//
+// 行は時刻順に並んでいるので、since は各ファイルを二分探索して読み始める位置を決め、until を過ぎた行が
+// 出たところで読むのをやめる。
+pub(super) fn read(path: &Path, options: &ReadOptions) -> std::io::Result<Vec<LogRecord>> {
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|index| rotated_path(path, index))
+        .take_while(|path| path.exists())
+        .collect();
+    files.reverse();
+    files.push(path.to_path_buf());
+
+    let mut records = VecDeque::new();
+    for file in files {
+        let file = match File::open(&file) {
+            Ok(file) => file,
+            // 読み始める前にずらされたファイルは飛ばす
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let mut reader = BufReader::new(file);
+        let start = match options.since {
+            Some(since) => seek_since(&mut reader, since)?,
+            None => 0,
+        };
+        reader.seek(SeekFrom::Start(start))?;
+
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let Some(record) = parse_line(&line) else {
+                continue;
+            };
+            if options.until.is_some_and(|until| record.time > until) {
+                return Ok(records.into());
+            }
+            if options.since.is_some_and(|since| record.time < since) {
+                continue;
+            }
+            records.push_back(record);
+            if options.tail.is_some_and(|tail| records.len() > tail) {
+                records.pop_front();
+            }
+        }
+    }
+    Ok(records.into())
+}
+
+fn parse_line(line: &[u8]) -> Option<LogRecord> {
+    serde_json::from_slice(line.strip_suffix(b"\n").unwrap_or(line)).ok()
+}
+
+// 時刻が since 以降の最初の行の位置
+fn seek_since(reader: &mut BufReader<File>, since: DateTime<Utc>) -> std::io::Result<u64> {
+    let len = reader.get_ref().metadata()?.len();
+    // position 以降で最初に始まる行が since 以降（または行が無い）になる最小の position を探す
+    let mut low = 0;
+    let mut high = len;
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let (start, line) = line_from(reader, middle)?;
+        match parse_line(&line) {
+            Some(record) if record.time < since => low = start + 1,
+            // 壊れた行は読み飛ばせるよう since 以降とみなす
+            _ => high = middle,
+        }
+    }
+    Ok(line_from(reader, low)?.0)
+}
+
+// position 以降で最初に始まる行の位置と内容（無ければファイルの末尾と空の行）
+fn line_from(reader: &mut BufReader<File>, position: u64) -> std::io::Result<(u64, Vec<u8>)> {
+    let mut start = position;
+    if position > 0 {
+        // 直前の位置から改行までを読み飛ばす（直前が改行なら position が行の先頭）
+        reader.seek(SeekFrom::Start(position - 1))?;
+        let mut skipped = Vec::new();
+        start = position - 1 + reader.read_until(b'\n', &mut skipped)? as u64;
+    } else {
+        reader.seek(SeekFrom::Start(0))?;
+    }
+    let mut line = Vec::new();
+    reader.read_until(b'\n', &mut line)?;
+    Ok((start, line))
+}
@@ -0,0 +1,286 @@
+use chrono::{DateTime, Utc};
+use rocker_core::{Container, ContainerError, LogConfig, LogRecord, LogStream, DEFAULT_LOG_DRIVER};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::unix::pipe;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, warn};
+
+use crate::DAEMON_CONFIG_PATH;
+
+mod fluentd;
+mod gelf;
+mod json_file;
+mod remote;
+
+// logs --follow の受信側が読み遅れても溜めておく行数
+const FOLLOW_BUFFER: usize = 1024;
+
+// 改行が無くてもここで区切る 1 行の長さ
+const MAX_LINE: usize = 16 * 1024;
+
+// コンテナの出力の書き込み先
+pub trait LogDriver: Send {
+    // 1 行を書き込む
+    fn log(&mut self, record: &LogRecord) -> std::io::Result<()>;
+
+    // 出力が閉じた後に呼ばれる（溜めている行があれば書き出す）
+    fn close(&mut self) {}
+}
+
+// 出力を捨てるドライバ（none）
+struct Discard;
+
+impl LogDriver for Discard {
+    fn log(&mut self, _record: &LogRecord) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct DaemonConfig {
+    #[serde(rename = "log-driver")]
+    log_driver: Option<String>,
+    #[serde(rename = "log-opts", default)]
+    log_opts: HashMap<String, String>,
+}
+
+// デーモンの設定ファイルからログドライバの既定値を読む（無ければ json-file）
+pub fn load_default_config() -> LogConfig {
+    let content = match std::fs::read_to_string(DAEMON_CONFIG_PATH) {
+        Ok(content) => content,
+        Err(_) => return LogConfig::default(),
+    };
+    let config = match serde_json::from_str::<DaemonConfig>(&content) {
+        Ok(config) => LogConfig {
+            driver: config.log_driver.unwrap_or_else(|| DEFAULT_LOG_DRIVER.to_string()),
+            options: config.log_opts,
+        },
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", DAEMON_CONFIG_PATH, e);
+            return LogConfig::default();
+        }
+    };
+    if let Err(e) = validate(&config) {
+        warn!("Ignoring the log configuration in {}: {}", DAEMON_CONFIG_PATH, e);
+        return LogConfig::default();
+    }
+
+    info!("Using the {} logging driver by default", config.driver);
+    config
+}
+
+// ドライバ名とオプションを確認する（コンテナの作成時に誤りを返すため）
+pub fn validate(config: &LogConfig) -> Result<(), ContainerError> {
+    match config.driver.as_str() {
+        "json-file" => json_file::Options::parse(&config.options).map(|_| ()),
+        "fluentd" => fluentd::Options::parse(&config.options).map(|_| ()),
+        "gelf" => gelf::Options::parse(&config.options).map(|_| ()),
+        "none" => match config.options.keys().next() {
+            Some(key) => Err(ContainerError::InvalidLogConfig(format!("Unknown log option {} for driver none", key))),
+            None => Ok(()),
+        },
+        driver => Err(ContainerError::InvalidLogConfig(format!("Unknown logging driver: {}", driver))),
+    }
+}
+
+// コンテナのログドライバを開く（dir はコンテナの状態ディレクトリ）
+pub fn open(config: &LogConfig, dir: &Path, container: &Container) -> Result<Box<dyn LogDriver>, ContainerError> {
+    match config.driver.as_str() {
+        "json-file" => {
+            let options = json_file::Options::parse(&config.options)?;
+            Ok(Box::new(json_file::JsonFile::open(&log_path(dir, &container.id), options)?))
+        }
+        "fluentd" => Ok(Box::new(fluentd::open(fluentd::Options::parse(&config.options)?, container))),
+        "gelf" => Ok(Box::new(gelf::open(gelf::Options::parse(&config.options)?, container))),
+        "none" => Ok(Box::new(Discard)),
+        driver => Err(ContainerError::InvalidLogConfig(format!("Unknown logging driver: {}", driver))),
+    }
+}
+
+fn log_path(dir: &Path, container_id: &str) -> PathBuf {
+    dir.join(format!("{}-json.log", container_id))
+}
+
+// 動作中のコンテナごとの、logs --follow に出力を送る送信側
+#[derive(Clone, Default)]
+pub struct Followers(Arc<std::sync::Mutex<HashMap<String, Arc<broadcast::Sender<LogRecord>>>>>);
+
+impl Followers {
+    fn subscribe(&self, container_id: &str) -> Option<broadcast::Receiver<LogRecord>> {
+        let senders = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        senders.get(container_id).map(|sender| sender.subscribe())
+    }
+}
+
+// コンテナの標準出力・標準エラー出力を 1 行ずつドライバに書き込み、logs --follow にも送る
+//
+// コンテナのプロセスが全て終了して両方の出力が閉じるとドライバを閉じ、follow も終わる。
+pub fn attach(
+    container_id: &str,
+    mut driver: Box<dyn LogDriver>,
+    stdout: OwnedFd,
+    stderr: OwnedFd,
+    followers: &Followers,
+) -> Result<(), ContainerError> {
+    let stdout = pipe::Receiver::from_owned_fd(stdout).map_err(|e| ContainerError::Logs(e.to_string()))?;
+    let stderr = pipe::Receiver::from_owned_fd(stderr).map_err(|e| ContainerError::Logs(e.to_string()))?;
+
+    let sender = Arc::new(broadcast::channel(FOLLOW_BUFFER).0);
+    followers
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(container_id.to_string(), Arc::clone(&sender));
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tokio::spawn(read_lines(stdout, LogStream::Stdout, tx.clone()));
+    tokio::spawn(read_lines(stderr, LogStream::Stderr, tx));
+
+    let container_id = container_id.to_string();
+    let followers = followers.clone();
+    tokio::spawn(async move {
+        let mut failed = false;
+        while let Some(record) = rx.recv().await {
+            // 書き込めなくなってもコンテナが止まらないよう出力は読み続ける
+            if let Err(e) = driver.log(&record) {
+                if !failed {
+                    warn!("Failed to write logs of container {}: {}", container_id, e);
+                }
+                failed = true;
+            } else {
+                failed = false;
+            }
+            let _ = sender.send(record);
+        }
+        driver.close();
+
+        // 再起動したコンテナの送信側は残す
+        let mut senders = followers.0.lock().unwrap_or_else(|e| e.into_inner());
+        if senders.get(&container_id).is_some_and(|current| Arc::ptr_eq(current, &sender)) {
+            senders.remove(&container_id);
+        }
+    });
+    Ok(())
+}
+
+// 出力を 1 行ずつ LogRecord にして送る（UTF-8 でないバイトは置き換え、長すぎる行は分ける）
+async fn read_lines(reader: pipe::Receiver, stream: LogStream, output: mpsc::UnboundedSender<LogRecord>) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let send = |line: &mut Vec<u8>| {
+        let text = String::from_utf8_lossy(line.strip_suffix(b"\n").unwrap_or(line)).into_owned();
+        line.clear();
+        let _ = output.send(LogRecord {
+            stream,
+            time: Utc::now(),
+            line: text,
+        });
+    };
+    loop {
+        let buffer = match reader.fill_buf().await {
+            Ok(buffer) if !buffer.is_empty() => buffer,
+            _ => break,
+        };
+        let wanted = MAX_LINE - line.len();
+        let (taken, complete) = match buffer.iter().take(wanted).position(|b| *b == b'\n') {
+            Some(end) => (end + 1, true),
+            None => (buffer.len().min(wanted), false),
+        };
+        line.extend_from_slice(&buffer[..taken]);
+        reader.consume(taken);
+        if complete || line.len() >= MAX_LINE {
+            send(&mut line);
+        }
+    }
+    if !line.is_empty() {
+        send(&mut line);
+    }
+}
+
+// logs で読む範囲
+#[derive(Default)]
+pub struct ReadOptions {
+    // 末尾の行数
+    pub tail: Option<usize>,
+    // この時刻以降の行だけ
+    pub since: Option<DateTime<Utc>>,
+    // この時刻までの行だけ（follow もこの時刻で終わる）
+    pub until: Option<DateTime<Utc>>,
+}
+
+// 保存されたログのうち options の範囲の行を送り、follow の場合は続く出力も送る
+pub fn read(
+    config: &LogConfig,
+    dir: &Path,
+    container_id: &str,
+    options: ReadOptions,
+    follow: Option<&Followers>,
+) -> Result<mpsc::UnboundedReceiver<LogRecord>, ContainerError> {
+    if config.driver != "json-file" {
+        return Err(ContainerError::Logs(format!(
+            "The {} logging driver does not support reading logs",
+            config.driver
+        )));
+    }
+    let path = log_path(dir, container_id);
+    // ファイルを読む間の出力も受け取れるよう先に購読する（until が過ぎていれば follow しない）
+    let follow = follow.filter(|_| options.until.is_none_or(|until| until > Utc::now()));
+    let mut live = follow.and_then(|followers| followers.subscribe(container_id));
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let container_id = container_id.to_string();
+    tokio::spawn(async move {
+        let until = options.until;
+        let records = tokio::task::spawn_blocking(move || json_file::read(&path, &options))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|records| records.map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                warn!("Failed to read logs of container {}: {}", container_id, e);
+                Vec::new()
+            });
+        let last = records.last().map(|record| record.time);
+        for record in records {
+            if tx.send(record).is_err() {
+                return;
+            }
+        }
+
+        let Some(live) = live.as_mut() else {
+            return;
+        };
+        loop {
+            let next = match until {
+                Some(until) => {
+                    let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+                    match tokio::time::timeout(remaining, live.recv()).await {
+                        Ok(next) => next,
+                        Err(_) => return,
+                    }
+                }
+                None => live.recv().await,
+            };
+            match next {
+                // ファイルから読んだ行は送らない
+                Ok(record) if last.is_some_and(|last| record.time <= last) => continue,
+                Ok(record) if until.is_some_and(|until| record.time > until) => return,
+                Ok(record) => {
+                    if tx.send(record).is_err() {
+                        return;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("Log follower of container {} skipped {} lines", container_id, skipped)
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    });
+    Ok(rx)
+}
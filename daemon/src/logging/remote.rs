@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use rocker_core::{ContainerError, LogRecord};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tracing::{info, warn};
+
+use super::LogDriver;
+
+// 送信先に届くまで溜めておく行数の既定値
+const DEFAULT_BUFFER_LIMIT: usize = 8192;
+
+// 再接続までの待ち時間（失敗するたびに倍にする）
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+// ネットワークのログドライバの送信先
+#[async_trait]
+pub(super) trait Transport: Send + 'static {
+    // 送信先に接続する（send に失敗した後にも呼ばれる）
+    async fn connect(&mut self) -> std::io::Result<()>;
+
+    async fn send(&mut self, record: &LogRecord) -> std::io::Result<()>;
+}
+
+// buffer-limit オプション（他のオプションの確認は各ドライバが行う）
+pub(super) fn buffer_limit(options: &HashMap<String, String>) -> Result<usize, ContainerError> {
+    match options.get("buffer-limit") {
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|limit| *limit > 0)
+            .ok_or_else(|| ContainerError::InvalidLogConfig(format!("Invalid buffer-limit: {}", value))),
+        None => Ok(DEFAULT_BUFFER_LIMIT),
+    }
+}
+
+// 行をバッファに溜め、バックグラウンドのタスクで送信先に送るドライバ
+//
+// 送信先に接続できない間は buffer-limit 行まで溜めて再接続を繰り返し、溢れた行は捨てる。送信に失敗した
+// 行は再接続してから送り直す。コンテナの出力が閉じた後は、接続できなくなった時点で残りの行を捨てる。
+pub(super) struct Forwarder {
+    tx: Option<mpsc::Sender<LogRecord>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl Forwarder {
+    pub(super) fn spawn<T: Transport>(driver: &'static str, container_id: &str, transport: T, buffer_limit: usize) -> Self {
+        let (tx, rx) = mpsc::channel(buffer_limit);
+        let closed = Arc::new(AtomicBool::new(false));
+        tokio::spawn(forward(driver, container_id.to_string(), transport, rx, Arc::clone(&closed)));
+        Forwarder { tx: Some(tx), closed }
+    }
+}
+
+impl LogDriver for Forwarder {
+    fn log(&mut self, record: &LogRecord) -> std::io::Result<()> {
+        let Some(tx) = &self.tx else {
+            return Err(std::io::ErrorKind::BrokenPipe.into());
+        };
+        match tx.try_send(record.clone()) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => Err(std::io::Error::new(
+                std::io::ErrorKind::WouldBlock,
+                "the log buffer is full, dropping lines",
+            )),
+            Err(TrySendError::Closed(_)) => Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn close(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.tx = None;
+    }
+}
+
+async fn forward<T: Transport>(
+    driver: &'static str,
+    container_id: String,
+    mut transport: T,
+    mut rx: mpsc::Receiver<LogRecord>,
+    closed: Arc<AtomicBool>,
+) {
+    let mut connected = false;
+    let mut backoff = MIN_BACKOFF;
+    while let Some(record) = rx.recv().await {
+        loop {
+            if !connected {
+                if let Err(e) = transport.connect().await {
+                    if closed.load(Ordering::SeqCst) {
+                        warn!(
+                            "Dropping the remaining logs of container {}: {} is unreachable: {}",
+                            container_id, driver, e
+                        );
+                        return;
+                    }
+                    // 失敗が続く間は最初の 1 回だけ警告する
+                    if backoff == MIN_BACKOFF {
+                        warn!("Failed to connect {} logging of container {}: {}", driver, container_id, e);
+                    }
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+                if backoff != MIN_BACKOFF {
+                    info!("Reconnected {} logging of container {}", driver, container_id);
+                }
+                connected = true;
+                backoff = MIN_BACKOFF;
+            }
+            match transport.send(&record).await {
+                Ok(()) => break,
+                Err(e) => {
+                    warn!("Failed to send logs of container {} to {}, reconnecting: {}", container_id, driver, e);
+                    connected = false;
+                }
+            }
+        }
+    }
+}
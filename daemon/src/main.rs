@@ -1,98 +1,362 @@
+use clap::Parser;
+use rocker_core::{Container, ContainerConfig, RockerError};
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::Mutex;
-use tracing::{info, error};
+use tracing::{info, error, Level};
 
 mod api;
+mod auto_update;
 mod container;
+mod events;
 mod image;
+mod logging;
 mod network;
+mod proxy;
+mod reflink;
+mod resources;
+mod schedule;
+mod secret;
+mod swarm;
 mod volume;
-mod utils;
+
+// デーモンの設定ファイル（log-driver・log-opts でログドライバの既定値、image-scanner でスキャナを指定する）
+const DAEMON_CONFIG_PATH: &str = "/etc/rocker/daemon.json";
+
+// 状態を保存するディレクトリと API の Unix ソケットの既定値
+const DEFAULT_DATA_ROOT: &str = "/var/lib/rocker";
+const DEFAULT_SOCKET_PATH: &str = "/var/run/rocker.sock";
+// --dry-run の状態を保存する一時ディレクトリの名前（本物のコンテナの状態と混ざらないよう分ける）
+const DRY_RUN_DATA_ROOT: &str = "rocker-dry-run";
+
+// rockerd のコマンドライン
+#[derive(Parser)]
+#[command(name = "rockerd", version, about = "Rocker container engine daemon")]
+struct Options {
+    /// Directory to store the state in (default /var/lib/rocker, or rocker-dry-run in the temporary directory with --dry-run)
+    #[arg(long)]
+    data_root: Option<PathBuf>,
+
+    /// Unix socket to serve the API on (default /var/run/rocker.sock, or rocker.sock in the data root with --dry-run)
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// Directory of network driver plugin sockets, <driver>.sock (default /run/rocker/plugins, or plugins in the data root with --dry-run)
+    #[arg(long)]
+    plugin_dir: Option<PathBuf>,
+
+    /// Simulate containers without creating namespaces, cgroups, mounts, network devices or firewall rules (required on systems other than Linux)
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Log debug messages (same as --log-level debug)
+    #[arg(short = 'D', long)]
+    debug: bool,
+
+    /// Least severe level of messages to log: error, warn, info, debug or trace
+    #[arg(short = 'l', long, default_value = "info")]
+    log_level: Level,
+}
 
 // デーモンの状態を管理する構造体
 struct RockerDaemon {
+    events: events::EventBus,
     container_manager: container::Manager,
     image_manager: image::Manager,
     network_manager: network::Manager,
     volume_manager: volume::Manager,
+    secret_manager: secret::Manager,
+    schedule_manager: schedule::Manager,
+    swarm_manager: swarm::Manager,
 }
 
 impl RockerDaemon {
-    fn new() -> Self {
+    fn new(data_root: &Path, dry_run: bool) -> Self {
+        let events = events::EventBus::new();
         RockerDaemon {
-            container_manager: container::Manager::new(),
-            image_manager: image::Manager::new(),
-            network_manager: network::Manager::new(),
-            volume_manager: volume::Manager::new(),
+            container_manager: container::Manager::new(events.clone(), data_root, dry_run),
+            image_manager: image::Manager::new(data_root, dry_run),
+            network_manager: network::Manager::new(data_root, dry_run),
+            volume_manager: volume::Manager::new(data_root, dry_run),
+            secret_manager: secret::Manager::new(data_root),
+            schedule_manager: schedule::Manager::new(data_root),
+            swarm_manager: swarm::Manager::new(data_root),
+            events,
         }
     }
 
     // 初期化処理
-    async fn init(&mut self) -> Result<(), Box<dyn Error>> {
+    async fn init(&mut self, plugin_dir: &Path) -> Result<(), RockerError> {
         // 各マネージャの初期化
         self.container_manager.init().await?;
         self.image_manager.init().await?;
+        // 保存済みのネットワークをプラグインのドライバでも作り直せるよう、init より前に登録する
+        self.network_manager.load_plugins(plugin_dir).await?;
         self.network_manager.init().await?;
         self.volume_manager.init().await?;
+        self.secret_manager.init().await?;
+        self.schedule_manager.init().await?;
         
         // デフォルトネットワークの作成
-        if !self.network_manager.exists("bridge").await? {
+        if !self.network_manager.exists(network::DEFAULT_NETWORK_NAME).await? {
             self.network_manager.create_default_bridge().await?;
         }
         
+        // 前回のデーモンが残したエンドポイントとファイアウォールのルールを現在のコンテナの状態に合わせる
+        let live = self.container_manager.live_containers();
+        self.network_manager.reconcile(&live).await?;
+        let mut mounted = self.container_manager.volume_references();
+        for containers in mounted.values_mut() {
+            containers.retain(|id| live.contains(id));
+        }
+        self.volume_manager.reconcile(&mounted);
+        
         Ok(())
     }
     
+    // コンテナを停止する（終了を待つ間はデーモンのロックを外し、他の操作を待たせない）
+    async fn stop_container(
+        daemon: &Arc<Mutex<RockerDaemon>>,
+        id_or_name: &str,
+        timeout: Option<u64>,
+    ) -> Result<(), RockerError> {
+        let request = match daemon.lock().await.container_manager.begin_stop(id_or_name, timeout).await? {
+            Some(request) => request,
+            None => return Ok(()),
+        };
+        let id = request.id().to_string();
+        let exit_code = request.wait().await;
+
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        daemon
+            .container_manager
+            .finish_stop(&id, exit_code, &mut daemon.network_manager, &mut daemon.volume_manager)
+            .await
+    }
+
+    // name のコンテナを新しいコンテナで置き換える（古いコンテナの停止を待つ間は stop_container と同じくロックを外す）
+    async fn replace_container(
+        daemon: &Arc<Mutex<RockerDaemon>>,
+        name: &str,
+        config: ContainerConfig,
+    ) -> Result<Container, RockerError> {
+        let (image, mut replacement) = {
+            let mut daemon_guard = daemon.lock().await;
+            let daemon = &mut *daemon_guard;
+            let image = daemon.image_manager.get(&config.image)?;
+            let replacement = daemon
+                .container_manager
+                .begin_replace(name, &config, &image, &daemon.network_manager)
+                .await?;
+            (image, replacement)
+        };
+        if let Some(request) = replacement.take_stop() {
+            let id = request.id().to_string();
+            let exit_code = request.wait().await;
+            let mut daemon_guard = daemon.lock().await;
+            let daemon = &mut *daemon_guard;
+            daemon
+                .container_manager
+                .finish_stop(&id, exit_code, &mut daemon.network_manager, &mut daemon.volume_manager)
+                .await?;
+        }
+
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        daemon
+            .container_manager
+            .finish_replace(
+                replacement,
+                name,
+                config,
+                &image,
+                &mut daemon.network_manager,
+                &mut daemon.volume_manager,
+                &daemon.secret_manager,
+            )
+            .await
+    }
+
+    // コンテナを停止してから起動し直す（停止を待つ間は stop_container と同じくデーモンのロックを外す）
+    async fn restart_container(daemon: &Arc<Mutex<RockerDaemon>>, id: &str) -> Result<(), RockerError> {
+        Self::stop_container(daemon, id, None).await?;
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        daemon
+            .container_manager
+            .restart(id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+            .await
+    }
+
     // 既存コンテナの復元
-    async fn restore_containers(&mut self) -> Result<(), Box<dyn Error>> {
+    //
+    // --wait-for のあるコンテナは依存先と一緒に起動できるよう、ここでは起動せずに ID を返す
+    async fn restore_containers(&mut self) -> Result<Vec<String>, RockerError> {
         info!("Restoring existing containers...");
         let containers = self.container_manager.list_all().await?;
         
+        let mut waiting = Vec::new();
         for container in containers {
-            if container.auto_restart() {
-                match self.container_manager.start(&container.id).await {
-                    Ok(_) => info!("Restored container: {}", container.id),
-                    Err(e) => error!("Failed to restore container {}: {}", container.id, e),
-                }
+            if !container.auto_restart() {
+                continue;
+            }
+            if !container.config.wait_for.is_empty() {
+                waiting.push(container.id);
+                continue;
+            }
+            match self
+                .container_manager
+                .start(&container.id, &mut self.network_manager, &mut self.volume_manager, &self.secret_manager)
+                .await
+            {
+                Ok(_) => info!("Restored container: {}", container.id),
+                Err(e) => error!("Failed to restore container {}: {}", container.id, e),
             }
         }
         
-        Ok(())
+        Ok(waiting)
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    // ロギングの初期化
-    tracing_subscriber::fmt::init();
-    
+    let options = Options::parse();
+
+ // ロギングの初期化（--debug は --log-level trace より詳しくしない）
+    let level = if options.debug { options.log_level.max(Level::DEBUG) } else { options.log_level };
+    tracing_subscriber::fmt().with_max_level(level).init();
+
+    // Linux 以外には名前空間も cgroup も無いため、コンテナを真似るだけの --dry-run でしか動かせない
+    if !cfg!(target_os = "linux") && !options.dry_run {
+        return Err("rockerd can only run containers on Linux; use --dry-run on other systems".into());
+    }
+
     info!("Starting rocker daemon...");
     
     // データディレクトリの作成
-    let data_dir = Path::new("/var/lib/rocker");
+    let data_dir = match &options.data_root {
+        Some(data_root) => data_root.clone(),
+        None if options.dry_run => std::env::temp_dir().join(DRY_RUN_DATA_ROOT),
+        None => PathBuf::from(DEFAULT_DATA_ROOT),
+    };
     if !data_dir.exists() {
-        std::fs::create_dir_all(data_dir)?;
+        std::fs::create_dir_all(&data_dir)?;
     }
     
+    let plugin_dir = match &options.plugin_dir {
+        Some(plugin_dir) => plugin_dir.clone(),
+        None if options.dry_run => data_dir.join("plugins"),
+        None => PathBuf::from(network::DEFAULT_PLUGIN_DIR),
+    };
+
     // デーモンの初期化
-    let daemon = Arc::new(Mutex::new(RockerDaemon::new()));
-    {
+    let daemon = Arc::new(Mutex::new(RockerDaemon::new(&data_dir, options.dry_run)));
+    let waiting = {
         let mut daemon_guard = daemon.lock().await;
-        daemon_guard.init().await?;
-        daemon_guard.restore_containers().await?;
+        daemon_guard.init(&plugin_dir).await?;
+        let waiting = daemon_guard.restore_containers().await?;
+        daemon_guard.swarm_manager.init(&daemon).await?;
+        waiting
+    };
+    for id in waiting {
+        container::spawn_start(Arc::clone(&daemon), id);
     }
+    // クラスタに参加していれば、割り当てられたタスクのコンテナを動かす
+    swarm::spawn_agent(Arc::clone(&daemon));
+    // スケジュールの時刻が来たコンテナを動かし、終了したものの結果を記録する
+    schedule::spawn_scheduler(Arc::clone(&daemon));
+    // daemon.json に auto-update があれば、ラベルを付けたコンテナをイメージの更新に合わせて作り直す
+    auto_update::spawn_watcher(Arc::clone(&daemon));
     
-    // Unixソケットの作成
-    let socket_path = Path::new("/var/run/rocker.sock");
+    // コンテナプロセスの終了を状態に反映する
+    let exit_rx = daemon.lock().await.container_manager.take_exit_receiver();
+    if let Some(mut exit_rx) = exit_rx {
+        let exit_daemon = Arc::clone(&daemon);
+        tokio::spawn(async move {
+            while let Some(status) = exit_rx.recv().await {
+                let id = status.container_id.clone();
+                let mut daemon_guard = exit_daemon.lock().await;
+                let daemon = &mut *daemon_guard;
+                let restart = match daemon
+                    .container_manager
+                    .handle_exit(status, &mut daemon.network_manager, &mut daemon.volume_manager)
+                    .await
+                {
+                    Ok(restart) => restart,
+                    Err(e) => {
+                        error!("Failed to update state of container {}: {}", id, e);
+                        None
+                    }
+                };
+                // 再起動ポリシーで再起動するコンテナは、ロックを持たずに待ってから起動する
+                if let Some(delay) = restart {
+                    let restart_daemon = Arc::clone(&exit_daemon);
+                    tokio::spawn(async move {
+                        tokio::time::sleep(delay).await;
+                        let mut daemon_guard = restart_daemon.lock().await;
+                        let daemon = &mut *daemon_guard;
+                        if let Err(e) = daemon
+                            .container_manager
+                            .restart_exited(&id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+                            .await
+                        {
+                            error!("Failed to restart container {}: {}", id, e);
+                        }
+                    });
+                }
+            }
+        });
+    }
+    
+    // ヘルスチェックの結果を状態に反映する
+    let health_rx = daemon.lock().await.container_manager.take_health_receiver();
+    if let Some(mut health_rx) = health_rx {
+        let health_daemon = Arc::clone(&daemon);
+        tokio::spawn(async move {
+            while let Some(report) = health_rx.recv().await {
+                let id = report.container_id.clone();
+                let restart = match health_daemon.lock().await.container_manager.handle_health(report).await {
+                    Ok(restart) => restart,
+                    Err(e) => {
+                        error!("Failed to update health of container {}: {}", id, e);
+                        false
+                    }
+                };
+                // 停止を待つ間も他のコンテナのヘルスチェックの結果を反映できるよう、別のタスクで再起動する
+                if restart {
+                    info!("Restarting unhealthy container {}", id);
+                    let restart_daemon = Arc::clone(&health_daemon);
+                    tokio::spawn(async move {
+                        if let Err(e) = RockerDaemon::restart_container(&restart_daemon, &id).await {
+                            error!("Failed to restart unhealthy container {}: {}", id, e);
+                        }
+                    });
+                }
+            }
+        });
+    }
+    
+    // Unixソケットの作成（--dry-run では root 権限が要らないようデータディレクトリに置く）
+    let socket_path = match options.socket {
+        Some(socket) => socket,
+        None if options.dry_run => data_dir.join("rocker.sock"),
+        None => PathBuf::from(DEFAULT_SOCKET_PATH),
+    };
     if socket_path.exists() {
-        std::fs::remove_file(socket_path)?;
+        std::fs::remove_file(&socket_path)?;
     }
     
-    let listener = UnixListener::bind(socket_path)?;
+    let listener = UnixListener::bind(&socket_path)?;
     info!("Listening on unix://{}", socket_path.display());
+    if options.dry_run {
+        info!(
+            "Running in dry-run mode, no containers are actually run (use ROCKER_HOST=unix://{})",
+            socket_path.display()
+        );
+    }
     
     // HTTP APIサーバーの起動
     let api_daemon = Arc::clone(&daemon);
@@ -114,8 +378,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
 }
 
 async fn handle_connection(stream: UnixStream, daemon: Arc<Mutex<RockerDaemon>>) -> Result<(), Box<dyn Error>> {
-    // ここでクライアントからのリクエストを処理
-    // JSONベースのプロトコル実装
+    // Unix ソケット上の HTTP で API のリクエストを処理
+    api::serve_connection(stream, daemon).await?;
     
     Ok(())
 } 
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+use rocker_core::{parse_cidr, Network, NetworkError};
+use std::net::Ipv4Addr;
+use tracing::info;
+
+use super::endpoint::{create_veth_endpoint, delete_veth_endpoint, join_veth_endpoint};
+use super::{bridge_name, netlink, network_mtu, Driver, Endpoint};
+
+// ホストのブリッジに veth でコンテナを接続するドライバ
+pub struct BridgeDriver;
+
+#[async_trait]
+impl Driver for BridgeDriver {
+    fn name(&self) -> &str {
+        "bridge"
+    }
+
+    async fn create_network(&self, network: &Network) -> Result<(), NetworkError> {
+        setup_bridge(network)
+    }
+
+    async fn delete_network(&self, network: &Network) -> Result<(), NetworkError> {
+        let bridge = bridge_name(network);
+        netlink::delete_link(&bridge)
+            .map_err(|e| NetworkError::Remove(format!("Failed to remove bridge {}: {}", bridge, e)))
+    }
+
+    async fn create_endpoint(&self, network: &Network, endpoint: &Endpoint) -> Result<(), NetworkError> {
+        create_veth_endpoint(bridge_name(network), &network.id, endpoint).await
+    }
+
+    async fn delete_endpoint(&self, network: &Network, container_id: &str) -> Result<(), NetworkError> {
+        delete_veth_endpoint(&network.id, container_id).await
+    }
+
+    async fn join(&self, network: &Network, endpoint: &Endpoint, pid: i32) -> Result<(), NetworkError> {
+        join_veth_endpoint(&network.id, endpoint, Some(gateway(network)?), pid).await
+    }
+
+    // ピアはホスト側の veth を削除するときに一緒に削除される
+    async fn leave(&self, _network: &Network, _container_id: &str) -> Result<(), NetworkError> {
+        Ok(())
+    }
+}
+
+fn gateway(network: &Network) -> Result<Ipv4Addr, NetworkError> {
+    network
+        .config
+        .gateway
+        .parse()
+        .map_err(|_| NetworkError::InvalidConfig(format!("Invalid gateway: {}", network.config.gateway)))
+}
+
+// ブリッジデバイスを作成してゲートウェイのアドレスを割り当て、IP フォワーディングを有効にする
+fn setup_bridge(network: &Network) -> Result<(), NetworkError> {
+    let name = bridge_name(network);
+    let gateway = gateway(network)?;
+    let (_, prefix_len) = parse_cidr(&network.config.subnet)?;
+    let mtu = network_mtu(network)?;
+
+    netlink::create_bridge(&name)
+        .map_err(|e| NetworkError::Create(format!("Failed to create bridge {}: {}", name, e)))?;
+    let index = netlink::link_index(&name)
+        .map_err(|e| NetworkError::Create(format!("Bridge {} not found: {}", name, e)))?;
+    netlink::set_mtu(index, mtu)
+        .map_err(|e| NetworkError::Create(format!("Failed to set MTU of {} to {}: {}", name, mtu, e)))?;
+    netlink::add_address(index, gateway, prefix_len)
+        .map_err(|e| NetworkError::Create(format!("Failed to assign {} to {}: {}", gateway, name, e)))?;
+    netlink::set_link_up(index)
+        .map_err(|e| NetworkError::Create(format!("Failed to bring up {}: {}", name, e)))?;
+
+    std::fs::write("/proc/sys/net/ipv4/ip_forward", "1")
+        .map_err(|e| NetworkError::Create(format!("Failed to enable IP forwarding: {}", e)))?;
+
+    info!("Bridge {} is up with {}/{}", name, gateway, prefix_len);
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use rocker_core::EMBEDDED_DNS_SERVER;
+use std::collections::HashMap;
+use std::fs::File;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use super::netlink;
+
+// 答えたレコードの TTL（秒）
+const RECORD_TTL: u32 = 600;
+// 上流のネームサーバを待つ時間
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+// ホストの resolv.conf にネームサーバが無い場合の転送先
+const FALLBACK_NAMESERVER: &str = "8.8.8.8";
+
+const TYPE_A: u16 = 1;
+const CLASS_IN: u16 = 1;
+const RCODE_SERVFAIL: u8 = 2;
+
+// ネットワーク ID → コンテナ ID → (解決できる名前, アドレス)
+type Records = HashMap<String, HashMap<String, (Vec<String>, Ipv4Addr)>>;
+
+// コンテナ向けの組み込み DNS サーバ
+//
+// コンテナのネットワーク名前空間の 127.0.0.11:53 で問い合わせを受け、同じネットワークに参加しているコンテナの
+// 名前とエイリアスを答える。同じ名前を持つコンテナが複数あれば問い合わせごとに順番を回して返し（ラウンドロビン）、
+// 知らない名前はホストのネームサーバに転送する。
+pub struct DnsServer {
+    records: Arc<RwLock<Records>>,
+    rotation: Arc<AtomicUsize>,
+    // コンテナごとの待ち受けタスク
+    listeners: HashMap<String, JoinHandle<()>>,
+}
+
+impl DnsServer {
+    pub fn new() -> Self {
+        DnsServer {
+            records: Arc::new(RwLock::new(HashMap::new())),
+            rotation: Arc::new(AtomicUsize::new(0)),
+            listeners: HashMap::new(),
+        }
+    }
+
+    // コンテナの名前をネットワークに登録し、コンテナ内で待ち受けていなければ待ち受けを始める
+    //
+    // pid はコンテナのネットワーク名前空間に属するプロセス。
+    pub fn register(&mut self, network_id: &str, container_id: &str, names: Vec<String>, address: Ipv4Addr, pid: i32) {
+        let names = names.into_iter().map(|name| name.to_lowercase()).collect();
+        self.records
+            .write()
+            .unwrap()
+            .entry(network_id.to_string())
+            .or_default()
+            .insert(container_id.to_string(), (names, address));
+
+        if self.listeners.get(container_id).is_some_and(|listener| !listener.is_finished()) {
+            return;
+        }
+        let socket = match bind_in_namespace(pid) {
+            Ok(socket) => socket,
+            Err(e) => {
+                warn!("Failed to start the embedded DNS server in container {}: {}", container_id, e);
+                return;
+            }
+        };
+        let (records, rotation) = (Arc::clone(&self.records), Arc::clone(&self.rotation));
+        let id = container_id.to_string();
+        self.listeners
+            .insert(id.clone(), tokio::spawn(serve(socket, id, records, rotation)));
+        info!("Started the embedded DNS server in container {}", container_id);
+    }
+
+    // コンテナの名前をネットワークから外し、どのネットワークにも残っていなければ待ち受けをやめる
+    pub fn unregister(&mut self, network_id: &str, container_id: &str) {
+        let mut records = self.records.write().unwrap();
+        if let Some(containers) = records.get_mut(network_id) {
+            containers.remove(container_id);
+            if containers.is_empty() {
+                records.remove(network_id);
+            }
+        }
+        if !records.values().any(|containers| containers.contains_key(container_id)) {
+            if let Some(listener) = self.listeners.remove(container_id) {
+                listener.abort();
+            }
+        }
+    }
+}
+
+// コンテナのネットワーク名前空間の中で UDP ソケットを作る
+//
+// setns はスレッドにだけ効くため、専用のスレッドで名前空間に入ってから bind する。作ったソケットは
+// スレッドが終わってもコンテナの名前空間に属したまま使える。
+fn bind_in_namespace(pid: i32) -> std::io::Result<UdpSocket> {
+    let namespace = File::open(format!("/proc/{}/ns/net", pid))?;
+    let socket = std::thread::spawn(move || -> std::io::Result<std::net::UdpSocket> {
+        netlink::enter_namespace(&namespace)?;
+        let socket = std::net::UdpSocket::bind((EMBEDDED_DNS_SERVER, 53))?;
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    })
+    .join()
+    .map_err(|_| std::io::Error::other("DNS listener thread panicked"))??;
+    UdpSocket::from_std(socket)
+}
+
+async fn serve(socket: UdpSocket, container_id: String, records: Arc<RwLock<Records>>, rotation: Arc<AtomicUsize>) {
+    let socket = Arc::new(socket);
+    let mut buffer = [0u8; 4096];
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buffer).await {
+            Ok(received) => received,
+            Err(e) => {
+                warn!("Embedded DNS server of container {} stopped: {}", container_id, e);
+                return;
+            }
+        };
+        let query = buffer[..len].to_vec();
+        let Some(question) = parse_question(&query) else {
+            continue;
+        };
+
+        let addresses = lookup(&records, &container_id, &question.name);
+        if !addresses.is_empty() {
+            let offset = rotation.fetch_add(1, Ordering::Relaxed);
+            let response = answer(&query, &question, &rotate(addresses, offset));
+            let _ = socket.send_to(&response, peer).await;
+            continue;
+        }
+
+        // コンテナの名前でなければホストのネームサーバに問い合わせる（待つ間も他の問い合わせを受ける）
+        let socket = Arc::clone(&socket);
+        tokio::spawn(async move {
+            let response = match forward(&query).await {
+                Some(response) => response,
+                None => failure(&query, &question),
+            };
+            let _ = socket.send_to(&response, peer).await;
+        });
+    }
+}
+
+// 問い合わせたコンテナと同じネットワークにいるコンテナのうち、name を持つもののアドレス
+fn lookup(records: &RwLock<Records>, container_id: &str, name: &str) -> Vec<Ipv4Addr> {
+    let records = records.read().unwrap();
+    let mut addresses: Vec<Ipv4Addr> = records
+        .values()
+        .filter(|containers| containers.contains_key(container_id))
+        .flat_map(|containers| containers.values())
+        .filter(|(names, _)| names.iter().any(|candidate| candidate == name))
+        .map(|(_, address)| *address)
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    addresses
+}
+
+fn rotate(mut addresses: Vec<Ipv4Addr>, offset: usize) -> Vec<Ipv4Addr> {
+    let len = addresses.len();
+    addresses.rotate_left(offset % len);
+    addresses
+}
+
+// 問い合わせの最初の質問
+struct Question {
+    // 小文字にして末尾の . を除いた名前
+    name: String,
+    qtype: u16,
+    // 質問部分の終わりの位置
+    end: usize,
+}
+
+fn parse_question(packet: &[u8]) -> Option<Question> {
+    // 応答や質問のないパケットは無視する
+    if packet.len() < 12 || packet[2] & 0x80 != 0 || u16::from_be_bytes([packet[4], packet[5]]) == 0 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut pos = 12;
+    loop {
+        let len = *packet.get(pos)? as usize;
+        pos += 1;
+        if len == 0 {
+            break;
+        }
+        // 問い合わせの名前は圧縮されない
+        if len & 0xc0 != 0 {
+            return None;
+        }
+        labels.push(String::from_utf8_lossy(packet.get(pos..pos + len)?).to_lowercase());
+        pos += len;
+    }
+    let qtype = u16::from_be_bytes([*packet.get(pos)?, *packet.get(pos + 1)?]);
+    let end = pos + 4;
+    if packet.len() < end {
+        return None;
+    }
+
+    Some(Question {
+        name: labels.join("."),
+        qtype,
+        end,
+    })
+}
+
+// 問い合わせの ID と質問をそのまま使った応答のヘッダ
+fn response_header(query: &[u8], question: &Question, rcode: u8, answers: u16) -> Vec<u8> {
+    let mut response = Vec::with_capacity(question.end + answers as usize * 16);
+    response.extend_from_slice(&query[..2]);
+    // QR・AA を立て、オペコードと RD は問い合わせのものを残す
+    response.push(0x80 | 0x04 | (query[2] & 0x79));
+    // RA
+    response.push(0x80 | rcode);
+    response.extend_from_slice(&1u16.to_be_bytes());
+    response.extend_from_slice(&answers.to_be_bytes());
+    response.extend_from_slice(&[0, 0, 0, 0]);
+    response.extend_from_slice(&query[12..question.end]);
+    response
+}
+
+// コンテナのアドレスを答える（AAAA などには答えを持たない成功を返し、A の結果を使わせる）
+fn answer(query: &[u8], question: &Question, addresses: &[Ipv4Addr]) -> Vec<u8> {
+    let addresses: &[Ipv4Addr] = if question.qtype == TYPE_A { addresses } else { &[] };
+    let mut response = response_header(query, question, 0, addresses.len() as u16);
+    for address in addresses {
+        // 名前は質問の名前（オフセット 12）への圧縮ポインタ
+        response.extend_from_slice(&[0xc0, 0x0c]);
+        response.extend_from_slice(&TYPE_A.to_be_bytes());
+        response.extend_from_slice(&CLASS_IN.to_be_bytes());
+        response.extend_from_slice(&RECORD_TTL.to_be_bytes());
+        response.extend_from_slice(&4u16.to_be_bytes());
+        response.extend_from_slice(&address.octets());
+    }
+    response
+}
+
+fn failure(query: &[u8], question: &Question) -> Vec<u8> {
+    response_header(query, question, RCODE_SERVFAIL, 0)
+}
+
+// ホストの resolv.conf のネームサーバに順に問い合わせ、最初に返った応答を返す
+async fn forward(query: &[u8]) -> Option<Vec<u8>> {
+    let resolv_conf = tokio::fs::read_to_string("/etc/resolv.conf").await.unwrap_or_default();
+    let mut nameservers: Vec<SocketAddr> = resolv_conf
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some("nameserver"), Some(address)) => address.parse().ok().map(|ip| SocketAddr::new(ip, 53)),
+                _ => None,
+            }
+        })
+        .collect();
+    if nameservers.is_empty() {
+        nameservers.push(SocketAddr::new(FALLBACK_NAMESERVER.parse().ok()?, 53));
+    }
+
+    for nameserver in nameservers {
+        let bind: SocketAddr = if nameserver.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }.parse().ok()?;
+        let Ok(socket) = UdpSocket::bind(bind).await else {
+            continue;
+        };
+        if socket.send_to(query, nameserver).await.is_err() {
+            continue;
+        }
+        let mut buffer = vec![0u8; 4096];
+        if let Ok(Ok((len, _))) = tokio::time::timeout(FORWARD_TIMEOUT, socket.recv_from(&mut buffer)).await {
+            buffer.truncate(len);
+            return Some(buffer);
+        }
+    }
+    None
+}
@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use rocker_core::{Network, NetworkError, TrafficShaping};
+use std::net::Ipv4Addr;
+
+// コンテナをネットワークに接続するエンドポイント（アドレスは Manager が IPAM で割り当てる）
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    pub container_id: String,
+    // コンテナ内のインターフェース名
+    pub interface: String,
+    pub address: Ipv4Addr,
+    pub prefix_len: u8,
+    pub mac_address: [u8; 6],
+    pub mtu: u32,
+    pub shaping: TrafficShaping,
+}
+
+// ネットワークドライバ
+//
+// Manager は network.driver の名前で登録済みのドライバを選び、ネットワークとエンドポイントの
+// 作成・削除、コンテナの参加・離脱を委ねる。アドレスの割り当てと状態の保存は Manager が行う。
+// 接続時は create_endpoint の後に join を、切断時は leave の後に delete_endpoint を呼ぶ。
+#[async_trait]
+pub trait Driver: Send + Sync {
+    // network create -d で指定する名前
+    fn name(&self) -> &str;
+
+    // ネットワークのデバイスを用意する（デーモンの起動時にも呼ぶため、作成済みでも成功させる）
+    async fn create_network(&self, network: &Network) -> Result<(), NetworkError>;
+
+    async fn delete_network(&self, network: &Network) -> Result<(), NetworkError>;
+
+    // ホスト側でエンドポイントを用意する
+    async fn create_endpoint(&self, network: &Network, endpoint: &Endpoint) -> Result<(), NetworkError>;
+
+    // エンドポイントを削除する（コンテナが既に終了していても呼ばれる）
+    async fn delete_endpoint(&self, network: &Network, container_id: &str) -> Result<(), NetworkError>;
+
+    // エンドポイントを pid のネットワーク名前空間に移して設定する
+    async fn join(&self, network: &Network, endpoint: &Endpoint, pid: i32) -> Result<(), NetworkError>;
+
+    async fn leave(&self, network: &Network, container_id: &str) -> Result<(), NetworkError>;
+
+    // IPAM で割り当てないアドレス（他のホストのコンテナが使っているアドレスなど）
+    fn reserved_addresses(&self, _network: &Network) -> Vec<Ipv4Addr> {
+        Vec::new()
+    }
+
+    // ネットワークに接続中のコンテナが変わったときに呼ばれる
+    fn endpoints_changed(&self, _network: &Network) {}
+}
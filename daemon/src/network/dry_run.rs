@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+use rocker_core::{Network, NetworkError};
+
+use super::{Driver, Endpoint};
+
+// --dry-run で組み込みのドライバの代わりに使うドライバ
+//
+// デバイスを作らず何もしない。アドレスの割り当てと状態の保存は通常どおり Manager が行う。
+pub struct SimulatedDriver {
+    name: &'static str,
+}
+
+impl SimulatedDriver {
+    pub fn new(name: &'static str) -> Self {
+        SimulatedDriver { name }
+    }
+}
+
+#[async_trait]
+impl Driver for SimulatedDriver {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    async fn create_network(&self, _network: &Network) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    async fn delete_network(&self, _network: &Network) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    async fn create_endpoint(&self, _network: &Network, _endpoint: &Endpoint) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    async fn delete_endpoint(&self, _network: &Network, _container_id: &str) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    async fn join(&self, _network: &Network, _endpoint: &Endpoint, _pid: i32) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    async fn leave(&self, _network: &Network, _container_id: &str) -> Result<(), NetworkError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,258 @@
+use rocker_core::{
+    format_mac_address, mac_address_for, parse_cidr, NetworkContainer, NetworkEndpoint, NetworkError, RockerError,
+    TrafficShaping,
+};
+use std::fs::File;
+use std::net::Ipv4Addr;
+use tracing::{info, warn};
+
+use super::{netlink, network_mtu, shaping::apply_shaping, Endpoint, Manager};
+
+// コンテナ側のインターフェースの設定
+struct InterfaceConfig {
+    name: String,
+    address: Ipv4Addr,
+    prefix_len: u8,
+    // オーバーレイネットワークにはゲートウェイがないためデフォルトルートを設定しない
+    gateway: Option<Ipv4Addr>,
+}
+
+// コンテナをネットワークに接続するときの指定
+pub struct EndpointOptions {
+    // コンテナ名（エイリアスと共に組み込み DNS で解決できる）
+    pub name: String,
+    // コンテナ内のインターフェース名
+    pub interface: String,
+    pub aliases: Vec<String>,
+    // 指定がなければ IPAM で割り当てる
+    pub ip_address: Option<Ipv4Addr>,
+    pub shaping: TrafficShaping,
+}
+
+impl Manager {
+    // ネットワークのドライバでエンドポイントを作成し、コンテナを接続する
+    //
+    // pid はネットワーク名前空間を作成済みのコンテナのプロセス。コンテナ内のインターフェースは
+    // options.interface の名前で作成される。
+    pub async fn connect(
+        &mut self,
+        id_or_name: &str,
+        container_id: &str,
+        pid: i32,
+        options: EndpointOptions,
+    ) -> Result<NetworkEndpoint, RockerError> {
+        let network = self.get(id_or_name)?;
+        let driver = self.driver(&network.driver)?;
+        if network.containers.contains_key(container_id) {
+            return Err(NetworkError::Connect(format!(
+                "Container {} is already connected to {}",
+                container_id, network.name
+            ))
+            .into());
+        }
+
+        let reserved = driver.reserved_addresses(network);
+        let address = match options.ip_address {
+            Some(address) => network.reserve_ip(address, &reserved)?,
+            None => network.allocate_ip(&reserved)?,
+        };
+        let (_, prefix_len) = parse_cidr(&network.config.subnet)?;
+        let endpoint = Endpoint {
+            container_id: container_id.to_string(),
+            interface: options.interface.clone(),
+            address,
+            prefix_len,
+            mac_address: mac_address_for(address),
+            mtu: network_mtu(network)?,
+            shaping: options.shaping.clone(),
+        };
+
+        driver.create_endpoint(network, &endpoint).await?;
+        if let Err(e) = driver.join(network, &endpoint, pid).await {
+            if let Err(e) = driver.delete_endpoint(network, container_id).await {
+                warn!("Failed to remove endpoint of {}: {}", container_id, e);
+            }
+            return Err(e.into());
+        }
+
+        let mac_address = format_mac_address(&endpoint.mac_address);
+        info!("Connected container {} to {} as {}", container_id, network.name, address);
+
+        let network_id = network.id.clone();
+        let network = self
+            .networks
+            .get_mut(&network_id)
+            .ok_or_else(|| NetworkError::NotFound(network_id.clone()))?;
+        network.containers.insert(
+            container_id.to_string(),
+            NetworkContainer {
+                container_id: container_id.to_string(),
+                ip_address: address.to_string(),
+                mac_address: mac_address.clone(),
+                aliases: options.aliases.clone(),
+            },
+        );
+        driver.endpoints_changed(network);
+        // デフォルトのブリッジではコンテナ名で名前解決しない（--dry-run では待ち受ける名前空間が無い）
+        if network.name.as_str() != super::DEFAULT_NETWORK_NAME && !self.dry_run {
+            let mut names = vec![options.name.clone(), container_id[..12.min(container_id.len())].to_string()];
+            names.extend(options.aliases.iter().cloned());
+            self.dns.register(&network_id, container_id, names, address, pid);
+        }
+        self.save(&network_id).await?;
+
+        Ok(NetworkEndpoint {
+            network_id,
+            ip_address: address.to_string(),
+            mac_address,
+            interface: options.interface,
+            requested_ip: options.ip_address.map(|ip| ip.to_string()),
+            aliases: options.aliases,
+            shaping: options.shaping,
+        })
+    }
+
+    // コンテナをネットワークから切り離してエンドポイントを削除し、割り当てたアドレスを解放する
+    pub async fn disconnect(&mut self, id_or_name: &str, container_id: &str) -> Result<(), RockerError> {
+        let network = self.get(id_or_name)?;
+        let driver = self.driver(&network.driver)?;
+
+        driver
+            .leave(network, container_id)
+            .await
+            .map_err(|e| NetworkError::Disconnect(e.to_string()))?;
+        driver
+            .delete_endpoint(network, container_id)
+            .await
+            .map_err(|e| NetworkError::Disconnect(e.to_string()))?;
+
+        let network_id = network.id.clone();
+        let network = self
+            .networks
+            .get_mut(&network_id)
+            .ok_or_else(|| NetworkError::NotFound(network_id.clone()))?;
+        if network.containers.remove(container_id).is_some() {
+            info!("Disconnected container {} from {}", container_id, network.name);
+        }
+        driver.endpoints_changed(network);
+        self.dns.unregister(&network_id, container_id);
+        self.save(&network_id).await
+    }
+}
+
+// ホスト側の veth 名（IFNAMSIZ に収まるようコンテナとネットワークの ID を切り詰める）
+pub(super) fn veth_name(container_id: &str, network_id: &str) -> String {
+    format!(
+        "veth{}{}",
+        &container_id[..6.min(container_id.len())],
+        &network_id[..5.min(network_id.len())]
+    )
+}
+
+// コンテナの名前空間に移すまでのピアの名前
+fn peer_name(container_id: &str, network_id: &str) -> String {
+    format!(
+        "vp{}{}",
+        &container_id[..6.min(container_id.len())],
+        &network_id[..5.min(network_id.len())]
+    )
+}
+
+// veth ペアを作成してホスト側をブリッジに接続し、帯域制限を設定する（ブリッジ・オーバーレイ共通）
+pub(super) async fn create_veth_endpoint(
+    bridge: String,
+    network_id: &str,
+    endpoint: &Endpoint,
+) -> Result<(), NetworkError> {
+    let host_veth = veth_name(&endpoint.container_id, network_id);
+    let peer = peer_name(&endpoint.container_id, network_id);
+    let (mac, mtu) = (endpoint.mac_address, endpoint.mtu);
+
+    let veth = host_veth.clone();
+    let plumbed = tokio::task::spawn_blocking(move || plumb_veth(&veth, &peer, &bridge, &mac, mtu))
+        .await
+        .map_err(|e| NetworkError::Connect(e.to_string()))?;
+    let shaped = match plumbed {
+        Ok(()) if !endpoint.shaping.is_empty() => apply_shaping(&host_veth, &endpoint.shaping).await,
+        result => result,
+    };
+    if shaped.is_err() {
+        if let Err(e) = netlink::delete_link(&host_veth) {
+            warn!("Failed to remove {}: {}", host_veth, e);
+        }
+    }
+    shaped
+}
+
+// ピアをコンテナの名前空間に移し、アドレスとデフォルトルートを設定する
+pub(super) async fn join_veth_endpoint(
+    network_id: &str,
+    endpoint: &Endpoint,
+    gateway: Option<Ipv4Addr>,
+    pid: i32,
+) -> Result<(), NetworkError> {
+    let peer = peer_name(&endpoint.container_id, network_id);
+    let config = InterfaceConfig {
+        name: endpoint.interface.clone(),
+        address: endpoint.address,
+        prefix_len: endpoint.prefix_len,
+        gateway,
+    };
+
+    tokio::task::spawn_blocking(move || {
+        let index = netlink::link_index(&peer).map_err(|e| NetworkError::Connect(format!("{}: {}", peer, e)))?;
+        netlink::move_link(index, pid, &config.name)
+            .map_err(|e| NetworkError::Connect(format!("Failed to move {} into {}: {}", peer, pid, e)))?;
+        configure_peer(&config, pid)
+    })
+    .await
+    .map_err(|e| NetworkError::Connect(e.to_string()))?
+}
+
+// ホスト側の veth を削除する（コンテナの名前空間が既に破棄されていればピアと共に削除済み）
+pub(super) async fn delete_veth_endpoint(network_id: &str, container_id: &str) -> Result<(), NetworkError> {
+    let host_veth = veth_name(container_id, network_id);
+    tokio::task::spawn_blocking(move || netlink::delete_link(&host_veth))
+        .await
+        .map_err(|e| NetworkError::Disconnect(e.to_string()))?
+        .map_err(|e| NetworkError::Disconnect(e.to_string()))
+}
+
+fn plumb_veth(host_veth: &str, peer: &str, bridge: &str, mac: &[u8; 6], mtu: u32) -> Result<(), NetworkError> {
+    let connect_error = |e: std::io::Error| NetworkError::Connect(format!("{}: {}", host_veth, e));
+
+    netlink::create_veth(host_veth, peer, mac, mtu).map_err(connect_error)?;
+    let index = netlink::link_index(host_veth).map_err(connect_error)?;
+    let bridge_index = netlink::link_index(bridge)
+        .map_err(|e| NetworkError::Connect(format!("Bridge {} not found: {}", bridge, e)))?;
+    netlink::set_master(index, bridge_index).map_err(connect_error)?;
+    netlink::set_link_up(index).map_err(connect_error)
+}
+
+// コンテナのネットワーク名前空間に入ったスレッドでピア側を設定する
+fn configure_peer(peer: &InterfaceConfig, pid: i32) -> Result<(), NetworkError> {
+    let netns = File::open(format!("/proc/{}/ns/net", pid))
+        .map_err(|e| NetworkError::Connect(format!("Failed to open network namespace of {}: {}", pid, e)))?;
+
+    let name = peer.name.clone();
+    let (address, prefix_len, gateway) = (peer.address, peer.prefix_len, peer.gateway);
+    std::thread::spawn(move || -> Result<(), String> {
+        netlink::enter_namespace(&netns).map_err(|e| format!("setns: {}", e))?;
+
+        let loopback = netlink::link_index("lo").map_err(|e| format!("lo: {}", e))?;
+        netlink::set_link_up(loopback).map_err(|e| format!("lo: {}", e))?;
+
+        let index = netlink::link_index(&name).map_err(|e| format!("{}: {}", name, e))?;
+        netlink::add_address(index, address, prefix_len).map_err(|e| format!("{}: {}", name, e))?;
+        netlink::set_link_up(index).map_err(|e| format!("{}: {}", name, e))?;
+
+        // 複数のネットワークに接続する場合は最初のネットワークのゲートウェイを使う
+        match gateway.map(netlink::add_default_route) {
+            Some(Err(e)) if e.raw_os_error() != Some(nix::libc::EEXIST) => Err(format!("default route: {}", e)),
+            _ => Ok(()),
+        }
+    })
+    .join()
+    .map_err(|_| NetworkError::Connect("Network namespace thread panicked".to_string()))?
+    .map_err(NetworkError::Connect)
+}
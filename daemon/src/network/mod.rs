@@ -0,0 +1,447 @@
+use rocker_core::{
+    cidr_contains, cidr_overlaps, first_host, lookup, parse_cidr, LookupError, Network, NetworkConfig, NetworkDriver,
+    NetworkError, RockerError,
+};
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+mod bridge;
+mod dns;
+mod driver;
+mod dry_run;
+mod endpoint;
+mod netlink;
+mod overlay;
+mod portmap;
+mod remote;
+mod shaping;
+pub use driver::{Driver, Endpoint};
+pub use endpoint::EndpointOptions;
+pub use overlay::{KEY_OPTION, LOCAL_OPTION, PEERS_OPTION, VNI_OPTION};
+pub use remote::DEFAULT_PLUGIN_DIR;
+
+// デフォルトのネットワーク名とブリッジデバイス名
+pub const DEFAULT_NETWORK_NAME: &str = "bridge";
+const DEFAULT_BRIDGE_NAME: &str = "rocker0";
+
+// サブネットを指定せずに作成したネットワークに割り当てるアドレスの範囲（先頭・プレフィックス長・個数）
+const ADDRESS_POOLS: [(Ipv4Addr, u8, u32); 2] = [
+    (Ipv4Addr::new(172, 17, 0, 0), 16, 15),
+    (Ipv4Addr::new(192, 168, 0, 0), 20, 16),
+];
+
+// ネットワークのオプションでブリッジデバイス名を指定するキー
+pub const BRIDGE_NAME_OPTION: &str = "com.rocker.network.bridge.name";
+// ブリッジ・veth の MTU（VPN 越しなど経路の MTU が小さい環境向け）
+pub const MTU_OPTION: &str = "com.rocker.network.mtu";
+// false の場合は外部へ送信するパケットの送信元を書き換えない
+pub const IP_MASQUERADE_OPTION: &str = "com.rocker.network.bridge.enable_ip_masquerade";
+
+// ブリッジネットワークの既定の MTU
+const BRIDGE_MTU: u32 = 1500;
+// インターフェース名の最大長（IFNAMSIZ から終端の NUL を除いた長さ）
+const MAX_INTERFACE_NAME_LEN: usize = 15;
+
+// ネットワークを管理する構造体
+pub struct Manager {
+    networks: HashMap<String, Network>,
+    state_dir: PathBuf,
+    // コンテナごとに公開しているポート
+    port_mappings: HashMap<String, Vec<portmap::PortMapping>>,
+    firewall: Option<portmap::Firewall>,
+    // オーバーレイネットワークのエンドポイント情報をホスト間で交換する
+    control_plane: overlay::ControlPlane,
+    // 名前ごとのネットワークドライバ
+    drivers: HashMap<String, Arc<dyn Driver>>,
+    // 利用者が作成したネットワークのコンテナの名前解決
+    dns: dns::DnsServer,
+    // --dry-run ではデバイス・ファイアウォールのルール・DNS の待ち受けを作らない
+    dry_run: bool,
+}
+
+impl Manager {
+    pub fn new(data_root: &Path, dry_run: bool) -> Self {
+        let control_plane = overlay::ControlPlane::new();
+        let mut drivers: HashMap<String, Arc<dyn Driver>> = HashMap::new();
+        let builtin = if dry_run {
+            [
+                Arc::new(dry_run::SimulatedDriver::new("bridge")) as Arc<dyn Driver>,
+                Arc::new(dry_run::SimulatedDriver::new("overlay")),
+            ]
+        } else {
+            [
+                Arc::new(bridge::BridgeDriver) as Arc<dyn Driver>,
+                Arc::new(overlay::OverlayDriver::new(control_plane.clone())),
+            ]
+        };
+        for driver in builtin {
+            drivers.insert(driver.name().to_string(), driver);
+        }
+
+        Manager {
+            networks: HashMap::new(),
+            state_dir: data_root.join("networks"),
+            port_mappings: HashMap::new(),
+            firewall: None,
+            control_plane,
+            drivers,
+            dns: dns::DnsServer::new(),
+            dry_run,
+        }
+    }
+
+    // ドライバを登録する（init より前に登録すれば、保存済みのネットワークも作り直される）
+    pub fn register_driver(&mut self, driver: Arc<dyn Driver>) -> Result<(), NetworkError> {
+        let name = driver.name().to_string();
+        if self.drivers.contains_key(&name) {
+            return Err(NetworkError::AlreadyExists(format!("driver {}", name)));
+        }
+        // host・none などの組み込みのドライバ名は使えない
+        if NetworkDriver::parse(&name).map_err(NetworkError::InvalidConfig)? != NetworkDriver::Plugin(name.clone()) {
+            return Err(NetworkError::InvalidConfig(format!("{} is a reserved driver name", name)));
+        }
+        info!("Registered network driver {}", name);
+        self.drivers.insert(name, driver);
+        Ok(())
+    }
+
+    // プラグインのディレクトリにあるソケットをドライバとして登録する（init より前に呼ぶ）
+    pub async fn load_plugins(&mut self, dir: &Path) -> Result<(), RockerError> {
+        for driver in remote::discover(dir).await? {
+            let socket = driver.socket().display().to_string();
+            if let Err(e) = self.register_driver(Arc::new(driver)) {
+                warn!("Skipping network plugin {}: {}", socket, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn driver(&self, driver: &NetworkDriver) -> Result<Arc<dyn Driver>, NetworkError> {
+        self.drivers
+            .get(&driver.to_string())
+            .cloned()
+            .ok_or_else(|| NetworkError::InvalidConfig(format!("{} driver is not supported", driver)))
+    }
+
+    // 保存済みのネットワーク情報を読み込む
+    pub async fn init(&mut self) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+
+        let mut entries = tokio::fs::read_dir(&self.state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&path).await?;
+            match serde_json::from_str::<Network>(&content) {
+                Ok(network) => {
+                    self.networks.insert(network.id.clone(), network);
+                }
+                Err(e) => warn!("Skipping invalid network state {}: {}", path.display(), e),
+            }
+        }
+
+        // ホストの再起動後などでデバイスが無くなっていれば作り直す
+        for network in self.networks.values() {
+            let result = match self.driver(&network.driver) {
+                Ok(driver) => driver.create_network(network).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("Failed to set up network {}: {}", network.name, e);
+            }
+        }
+        if !self.dry_run {
+            self.control_plane.start().await;
+
+            // ルールは reconcile でコンテナの状態と突き合わせてから作り直す
+            self.firewall = portmap::detect_firewall().await;
+        }
+        self.load_port_mappings().await?;
+
+        info!("Loaded {} networks", self.networks.len());
+        Ok(())
+    }
+
+    // デーモンの再起動時に、プロセスが残っていないコンテナのエンドポイントと公開ポートを破棄し、
+    // ファイアウォールのルールを作り直す
+    //
+    // live はプロセスが動作しているコンテナの ID。異常終了した前回のデーモンが残したルールも置き換わる。
+    pub async fn reconcile(&mut self, live: &HashSet<String>) -> Result<(), RockerError> {
+        let stale_mappings: Vec<String> = self.port_mappings.keys().filter(|id| !live.contains(*id)).cloned().collect();
+        for container_id in stale_mappings {
+            info!("Removing stale port mappings of container {}", container_id);
+            self.port_mappings.remove(&container_id);
+            self.save_port_mappings(&container_id).await?;
+        }
+
+        let mut changed = Vec::new();
+        for network in self.networks.values_mut() {
+            let stale: Vec<String> = network.containers.keys().filter(|id| !live.contains(*id)).cloned().collect();
+            if stale.is_empty() {
+                continue;
+            }
+            let driver = self.drivers.get(&network.driver.to_string()).cloned();
+            for container_id in &stale {
+                info!("Removing stale endpoint of container {} from {}", container_id, network.name);
+                network.containers.remove(container_id);
+                if let Some(driver) = &driver {
+                    if let Err(e) = driver.delete_endpoint(network, container_id).await {
+                        warn!("Failed to remove endpoint of container {}: {}", container_id, e);
+                    }
+                }
+            }
+            if let Some(driver) = &driver {
+                driver.endpoints_changed(network);
+            }
+            changed.push(network.id.clone());
+        }
+        for id in changed {
+            self.save(&id).await?;
+        }
+
+        self.remove_foreign_rules().await;
+        if let Err(e) = self.apply_port_rules().await {
+            warn!("Failed to restore NAT rules: {}", e);
+        }
+        Ok(())
+    }
+
+    pub async fn list_all(&self) -> Result<Vec<Network>, RockerError> {
+        Ok(self.networks.values().cloned().collect())
+    }
+
+    // ID・ID の前方一致・名前のいずれかでネットワークを探す
+    pub fn get(&self, id_or_name: &str) -> Result<&Network, NetworkError> {
+        lookup(self.networks.values(), id_or_name).map_err(|e| match e {
+            LookupError::NotFound => NetworkError::NotFound(id_or_name.to_string()),
+            LookupError::Ambiguous(_) => NetworkError::Ambiguous(id_or_name.to_string()),
+        })
+    }
+
+    pub async fn exists(&self, id_or_name: &str) -> Result<bool, RockerError> {
+        match self.get(id_or_name) {
+            Ok(_) => Ok(true),
+            Err(NetworkError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // ネットワークを作成し、ドライバに応じたデバイスを用意する
+    //
+    // サブネットが空の場合は既存のネットワークと重ならない範囲を、ゲートウェイが空の場合は
+    // サブネットの最初のアドレスを割り当てる。
+    pub async fn create(
+        &mut self,
+        name: &str,
+        driver: NetworkDriver,
+        mut config: NetworkConfig,
+        options: HashMap<String, String>,
+    ) -> Result<Network, RockerError> {
+        if self.exists(name).await? {
+            return Err(NetworkError::AlreadyExists(name.to_string()).into());
+        }
+
+        if config.subnet.is_empty() {
+            config.subnet = self.allocate_subnet()?;
+        } else {
+            parse_cidr(&config.subnet)?;
+            for network in self.networks.values() {
+                if cidr_overlaps(&network.config.subnet, &config.subnet)? {
+                    return Err(NetworkError::InvalidConfig(format!(
+                        "Subnet {} overlaps with network {} ({})",
+                        config.subnet, network.name, network.config.subnet
+                    ))
+                    .into());
+                }
+            }
+        }
+        if config.gateway.is_empty() {
+            config.gateway = first_host(&config.subnet)?.to_string();
+        }
+        let gateway: Ipv4Addr = config
+            .gateway
+            .parse()
+            .map_err(|_| NetworkError::InvalidConfig(format!("Invalid gateway: {}", config.gateway)))?;
+        if !cidr_contains(&config.subnet, gateway)? {
+            return Err(NetworkError::InvalidConfig(format!(
+                "Gateway {} is not in subnet {}",
+                gateway, config.subnet
+            ))
+            .into());
+        }
+
+        if let Some(value) = options.get(IP_MASQUERADE_OPTION) {
+            config.enable_ip_masquerade = match value.as_str() {
+                "true" => true,
+                "false" => false,
+                _ => {
+                    return Err(
+                        NetworkError::InvalidConfig(format!("Invalid value for {}: {}", IP_MASQUERADE_OPTION, value)).into(),
+                    )
+                }
+            };
+        }
+
+        let mut network = Network::new(name.to_string(), driver, config);
+        network.options = options;
+        let driver = self.driver(&network.driver)?;
+        network_mtu(&network)?;
+        if uses_bridge(&network) {
+            let bridge = bridge_name(&network);
+            if bridge.is_empty() || bridge.len() > MAX_INTERFACE_NAME_LEN {
+                return Err(NetworkError::InvalidConfig(format!(
+                    "Bridge name must be 1 to {} characters: {}",
+                    MAX_INTERFACE_NAME_LEN, bridge
+                ))
+                .into());
+            }
+            if let Some(other) = self.networks.values().find(|n| uses_bridge(n) && bridge_name(n) == bridge) {
+                return Err(NetworkError::InvalidConfig(format!(
+                    "Bridge {} is already used by network {}",
+                    bridge, other.name
+                ))
+                .into());
+            }
+        }
+        driver.create_network(&network).await?;
+        info!("Created network {} ({})", network.name, network.id);
+
+        let id = network.id.clone();
+        self.networks.insert(id.clone(), network.clone());
+        self.save(&id).await?;
+
+        if let Err(e) = self.apply_port_rules().await {
+            warn!("Failed to set up NAT rules: {}", e);
+        }
+        Ok(network)
+    }
+
+    // ネットワークを削除する（既定のネットワークと、コンテナが接続中のネットワークは削除できない）
+    pub async fn remove(&mut self, id_or_name: &str) -> Result<(), RockerError> {
+        let network = self.get(id_or_name)?;
+        if network.name == DEFAULT_NETWORK_NAME {
+            return Err(NetworkError::Remove(format!(
+                "{} is a pre-defined network and cannot be removed",
+                network.name
+            ))
+            .into());
+        }
+        if !network.containers.is_empty() {
+            return Err(NetworkError::Remove(format!("Network {} has active endpoints", network.name)).into());
+        }
+
+        let network = network.clone();
+        self.driver(&network.driver)?.delete_network(&network).await?;
+
+        self.networks.remove(&network.id);
+        let path = self.state_dir.join(format!("{}.json", network.id));
+        if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+        info!("Removed network {} ({})", network.name, network.id);
+
+        if let Err(e) = self.apply_port_rules().await {
+            warn!("Failed to update NAT rules: {}", e);
+        }
+        Ok(())
+    }
+
+    // コンテナが接続していないネットワークのうち filter に一致するものを全て削除し、削除したネットワーク名を返す
+    pub async fn prune(&mut self, filter: impl Fn(&Network) -> bool) -> Result<Vec<String>, RockerError> {
+        let unused: Vec<(String, String)> = self
+            .networks
+            .values()
+            .filter(|n| n.name != DEFAULT_NETWORK_NAME && n.containers.is_empty() && filter(n))
+            .map(|n| (n.id.clone(), n.name.clone()))
+            .collect();
+
+        let mut removed = Vec::new();
+        for (id, name) in unused {
+            match self.remove(&id).await {
+                Ok(()) => removed.push(name),
+                Err(e) => warn!("Failed to remove network {}: {}", name, e),
+            }
+        }
+        Ok(removed)
+    }
+
+    // 既存のネットワークと重ならないサブネットを選ぶ
+    fn allocate_subnet(&self) -> Result<String, NetworkError> {
+        for (base, prefix_len, count) in ADDRESS_POOLS {
+            for n in 0..count {
+                let subnet = format!("{}/{}", Ipv4Addr::from(u32::from(base) + (n << (32 - prefix_len))), prefix_len);
+                let mut in_use = false;
+                for network in self.networks.values() {
+                    in_use |= cidr_overlaps(&network.config.subnet, &subnet)?;
+                }
+                if !in_use {
+                    return Ok(subnet);
+                }
+            }
+        }
+        Err(NetworkError::IpAllocation("No available subnets in the address pools".to_string()))
+    }
+
+    // デフォルトのブリッジネットワークを作成し、ブリッジデバイスを用意する
+    pub async fn create_default_bridge(&mut self) -> Result<(), RockerError> {
+        let mut network = Network::new(DEFAULT_NETWORK_NAME.to_string(), NetworkDriver::Bridge, NetworkConfig::default());
+        network
+            .options
+            .insert(BRIDGE_NAME_OPTION.to_string(), DEFAULT_BRIDGE_NAME.to_string());
+        info!("Creating default bridge network {}", network.id);
+
+        self.driver(&network.driver)?.create_network(&network).await?;
+
+        let id = network.id.clone();
+        self.networks.insert(id.clone(), network);
+        self.save(&id).await?;
+
+        if let Err(e) = self.apply_port_rules().await {
+            warn!("Failed to set up NAT rules: {}", e);
+        }
+        Ok(())
+    }
+
+    // ネットワークの状態をディスクに保存する
+    async fn save(&self, id: &str) -> Result<(), RockerError> {
+        let network = self.get(id)?;
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        tokio::fs::write(
+            self.state_dir.join(format!("{}.json", network.id)),
+            serde_json::to_vec_pretty(network)?,
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+// ホストにブリッジデバイスを作成する組み込みのドライバか
+fn uses_bridge(network: &Network) -> bool {
+    matches!(network.driver, NetworkDriver::Bridge | NetworkDriver::Overlay)
+}
+
+// コンテナのインターフェースの MTU（未指定の場合はドライバの既定値）
+pub fn network_mtu(network: &Network) -> Result<u32, NetworkError> {
+    match network.options.get(MTU_OPTION) {
+        Some(value) => match value.parse::<u32>() {
+            Ok(mtu) if (68..=65535).contains(&mtu) => Ok(mtu),
+            _ => Err(NetworkError::InvalidConfig(format!("Invalid MTU: {}", value))),
+        },
+        None if network.driver == NetworkDriver::Overlay => Ok(overlay::OVERLAY_MTU),
+        None => Ok(BRIDGE_MTU),
+    }
+}
+
+// ブリッジデバイス名（未指定の場合はネットワーク ID から決める）
+pub fn bridge_name(network: &Network) -> String {
+    match network.options.get(BRIDGE_NAME_OPTION) {
+        Some(name) => name.clone(),
+        None => format!("br-{}", &network.id[..12.min(network.id.len())]),
+    }
+}
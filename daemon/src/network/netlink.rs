@@ -0,0 +1,405 @@
+use nix::libc;
+use std::fs::File;
+use std::io;
+use std::net::Ipv4Addr;
+
+// rtnetlink のメッセージ種別とフラグ
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const RTM_NEWADDR: u16 = 20;
+const RTM_NEWROUTE: u16 = 24;
+const RTM_NEWNEIGH: u16 = 28;
+const RTM_DELNEIGH: u16 = 29;
+#[cfg(target_os = "linux")]
+const NLMSG_ERROR: u16 = 2;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_REPLACE: u16 = 0x100;
+const NLM_F_EXCL: u16 = 0x200;
+const NLM_F_CREATE: u16 = 0x400;
+const NLM_F_APPEND: u16 = 0x800;
+
+// 属性の種別
+const IFLA_ADDRESS: u16 = 1;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_MTU: u16 = 4;
+const IFLA_MASTER: u16 = 10;
+const IFLA_LINKINFO: u16 = 18;
+const IFLA_NET_NS_PID: u16 = 19;
+const IFLA_INFO_KIND: u16 = 1;
+const IFLA_INFO_DATA: u16 = 2;
+const VETH_INFO_PEER: u16 = 1;
+const IFLA_VXLAN_ID: u16 = 1;
+const IFLA_VXLAN_LOCAL: u16 = 4;
+const IFLA_VXLAN_LEARNING: u16 = 7;
+const IFLA_VXLAN_PORT: u16 = 15;
+const NDA_DST: u16 = 1;
+const NDA_LLADDR: u16 = 2;
+const NUD_PERMANENT: u16 = 0x80;
+const NTF_SELF: u8 = 0x02;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const RTA_GATEWAY: u16 = 5;
+const NLA_F_NESTED: u16 = 0x8000;
+
+// Linux 以外の libc には無い値
+const AF_BRIDGE: u8 = 7;
+const RT_TABLE_MAIN: u8 = 254;
+const RTPROT_BOOT: u8 = 3;
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RTN_UNICAST: u8 = 1;
+
+const NLMSG_HDR_LEN: usize = 16;
+
+// rtnetlink のリクエストを組み立てる
+struct Request {
+    buf: Vec<u8>,
+}
+
+impl Request {
+    fn new(message_type: u16, flags: u16) -> Self {
+        let mut buf = vec![0u8; NLMSG_HDR_LEN];
+        buf[4..6].copy_from_slice(&message_type.to_ne_bytes());
+        buf[6..8].copy_from_slice(&(flags | NLM_F_REQUEST | NLM_F_ACK).to_ne_bytes());
+        buf[8..12].copy_from_slice(&1u32.to_ne_bytes());
+        Request { buf }
+    }
+
+    fn ifinfomsg(mut self, index: i32, flags: u32, change: u32) -> Self {
+        push_ifinfomsg(&mut self.buf, index, flags, change);
+        self
+    }
+
+    // struct ifaddrmsg
+    fn ifaddrmsg(mut self, prefix_len: u8, index: i32) -> Self {
+        self.buf.push(libc::AF_INET as u8);
+        self.buf.push(prefix_len);
+        self.buf.push(0);
+        self.buf.push(0);
+        self.buf.extend_from_slice(&(index as u32).to_ne_bytes());
+        self
+    }
+
+    // struct ndmsg（ブリッジの FDB エントリ）
+    fn ndmsg(mut self, index: i32) -> Self {
+        self.buf.push(AF_BRIDGE);
+        self.buf.push(0);
+        self.buf.extend_from_slice(&0u16.to_ne_bytes());
+        self.buf.extend_from_slice(&index.to_ne_bytes());
+        self.buf.extend_from_slice(&NUD_PERMANENT.to_ne_bytes());
+        self.buf.push(NTF_SELF);
+        self.buf.push(0);
+        self
+    }
+
+    // struct rtmsg（main テーブルのユニキャスト経路）
+    fn rtmsg(mut self, dst_len: u8) -> Self {
+        self.buf.push(libc::AF_INET as u8);
+        self.buf.push(dst_len);
+        self.buf.push(0);
+        self.buf.push(0);
+        self.buf.push(RT_TABLE_MAIN);
+        self.buf.push(RTPROT_BOOT);
+        self.buf.push(RT_SCOPE_UNIVERSE);
+        self.buf.push(RTN_UNICAST);
+        self.buf.extend_from_slice(&0u32.to_ne_bytes());
+        self
+    }
+
+    fn attr(mut self, attr_type: u16, data: &[u8]) -> Self {
+        push_attr(&mut self.buf, attr_type, data);
+        self
+    }
+
+    fn nested(mut self, attr_type: u16, attrs: &[(u16, &[u8])]) -> Self {
+        let mut inner = Vec::new();
+        for (inner_type, data) in attrs {
+            push_attr(&mut inner, *inner_type, data);
+        }
+        push_attr(&mut self.buf, attr_type | NLA_F_NESTED, &inner);
+        self
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let len = self.buf.len() as u32;
+        self.buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf
+    }
+}
+
+// struct ifinfomsg
+fn push_ifinfomsg(buf: &mut Vec<u8>, index: i32, flags: u32, change: u32) {
+    buf.push(libc::AF_UNSPEC as u8);
+    buf.push(0);
+    buf.extend_from_slice(&0u16.to_ne_bytes());
+    buf.extend_from_slice(&index.to_ne_bytes());
+    buf.extend_from_slice(&flags.to_ne_bytes());
+    buf.extend_from_slice(&change.to_ne_bytes());
+}
+
+// struct rtattr（4 バイト境界に揃える）
+fn push_attr(buf: &mut Vec<u8>, attr_type: u16, data: &[u8]) {
+    let len = (4 + data.len()) as u16;
+    buf.extend_from_slice(&len.to_ne_bytes());
+    buf.extend_from_slice(&attr_type.to_ne_bytes());
+    buf.extend_from_slice(data);
+    while !buf.len().is_multiple_of(4) {
+        buf.push(0);
+    }
+}
+
+fn cstr_bytes(s: &str) -> Vec<u8> {
+    let mut bytes = s.as_bytes().to_vec();
+    bytes.push(0);
+    bytes
+}
+
+// 呼び出したスレッドを netns（/proc/<pid>/ns/net）のネットワーク名前空間に入れる
+#[cfg(target_os = "linux")]
+pub fn enter_namespace(netns: &File) -> io::Result<()> {
+    nix::sched::setns(netns, nix::sched::CloneFlags::CLONE_NEWNET).map_err(io::Error::from)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn enter_namespace(_netns: &File) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Network namespaces are only available on Linux"))
+}
+
+// リクエストを送信し、カーネルからの ACK を待つ
+#[cfg(target_os = "linux")]
+fn send(request: &[u8]) -> io::Result<()> {
+    let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_ROUTE) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let result = send_and_ack(fd, request);
+    unsafe {
+        libc::close(fd);
+    }
+    result
+}
+
+// netlink は Linux にしか無いため、他の OS では（--dry-run 以外で）デバイスを作れない
+#[cfg(not(target_os = "linux"))]
+fn send(_request: &[u8]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "netlink is only available on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn send_and_ack(fd: libc::c_int, request: &[u8]) -> io::Result<()> {
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+
+    let sent = unsafe {
+        libc::sendto(
+            fd,
+            request.as_ptr() as *const libc::c_void,
+            request.len(),
+            0,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buf = [0u8; 4096];
+    let received = unsafe { libc::recv(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+    if received < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if (received as usize) < NLMSG_HDR_LEN + 4 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Short netlink response"));
+    }
+
+    // NLMSG_ERROR の error が 0 なら成功、負の値なら -errno
+    let message_type = u16::from_ne_bytes([buf[4], buf[5]]);
+    if message_type != NLMSG_ERROR {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Unexpected netlink response"));
+    }
+    let error = i32::from_ne_bytes([buf[16], buf[17], buf[18], buf[19]]);
+    if error != 0 {
+        return Err(io::Error::from_raw_os_error(-error));
+    }
+
+    Ok(())
+}
+
+// ブリッジデバイスを作成する（既に存在する場合は何もしない）
+pub fn create_bridge(name: &str) -> io::Result<()> {
+    let request = Request::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL)
+        .ifinfomsg(0, 0, 0)
+        .attr(IFLA_IFNAME, &cstr_bytes(name))
+        .nested(IFLA_LINKINFO, &[(IFLA_INFO_KIND, b"bridge")])
+        .finish();
+
+    match send(&request) {
+        Err(e) if e.raw_os_error() == Some(libc::EEXIST) => Ok(()),
+        result => result,
+    }
+}
+
+// インターフェースに IPv4 アドレスを割り当てる（割り当て済みの場合は何もしない）
+pub fn add_address(index: i32, address: Ipv4Addr, prefix_len: u8) -> io::Result<()> {
+    let request = Request::new(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL)
+        .ifaddrmsg(prefix_len, index)
+        .attr(IFA_LOCAL, &address.octets())
+        .attr(IFA_ADDRESS, &address.octets())
+        .finish();
+
+    match send(&request) {
+        Err(e) if e.raw_os_error() == Some(libc::EEXIST) => Ok(()),
+        result => result,
+    }
+}
+
+// インターフェースを up にする
+pub fn set_link_up(index: i32) -> io::Result<()> {
+    let up = libc::IFF_UP as u32;
+    let request = Request::new(RTM_NEWLINK, 0).ifinfomsg(index, up, up).finish();
+    send(&request)
+}
+
+// インターフェース名からインデックスを取得する
+pub fn link_index(name: &str) -> io::Result<i32> {
+    let name = std::ffi::CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+    if index == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(index as i32)
+}
+
+// veth ペアを作成する。ピア側はホストの名前空間に作成し、move_link でコンテナへ移す
+pub fn create_veth(name: &str, peer_name: &str, peer_mac: &[u8; 6], mtu: u32) -> io::Result<()> {
+    let mut peer = Vec::new();
+    push_ifinfomsg(&mut peer, 0, 0, 0);
+    push_attr(&mut peer, IFLA_IFNAME, &cstr_bytes(peer_name));
+    push_attr(&mut peer, IFLA_ADDRESS, peer_mac);
+    push_attr(&mut peer, IFLA_MTU, &mtu.to_ne_bytes());
+
+    let mut info_data = Vec::new();
+    push_attr(&mut info_data, VETH_INFO_PEER, &peer);
+
+    let request = Request::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL)
+        .ifinfomsg(0, 0, 0)
+        .attr(IFLA_IFNAME, &cstr_bytes(name))
+        .attr(IFLA_MTU, &mtu.to_ne_bytes())
+        .nested(
+            IFLA_LINKINFO,
+            &[(IFLA_INFO_KIND, b"veth".as_slice()), (IFLA_INFO_DATA | NLA_F_NESTED, info_data.as_slice())],
+        )
+        .finish();
+    send(&request)
+}
+
+// インターフェースを指定したプロセスのネットワーク名前空間へ移し、名前を変える
+pub fn move_link(index: i32, netns_pid: i32, new_name: &str) -> io::Result<()> {
+    let request = Request::new(RTM_NEWLINK, 0)
+        .ifinfomsg(index, 0, 0)
+        .attr(IFLA_NET_NS_PID, &(netns_pid as u32).to_ne_bytes())
+        .attr(IFLA_IFNAME, &cstr_bytes(new_name))
+        .finish();
+    send(&request)
+}
+
+// インターフェースの MTU を設定する
+pub fn set_mtu(index: i32, mtu: u32) -> io::Result<()> {
+    let request = Request::new(RTM_NEWLINK, 0)
+        .ifinfomsg(index, 0, 0)
+        .attr(IFLA_MTU, &mtu.to_ne_bytes())
+        .finish();
+    send(&request)
+}
+
+// インターフェースをブリッジに接続する
+pub fn set_master(index: i32, master_index: i32) -> io::Result<()> {
+    let request = Request::new(RTM_NEWLINK, 0)
+        .ifinfomsg(index, 0, 0)
+        .attr(IFLA_MASTER, &(master_index as u32).to_ne_bytes())
+        .finish();
+    send(&request)
+}
+
+// デフォルトルートを追加する
+pub fn add_default_route(gateway: Ipv4Addr) -> io::Result<()> {
+    let request = Request::new(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL)
+        .rtmsg(0)
+        .attr(RTA_GATEWAY, &gateway.octets())
+        .finish();
+    send(&request)
+}
+
+// インターフェースを削除する（存在しない場合は何もしない）
+pub fn delete_link(name: &str) -> io::Result<()> {
+    let index = match link_index(name) {
+        Ok(index) => index,
+        Err(e) if e.raw_os_error() == Some(libc::ENODEV) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let request = Request::new(RTM_DELLINK, 0).ifinfomsg(index, 0, 0).finish();
+    match send(&request) {
+        Err(e) if e.raw_os_error() == Some(libc::ENODEV) => Ok(()),
+        result => result,
+    }
+}
+
+// VXLAN デバイスを作成する（既に存在する場合は何もしない）
+pub fn create_vxlan(name: &str, vni: u32, local: Ipv4Addr, port: u16) -> io::Result<()> {
+    let mut info_data = Vec::new();
+    push_attr(&mut info_data, IFLA_VXLAN_ID, &vni.to_ne_bytes());
+    push_attr(&mut info_data, IFLA_VXLAN_LOCAL, &local.octets());
+    push_attr(&mut info_data, IFLA_VXLAN_LEARNING, &[1]);
+    push_attr(&mut info_data, IFLA_VXLAN_PORT, &port.to_be_bytes());
+
+    let request = Request::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL)
+        .ifinfomsg(0, 0, 0)
+        .attr(IFLA_IFNAME, &cstr_bytes(name))
+        .nested(
+            IFLA_LINKINFO,
+            &[(IFLA_INFO_KIND, b"vxlan".as_slice()), (IFLA_INFO_DATA | NLA_F_NESTED, info_data.as_slice())],
+        )
+        .finish();
+
+    match send(&request) {
+        Err(e) if e.raw_os_error() == Some(libc::EEXIST) => Ok(()),
+        result => result,
+    }
+}
+
+// VXLAN デバイスの FDB に MAC アドレスの転送先の VTEP を登録する
+//
+// MAC アドレスが全て 0 のエントリはブロードキャスト等の送信先になり、VTEP ごとに追加する。
+pub fn add_fdb(index: i32, mac: &[u8; 6], dst: Ipv4Addr) -> io::Result<()> {
+    let flags = if mac == &[0; 6] {
+        NLM_F_CREATE | NLM_F_APPEND
+    } else {
+        NLM_F_CREATE | NLM_F_REPLACE
+    };
+    let request = Request::new(RTM_NEWNEIGH, flags)
+        .ndmsg(index)
+        .attr(NDA_LLADDR, mac)
+        .attr(NDA_DST, &dst.octets())
+        .finish();
+
+    match send(&request) {
+        Err(e) if e.raw_os_error() == Some(libc::EEXIST) => Ok(()),
+        result => result,
+    }
+}
+
+// FDB のエントリを削除する（存在しない場合は何もしない）
+pub fn del_fdb(index: i32, mac: &[u8; 6], dst: Ipv4Addr) -> io::Result<()> {
+    let request = Request::new(RTM_DELNEIGH, 0)
+        .ndmsg(index)
+        .attr(NDA_LLADDR, mac)
+        .attr(NDA_DST, &dst.octets())
+        .finish();
+
+    match send(&request) {
+        Err(e) if e.raw_os_error() == Some(libc::ENOENT) => Ok(()),
+        result => result,
+    }
+}
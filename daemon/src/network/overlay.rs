@@ -0,0 +1,523 @@
+use async_trait::async_trait;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use rocker_core::{Network, NetworkError};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket as StdUdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tracing::{info, warn};
+
+use super::endpoint::{create_veth_endpoint, delete_veth_endpoint, join_veth_endpoint};
+use super::{bridge_name, netlink, network_mtu, Driver, Endpoint};
+
+// オーバーレイネットワークのオプション
+pub const VNI_OPTION: &str = "com.rocker.network.overlay.vni";
+pub const PEERS_OPTION: &str = "com.rocker.network.overlay.peers";
+pub const LOCAL_OPTION: &str = "com.rocker.network.overlay.local";
+// ホスト間で交換するメッセージに HMAC-SHA256 を付ける共有の鍵（全てのホストで同じ値にする）
+pub const KEY_OPTION: &str = "com.rocker.network.overlay.key";
+
+// VXLAN の UDP ポートと、エンドポイント情報を交換するポート
+const VXLAN_PORT: u16 = 4789;
+const GOSSIP_PORT: u16 = 7946;
+
+// エンドポイント情報を送る間隔と、受信が途絶えたホストの情報を破棄するまでの時間
+const GOSSIP_INTERVAL: Duration = Duration::from_secs(5);
+const PEER_TIMEOUT: Duration = Duration::from_secs(30);
+
+// VXLAN のヘッダ分だけ MTU を小さくする
+pub const OVERLAY_MTU: u32 = 1450;
+
+// メッセージの先頭に付ける HMAC-SHA256 の長さ
+const MAC_LEN: usize = 32;
+
+// 他のホストに通知するエンドポイント
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AnnouncedEndpoint {
+    ip: Ipv4Addr,
+    mac: [u8; 6],
+}
+
+// ホスト間で交換するメッセージ
+#[derive(Debug, Serialize, Deserialize)]
+struct Announcement {
+    vni: u32,
+    vtep: Ipv4Addr,
+    endpoints: Vec<AnnouncedEndpoint>,
+}
+
+// このホストのオーバーレイネットワーク
+struct LocalOverlay {
+    vxlan: String,
+    local: Ipv4Addr,
+    peers: Vec<Ipv4Addr>,
+    key: Vec<u8>,
+    endpoints: Vec<AnnouncedEndpoint>,
+}
+
+// 他のホストから通知されたエンドポイント
+struct RemoteHost {
+    endpoints: Vec<AnnouncedEndpoint>,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct State {
+    // VNI ごとのネットワーク
+    networks: HashMap<u32, LocalOverlay>,
+    // VNI ごと・VTEP ごとのリモートのエンドポイント
+    remote: HashMap<u32, HashMap<Ipv4Addr, RemoteHost>>,
+    // VTEP のアドレスごとの受信・送信のソケット
+    sockets: HashMap<Ipv4Addr, Arc<UdpSocket>>,
+}
+
+// オーバーレイネットワークのオプションから読み取った設定
+pub struct OverlayConfig {
+    pub vni: u32,
+    pub local: Ipv4Addr,
+    pub peers: Vec<Ipv4Addr>,
+    pub key: Vec<u8>,
+}
+
+impl OverlayConfig {
+    pub fn from_network(network: &Network) -> Result<Self, NetworkError> {
+        let vni = network_vni(network)?;
+
+        let peers = match network.options.get(PEERS_OPTION) {
+            Some(peers) => peers
+                .split(',')
+                .map(str::trim)
+                .filter(|peer| !peer.is_empty())
+                .map(|peer| {
+                    peer.parse::<Ipv4Addr>()
+                        .map_err(|_| NetworkError::InvalidConfig(format!("Invalid peer address: {}", peer)))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => Vec::new(),
+        };
+
+        // 指定がなければピアへの経路で使われる送信元アドレスを VTEP のアドレスにする
+        let local = match network.options.get(LOCAL_OPTION) {
+            Some(local) => local
+                .parse::<Ipv4Addr>()
+                .map_err(|_| NetworkError::InvalidConfig(format!("Invalid local address: {}", local)))?,
+            None => peers.first().and_then(|peer| source_address(*peer)).ok_or_else(|| {
+                NetworkError::InvalidConfig(format!("{} is required when no peers are reachable", LOCAL_OPTION))
+            })?,
+        };
+
+        // 鍵の無いメッセージを受け入れると、UDP で届く誰もが FDB と IPAM を書き換えられてしまう
+        let key = match network.options.get(KEY_OPTION) {
+            Some(key) if !key.is_empty() => key.as_bytes().to_vec(),
+            _ => return Err(NetworkError::InvalidConfig(format!("{} is required for overlay networks", KEY_OPTION))),
+        };
+
+        Ok(OverlayConfig { vni, local, peers, key })
+    }
+}
+
+// VNI は 24 ビット
+fn network_vni(network: &Network) -> Result<u32, NetworkError> {
+    let vni = network
+        .options
+        .get(VNI_OPTION)
+        .ok_or_else(|| NetworkError::InvalidConfig(format!("{} is required for overlay networks", VNI_OPTION)))?;
+    match vni.parse::<u32>() {
+        Ok(vni) if (1..1 << 24).contains(&vni) => Ok(vni),
+        _ => Err(NetworkError::InvalidConfig(format!("Invalid VNI: {}", vni))),
+    }
+}
+
+fn source_address(peer: Ipv4Addr) -> Option<Ipv4Addr> {
+    let socket = StdUdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect((peer, GOSSIP_PORT)).ok()?;
+    match socket.local_addr().ok()? {
+        SocketAddr::V4(addr) => Some(*addr.ip()),
+        SocketAddr::V6(_) => None,
+    }
+}
+
+// VXLAN デバイスの名前（IFNAMSIZ に収まるようネットワーク ID を切り詰める）
+pub fn vxlan_name(network: &Network) -> String {
+    format!("vx-{}", &network.id[..12.min(network.id.len())])
+}
+
+// ブリッジと VXLAN デバイスを作成し、ブロードキャストを各ピアに複製して送るよう設定する
+//
+// ゲートウェイのアドレスは各ホストで重複するためブリッジには割り当てない。
+pub fn setup_overlay(network: &Network) -> Result<OverlayConfig, NetworkError> {
+    let config = OverlayConfig::from_network(network)?;
+    let bridge = bridge_name(network);
+    let vxlan = vxlan_name(network);
+    let create_error = |name: &str, e: std::io::Error| NetworkError::Create(format!("{}: {}", name, e));
+
+    let mtu = network_mtu(network)?;
+
+    netlink::create_bridge(&bridge).map_err(|e| create_error(&bridge, e))?;
+    let bridge_index = netlink::link_index(&bridge).map_err(|e| create_error(&bridge, e))?;
+    netlink::set_mtu(bridge_index, mtu).map_err(|e| create_error(&bridge, e))?;
+    netlink::set_link_up(bridge_index).map_err(|e| create_error(&bridge, e))?;
+
+    netlink::create_vxlan(&vxlan, config.vni, config.local, VXLAN_PORT).map_err(|e| create_error(&vxlan, e))?;
+    let index = netlink::link_index(&vxlan).map_err(|e| create_error(&vxlan, e))?;
+    netlink::set_mtu(index, mtu).map_err(|e| create_error(&vxlan, e))?;
+    netlink::set_master(index, bridge_index).map_err(|e| create_error(&vxlan, e))?;
+    netlink::set_link_up(index).map_err(|e| create_error(&vxlan, e))?;
+
+    for peer in &config.peers {
+        netlink::add_fdb(index, &[0; 6], *peer).map_err(|e| create_error(&vxlan, e))?;
+    }
+
+    info!(
+        "Overlay {} is up with VNI {} ({} peers)",
+        network.name,
+        config.vni,
+        config.peers.len()
+    );
+    Ok(config)
+}
+
+// VXLAN でホストをまたいでコンテナを接続するドライバ
+pub struct OverlayDriver {
+    control_plane: ControlPlane,
+}
+
+impl OverlayDriver {
+    pub fn new(control_plane: ControlPlane) -> Self {
+        OverlayDriver { control_plane }
+    }
+}
+
+#[async_trait]
+impl Driver for OverlayDriver {
+    fn name(&self) -> &str {
+        "overlay"
+    }
+
+    async fn create_network(&self, network: &Network) -> Result<(), NetworkError> {
+        let config = setup_overlay(network)?;
+        self.control_plane.register(network, &config)?;
+        self.control_plane.update_endpoints(network);
+        Ok(())
+    }
+
+    async fn delete_network(&self, network: &Network) -> Result<(), NetworkError> {
+        self.control_plane.unregister(network);
+        netlink::delete_link(&vxlan_name(network))
+            .map_err(|e| NetworkError::Remove(format!("Failed to remove VXLAN device: {}", e)))?;
+        let bridge = bridge_name(network);
+        netlink::delete_link(&bridge)
+            .map_err(|e| NetworkError::Remove(format!("Failed to remove bridge {}: {}", bridge, e)))
+    }
+
+    async fn create_endpoint(&self, network: &Network, endpoint: &Endpoint) -> Result<(), NetworkError> {
+        create_veth_endpoint(bridge_name(network), &network.id, endpoint).await
+    }
+
+    async fn delete_endpoint(&self, network: &Network, container_id: &str) -> Result<(), NetworkError> {
+        delete_veth_endpoint(&network.id, container_id).await
+    }
+
+    // ゲートウェイはないためデフォルトルートを設定しない
+    async fn join(&self, network: &Network, endpoint: &Endpoint, pid: i32) -> Result<(), NetworkError> {
+        join_veth_endpoint(&network.id, endpoint, None, pid).await
+    }
+
+    async fn leave(&self, _network: &Network, _container_id: &str) -> Result<(), NetworkError> {
+        Ok(())
+    }
+
+    // 他のホストのコンテナが使っているアドレスも避ける
+    fn reserved_addresses(&self, network: &Network) -> Vec<Ipv4Addr> {
+        self.control_plane.remote_addresses(network)
+    }
+
+    fn endpoints_changed(&self, network: &Network) {
+        self.control_plane.update_endpoints(network);
+    }
+}
+
+// ホスト間でエンドポイントの IP・MAC アドレスを交換する
+//
+// 各ホストは定期的に接続中のエンドポイントをピアに送り、受信した情報から FDB を設定する。
+// 他のホストで使われているアドレスは IPAM で割り当てないようにする。
+#[derive(Clone, Default)]
+pub struct ControlPlane {
+    state: Arc<Mutex<State>>,
+}
+
+impl ControlPlane {
+    pub fn new() -> Self {
+        ControlPlane::default()
+    }
+
+    // 定期送信のタスクを開始する（受信のソケットはネットワークを登録したときに VTEP のアドレスで開く）
+    pub async fn start(&self) {
+        let sender = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(GOSSIP_INTERVAL);
+            loop {
+                interval.tick().await;
+                sender.expire();
+                for (socket, peer, message) in sender.announcements() {
+                    if let Err(e) = socket.send_to(&message, (peer, GOSSIP_PORT)).await {
+                        warn!("Failed to send overlay announcement to {}: {}", peer, e);
+                    }
+                }
+            }
+        });
+    }
+
+    // ネットワークを登録する（エンドポイントは update_endpoints で設定する）
+    pub fn register(&self, network: &Network, config: &OverlayConfig) -> Result<(), NetworkError> {
+        let mut state = self.state.lock().unwrap();
+        if let Entry::Vacant(entry) = state.sockets.entry(config.local) {
+            let socket = self.listen(config.local).map_err(|e| {
+                NetworkError::Create(format!("Failed to listen on {}:{}: {}", config.local, GOSSIP_PORT, e))
+            })?;
+            entry.insert(socket);
+        }
+        state.networks.insert(
+            config.vni,
+            LocalOverlay {
+                vxlan: vxlan_name(network),
+                local: config.local,
+                peers: config.peers.clone(),
+                key: config.key.clone(),
+                endpoints: Vec::new(),
+            },
+        );
+        Ok(())
+    }
+
+    // VTEP のアドレスでメッセージを受信するタスクを開始する（他のインターフェースからは受け付けない）
+    fn listen(&self, local: Ipv4Addr) -> std::io::Result<Arc<UdpSocket>> {
+        let socket = StdUdpSocket::bind((local, GOSSIP_PORT))?;
+        socket.set_nonblocking(true)?;
+        let socket = Arc::new(UdpSocket::from_std(socket)?);
+        info!("Overlay control plane listening on {}:{}", local, GOSSIP_PORT);
+
+        let receiver = self.clone();
+        let receive_socket = Arc::clone(&socket);
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 65536];
+            loop {
+                match receive_socket.recv_from(&mut buf).await {
+                    Ok((len, from)) => receiver.receive(&buf[..len], from),
+                    Err(e) => warn!("Overlay control plane receive error: {}", e),
+                }
+            }
+        });
+        Ok(socket)
+    }
+
+    // 削除したネットワークの通知をやめる
+    pub fn unregister(&self, network: &Network) {
+        if let Ok(vni) = network_vni(network) {
+            let mut state = self.state.lock().unwrap();
+            state.networks.remove(&vni);
+            state.remote.remove(&vni);
+        }
+    }
+
+    // ネットワークに接続中のコンテナを他のホストへの通知に反映する
+    pub fn update_endpoints(&self, network: &Network) {
+        let vni = match network_vni(network) {
+            Ok(vni) => vni,
+            Err(_) => return,
+        };
+        let endpoints = network
+            .containers
+            .values()
+            .filter_map(|c| {
+                Some(AnnouncedEndpoint {
+                    ip: c.ip_address.parse().ok()?,
+                    mac: parse_mac(&c.mac_address)?,
+                })
+            })
+            .collect();
+
+        if let Some(overlay) = self.state.lock().unwrap().networks.get_mut(&vni) {
+            overlay.endpoints = endpoints;
+        }
+    }
+
+    // 他のホストで使われているアドレス
+    pub fn remote_addresses(&self, network: &Network) -> Vec<Ipv4Addr> {
+        let vni = match network_vni(network) {
+            Ok(vni) => vni,
+            Err(_) => return Vec::new(),
+        };
+        let state = self.state.lock().unwrap();
+        state
+            .remote
+            .get(&vni)
+            .map(|hosts| hosts.values().flat_map(|h| h.endpoints.iter().map(|e| e.ip)).collect())
+            .unwrap_or_default()
+    }
+
+    // ピアごとに送るメッセージと、送信に使う VTEP のアドレスのソケット
+    fn announcements(&self) -> Vec<(Arc<UdpSocket>, Ipv4Addr, Vec<u8>)> {
+        let state = self.state.lock().unwrap();
+        let mut messages = Vec::new();
+        for (vni, overlay) in &state.networks {
+            let Some(socket) = state.sockets.get(&overlay.local) else {
+                continue;
+            };
+            let announcement = Announcement {
+                vni: *vni,
+                vtep: overlay.local,
+                endpoints: overlay.endpoints.clone(),
+            };
+            let message = match serde_json::to_vec(&announcement) {
+                Ok(body) => match sign(&overlay.key, &body) {
+                    Ok(mac) => [mac, body].concat(),
+                    Err(e) => {
+                        warn!("Failed to sign overlay announcement: {}", e);
+                        continue;
+                    }
+                },
+                Err(_) => continue,
+            };
+            messages.extend(overlay.peers.iter().map(|peer| (Arc::clone(socket), *peer, message.clone())));
+        }
+        messages
+    }
+
+    // 受信したエンドポイントの差分を FDB に反映する
+    //
+    // ネットワークのピアから VTEP のアドレスで送られ、ネットワークの鍵で署名されたメッセージだけを受け入れる。
+    fn receive(&self, message: &[u8], from: SocketAddr) {
+        if message.len() < MAC_LEN {
+            warn!("Invalid overlay announcement from {}: too short", from);
+            return;
+        }
+        let (mac, body) = message.split_at(MAC_LEN);
+        let announcement = match serde_json::from_slice::<Announcement>(body) {
+            Ok(announcement) => announcement,
+            Err(e) => {
+                warn!("Invalid overlay announcement from {}: {}", from, e);
+                return;
+            }
+        };
+
+        let mut state = self.state.lock().unwrap();
+        let vxlan = match state.networks.get(&announcement.vni) {
+            Some(overlay) if overlay.local != announcement.vtep => {
+                let trusted = from.ip() == announcement.vtep && overlay.peers.contains(&announcement.vtep);
+                if !trusted || !verify(&overlay.key, body, mac) {
+                    warn!("Ignoring an unauthenticated overlay announcement for VNI {} from {}", announcement.vni, from);
+                    return;
+                }
+                overlay.vxlan.clone()
+            }
+            _ => return,
+        };
+
+        let hosts = state.remote.entry(announcement.vni).or_default();
+        let previous = hosts
+            .insert(
+                announcement.vtep,
+                RemoteHost {
+                    endpoints: announcement.endpoints.clone(),
+                    last_seen: Instant::now(),
+                },
+            )
+            .map(|host| host.endpoints)
+            .unwrap_or_default();
+        drop(state);
+
+        let removed: Vec<_> = previous
+            .iter()
+            .filter(|e| !announcement.endpoints.contains(e))
+            .cloned()
+            .collect();
+        let added: Vec<_> = announcement
+            .endpoints
+            .iter()
+            .filter(|e| !previous.contains(e))
+            .cloned()
+            .collect();
+        program_fdb(&vxlan, announcement.vtep, &added, &removed);
+    }
+
+    // 通知が途絶えたホストのエンドポイントを破棄する
+    fn expire(&self) {
+        let mut expired = Vec::new();
+        {
+            let mut state = self.state.lock().unwrap();
+            let vxlans: HashMap<u32, String> =
+                state.networks.iter().map(|(vni, o)| (*vni, o.vxlan.clone())).collect();
+            for (vni, hosts) in state.remote.iter_mut() {
+                hosts.retain(|vtep, host| {
+                    if host.last_seen.elapsed() < PEER_TIMEOUT {
+                        return true;
+                    }
+                    if let Some(vxlan) = vxlans.get(vni) {
+                        expired.push((vxlan.clone(), *vtep, host.endpoints.clone()));
+                    }
+                    false
+                });
+            }
+        }
+
+        for (vxlan, vtep, endpoints) in expired {
+            warn!("Overlay peer {} timed out", vtep);
+            program_fdb(&vxlan, vtep, &[], &endpoints);
+        }
+    }
+}
+
+fn program_fdb(vxlan: &str, vtep: Ipv4Addr, added: &[AnnouncedEndpoint], removed: &[AnnouncedEndpoint]) {
+    if added.is_empty() && removed.is_empty() {
+        return;
+    }
+    let index = match netlink::link_index(vxlan) {
+        Ok(index) => index,
+        Err(e) => {
+            warn!("VXLAN device {} not found: {}", vxlan, e);
+            return;
+        }
+    };
+
+    for endpoint in removed {
+        if let Err(e) = netlink::del_fdb(index, &endpoint.mac, vtep) {
+            warn!("Failed to remove FDB entry for {}: {}", endpoint.ip, e);
+        }
+    }
+    for endpoint in added {
+        if let Err(e) = netlink::add_fdb(index, &endpoint.mac, vtep) {
+            warn!("Failed to add FDB entry for {}: {}", endpoint.ip, e);
+        }
+    }
+}
+
+// メッセージの HMAC-SHA256
+fn sign(key: &[u8], body: &[u8]) -> Result<Vec<u8>, openssl::error::ErrorStack> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(body)?;
+    signer.sign_to_vec()
+}
+
+// HMAC を定数時間で比べる
+fn verify(key: &[u8], body: &[u8], mac: &[u8]) -> bool {
+    match sign(key, body) {
+        Ok(expected) => expected.len() == mac.len() && openssl::memcmp::eq(&expected, mac),
+        Err(_) => false,
+    }
+}
+
+fn parse_mac(mac: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<u8> = mac
+        .split(':')
+        .map(|b| u8::from_str_radix(b, 16))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    bytes.try_into().ok()
+}
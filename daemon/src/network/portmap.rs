@@ -0,0 +1,541 @@
+use rocker_core::{NetworkDriver, NetworkError, PortBinding, PortProtocol, RockerError};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, UdpSocket};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use super::{bridge_name, endpoint::veth_name, Manager};
+
+// nftables のテーブル名と iptables のチェイン名
+const NFT_TABLE: &str = "rocker";
+const IPTABLES_CHAIN: &str = "ROCKER";
+const IPTABLES_POSTROUTING_CHAIN: &str = "ROCKER-POSTROUTING";
+const IPTABLES_ISOLATION_CHAIN: &str = "ROCKER-ISOLATION";
+
+// ホスト側のポートを割り当てる範囲（カーネルのエフェメラルポートの範囲を使う）
+const LOCAL_PORT_RANGE: &str = "/proc/sys/net/ipv4/ip_local_port_range";
+const DEFAULT_LOCAL_PORT_RANGE: (u16, u16) = (32768, 60999);
+
+// NAT ルールを設定するコマンド（nftables を優先する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Firewall {
+    Nftables,
+    Iptables,
+}
+
+// コンテナに公開したポート（binding のホスト側のポートは割り当て済み）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortMapping {
+    pub container_ip: Ipv4Addr,
+    pub binding: PortBinding,
+}
+
+impl PortMapping {
+    fn host_port(&self) -> u16 {
+        self.binding.host_port.unwrap_or_default()
+    }
+}
+
+// 利用できるファイアウォールのコマンドを調べる
+pub async fn detect_firewall() -> Option<Firewall> {
+    for (firewall, program) in [(Firewall::Nftables, "nft"), (Firewall::Iptables, "iptables")] {
+        let status = Command::new(program)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+        if matches!(status, Ok(status) if status.success()) {
+            info!("Using {} for port publishing", program);
+            return Some(firewall);
+        }
+    }
+
+    warn!("Neither nft nor iptables found, port publishing is disabled");
+    None
+}
+
+impl Manager {
+    // 保存済みのポートの公開状況を読み込む
+    pub(super) async fn load_port_mappings(&mut self) -> Result<(), RockerError> {
+        let dir = self.portmap_dir();
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let container_id = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(id) if path.extension().and_then(|e| e.to_str()) == Some("json") => id.to_string(),
+                _ => continue,
+            };
+
+            let content = tokio::fs::read_to_string(&path).await?;
+            match serde_json::from_str::<Vec<PortMapping>>(&content) {
+                Ok(mappings) => {
+                    self.port_mappings.insert(container_id, mappings);
+                }
+                Err(e) => warn!("Skipping invalid port mappings {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(())
+    }
+
+    // コンテナのポートをホストに公開し、ホスト側のポートを割り当てた結果を返す
+    // （コンテナは network に接続済みであること）
+    pub async fn publish_ports(
+        &mut self,
+        container_id: &str,
+        network_id_or_name: &str,
+        bindings: &[PortBinding],
+    ) -> Result<Vec<PortBinding>, RockerError> {
+        if bindings.is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.firewall.is_none() && !self.dry_run {
+            return Err(NetworkError::Connect("Port publishing requires nft or iptables".to_string()).into());
+        }
+
+        let network = self.get(network_id_or_name)?;
+        if network.config.internal {
+            return Err(NetworkError::Connect(format!(
+                "Cannot publish ports of a container on internal network {}",
+                network.name
+            ))
+            .into());
+        }
+        let container_ip: Ipv4Addr = network
+            .containers
+            .get(container_id)
+            .and_then(|c| c.ip_address.parse().ok())
+            .ok_or_else(|| {
+                NetworkError::Connect(format!("Container {} is not connected to {}", container_id, network.name))
+            })?;
+        let host_veth = veth_name(container_id, &network.id);
+
+        let allocated = self.allocated_ports(Some(container_id));
+        // 明示的に指定されたポートを先に確保し、残りに空いているポートを割り当てる
+        let mut assigned = reserve_explicit(bindings, &allocated)?;
+        for binding in bindings.iter().filter(|b| b.host_port.is_none()) {
+            let host_port = free_host_port(binding, &allocated, &assigned)?;
+            assigned.push(PortBinding {
+                host_port: Some(host_port),
+                ..binding.clone()
+            });
+        }
+
+        // コンテナが自身の公開ポートにホストのアドレスで接続できるよう、ブリッジのヘアピンを有効にする
+        // （--dry-run では veth が無い）
+        if !self.dry_run {
+            let hairpin = format!("/sys/class/net/{}/brport/hairpin_mode", host_veth);
+            if let Err(e) = tokio::fs::write(&hairpin, "1").await {
+                warn!("Failed to enable hairpin mode on {}: {}", host_veth, e);
+            }
+        }
+
+        let mappings = assigned
+            .iter()
+            .map(|binding| PortMapping {
+                container_ip,
+                binding: binding.clone(),
+            })
+            .collect();
+        self.port_mappings.insert(container_id.to_string(), mappings);
+        self.save_port_mappings(container_id).await?;
+
+        if let Err(e) = self.apply_port_rules().await {
+            self.port_mappings.remove(container_id);
+            self.save_port_mappings(container_id).await?;
+            return Err(e.into());
+        }
+
+        info!("Published {} ports of container {}", assigned.len(), container_id);
+        Ok(assigned)
+    }
+
+    // 作成するコンテナの明示的に指定されたホスト側のポートが、公開済みのポートとも、作成済みでまだ起動していない
+    // コンテナのポート（created）とも、他のプロセスとも衝突しないことを確かめる（起動までに他で使われた場合は
+    // publish_ports で改めて失敗する）
+    //
+    // replacing は置き換えるコンテナの ID で、その公開ポートとは衝突しない。
+    pub fn check_ports(
+        &self,
+        bindings: &[PortBinding],
+        created: &[PortBinding],
+        replacing: Option<&str>,
+    ) -> Result<(), NetworkError> {
+        let mut allocated = self.allocated_ports(replacing);
+        allocated.extend(created);
+        reserve_explicit(bindings, &allocated).map(|_| ())
+    }
+
+    // except 以外のコンテナが公開しているポート
+    fn allocated_ports(&self, except: Option<&str>) -> Vec<&PortBinding> {
+        self.port_mappings
+            .iter()
+            .filter(|(id, _)| Some(id.as_str()) != except)
+            .flat_map(|(_, mappings)| mappings.iter().map(|mapping| &mapping.binding))
+            .collect()
+    }
+
+    // コンテナのポートの公開をやめる
+    pub async fn unpublish_ports(&mut self, container_id: &str) -> Result<(), RockerError> {
+        if self.port_mappings.remove(container_id).is_none() {
+            return Ok(());
+        }
+
+        self.save_port_mappings(container_id).await?;
+        self.apply_port_rules().await?;
+        Ok(())
+    }
+
+    // 現在の状態から NAT ルールと内部ネットワークの隔離ルールを全て作り直す
+    //
+    // --dry-run では公開ポートを記録するだけでルールは作らない。
+    pub(super) async fn apply_port_rules(&self) -> Result<(), NetworkError> {
+        if self.dry_run {
+            return Ok(());
+        }
+        let mappings: Vec<&PortMapping> = self.port_mappings.values().flatten().collect();
+        let masquerade: Vec<(String, String)> = self
+            .networks
+            .values()
+            .filter(|n| n.driver == NetworkDriver::Bridge)
+            .filter(|n| n.config.enable_ip_masquerade && !n.config.internal)
+            .map(|n| (n.config.subnet.clone(), bridge_name(n)))
+            .collect();
+        // 内部ネットワークのブリッジは外部のインターフェースとの間で転送させない
+        let isolated: Vec<String> = self
+            .networks
+            .values()
+            .filter(|n| matches!(n.driver, NetworkDriver::Bridge | NetworkDriver::Overlay) && n.config.internal)
+            .map(bridge_name)
+            .collect();
+
+        match self.firewall {
+            Some(Firewall::Nftables) => apply_nftables(&nftables_ruleset(&mappings, &masquerade, &isolated)).await,
+            Some(Firewall::Iptables) => apply_iptables(&mappings, &masquerade, &isolated).await,
+            None => {
+                if !isolated.is_empty() {
+                    warn!("Neither nft nor iptables found, internal networks are not isolated");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // 使っていない方のファイアウォールに以前のデーモンが残したルールを削除する
+    // （nft と iptables のどちらを使うかは起動時の環境で変わりうる）
+    pub(super) async fn remove_foreign_rules(&self) {
+        match self.firewall {
+            Some(Firewall::Nftables) => {
+                let jumps: [(&str, &[&str]); 4] = [
+                    ("nat", &["PREROUTING", "-m", "addrtype", "--dst-type", "LOCAL", "-j", IPTABLES_CHAIN]),
+                    (
+                        "nat",
+                        &["OUTPUT", "!", "-d", "127.0.0.0/8", "-m", "addrtype", "--dst-type", "LOCAL", "-j", IPTABLES_CHAIN],
+                    ),
+                    ("nat", &["POSTROUTING", "-j", IPTABLES_POSTROUTING_CHAIN]),
+                    ("filter", &["FORWARD", "-j", IPTABLES_ISOLATION_CHAIN]),
+                ];
+                for (table, jump) in jumps {
+                    let delete: Vec<&str> = ["-t", table, "-D"].iter().chain(jump.iter()).copied().collect();
+                    // 同じジャンプが重複して追加されている場合に備えて、無くなるまで削除する
+                    while iptables(&delete).await.is_ok() {}
+                }
+                for (table, chain) in [
+                    ("nat", IPTABLES_CHAIN),
+                    ("nat", IPTABLES_POSTROUTING_CHAIN),
+                    ("filter", IPTABLES_ISOLATION_CHAIN),
+                ] {
+                    if iptables(&["-t", table, "-F", chain]).await.is_ok() {
+                        let _ = iptables(&["-t", table, "-X", chain]).await;
+                        info!("Removed stale iptables chain {}", chain);
+                    }
+                }
+            }
+            Some(Firewall::Iptables) => {
+                let deleted = Command::new("nft")
+                    .args(["delete", "table", "ip", NFT_TABLE])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .await;
+                if matches!(deleted, Ok(status) if status.success()) {
+                    info!("Removed stale nftables table {}", NFT_TABLE);
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn portmap_dir(&self) -> std::path::PathBuf {
+        self.state_dir.join("portmap")
+    }
+
+    pub(super) async fn save_port_mappings(&self, container_id: &str) -> Result<(), RockerError> {
+        let path = self.portmap_dir().join(format!("{}.json", container_id));
+        match self.port_mappings.get(container_id) {
+            Some(mappings) => tokio::fs::write(&path, serde_json::to_vec_pretty(mappings)?).await?,
+            None => {
+                if path.exists() {
+                    tokio::fs::remove_file(&path).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// 明示的に指定されたホスト側のポートを確保する（公開済みのポート、同時に指定したポート、
+// ホストの他のプロセスが待ち受けているポートとは衝突する）
+fn reserve_explicit(bindings: &[PortBinding], allocated: &[&PortBinding]) -> Result<Vec<PortBinding>, NetworkError> {
+    let mut assigned: Vec<PortBinding> = Vec::new();
+    for binding in bindings.iter().filter(|b| b.host_port.is_some()) {
+        let published = allocated.iter().copied().chain(assigned.iter()).any(|b| conflicts(b, binding));
+        if published || port_in_use(binding) {
+            return Err(NetworkError::PortAllocated(binding.to_string()));
+        }
+        assigned.push(binding.clone());
+    }
+    Ok(assigned)
+}
+
+// エフェメラルポートの範囲から、公開済みのポートとも他のプロセスとも重ならないポートを選ぶ
+fn free_host_port(binding: &PortBinding, allocated: &[&PortBinding], assigned: &[PortBinding]) -> Result<u16, NetworkError> {
+    let (start, end) = std::fs::read_to_string(LOCAL_PORT_RANGE)
+        .ok()
+        .and_then(|range| {
+            let mut ports = range.split_whitespace().map(|p| p.parse::<u16>());
+            match (ports.next(), ports.next()) {
+                (Some(Ok(start)), Some(Ok(end))) if start <= end => Some((start, end)),
+                _ => None,
+            }
+        })
+        .unwrap_or(DEFAULT_LOCAL_PORT_RANGE);
+
+    for port in start..=end {
+        let candidate = PortBinding {
+            host_port: Some(port),
+            ..binding.clone()
+        };
+        let in_use = allocated.iter().copied().chain(assigned.iter()).any(|b| conflicts(b, &candidate));
+        if !in_use && port_available(binding.host_ip, port, binding.protocol) {
+            return Ok(port);
+        }
+    }
+    Err(NetworkError::Connect(format!("No free host port in {}-{} for {}", start, end, binding)))
+}
+
+// ホストの他のプロセスが使っていないかを実際に bind して確かめる
+fn port_available(host_ip: Option<Ipv4Addr>, port: u16, protocol: PortProtocol) -> bool {
+    let address = SocketAddrV4::new(host_ip.unwrap_or(Ipv4Addr::UNSPECIFIED), port);
+    match protocol {
+        PortProtocol::Tcp => TcpListener::bind(address).is_ok(),
+        PortProtocol::Udp => UdpSocket::bind(address).is_ok(),
+        PortProtocol::Sctp => true,
+    }
+}
+
+// ホストの他のプロセスが待ち受けているか（アドレスが無い・権限が無いなど、使用中以外の失敗は問わない）
+fn port_in_use(binding: &PortBinding) -> bool {
+    let address = SocketAddrV4::new(
+        binding.host_ip.unwrap_or(Ipv4Addr::UNSPECIFIED),
+        binding.host_port.unwrap_or_default(),
+    );
+    let bound = match binding.protocol {
+        PortProtocol::Tcp => TcpListener::bind(address).map(drop),
+        PortProtocol::Udp => UdpSocket::bind(address).map(drop),
+        PortProtocol::Sctp => Ok(()),
+    };
+    matches!(bound, Err(e) if e.kind() == std::io::ErrorKind::AddrInUse)
+}
+
+// 同じプロトコル・ポートで、待ち受けるアドレスが重なる場合は衝突する
+fn conflicts(a: &PortBinding, b: &PortBinding) -> bool {
+    a.protocol == b.protocol
+        && a.host_port == b.host_port
+        && (a.host_ip.is_none() || b.host_ip.is_none() || a.host_ip == b.host_ip)
+}
+
+// テーブルごと置き換える nftables のルールセット
+fn nftables_ruleset(mappings: &[&PortMapping], masquerade: &[(String, String)], isolated: &[String]) -> String {
+    let mut portmap = String::new();
+    let mut postrouting = String::new();
+    for mapping in mappings {
+        let binding = &mapping.binding;
+        let daddr = match binding.host_ip {
+            Some(host_ip) => format!("ip daddr {} ", host_ip),
+            None => String::new(),
+        };
+        portmap.push_str(&format!(
+            "\t\t{}{} dport {} dnat to {}:{}\n",
+            daddr,
+            binding.protocol,
+            mapping.host_port(),
+            mapping.container_ip,
+            binding.container_port
+        ));
+        // ヘアピン接続の戻りのパケットがブリッジを経由するよう送信元を書き換える
+        postrouting.push_str(&format!(
+            "\t\tip saddr {ip} ip daddr {ip} {} dport {} masquerade\n",
+            binding.protocol,
+            binding.container_port,
+            ip = mapping.container_ip
+        ));
+    }
+    for (subnet, bridge) in masquerade {
+        postrouting.push_str(&format!("\t\tip saddr {} oifname != \"{}\" masquerade\n", subnet, bridge));
+    }
+    // 同じブリッジ内の通信とコンテナ内の組み込み DNS（ループバック）には影響しない
+    let mut forward = String::new();
+    for bridge in isolated {
+        forward.push_str(&format!("\t\tiifname \"{bridge}\" oifname != \"{bridge}\" drop\n", bridge = bridge));
+        forward.push_str(&format!("\t\toifname \"{bridge}\" iifname != \"{bridge}\" drop\n", bridge = bridge));
+    }
+
+    format!(
+        "table ip {table}\n\
+         delete table ip {table}\n\
+         table ip {table} {{\n\
+         \tchain prerouting {{\n\
+         \t\ttype nat hook prerouting priority dstnat; policy accept;\n\
+         \t\tfib daddr type local jump portmap\n\
+         \t}}\n\
+         \tchain output {{\n\
+         \t\ttype nat hook output priority -100; policy accept;\n\
+         \t\tip daddr != 127.0.0.0/8 fib daddr type local jump portmap\n\
+         \t}}\n\
+         \tchain portmap {{\n\
+         {portmap}\
+         \t}}\n\
+         \tchain postrouting {{\n\
+         \t\ttype nat hook postrouting priority srcnat; policy accept;\n\
+         {postrouting}\
+         \t}}\n\
+         \tchain forward {{\n\
+         \t\ttype filter hook forward priority filter; policy accept;\n\
+         {forward}\
+         \t}}\n\
+         }}\n",
+        table = NFT_TABLE,
+        portmap = portmap,
+        postrouting = postrouting,
+        forward = forward
+    )
+}
+
+async fn apply_nftables(ruleset: &str) -> Result<(), NetworkError> {
+    let mut child = Command::new("nft")
+        .args(["-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| NetworkError::Connect(format!("Failed to run nft: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(ruleset.as_bytes())
+            .await
+            .map_err(|e| NetworkError::Connect(format!("Failed to run nft: {}", e)))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| NetworkError::Connect(format!("Failed to run nft: {}", e)))?;
+    if !output.status.success() {
+        return Err(NetworkError::Connect(format!(
+            "nft failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+// nat テーブルと filter テーブルに専用のチェインを用意し、中身を入れ替える
+async fn apply_iptables(
+    mappings: &[&PortMapping],
+    masquerade: &[(String, String)],
+    isolated: &[String],
+) -> Result<(), NetworkError> {
+    for chain in [IPTABLES_CHAIN, IPTABLES_POSTROUTING_CHAIN] {
+        // 既にチェインが存在する場合は -N が失敗するだけなので無視する
+        let _ = iptables(&["-t", "nat", "-N", chain]).await;
+        iptables(&["-t", "nat", "-F", chain]).await?;
+    }
+
+    let jumps: [&[&str]; 3] = [
+        &["PREROUTING", "-m", "addrtype", "--dst-type", "LOCAL", "-j", IPTABLES_CHAIN],
+        &["OUTPUT", "!", "-d", "127.0.0.0/8", "-m", "addrtype", "--dst-type", "LOCAL", "-j", IPTABLES_CHAIN],
+        &["POSTROUTING", "-j", IPTABLES_POSTROUTING_CHAIN],
+    ];
+    for jump in jumps {
+        let check: Vec<&str> = ["-t", "nat", "-C"].iter().chain(jump.iter()).copied().collect();
+        if iptables(&check).await.is_err() {
+            let append: Vec<&str> = ["-t", "nat", "-A"].iter().chain(jump.iter()).copied().collect();
+            iptables(&append).await?;
+        }
+    }
+
+    for mapping in mappings {
+        let binding = &mapping.binding;
+        let protocol = binding.protocol.to_string();
+        let host_port = mapping.host_port().to_string();
+        let container_port = binding.container_port.to_string();
+        let container_ip = mapping.container_ip.to_string();
+        let destination = format!("{}:{}", container_ip, container_port);
+
+        let mut dnat = vec!["-t", "nat", "-A", IPTABLES_CHAIN];
+        let host_ip = binding.host_ip.map(|ip| ip.to_string());
+        if let Some(host_ip) = &host_ip {
+            dnat.extend(["-d", host_ip.as_str()]);
+        }
+        dnat.extend(["-p", &protocol, "--dport", &host_port, "-j", "DNAT", "--to-destination", &destination]);
+        iptables(&dnat).await?;
+
+        iptables(&[
+            "-t", "nat", "-A", IPTABLES_POSTROUTING_CHAIN, "-s", &container_ip, "-d", &container_ip, "-p", &protocol,
+            "--dport", &container_port, "-j", "MASQUERADE",
+        ])
+        .await?;
+    }
+
+    for (subnet, bridge) in masquerade {
+        iptables(&["-t", "nat", "-A", IPTABLES_POSTROUTING_CHAIN, "-s", subnet, "!", "-o", bridge, "-j", "MASQUERADE"])
+            .await?;
+    }
+
+    let _ = iptables(&["-t", "filter", "-N", IPTABLES_ISOLATION_CHAIN]).await;
+    iptables(&["-t", "filter", "-F", IPTABLES_ISOLATION_CHAIN]).await?;
+    // 他のツールが FORWARD に追加した ACCEPT より先に評価されるよう先頭に挿入する
+    if iptables(&["-t", "filter", "-C", "FORWARD", "-j", IPTABLES_ISOLATION_CHAIN]).await.is_err() {
+        iptables(&["-t", "filter", "-I", "FORWARD", "1", "-j", IPTABLES_ISOLATION_CHAIN]).await?;
+    }
+    for bridge in isolated {
+        let bridge = bridge.as_str();
+        iptables(&["-t", "filter", "-A", IPTABLES_ISOLATION_CHAIN, "-i", bridge, "!", "-o", bridge, "-j", "DROP"]).await?;
+        iptables(&["-t", "filter", "-A", IPTABLES_ISOLATION_CHAIN, "-o", bridge, "!", "-i", bridge, "-j", "DROP"]).await?;
+    }
+
+    Ok(())
+}
+
+async fn iptables(args: &[&str]) -> Result<(), NetworkError> {
+    let output = Command::new("iptables")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| NetworkError::Connect(format!("Failed to run iptables: {}", e)))?;
+    if !output.status.success() {
+        return Err(NetworkError::Connect(format!(
+            "iptables {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
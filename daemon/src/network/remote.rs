@@ -0,0 +1,172 @@
+use async_trait::async_trait;
+use hyper::header::{CONTENT_TYPE, HOST};
+use hyper::{Body, Method, Request};
+use rocker_core::{format_mac_address, Network, NetworkError};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::net::UnixStream;
+use tracing::warn;
+
+use super::{Driver, Endpoint};
+
+// プラグインのソケットを置くディレクトリの既定値
+pub const DEFAULT_PLUGIN_DIR: &str = "/run/rocker/plugins";
+// プラグインのソケットの拡張子（ファイル名の残りがドライバ名になる）
+const SOCKET_EXTENSION: &str = "sock";
+// プラグインの応答を待つ時間
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 失敗した操作の応答
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+// ツリー外のドライバ（プラグイン）に Unix ソケットの HTTP で操作を委ねるドライバ
+//
+// 操作ごとに POST /NetworkDriver.<操作名> で JSON を送る。2xx 以外の応答は本文の {"error": "..."} を
+// 失敗の理由として返す。エンドポイントのアドレスは Manager が割り当てたものを渡す。
+pub struct RemoteDriver {
+    name: String,
+    socket: PathBuf,
+}
+
+impl RemoteDriver {
+    pub fn new(name: &str, socket: PathBuf) -> Self {
+        RemoteDriver { name: name.to_string(), socket }
+    }
+
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    async fn call(&self, operation: &str, body: Value) -> Result<(), String> {
+        let stream = UnixStream::connect(&self.socket)
+            .await
+            .map_err(|e| format!("cannot connect to the {} plugin at {}: {}", self.name, self.socket.display(), e))?;
+        let (mut sender, connection) = hyper::client::conn::handshake(stream).await.map_err(|e| e.to_string())?;
+        tokio::spawn(async move {
+            let _ = connection.await;
+        });
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/NetworkDriver.{}", operation))
+            .header(HOST, "localhost")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .map_err(|e| e.to_string())?;
+
+        let response = async {
+            let response = sender.send_request(request).await?;
+            let status = response.status();
+            let body = hyper::body::to_bytes(response.into_body()).await?;
+            Ok::<_, hyper::Error>((status, body))
+        };
+        let (status, body) = tokio::time::timeout(REQUEST_TIMEOUT, response)
+            .await
+            .map_err(|_| format!("the {} plugin did not answer {} within {}s", self.name, operation, REQUEST_TIMEOUT.as_secs()))?
+            .map_err(|e| format!("{} plugin: {}", self.name, e))?;
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let message = match serde_json::from_slice::<ErrorResponse>(&body) {
+            Ok(response) => response.error,
+            Err(_) => String::from_utf8_lossy(&body).trim().to_string(),
+        };
+        if message.is_empty() {
+            Err(format!("{} plugin: {} failed with {}", self.name, operation, status))
+        } else {
+            Err(format!("{} plugin: {}", self.name, message))
+        }
+    }
+}
+
+fn endpoint_json(endpoint: &Endpoint) -> Value {
+    json!({
+        "container_id": endpoint.container_id,
+        "interface": endpoint.interface,
+        "address": endpoint.address,
+        "prefix_len": endpoint.prefix_len,
+        "mac_address": format_mac_address(&endpoint.mac_address),
+        "mtu": endpoint.mtu,
+        "shaping": endpoint.shaping,
+    })
+}
+
+#[async_trait]
+impl Driver for RemoteDriver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn create_network(&self, network: &Network) -> Result<(), NetworkError> {
+        self.call("CreateNetwork", json!({ "network": network })).await.map_err(NetworkError::Create)
+    }
+
+    async fn delete_network(&self, network: &Network) -> Result<(), NetworkError> {
+        self.call("DeleteNetwork", json!({ "network": network })).await.map_err(NetworkError::Remove)
+    }
+
+    async fn create_endpoint(&self, network: &Network, endpoint: &Endpoint) -> Result<(), NetworkError> {
+        self.call("CreateEndpoint", json!({ "network": network, "endpoint": endpoint_json(endpoint) }))
+            .await
+            .map_err(NetworkError::Connect)
+    }
+
+    async fn delete_endpoint(&self, network: &Network, container_id: &str) -> Result<(), NetworkError> {
+        self.call("DeleteEndpoint", json!({ "network": network, "container_id": container_id }))
+            .await
+            .map_err(NetworkError::Disconnect)
+    }
+
+    async fn join(&self, network: &Network, endpoint: &Endpoint, pid: i32) -> Result<(), NetworkError> {
+        self.call("Join", json!({ "network": network, "endpoint": endpoint_json(endpoint), "pid": pid }))
+            .await
+            .map_err(NetworkError::Connect)
+    }
+
+    async fn leave(&self, network: &Network, container_id: &str) -> Result<(), NetworkError> {
+        self.call("Leave", json!({ "network": network, "container_id": container_id }))
+            .await
+            .map_err(NetworkError::Disconnect)
+    }
+}
+
+// ディレクトリにある <ドライバ名>.sock のソケットをプラグインとして見つける（ディレクトリが無ければ空）
+pub async fn discover(dir: &Path) -> std::io::Result<Vec<RemoteDriver>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut drivers = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(SOCKET_EXTENSION) {
+            continue;
+        }
+        // ソケットへのシンボリックリンクも使えるようにリンク先を見る
+        match tokio::fs::metadata(&path).await {
+            Ok(metadata) if metadata.file_type().is_socket() => {}
+            Ok(_) => {
+                warn!("Skipping network plugin {}: not a socket", path.display());
+                continue;
+            }
+            Err(e) => {
+                warn!("Skipping network plugin {}: {}", path.display(), e);
+                continue;
+            }
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        drivers.push(RemoteDriver::new(name, path.clone()));
+    }
+    drivers.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(drivers)
+}
@@ -0,0 +1,80 @@
+use rocker_core::{NetworkError, TrafficShaping};
+use std::process::Stdio;
+use tokio::process::Command;
+
+// トークンバケットのバーストは 10ms 分の転送量とし、MTU を下回らないようにする
+const BURST_INTERVALS_PER_SECOND: u64 = 100;
+const MIN_BURST_BYTES: u64 = 1600;
+const TBF_LATENCY: &str = "50ms";
+
+// ホスト側の veth に tc で帯域制限と優先度を設定する
+//
+// コンテナが受信するトラフィックは veth の送信側なので tbf で整形し、コンテナが送信する
+// トラフィックは veth の受信側なので ingress qdisc の police で制限して skbedit で優先度を付ける。
+pub(super) async fn apply_shaping(host_veth: &str, shaping: &TrafficShaping) -> Result<(), NetworkError> {
+    if let Some(rate) = shaping.ingress_rate {
+        let burst = burst_bytes(rate).to_string();
+        let rate = format!("{}bit", rate);
+        tc(&[
+            "qdisc", "replace", "dev", host_veth, "root", "tbf", "rate", &rate, "burst", &burst, "latency", TBF_LATENCY,
+        ])
+        .await?;
+    }
+
+    if shaping.egress_rate.is_none() && shaping.priority.is_none() {
+        return Ok(());
+    }
+    tc(&["qdisc", "replace", "dev", host_veth, "handle", "ffff:", "ingress"]).await?;
+
+    // 全てのパケットに一致する u32 フィルタ（matchall より古いカーネルでも使える）
+    let mut filter: Vec<String> = [
+        "filter", "add", "dev", host_veth, "parent", "ffff:", "protocol", "all", "u32", "match", "u32", "0", "0",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    if let Some(rate) = shaping.egress_rate {
+        // 優先度の設定に進めるよう、制限内のパケットは pipe で次のアクションに渡す
+        filter.extend([
+            "action".to_string(),
+            "police".to_string(),
+            "rate".to_string(),
+            format!("{}bit", rate),
+            "burst".to_string(),
+            burst_bytes(rate).to_string(),
+            "conform-exceed".to_string(),
+            "drop/pipe".to_string(),
+        ]);
+    }
+    if let Some(priority) = shaping.priority {
+        filter.extend([
+            "action".to_string(),
+            "skbedit".to_string(),
+            "priority".to_string(),
+            priority.to_string(),
+        ]);
+    }
+    let filter: Vec<&str> = filter.iter().map(String::as_str).collect();
+    tc(&filter).await
+}
+
+fn burst_bytes(rate: u64) -> u64 {
+    (rate / 8 / BURST_INTERVALS_PER_SECOND).max(MIN_BURST_BYTES)
+}
+
+async fn tc(args: &[&str]) -> Result<(), NetworkError> {
+    let output = Command::new("tc")
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| NetworkError::Connect(format!("Failed to run tc: {}", e)))?;
+    if !output.status.success() {
+        return Err(NetworkError::Connect(format!(
+            "tc {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
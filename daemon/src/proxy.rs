@@ -0,0 +1,99 @@
+use reqwest::{ClientBuilder, NoProxy, Proxy};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::DAEMON_CONFIG_PATH;
+
+// ビルドの RUN で ARG として宣言しなくても使える、プロキシのビルド引数
+pub const PROXY_BUILD_ARGS: [&str; 10] = [
+    "HTTP_PROXY",
+    "http_proxy",
+    "HTTPS_PROXY",
+    "https_proxy",
+    "FTP_PROXY",
+    "ftp_proxy",
+    "NO_PROXY",
+    "no_proxy",
+    "ALL_PROXY",
+    "all_proxy",
+];
+
+// デーモンの設定ファイルの proxies（レジストリへのアクセスと、ビルド・コンテナの既定値に使う）
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProxyConfig {
+    #[serde(rename = "http-proxy")]
+    pub http_proxy: Option<String>,
+    #[serde(rename = "https-proxy")]
+    pub https_proxy: Option<String>,
+    // カンマ区切りの、プロキシを通さないホストとドメイン
+    #[serde(rename = "no-proxy")]
+    pub no_proxy: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DaemonConfig {
+    #[serde(default)]
+    proxies: ProxyConfig,
+}
+
+// デーモンの設定ファイルからプロキシの設定を読む（無ければ設定しない）
+pub fn load_config() -> ProxyConfig {
+    let content = match std::fs::read_to_string(DAEMON_CONFIG_PATH) {
+        Ok(content) => content,
+        Err(_) => return ProxyConfig::default(),
+    };
+    match serde_json::from_str::<DaemonConfig>(&content) {
+        Ok(config) => config.proxies,
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", DAEMON_CONFIG_PATH, e);
+            ProxyConfig::default()
+        }
+    }
+}
+
+impl ProxyConfig {
+    // 環境変数として渡す値（HTTP_PROXY と http_proxy のように大文字と小文字の両方）
+    pub fn env(&self) -> Vec<(String, String)> {
+        let values = [
+            ("HTTP_PROXY", &self.http_proxy),
+            ("HTTPS_PROXY", &self.https_proxy),
+            ("NO_PROXY", &self.no_proxy),
+        ];
+        let mut env = Vec::new();
+        for (name, value) in values {
+            if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+                env.push((name.to_string(), value.clone()));
+                env.push((name.to_lowercase(), value.clone()));
+            }
+        }
+        env
+    }
+
+    // デーモン自身の HTTP クライアント（レジストリや ADD の URL）にプロキシを設定する
+    //
+    // 設定ファイルに無ければ reqwest の既定どおり、デーモンの環境変数のプロキシを使う。
+    pub fn apply(&self, builder: ClientBuilder) -> Result<ClientBuilder, reqwest::Error> {
+        if self.http_proxy.is_none() && self.https_proxy.is_none() {
+            return Ok(builder);
+        }
+        let no_proxy = self.no_proxy.as_deref().and_then(NoProxy::from_string);
+        let mut builder = builder.no_proxy();
+        if let Some(url) = self.http_proxy.as_deref().filter(|url| !url.is_empty()) {
+            builder = builder.proxy(Proxy::http(url)?.no_proxy(no_proxy.clone()));
+        }
+        if let Some(url) = self.https_proxy.as_deref().filter(|url| !url.is_empty()) {
+            builder = builder.proxy(Proxy::https(url)?.no_proxy(no_proxy));
+        }
+        Ok(builder)
+    }
+}
+
+// env に無いプロキシの環境変数を加える（大文字か小文字のどちらかが既にあれば加えない）
+pub fn add_env(env: &mut HashMap<String, String>, proxy: &[(String, String)]) {
+    let is_set = |env: &HashMap<String, String>, name: &str| {
+        env.contains_key(&name.to_uppercase()) || env.contains_key(&name.to_lowercase())
+    };
+    let missing: Vec<_> = proxy.iter().filter(|(name, _)| !is_set(env, name)).cloned().collect();
+    env.extend(missing);
+}
@@ -0,0 +1,53 @@
+#[cfg(target_os = "linux")]
+use nix::libc;
+use std::fs::File;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+use tracing::warn;
+
+// cp に渡す reflink のオプション（cp が対応していなければ None）
+//
+// GNU coreutils の cp は --reflink=auto で reflink を使い、できなければ copy_file_range でコピーする。
+// busybox などの cp はこのオプションを知らないため、付けずに通常のコピーにする。
+pub fn cp_arg() -> Option<&'static str> {
+    static SUPPORTED: OnceLock<bool> = OnceLock::new();
+    let supported = *SUPPORTED.get_or_init(|| {
+        let supported = Command::new("cp")
+            .arg("--help")
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout).contains("--reflink")
+                    || String::from_utf8_lossy(&output.stderr).contains("--reflink")
+            })
+            .unwrap_or(false);
+        if !supported {
+            warn!("cp does not support --reflink; files are copied without sharing their data");
+        }
+        supported
+    });
+    supported.then_some("--reflink=auto")
+}
+
+// ファイルを target にコピーし、モードを写す（所有者と時刻は呼び出し側が写す）
+//
+// 同じ btrfs・XFS などの上なら FICLONE でデータを共有し、できなければ std::fs::copy
+// （copy_file_range、それもできなければ読み書き）でコピーする。
+pub fn copy_file(source: &Path, target: &Path) -> io::Result<()> {
+    let from = File::open(source)?;
+    let to = File::create(target)?;
+    // FICLONE は target の中身を source と同じエクステントで置き換える（Linux 以外では常にコピーする）
+    #[cfg(target_os = "linux")]
+    let cloned = unsafe { libc::ioctl(to.as_raw_fd(), libc::FICLONE, from.as_raw_fd()) } == 0;
+    #[cfg(not(target_os = "linux"))]
+    let cloned = false;
+    if !cloned {
+        drop(to);
+        std::fs::copy(source, target)?;
+        return Ok(());
+    }
+    to.set_permissions(from.metadata()?.permissions())
+}
@@ -0,0 +1,135 @@
+use rocker_core::{format_size, parse_memory_size, ContainerError, ResourceLimits};
+use serde::Deserialize;
+use tracing::warn;
+
+use crate::DAEMON_CONFIG_PATH;
+
+// デーモンの設定ファイルの resources（コンテナに割り当てるホストの CPU とメモリ、超える要求を拒否するか）
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ResourcesConfig {
+    // 割り当てる CPU の数（無ければホストの CPU の数）
+    cpus: Option<f64>,
+    // 割り当てるメモリ（"12g" など。無ければホストのメモリ）
+    memory: Option<String>,
+    // 予約の合計が割り当てを超えるコンテナの作成と起動を拒否する
+    #[serde(default, rename = "reject-oversubscription")]
+    reject_oversubscription: bool,
+}
+
+#[derive(Deserialize)]
+struct DaemonConfig {
+    #[serde(default)]
+    resources: ResourcesConfig,
+}
+
+// コンテナに割り当てられる CPU とメモリ
+#[derive(Debug, Clone)]
+pub struct Capacity {
+    pub cpus: f64,
+    pub memory_bytes: u64,
+    // 超える予約を拒否するか（しなければ info で報告するだけ）
+    pub enforce: bool,
+}
+
+impl Default for Capacity {
+    fn default() -> Self {
+        Capacity {
+            cpus: host_cpus(),
+            memory_bytes: host_memory_bytes(),
+            enforce: false,
+        }
+    }
+}
+
+// コンテナが予約している CPU とメモリの合計
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Reserved {
+    pub cpus: f64,
+    pub memory_bytes: u64,
+}
+
+impl Reserved {
+    pub fn add(&mut self, limits: &ResourceLimits) {
+        self.cpus += limits.reserved_cpus();
+        self.memory_bytes += limits.reserved_memory_bytes();
+    }
+}
+
+impl Capacity {
+    // 既に予約されている分に加えて limits を予約できるかを確かめる（拒否しない設定なら常に通す）
+    pub fn admit(&self, reserved: Reserved, limits: &ResourceLimits) -> Result<(), ContainerError> {
+        if !self.enforce {
+            return Ok(());
+        }
+        let cpus = limits.reserved_cpus();
+        // 小数の CPU 数の足し算の誤差で拒否しないよう、わずかに余裕を持たせる
+        if cpus > 0.0 && reserved.cpus + cpus > self.cpus + 1e-6 {
+            return Err(ContainerError::InsufficientResources(format!(
+                "Reserving {} CPUs would exceed the {} allocatable CPUs ({} already reserved)",
+                round_cpus(cpus),
+                round_cpus(self.cpus),
+                round_cpus(reserved.cpus)
+            )));
+        }
+        let memory_bytes = limits.reserved_memory_bytes();
+        if memory_bytes > 0 && reserved.memory_bytes + memory_bytes > self.memory_bytes {
+            return Err(ContainerError::InsufficientResources(format!(
+                "Reserving {} of memory would exceed the {} allocatable ({} already reserved)",
+                format_size(memory_bytes),
+                format_size(self.memory_bytes),
+                format_size(reserved.memory_bytes)
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn round_cpus(cpus: f64) -> f64 {
+    (cpus * 1000.0).round() / 1000.0
+}
+
+// デーモンの設定ファイルから割り当てを読む（無ければホストの全体を割り当て、拒否はしない）
+pub fn load_capacity() -> Capacity {
+    let mut capacity = Capacity::default();
+    let Ok(content) = std::fs::read_to_string(DAEMON_CONFIG_PATH) else {
+        return capacity;
+    };
+    let config = match serde_json::from_str::<DaemonConfig>(&content) {
+        Ok(config) => config.resources,
+        Err(e) => {
+            warn!("Ignoring invalid {}: {}", DAEMON_CONFIG_PATH, e);
+            return capacity;
+        }
+    };
+    match config.cpus {
+        Some(cpus) if cpus > 0.0 => capacity.cpus = cpus,
+        Some(cpus) => warn!("Ignoring resources.cpus in {}: invalid number of CPUs {}", DAEMON_CONFIG_PATH, cpus),
+        None => {}
+    }
+    if let Some(memory) = &config.memory {
+        match parse_memory_size(memory) {
+            Ok(bytes) if bytes > 0 => capacity.memory_bytes = bytes,
+            _ => warn!("Ignoring resources.memory in {}: invalid size {:?}", DAEMON_CONFIG_PATH, memory),
+        }
+    }
+    capacity.enforce = config.reject_oversubscription;
+    capacity
+}
+
+// ホストの CPU の数
+pub fn host_cpus() -> f64 {
+    std::thread::available_parallelism().map_or(1, |n| n.get()) as f64
+}
+
+// ホストのメモリ（/proc/meminfo の MemTotal）
+pub fn host_memory_bytes() -> u64 {
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|meminfo| {
+            meminfo
+                .lines()
+                .find_map(|line| line.strip_prefix("MemTotal:"))
+                .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        })
+        .map_or(0, |kb| kb * 1024)
+}
@@ -0,0 +1,241 @@
+use chrono::{DateTime, Local, Utc};
+use rocker_core::{
+    lookup, Container, CronExpr, LookupError, RestartPolicy, RockerError, RunState, Schedule, ScheduleCreateRequest,
+    ScheduleError, ScheduleRun, DEFAULT_SCHEDULE_HISTORY,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+mod scheduler;
+
+pub use scheduler::spawn_scheduler;
+
+// スケジュールを管理する構造体
+//
+// スケジュールは <state_dir>/<ID>.json に実行の履歴と一緒に保存する。cron 式はデーモンのタイムゾーンで
+// 評価し、デーモンが止まっていた間に来た時刻の実行は（cron と同じく）行わない。
+pub struct Manager {
+    schedules: HashMap<String, Schedule>,
+    state_dir: PathBuf,
+}
+
+impl Manager {
+    pub fn new(data_root: &Path) -> Self {
+        Manager {
+            schedules: HashMap::new(),
+            state_dir: data_root.join("schedules"),
+        }
+    }
+
+    // 保存済みのスケジュールを読み込み、次の実行時刻を今から計算し直す
+    pub async fn init(&mut self) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+
+        let mut entries = tokio::fs::read_dir(&self.state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&path).await?;
+            match serde_json::from_str::<Schedule>(&content) {
+                Ok(mut schedule) => {
+                    schedule.next_run = next_run(&schedule.cron)?;
+                    self.schedules.insert(schedule.id.clone(), schedule);
+                }
+                Err(e) => warn!("Skipping invalid schedule state {}: {}", path.display(), e),
+            }
+        }
+
+        info!("Loaded {} schedules", self.schedules.len());
+        Ok(())
+    }
+
+    pub fn list_all(&self) -> Vec<Schedule> {
+        self.schedules.values().cloned().collect()
+    }
+
+    // ID・ID の前方一致・名前のいずれかでスケジュールを探す
+    pub fn get(&self, id_or_name: &str) -> Result<&Schedule, ScheduleError> {
+        lookup(self.schedules.values(), id_or_name).map_err(|e| match e {
+            LookupError::NotFound => ScheduleError::NotFound(id_or_name.to_string()),
+            LookupError::Ambiguous(_) => ScheduleError::Ambiguous(id_or_name.to_string()),
+        })
+    }
+
+    pub async fn create(&mut self, request: ScheduleCreateRequest) -> Result<Schedule, RockerError> {
+        if !request.name.is_empty() && self.schedules.values().any(|schedule| schedule.name == request.name) {
+            return Err(ScheduleError::AlreadyExists(request.name).into());
+        }
+        if request.config.image.is_empty() {
+            return Err(ScheduleError::Invalid("No image specified".to_string()).into());
+        }
+        // 再起動や自動削除があると、実行の終了と終了コードを履歴に残せない
+        if !matches!(request.config.restart_policy, RestartPolicy::No) {
+            return Err(ScheduleError::Invalid("Scheduled containers cannot have a restart policy".to_string()).into());
+        }
+        if request.config.auto_remove {
+            return Err(ScheduleError::Invalid(
+                "--rm cannot be used with a schedule (the containers of old runs are removed by the history limit)"
+                    .to_string(),
+            )
+            .into());
+        }
+        let history_limit = request.history_limit.unwrap_or(DEFAULT_SCHEDULE_HISTORY);
+        if history_limit == 0 {
+            return Err(ScheduleError::Invalid("The history limit must be at least 1".to_string()).into());
+        }
+
+        let mut schedule = Schedule::new(request.name, request.cron, request.config, request.overlap, history_limit);
+        if schedule.name.is_empty() {
+            schedule.name = schedule.id.chars().take(12).collect();
+        }
+        schedule.next_run = next_run(&schedule.cron)?;
+        if schedule.next_run.is_none() {
+            return Err(ScheduleError::Invalid(format!("Cron expression {:?} never matches", schedule.cron)).into());
+        }
+
+        self.schedules.insert(schedule.id.clone(), schedule.clone());
+        self.save(&schedule.id).await?;
+        info!("Created schedule {} ({}) running at {:?}", schedule.name, schedule.id, schedule.cron);
+        Ok(schedule)
+    }
+
+    // スケジュールを削除する（実行のコンテナは呼び出し側が削除する）
+    pub async fn remove(&mut self, id_or_name: &str) -> Result<Schedule, RockerError> {
+        let id = self.get(id_or_name)?.id.clone();
+        let path = self.state_path(&id);
+        if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+        let schedule = self.schedules.remove(&id).ok_or_else(|| ScheduleError::NotFound(id.clone()))?;
+        info!("Removed schedule {} ({})", schedule.name, schedule.id);
+        Ok(schedule)
+    }
+
+    // 実行を待っているか、実行中のコンテナがあるスケジュールがあるか
+    pub fn has_work(&self, now: DateTime<Utc>) -> bool {
+        self.schedules.values().any(|schedule| {
+            schedule.next_run.is_some_and(|next_run| next_run <= now) || !schedule.running_containers().is_empty()
+        })
+    }
+
+    // 実行の時刻が来たスケジュールを返し、次の実行時刻に進める
+    pub async fn take_due(&mut self, now: DateTime<Utc>) -> Result<Vec<(Schedule, DateTime<Utc>)>, RockerError> {
+        let mut due = Vec::new();
+        for schedule in self.schedules.values_mut() {
+            let Some(scheduled_at) = schedule.next_run.filter(|next_run| *next_run <= now) else {
+                continue;
+            };
+            schedule.next_run = next_run(&schedule.cron)?;
+            due.push((schedule.clone(), scheduled_at));
+        }
+        for (schedule, _) in &due {
+            self.save(&schedule.id).await?;
+        }
+        Ok(due)
+    }
+
+    // 実行を履歴に加える
+    //
+    // 履歴の上限を超えた終了済みの実行は古いものから履歴から外し、それらのコンテナの ID を返す。
+    pub async fn record(&mut self, id: &str, run: ScheduleRun) -> Result<Vec<String>, RockerError> {
+        let Some(schedule) = self.schedules.get_mut(id) else {
+            // 実行の間にスケジュールが削除された
+            return Ok(run.container_id.into_iter().collect());
+        };
+        schedule.history.push(run);
+
+        let limit = schedule.history_limit;
+        let mut expired = Vec::new();
+        let mut finished = schedule
+            .history
+            .iter()
+            .filter(|run| run.state != RunState::Running)
+            .count();
+        schedule.history.retain(|run| {
+            if finished <= limit || run.state == RunState::Running {
+                return true;
+            }
+            finished -= 1;
+            expired.extend(run.container_id.clone());
+            false
+        });
+        self.save(id).await?;
+        Ok(expired)
+    }
+
+    // 実行中の実行のうちコンテナが終了したもの（削除されたものも含む）の結果を記録する
+    pub async fn update_runs(&mut self, containers: &HashMap<String, Container>) -> Result<(), RockerError> {
+        let mut changed = Vec::new();
+        for schedule in self.schedules.values_mut() {
+            for run in schedule.history.iter_mut().filter(|run| run.state == RunState::Running) {
+                let Some(container_id) = &run.container_id else {
+                    continue;
+                };
+                match containers.get(container_id) {
+                    Some(container) if container.state.is_running() || container.state.is_paused() => continue,
+                    Some(container) => {
+                        run.finished_at = container.finished_at.or_else(|| Some(Utc::now()));
+                        run.exit_code = container.exit_code;
+                        run.state = match container.exit_code {
+                            Some(0) => RunState::Succeeded,
+                            _ => RunState::Failed,
+                        };
+                    }
+                    None => {
+                        run.finished_at = Some(Utc::now());
+                        run.state = RunState::Failed;
+                        run.error = Some("The container was removed before it exited".to_string());
+                    }
+                }
+                info!(
+                    "Run {} of schedule {} {} (exit code {:?})",
+                    run.container_name.as_deref().unwrap_or(container_id),
+                    schedule.name,
+                    run.state,
+                    run.exit_code
+                );
+                changed.push(schedule.id.clone());
+            }
+        }
+        changed.dedup();
+        for id in &changed {
+            self.save(id).await?;
+        }
+        Ok(())
+    }
+
+    // 実行中の実行に、止めた理由を残す（結果は終了した後で update_runs が記録する）
+    pub async fn mark_replaced(&mut self, id: &str, container_id: &str, reason: String) -> Result<(), RockerError> {
+        let Some(schedule) = self.schedules.get_mut(id) else {
+            return Ok(());
+        };
+        if let Some(run) = schedule
+            .history
+            .iter_mut()
+            .find(|run| run.state == RunState::Running && run.container_id.as_deref() == Some(container_id))
+        {
+            run.error = Some(reason);
+        }
+        self.save(id).await
+    }
+
+    fn state_path(&self, id: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.json", id))
+    }
+
+    async fn save(&self, id: &str) -> Result<(), RockerError> {
+        let schedule = self.schedules.get(id).ok_or_else(|| ScheduleError::NotFound(id.to_string()))?;
+        tokio::fs::write(self.state_path(id), serde_json::to_vec_pretty(schedule)?).await?;
+        Ok(())
+    }
+}
+
+// 今より後で cron 式に合う最初の時刻（デーモンのタイムゾーンで評価する）
+fn next_run(cron: &str) -> Result<Option<DateTime<Utc>>, ScheduleError> {
+    let expr = CronExpr::parse(cron)?;
+    Ok(expr.next_after(&Local::now()).map(|next| next.with_timezone(&Utc)))
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Local, Utc};
+use rocker_core::{Container, OverlapPolicy, RockerError, RunState, Schedule, ScheduleRun, SCHEDULE_LABEL};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+use crate::RockerDaemon;
+
+// 実行の時刻と実行中のコンテナを確かめる間隔
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+// 時刻が来たスケジュールのコンテナを作成して起動し、終了したコンテナの結果を履歴に記録し続ける
+pub fn spawn_scheduler(daemon: Arc<Mutex<RockerDaemon>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_once(&daemon).await {
+                warn!("Scheduler: {}", e);
+            }
+        }
+    });
+}
+
+async fn run_once(daemon: &Arc<Mutex<RockerDaemon>>) -> Result<(), RockerError> {
+    let now = Utc::now();
+    let due = {
+        let mut daemon = daemon.lock().await;
+        if !daemon.schedule_manager.has_work(now) {
+            return Ok(());
+        }
+        let containers: HashMap<String, Container> = daemon
+            .container_manager
+            .list_all()
+            .await?
+            .into_iter()
+            .filter(|container| container.config.labels.contains_key(SCHEDULE_LABEL))
+            .map(|container| (container.id.clone(), container))
+            .collect();
+        daemon.schedule_manager.update_runs(&containers).await?;
+        daemon.schedule_manager.take_due(now).await?
+    };
+
+    for (schedule, scheduled_at) in due {
+        let run = match start_run(daemon, &schedule, scheduled_at).await {
+            Ok(run) => run,
+            Err(e) => {
+                warn!("Failed to run schedule {}: {}", schedule.name, e);
+                ScheduleRun::not_started(scheduled_at, RunState::Failed, e.to_string())
+            }
+        };
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        let expired = daemon.schedule_manager.record(&schedule.id, run).await?;
+        // 履歴から外れた実行のコンテナを削除する
+        for container_id in expired {
+            if daemon.container_manager.get(&container_id).is_err() {
+                continue;
+            }
+            if let Err(e) = daemon
+                .container_manager
+                .remove(&container_id, true, &mut daemon.volume_manager)
+                .await
+            {
+                warn!("Failed to remove container {} of schedule {}: {}", container_id, schedule.name, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+// 重なりの方針に従って実行する（スキップした実行も履歴に残す）
+async fn start_run(
+    daemon: &Arc<Mutex<RockerDaemon>>,
+    schedule: &Schedule,
+    scheduled_at: DateTime<Utc>,
+) -> Result<ScheduleRun, RockerError> {
+    let running = schedule.running_containers();
+    if !running.is_empty() {
+        match schedule.overlap {
+            OverlapPolicy::Allow => {}
+            OverlapPolicy::Forbid => {
+                info!("Skipping run of schedule {}: the previous run is still running", schedule.name);
+                return Ok(ScheduleRun::not_started(
+                    scheduled_at,
+                    RunState::Skipped,
+                    "The previous run was still running".to_string(),
+                ));
+            }
+            OverlapPolicy::Replace => {
+                for container_id in &running {
+                    info!("Stopping container {} of schedule {} for the next run", container_id, schedule.name);
+                    let exists = {
+                        let mut daemon_guard = daemon.lock().await;
+                        daemon_guard
+                            .schedule_manager
+                            .mark_replaced(&schedule.id, container_id, "Stopped for the next run".to_string())
+                            .await?;
+                        daemon_guard.container_manager.get(container_id).is_ok()
+                    };
+                    if exists {
+                        RockerDaemon::stop_container(daemon, container_id, None).await?;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut config = schedule.config.clone();
+    config.labels.insert(SCHEDULE_LABEL.to_string(), schedule.id.clone());
+
+    // pull の間はデーモンのロックを持たない
+    let image_manager = daemon.lock().await.image_manager.clone();
+    if image_manager.get(&config.image).is_err() {
+        info!("Pulling {} for schedule {}", config.image, schedule.name);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        image_manager.pull(&config.image, None, &tx).await?;
+    }
+
+    let mut daemon_guard = daemon.lock().await;
+    let daemon = &mut *daemon_guard;
+    let image = daemon.image_manager.get(&config.image)?;
+    let name = format!("{}-{}", schedule.name, scheduled_at.with_timezone(&Local).format("%Y%m%d%H%M"));
+    let container = daemon
+        .container_manager
+        .create(&name, config, &image, &daemon.network_manager)
+        .await?;
+    info!("Starting container {} of schedule {}", container.name, schedule.name);
+    let started = daemon
+        .container_manager
+        .start(&container.id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+        .await;
+    if let Err(start_error) = started {
+        if let Err(e) = daemon
+            .container_manager
+            .remove(&container.id, true, &mut daemon.volume_manager)
+            .await
+        {
+            warn!("Failed to remove container {} of schedule {}: {}", container.name, schedule.name, e);
+        }
+        return Err(start_error);
+    }
+    Ok(ScheduleRun::started(scheduled_at, container.id, container.name))
+}
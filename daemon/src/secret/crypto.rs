@@ -0,0 +1,62 @@
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use rocker_core::SecretError;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+// AES-256-GCM の鍵・ナンス・認証タグの長さ
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+// 秘密情報を暗号化する鍵（データルートごとに 1 つ作り、所有者だけが読めるファイルに置く）
+pub struct Key(Vec<u8>);
+
+impl Key {
+    pub fn load_or_create(path: &Path) -> Result<Self, SecretError> {
+        let store_error = |e: std::io::Error| SecretError::Store(format!("{}: {}", path.display(), e));
+        if path.exists() {
+            let key = std::fs::read(path).map_err(store_error)?;
+            if key.len() != KEY_LEN {
+                return Err(SecretError::Store(format!("{}: invalid key length {}", path.display(), key.len())));
+            }
+            return Ok(Key(key));
+        }
+
+        let mut key = vec![0; KEY_LEN];
+        rand_bytes(&mut key).map_err(|e| SecretError::Store(e.to_string()))?;
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+            .map_err(store_error)?;
+        file.write_all(&key).map_err(store_error)?;
+        Ok(Key(key))
+    }
+
+    // ナンス・認証タグ・暗号文を連結して返す（aad は秘密情報の ID で、別の秘密情報のファイルとの入れ替えを防ぐ）
+    pub fn seal(&self, aad: &[u8], data: &[u8]) -> Result<Vec<u8>, SecretError> {
+        let mut nonce = [0; NONCE_LEN];
+        rand_bytes(&mut nonce).map_err(|e| SecretError::Store(e.to_string()))?;
+        let mut tag = [0; TAG_LEN];
+        let ciphertext = encrypt_aead(Cipher::aes_256_gcm(), &self.0, Some(&nonce), aad, data, &mut tag)
+            .map_err(|e| SecretError::Store(format!("Failed to encrypt: {}", e)))?;
+        let mut sealed = Vec::with_capacity(NONCE_LEN + TAG_LEN + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&tag);
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, SecretError> {
+        if sealed.len() < NONCE_LEN + TAG_LEN {
+            return Err(SecretError::Store("Encrypted data is truncated".to_string()));
+        }
+        let (nonce, rest) = sealed.split_at(NONCE_LEN);
+        let (tag, ciphertext) = rest.split_at(TAG_LEN);
+        decrypt_aead(Cipher::aes_256_gcm(), &self.0, Some(nonce), aad, ciphertext, tag)
+            .map_err(|_| SecretError::Store("Failed to decrypt (the data or the key was modified)".to_string()))
+    }
+}
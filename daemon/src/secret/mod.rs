@@ -0,0 +1,167 @@
+use rocker_core::{lookup, LookupError, RockerError, Secret, SecretError, MAX_SECRET_SIZE};
+use std::collections::HashMap;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+mod crypto;
+
+// 秘密情報を管理する構造体
+//
+// 内容は <state_dir>/<ID>/data に暗号化して保存し、鍵は <state_dir>/key に置く。API は内容を返さず、
+// コンテナの起動時にだけ復号する。
+pub struct Manager {
+    secrets: HashMap<String, Secret>,
+    state_dir: PathBuf,
+    // init で読み込む（作成する）
+    key: Option<crypto::Key>,
+}
+
+impl Manager {
+    pub fn new(data_root: &Path) -> Self {
+        Manager {
+            secrets: HashMap::new(),
+            state_dir: data_root.join("secrets"),
+            key: None,
+        }
+    }
+
+    // 鍵と保存済みの秘密情報を読み込む
+    pub async fn init(&mut self) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        tokio::fs::set_permissions(&self.state_dir, std::fs::Permissions::from_mode(0o700)).await?;
+        self.key = Some(crypto::Key::load_or_create(&self.state_dir.join("key"))?);
+
+        let mut entries = tokio::fs::read_dir(&self.state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let config_path = entry.path().join("secret.json");
+            if !config_path.exists() {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&config_path).await?;
+            match serde_json::from_str::<Secret>(&content) {
+                Ok(secret) => {
+                    self.secrets.insert(secret.id.clone(), secret);
+                }
+                Err(e) => warn!("Skipping invalid secret state {}: {}", config_path.display(), e),
+            }
+        }
+
+        info!("Loaded {} secrets", self.secrets.len());
+        Ok(())
+    }
+
+    pub fn list_all(&self) -> Vec<Secret> {
+        self.secrets.values().cloned().collect()
+    }
+
+    // ID・ID の前方一致・名前のいずれかで秘密情報を探す
+    pub fn get(&self, id_or_name: &str) -> Result<&Secret, SecretError> {
+        lookup(self.secrets.values(), id_or_name).map_err(|e| match e {
+            LookupError::NotFound => SecretError::NotFound(id_or_name.to_string()),
+            LookupError::Ambiguous(_) => SecretError::Ambiguous(id_or_name.to_string()),
+        })
+    }
+
+    // 秘密情報を暗号化して保存する（内容は変更できないため、変えるときは削除して作り直す）
+    pub async fn create(
+        &mut self,
+        name: &str,
+        data: &[u8],
+        labels: HashMap<String, String>,
+    ) -> Result<Secret, RockerError> {
+        validate_name(name)?;
+        if self.secrets.values().any(|secret| secret.name == name) {
+            return Err(SecretError::AlreadyExists(name.to_string()).into());
+        }
+        if data.is_empty() || data.len() > MAX_SECRET_SIZE {
+            return Err(SecretError::Invalid(format!(
+                "Secret data must be between 1 and {} bytes, got {}",
+                MAX_SECRET_SIZE,
+                data.len()
+            ))
+            .into());
+        }
+
+        let secret = Secret::new(name.to_string(), data.len(), labels);
+        let sealed = self.key()?.seal(secret.id.as_bytes(), data)?;
+        let dir = self.state_dir.join(&secret.id);
+        let store_error = |e: std::io::Error| SecretError::Store(format!("{}: {}", dir.display(), e));
+        tokio::fs::create_dir_all(&dir).await.map_err(store_error)?;
+        tokio::fs::write(dir.join("data"), sealed).await.map_err(store_error)?;
+        tokio::fs::write(dir.join("secret.json"), serde_json::to_string_pretty(&secret)?)
+            .await
+            .map_err(store_error)?;
+
+        self.secrets.insert(secret.id.clone(), secret.clone());
+        info!("Created secret {} ({})", secret.name, secret.id);
+        Ok(secret)
+    }
+
+    // 秘密情報を削除する
+    //
+    // references はコンテナが参照している秘密情報（名前か ID）ごとのコンテナ ID。参照されている
+    // 秘密情報は停止中のコンテナのものでも削除しない。
+    pub async fn remove(
+        &mut self,
+        id_or_name: &str,
+        references: &HashMap<String, Vec<String>>,
+    ) -> Result<(), RockerError> {
+        let secret = self.get(id_or_name)?;
+        let containers: Vec<String> = [&secret.name, &secret.id]
+            .iter()
+            .filter_map(|key| references.get(*key))
+            .flatten()
+            .cloned()
+            .collect();
+        if !containers.is_empty() {
+            return Err(SecretError::InUse(format!(
+                "{} is used by container(s) {}",
+                secret.name,
+                containers.join(", ")
+            ))
+            .into());
+        }
+
+        let (id, name) = (secret.id.clone(), secret.name.clone());
+        let dir = self.state_dir.join(&id);
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .map_err(|e| SecretError::Store(format!("{}: {}", dir.display(), e)))?;
+        }
+        self.secrets.remove(&id);
+        info!("Removed secret {} ({})", name, id);
+        Ok(())
+    }
+
+    // 秘密情報を復号する（コンテナの起動時に tmpfs へ書き込むのに使う）
+    pub fn reveal(&self, id_or_name: &str) -> Result<Vec<u8>, SecretError> {
+        let secret = self.get(id_or_name)?;
+        let path = self.state_dir.join(&secret.id).join("data");
+        let sealed = std::fs::read(&path).map_err(|e| SecretError::Store(format!("{}: {}", path.display(), e)))?;
+        self.key()?.open(secret.id.as_bytes(), &sealed)
+    }
+
+    fn key(&self) -> Result<&crypto::Key, SecretError> {
+        self.key
+            .as_ref()
+            .ok_or_else(|| SecretError::Store("The secret store is not initialized".to_string()))
+    }
+}
+
+// 秘密情報の名前は英数字で始まり、英数字と _ . - のみを含む（64 文字まで）
+fn validate_name(name: &str) -> Result<(), SecretError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+        && name.len() <= 64;
+    if !valid {
+        return Err(SecretError::Invalid(format!(
+            "Invalid secret name {:?}: only [a-zA-Z0-9][a-zA-Z0-9_.-] up to 64 characters are allowed",
+            name
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,240 @@
+use rocker_core::{
+    Container, NetworkConfig, NetworkDriver, NetworkMode, NodeRole, RockerError, SwarmError, TaskState, SERVICE_LABEL,
+    SWARM_NETWORK_LABEL, TASK_LABEL,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+use super::rpc::{self, AssignedTask, Assignment, Request, Response, TaskStatus};
+use super::store::SwarmNetwork;
+use super::Membership;
+use crate::network::{self, KEY_OPTION, LOCAL_OPTION, PEERS_OPTION, VNI_OPTION};
+use crate::RockerDaemon;
+
+// マネージャに報告して割り当てを受け取る間隔
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+// 割り当てられたタスクのコンテナをこのノードで動かし続ける
+//
+// 割り当てに無いタスクのコンテナは削除し、止まったコンテナは起動し直す。クラスタに参加していなければ
+// 全てのタスクのコンテナを削除する。マネージャに届かない間は今のコンテナをそのまま動かす。
+pub fn spawn_agent(daemon: Arc<Mutex<RockerDaemon>>) {
+    tokio::spawn(async move {
+        // 作成できなかったタスクとその理由（次の報告で失敗として伝える）
+        let mut failures: HashMap<String, String> = HashMap::new();
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_once(&daemon, &mut failures).await {
+                warn!("Swarm agent: {}", e);
+            }
+        }
+    });
+}
+
+async fn run_once(
+    daemon: &Arc<Mutex<RockerDaemon>>,
+    failures: &mut HashMap<String, String>,
+) -> Result<(), RockerError> {
+    let (membership, containers) = {
+        let daemon = daemon.lock().await;
+        let containers: Vec<Container> = daemon
+            .container_manager
+            .list_all()
+            .await?
+            .into_iter()
+            .filter(|container| container.config.labels.contains_key(TASK_LABEL))
+            .collect();
+        (daemon.swarm_manager.membership(), containers)
+    };
+    let membership = match membership {
+        Some(membership) => membership,
+        None if containers.is_empty() => return Ok(()),
+        None => return converge(daemon, None, &Assignment::default(), &containers, failures).await,
+    };
+
+    let mut statuses: Vec<TaskStatus> = containers.iter().map(task_status).collect();
+    statuses.extend(failures.iter().map(|(task_id, error)| TaskStatus {
+        task_id: task_id.clone(),
+        state: TaskState::Failed,
+        container_id: None,
+        error: Some(error.clone()),
+    }));
+    let assignment = match membership.role {
+        NodeRole::Manager => daemon.lock().await.swarm_manager.heartbeat_local(&statuses).await?,
+        NodeRole::Worker => {
+            let (address, identity) = (membership.manager_address.clone(), membership.identity.clone());
+            let response = tokio::task::spawn_blocking(move || {
+                rpc::call(&address, &identity, &Request::Heartbeat { tasks: statuses })
+            })
+            .await?
+            .map_err(|e| SwarmError::Communication(e.to_string()))?;
+            match response {
+                Response::Assignment(assignment) => assignment,
+                _ => return Err(SwarmError::Communication("Unexpected response from the manager".to_string()).into()),
+            }
+        }
+    };
+    converge(daemon, Some(&membership), &assignment, &containers, failures).await
+}
+
+// コンテナの状態をタスクの状態にする
+fn task_status(container: &Container) -> TaskStatus {
+    let (state, error) = if container.state.is_running() || container.state.is_paused() {
+        (TaskState::Running, None)
+    } else if container.exit_code.is_some() {
+        (TaskState::Failed, Some(format!("Container {}", container.status())))
+    } else {
+        (TaskState::Assigned, None)
+    };
+    TaskStatus {
+        task_id: container.config.labels[TASK_LABEL].clone(),
+        state,
+        container_id: Some(container.id.clone()),
+        error,
+    }
+}
+
+async fn converge(
+    daemon: &Arc<Mutex<RockerDaemon>>,
+    membership: Option<&Membership>,
+    assignment: &Assignment,
+    containers: &[Container],
+    failures: &mut HashMap<String, String>,
+) -> Result<(), RockerError> {
+    let assigned: HashMap<&str, &AssignedTask> = assignment
+        .tasks
+        .iter()
+        .map(|assigned| (assigned.task.id.as_str(), assigned))
+        .collect();
+    failures.retain(|task_id, _| assigned.contains_key(task_id.as_str()));
+
+    for container in containers {
+        let task_id = container.config.labels[TASK_LABEL].as_str();
+        if assigned.contains_key(task_id) {
+            continue;
+        }
+        info!("Removing container {} of task {}", container.name, task_id);
+        if container.state.is_running() || container.state.is_paused() {
+            RockerDaemon::stop_container(daemon, &container.id, None).await?;
+        }
+        let mut daemon_guard = daemon.lock().await;
+        let daemon = &mut *daemon_guard;
+        if daemon.container_manager.get(&container.id).is_ok() {
+            daemon
+                .container_manager
+                .remove(&container.id, true, &mut daemon.volume_manager)
+                .await?;
+        }
+    }
+
+    let Some(membership) = membership else {
+        return Ok(());
+    };
+    for (task_id, assigned) in &assigned {
+        let existing = containers
+            .iter()
+            .find(|container| container.config.labels[TASK_LABEL] == *task_id);
+        let result = match existing {
+            Some(container) if container.state.is_running() || container.state.is_paused() => continue,
+            Some(container) => {
+                info!("Restarting container {} of task {}", container.name, task_id);
+                let mut daemon_guard = daemon.lock().await;
+                let daemon = &mut *daemon_guard;
+                daemon
+                    .container_manager
+                    .start(
+                        &container.id,
+                        &mut daemon.network_manager,
+                        &mut daemon.volume_manager,
+                        &daemon.secret_manager,
+                    )
+                    .await
+            }
+            None => start_task(daemon, membership, assignment, assigned).await,
+        };
+        match result {
+            Ok(()) => {
+                failures.remove(*task_id);
+            }
+            Err(e) => {
+                warn!("Failed to run task {} of service {}: {}", task_id, assigned.task.service_name, e);
+                failures.insert(task_id.to_string(), e.to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+// タスクのコンテナを作成して起動する（イメージが無ければ先に取得する）
+async fn start_task(
+    daemon: &Arc<Mutex<RockerDaemon>>,
+    membership: &Membership,
+    assignment: &Assignment,
+    assigned: &AssignedTask,
+) -> Result<(), RockerError> {
+    let task = &assigned.task;
+    let mut config = assigned.template.clone();
+
+    // pull の間はデーモンのロックを持たない
+    let image_manager = daemon.lock().await.image_manager.clone();
+    if image_manager.get(&config.image).is_err() {
+        info!("Pulling {} for service {}", config.image, task.service_name);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        image_manager.pull(&config.image, None, &tx).await?;
+    }
+
+    let mut daemon_guard = daemon.lock().await;
+    let daemon = &mut *daemon_guard;
+    config.labels.insert(SERVICE_LABEL.to_string(), task.service_name.clone());
+    config.labels.insert(TASK_LABEL.to_string(), task.id.clone());
+    if let Some(network) = &assigned.network {
+        ensure_network(&mut daemon.network_manager, network, membership, assignment).await?;
+        config.network_mode = NetworkMode::Custom(network.name.clone());
+        config.network_aliases.push(task.service_name.clone());
+    }
+    let image = daemon.image_manager.get(&config.image)?;
+    let container = daemon
+        .container_manager
+        .create(&task.container_name(), config, &image, &daemon.network_manager)
+        .await?;
+    info!("Starting container {} of task {}", container.name, task.id);
+    daemon
+        .container_manager
+        .start(&container.id, &mut daemon.network_manager, &mut daemon.volume_manager, &daemon.secret_manager)
+        .await
+}
+
+// サービスのオーバーレイネットワークが無ければ作る（ピアは作成した時点のクラスタの他のノード）
+async fn ensure_network(
+    networks: &mut network::Manager,
+    network: &SwarmNetwork,
+    membership: &Membership,
+    assignment: &Assignment,
+) -> Result<(), RockerError> {
+    if networks.exists(&network.name).await? {
+        return Ok(());
+    }
+    let host = |address: &str| address.parse::<SocketAddr>().map(|address| address.ip().to_string()).ok();
+    let peers: Vec<String> = assignment.peers.iter().filter_map(|peer| host(peer)).collect();
+    let mut options = HashMap::new();
+    options.insert(VNI_OPTION.to_string(), network.vni.to_string());
+    options.insert(PEERS_OPTION.to_string(), peers.join(","));
+    options.insert(KEY_OPTION.to_string(), network.key.clone());
+    if let Some(local) = host(&membership.advertise_address) {
+        options.insert(LOCAL_OPTION.to_string(), local);
+    }
+    let config = NetworkConfig {
+        subnet: network.subnet.clone(),
+        gateway: String::new(),
+        ip_range: Some(network.ip_range(assignment.node_index)),
+        labels: HashMap::from([(SWARM_NETWORK_LABEL.to_string(), "true".to_string())]),
+        ..Default::default()
+    };
+    networks.create(&network.name, NetworkDriver::Overlay, config, options).await?;
+    Ok(())
+}
@@ -0,0 +1,528 @@
+use rocker_core::{
+    Node, NodeRole, NodeUpdate, RockerError, Service, ServiceSpec, SwarmError, SwarmInfo, SwarmInitRequest,
+    SwarmJoinRequest, DEFAULT_SWARM_PORT,
+};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::RockerDaemon;
+
+mod agent;
+mod pki;
+mod rpc;
+mod store;
+
+pub use agent::spawn_agent;
+
+use pki::{CertificateAuthority, Identity};
+use rpc::{Assignment, Request, Response, TaskStatus};
+use store::Store;
+
+// 参加トークンの接頭辞（続けて CA 証明書のフィンガープリントと秘密の値を "-" で区切る）
+const TOKEN_PREFIX: &str = "SWMTKN-1-";
+
+const DEFAULT_LISTEN_ADDRESS: &str = "0.0.0.0";
+
+// このノードのクラスタでの状態（swarm/state.json に保存する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LocalState {
+    node_id: String,
+    role: NodeRole,
+    manager_address: String,
+    advertise_address: String,
+    // マネージャのみ
+    #[serde(default)]
+    listen_address: Option<String>,
+    #[serde(default)]
+    join_secret: Option<String>,
+}
+
+// エージェントがマネージャに報告するのに使う情報
+#[derive(Clone)]
+pub struct Membership {
+    pub role: NodeRole,
+    pub manager_address: String,
+    pub advertise_address: String,
+    identity: Identity,
+}
+
+// クラスタ（swarm）モードの管理
+//
+// マネージャはノード・サービス・タスクの望ましい状態を swarm/store.json に保存し、参加したノードに
+// タスクを割り当てる。ノードはマネージャの CA が発行した証明書を使い、相互に検証する TLS でマネージャに
+// 定期的に報告して割り当てを受け取る（マネージャは 1 台で、ノードからは接続を受けない）。
+pub struct Manager {
+    state_dir: PathBuf,
+    state: Option<LocalState>,
+    identity: Option<Identity>,
+    // マネージャのみ
+    ca: Option<CertificateAuthority>,
+    store: Store,
+    server: Option<JoinHandle<()>>,
+}
+
+impl Manager {
+    pub fn new(data_root: &Path) -> Self {
+        Manager {
+            state_dir: data_root.join("swarm"),
+            state: None,
+            identity: None,
+            ca: None,
+            store: Store::default(),
+            server: None,
+        }
+    }
+
+    // 前回参加していたクラスタの状態を読み込む（マネージャならノードからの接続を受け付け直す）
+    pub async fn init(&mut self, daemon: &Arc<Mutex<RockerDaemon>>) -> Result<(), RockerError> {
+        let content = match tokio::fs::read_to_string(self.state_dir.join("state.json")).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let state: LocalState = serde_json::from_str(&content)?;
+        self.identity = Some(Identity::load(&self.state_dir)?);
+        if state.role == NodeRole::Manager {
+            self.ca = Some(CertificateAuthority::load(&self.state_dir)?);
+            if let Ok(content) = tokio::fs::read_to_string(self.state_dir.join("store.json")).await {
+                self.store = serde_json::from_str(&content)?;
+            }
+        }
+        info!("Rejoined swarm as {} {}", state.role, state.node_id);
+        self.state = Some(state);
+        if self.is_manager() {
+            self.start_server(daemon).await?;
+        }
+        Ok(())
+    }
+
+    pub fn info(&self) -> Result<SwarmInfo, SwarmError> {
+        let state = self.state.as_ref().ok_or(SwarmError::NotInSwarm)?;
+        Ok(SwarmInfo {
+            node_id: state.node_id.clone(),
+            role: state.role,
+            manager_address: state.manager_address.clone(),
+            join_token: self.join_token(),
+        })
+    }
+
+    pub fn membership(&self) -> Option<Membership> {
+        let state = self.state.as_ref()?;
+        Some(Membership {
+            role: state.role,
+            manager_address: state.manager_address.clone(),
+            advertise_address: state.advertise_address.clone(),
+            identity: self.identity.clone()?,
+        })
+    }
+
+    // 新しいクラスタを作り、このノードをマネージャにする
+    pub async fn init_swarm(
+        &mut self,
+        request: SwarmInitRequest,
+        daemon: &Arc<Mutex<RockerDaemon>>,
+    ) -> Result<SwarmInfo, RockerError> {
+        if self.state.is_some() {
+            return Err(SwarmError::AlreadyInSwarm.into());
+        }
+        let listen = with_port(
+            request.listen_address.as_deref().unwrap_or(DEFAULT_LISTEN_ADDRESS),
+            DEFAULT_SWARM_PORT,
+        )?;
+        let advertise = advertise_address(&request.advertise_address, listen.port())?;
+
+        let ca = CertificateAuthority::create().map_err(pki::certificate_error)?;
+        let mut store = Store::default();
+        let node = store.add_node(&hostname(), &advertise.to_string(), NodeRole::Manager);
+        let identity = ca.issue_identity(&node.id).map_err(pki::certificate_error)?;
+        let state = LocalState {
+            node_id: node.id.clone(),
+            role: NodeRole::Manager,
+            manager_address: advertise.to_string(),
+            advertise_address: advertise.to_string(),
+            listen_address: Some(listen.to_string()),
+            join_secret: Some(uuid::Uuid::new_v4().simple().to_string()),
+        };
+
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        ca.save(&self.state_dir)?;
+        identity.save(&self.state_dir)?;
+        self.ca = Some(ca);
+        self.identity = Some(identity);
+        self.store = store;
+        self.state = Some(state);
+        if let Err(e) = self.start_server(daemon).await {
+            self.reset().await;
+            return Err(e);
+        }
+        self.save_store().await?;
+        self.save_state().await?;
+
+        info!("Initialized swarm, this node {} is the manager at {}", node.id, advertise);
+        Ok(self.info()?)
+    }
+
+    // トークンを使ってマネージャのクラスタにワーカーとして参加する
+    pub async fn join_swarm(&mut self, request: SwarmJoinRequest) -> Result<SwarmInfo, RockerError> {
+        if self.state.is_some() {
+            return Err(SwarmError::AlreadyInSwarm.into());
+        }
+        let (fingerprint, secret) = parse_token(&request.token)?;
+        let remote = if request.remote_address.contains(':') {
+            request.remote_address.clone()
+        } else {
+            format!("{}:{}", request.remote_address, DEFAULT_SWARM_PORT)
+        };
+        let advertise = advertise_address(&request.advertise_address, DEFAULT_SWARM_PORT)?;
+
+        let (key, csr) = pki::new_request().map_err(pki::certificate_error)?;
+        let join = Request::Join {
+            secret,
+            hostname: hostname(),
+            address: advertise.to_string(),
+            csr: String::from_utf8_lossy(&csr).into_owned(),
+        };
+        let address = remote.clone();
+        let (response, ca) = tokio::task::spawn_blocking(move || rpc::join(&address, &fingerprint, &join))
+            .await?
+            .map_err(|e| SwarmError::Communication(e.to_string()))?;
+        let (node_id, certificate) = match response {
+            Response::Joined { node_id, certificate } => (node_id, certificate),
+            _ => return Err(SwarmError::Communication("Unexpected response from the manager".to_string()).into()),
+        };
+
+        let identity = Identity {
+            cert: certificate.into_bytes(),
+            key,
+            ca,
+        };
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        identity.save(&self.state_dir)?;
+        self.identity = Some(identity);
+        self.state = Some(LocalState {
+            node_id: node_id.clone(),
+            role: NodeRole::Worker,
+            manager_address: remote.clone(),
+            advertise_address: advertise.to_string(),
+            listen_address: None,
+            join_secret: None,
+        });
+        self.save_state().await?;
+
+        info!("Joined swarm at {} as worker {}", remote, node_id);
+        Ok(self.info()?)
+    }
+
+    // クラスタから抜ける（タスクのコンテナはエージェントが削除する）
+    //
+    // 他のノードが残っているマネージャは、force を指定しない限り抜けられない。
+    pub async fn leave(&mut self, force: bool) -> Result<(), RockerError> {
+        let membership = self.membership().ok_or(SwarmError::NotInSwarm)?;
+        match membership.role {
+            NodeRole::Manager => {
+                if !force && self.store.nodes().len() > 1 {
+                    return Err(SwarmError::InvalidConfig(
+                        "Other nodes are still part of the swarm, leaving removes it for them (use --force)".to_string(),
+                    )
+                    .into());
+                }
+            }
+            NodeRole::Worker => {
+                let result = tokio::task::spawn_blocking(move || {
+                    rpc::call(&membership.manager_address, &membership.identity, &Request::Leave)
+                })
+                .await?;
+                if let Err(e) = result {
+                    if !force {
+                        return Err(SwarmError::Communication(format!("{} (use --force to leave anyway)", e)).into());
+                    }
+                    warn!("Failed to tell the manager that this node leaves: {}", e);
+                }
+            }
+        }
+
+        self.reset().await;
+        info!("Left the swarm");
+        Ok(())
+    }
+
+    // 参加トークンの秘密の値を新しくする（参加済みのノードは証明書で認証するため影響しない）
+    pub async fn rotate_join_token(&mut self) -> Result<SwarmInfo, RockerError> {
+        self.manager_store()?;
+        if let Some(state) = self.state.as_mut() {
+            state.join_secret = Some(uuid::Uuid::new_v4().simple().to_string());
+        }
+        self.save_state().await?;
+        info!("Rotated the swarm join token");
+        Ok(self.info()?)
+    }
+
+    pub fn nodes(&self) -> Result<Vec<Node>, SwarmError> {
+        self.manager_store().map(Store::nodes)
+    }
+
+    pub fn node(&self, id_or_name: &str) -> Result<Node, SwarmError> {
+        self.manager_store()?.node(id_or_name)
+    }
+
+    pub async fn update_node(&mut self, id_or_name: &str, update: &NodeUpdate) -> Result<Node, RockerError> {
+        self.manager_store()?;
+        let node = self.store.update_node(id_or_name, update)?;
+        self.save_store().await?;
+        info!("Updated node {} ({}), availability {}", node.hostname, node.id, node.availability);
+        Ok(node)
+    }
+
+    // ノードを外す（そのノードの証明書では報告できなくなり、タスクは他のノードに移る）
+    pub async fn remove_node(&mut self, id_or_name: &str) -> Result<(), RockerError> {
+        let node = self.manager_store()?.node(id_or_name)?;
+        if node.role == NodeRole::Manager {
+            return Err(SwarmError::InvalidConfig("The manager cannot be removed, use swarm leave --force".to_string()).into());
+        }
+        self.store.remove_node(&node.id)?;
+        self.store.reconcile(chrono::Utc::now());
+        self.save_store().await?;
+        info!("Removed node {} ({})", node.hostname, node.id);
+        Ok(())
+    }
+
+    pub async fn create_service(&mut self, spec: ServiceSpec) -> Result<Service, RockerError> {
+        self.manager_store()?;
+        let service = self.store.create_service(spec)?;
+        self.save_store().await?;
+        info!("Created service {} ({}) with {} replicas", service.spec.name, service.id, service.spec.replicas);
+        Ok(service)
+    }
+
+    pub async fn update_service(&mut self, id_or_name: &str, spec: ServiceSpec) -> Result<Service, RockerError> {
+        self.manager_store()?;
+        let service = self.store.update_service(id_or_name, spec)?;
+        self.save_store().await?;
+        info!("Updated service {} ({})", service.spec.name, service.id);
+        Ok(service)
+    }
+
+    pub async fn scale_service(&mut self, id_or_name: &str, replicas: u64) -> Result<Service, RockerError> {
+        self.manager_store()?;
+        let service = self.store.scale_service(id_or_name, replicas)?;
+        self.save_store().await?;
+        info!("Scaled service {} to {} replicas", service.spec.name, replicas);
+        Ok(service)
+    }
+
+    pub async fn remove_service(&mut self, id_or_name: &str) -> Result<(), RockerError> {
+        self.manager_store()?;
+        self.store.remove_service(id_or_name)?;
+        self.save_store().await?;
+        info!("Removed service {}", id_or_name);
+        Ok(())
+    }
+
+    pub fn services(&self) -> Result<Vec<Service>, SwarmError> {
+        self.manager_store().map(Store::services)
+    }
+
+    pub fn service(&self, id_or_name: &str) -> Result<Service, SwarmError> {
+        self.manager_store()?.service(id_or_name)
+    }
+
+    // マネージャ自身のエージェントの報告
+    pub async fn heartbeat_local(&mut self, statuses: &[TaskStatus]) -> Result<Assignment, RockerError> {
+        let node_id = self.state.as_ref().ok_or(SwarmError::NotInSwarm)?.node_id.clone();
+        self.manager_store()?;
+        let (assignment, changed) = self.store.heartbeat(&node_id, statuses, chrono::Utc::now())?;
+        if changed {
+            self.save_store().await?;
+        }
+        Ok(assignment)
+    }
+
+    // ノードからの要求を処理する（peer はクライアント証明書の CN）
+    async fn handle_request(&mut self, peer: Option<String>, request: Request) -> Response {
+        match self.dispatch(peer, request).await {
+            Ok(response) => response,
+            Err(e) => Response::Error { message: e.to_string() },
+        }
+    }
+
+    async fn dispatch(&mut self, peer: Option<String>, request: Request) -> Result<Response, RockerError> {
+        self.manager_store()?;
+        if let Request::Join {
+            secret,
+            hostname,
+            address,
+            csr,
+        } = request
+        {
+            return self.admit(&secret, &hostname, &address, &csr).await;
+        }
+        // 参加後の要求は、登録されているノードの証明書を提示した接続に限る
+        let node_id = match peer {
+            Some(peer) if self.store.contains_node(&peer) => peer,
+            _ => return Err(SwarmError::PermissionDenied("This node is not part of the swarm".to_string()).into()),
+        };
+        match request {
+            Request::Heartbeat { tasks } => {
+                let (assignment, changed) = self.store.heartbeat(&node_id, &tasks, chrono::Utc::now())?;
+                if changed {
+                    self.save_store().await?;
+                }
+                Ok(Response::Assignment(assignment))
+            }
+            Request::Leave => {
+                let node = self.store.remove_node(&node_id)?;
+                self.store.reconcile(chrono::Utc::now());
+                self.save_store().await?;
+                info!("Node {} ({}) left the swarm", node.hostname, node.id);
+                Ok(Response::Left)
+            }
+            Request::Join { .. } => unreachable!("join requests are handled above"),
+        }
+    }
+
+    // トークンの秘密の値が一致すればノードを登録し、証明書を発行する
+    async fn admit(&mut self, secret: &str, hostname: &str, address: &str, csr: &str) -> Result<Response, RockerError> {
+        let expected = self.state.as_ref().and_then(|state| state.join_secret.as_deref()).unwrap_or_default();
+        if !constant_time_eq(secret.as_bytes(), expected.as_bytes()) {
+            return Err(SwarmError::PermissionDenied("Invalid join token".to_string()).into());
+        }
+        let address = advertise_address(address, DEFAULT_SWARM_PORT)?.to_string();
+        let ca = self.ca.as_ref().ok_or(SwarmError::NotManager)?;
+        let node = self.store.add_node(hostname, &address, NodeRole::Worker);
+        let certificate = match ca.sign_request(&node.id, csr.as_bytes()) {
+            Ok(certificate) => certificate,
+            Err(e) => {
+                self.store.remove_node(&node.id)?;
+                return Err(e.into());
+            }
+        };
+        self.save_store().await?;
+        info!("Node {} ({}) joined the swarm from {}", hostname, node.id, address);
+        Ok(Response::Joined {
+            node_id: node.id,
+            certificate: String::from_utf8_lossy(&certificate).into_owned(),
+        })
+    }
+
+    // ノードからの接続を受け付ける（要求はデーモンのロックを取って handle_request で処理する）
+    async fn start_server(&mut self, daemon: &Arc<Mutex<RockerDaemon>>) -> Result<(), RockerError> {
+        let state = self.state.as_ref().ok_or(SwarmError::NotInSwarm)?;
+        let listen = state.listen_address.clone().unwrap_or_else(|| state.advertise_address.clone());
+        let identity = self.identity.as_ref().ok_or(SwarmError::NotInSwarm)?;
+        let acceptor = Arc::new(rpc::acceptor(identity).map_err(pki::certificate_error)?);
+        let listener = TcpListener::bind(&listen)
+            .await
+            .map_err(|e| SwarmError::InvalidConfig(format!("Failed to listen on {}: {}", listen, e)))?;
+        info!("Swarm manager listening on tcp://{}", listen);
+
+        let runtime = tokio::runtime::Handle::current();
+        let daemon = Arc::clone(daemon);
+        let handler: rpc::Handler = Arc::new(move |peer, request| {
+            let daemon = Arc::clone(&daemon);
+            runtime.block_on(async move { daemon.lock().await.swarm_manager.handle_request(peer, request).await })
+        });
+        self.server = Some(tokio::spawn(rpc::serve(listener, acceptor, handler)));
+        Ok(())
+    }
+
+    fn manager_store(&self) -> Result<&Store, SwarmError> {
+        match &self.state {
+            Some(state) if state.role == NodeRole::Manager => Ok(&self.store),
+            Some(_) => Err(SwarmError::NotManager),
+            None => Err(SwarmError::NotInSwarm),
+        }
+    }
+
+    fn is_manager(&self) -> bool {
+        self.manager_store().is_ok()
+    }
+
+    fn join_token(&self) -> Option<String> {
+        let secret = self.state.as_ref()?.join_secret.as_ref()?;
+        let fingerprint = self.ca.as_ref()?.fingerprint().ok()?;
+        Some(format!("{}{}-{}", TOKEN_PREFIX, fingerprint, secret))
+    }
+
+    async fn save_state(&self) -> Result<(), RockerError> {
+        if let Some(state) = &self.state {
+            write_atomic(&self.state_dir.join("state.json"), &serde_json::to_vec_pretty(state)?).await?;
+        }
+        Ok(())
+    }
+
+    async fn save_store(&self) -> Result<(), RockerError> {
+        write_atomic(&self.state_dir.join("store.json"), &serde_json::to_vec_pretty(&self.store)?).await?;
+        Ok(())
+    }
+
+    // 接続の受け付けをやめ、証明書と状態を消す
+    async fn reset(&mut self) {
+        if let Some(server) = self.server.take() {
+            server.abort();
+        }
+        self.state = None;
+        self.identity = None;
+        self.ca = None;
+        self.store = Store::default();
+        if let Err(e) = tokio::fs::remove_dir_all(&self.state_dir).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to remove {}: {}", self.state_dir.display(), e);
+            }
+        }
+    }
+}
+
+// 途中で止まっても壊れたファイルが残らないよう、一時ファイルに書いてから置き換える
+async fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let temp = path.with_extension("tmp");
+    tokio::fs::write(&temp, contents).await?;
+    tokio::fs::rename(&temp, path).await
+}
+
+fn parse_token(token: &str) -> Result<(String, String), SwarmError> {
+    token
+        .strip_prefix(TOKEN_PREFIX)
+        .and_then(|rest| rest.split_once('-'))
+        .filter(|(fingerprint, secret)| fingerprint.len() == 64 && !secret.is_empty())
+        .map(|(fingerprint, secret)| (fingerprint.to_string(), secret.to_string()))
+        .ok_or_else(|| SwarmError::InvalidConfig("Invalid join token".to_string()))
+}
+
+// "IP" または "IP:ポート"（ポートが無ければ port を使う）
+fn with_port(address: &str, port: u16) -> Result<SocketAddr, SwarmError> {
+    if let Ok(address) = address.parse::<SocketAddr>() {
+        return Ok(address);
+    }
+    address
+        .parse::<IpAddr>()
+        .map(|ip| SocketAddr::new(ip, port))
+        .map_err(|_| SwarmError::InvalidConfig(format!("Invalid address: {}", address)))
+}
+
+// 他のノードから届くアドレス（オーバーレイネットワークのピアにも使うため IPv4 に限る）
+fn advertise_address(address: &str, port: u16) -> Result<SocketAddr, SwarmError> {
+    let address = with_port(address, port)?;
+    if !address.is_ipv4() || address.ip().is_unspecified() {
+        return Err(SwarmError::InvalidConfig(format!(
+            "The advertise address must be an IPv4 address other nodes can reach: {}",
+            address
+        )));
+    }
+    Ok(address)
+}
+
+fn hostname() -> String {
+    nix::unistd::gethostname()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,187 @@
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, PKeyRef, Private};
+use openssl::x509::extension::{BasicConstraints, ExtendedKeyUsage, KeyUsage};
+use openssl::x509::{X509Name, X509NameRef, X509Ref, X509Req, X509};
+use rocker_core::{RockerError, SwarmError};
+use std::path::Path;
+
+// 証明書の有効期間（更新の仕組みがないため長くする）
+const CERT_DAYS: u32 = 3650;
+
+const CA_NAME: &str = "rocker-swarm-ca";
+
+// ノードの証明書と鍵、クラスタの CA 証明書（いずれも PEM）
+#[derive(Clone)]
+pub struct Identity {
+    pub cert: Vec<u8>,
+    pub key: Vec<u8>,
+    pub ca: Vec<u8>,
+}
+
+impl Identity {
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        Ok(Identity {
+            cert: std::fs::read(dir.join("node.pem"))?,
+            key: std::fs::read(dir.join("node-key.pem"))?,
+            ca: std::fs::read(dir.join("ca.pem"))?,
+        })
+    }
+
+    pub fn save(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::write(dir.join("node.pem"), &self.cert)?;
+        write_private(&dir.join("node-key.pem"), &self.key)?;
+        std::fs::write(dir.join("ca.pem"), &self.ca)
+    }
+}
+
+// クラスタの CA（マネージャだけが鍵を持ち、参加したノードの証明書に署名する）
+pub struct CertificateAuthority {
+    cert: X509,
+    key: PKey<Private>,
+}
+
+impl CertificateAuthority {
+    pub fn create() -> Result<Self, ErrorStack> {
+        let key = new_key()?;
+        let name = common_name_entry(CA_NAME)?;
+        let mut builder = X509::builder()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&*serial_number()?.to_asn1_integer()?)?;
+        builder.set_subject_name(&name)?;
+        builder.set_issuer_name(&name)?;
+        builder.set_pubkey(&key)?;
+        builder.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+        builder.set_not_after(&*Asn1Time::days_from_now(CERT_DAYS)?)?;
+        builder.append_extension(BasicConstraints::new().critical().ca().build()?)?;
+        builder.append_extension(KeyUsage::new().critical().key_cert_sign().crl_sign().build()?)?;
+        builder.sign(&key, MessageDigest::sha256())?;
+        Ok(CertificateAuthority {
+            cert: builder.build(),
+            key,
+        })
+    }
+
+    pub fn load(dir: &Path) -> Result<Self, RockerError> {
+        Ok(CertificateAuthority {
+            cert: X509::from_pem(&std::fs::read(dir.join("ca.pem"))?).map_err(certificate_error)?,
+            key: PKey::private_key_from_pem(&std::fs::read(dir.join("ca-key.pem"))?).map_err(certificate_error)?,
+        })
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), RockerError> {
+        std::fs::write(dir.join("ca.pem"), self.cert.to_pem().map_err(certificate_error)?)?;
+        write_private(&dir.join("ca-key.pem"), &self.key.private_key_to_pem_pkcs8().map_err(certificate_error)?)?;
+        Ok(())
+    }
+
+    // 参加トークンに入れる CA 証明書のフィンガープリント
+    pub fn fingerprint(&self) -> Result<String, ErrorStack> {
+        fingerprint(&self.cert)
+    }
+
+    // マネージャ自身の証明書を作る
+    pub fn issue_identity(&self, node_id: &str) -> Result<Identity, ErrorStack> {
+        let key = new_key()?;
+        let cert = self.sign(node_id, &key)?;
+        Ok(Identity {
+            cert: cert.to_pem()?,
+            key: key.private_key_to_pem_pkcs8()?,
+            ca: self.cert.to_pem()?,
+        })
+    }
+
+    // ノードの証明書署名要求に署名する（CN は要求の内容によらずノード ID にする）
+    pub fn sign_request(&self, node_id: &str, csr: &[u8]) -> Result<Vec<u8>, SwarmError> {
+        let invalid = |e: ErrorStack| SwarmError::InvalidConfig(format!("Invalid certificate signing request: {}", e));
+        let request = X509Req::from_pem(csr).map_err(invalid)?;
+        let public_key = request.public_key().map_err(invalid)?;
+        if !request.verify(&public_key).map_err(invalid)? {
+            return Err(SwarmError::InvalidConfig("Invalid signature on the certificate signing request".to_string()));
+        }
+        self.sign(node_id, &public_key)
+            .and_then(|cert| cert.to_pem())
+            .map_err(|e| SwarmError::Communication(format!("Failed to issue certificate: {}", e)))
+    }
+
+    fn sign<T: openssl::pkey::HasPublic>(&self, node_id: &str, public_key: &PKeyRef<T>) -> Result<X509, ErrorStack> {
+        let mut builder = X509::builder()?;
+        builder.set_version(2)?;
+        builder.set_serial_number(&*serial_number()?.to_asn1_integer()?)?;
+        builder.set_subject_name(&*common_name_entry(node_id)?)?;
+        builder.set_issuer_name(self.cert.subject_name())?;
+        builder.set_pubkey(public_key)?;
+        builder.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+        builder.set_not_after(&*Asn1Time::days_from_now(CERT_DAYS)?)?;
+        builder.append_extension(BasicConstraints::new().build()?)?;
+        builder.append_extension(KeyUsage::new().critical().digital_signature().build()?)?;
+        builder.append_extension(ExtendedKeyUsage::new().server_auth().client_auth().build()?)?;
+        builder.sign(&self.key, MessageDigest::sha256())?;
+        Ok(builder.build())
+    }
+}
+
+// 参加するノードの鍵と証明書署名要求（PEM）
+pub fn new_request() -> Result<(Vec<u8>, Vec<u8>), ErrorStack> {
+    let key = new_key()?;
+    let mut builder = X509Req::builder()?;
+    builder.set_subject_name(&*common_name_entry("rocker-swarm-node")?)?;
+    builder.set_pubkey(&key)?;
+    builder.sign(&key, MessageDigest::sha256())?;
+    Ok((key.private_key_to_pem_pkcs8()?, builder.build().to_pem()?))
+}
+
+// 証明書の SHA-256 の 16 進表記
+pub fn fingerprint(cert: &X509Ref) -> Result<String, ErrorStack> {
+    let digest = cert.digest(MessageDigest::sha256())?;
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// 証明書の CN（ノード ID）
+pub fn common_name(cert: &X509Ref) -> Option<String> {
+    common_name_of(cert.subject_name())
+}
+
+fn common_name_of(name: &X509NameRef) -> Option<String> {
+    let entry = name.entries_by_nid(Nid::COMMONNAME).next()?;
+    entry.data().to_string().ok()
+}
+
+fn common_name_entry(name: &str) -> Result<X509Name, ErrorStack> {
+    let mut builder = X509Name::builder()?;
+    builder.append_entry_by_nid(Nid::COMMONNAME, name)?;
+    Ok(builder.build())
+}
+
+fn new_key() -> Result<PKey<Private>, ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    PKey::from_ec_key(EcKey::generate(&group)?)
+}
+
+fn serial_number() -> Result<BigNum, ErrorStack> {
+    let mut serial = BigNum::new()?;
+    serial.rand(127, MsbOption::MAYBE_ZERO, false)?;
+    Ok(serial)
+}
+
+// 証明書や鍵を作れない・読めないときのエラー
+pub fn certificate_error(e: ErrorStack) -> SwarmError {
+    SwarmError::Communication(format!("Certificate error: {}", e))
+}
+
+// 秘密鍵は所有者だけが読めるようにする
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)
+}
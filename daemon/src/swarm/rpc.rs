@@ -0,0 +1,225 @@
+use openssl::error::ErrorStack;
+use openssl::pkey::PKey;
+use openssl::ssl::{SslAcceptor, SslConnector, SslMethod, SslRef, SslStream, SslVerifyMode};
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::X509;
+use rocker_core::{ContainerConfig, SwarmError, Task, TaskState};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::warn;
+
+use super::pki::{common_name, fingerprint, Identity};
+use super::store::SwarmNetwork;
+
+// 接続と 1 回のやり取りのタイムアウト
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 1 つのメッセージの上限（タスクの一覧を含む）
+const MAX_MESSAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+// ノードからマネージャへの要求（1 つの接続で 1 行の JSON を送り、1 行の JSON を受け取る）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    // 参加する（まだ証明書が無いため、トークンの秘密の値で認証する）
+    Join {
+        secret: String,
+        hostname: String,
+        address: String,
+        csr: String,
+    },
+    // 動作中のタスクを報告し、割り当てられたタスクを受け取る
+    Heartbeat { tasks: Vec<TaskStatus> },
+    // クラスタから抜ける
+    Leave,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Joined { node_id: String, certificate: String },
+    Assignment(Assignment),
+    Left,
+    Error { message: String },
+}
+
+// ノードで動いているタスクのコンテナの状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskStatus {
+    pub task_id: String,
+    pub state: TaskState,
+    pub container_id: Option<String>,
+    pub error: Option<String>,
+}
+
+// ノードに割り当てたタスク
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssignedTask {
+    pub task: Task,
+    pub template: ContainerConfig,
+    pub network: Option<SwarmNetwork>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Assignment {
+    pub tasks: Vec<AssignedTask>,
+    // 他のノードのアドレス（オーバーレイネットワークのピアにする）
+    pub peers: Vec<String>,
+    // オーバーレイネットワークのアドレスの範囲に使うノードの番号
+    pub node_index: u32,
+}
+
+// ブロッキングのスレッドから返すエラー
+pub type RpcError = Box<dyn Error + Send + Sync>;
+
+// 要求を処理する関数（クライアント証明書の CN と要求を受け取る）
+pub type Handler = Arc<dyn Fn(Option<String>, Request) -> Response + Send + Sync>;
+
+// マネージャの TLS の設定
+//
+// 参加前のノードは証明書を持たないため、クライアント証明書は要求するが必須にはしない。提示された
+// 証明書はクラスタの CA で検証し、証明書の無い接続は handler が Join 以外を拒否する。
+pub fn acceptor(identity: &Identity) -> Result<SslAcceptor, ErrorStack> {
+    let ca = X509::from_pem(&identity.ca)?;
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+    builder.set_certificate(&*X509::from_pem(&identity.cert)?)?;
+    builder.set_private_key(&*PKey::private_key_from_pem(&identity.key)?)?;
+    builder.add_extra_chain_cert(ca.clone())?;
+    builder.check_private_key()?;
+    let mut store = X509StoreBuilder::new()?;
+    store.add_cert(ca)?;
+    builder.set_verify_cert_store(store.build())?;
+    builder.set_verify(SslVerifyMode::PEER);
+    Ok(builder.build())
+}
+
+// ノードからの接続を受け付け続ける（TLS とメッセージの読み書きはブロッキングのスレッドで行う）
+pub async fn serve(listener: TcpListener, acceptor: Arc<SslAcceptor>, handler: Handler) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Failed to accept swarm connection: {}", e);
+                continue;
+            }
+        };
+        let (acceptor, handler) = (Arc::clone(&acceptor), Arc::clone(&handler));
+        tokio::task::spawn_blocking(move || {
+            let result = stream
+                .into_std()
+                .map_err(RpcError::from)
+                .and_then(|stream| handle_connection(stream, &acceptor, &handler));
+            if let Err(e) = result {
+                warn!("Swarm connection from {} failed: {}", peer, e);
+            }
+        });
+    }
+}
+
+fn handle_connection(stream: TcpStream, acceptor: &SslAcceptor, handler: &Handler) -> Result<(), RpcError> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let mut stream = acceptor.accept(stream)?;
+    let peer = stream.ssl().peer_certificate().and_then(|cert| common_name(&cert));
+    let request: Request = read_message(&mut stream)?;
+    let response = handler(peer, request);
+    write_message(&mut stream, &response)?;
+    let _ = stream.shutdown();
+    Ok(())
+}
+
+// 参加の要求を送る
+//
+// まだ CA 証明書を持たないため、マネージャが提示した証明書チェーンにトークンのフィンガープリントの
+// CA 証明書があり、マネージャの証明書がその CA の署名であることを確かめてから秘密の値を送る。
+pub fn join(address: &str, ca_fingerprint: &str, request: &Request) -> Result<(Response, Vec<u8>), RpcError> {
+    let mut builder = SslConnector::builder(SslMethod::tls())?;
+    builder.set_verify(SslVerifyMode::NONE);
+    let mut stream = builder
+        .build()
+        .configure()?
+        .verify_hostname(false)
+        .use_server_name_indication(false)
+        .connect("rocker-swarm", connect(address)?)?;
+    let ca = pinned_ca(stream.ssl(), ca_fingerprint)?;
+    Ok((exchange(&mut stream, request)?, ca))
+}
+
+fn pinned_ca(ssl: &SslRef, ca_fingerprint: &str) -> Result<Vec<u8>, RpcError> {
+    let mismatch = || SwarmError::PermissionDenied("The manager's certificate does not match the join token".to_string());
+    let leaf = ssl.peer_certificate().ok_or_else(mismatch)?;
+    let chain = ssl.peer_cert_chain().ok_or_else(mismatch)?;
+    let ca = chain
+        .iter()
+        .find(|cert| fingerprint(cert).is_ok_and(|digest| digest == ca_fingerprint))
+        .ok_or_else(mismatch)?;
+    // CA 証明書そのものをマネージャの証明書として提示された場合も拒否する
+    if !leaf.verify(&*ca.public_key()?)? || fingerprint(&leaf)? == ca_fingerprint {
+        return Err(mismatch().into());
+    }
+    Ok(ca.to_pem()?)
+}
+
+// 参加した後の要求を送る（マネージャの証明書をクラスタの CA で検証し、ノードの証明書を提示する）
+pub fn call(address: &str, identity: &Identity, request: &Request) -> Result<Response, RpcError> {
+    let mut builder = SslConnector::builder(SslMethod::tls())?;
+    // システムの CA は信頼しない
+    let mut store = X509StoreBuilder::new()?;
+    store.add_cert(X509::from_pem(&identity.ca)?)?;
+    builder.set_cert_store(store.build());
+    builder.set_certificate(&*X509::from_pem(&identity.cert)?)?;
+    builder.set_private_key(&*PKey::private_key_from_pem(&identity.key)?)?;
+    builder.set_verify(SslVerifyMode::PEER);
+    let mut stream = builder
+        .build()
+        .configure()?
+        .verify_hostname(false)
+        .use_server_name_indication(false)
+        .connect("rocker-swarm", connect(address)?)?;
+    exchange(&mut stream, request)
+}
+
+fn connect(address: &str) -> Result<TcpStream, RpcError> {
+    let addr = address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| SwarmError::InvalidConfig(format!("Invalid address: {}", address)))?;
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+        .map_err(|e| SwarmError::Communication(format!("Failed to connect to {}: {}", address, e)))?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    Ok(stream)
+}
+
+fn exchange(stream: &mut SslStream<TcpStream>, request: &Request) -> Result<Response, RpcError> {
+    write_message(stream, request)?;
+    let response = read_message(stream)?;
+    let _ = stream.shutdown();
+    match response {
+        // マネージャのエラーのメッセージをそのまま返す
+        Response::Error { message } => Err(message.into()),
+        response => Ok(response),
+    }
+}
+
+fn read_message<T: DeserializeOwned>(stream: &mut impl Read) -> Result<T, RpcError> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_MESSAGE_SIZE)).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn write_message<T: Serialize>(stream: &mut impl Write, message: &T) -> Result<(), RpcError> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()?;
+    Ok(())
+}
@@ -0,0 +1,450 @@
+use chrono::{DateTime, Duration, Utc};
+use rocker_core::{
+    Constraint, Node, NodeAvailability, NodeRole, NodeStatus, NodeUpdate, Service, ServiceSpec, SwarmError, Task,
+    TaskState,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::rpc::{AssignedTask, Assignment, TaskStatus};
+
+// 報告が途絶えたノードを停止とみなすまでの秒数
+const NODE_TIMEOUT_SECS: i64 = 20;
+
+// サービスのオーバーレイネットワークの VNI とサブネット（10.10.0.0/16 から 1 つずつ割り当てる）
+const FIRST_SWARM_VNI: u32 = 4096;
+const FIRST_SWARM_SUBNET: u8 = 10;
+const MAX_SWARM_NETWORKS: u8 = 240;
+
+// サービスのオーバーレイネットワーク（全てのノードで同じ VNI とサブネットを使う）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwarmNetwork {
+    pub name: String,
+    pub vni: u32,
+    pub subnet: String,
+    // ノード間で交換するエンドポイントの通知に署名する鍵（割り当てと一緒に mTLS で配る）
+    #[serde(default)]
+    pub key: String,
+}
+
+impl SwarmNetwork {
+    // ノードごとに /24 のアドレスの範囲を分け、別のノードのコンテナとアドレスが重ならないようにする
+    pub fn ip_range(&self, node_index: u32) -> String {
+        let prefix = self.subnet.split('.').take(2).collect::<Vec<_>>().join(".");
+        format!("{}.{}.0/24", prefix, node_index)
+    }
+}
+
+// マネージャが保存するクラスタの望ましい状態（ノード・サービス・タスク・ネットワーク）
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Store {
+    nodes: HashMap<String, Node>,
+    services: HashMap<String, Service>,
+    tasks: HashMap<String, Task>,
+    networks: HashMap<String, SwarmNetwork>,
+}
+
+impl Store {
+    // ノードを登録する（アドレスの範囲に使う番号は空いている最小の番号）
+    pub fn add_node(&mut self, hostname: &str, address: &str, role: NodeRole) -> Node {
+        let mut index = 1;
+        while self.nodes.values().any(|node| node.index == index) {
+            index += 1;
+        }
+        let now = Utc::now();
+        let node = Node {
+            id: Uuid::new_v4().simple().to_string(),
+            hostname: hostname.to_string(),
+            role,
+            address: address.to_string(),
+            status: NodeStatus::Ready,
+            availability: NodeAvailability::Active,
+            labels: HashMap::new(),
+            index,
+            joined_at: now,
+            last_seen: now,
+        };
+        self.nodes.insert(node.id.clone(), node.clone());
+        node
+    }
+
+    // ノードを外す（タスクは reconcile で他のノードに移る）
+    pub fn remove_node(&mut self, id_or_name: &str) -> Result<Node, SwarmError> {
+        let id = self.node(id_or_name)?.id;
+        Ok(self.nodes.remove(&id).expect("node exists"))
+    }
+
+    pub fn contains_node(&self, id: &str) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    // ID・ID の前方一致・ホスト名のいずれかでノードを探す
+    pub fn node(&self, id_or_name: &str) -> Result<Node, SwarmError> {
+        if let Some(node) = self.nodes.get(id_or_name) {
+            return Ok(node.clone());
+        }
+        let matches: Vec<&Node> = self
+            .nodes
+            .values()
+            .filter(|node| node.id.starts_with(id_or_name) || node.hostname == id_or_name)
+            .collect();
+        match matches.as_slice() {
+            [node] => Ok((*node).clone()),
+            _ => Err(SwarmError::NodeNotFound(id_or_name.to_string())),
+        }
+    }
+
+    // ノードの利用可否とラベルを変える（drain や制約に合わなくなったタスクは reconcile で他のノードに移る）
+    pub fn update_node(&mut self, id_or_name: &str, update: &NodeUpdate) -> Result<Node, SwarmError> {
+        let id = self.node(id_or_name)?.id;
+        let node = self.nodes.get_mut(&id).expect("node exists");
+        if let Some(availability) = update.availability {
+            node.availability = availability;
+        }
+        for key in &update.labels_rm {
+            node.labels.remove(key);
+        }
+        for (key, value) in &update.labels_add {
+            node.labels.insert(key.clone(), value.clone());
+        }
+        self.reconcile(Utc::now());
+        self.node(&id)
+    }
+
+    pub fn nodes(&self) -> Vec<Node> {
+        let mut nodes: Vec<Node> = self.nodes.values().cloned().collect();
+        nodes.sort_by_key(|node| node.index);
+        nodes
+    }
+
+    pub fn create_service(&mut self, spec: ServiceSpec) -> Result<Service, SwarmError> {
+        validate_name(&spec.name)?;
+        if self.services.values().any(|service| service.spec.name == spec.name) {
+            return Err(SwarmError::ServiceAlreadyExists(spec.name));
+        }
+        self.validate_spec(&spec)?;
+
+        let now = Utc::now();
+        let service = Service {
+            id: Uuid::new_v4().simple().to_string(),
+            spec,
+            created_at: now,
+            updated_at: now,
+            running: 0,
+            tasks: Vec::new(),
+        };
+        self.services.insert(service.id.clone(), service.clone());
+        self.reconcile(now);
+        self.service(&service.id)
+    }
+
+    // サービスの定義を置き換える（コンテナの設定・ネットワーク・制約が変わればタスクを作り直す）
+    pub fn update_service(&mut self, id_or_name: &str, spec: ServiceSpec) -> Result<Service, SwarmError> {
+        let service = self.find_service(id_or_name)?;
+        let id = service.id.clone();
+        if spec.name != service.spec.name {
+            return Err(SwarmError::InvalidConfig(format!(
+                "Cannot rename service {} to {}",
+                service.spec.name, spec.name
+            )));
+        }
+        let replace = spec.network != service.spec.network
+            || spec.constraints != service.spec.constraints
+            || serde_json::to_value(&spec.template).ok() != serde_json::to_value(&service.spec.template).ok();
+        self.validate_spec(&spec)?;
+
+        let now = Utc::now();
+        if replace {
+            // 新しいタスクは別の ID になるため、ノードは古いタスクのコンテナを削除して作り直す
+            self.tasks.retain(|_, task| task.service_id != id);
+        }
+        if let Some(service) = self.services.get_mut(&id) {
+            service.spec = spec;
+            service.updated_at = now;
+        }
+        self.reconcile(now);
+        self.service(&id)
+    }
+
+    pub fn scale_service(&mut self, id_or_name: &str, replicas: u64) -> Result<Service, SwarmError> {
+        let id = self.find_service(id_or_name)?.id.clone();
+        let now = Utc::now();
+        if let Some(service) = self.services.get_mut(&id) {
+            service.spec.replicas = replicas;
+            service.updated_at = now;
+        }
+        self.reconcile(now);
+        self.service(&id)
+    }
+
+    // サービスとタスクを削除する（各ノードがタスクのコンテナを削除する）
+    pub fn remove_service(&mut self, id_or_name: &str) -> Result<(), SwarmError> {
+        let id = self.find_service(id_or_name)?.id.clone();
+        self.services.remove(&id);
+        self.tasks.retain(|_, task| task.service_id != id);
+        Ok(())
+    }
+
+    // タスクを含めたサービス
+    pub fn service(&self, id_or_name: &str) -> Result<Service, SwarmError> {
+        let mut service = self.with_running(self.find_service(id_or_name)?);
+        service.tasks = self
+            .tasks
+            .values()
+            .filter(|task| task.service_id == service.id)
+            .cloned()
+            .collect();
+        service.tasks.sort_by_key(|task| task.slot);
+        Ok(service)
+    }
+
+    pub fn services(&self) -> Vec<Service> {
+        let mut services: Vec<Service> = self.services.values().map(|service| self.with_running(service)).collect();
+        services.sort_by(|a, b| a.spec.name.cmp(&b.spec.name));
+        services
+    }
+
+    // ノードの報告を反映し、そのノードに割り当てたタスクを返す（状態が変わったかも返す）
+    pub fn heartbeat(
+        &mut self,
+        node_id: &str,
+        statuses: &[TaskStatus],
+        now: DateTime<Utc>,
+    ) -> Result<(Assignment, bool), SwarmError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| SwarmError::PermissionDenied(format!("Node {} has been removed from the swarm", node_id)))?;
+        let mut changed = node.status != NodeStatus::Ready;
+        node.status = NodeStatus::Ready;
+        node.last_seen = now;
+        let node_index = node.index;
+
+        for status in statuses {
+            let task = match self.tasks.get_mut(&status.task_id) {
+                Some(task) if task.node_id.as_deref() == Some(node_id) => task,
+                _ => continue,
+            };
+            if task.state != status.state || task.container_id != status.container_id || task.error != status.error {
+                task.state = status.state;
+                task.container_id = status.container_id.clone();
+                task.error = status.error.clone();
+                task.updated_at = now;
+                changed = true;
+            }
+        }
+        changed |= self.reconcile(now);
+
+        let mut tasks = Vec::new();
+        for task in self.tasks.values().filter(|task| task.node_id.as_deref() == Some(node_id)) {
+            let spec = match self.services.get(&task.service_id) {
+                Some(service) => &service.spec,
+                None => continue,
+            };
+            tasks.push(AssignedTask {
+                task: task.clone(),
+                template: spec.template.clone(),
+                network: spec.network.as_ref().and_then(|name| self.networks.get(name)).cloned(),
+            });
+        }
+        let peers = self
+            .nodes
+            .values()
+            .filter(|node| node.id != node_id)
+            .map(|node| node.address.clone())
+            .collect();
+        Ok((
+            Assignment {
+                tasks,
+                peers,
+                node_index,
+            },
+            changed,
+        ))
+    }
+
+    // 望ましい状態に合わせてタスクを作成・削除・割り当てる（変更があれば true）
+    //
+    // 報告が途絶えたノード・drain のノード・制約に合わなくなったノードのタスクは削除し、代わりのタスクを
+    // 割り当てる。割り当て先は動作中で active かつ制約を満たすノードのうち、そのサービスのタスクが最も
+    // 少なく、次に全体のタスクが最も少ないノード。
+    pub fn reconcile(&mut self, now: DateTime<Utc>) -> bool {
+        let mut changed = false;
+        for node in self.nodes.values_mut() {
+            if node.status == NodeStatus::Ready && now - node.last_seen > Duration::seconds(NODE_TIMEOUT_SECS) {
+                node.status = NodeStatus::Down;
+                changed = true;
+            }
+        }
+
+        // 制約はサービスの作成時に確かめてある
+        let constraints: HashMap<&str, Vec<Constraint>> = self
+            .services
+            .values()
+            .map(|service| {
+                let constraints = service.spec.constraints.iter().filter_map(|c| Constraint::parse(c).ok());
+                (service.id.as_str(), constraints.collect())
+            })
+            .collect();
+        let satisfies = |node: &Node, service_id: &str| {
+            constraints
+                .get(service_id)
+                .is_some_and(|constraints| constraints.iter().all(|constraint| constraint.matches(node)))
+        };
+
+        let before = self.tasks.len();
+        let nodes = &self.nodes;
+        let services = &self.services;
+        self.tasks.retain(|_, task| {
+            let placed = task.node_id.as_ref().is_none_or(|node_id| {
+                nodes.get(node_id).is_some_and(|node| {
+                    node.status == NodeStatus::Ready
+                        && node.availability != NodeAvailability::Drain
+                        && satisfies(node, &task.service_id)
+                })
+            });
+            let wanted = services
+                .get(&task.service_id)
+                .is_some_and(|service| task.slot <= service.spec.replicas);
+            placed && wanted
+        });
+        changed |= self.tasks.len() != before;
+
+        let mut services: Vec<&Service> = self.services.values().collect();
+        services.sort_by_key(|service| service.created_at);
+        for service in services {
+            let candidates: Vec<String> = self
+                .nodes
+                .values()
+                .filter(|node| {
+                    node.status == NodeStatus::Ready
+                        && node.availability == NodeAvailability::Active
+                        && satisfies(node, &service.id)
+                })
+                .map(|node| node.id.clone())
+                .collect();
+            for slot in 1..=service.spec.replicas {
+                let existing = self
+                    .tasks
+                    .values()
+                    .find(|task| task.service_id == service.id && task.slot == slot)
+                    .map(|task| (task.id.clone(), task.node_id.is_some()));
+                if matches!(existing, Some((_, true))) {
+                    continue;
+                }
+                let node_id = pick_node(&self.tasks, &candidates, &service.id);
+                if existing.is_some() && node_id.is_none() {
+                    continue;
+                }
+                changed = true;
+                let (state, error) = if node_id.is_some() {
+                    (TaskState::Assigned, None)
+                } else {
+                    (TaskState::Pending, Some("No suitable node".to_string()))
+                };
+                let id = existing.map(|(id, _)| id).unwrap_or_else(|| Uuid::new_v4().simple().to_string());
+                let task = Task {
+                    id: id.clone(),
+                    service_id: service.id.clone(),
+                    service_name: service.spec.name.clone(),
+                    slot,
+                    node_id,
+                    state,
+                    container_id: None,
+                    error,
+                    updated_at: now,
+                };
+                self.tasks.insert(id, task);
+            }
+        }
+        changed
+    }
+
+    // イメージと制約を確かめ、ネットワークを割り当てる
+    fn validate_spec(&mut self, spec: &ServiceSpec) -> Result<(), SwarmError> {
+        if spec.template.image.is_empty() {
+            return Err(SwarmError::InvalidConfig("Image is required".to_string()));
+        }
+        // 秘密情報はノードごとのデーモンにあり、クラスタで共有しない
+        if !spec.template.secrets.is_empty() {
+            return Err(SwarmError::InvalidConfig("Secrets are not supported for services".to_string()));
+        }
+        for constraint in &spec.constraints {
+            Constraint::parse(constraint)?;
+        }
+        if let Some(network) = &spec.network {
+            validate_name(network)?;
+            self.allocate_network(network)?;
+        }
+        Ok(())
+    }
+
+    fn find_service(&self, id_or_name: &str) -> Result<&Service, SwarmError> {
+        if let Some(service) = self.services.get(id_or_name) {
+            return Ok(service);
+        }
+        let matches: Vec<&Service> = self
+            .services
+            .values()
+            .filter(|service| service.spec.name == id_or_name || service.id.starts_with(id_or_name))
+            .collect();
+        match matches.as_slice() {
+            [service] => Ok(service),
+            _ => Err(SwarmError::ServiceNotFound(id_or_name.to_string())),
+        }
+    }
+
+    fn with_running(&self, service: &Service) -> Service {
+        let mut service = service.clone();
+        service.running = self
+            .tasks
+            .values()
+            .filter(|task| task.service_id == service.id && task.state == TaskState::Running)
+            .count() as u64;
+        service
+    }
+
+    // 初めて使うネットワークに VNI・サブネット・鍵を割り当てる（鍵の無い以前のネットワークには鍵だけを足す）
+    fn allocate_network(&mut self, name: &str) -> Result<(), SwarmError> {
+        if let Some(network) = self.networks.get_mut(name) {
+            if network.key.is_empty() {
+                network.key = Uuid::new_v4().simple().to_string();
+            }
+            return Ok(());
+        }
+        let index = (0..MAX_SWARM_NETWORKS as u32)
+            .find(|index| !self.networks.values().any(|network| network.vni == FIRST_SWARM_VNI + index))
+            .ok_or_else(|| SwarmError::InvalidConfig("No available subnets for swarm networks".to_string()))?;
+        let network = SwarmNetwork {
+            name: name.to_string(),
+            vni: FIRST_SWARM_VNI + index,
+            subnet: format!("10.{}.0.0/16", FIRST_SWARM_SUBNET as u32 + index),
+            key: Uuid::new_v4().simple().to_string(),
+        };
+        self.networks.insert(name.to_string(), network);
+        Ok(())
+    }
+}
+
+fn pick_node(tasks: &HashMap<String, Task>, candidates: &[String], service_id: &str) -> Option<String> {
+    candidates
+        .iter()
+        .min_by_key(|node| {
+            let on_node = tasks.values().filter(|task| task.node_id.as_ref() == Some(*node));
+            let total = on_node.clone().count();
+            let of_service = on_node.filter(|task| task.service_id == service_id).count();
+            (of_service, total, (*node).clone())
+        })
+        .cloned()
+}
+
+// サービスとネットワークの名前はコンテナとネットワークの名前に使うため英数字・"_"・"-" に限る
+fn validate_name(name: &str) -> Result<(), SwarmError> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid {
+        return Err(SwarmError::InvalidConfig(format!("Invalid name: {:?}", name)));
+    }
+    Ok(())
+}
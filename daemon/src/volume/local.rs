@@ -0,0 +1,302 @@
+use nix::errno::Errno;
+#[cfg(target_os = "linux")]
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use rocker_core::VolumeError;
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
+
+// local ドライバで指定できるオプション
+pub const DRIVER_OPTS: [&str; 3] = ["type", "device", "o"];
+
+// o オプションのうち mount(2) のフラグになるもの（それ以外はファイルシステムに渡す）
+const MOUNT_FLAGS: [&str; 9] = ["ro", "nosuid", "nodev", "noexec", "sync", "noatime", "nodiratime", "relatime", "bind"];
+#[cfg(target_os = "linux")]
+const MS_FLAGS: [(&str, MsFlags); 9] = [
+    ("ro", MsFlags::MS_RDONLY),
+    ("nosuid", MsFlags::MS_NOSUID),
+    ("nodev", MsFlags::MS_NODEV),
+    ("noexec", MsFlags::MS_NOEXEC),
+    ("sync", MsFlags::MS_SYNCHRONOUS),
+    ("noatime", MsFlags::MS_NOATIME),
+    ("nodiratime", MsFlags::MS_NODIRATIME),
+    ("relatime", MsFlags::MS_RELATIME),
+    ("bind", MsFlags::MS_BIND),
+];
+// 既定の動作と同じため無視するフラグ
+const DEFAULT_FLAGS: [&str; 5] = ["rw", "suid", "dev", "exec", "async"];
+
+// サーバのアドレスを指定するオプション（カーネルは名前解決をしないため省略時はマウント時に解決する）
+const NFS_ADDRESS_OPTION: &str = "addr";
+const CIFS_ADDRESS_OPTIONS: [&str; 2] = ["ip", "addr"];
+// CIFS のユーザ名・パスワードを書いたファイル（mount.cifs と同じ username=・password=・domain= の形式）
+const CIFS_CREDENTIALS_OPTION: &str = "credentials";
+
+// driver_opts から作った mount(2) の引数
+pub struct MountSpec {
+    fstype: String,
+    device: String,
+    // mount(2) に渡すのは Linux だけ
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    flags: Vec<&'static str>,
+    data: Vec<String>,
+}
+
+// ネットワークファイルシステムの種類
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Remote {
+    Nfs,
+    Cifs,
+}
+
+impl MountSpec {
+    fn remote(&self) -> Option<Remote> {
+        match self.fstype.as_str() {
+            "nfs" | "nfs4" => Some(Remote::Nfs),
+            "cifs" | "smb3" => Some(Remote::Cifs),
+            _ => None,
+        }
+    }
+
+    fn option(&self, key: &str) -> Option<&str> {
+        self.data
+            .iter()
+            .find_map(|option| option.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+    }
+
+    fn has_address(&self, remote: Remote) -> bool {
+        match remote {
+            Remote::Nfs => self.option(NFS_ADDRESS_OPTION).is_some(),
+            Remote::Cifs => CIFS_ADDRESS_OPTIONS.iter().any(|key| self.option(key).is_some()),
+        }
+    }
+
+    // device からサーバのホスト名を取り出す（nfs は host:/export、cifs は //host/share）
+    fn server(&self) -> Option<&str> {
+        let host = match self.remote()? {
+            Remote::Nfs => self.device.split_once(":/")?.0,
+            Remote::Cifs => self.device.strip_prefix("//")?.split('/').next()?,
+        };
+        Some(host.trim_start_matches('[').trim_end_matches(']'))
+    }
+
+    // NFS・CIFS の device とオプションを検証する（サーバへの接続はマウント時まで行わない）
+    fn validate(&self) -> Result<(), VolumeError> {
+        let remote = match self.remote() {
+            Some(remote) => remote,
+            None => return Ok(()),
+        };
+        let (format, valid) = match remote {
+            Remote::Nfs => ("host:/export", self.device.contains(":/")),
+            Remote::Cifs => (
+                "//host/share",
+                self.device
+                    .strip_prefix("//")
+                    .and_then(|rest| rest.split_once('/'))
+                    .is_some_and(|(host, share)| !host.is_empty() && !share.is_empty()),
+            ),
+        };
+        if !valid {
+            return Err(VolumeError::Create(format!(
+                "Invalid device for {} volumes (expected {}): {}",
+                self.fstype, format, self.device
+            )));
+        }
+        if self.server().is_none_or(str::is_empty) && !self.has_address(remote) {
+            return Err(VolumeError::Create(format!(
+                "The {} server must be given in device or with the {} option",
+                self.fstype,
+                if remote == Remote::Nfs { "addr" } else { "ip" }
+            )));
+        }
+        if let Some(path) = self.option(CIFS_CREDENTIALS_OPTION) {
+            if remote != Remote::Cifs {
+                return Err(VolumeError::Create(format!("credentials is not supported for {} volumes", self.fstype)));
+            }
+            if !Path::new(path).is_absolute() {
+                return Err(VolumeError::Create(format!("credentials must be an absolute path: {}", path)));
+            }
+        }
+        Ok(())
+    }
+
+    // マウント時にカーネルに渡すオプション（サーバのアドレスを解決し、認証情報のファイルを読み込む）
+    fn mount_data(&self) -> Result<String, VolumeError> {
+        let remote = match self.remote() {
+            Some(remote) => remote,
+            None => return Ok(self.data.join(",")),
+        };
+
+        let mut data: Vec<String> = self
+            .data
+            .iter()
+            .filter(|option| !option.starts_with(&format!("{}=", CIFS_CREDENTIALS_OPTION)))
+            .cloned()
+            .collect();
+
+        if !self.has_address(remote) {
+            let address_option = match remote {
+                Remote::Nfs => NFS_ADDRESS_OPTION,
+                Remote::Cifs => CIFS_ADDRESS_OPTIONS[0],
+            };
+            let server = self.server().unwrap_or_default();
+            let address = resolve(server).ok_or_else(|| {
+                VolumeError::Mount(format!("Cannot resolve the {} server {}", self.fstype, server))
+            })?;
+            data.push(format!("{}={}", address_option, address));
+        }
+
+        if let Some(path) = self.option(CIFS_CREDENTIALS_OPTION) {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| VolumeError::Mount(format!("Cannot read credentials file {}: {}", path, e)))?;
+            for line in content.lines().map(str::trim) {
+                match line.split_once('=') {
+                    Some(("username" | "user" | "password" | "pass" | "domain" | "dom", _)) => {
+                        data.push(line.to_string())
+                    }
+                    _ if line.is_empty() || line.starts_with('#') => {}
+                    _ => {
+                        return Err(VolumeError::Mount(format!("Invalid line in credentials file {}", path)));
+                    }
+                }
+            }
+        }
+        Ok(data.join(","))
+    }
+}
+
+fn resolve(host: &str) -> Option<String> {
+    let addresses: Vec<SocketAddr> = (host, 0).to_socket_addrs().ok()?.collect();
+    addresses
+        .iter()
+        .find(|address| address.is_ipv4())
+        .or_else(|| addresses.first())
+        .map(|address| address.ip().to_string())
+}
+
+// type・device・o からマウントの指定を作る（type がなければ _data ディレクトリをそのまま使う）
+//
+// 例: type=nfs,device=nfs.example.com:/export,o=vers=4,soft や type=tmpfs,device=tmpfs,o=size=100m、
+// type=cifs,device=//fs.example.com/share,o=credentials=/etc/rocker/smb.cred,vers=3.0
+pub fn mount_spec(opts: &HashMap<String, String>) -> Result<Option<MountSpec>, VolumeError> {
+    if let Some(key) = opts.keys().find(|k| !DRIVER_OPTS.contains(&k.as_str())) {
+        return Err(VolumeError::Create(format!("Invalid option for the local driver: {}", key)));
+    }
+    let fstype = match opts.get("type") {
+        Some(fstype) if !fstype.is_empty() => fstype.clone(),
+        Some(_) => return Err(VolumeError::Create("type must not be empty".to_string())),
+        None if opts.is_empty() => return Ok(None),
+        None => return Err(VolumeError::Create("type is required with device and o options".to_string())),
+    };
+    let device = match opts.get("device") {
+        Some(device) if !device.is_empty() => device.clone(),
+        _ if fstype == "tmpfs" => "tmpfs".to_string(),
+        _ => return Err(VolumeError::Create(format!("device is required for {} volumes", fstype))),
+    };
+
+    let mut flags = Vec::new();
+    let mut data = Vec::new();
+    for option in opts.get("o").map(String::as_str).unwrap_or("").split(',') {
+        if option.is_empty() || DEFAULT_FLAGS.contains(&option) {
+            continue;
+        }
+        match MOUNT_FLAGS.iter().find(|name| **name == option) {
+            Some(flag) => flags.push(*flag),
+            None => data.push(option.to_string()),
+        }
+    }
+
+    let spec = MountSpec {
+        fstype,
+        device,
+        flags,
+        data,
+    };
+    spec.validate()?;
+    Ok(Some(spec))
+}
+
+// マウントの指定に従って target にマウントする
+#[cfg(target_os = "linux")]
+pub fn mount_volume(spec: &MountSpec, target: &Path) -> Result<(), VolumeError> {
+    let data = spec.mount_data()?;
+    let data = if data.is_empty() { None } else { Some(data.as_str()) };
+    let flags = MS_FLAGS
+        .iter()
+        .filter(|(name, _)| spec.flags.contains(name))
+        .fold(MsFlags::empty(), |flags, (_, flag)| flags | *flag);
+    mount(
+        Some(spec.device.as_str()),
+        target,
+        Some(spec.fstype.as_str()),
+        flags,
+        data,
+    )
+    .map_err(|e| {
+        VolumeError::Mount(format!(
+            "mount -t {} {} {}: {}{}",
+            spec.fstype,
+            spec.device,
+            target.display(),
+            e,
+            mount_hint(spec, e)
+        ))
+    })
+}
+
+// Linux 以外ではボリュームをマウントできない（--dry-run ではマウントしない）
+#[cfg(not(target_os = "linux"))]
+pub fn mount_volume(spec: &MountSpec, target: &Path) -> Result<(), VolumeError> {
+    spec.mount_data()?;
+    Err(VolumeError::Mount(format!(
+        "mount -t {} {} {}: {}",
+        spec.fstype,
+        spec.device,
+        target.display(),
+        Errno::ENOSYS
+    )))
+}
+
+// よくある失敗の原因
+#[cfg(target_os = "linux")]
+fn mount_hint(spec: &MountSpec, errno: Errno) -> String {
+    let hint = match (spec.remote(), errno) {
+        (_, Errno::ENODEV) => format!("the kernel does not support {} (is the module loaded?)", spec.fstype),
+        (Some(_), Errno::ETIMEDOUT | Errno::EHOSTUNREACH | Errno::ECONNREFUSED | Errno::ENETUNREACH) => {
+            format!("the server {} is not reachable", spec.server().unwrap_or_default())
+        }
+        (Some(Remote::Nfs), Errno::ENOENT) => "the export does not exist on the server".to_string(),
+        (Some(Remote::Nfs), Errno::EACCES | Errno::EPERM) => {
+            "the server denied access to the export (check the exports of the server)".to_string()
+        }
+        (Some(Remote::Cifs), Errno::EACCES | Errno::EPERM) => {
+            "the server rejected the credentials (check username, password or credentials)".to_string()
+        }
+        (Some(Remote::Cifs), Errno::ENOENT) => "the share does not exist on the server".to_string(),
+        _ => return String::new(),
+    };
+    format!(" ({})", hint)
+}
+
+#[cfg(target_os = "linux")]
+pub fn unmount_volume(target: &Path) -> Result<(), VolumeError> {
+    umount2(target, MntFlags::MNT_DETACH).map_err(|e| VolumeError::Unmount(format!("{}: {}", target.display(), e)))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn unmount_volume(target: &Path) -> Result<(), VolumeError> {
+    nix::mount::unmount(target, nix::mount::MntFlags::empty())
+        .map_err(|e| VolumeError::Unmount(format!("{}: {}", target.display(), e)))
+}
+
+// target がマウントポイントになっているか（/proc/self/mountinfo の 5 番目の列を見る）
+pub fn is_mounted(target: &Path) -> bool {
+    let mountinfo = match std::fs::read_to_string("/proc/self/mountinfo") {
+        Ok(mountinfo) => mountinfo,
+        Err(_) => return false,
+    };
+    mountinfo
+        .lines()
+        .filter_map(|line| line.split(' ').nth(4))
+        .any(|mount_point| Path::new(mount_point) == target)
+}
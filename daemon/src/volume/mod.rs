@@ -0,0 +1,466 @@
+use chrono::Utc;
+use rocker_core::{
+    lookup, LookupError, RockerError, Volume, VolumeConfig, VolumeDriver, VolumeError, VolumeSnapshot, VolumeUsage,
+    ANONYMOUS_VOLUME_LABEL,
+};
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+use crate::reflink;
+
+mod local;
+mod snapshot;
+mod usage;
+
+// ボリュームを管理する構造体
+pub struct Manager {
+    volumes: HashMap<String, Volume>,
+    state_dir: PathBuf,
+    // ボリューム ID ごとの、ボリュームをマウントして動作中のコンテナ
+    mounts: HashMap<String, HashSet<String>>,
+    usage: usage::UsageCache,
+    // --dry-run では device などを指定したボリュームもマウントしない
+    dry_run: bool,
+}
+
+impl Manager {
+    pub fn new(data_root: &Path, dry_run: bool) -> Self {
+        Manager {
+            volumes: HashMap::new(),
+            state_dir: data_root.join("volumes"),
+            mounts: HashMap::new(),
+            usage: usage::UsageCache::default(),
+            dry_run,
+        }
+    }
+
+    // 保存済みのボリューム情報を読み込む
+    pub async fn init(&mut self) -> Result<(), RockerError> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+
+        let mut entries = tokio::fs::read_dir(&self.state_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let config_path = entry.path().join("volume.json");
+            if !config_path.exists() {
+                continue;
+            }
+
+            let content = tokio::fs::read_to_string(&config_path).await?;
+            match serde_json::from_str::<Volume>(&content) {
+                Ok(volume) => {
+                    self.volumes.insert(volume.id.clone(), volume);
+                }
+                Err(e) => warn!("Skipping invalid volume state {}: {}", config_path.display(), e),
+            }
+        }
+
+        info!("Loaded {} volumes", self.volumes.len());
+        Ok(())
+    }
+
+    pub async fn list_all(&self) -> Result<Vec<Volume>, RockerError> {
+        Ok(self.volumes.values().cloned().collect())
+    }
+
+    // ボリュームの使用量と参照しているコンテナの数（使用量は一定時間キャッシュする）
+    pub async fn usage(&self, volume: &Volume, references: &HashMap<String, Vec<String>>) -> VolumeUsage {
+        VolumeUsage {
+            size: self.usage.size(&volume.mountpoint).await,
+            ref_count: users(volume, references).len(),
+        }
+    }
+
+    // 使用量を付けたボリュームの一覧
+    pub async fn list_with_usage(&self, references: &HashMap<String, Vec<String>>) -> Vec<Volume> {
+        let mut volumes = Vec::with_capacity(self.volumes.len());
+        for volume in self.volumes.values() {
+            let mut volume = volume.clone();
+            volume.usage = Some(self.usage(&volume, references).await);
+            volumes.push(volume);
+        }
+        volumes
+    }
+
+    // ID・ID の前方一致・名前のいずれかでボリュームを探す
+    pub fn get(&self, id_or_name: &str) -> Result<&Volume, VolumeError> {
+        lookup(self.volumes.values(), id_or_name).map_err(|e| match e {
+            LookupError::NotFound => VolumeError::NotFound(id_or_name.to_string()),
+            LookupError::Ambiguous(_) => VolumeError::Ambiguous(id_or_name.to_string()),
+        })
+    }
+
+    // ボリュームを作成し、<state_dir>/<name>/_data を用意する（名前が空の場合は ID を名前にする）
+    //
+    // local ドライバの type・device・o はここで検証し、コンテナが最初に使うときにマウントする。
+    pub async fn create(
+        &mut self,
+        name: &str,
+        driver: VolumeDriver,
+        config: VolumeConfig,
+    ) -> Result<Volume, RockerError> {
+        if !name.is_empty() {
+            validate_name(name)?;
+            if self.volumes.values().any(|v| v.name == name) {
+                return Err(VolumeError::AlreadyExists(name.to_string()).into());
+            }
+        }
+        if driver != VolumeDriver::Local {
+            return Err(VolumeError::InvalidDriver(driver.to_string()).into());
+        }
+        local::mount_spec(&config.driver_opts)?;
+
+        let mut volume = Volume::new(name.to_string(), driver, config);
+        if volume.name.is_empty() {
+            volume.name = volume.id.replace('-', "");
+        }
+        volume.labels = volume.config.labels.clone();
+        volume.mountpoint = self.state_dir.join(&volume.name).join("_data");
+        tokio::fs::create_dir_all(&volume.mountpoint)
+            .await
+            .map_err(|e| VolumeError::Create(format!("{}: {}", volume.mountpoint.display(), e)))?;
+
+        let id = volume.id.clone();
+        self.volumes.insert(id.clone(), volume.clone());
+        self.save(&id).await?;
+        info!("Created volume {} ({})", volume.name, volume.id);
+        Ok(volume)
+    }
+
+    // ボリュームとデータを削除する
+    //
+    // references はコンテナがマウントしているボリューム（名前か ID）ごとのコンテナ ID。参照されている
+    // ボリュームは停止中のコンテナのものでも削除しない。
+    pub async fn remove(
+        &mut self,
+        id_or_name: &str,
+        references: &HashMap<String, Vec<String>>,
+    ) -> Result<(), RockerError> {
+        let volume = self.get(id_or_name)?;
+        let containers = users(volume, references);
+        if !containers.is_empty() {
+            return Err(VolumeError::InUse(format!(
+                "{} is used by container(s) {}",
+                volume.name,
+                containers.join(", ")
+            ))
+            .into());
+        }
+        if self.mounts.get(&volume.id).is_some_and(|users| !users.is_empty()) {
+            return Err(VolumeError::InUse(format!("{} is mounted by a running container", volume.name)).into());
+        }
+
+        let (id, name) = (volume.id.clone(), volume.name.clone());
+        if local::is_mounted(&volume.mountpoint) {
+            local::unmount_volume(&volume.mountpoint)?;
+        }
+        let dir = self.state_dir.join(&name);
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .map_err(|e| VolumeError::Remove(format!("{}: {}", dir.display(), e)))?;
+        }
+        if let Some(volume) = self.volumes.remove(&id) {
+            self.usage.invalidate(&volume.mountpoint);
+        }
+        info!("Removed volume {} ({})", name, id);
+        Ok(())
+    }
+
+    // どのコンテナからも参照されていないボリュームのうち filter に一致するものを全て削除し、削除したボリューム名と
+    // 解放した容量を返す
+    pub async fn prune(
+        &mut self,
+        references: &HashMap<String, Vec<String>>,
+        filter: impl Fn(&Volume) -> bool,
+    ) -> Result<(Vec<String>, u64), RockerError> {
+        let unused: Vec<(String, String, PathBuf)> = self
+            .volumes
+            .values()
+            .filter(|v| users(v, references).is_empty() && filter(v))
+            .map(|v| (v.id.clone(), v.name.clone(), v.mountpoint.clone()))
+            .collect();
+
+        let mut removed = Vec::new();
+        let mut reclaimed = 0;
+        for (id, name, mountpoint) in unused {
+            // 解放した容量はキャッシュではなく削除直前の使用量から数える
+            self.usage.invalidate(&mountpoint);
+            let size = self.usage.size(&mountpoint).await;
+            match self.remove(&id, references).await {
+                Ok(()) => {
+                    removed.push(name);
+                    reclaimed += size;
+                }
+                Err(e) => warn!("Failed to remove volume {}: {}", name, e),
+            }
+        }
+        Ok((removed, reclaimed))
+    }
+
+    // コンテナの VOLUME のパスや -v /path のために名前のないボリュームを作成する
+    pub async fn create_anonymous(&mut self) -> Result<Volume, RockerError> {
+        let mut config = VolumeConfig::default();
+        config.labels.insert(ANONYMOUS_VOLUME_LABEL.to_string(), String::new());
+        self.create("", VolumeDriver::Local, config).await
+    }
+
+    // 作成したばかりのボリュームに、イメージのマウント先にあった内容をコピーする
+    pub async fn seed(&self, id_or_name: &str, source: PathBuf) -> Result<(), RockerError> {
+        let target = self.get(id_or_name)?.mountpoint.clone();
+        if !source.is_dir() {
+            return Ok(());
+        }
+        tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+            copy_tree(&source, &target)?;
+            copy_attributes(&std::fs::symlink_metadata(&source)?, &target)
+        })
+        .await?
+        .map_err(|e| VolumeError::Create(format!("Failed to copy image content into volume {}: {}", id_or_name, e)))?;
+        Ok(())
+    }
+
+    // ボリュームのデータをコピーした新しいボリュームを作る（ラベルと driver_opts は引き継がない）
+    //
+    // device などをマウントするボリュームは _data ディレクトリにデータがないためコピーできない。
+    pub async fn clone_volume(&mut self, id_or_name: &str, name: &str) -> Result<Volume, RockerError> {
+        let source = self.get(id_or_name)?.clone();
+        if !source.config.driver_opts.is_empty() {
+            return Err(VolumeError::Create(format!(
+                "Cannot clone volume {}: only volumes without driver options can be cloned",
+                source.name
+            ))
+            .into());
+        }
+        if name.is_empty() {
+            return Err(VolumeError::Create("A name is required for the cloned volume".to_string()).into());
+        }
+
+        let volume = self.create(name, VolumeDriver::Local, VolumeConfig::default()).await?;
+        if let Err(e) = snapshot::copy_data(&source.mountpoint, &volume.mountpoint, false).await {
+            if let Err(e) = self.remove(&volume.id, &HashMap::new()).await {
+                warn!("Failed to remove volume {}: {}", volume.name, e);
+            }
+            return Err(e.into());
+        }
+        info!("Cloned volume {} into {}", source.name, volume.name);
+        Ok(volume)
+    }
+
+    // ボリュームのデータのスナップショットを <state_dir>/<name>/snapshots/<snapshot> に作る
+    //
+    // データの共有に reflink を使うため、対応していないファイルシステムではエラーにする。
+    pub async fn snapshot(&mut self, id_or_name: &str, name: &str) -> Result<VolumeSnapshot, RockerError> {
+        let volume = self.get(id_or_name)?.clone();
+        validate_name(name)?;
+        if !volume.config.driver_opts.is_empty() {
+            return Err(VolumeError::Snapshot(format!(
+                "Cannot snapshot volume {}: only volumes without driver options support snapshots",
+                volume.name
+            ))
+            .into());
+        }
+        if volume.snapshots.iter().any(|s| s.name == name) {
+            return Err(VolumeError::AlreadyExists(format!("snapshot {} of {}", name, volume.name)).into());
+        }
+
+        let dir = self.snapshot_dir(&volume, name);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| VolumeError::Snapshot(format!("{}: {}", dir.display(), e)))?;
+        if let Err(e) = snapshot::copy_data(&volume.mountpoint, &dir, true).await {
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            return Err(e.into());
+        }
+
+        let snapshot = VolumeSnapshot {
+            name: name.to_string(),
+            created_at: Utc::now(),
+        };
+        if let Some(volume) = self.volumes.get_mut(&volume.id) {
+            volume.snapshots.push(snapshot.clone());
+        }
+        self.save(&volume.id).await?;
+        info!("Created snapshot {} of volume {}", name, volume.name);
+        Ok(snapshot)
+    }
+
+    // ボリュームのデータをスナップショットの時点に戻す（スナップショットは残す）
+    //
+    // 動作中のコンテナがマウントしている間は書き込み中のデータを失うため戻さない。
+    pub async fn restore(&mut self, id_or_name: &str, name: &str) -> Result<(), RockerError> {
+        let volume = self.get(id_or_name)?.clone();
+        if !volume.snapshots.iter().any(|s| s.name == name) {
+            return Err(VolumeError::SnapshotNotFound(format!("{} of {}", name, volume.name)).into());
+        }
+        if self.mounts.get(&volume.id).is_some_and(|users| !users.is_empty()) {
+            return Err(VolumeError::InUse(format!("{} is mounted by a running container", volume.name)).into());
+        }
+
+        // 新しいデータを隣に用意してから入れ替え、途中で失敗しても元のデータを残す
+        let restored = volume.mountpoint.with_file_name("_data.restore");
+        let previous = volume.mountpoint.with_file_name("_data.old");
+        for dir in [&restored, &previous] {
+            if dir.exists() {
+                tokio::fs::remove_dir_all(dir).await?;
+            }
+        }
+        tokio::fs::create_dir_all(&restored).await?;
+        if let Err(e) = snapshot::copy_data(&self.snapshot_dir(&volume, name), &restored, true).await {
+            let _ = tokio::fs::remove_dir_all(&restored).await;
+            return Err(e.into());
+        }
+        copy_attributes(&std::fs::symlink_metadata(&volume.mountpoint)?, &restored)?;
+        tokio::fs::rename(&volume.mountpoint, &previous).await?;
+        tokio::fs::rename(&restored, &volume.mountpoint).await?;
+        if let Err(e) = tokio::fs::remove_dir_all(&previous).await {
+            warn!("Failed to remove {}: {}", previous.display(), e);
+        }
+
+        self.usage.invalidate(&volume.mountpoint);
+        info!("Restored volume {} from snapshot {}", volume.name, name);
+        Ok(())
+    }
+
+    pub async fn remove_snapshot(&mut self, id_or_name: &str, name: &str) -> Result<(), RockerError> {
+        let volume = self.get(id_or_name)?.clone();
+        if !volume.snapshots.iter().any(|s| s.name == name) {
+            return Err(VolumeError::SnapshotNotFound(format!("{} of {}", name, volume.name)).into());
+        }
+
+        let dir = self.snapshot_dir(&volume, name);
+        if dir.exists() {
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .map_err(|e| VolumeError::Remove(format!("{}: {}", dir.display(), e)))?;
+        }
+        if let Some(volume) = self.volumes.get_mut(&volume.id) {
+            volume.snapshots.retain(|s| s.name != name);
+        }
+        self.save(&volume.id).await?;
+        info!("Removed snapshot {} of volume {}", name, volume.name);
+        Ok(())
+    }
+
+    // コンテナが使うボリュームを用意し、コンテナにバインドマウントするディレクトリを返す
+    //
+    // device などを指定したボリュームは最初に使うコンテナの起動時にマウントし、最後のコンテナが
+    // 停止したときにアンマウントする。
+    pub async fn mount(&mut self, id_or_name: &str, container_id: &str) -> Result<PathBuf, RockerError> {
+        let volume = self.get(id_or_name)?.clone();
+
+        if let Some(spec) = local::mount_spec(&volume.config.driver_opts)? {
+            if !self.dry_run && !local::is_mounted(&volume.mountpoint) {
+                tokio::fs::create_dir_all(&volume.mountpoint).await?;
+                local::mount_volume(&spec, &volume.mountpoint)?;
+                info!("Mounted volume {} on {}", volume.name, volume.mountpoint.display());
+            }
+        }
+        self.mounts
+            .entry(volume.id.clone())
+            .or_default()
+            .insert(container_id.to_string());
+        Ok(volume.mountpoint)
+    }
+
+    // コンテナがボリュームを使い終えたことを記録し、使うコンテナがなくなればアンマウントする
+    pub fn unmount(&mut self, id_or_name: &str, container_id: &str) -> Result<(), VolumeError> {
+        let volume = self.get(id_or_name)?.clone();
+        let users = self.mounts.entry(volume.id.clone()).or_default();
+        users.remove(container_id);
+        if users.is_empty() && !volume.config.driver_opts.is_empty() && local::is_mounted(&volume.mountpoint) {
+            local::unmount_volume(&volume.mountpoint)?;
+            info!("Unmounted volume {}", volume.name);
+        }
+        Ok(())
+    }
+
+    // デーモンの再起動時に、動作中のコンテナが使っているボリュームを記録し、使われていない
+    // ボリュームのマウントを解除する
+    //
+    // references は動作中のコンテナがマウントしているボリューム（名前か ID）ごとのコンテナ ID。
+    pub fn reconcile(&mut self, references: &HashMap<String, Vec<String>>) {
+        self.mounts.clear();
+        for volume in self.volumes.values() {
+            let containers = users(volume, references);
+            if containers.is_empty() {
+                if !volume.config.driver_opts.is_empty() && local::is_mounted(&volume.mountpoint) {
+                    if let Err(e) = local::unmount_volume(&volume.mountpoint) {
+                        warn!("Failed to unmount volume {}: {}", volume.name, e);
+                    }
+                }
+                continue;
+            }
+            self.mounts.insert(volume.id.clone(), containers.into_iter().collect());
+        }
+    }
+
+    fn snapshot_dir(&self, volume: &Volume, name: &str) -> PathBuf {
+        self.state_dir.join(&volume.name).join("snapshots").join(name)
+    }
+
+    // ボリュームの状態をディスクに保存する
+    async fn save(&self, id: &str) -> Result<(), RockerError> {
+        let volume = self.get(id)?;
+        let dir = self.state_dir.join(&volume.name);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join("volume.json"), serde_json::to_vec_pretty(volume)?).await?;
+        Ok(())
+    }
+}
+
+// ディレクトリの中身を所有者と権限を保ったままコピーする（デバイスファイルなどは除く）
+fn copy_tree(source: &Path, target: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(source)? {
+        let entry = entry?;
+        let from = entry.path();
+        let to = target.join(entry.file_name());
+        let metadata = std::fs::symlink_metadata(&from)?;
+        let file_type = metadata.file_type();
+        if file_type.is_dir() {
+            std::fs::create_dir_all(&to)?;
+            copy_tree(&from, &to)?;
+        } else if file_type.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(&from)?, &to)?;
+        } else if file_type.is_file() {
+            reflink::copy_file(&from, &to)?;
+        } else {
+            continue;
+        }
+        copy_attributes(&metadata, &to)?;
+    }
+    Ok(())
+}
+
+fn copy_attributes(metadata: &std::fs::Metadata, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::lchown(target, Some(metadata.uid()), Some(metadata.gid()))?;
+    if !metadata.file_type().is_symlink() {
+        std::fs::set_permissions(target, metadata.permissions())?;
+    }
+    Ok(())
+}
+
+// ボリュームをマウントしているコンテナ
+fn users(volume: &Volume, references: &HashMap<String, Vec<String>>) -> Vec<String> {
+    [&volume.name, &volume.id]
+        .iter()
+        .filter_map(|key| references.get(*key))
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+// ボリューム名は英数字で始まり、英数字と _ . - のみを含む
+fn validate_name(name: &str) -> Result<(), VolumeError> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !valid {
+        return Err(VolumeError::Create(format!(
+            "Invalid volume name {:?}: only [a-zA-Z0-9][a-zA-Z0-9_.-] are allowed",
+            name
+        )));
+    }
+    Ok(())
+}
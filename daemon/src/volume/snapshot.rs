@@ -0,0 +1,51 @@
+use rocker_core::VolumeError;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::reflink;
+
+// ディレクトリの中身を target にコピーする
+//
+// reflink が使えるファイルシステム（btrfs・XFS・ZFS 2.2 以降など）ではデータを共有するため、
+// 大きなボリュームでもすぐに終わる。require_reflink の場合は通常のコピーに切り替えずにエラーにする。
+pub async fn copy_data(source: &Path, target: &Path, require_reflink: bool) -> Result<(), VolumeError> {
+    let reflink = if require_reflink {
+        // --reflink を知らない cp では共有できたかを確かめられない
+        if reflink::cp_arg().is_none() {
+            return Err(VolumeError::Snapshot(
+                "Snapshots need a cp supporting --reflink (GNU coreutils)".to_string(),
+            ));
+        }
+        Some("--reflink=always")
+    } else {
+        reflink::cp_arg()
+    };
+    let output = Command::new("cp")
+        .arg("-a")
+        .args(reflink)
+        .arg("--")
+        .arg(source.join("."))
+        .arg(target)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| VolumeError::Snapshot(format!("Failed to run cp: {}", e)))?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if require_reflink && (stderr.contains("clone") || stderr.contains("not supported")) {
+        return Err(VolumeError::Snapshot(format!(
+            "The filesystem of {} does not support reflinks (snapshots need btrfs, XFS or ZFS)",
+            source.display()
+        )));
+    }
+    Err(VolumeError::Snapshot(format!(
+        "cp {} {}: {}",
+        source.display(),
+        target.display(),
+        stderr.trim()
+    )))
+}
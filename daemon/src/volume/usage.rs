@@ -0,0 +1,91 @@
+use nix::sys::statvfs::statvfs;
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::local;
+
+// 走査結果を使い回す時間（system df や volume ls のたびにディレクトリを走査しない）
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+// ボリュームのディレクトリごとの使用量のキャッシュ
+#[derive(Default)]
+pub struct UsageCache {
+    entries: Mutex<HashMap<PathBuf, (u64, Instant)>>,
+}
+
+impl UsageCache {
+    // キャッシュが古ければ走査し直して使用量を返す
+    pub async fn size(&self, path: &Path) -> u64 {
+        if let Some((size, scanned_at)) = self.entries.lock().unwrap().get(path) {
+            if scanned_at.elapsed() < CACHE_TTL {
+                return *size;
+            }
+        }
+
+        let target = path.to_path_buf();
+        let size = tokio::task::spawn_blocking(move || disk_usage(&target))
+            .await
+            .unwrap_or(0);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), (size, Instant::now()));
+        size
+    }
+
+    pub fn invalidate(&self, path: &Path) {
+        self.entries.lock().unwrap().remove(path);
+    }
+}
+
+// ディレクトリが使っているディスクの容量
+//
+// デバイスや NFS をマウントしたボリュームはファイルシステム全体をボリュームが使うため、走査せずに
+// statvfs の使用量を返す。それ以外は du と同様にブロック数を合計する。
+fn disk_usage(path: &Path) -> u64 {
+    if local::is_mounted(path) {
+        if let Ok(stat) = statvfs(path) {
+            // ブロック数の型は OS によって異なる（macOS では u32）
+            #[allow(clippy::unnecessary_cast)]
+            let used = stat.blocks().saturating_sub(stat.blocks_free()) as u64;
+            return used * stat.fragment_size();
+        }
+    }
+
+    let root = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    let mut seen = HashSet::new();
+    scan(path, root.dev(), &mut seen)
+}
+
+// ハードリンクは 1 度だけ数え、別のファイルシステムには入らない
+fn scan(dir: &Path, dev: u64, seen: &mut HashSet<(u64, u64)>) -> u64 {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let metadata = match entry.path().symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.dev() != dev {
+            continue;
+        }
+        if metadata.nlink() > 1 && !seen.insert((metadata.dev(), metadata.ino())) {
+            continue;
+        }
+        total += metadata.blocks() * 512;
+        if metadata.is_dir() {
+            total += scan(&entry.path(), dev, seen);
+        }
+    }
+    total
+}
@@ -294,8 +294,12 @@ impl Instruction {
                     String::new()
                 };
 
-                let cmd_str = serde_json::to_string(&command).unwrap();
-                format!("HEALTHCHECK {}CMD {}", options_str, cmd_str)
+                if command.is_empty() {
+                    "HEALTHCHECK NONE".to_string()
+                } else {
+                    let cmd_str = serde_json::to_string(&command).unwrap();
+                    format!("HEALTHCHECK {}CMD {}", options_str, cmd_str)
+                }
             }
             Instruction::Shell { shell } => {
                 let shell_str = serde_json::to_string(&shell).unwrap();
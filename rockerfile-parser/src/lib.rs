@@ -43,8 +43,8 @@ pub type Result<T> = std::result::Result<T, RockerfileError>;
 
 /// Main function to parse a Rockerfile
 pub fn parse_rockerfile<P: AsRef<Path>>(path: P) -> Result<Vec<Stage>> {
-    let parser = RockerfileParser::new();
-    parser.parse_file(path)
+    let mut parser = RockerfileParser::new();
+    parser.parse_file(path).map(|stages| stages.to_vec())
 }
 
 /// Build context for a Rockerfile build
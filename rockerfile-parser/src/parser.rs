@@ -63,6 +63,8 @@ impl RockerfileParser {
                 "LABEL" => self.parse_label(&full_args)?,
                 "USER" => self.parse_user(&full_args)?,
                 "ARG" => self.parse_arg(&full_args)?,
+                "HEALTHCHECK" => self.parse_healthcheck(&full_args)?,
+                "ONBUILD" => self.parse_onbuild(&full_args)?,
                 _ => return Err(RockerfileError::UnknownInstruction(instruction.to_string())),
             }
             
@@ -331,6 +333,91 @@ impl RockerfileParser {
         Ok(())
     }
 
+    fn parse_healthcheck(&mut self, args: &str) -> Result<()> {
+        // HEALTHCHECK [--interval=30s] [--timeout=30s] [--start-period=0s] [--retries=3] CMD command
+        let mut interval = None;
+        let mut timeout = None;
+        let mut retries = None;
+        let mut start_period = None;
+        let mut remaining = args.trim();
+        while remaining.starts_with("--") {
+            let (opt, rest) = remaining
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| RockerfileError::InvalidInstruction("Invalid HEALTHCHECK instruction".to_string()))?;
+            let (name, value) = opt
+                .split_once('=')
+                .ok_or_else(|| RockerfileError::InvalidInstruction(format!("Invalid option: {}", opt)))?;
+            match name {
+                "--interval" => interval = Some(value.to_string()),
+                "--timeout" => timeout = Some(value.to_string()),
+                "--start-period" => start_period = Some(value.to_string()),
+                "--retries" => {
+                    retries = Some(value.parse().map_err(|_| {
+                        RockerfileError::InvalidInstruction(format!("Invalid HEALTHCHECK retries: {}", value))
+                    })?)
+                }
+                _ => return Err(RockerfileError::InvalidInstruction(format!("Unknown option: {}", opt))),
+            }
+            remaining = rest.trim_start();
+        }
+
+        // HEALTHCHECK NONE は継承したヘルスチェックを無効にする（コマンドを空にする）
+        let command = if remaining.eq_ignore_ascii_case("NONE") {
+            Vec::new()
+        } else {
+            let (kind, command) = remaining
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| RockerfileError::MissingArgument("HEALTHCHECK".to_string()))?;
+            if !kind.eq_ignore_ascii_case("CMD") {
+                return Err(RockerfileError::InvalidInstruction(format!(
+                    "HEALTHCHECK expects CMD or NONE: {}",
+                    remaining
+                )));
+            }
+            let command = command.trim();
+            if command.starts_with('[') && command.ends_with(']') {
+                serde_json::from_str(command)?
+            } else {
+                // シェル形式は CMD と同じくシェルによって実行される
+                vec![command.to_string()]
+            }
+        };
+
+        self.stages[self.current_stage].add_instruction(Instruction::Healthcheck {
+            command,
+            interval,
+            timeout,
+            retries,
+            start_period,
+        });
+        Ok(())
+    }
+
+    fn parse_onbuild(&mut self, args: &str) -> Result<()> {
+        // ONBUILD <命令>：このイメージを FROM で使うビルドで実行する命令
+        let trigger = args.trim();
+        let name = trigger.split_whitespace().next().unwrap_or("").to_uppercase();
+        match name.as_str() {
+            "" => return Err(RockerfileError::MissingArgument("ONBUILD".to_string())),
+            "ONBUILD" | "FROM" | "MAINTAINER" => {
+                return Err(RockerfileError::InvalidInstruction(format!("{} isn't allowed as an ONBUILD trigger", name)))
+            }
+            _ => {}
+        }
+
+        // 命令は 1 行の Rockerfile として解析する
+        let mut parser = RockerfileParser::new();
+        let instruction = parser.parse_content(trigger)?[0]
+            .instructions
+            .first()
+            .cloned()
+            .ok_or_else(|| RockerfileError::MissingArgument("ONBUILD".to_string()))?;
+        self.stages[self.current_stage].add_instruction(Instruction::OnBuild {
+            instruction: Box::new(instruction),
+        });
+        Ok(())
+    }
+
     fn parse_label(&mut self, args: &str) -> Result<()> {
         let mut labels = HashMap::new();
         
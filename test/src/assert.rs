@@ -0,0 +1,43 @@
+use rocker_client::Client;
+use rocker_core::{Container, ContainerState};
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+/// Interval between two inspections while waiting for a container
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Wait until `container` (a name or ID) is in `state` and return it as last inspected
+///
+/// Fails if the container does not reach the state within `timeout` or cannot be inspected.
+pub async fn wait_for_state(
+    client: &Client,
+    container: &str,
+    state: ContainerState,
+    timeout: Duration,
+) -> Result<Container, Box<dyn Error>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let current = client.inspect_container(container).await?;
+        if current.state == state {
+            return Ok(current);
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "Container {} is {} after {:?}, expected {}",
+                container, current.state, timeout, state
+            )
+            .into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Panic unless `container` (a name or ID) is in `state` now
+pub async fn assert_container_state(client: &Client, container: &str, state: ContainerState) -> Container {
+    let current = match client.inspect_container(container).await {
+        Ok(current) => current,
+        Err(e) => panic!("Failed to inspect container {}: {}", container, e),
+    };
+    assert_eq!(current.state, state, "state of container {}", container);
+    current
+}
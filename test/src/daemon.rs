@@ -0,0 +1,160 @@
+use rocker_client::Client;
+use std::error::Error;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Path of the `rockerd` binary to launch, overriding the lookup of [`rockerd_path`]
+pub const ROCKERD_ENV: &str = "ROCKERD";
+
+/// How long [`TestDaemon`] waits for the daemon to answer on its socket
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Interval between two attempts to reach the starting daemon
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The `rockerd` binary used by [`TestDaemon`]
+///
+/// `ROCKERD` if set, otherwise `rockerd` next to the running test (the workspace's `target/debug`
+/// after `cargo build -p rocker-daemon`), otherwise `rockerd` from `PATH`.
+pub fn rockerd_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(ROCKERD_ENV).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    // Test binaries are in target/<profile>/deps, the daemon in target/<profile>
+    let built = std::env::current_exe().ok().and_then(|exe| {
+        let dir = exe.parent()?;
+        let dir = if dir.ends_with("deps") { dir.parent()? } else { dir };
+        Some(dir.join("rockerd"))
+    });
+    match built {
+        Some(path) if path.is_file() => path,
+        _ => PathBuf::from("rockerd"),
+    }
+}
+
+/// A `rockerd` running for one test
+///
+/// The daemon keeps all its state in a new directory under the system's temporary directory and
+/// serves the API only on a socket inside it. Dropping the fixture kills the daemon and removes the
+/// directory.
+pub struct TestDaemon {
+    child: Child,
+    data_root: PathBuf,
+    socket: PathBuf,
+    client: Client,
+}
+
+impl TestDaemon {
+    /// Launch a daemon that runs containers for real (needs root, like `rockerd` itself)
+    pub async fn start() -> Result<Self, Box<dyn Error>> {
+        TestDaemon::launch(false).await
+    }
+
+    /// Launch a daemon in dry-run mode (`rockerd --dry-run`), which simulates containers and needs no privileges
+    pub async fn start_dry_run() -> Result<Self, Box<dyn Error>> {
+        TestDaemon::launch(true).await
+    }
+
+    async fn launch(dry_run: bool) -> Result<Self, Box<dyn Error>> {
+        let data_root = std::env::temp_dir().join(format!("rocker-test-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&data_root)?;
+        let socket = data_root.join("rocker.sock");
+        let log = File::create(data_root.join("rockerd.log"))?;
+
+        let rockerd = rockerd_path();
+        let mut command = Command::new(&rockerd);
+        command
+            .arg("--data-root")
+            .arg(&data_root)
+            .arg("--socket")
+            .arg(&socket)
+            // Only the private socket, never a TCP address inherited from the environment
+            .env_remove("ROCKER_API_ADDR")
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        if dry_run {
+            command.arg("--dry-run");
+        }
+        let child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&data_root);
+                return Err(format!(
+                    "Failed to launch {} (build it with 'cargo build -p rocker-daemon' or set {}): {}",
+                    rockerd.display(),
+                    ROCKERD_ENV,
+                    e
+                )
+                .into());
+            }
+        };
+
+        let client = Client::with_host(&format!("unix://{}", socket.display()), None);
+        let mut daemon = TestDaemon {
+            child,
+            data_root,
+            socket,
+            client,
+        };
+        daemon.wait_until_ready().await?;
+        Ok(daemon)
+    }
+
+    // Wait until the daemon answers a request, failing with its log if it exits or takes too long
+    async fn wait_until_ready(&mut self) -> Result<(), Box<dyn Error>> {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                return Err(format!("rockerd exited during startup ({}):\n{}", status, self.logs()).into());
+            }
+            if self.socket.exists() && self.client.list_containers(true, &[]).await.is_ok() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "rockerd did not answer on {} within {:?}:\n{}",
+                    self.socket.display(),
+                    STARTUP_TIMEOUT,
+                    self.logs()
+                )
+                .into());
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Client connected to this daemon
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Address of the daemon in the `ROCKER_HOST` format, for running the `rocker` CLI against it
+    pub fn host(&self) -> String {
+        format!("unix://{}", self.socket.display())
+    }
+
+    /// Socket the daemon serves the API on
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Directory holding the daemon's state (removed when the fixture is dropped)
+    pub fn data_root(&self) -> &Path {
+        &self.data_root
+    }
+
+    /// What the daemon has logged so far
+    pub fn logs(&self) -> String {
+        std::fs::read_to_string(self.data_root.join("rockerd.log")).unwrap_or_default()
+    }
+}
+
+impl Drop for TestDaemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.data_root);
+    }
+}
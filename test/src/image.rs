@@ -0,0 +1,102 @@
+use rocker_client::{Client, ImageBuildOptions};
+use std::error::Error;
+
+/// A small image built `FROM scratch` out of files given by the test
+///
+/// Every file is copied to the same path in the image by its own `COPY`, followed by the extra
+/// instructions in the order they were added.
+///
+/// ```no_run
+/// # async fn example(client: &rocker_client::Client) -> Result<(), Box<dyn std::error::Error>> {
+/// let id = rocker_test::ScratchImage::new()
+///     .file("etc/greeting", "hello")
+///     .instruction("CMD [\"/bin/app\"]")
+///     .build(client, "test/greeting:latest")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScratchImage {
+    files: Vec<(String, Vec<u8>, u32)>,
+    instructions: Vec<String>,
+}
+
+impl ScratchImage {
+    pub fn new() -> Self {
+        ScratchImage::default()
+    }
+
+    /// Add a regular file (mode 0644) at `path`, relative to the image's root
+    pub fn file(self, path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.add(path, contents.into(), 0o644)
+    }
+
+    /// Add an executable file (mode 0755) at `path`, relative to the image's root
+    pub fn executable(self, path: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.add(path, contents.into(), 0o755)
+    }
+
+    fn add(mut self, path: &str, contents: Vec<u8>, mode: u32) -> Self {
+        self.files.push((path.trim_start_matches('/').to_string(), contents, mode));
+        self
+    }
+
+    /// Append a Rockerfile instruction (e.g. `ENV KEY=value` or `CMD ["/bin/app"]`) after the files
+    pub fn instruction(mut self, line: impl Into<String>) -> Self {
+        self.instructions.push(line.into());
+        self
+    }
+
+    /// The Rockerfile of the image
+    pub fn rockerfile(&self) -> String {
+        let mut rockerfile = String::from("FROM scratch\n");
+        for (path, _, _) in &self.files {
+            rockerfile.push_str(&format!("COPY {} /{}\n", path, path));
+        }
+        for instruction in &self.instructions {
+            rockerfile.push_str(instruction);
+            rockerfile.push('\n');
+        }
+        rockerfile
+    }
+
+    /// The build context: a tar of the Rockerfile and the files
+    pub fn context(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let rockerfile = self.rockerfile();
+        append(&mut builder, "Rockerfile", rockerfile.as_bytes(), 0o644)?;
+        for (path, contents, mode) in &self.files {
+            append(&mut builder, path, contents, *mode)?;
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    /// Build the image with the daemon behind `client`, tag it as `tag` and return its ID
+    pub async fn build(&self, client: &Client, tag: &str) -> Result<String, Box<dyn Error>> {
+        let options = ImageBuildOptions {
+            tag: Some(tag.to_string()),
+            ..Default::default()
+        };
+        let mut stream = client.build_image(&options, self.context()?).await?;
+        let mut image_id = None;
+        while let Some(message) = stream.next().await? {
+            if let Some(error) = message.error {
+                return Err(format!("Failed to build {}: {}", tag, error).into());
+            }
+            if message.image_id.is_some() {
+                image_id = message.image_id;
+            }
+        }
+        image_id.ok_or_else(|| format!("Build of {} ended without an image ID", tag).into())
+    }
+}
+
+fn append(builder: &mut tar::Builder<Vec<u8>>, path: &str, contents: &[u8], mode: u32) -> Result<(), Box<dyn Error>> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(mode);
+    header.set_cksum();
+    builder.append_data(&mut header, path, contents)?;
+    Ok(())
+}
@@ -0,0 +1,17 @@
+//! Fixtures for integration tests against a real Rocker daemon
+//!
+//! [`TestDaemon`] launches `rockerd` with a temporary data root and a private socket, and removes
+//! both when dropped, so tests never touch the daemon of the machine they run on. [`ScratchImage`]
+//! builds small images `FROM scratch` out of files given by the test, and [`wait_for_state`] and
+//! [`assert_container_state`] check what the daemon reports about a container.
+//!
+//! The daemon binary is taken from `ROCKERD`, the workspace's `target/debug`, or `PATH` (see
+//! [`rockerd_path`]); build it first with `cargo build -p rocker-daemon`.
+
+mod assert;
+mod daemon;
+mod image;
+
+pub use crate::assert::*;
+pub use crate::daemon::*;
+pub use crate::image::*;
@@ -0,0 +1,38 @@
+// rockerd --dry-run を起動して、イメージのビルドからコンテナの起動・停止までを確かめる
+//
+// 先にデーモンをビルドしておく: cargo build -p rocker-daemon && cargo test -p rocker-test -- --ignored
+
+use rocker_core::{ContainerConfig, ContainerState};
+use rocker_test::{assert_container_state, wait_for_state, ScratchImage, TestDaemon};
+use std::time::Duration;
+
+#[tokio::test]
+#[ignore = "needs a built rockerd"]
+async fn container_lifecycle() {
+    let daemon = TestDaemon::start_dry_run().await.unwrap();
+    let client = daemon.client();
+
+    ScratchImage::new()
+        .executable("bin/app", "#!/bin/sh\n")
+        .instruction("CMD [\"/bin/app\"]")
+        .build(client, "rocker-test/app:latest")
+        .await
+        .unwrap();
+
+    let config = ContainerConfig {
+        image: "rocker-test/app:latest".to_string(),
+        ..Default::default()
+    };
+    client.create_container(Some("app"), &config).await.unwrap();
+    assert_container_state(client, "app", ContainerState::Created).await;
+
+    client.start_container("app").await.unwrap();
+    wait_for_state(client, "app", ContainerState::Running, Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    client.stop_container("app", Some(1)).await.unwrap();
+    wait_for_state(client, "app", ContainerState::Stopped, Duration::from_secs(5))
+        .await
+        .unwrap();
+}